@@ -7,6 +7,7 @@ const riskRoutes = require('./risk');
 const recommendationRoutes = require('./recommendations');
 const alertRoutes = require('./alerts');
 const tradeRoutes = require('./trades');
+const programEventRoutes = require('./programEvents');
 
 // Setup routes
 router.use('/portfolio', portfolioRoutes);
@@ -14,5 +15,6 @@ router.use('/risk', riskRoutes);
 router.use('/recommendations', recommendationRoutes);
 router.use('/alerts', alertRoutes);
 router.use('/trades', tradeRoutes);
+router.use('/program-events', programEventRoutes);
 
 module.exports = router;
\ No newline at end of file
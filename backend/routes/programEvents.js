@@ -0,0 +1,21 @@
+const express = require('express');
+const router = express.Router();
+const notificationService = require('../services/notificationService');
+
+// Program events delivered by the aistm7-webhooks service. Deliveries are
+// signed over the raw body, which server.js keeps as `req.rawBody`.
+router.post('/', async (req, res) => {
+  const signature = req.get('Aistm7-Signature');
+  if (!req.rawBody || !notificationService.verifyProgramEventSignature(req.rawBody, signature)) {
+    return res.status(401).json({ error: 'Invalid signature' });
+  }
+  try {
+    await notificationService.handleProgramEvent(req.body);
+    res.status(204).end();
+  } catch (error) {
+    // A 5xx has the delivery retried
+    res.status(500).json({ error: error.message });
+  }
+});
+
+module.exports = router;
@@ -3,6 +3,7 @@ const express = require('express');
 const cors = require('cors');
 const expressWs = require('express-ws');
 const routes = require('./routes');
+const notificationService = require('./services/notificationService');
 const { connectDB } = require('./utils/db');
 const backupService = require('./utils/backup');
 const cron = require('node-cron');
@@ -35,7 +36,8 @@ const startServer = async () => {
 
     // Middleware
     app.use(cors());
-    app.use(express.json());
+    // Program event deliveries are verified against the raw body
+    app.use(express.json({ verify: (req, res, buf) => { req.rawBody = buf; } }));
     app.use(morgan('dev'));
 
     // Schedule database backups
@@ -66,6 +68,13 @@ const startServer = async () => {
       console.log(`Server running on port ${PORT}`);
     });
 
+    // Subscribe to program events through the webhook service
+    if (process.env.AISTM7_WEBHOOKS_URL) {
+      notificationService
+        .registerProgramWebhook(`${process.env.PUBLIC_API_URL}/api/program-events`)
+        .catch((error) => console.error('Error registering program event webhook:', error));
+    }
+
     // Handle WebSocket errors
     server.on('upgrade', (request, socket, head) => {
       socket.on('error', (err) => {
@@ -1,5 +1,15 @@
 const { Connection, PublicKey } = require('@solana/web3.js');
 const { getOrCreateAssociatedTokenAccount } = require('@solana/spl-token');
+const notificationService = require('./notificationService');
+
+// How long a history read from the chain is served before it is read again,
+// for instances the webhook deliveries do not reach
+const HISTORY_CACHE_MS = 60_000;
+
+// Requirement histories read from the chain, by program id, shared by every
+// instance and dropped whenever the webhook service reports an update
+const historyCache = new Map();
+notificationService.onProgramEvent('BalanceRequirementUpdated', () => historyCache.clear());
 
 class BalanceRequirementService {
     constructor(connection, tokenProgramId, priceFeedAddress) {
@@ -107,39 +117,50 @@ class BalanceRequirementService {
         return stateAddress;
     }
 
+    async getRequirementHistoryAddress() {
+        const [historyAddress] = await PublicKey.findProgramAddress(
+            [Buffer.from('requirement_history')],
+            this.tokenProgramId
+        );
+        return historyAddress;
+    }
+
+    // Requirement changes held by the program's on-chain history, oldest first
+    async fetchRequirementHistory() {
+        const historyAddress = await this.getRequirementHistoryAddress();
+        const accountInfo = await this.connection.getAccountInfo(historyAddress);
+        if (!accountInfo) {
+            return [];
+        }
+
+        const history = this.program.coder.accounts.decode('RequirementHistory', accountInfo.data);
+        const { priceDecimals } = await this.getTokenState();
+        const length = history.entries.length;
+        const start = (history.head + length - history.count) % length;
+        return Array.from({ length: history.count }, (_, i) => {
+            const entry = history.entries[(start + i) % length];
+            return {
+                timestamp: new Date(entry.timestamp.toNumber() * 1000),
+                requirement: entry.requirement.toNumber(),
+                price: entry.price.toNumber() / 10 ** priceDecimals, // Convert to whole dollars
+            };
+        });
+    }
+
     async getRequirementHistory(days = 7) {
         try {
-            // Get requirement update events from the program
-            const signatures = await this.connection.getSignaturesForAddress(
-                this.tokenProgramId,
-                { limit: 100 }
-            );
-
-            const events = [];
-            for (const sig of signatures) {
-                const tx = await this.connection.getTransaction(sig.signature);
-                if (!tx?.meta?.logMessages) continue;
-
-                // Parse logs for BalanceRequirementUpdated events
-                for (const log of tx.meta.logMessages) {
-                    if (log.includes('BalanceRequirementUpdated')) {
-                        const event = this.program.coder.events.decode(log);
-                        if (event) {
-                            events.push({
-                                timestamp: new Date(event.timestamp * 1000),
-                                requirement: event.newRequirement,
-                                price: event.price / 1_000_000, // Convert to whole dollars
-                            });
-                        }
-                    }
-                }
+            const key = this.tokenProgramId.toBase58();
+            let cached = historyCache.get(key);
+            if (!cached || Date.now() - cached.fetchedAt > HISTORY_CACHE_MS) {
+                cached = { entries: await this.fetchRequirementHistory(), fetchedAt: Date.now() };
+                historyCache.set(key, cached);
             }
 
             // Filter for the requested time period
             const cutoff = new Date();
             cutoff.setDate(cutoff.getDate() - days);
-            
-            return events
+
+            return cached.entries
                 .filter(e => e.timestamp >= cutoff)
                 .sort((a, b) => b.timestamp - a.timestamp);
         } catch (error) {
@@ -1,8 +1,22 @@
+const crypto = require('crypto');
+const { EventEmitter } = require('events');
 const nodemailer = require('nodemailer');
 const { formatRiskLevel, formatCurrency } = require('../utils/formatting');
 
+// Program events the notification stack acts on, delivered by the
+// aistm7-webhooks service
+const PROGRAM_EVENTS = ['BalanceRequirementUpdated', 'GracePeriodStarted', 'CircuitBreakerTripped'];
+
+// How far a delivery's signed timestamp may be from our clock, in seconds
+const SIGNATURE_TOLERANCE_SECS = 300;
+
+// Delivery ids remembered, since retries and replays resend the same one
+const SEEN_DELIVERIES = 10_000;
+
 class NotificationService {
   constructor() {
+    this.programEvents = new EventEmitter();
+    this.seenDeliveries = new Set();
     this.transporter = nodemailer.createTransport({
       host: process.env.EMAIL_SERVER_HOST,
       port: process.env.EMAIL_SERVER_PORT,
@@ -97,6 +111,92 @@ class NotificationService {
     `;
     return this.sendEmail(user.email, subject, html);
   }
+
+  async webhooksRequest(path, options = {}) {
+    const response = await fetch(`${process.env.AISTM7_WEBHOOKS_URL}${path}`, {
+      ...options,
+      headers: {
+        'Content-Type': 'application/json',
+        Authorization: `Bearer ${process.env.AISTM7_WEBHOOKS_TOKEN}`,
+      },
+    });
+    if (!response.ok) {
+      throw new Error(`Webhook service request failed: ${response.status} ${await response.text()}`);
+    }
+    return response.status === 204 ? null : response.json();
+  }
+
+  // Register `url` with the webhook service for the program events we act
+  // on, reusing a webhook already registered for it so restarts do not
+  // multiply deliveries. Others left for the same url, e.g. with an older
+  // event list, are removed.
+  async registerProgramWebhook(url) {
+    const events = [...PROGRAM_EVENTS].sort();
+    const existing = (await this.webhooksRequest('/webhooks')).filter((webhook) => webhook.url === url);
+    const current = existing.find(
+      (webhook) => JSON.stringify(webhook.events) === JSON.stringify(events)
+    );
+    for (const stale of existing.filter((webhook) => webhook !== current)) {
+      await this.webhooksRequest(`/webhooks/${stale.id}`, { method: 'DELETE' });
+    }
+    if (current) {
+      console.log('Reusing program event webhook:', current.id);
+      return current;
+    }
+
+    const webhook = await this.webhooksRequest('/webhooks', {
+      method: 'POST',
+      body: JSON.stringify({
+        url,
+        events,
+        secret: process.env.AISTM7_WEBHOOKS_SECRET,
+      }),
+    });
+    console.log('Registered program event webhook:', webhook.id);
+    return webhook;
+  }
+
+  // Check an `Aistm7-Signature: t=<unix time>,v1=<hex HMAC-SHA256>` header
+  // against the raw body the delivery was signed over
+  verifyProgramEventSignature(rawBody, header, now = Math.floor(Date.now() / 1000)) {
+    const parts = Object.fromEntries(
+      (header || '').split(',').map((part) => part.trim().split('=', 2))
+    );
+    const timestamp = Number(parts.t);
+    if (!Number.isInteger(timestamp) || Math.abs(now - timestamp) > SIGNATURE_TOLERANCE_SECS) {
+      return false;
+    }
+    const expected = crypto
+      .createHmac('sha256', process.env.AISTM7_WEBHOOKS_SECRET)
+      .update(`${timestamp}.`)
+      .update(rawBody)
+      .digest();
+    const given = Buffer.from(parts.v1 || '', 'hex');
+    return given.length === expected.length && crypto.timingSafeEqual(given, expected);
+  }
+
+  onProgramEvent(event, handler) {
+    this.programEvents.on(event, handler);
+  }
+
+  // Hand a verified delivery to the handlers for its event, once per id.
+  // The id is only remembered once every handler has succeeded, so a
+  // delivery whose handling failed is handled again when it is retried.
+  async handleProgramEvent(delivery) {
+    if (this.seenDeliveries.has(delivery.id)) {
+      return false;
+    }
+    await Promise.all(
+      this.programEvents
+        .listeners(delivery.event)
+        .map(async (handler) => handler(delivery.data, delivery))
+    );
+    this.seenDeliveries.add(delivery.id);
+    if (this.seenDeliveries.size > SEEN_DELIVERIES) {
+      this.seenDeliveries.delete(this.seenDeliveries.values().next().value);
+    }
+    return true;
+  }
 }
 
 module.exports = new NotificationService();
\ No newline at end of file
@@ -1,3 +1,4 @@
+const crypto = require('crypto');
 const NotificationService = require('../../services/notificationService');
 const { formatCurrency, formatRiskLevel } = require('../../utils/formatting');
 
@@ -135,4 +136,109 @@ describe('NotificationService', () => {
       );
     });
   });
+
+  describe('program events', () => {
+    const body = Buffer.from(JSON.stringify({
+      id: 'sig:0',
+      event: 'BalanceRequirementUpdated',
+      data: { new_requirement: 1000, price: 15000, timestamp: 1674392400 },
+    }));
+    const now = 1674392400;
+    const sign = (timestamp, secret = 'test-secret') =>
+      `t=${timestamp},v1=${crypto
+        .createHmac('sha256', secret)
+        .update(`${timestamp}.`)
+        .update(body)
+        .digest('hex')}`;
+
+    beforeEach(() => {
+      process.env.AISTM7_WEBHOOKS_SECRET = 'test-secret';
+    });
+
+    it('should accept a delivery signed with the webhook secret', () => {
+      expect(NotificationService.verifyProgramEventSignature(body, sign(now), now)).toBe(true);
+    });
+
+    it('should reject a delivery signed with another secret', () => {
+      expect(
+        NotificationService.verifyProgramEventSignature(body, sign(now, 'other'), now)
+      ).toBe(false);
+    });
+
+    it('should reject a replayed delivery', () => {
+      expect(
+        NotificationService.verifyProgramEventSignature(body, sign(now - 3600), now)
+      ).toBe(false);
+    });
+
+    it('should hand each delivery to its handlers once', async () => {
+      const handler = jest.fn();
+      NotificationService.onProgramEvent('BalanceRequirementUpdated', handler);
+      const delivery = JSON.parse(body);
+
+      await expect(NotificationService.handleProgramEvent(delivery)).resolves.toBe(true);
+      await expect(NotificationService.handleProgramEvent(delivery)).resolves.toBe(false);
+      expect(handler).toHaveBeenCalledTimes(1);
+      expect(handler).toHaveBeenCalledWith(delivery.data, delivery);
+      NotificationService.programEvents.removeListener('BalanceRequirementUpdated', handler);
+    });
+
+    it('should handle a delivery again when its handler failed', async () => {
+      const handler = jest
+        .fn()
+        .mockRejectedValueOnce(new Error('database unavailable'))
+        .mockResolvedValue();
+      NotificationService.onProgramEvent('GracePeriodStarted', handler);
+      const delivery = { id: 'sig:1', event: 'GracePeriodStarted', data: {} };
+
+      await expect(NotificationService.handleProgramEvent(delivery)).rejects.toThrow(
+        'database unavailable'
+      );
+      await expect(NotificationService.handleProgramEvent(delivery)).resolves.toBe(true);
+      expect(handler).toHaveBeenCalledTimes(2);
+      NotificationService.programEvents.removeListener('GracePeriodStarted', handler);
+    });
+  });
+
+  describe('registerProgramWebhook', () => {
+    const url = 'https://api.example.com/api/program-events';
+    const events = ['BalanceRequirementUpdated', 'CircuitBreakerTripped', 'GracePeriodStarted'];
+    const respond = (status, body) => ({
+      ok: status < 400,
+      status,
+      json: async () => body,
+      text: async () => JSON.stringify(body),
+    });
+
+    beforeEach(() => {
+      process.env.AISTM7_WEBHOOKS_URL = 'https://webhooks.example.com';
+    });
+
+    it('should reuse a webhook already registered for the url', async () => {
+      global.fetch = jest.fn().mockResolvedValue(respond(200, [{ id: 'a1', url, events }]));
+
+      const webhook = await NotificationService.registerProgramWebhook(url);
+
+      expect(webhook.id).toBe('a1');
+      expect(global.fetch).toHaveBeenCalledTimes(1);
+    });
+
+    it('should replace webhooks for the url with another event list', async () => {
+      global.fetch = jest
+        .fn()
+        .mockResolvedValueOnce(respond(200, [{ id: 'a1', url, events: ['GracePeriodStarted'] }]))
+        .mockResolvedValueOnce(respond(204))
+        .mockResolvedValueOnce(respond(201, { id: 'b2', url, events }));
+
+      const webhook = await NotificationService.registerProgramWebhook(url);
+
+      expect(webhook.id).toBe('b2');
+      expect(global.fetch).toHaveBeenNthCalledWith(
+        2,
+        'https://webhooks.example.com/webhooks/a1',
+        expect.objectContaining({ method: 'DELETE' })
+      );
+      expect(JSON.parse(global.fetch.mock.calls[2][1].body).events).toEqual(events);
+    });
+  });
 });
\ No newline at end of file
@@ -0,0 +1,97 @@
+//! Responses kept for a short while, so a burst of requests for the same
+//! wallet costs one round of RPC calls. Entries are only ever replaced or
+//! evicted, never refreshed early, so an answer is at most `ttl` stale.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+pub struct Cache<K, V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> Cache<K, V> {
+    /// Holds up to `capacity` entries for `ttl` each
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Cache {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The value cached for `key`, or the one `fetch` produces, which is
+    /// cached only if it is `Ok`
+    pub async fn get_or_fetch<E, F>(&self, key: K, fetch: F) -> Result<V, E>
+    where
+        F: std::future::Future<Output = Result<V, E>>,
+    {
+        if let Some((fetched_at, value)) = self.entries.lock().await.get(&key) {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(value.clone());
+            }
+        }
+        // Not held across the fetch: concurrent misses each fetch, and the
+        // last to finish is kept
+        let value = fetch.await?;
+        let mut entries = self.entries.lock().await;
+        if entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < ttl);
+        }
+        if entries.len() < self.capacity {
+            entries.insert(key, (Instant::now(), value.clone()));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    async fn fetch(calls: &AtomicU32) -> Result<u32, String> {
+        Ok(calls.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn answers_from_the_cache_until_the_ttl_passes() {
+        let cache = Cache::new(Duration::from_secs(10), 8);
+        let calls = AtomicU32::new(0);
+        assert_eq!(cache.get_or_fetch("wallet", fetch(&calls)).await, Ok(1));
+        tokio::time::advance(Duration::from_secs(9)).await;
+        assert_eq!(cache.get_or_fetch("wallet", fetch(&calls)).await, Ok(1));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(cache.get_or_fetch("wallet", fetch(&calls)).await, Ok(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_cache_errors() {
+        let cache = Cache::new(Duration::from_secs(10), 8);
+        let failed: Result<u32, String> = Err("rpc down".to_string());
+        assert!(cache.get_or_fetch("wallet", async { failed }).await.is_err());
+        let calls = AtomicU32::new(0);
+        assert_eq!(cache.get_or_fetch("wallet", fetch(&calls)).await, Ok(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn makes_room_only_by_evicting_stale_entries() {
+        let cache = Cache::new(Duration::from_secs(10), 1);
+        let calls = AtomicU32::new(0);
+        assert_eq!(cache.get_or_fetch("a", fetch(&calls)).await, Ok(1));
+        // Full of fresh entries, so "b" is answered but not kept
+        assert_eq!(cache.get_or_fetch("b", fetch(&calls)).await, Ok(2));
+        assert_eq!(cache.get_or_fetch("b", fetch(&calls)).await, Ok(3));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(cache.get_or_fetch("b", fetch(&calls)).await, Ok(4));
+        assert_eq!(cache.get_or_fetch("b", fetch(&calls)).await, Ok(4));
+    }
+}
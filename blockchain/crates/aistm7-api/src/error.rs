@@ -0,0 +1,50 @@
+use aistm7_sdk::SdkError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Body of every response that is not a success
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<SdkError> for ApiError {
+    fn from(err: SdkError) -> Self {
+        // The node, not the caller, failed; its error is logged rather than
+        // passed on
+        eprintln!("RPC read failed: {err}");
+        ApiError {
+            status: StatusCode::BAD_GATEWAY,
+            message: "reading from the cluster failed".to_string(),
+        }
+    }
+}
+
+impl From<solana_client::client_error::ClientError> for ApiError {
+    fn from(err: solana_client::client_error::ClientError) -> Self {
+        SdkError::from(err).into()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorBody { error: self.message })).into_response()
+    }
+}
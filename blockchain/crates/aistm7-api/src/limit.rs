@@ -0,0 +1,104 @@
+//! Per-client rate limiting: each address gets `requests` per window, and
+//! answers 429 with `Retry-After` once it has used them.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::error::ApiError;
+
+/// Clients tracked before those whose window has passed are forgotten
+const PRUNE_ABOVE: usize = 10_000;
+
+pub struct RateLimit {
+    requests: u32,
+    window: Duration,
+    clients: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, window: Duration) -> Self {
+        RateLimit {
+            requests,
+            window,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `client`, or the wait until it may send another
+    async fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        let mut clients = self.clients.lock().await;
+        let now = Instant::now();
+        if clients.len() > PRUNE_ABOVE {
+            let window = self.window;
+            clients.retain(|_, (started, _)| now.duration_since(*started) < window);
+        }
+        let (started, count) = clients.entry(client).or_insert((now, 0));
+        if now.duration_since(*started) >= self.window {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= self.requests {
+            return Err(self.window - now.duration_since(*started));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+pub async fn limit<B>(
+    State(limit): State<Arc<RateLimit>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match limit.check(client.ip()).await {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let error = ApiError {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                message: "rate limit exceeded".to_string(),
+            };
+            let retry_after = (wait.as_secs() + 1).to_string();
+            ([(header::RETRY_AFTER, retry_after)], error).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn allows_the_window_budget_then_names_the_wait() {
+        let limit = RateLimit::new(2, Duration::from_secs(60));
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(limit.check(client).await, Ok(()));
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert_eq!(limit.check(client).await, Ok(()));
+        assert_eq!(limit.check(client).await, Err(Duration::from_secs(40)));
+
+        // Another client has its own budget
+        assert_eq!(limit.check(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))).await, Ok(()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn starts_a_new_window_once_the_last_has_passed() {
+        let limit = RateLimit::new(1, Duration::from_secs(60));
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert_eq!(limit.check(client).await, Ok(()));
+        assert!(limit.check(client).await.is_err());
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(limit.check(client).await, Ok(()));
+    }
+}
@@ -0,0 +1,80 @@
+//! `aistm7-api`, a REST service over the SDK for the web backend, so it
+//! need not talk to RPC itself. Answers are cached briefly and each client
+//! address is rate limited; the OpenAPI document for the endpoints in
+//! `routes` is served at `/openapi.json`, and `/health` answers once the
+//! service is up.
+
+mod cache;
+mod error;
+mod limit;
+mod routes;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::Aistm7Client;
+use anyhow::Result;
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use clap::Parser;
+use solana_sdk::commitment_config::CommitmentConfig;
+use utoipa::OpenApi;
+
+use crate::cache::Cache;
+use crate::limit::RateLimit;
+use crate::routes::{ApiDoc, AppState, Caches};
+
+#[derive(Parser)]
+#[command(name = "aistm7-api", version, about = "Serve AISTM7 requirement and verification state")]
+struct Opts {
+    #[arg(long, env = "AISTM7_API_LISTEN", default_value = "0.0.0.0:8789")]
+    listen: SocketAddr,
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Seconds the requirement and each wallet's verification are cached
+    #[arg(long, default_value_t = 10)]
+    cache_secs: u64,
+    /// Seconds each page of a wallet's history is cached
+    #[arg(long, default_value_t = 60)]
+    history_cache_secs: u64,
+    /// Most wallets, and pages of history, cached at once
+    #[arg(long, default_value_t = 10_000)]
+    cache_capacity: usize,
+    /// Requests each client address may make per minute
+    #[arg(long, default_value_t = 120)]
+    rate_limit: u32,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let ttl = Duration::from_secs(opts.cache_secs);
+    let state = AppState {
+        client: Arc::new(Aistm7Client::with_url(opts.url, CommitmentConfig::confirmed())),
+        caches: Arc::new(Caches {
+            requirement: Cache::new(ttl, 1),
+            verify: Cache::new(ttl, opts.cache_capacity),
+            history: Cache::new(
+                Duration::from_secs(opts.history_cache_secs),
+                opts.cache_capacity,
+            ),
+        }),
+    };
+    let rate_limit = Arc::new(RateLimit::new(opts.rate_limit, Duration::from_secs(60)));
+
+    let api = Router::new()
+        .route("/requirement", get(routes::requirement))
+        .route("/verify/:wallet", get(routes::verify))
+        .route("/holders/:wallet/history", get(routes::history))
+        .route_layer(middleware::from_fn_with_state(rate_limit, limit::limit))
+        .with_state(state);
+    let app = api
+        .route("/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+        .route("/health", get(|| async { "ok" }));
+    eprintln!("Listening on {}", opts.listen);
+    axum::Server::bind(&opts.listen)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,320 @@
+//! The endpoints and the types they answer with, which the OpenAPI
+//! document is generated from. Token amounts are decimal strings of base
+//! units, since a u64 does not survive a JavaScript number.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aistm7_sdk::{pda, Aistm7Client, EventRecord, SdkError};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::cache::Cache;
+use crate::error::{ApiError, ErrorBody};
+
+const DEFAULT_HISTORY: usize = 10;
+const MAX_HISTORY: usize = 25;
+
+pub struct Caches {
+    pub requirement: Cache<(), Requirement>,
+    pub verify: Cache<Pubkey, Verification>,
+    pub history: Cache<(Pubkey, Option<Signature>, usize), History>,
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub client: Arc<Aistm7Client>,
+    pub caches: Arc<Caches>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "AISTM7 API", description = "Requirement, verification, and holder history"),
+    paths(requirement, verify, history),
+    components(schemas(Requirement, Verification, Receipt, History, HistoryEvent, ErrorBody))
+)]
+pub struct ApiDoc;
+
+/// The balance requirement as the program holds it
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Requirement {
+    /// Tokens, in base units, a wallet must hold
+    pub requirement: String,
+    /// Oracle price the requirement was last set from
+    pub price: String,
+    pub price_decimals: u8,
+    /// Unix time the requirement was last set
+    pub last_update: i64,
+    pub paused: bool,
+}
+
+/// Where a wallet stands: the access its receipt and any override grant
+/// now, and its associated token account against the requirement
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Verification {
+    pub wallet: String,
+    /// Access tier, 0 for none, as `check_access` would report it
+    pub access_tier: u8,
+    pub has_access: bool,
+    pub receipt: Option<Receipt>,
+    /// Balance of the wallet's associated token account alone; the program
+    /// also counts stake and extra accounts when verifying
+    pub balance: String,
+    pub requirement: String,
+    pub meets_requirement: bool,
+    /// Unix time the answer was read at
+    pub checked_at: i64,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub tier: u8,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub requirement_at_verification: String,
+    /// Unix time the balance fell below the requirement, 0 if it has not
+    pub below_since: i64,
+}
+
+/// A page of a wallet's history, newest first
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub wallet: String,
+    pub events: Vec<HistoryEvent>,
+    /// Pass as `before` for the next page; absent on the last one
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEvent {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Position among the transaction's events
+    pub index: u32,
+    /// Anchor event name
+    pub event: String,
+    /// The event's fields, keys in base58
+    #[schema(value_type = Object)]
+    pub data: Value,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Transactions to read, newest first; at most 25
+    limit: Option<usize>,
+    /// `next` from the previous page
+    before: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/requirement",
+    responses(
+        (status = 200, body = Requirement),
+        (status = 502, description = "Reading from the cluster failed", body = ErrorBody),
+    )
+)]
+pub async fn requirement(State(state): State<AppState>) -> Result<Json<Requirement>, ApiError> {
+    let requirement = state
+        .caches
+        .requirement
+        .get_or_fetch((), read_requirement(&state.client))
+        .await?;
+    Ok(Json(requirement))
+}
+
+async fn read_requirement(client: &Aistm7Client) -> Result<Requirement, ApiError> {
+    let token_state = client.token_state().await?;
+    Ok(Requirement {
+        requirement: token_state.current_requirement.to_string(),
+        price: token_state.last_price.to_string(),
+        price_decimals: token_state.price_decimals,
+        last_update: token_state.last_update,
+        paused: token_state.paused,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/verify/{wallet}",
+    params(("wallet" = String, Path, description = "Wallet address, base58")),
+    responses(
+        (status = 200, body = Verification),
+        (status = 400, description = "Malformed wallet address", body = ErrorBody),
+        (status = 502, description = "Reading from the cluster failed", body = ErrorBody),
+    )
+)]
+pub async fn verify(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> Result<Json<Verification>, ApiError> {
+    let wallet = parse_wallet(&wallet)?;
+    let verification = state
+        .caches
+        .verify
+        .get_or_fetch(wallet, read_verification(&state.client, wallet))
+        .await?;
+    Ok(Json(verification))
+}
+
+async fn read_verification(
+    client: &Aistm7Client,
+    wallet: Pubkey,
+) -> Result<Verification, ApiError> {
+    // Close enough to the cluster's clock for expiries measured in hours
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let status = client.wallet_status(&wallet, now).await?;
+    Ok(Verification {
+        wallet: wallet.to_string(),
+        access_tier: status.access_tier,
+        has_access: status.access_tier != 0,
+        receipt: status.receipt.map(|receipt| Receipt {
+            tier: receipt.tier,
+            verified_at: receipt.verified_at,
+            expires_at: receipt.expires_at,
+            requirement_at_verification: receipt.requirement_at_verification.to_string(),
+            below_since: receipt.below_since,
+        }),
+        balance: status.balance.to_string(),
+        requirement: status.requirement.to_string(),
+        meets_requirement: status.balance >= status.requirement,
+        checked_at: now,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/holders/{wallet}/history",
+    params(
+        ("wallet" = String, Path, description = "Wallet address, base58"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, body = History),
+        (status = 400, description = "Malformed wallet, cursor, or limit", body = ErrorBody),
+        (status = 502, description = "Reading from the cluster failed", body = ErrorBody),
+    )
+)]
+pub async fn history(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<History>, ApiError> {
+    let wallet = parse_wallet(&wallet)?;
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY);
+    if !(1..=MAX_HISTORY).contains(&limit) {
+        return Err(ApiError::bad_request(format!("limit must be from 1 to {MAX_HISTORY}")));
+    }
+    let before = query
+        .before
+        .map(|before| {
+            Signature::from_str(&before).map_err(|_| ApiError::bad_request("malformed cursor"))
+        })
+        .transpose()?;
+    let history = state
+        .caches
+        .history
+        .get_or_fetch(
+            (wallet, before, limit),
+            read_history(&state.client, wallet, before, limit),
+        )
+        .await?;
+    Ok(Json(history))
+}
+
+/// The program's events that name `wallet`, from the `limit` latest
+/// transactions before `before` that touched the wallet or its receipt.
+/// Verifications paid for by someone else need not touch the wallet
+/// itself, but always write its receipt.
+async fn read_history(
+    client: &Aistm7Client,
+    wallet: Pubkey,
+    before: Option<Signature>,
+    limit: usize,
+) -> Result<History, ApiError> {
+    let mut statuses = Vec::new();
+    for address in [wallet, pda::receipt(&wallet).0] {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: Some(limit),
+            commitment: Some(client.rpc().commitment()),
+        };
+        statuses.extend(
+            client
+                .rpc()
+                .get_signatures_for_address_with_config(&address, config)
+                .await?,
+        );
+    }
+    statuses.sort_by(|a, b| (b.slot, &b.signature).cmp(&(a.slot, &a.signature)));
+    statuses.dedup_by(|a, b| a.signature == b.signature);
+    let full = statuses.len() >= limit;
+    statuses.truncate(limit);
+    let next = if full {
+        statuses.last().map(|status| status.signature.clone())
+    } else {
+        None
+    };
+
+    let wallet_key = Value::String(wallet.to_string());
+    let mut events = Vec::new();
+    for status in statuses.iter().filter(|status| status.err.is_none()) {
+        let signature = Signature::from_str(&status.signature).map_err(|_| {
+            SdkError::InvalidTransaction(status.signature.clone(), "malformed signature")
+        })?;
+        for record in client.transaction_events(&signature).await? {
+            if let Some(event) = history_event(&record, &wallet_key) {
+                events.push(event);
+            }
+        }
+    }
+    Ok(History {
+        wallet: wallet.to_string(),
+        events,
+        next,
+    })
+}
+
+/// `record` as a history entry, if it decodes and names `wallet`
+fn history_event(record: &EventRecord, wallet: &Value) -> Option<HistoryEvent> {
+    let mut json = record.to_json();
+    if !mentions(&json["data"], wallet) {
+        return None;
+    }
+    Some(HistoryEvent {
+        signature: record.signature.to_string(),
+        slot: record.slot,
+        block_time: record.block_time,
+        index: record.index,
+        event: record.decode()?.name().to_string(),
+        data: json["data"].take(),
+    })
+}
+
+fn mentions(value: &Value, wallet: &Value) -> bool {
+    match value {
+        Value::Array(values) => values.iter().any(|value| mentions(value, wallet)),
+        Value::Object(fields) => fields.values().any(|value| mentions(value, wallet)),
+        value => value == wallet,
+    }
+}
+
+fn parse_wallet(wallet: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(wallet).map_err(|_| ApiError::bad_request("malformed wallet address"))
+}
@@ -0,0 +1,205 @@
+//! `aistm7 backfill`: replay every event the program has emitted, oldest
+//! first, into stdout as JSON lines, a Postgres table, or a Kafka topic.
+//! After each transaction's events are written its signature is saved to
+//! the resume file, and the next run starts after it. A run cut short
+//! mid-transaction writes that transaction's events again, so each event
+//! carries its signature and index to be deduplicated by; the Postgres sink
+//! does so itself.
+//!
+//! The Postgres and Kafka sinks are behind the `postgres` and `kafka`
+//! features.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use aistm7_sdk::{Aistm7Client, EventRecord};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use solana_sdk::signature::Signature;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SinkKind {
+    Stdout,
+    Postgres,
+    Kafka,
+}
+
+#[derive(Args)]
+pub struct BackfillOpts {
+    #[arg(long, value_enum, default_value = "stdout")]
+    sink: SinkKind,
+    /// Connection string for the postgres sink
+    #[arg(long, env = "AISTM7_POSTGRES_URL")]
+    postgres_url: Option<String>,
+    /// Bootstrap servers for the kafka sink
+    #[arg(long, env = "AISTM7_KAFKA_BROKERS")]
+    kafka_brokers: Option<String>,
+    #[arg(long, default_value = "aistm7-events")]
+    kafka_topic: String,
+    /// Holds the signature of the last transaction replayed
+    #[arg(long, default_value = ".aistm7-backfill")]
+    resume_file: PathBuf,
+    /// Start after this transaction rather than the one in the resume file
+    #[arg(long)]
+    after: Option<Signature>,
+    /// Stop before this transaction rather than at the latest
+    #[arg(long)]
+    before: Option<Signature>,
+}
+
+pub async fn run(client: &Aistm7Client, opts: BackfillOpts) -> Result<()> {
+    let after = match opts.after {
+        Some(after) => Some(after),
+        None => load_resume(&opts.resume_file)?,
+    };
+    let signatures = client.signatures_since(after, opts.before).await?;
+    eprintln!("{} transactions to replay", signatures.len());
+
+    let mut sink = Sink::open(&opts).await?;
+    let mut events = 0;
+    for signature in &signatures {
+        for record in client.transaction_events(signature).await? {
+            sink.write(&record).await?;
+            events += 1;
+        }
+        save_resume(&opts.resume_file, signature)?;
+    }
+    eprintln!("Replayed {events} events from {} transactions", signatures.len());
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS aistm7_events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    event TEXT,
+    data JSONB,
+    raw BYTEA NOT NULL,
+    PRIMARY KEY (signature, event_index)
+)";
+
+#[cfg(feature = "postgres")]
+const INSERT_EVENT: &str = "INSERT INTO aistm7_events
+    (signature, event_index, slot, block_time, event, data, raw)
+    VALUES ($1, $2, $3, $4, $5, $6::TEXT::JSONB, $7)
+    ON CONFLICT DO NOTHING";
+
+enum Sink {
+    Stdout,
+    #[cfg(feature = "postgres")]
+    Postgres(tokio_postgres::Client),
+    #[cfg(feature = "kafka")]
+    Kafka {
+        producer: rdkafka::producer::FutureProducer,
+        topic: String,
+    },
+}
+
+impl Sink {
+    async fn open(opts: &BackfillOpts) -> Result<Self> {
+        match opts.sink {
+            SinkKind::Stdout => Ok(Sink::Stdout),
+            #[cfg(feature = "postgres")]
+            SinkKind::Postgres => {
+                let url = opts
+                    .postgres_url
+                    .as_deref()
+                    .context("the postgres sink needs --postgres-url")?;
+                let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls)
+                    .await
+                    .context("connecting to postgres")?;
+                tokio::spawn(async move {
+                    if let Err(err) = connection.await {
+                        eprintln!("postgres connection failed: {err}");
+                    }
+                });
+                client.batch_execute(CREATE_TABLE).await?;
+                Ok(Sink::Postgres(client))
+            }
+            #[cfg(feature = "kafka")]
+            SinkKind::Kafka => {
+                let brokers = opts
+                    .kafka_brokers
+                    .as_deref()
+                    .context("the kafka sink needs --kafka-brokers")?;
+                let producer = rdkafka::ClientConfig::new()
+                    .set("bootstrap.servers", brokers)
+                    .set("enable.idempotence", "true")
+                    .create()
+                    .context("creating the kafka producer")?;
+                Ok(Sink::Kafka {
+                    producer,
+                    topic: opts.kafka_topic.clone(),
+                })
+            }
+            #[allow(unreachable_patterns)]
+            _ => anyhow::bail!("this build has no such sink; enable its feature"),
+        }
+    }
+
+    async fn write(&mut self, record: &EventRecord) -> Result<()> {
+        let json = record.to_json();
+        match self {
+            Sink::Stdout => {
+                let mut stdout = io::stdout().lock();
+                serde_json::to_writer(&mut stdout, &json)?;
+                writeln!(stdout)?;
+            }
+            #[cfg(feature = "postgres")]
+            Sink::Postgres(client) => {
+                let data = (!json["data"].is_null()).then(|| json["data"].to_string());
+                client
+                    .execute(
+                        INSERT_EVENT,
+                        &[
+                            &record.signature.to_string(),
+                            &(record.index as i32),
+                            &(record.slot as i64),
+                            &record.block_time,
+                            &json["event"].as_str(),
+                            &data,
+                            &record.data,
+                        ],
+                    )
+                    .await?;
+            }
+            #[cfg(feature = "kafka")]
+            Sink::Kafka { producer, topic } => {
+                // One key for every event keeps them on one partition, in
+                // order
+                let key = aistm7_sdk::ID.to_string();
+                let payload = json.to_string();
+                let message = rdkafka::producer::FutureRecord::to(topic)
+                    .key(&key)
+                    .payload(&payload);
+                producer
+                    .send(message, rdkafka::util::Timeout::Never)
+                    .await
+                    .map_err(|(err, _)| err)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn load_resume(path: &Path) -> Result<Option<Signature>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents
+            .trim()
+            .parse()
+            .map(Some)
+            .with_context(|| format!("reading resume file {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_resume(path: &Path, signature: &Signature) -> Result<()> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, signature.to_string())?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
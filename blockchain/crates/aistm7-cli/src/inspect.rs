@@ -0,0 +1,46 @@
+//! `aistm7 inspect`: decode an account without knowing its type up front
+
+use aistm7_sdk::json::keys_to_base58;
+use aistm7_sdk::{Aistm7Client, DecodedAccount};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+pub async fn run(client: &Aistm7Client, address: &Pubkey, json: bool) -> Result<()> {
+    let account = client
+        .rpc()
+        .get_account_with_commitment(address, client.rpc().commitment())
+        .await?
+        .value
+        .ok_or_else(|| anyhow!("account {address} does not exist"))?;
+    let decoded = DecodedAccount::decode(&account.data);
+
+    if json {
+        let data = match &decoded {
+            Some(decoded) => keys_to_base58(serde_json::to_value(decoded)?),
+            None => Value::Null,
+        };
+        let output = serde_json::json!({
+            "address": address.to_string(),
+            "owner": account.owner.to_string(),
+            "lamports": account.lamports,
+            "dataLength": account.data.len(),
+            "account": data,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("Address: {address}");
+    println!("Owner: {}", account.owner);
+    println!("Lamports: {}", account.lamports);
+    println!("Data: {} bytes", account.data.len());
+    match decoded {
+        Some(decoded) => println!("{}: {decoded:#?}", decoded.name()),
+        None => println!(
+            "Unrecognized account, discriminator {:02x?}",
+            &account.data[..account.data.len().min(8)]
+        ),
+    }
+    Ok(())
+}
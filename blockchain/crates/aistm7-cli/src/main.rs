@@ -0,0 +1,397 @@
+//! `aistm7`, the command line for administering and operating the AISTM7
+//! token program. Every command that sends a transaction signs with
+//! `--keypair`, which also pays, and `--dry-run` simulates it instead,
+//! printing the program logs and compute units without sending anything.
+
+mod backfill;
+mod inspect;
+mod migrate;
+mod snapshot;
+
+use std::path::PathBuf;
+
+use aistm7_sdk::instruction::{
+    self, AdminExtras, CrankExtras, UpdateParametersArgs, TOKEN_PROGRAM_ID,
+};
+use aistm7_sdk::state::Treasury;
+use aistm7_sdk::{pda, Aistm7Client};
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+
+/// Token-2022 program, for treasury vaults of token-2022 mints
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+#[derive(Parser)]
+#[command(name = "aistm7", version, about = "Administer and operate the AISTM7 token program")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(
+        long,
+        short = 'u',
+        env = "AISTM7_RPC_URL",
+        default_value = "http://127.0.0.1:8899",
+        global = true
+    )]
+    url: String,
+
+    /// Keypair file that signs and pays
+    #[arg(
+        long,
+        short = 'k',
+        env = "AISTM7_KEYPAIR",
+        default_value = "~/.config/solana/id.json",
+        global = true
+    )]
+    keypair: String,
+
+    /// Simulate the transaction and print its logs instead of sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Check the signer's role in the role registry rather than only
+    /// comparing it with the authority
+    #[arg(long, global = true)]
+    role_registry: bool,
+
+    /// Record admin actions in the admin log
+    #[arg(long, global = true)]
+    admin_log: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the program state and mint, with the initial supply in the
+    /// signer's associated account
+    Initialize {
+        /// Pyth price feed the requirement is priced from
+        #[arg(long)]
+        price_feed: Pubkey,
+        #[arg(long)]
+        initial_supply: u64,
+        #[arg(long)]
+        max_supply: u64,
+        /// Keypair file for the new mint; a fresh one is generated when
+        /// left out
+        #[arg(long)]
+        mint_keypair: Option<String>,
+    },
+    /// Change requirement parameters; those not passed stay as they are
+    UpdateParameters(UpdateParametersOpts),
+    /// Update the requirement from the oracle and collect the keeper reward
+    Crank {
+        /// Receives the reward; the signer's associated account by default
+        #[arg(long)]
+        keeper_token_account: Option<Pubkey>,
+        /// Feed the TWAP accumulator
+        #[arg(long)]
+        price_accumulator: bool,
+        /// Append to the requirement history
+        #[arg(long)]
+        requirement_history: bool,
+    },
+    Pause,
+    Unpause,
+    /// Mint new tokens, signed by a minter
+    Mint {
+        #[arg(long)]
+        destination: Pubkey,
+        /// Base units
+        #[arg(long)]
+        amount: u64,
+    },
+    #[command(subcommand)]
+    Treasury(TreasuryCommand),
+    /// Print the program state
+    State,
+    /// Decode and print any program account, detecting its type by its
+    /// discriminator
+    Inspect {
+        address: Pubkey,
+        /// Print JSON, with 32-byte keys and hashes in base58
+        #[arg(long)]
+        json: bool,
+    },
+    /// Upgrade accounts to the current layouts, after showing what changes
+    Migrate(migrate::MigrateOpts),
+    /// Export every holder's balance with a merkle root for distributions
+    Snapshot(snapshot::SnapshotOpts),
+    /// Replay every event the program has emitted to stdout, Postgres, or
+    /// Kafka, resuming after the last transaction replayed
+    Backfill(backfill::BackfillOpts),
+}
+
+#[derive(Args)]
+struct UpdateParametersOpts {
+    /// USD target in units of 10^-price_decimals
+    #[arg(long)]
+    target_usd_value: Option<u64>,
+    #[arg(long)]
+    min_tokens: Option<u64>,
+    #[arg(long)]
+    max_tokens: Option<u64>,
+    #[arg(long)]
+    price_decimals: Option<u8>,
+    #[arg(long)]
+    max_price_age_secs: Option<u64>,
+    #[arg(long)]
+    max_confidence_bps: Option<u64>,
+    #[arg(long)]
+    update_threshold_bps: Option<u64>,
+    #[arg(long)]
+    max_step_bps: Option<u64>,
+    #[arg(long)]
+    min_update_interval_secs: Option<u64>,
+    #[arg(long)]
+    use_ema_price: Option<bool>,
+}
+
+impl From<UpdateParametersOpts> for UpdateParametersArgs {
+    fn from(opts: UpdateParametersOpts) -> Self {
+        UpdateParametersArgs {
+            target_usd_value: opts.target_usd_value,
+            min_tokens: opts.min_tokens,
+            max_tokens: opts.max_tokens,
+            price_decimals: opts.price_decimals,
+            max_price_age_secs: opts.max_price_age_secs,
+            max_confidence_bps: opts.max_confidence_bps,
+            update_threshold_bps: opts.update_threshold_bps,
+            max_step_bps: opts.max_step_bps,
+            min_update_interval_secs: opts.min_update_interval_secs,
+            use_ema_price: opts.use_ema_price,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum TreasuryCommand {
+    /// Print the treasury's SOL balance and withdrawal limits
+    Show,
+    /// Lamports
+    DepositSol {
+        #[arg(long)]
+        amount: u64,
+    },
+    DepositTokens {
+        #[arg(long)]
+        mint: Pubkey,
+        /// The signer's associated account by default
+        #[arg(long)]
+        source: Option<Pubkey>,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Lamports, within the epoch's SOL limit
+    WithdrawSol {
+        #[arg(long)]
+        recipient: Pubkey,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Base units, within the epoch's limit for the mint
+    WithdrawTokens {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        recipient: Pubkey,
+        #[arg(long)]
+        amount: u64,
+        /// The mint belongs to the token-2022 program
+        #[arg(long)]
+        token_2022: bool,
+    },
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn load_keypair(path: &str) -> Result<Keypair> {
+    let path = expand_home(path);
+    read_keypair_file(&path).map_err(|err| anyhow!("reading {}: {err}", path.display()))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = Aistm7Client::with_url(cli.url.clone(), CommitmentConfig::confirmed());
+    // Read-only commands need no keypair
+    match cli.command {
+        Command::State => {
+            println!("{:#?}", client.token_state().await?);
+            return Ok(());
+        }
+        Command::Treasury(TreasuryCommand::Show) => return show_treasury(&client).await,
+        Command::Inspect { address, json } => return inspect::run(&client, &address, json).await,
+        Command::Snapshot(opts) => return snapshot::run(&client, opts).await,
+        Command::Backfill(opts) => return backfill::run(&client, opts).await,
+        _ => {}
+    }
+
+    let payer = load_keypair(&cli.keypair)?;
+    let signer = payer.pubkey();
+    let extras = AdminExtras {
+        role_registry: cli.role_registry,
+        admin_log: cli.admin_log,
+    };
+
+    let mut extra_signers = Vec::new();
+    let instructions: Vec<Instruction> = match cli.command {
+        Command::Migrate(opts) => return migrate::run(&client, &payer, opts, cli.dry_run).await,
+        Command::Initialize {
+            price_feed,
+            initial_supply,
+            max_supply,
+            mint_keypair,
+        } => {
+            let mint = match mint_keypair {
+                Some(path) => load_keypair(&path)?,
+                None => Keypair::new(),
+            };
+            println!("Mint: {}", mint.pubkey());
+            let ix = instruction::initialize(
+                &signer,
+                &mint.pubkey(),
+                &price_feed,
+                initial_supply,
+                max_supply,
+            );
+            extra_signers.push(mint);
+            vec![ix]
+        }
+        Command::UpdateParameters(opts) => {
+            vec![instruction::update_parameters(&signer, &opts.into(), extras)]
+        }
+        Command::Crank {
+            keeper_token_account,
+            price_accumulator,
+            requirement_history,
+        } => {
+            let state = client.token_state().await?;
+            let keeper_token_account = keeper_token_account
+                .unwrap_or_else(|| instruction::associated_token_account(&signer, &state.mint));
+            let crank_extras = CrankExtras {
+                fallback_price_feed: (state.fallback_price_feed != Pubkey::default())
+                    .then_some(state.fallback_price_feed),
+                price_accumulator,
+                requirement_history,
+            };
+            vec![instruction::crank_requirement(
+                &signer,
+                &state.price_feed,
+                &keeper_token_account,
+                crank_extras,
+            )]
+        }
+        Command::Pause => vec![instruction::pause(&signer, extras)],
+        Command::Unpause => vec![instruction::unpause(&signer, extras)],
+        Command::Mint { destination, amount } => {
+            let state = client.token_state().await?;
+            vec![instruction::mint_tokens(
+                &signer,
+                &state.mint,
+                &destination,
+                amount,
+                extras,
+            )]
+        }
+        Command::State
+        | Command::Treasury(TreasuryCommand::Show)
+        | Command::Inspect { .. }
+        | Command::Snapshot(_)
+        | Command::Backfill(_) => unreachable!(),
+        Command::Treasury(TreasuryCommand::DepositSol { amount }) => {
+            vec![instruction::deposit_treasury_sol(&signer, amount)]
+        }
+        Command::Treasury(TreasuryCommand::DepositTokens {
+            mint,
+            source,
+            amount,
+        }) => {
+            let source =
+                source.unwrap_or_else(|| instruction::associated_token_account(&signer, &mint));
+            vec![instruction::deposit_treasury_tokens(&signer, &source, &mint, amount)]
+        }
+        Command::Treasury(TreasuryCommand::WithdrawSol { recipient, amount }) => {
+            vec![instruction::withdraw_treasury_sol(&signer, &recipient, amount, extras)]
+        }
+        Command::Treasury(TreasuryCommand::WithdrawTokens {
+            mint,
+            recipient,
+            amount,
+            token_2022,
+        }) => {
+            let token_program = if token_2022 {
+                TOKEN_2022_PROGRAM_ID
+            } else {
+                TOKEN_PROGRAM_ID
+            };
+            vec![instruction::withdraw_treasury_tokens(
+                &signer,
+                &mint,
+                &recipient,
+                amount,
+                &token_program,
+                extras,
+            )]
+        }
+    };
+
+    let mut signers: Vec<&dyn Signer> = vec![&payer];
+    signers.extend(extra_signers.iter().map(|keypair| keypair as &dyn Signer));
+    if cli.dry_run {
+        let result = client.simulate(&instructions, &signers).await?;
+        match result.err {
+            Some(err) => println!("Simulation failed: {err}"),
+            None => println!("Simulation succeeded"),
+        }
+        if let Some(units) = result.units_consumed {
+            println!("Compute units: {units}");
+        }
+        for line in result.logs.unwrap_or_default() {
+            println!("  {line}");
+        }
+    } else {
+        let signature = client
+            .send(&instructions, &signers)
+            .await
+            .context("sending transaction")?;
+        println!("Signature: {signature}");
+    }
+    Ok(())
+}
+
+async fn show_treasury(client: &Aistm7Client) -> Result<()> {
+    let (address, _) = pda::treasury();
+    let treasury: Treasury = client.fetch(&address).await?;
+    let lamports = client.rpc().get_balance(&address).await?;
+    println!("Treasury: {address}");
+    println!("SOL balance: {lamports} lamports");
+    println!("Epoch: {}s from {}", treasury.epoch_secs, treasury.epoch_start);
+    for limit in treasury
+        .limits
+        .iter()
+        .filter(|limit| limit.per_epoch_limit > 0 || limit.withdrawn > 0)
+    {
+        let asset = if limit.asset == Pubkey::default() {
+            "SOL".to_string()
+        } else {
+            limit.asset.to_string()
+        };
+        println!(
+            "  {asset}: {} of {} withdrawn this epoch",
+            limit.withdrawn, limit.per_epoch_limit
+        );
+    }
+    Ok(())
+}
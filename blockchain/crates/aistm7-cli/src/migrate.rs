@@ -0,0 +1,287 @@
+//! `aistm7 migrate`: upgrade program accounts to the current layouts.
+//! Every pending step is simulated first and the account's fields before
+//! and after are diffed, so nothing is sent until the changes have been
+//! reviewed and confirmed. Steps are sent one transaction at a time, and
+//! each one that lands is written to the checkpoint file, so a run that
+//! stops halfway picks up where it left off.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use aistm7_sdk::instruction;
+use aistm7_sdk::json::keys_to_base58;
+use aistm7_sdk::pda;
+use aistm7_sdk::state::{state_version, ProgramAccount, TokenState, TokenStateV0, STATE_VERSION};
+use aistm7_sdk::Aistm7Client;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use serde_json::{Map, Value};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+#[derive(Args)]
+pub struct MigrateOpts {
+    /// Price feed to pin when upgrading a v0 state, which stored none
+    #[arg(long)]
+    price_feed: Option<Pubkey>,
+    /// Send without asking for confirmation
+    #[arg(long)]
+    yes: bool,
+    /// Where completed steps are recorded
+    #[arg(long, default_value = ".aistm7-migrate.json")]
+    checkpoint: PathBuf,
+}
+
+/// One account to upgrade and the instruction that does it
+struct Step {
+    label: String,
+    address: Pubkey,
+    instruction: Instruction,
+}
+
+pub async fn run(
+    client: &Aistm7Client,
+    payer: &Keypair,
+    opts: MigrateOpts,
+    dry_run: bool,
+) -> Result<()> {
+    let mut checkpoint = load_checkpoint(&opts.checkpoint)?;
+    let steps: Vec<Step> = plan(client, payer, &opts)
+        .await?
+        .into_iter()
+        .filter(|step| {
+            let done = checkpoint.contains_key(&step.address.to_string());
+            if done {
+                println!("{}: already migrated, skipping", step.label);
+            }
+            !done
+        })
+        .collect();
+    if steps.is_empty() {
+        println!("Nothing to migrate");
+        return Ok(());
+    }
+
+    let signers: [&dyn Signer; 1] = [payer];
+    for step in &steps {
+        let before = account_data(client, &step.address).await?;
+        let (result, after) = client
+            .simulate_with_accounts(std::slice::from_ref(&step.instruction), &signers, &[step.address])
+            .await?;
+        if let Some(err) = result.err {
+            for line in result.logs.unwrap_or_default() {
+                println!("  {line}");
+            }
+            bail!("{}: simulation failed: {err}", step.label);
+        }
+        let after = after
+            .into_iter()
+            .next()
+            .flatten()
+            .ok_or_else(|| anyhow!("{}: account missing after simulation", step.label))?;
+        println!("{} ({}):", step.label, step.address);
+        print_diff(&decode_state(&before)?, &decode_state(&after)?);
+    }
+
+    if dry_run {
+        println!("Dry run: nothing sent");
+        return Ok(());
+    }
+    if !opts.yes && !confirm(&format!("Send {} migration transaction(s)?", steps.len()))? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    for step in &steps {
+        let signature = client
+            .send(std::slice::from_ref(&step.instruction), &signers)
+            .await
+            .with_context(|| format!("{}: sending migration", step.label))?;
+        println!("{}: migrated in {signature}", step.label);
+        checkpoint.insert(step.address.to_string(), Value::String(signature.to_string()));
+        save_checkpoint(&opts.checkpoint, &checkpoint)?;
+    }
+    // Every step landed, so a later migration starts from scratch
+    fs::remove_file(&opts.checkpoint).ok();
+    Ok(())
+}
+
+/// Accounts not yet at the current layout. Only the state account has
+/// older layouts today; per-user accounts get their own steps here when
+/// their layouts change.
+async fn plan(client: &Aistm7Client, payer: &Keypair, opts: &MigrateOpts) -> Result<Vec<Step>> {
+    let (state, _) = pda::token_state();
+    let data = account_data(client, &state).await?;
+    let version = state_version(&data).ok_or_else(|| anyhow!("{state} is not a TokenState"))?;
+    println!("State layout: v{version}, current: v{STATE_VERSION}");
+    if version == STATE_VERSION {
+        return Ok(Vec::new());
+    }
+    if version > STATE_VERSION {
+        bail!("state is newer than this tool knows; update aistm7-cli");
+    }
+    if version == 0 && opts.price_feed.is_none() {
+        bail!("upgrading a v0 state needs --price-feed");
+    }
+    Ok(vec![Step {
+        label: "token_state".to_string(),
+        address: state,
+        instruction: instruction::migrate_state(&payer.pubkey(), opts.price_feed),
+    }])
+}
+
+async fn account_data(client: &Aistm7Client, address: &Pubkey) -> Result<Vec<u8>> {
+    Ok(client.rpc().get_account(address).await?.data)
+}
+
+/// State fields as JSON, in whichever layout `data` holds
+fn decode_state(data: &[u8]) -> Result<Value> {
+    let value = match state_version(data) {
+        Some(0) => serde_json::to_value(TokenStateV0::from_account_data(data)?)?,
+        Some(_) => serde_json::to_value(TokenState::from_account_data(data)?)?,
+        None => bail!("not a TokenState"),
+    };
+    Ok(keys_to_base58(value))
+}
+
+fn flatten(prefix: String, value: Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(path, value, fields);
+            }
+        }
+        Value::Array(items) => {
+            for (index, value) in items.into_iter().enumerate() {
+                flatten(format!("{prefix}[{index}]"), value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix, value);
+        }
+    }
+}
+
+fn print_diff(before: &Value, after: &Value) {
+    for line in diff(before, after) {
+        println!("  {line}");
+    }
+}
+
+/// Each field the migration adds (`+`), changes (`~`), or drops (`-`),
+/// then a count of the unchanged fields it leaves out
+fn diff(before: &Value, after: &Value) -> Vec<String> {
+    let mut old = BTreeMap::new();
+    let mut new = BTreeMap::new();
+    flatten(String::new(), before.clone(), &mut old);
+    flatten(String::new(), after.clone(), &mut new);
+    let mut lines = Vec::new();
+    let mut unchanged = 0;
+    for (field, value) in &new {
+        match old.get(field) {
+            None => lines.push(format!("+ {field}: {value}")),
+            Some(previous) if previous != value => {
+                lines.push(format!("~ {field}: {previous} -> {value}"))
+            }
+            Some(_) => unchanged += 1,
+        }
+    }
+    for (field, value) in old.iter().filter(|(field, _)| !new.contains_key(*field)) {
+        lines.push(format!("- {field}: {value}"));
+    }
+    lines.push(format!("({unchanged} fields unchanged)"));
+    lines
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Addresses already migrated, each with the signature that did it
+fn load_checkpoint(path: &Path) -> Result<Map<String, Value>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .with_context(|| format!("reading checkpoint {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Map::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_checkpoint(path: &Path, checkpoint: &Map<String, Value>) -> Result<()> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, serde_json::to_string_pretty(checkpoint)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn flattens_nested_fields_into_paths() {
+        let mut fields = BTreeMap::new();
+        flatten(
+            String::new(),
+            json!({ "oracle": { "feeds": [{ "weight": 1 }, { "weight": 2 }] }, "paused": false }),
+            &mut fields,
+        );
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("oracle.feeds[0].weight".to_string(), json!(1)),
+                ("oracle.feeds[1].weight".to_string(), json!(2)),
+                ("paused".to_string(), json!(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_added_changed_and_dropped_fields() {
+        let before = json!({ "version": 0, "authority": "A", "legacy_feed": "F", "paused": false });
+        let after = json!({ "version": 1, "authority": "A", "price_feed": "P", "paused": false });
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                "+ price_feed: \"P\"",
+                "~ version: 0 -> 1",
+                "- legacy_feed: \"F\"",
+                "(2 fields unchanged)",
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_nothing_for_an_unchanged_state() {
+        let state = json!({ "version": 1, "tiers": [1, 2, 3] });
+        assert_eq!(diff(&state, &state), vec!["(4 fields unchanged)"]);
+    }
+
+    #[test]
+    fn checkpoints_round_trip_and_start_empty() {
+        let path = std::env::temp_dir().join(format!("aistm7-migrate-{}.json", std::process::id()));
+        fs::remove_file(&path).ok();
+        assert!(load_checkpoint(&path).unwrap().is_empty());
+
+        let mut checkpoint = Map::new();
+        checkpoint.insert("state".to_string(), Value::String("sig".to_string()));
+        save_checkpoint(&path, &checkpoint).unwrap();
+        assert_eq!(load_checkpoint(&path).unwrap(), checkpoint);
+        assert!(!path.with_extension("partial").exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
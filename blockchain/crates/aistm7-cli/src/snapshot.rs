@@ -0,0 +1,148 @@
+//! `aistm7 snapshot`: the balance of every holder of the mint, written as
+//! CSV or JSON with a merkle root `create_distribution` and
+//! `publish_revenue_root` accept. Holders are sorted by address, so the same
+//! balances always give the same indexes and root.
+//!
+//! RPC nodes only serve current account state, so the snapshot is read
+//! once the node has reached `--slot` and records the slot it was actually
+//! read at; run it at the slot it is meant for.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use aistm7_sdk::interface::merkle::MerkleTree;
+use aistm7_sdk::state::{StakeAccount, VestingSchedule};
+use aistm7_sdk::Aistm7Client;
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Args)]
+pub struct SnapshotOpts {
+    /// Slot the snapshot is taken at
+    #[arg(long)]
+    slot: u64,
+    #[arg(long, value_enum, default_value = "json")]
+    format: Format,
+    /// Written to stdout when left out
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Leave out holders with less than this many base units in total
+    #[arg(long, default_value_t = 1)]
+    min_balance: u64,
+    /// Leave staked tokens out of each holder's balance
+    #[arg(long)]
+    no_staking: bool,
+    /// Leave tokens still vesting out of each beneficiary's balance
+    #[arg(long)]
+    no_vesting: bool,
+}
+
+/// One holder's balance and where it is held
+#[derive(Clone, Copy, Default)]
+struct Holding {
+    wallet: u64,
+    staked: u64,
+    vesting: u64,
+}
+
+impl Holding {
+    fn total(&self) -> u64 {
+        self.wallet + self.staked + self.vesting
+    }
+}
+
+pub async fn run(client: &Aistm7Client, opts: SnapshotOpts) -> Result<()> {
+    let read_at = client.rpc().get_slot().await?;
+    if read_at < opts.slot {
+        bail!("the node is at slot {read_at}, before {}", opts.slot);
+    }
+    if read_at > opts.slot {
+        eprintln!("Reading at slot {read_at}, {} after the one asked for", read_at - opts.slot);
+    }
+    let mint = client.token_state().await?.mint;
+
+    let mut holdings: BTreeMap<Pubkey, Holding> = BTreeMap::new();
+    for (owner, amount) in client.token_balances(&mint, Some(opts.slot)).await? {
+        // Vaults, escrows, and pools hold tokens for others; those held for
+        // stakers and beneficiaries are counted below
+        if owner.is_on_curve() {
+            holdings.entry(owner).or_default().wallet += amount;
+        }
+    }
+    if !opts.no_staking {
+        for (_, stake) in client.fetch_all::<StakeAccount>().await? {
+            holdings.entry(stake.owner).or_default().staked +=
+                stake.amount + stake.unstaking_amount;
+        }
+    }
+    if !opts.no_vesting {
+        for (_, schedule) in client.fetch_all::<VestingSchedule>().await? {
+            holdings.entry(schedule.beneficiary).or_default().vesting += schedule.unclaimed();
+        }
+    }
+    holdings.retain(|_, holding| holding.total() >= opts.min_balance);
+
+    let tree = MerkleTree::from_entries(holdings.iter().map(|(owner, h)| (owner, h.total())));
+    let root = bs58::encode(tree.root()).into_string();
+    let total: u64 = holdings.values().map(Holding::total).sum();
+    eprintln!("{} holders, {total} base units, merkle root {root}", holdings.len());
+
+    let output = match opts.format {
+        Format::Csv => {
+            let mut csv = String::from("index,owner,amount,wallet,staked,vesting\n");
+            for (index, (owner, holding)) in holdings.iter().enumerate() {
+                csv.push_str(&format!(
+                    "{index},{owner},{},{},{},{}\n",
+                    holding.total(),
+                    holding.wallet,
+                    holding.staked,
+                    holding.vesting
+                ));
+            }
+            csv
+        }
+        Format::Json => {
+            let holders: Vec<_> = holdings
+                .iter()
+                .enumerate()
+                .map(|(index, (owner, holding))| {
+                    let proof: Vec<String> = tree
+                        .proof(index)
+                        .iter()
+                        .map(|node| bs58::encode(node).into_string())
+                        .collect();
+                    json!({
+                        "index": index,
+                        "owner": owner.to_string(),
+                        "amount": holding.total(),
+                        "wallet": holding.wallet,
+                        "staked": holding.staked,
+                        "vesting": holding.vesting,
+                        "proof": proof,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&json!({
+                "slot": read_at,
+                "mint": mint.to_string(),
+                "merkleRoot": root,
+                "totalAmount": total,
+                "holders": holders,
+            }))?
+        }
+    };
+    match opts.out {
+        Some(path) => fs::write(path, output)?,
+        None => print!("{output}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! Reading the program's accounts into the metrics. A read that fails is
+//! logged and counted under its stage, and leaves the metrics it would have
+//! set at their last values; `up` covers the token state, which every
+//! oracle and keeper metric comes from.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use aistm7_sdk::state::{StakePool, TokenState, Treasury, VerificationReceipt};
+use aistm7_sdk::{pda, Aistm7Client, ID};
+use anyhow::Result;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::metrics::Metrics;
+
+/// Most signatures the RPC returns in one page
+const SIGNATURE_PAGE: usize = 1_000;
+/// What Anchor logs at the start of a `crank_requirement` instruction
+const CRANK_LOG: &str = "Program log: Instruction: CrankRequirement";
+
+pub struct Collector {
+    client: Aistm7Client,
+    metrics: Arc<Metrics>,
+    /// Newest program transaction already looked at for keeper failures;
+    /// `None` until the first collection, which only records where to start
+    keeper_cursor: Option<Signature>,
+}
+
+impl Collector {
+    pub fn new(client: Aistm7Client, metrics: Arc<Metrics>) -> Self {
+        Collector {
+            client,
+            metrics,
+            keeper_cursor: None,
+        }
+    }
+
+    /// Read everything once; verification receipts, which take a scan of
+    /// every receipt account, only when `receipts` is set
+    pub async fn collect(&mut self, now: i64, receipts: bool) {
+        match self.client.token_state().await {
+            Ok(state) => {
+                self.metrics.up.set(1);
+                self.token_state(&state, now);
+                self.vaults(&state).await;
+            }
+            Err(err) => {
+                self.metrics.up.set(0);
+                self.failed("state", err);
+            }
+        }
+        match self.client.fetch_optional::<StakePool>(&pda::stake_pool().0).await {
+            Ok(pool) => self
+                .metrics
+                .staked
+                .set(pool.map_or(0, |pool| pool.total_staked) as f64),
+            Err(err) => self.failed("stake_pool", err),
+        }
+        if receipts {
+            if let Err(err) = self.active_verifications(now).await {
+                self.failed("receipts", err);
+            }
+        }
+        if let Err(err) = self.keeper_failures().await {
+            self.failed("keeper", err);
+        }
+        self.metrics.last_collect.set(now);
+    }
+
+    fn token_state(&self, state: &TokenState, now: i64) {
+        let metrics = &self.metrics;
+        metrics.requirement.set(state.current_requirement as f64);
+        metrics.oracle_price.set(state.last_price as f64);
+        metrics.oracle_price_decimals.set(state.price_decimals.into());
+        metrics.oracle_staleness.set(now.saturating_sub(state.last_refresh));
+        metrics.oracle_max_age.set(state.max_price_age_secs as i64);
+        metrics.requirement_age.set(now.saturating_sub(state.last_update));
+        metrics.crank_age.set(now.saturating_sub(state.last_crank));
+        metrics.crank_interval.set(state.crank_interval_secs as i64);
+        metrics
+            .circuit_breaker_tripped
+            .set(state.circuit_breaker_tripped.into());
+        metrics.paused.set(state.paused.into());
+    }
+
+    /// The treasury's SOL and token vaults, the keeper vault, and the
+    /// insurance vault
+    async fn vaults(&self, state: &TokenState) {
+        let mint = state.mint.to_string();
+        let treasury = pda::treasury().0;
+        match self.client.rpc().get_balance(&treasury).await {
+            Ok(lamports) => self.balance("treasury", "SOL", lamports),
+            Err(err) => self.failed("treasury", err),
+        }
+        match self.client.fetch_optional::<Treasury>(&treasury).await {
+            Ok(Some(treasury)) => {
+                let assets = treasury.limits.iter().map(|limit| limit.asset);
+                for asset in assets.filter(|asset| *asset != Pubkey::default()) {
+                    let vault = pda::treasury_token_vault(&asset).0;
+                    self.token_vault("treasury", &asset.to_string(), &vault).await;
+                }
+            }
+            Ok(None) => {}
+            Err(err) => self.failed("treasury", err),
+        }
+        self.token_vault("keeper", &mint, &pda::keeper_vault().0).await;
+        if state.insurance_vault != Pubkey::default() {
+            self.token_vault("insurance", &mint, &state.insurance_vault).await;
+        }
+    }
+
+    async fn token_vault(&self, vault: &str, asset: &str, address: &Pubkey) {
+        match self.client.token_amount(address).await {
+            Ok(amount) => self.balance(vault, asset, amount.unwrap_or(0)),
+            Err(err) => self.failed(vault, err),
+        }
+    }
+
+    fn balance(&self, vault: &str, asset: &str, amount: u64) {
+        self.metrics
+            .vault_balance
+            .with_label_values(&[vault, asset])
+            .set(amount as f64);
+    }
+
+    async fn active_verifications(&self, now: i64) -> Result<()> {
+        let receipts = self.client.fetch_all::<VerificationReceipt>().await?;
+        let active = &self.metrics.active_verifications;
+        // Tiers no receipt holds any more drop to zero rather than
+        // keeping their last count
+        active.reset();
+        for (_, receipt) in receipts.iter().filter(|(_, receipt)| receipt.grants_access(now)) {
+            active.with_label_values(&[&receipt.tier.to_string()]).inc();
+        }
+        Ok(())
+    }
+
+    /// Count the failed `crank_requirement` transactions since the last
+    /// collection
+    async fn keeper_failures(&mut self) -> Result<()> {
+        let Some(cursor) = self.keeper_cursor else {
+            self.keeper_cursor = self.newest_signature().await?;
+            return Ok(());
+        };
+        let statuses = self.signatures_after(cursor).await?;
+        for status in statuses.iter().filter(|status| status.err.is_some()) {
+            let signature = Signature::from_str(&status.signature)?;
+            let logs = match self.logs(&signature).await {
+                Ok(logs) => logs,
+                Err(err) => {
+                    self.failed("keeper", err);
+                    continue;
+                }
+            };
+            if logs.iter().any(|log| log == CRANK_LOG) {
+                let error = anchor_error(&logs).unwrap_or("other");
+                self.metrics.keeper_failures.with_label_values(&[error]).inc();
+            }
+        }
+        if let Some(newest) = statuses.first() {
+            self.keeper_cursor = Some(Signature::from_str(&newest.signature)?);
+        }
+        Ok(())
+    }
+
+    async fn newest_signature(&self) -> Result<Option<Signature>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until: None,
+            limit: Some(1),
+            commitment: Some(self.client.rpc().commitment()),
+        };
+        let newest = self
+            .client
+            .rpc()
+            .get_signatures_for_address_with_config(&ID, config)
+            .await?;
+        newest
+            .first()
+            .map(|status| Signature::from_str(&status.signature))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// The program's transactions after `until`, failed ones included,
+    /// newest first
+    async fn signatures_after(
+        &self,
+        until: Signature,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let mut statuses = Vec::new();
+        let mut before = None;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: Some(until),
+                limit: Some(SIGNATURE_PAGE),
+                commitment: Some(self.client.rpc().commitment()),
+            };
+            let page = self
+                .client
+                .rpc()
+                .get_signatures_for_address_with_config(&ID, config)
+                .await?;
+            let full = page.len() == SIGNATURE_PAGE;
+            if let Some(last) = page.last() {
+                before = Some(Signature::from_str(&last.signature)?);
+            }
+            statuses.extend(page);
+            if !full {
+                return Ok(statuses);
+            }
+        }
+    }
+
+    async fn logs(&self, signature: &Signature) -> Result<Vec<String>> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(self.client.rpc().commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self
+            .client
+            .rpc()
+            .get_transaction_with_config(signature, config)
+            .await?;
+        let logs: Option<Vec<String>> = transaction
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages.into());
+        Ok(logs.unwrap_or_default())
+    }
+
+    fn failed(&self, stage: &str, err: impl Display) {
+        eprintln!("Reading {stage} failed: {err}");
+        self.metrics.collect_errors.with_label_values(&[stage]).inc();
+    }
+}
+
+/// Name of the program error a transaction failed with, as Anchor logs it:
+/// `AnchorError ... Error Code: CrankTooSoon. Error Number: ...`
+fn anchor_error(logs: &[String]) -> Option<&str> {
+    logs.iter().find_map(|log| {
+        let (_, rest) = log.split_once("Error Code: ")?;
+        rest.split('.').next()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_error_name_anchor_logs() {
+        let logs = vec![
+            "Program log: Instruction: CrankRequirement".to_string(),
+            "Program log: AnchorError thrown in programs/aistm7_token/src/lib.rs:812. \
+             Error Code: CrankTooSoon. Error Number: 6042. Error Message: Crank too soon."
+                .to_string(),
+        ];
+        assert_eq!(anchor_error(&logs), Some("CrankTooSoon"));
+    }
+
+    #[test]
+    fn finds_no_error_in_other_logs() {
+        let logs = vec!["Program log: Instruction: CrankRequirement".to_string()];
+        assert_eq!(anchor_error(&logs), None);
+        assert_eq!(anchor_error(&[]), None);
+    }
+}
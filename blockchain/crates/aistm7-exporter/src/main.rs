@@ -0,0 +1,97 @@
+//! `aistm7-exporter`, publishing the program's on-chain health for
+//! Prometheus at `/metrics`, so an oracle outage or a stalled keeper pages
+//! someone before users notice. `metrics` lists what is exported and
+//! `collect` how each value is read.
+
+mod collect;
+mod metrics;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aistm7_sdk::Aistm7Client;
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use prometheus::{Encoder, TextEncoder};
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::collect::Collector;
+use crate::metrics::Metrics;
+
+#[derive(Parser)]
+#[command(name = "aistm7-exporter", version, about = "Export AISTM7 on-chain health to Prometheus")]
+struct Opts {
+    #[arg(long, env = "AISTM7_EXPORTER_LISTEN", default_value = "0.0.0.0:9464")]
+    listen: SocketAddr,
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Seconds between collections
+    #[arg(long, default_value_t = 30)]
+    interval_secs: u64,
+    /// Seconds between counts of active verifications, which read every
+    /// receipt account
+    #[arg(long, default_value_t = 300)]
+    receipts_interval_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let metrics = Arc::new(Metrics::new()?);
+    let client = Aistm7Client::with_url(opts.url, CommitmentConfig::confirmed());
+    let mut collector = Collector::new(client, metrics.clone());
+    let receipts_interval = Duration::from_secs(opts.receipts_interval_secs);
+    let mut interval = tokio::time::interval(Duration::from_secs(opts.interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    tokio::spawn(async move {
+        let mut receipts_due = Instant::now();
+        loop {
+            interval.tick().await;
+            let receipts = Instant::now() >= receipts_due;
+            if receipts {
+                receipts_due = Instant::now() + receipts_interval;
+            }
+            collector.collect(unix_now(), receipts).await;
+        }
+    });
+
+    let app = Router::new()
+        .route("/metrics", get(export))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(metrics);
+    eprintln!("Listening on {}", opts.listen);
+    axum::Server::bind(&opts.listen)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+async fn export(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    match encoder.encode(&metrics.registry.gather(), &mut body) {
+        Ok(()) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, encoder.format_type().to_string())],
+            body,
+        ),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain".to_string())],
+            err.to_string().into_bytes(),
+        ),
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
@@ -0,0 +1,152 @@
+//! The metrics exported. Token amounts are in base units; ages are seconds
+//! against the exporter's clock. Thresholds the program is configured with
+//! are exported beside the values they bound, so alerts can compare the
+//! two instead of hardcoding the configuration.
+
+use prometheus::{Gauge, GaugeVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
+
+pub struct Metrics {
+    pub registry: Registry,
+    pub up: IntGauge,
+    pub collect_errors: IntCounterVec,
+    pub last_collect: IntGauge,
+
+    pub requirement: Gauge,
+    pub oracle_price: Gauge,
+    pub oracle_price_decimals: IntGauge,
+    pub oracle_staleness: IntGauge,
+    pub oracle_max_age: IntGauge,
+    pub requirement_age: IntGauge,
+    pub crank_age: IntGauge,
+    pub crank_interval: IntGauge,
+    pub circuit_breaker_tripped: IntGauge,
+    pub paused: IntGauge,
+
+    pub vault_balance: GaugeVec,
+    pub staked: Gauge,
+    pub active_verifications: IntGaugeVec,
+    pub keeper_failures: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("aistm7".to_string()), None)?;
+        let gauge = |name: &str, help: &str| -> prometheus::Result<Gauge> {
+            let gauge = Gauge::new(name, help)?;
+            registry.register(Box::new(gauge.clone()))?;
+            Ok(gauge)
+        };
+        let int_gauge = |name: &str, help: &str| -> prometheus::Result<IntGauge> {
+            let gauge = IntGauge::new(name, help)?;
+            registry.register(Box::new(gauge.clone()))?;
+            Ok(gauge)
+        };
+
+        let collect_errors = IntCounterVec::new(
+            Opts::new("collect_errors_total", "Failed reads, by what was being read"),
+            &["stage"],
+        )?;
+        registry.register(Box::new(collect_errors.clone()))?;
+        let vault_balance = GaugeVec::new(
+            Opts::new("vault_balance", "Balance of a program-held vault, in base units"),
+            &["vault", "asset"],
+        )?;
+        registry.register(Box::new(vault_balance.clone()))?;
+        let active_verifications = IntGaugeVec::new(
+            Opts::new("active_verifications", "Verification receipts granting access now, by tier"),
+            &["tier"],
+        )?;
+        registry.register(Box::new(active_verifications.clone()))?;
+        let keeper_failures = IntCounterVec::new(
+            Opts::new(
+                "keeper_failures_total",
+                "Failed crank_requirement transactions seen since the exporter started, \
+                 by the program error they failed with",
+            ),
+            &["error"],
+        )?;
+        registry.register(Box::new(keeper_failures.clone()))?;
+
+        Ok(Metrics {
+            up: int_gauge("up", "Whether the last read of the token state succeeded")?,
+            last_collect: int_gauge(
+                "last_collect_timestamp_seconds",
+                "Unix time of the last collection",
+            )?,
+            requirement: gauge("requirement_tokens", "Current balance requirement")?,
+            oracle_price: gauge("oracle_price", "Price the requirement was last set from")?,
+            oracle_price_decimals: int_gauge(
+                "oracle_price_decimals",
+                "Decimals of aistm7_oracle_price",
+            )?,
+            oracle_staleness: int_gauge(
+                "oracle_staleness_seconds",
+                "Seconds since the oracle price was last read into the requirement",
+            )?,
+            oracle_max_age: int_gauge(
+                "oracle_max_age_seconds",
+                "Oldest oracle price the program accepts",
+            )?,
+            requirement_age: int_gauge(
+                "requirement_age_seconds",
+                "Seconds since the requirement last changed",
+            )?,
+            crank_age: int_gauge(
+                "crank_age_seconds",
+                "Seconds since the requirement was last cranked",
+            )?,
+            crank_interval: int_gauge(
+                "crank_interval_seconds",
+                "Least time the program allows between cranks",
+            )?,
+            circuit_breaker_tripped: int_gauge(
+                "circuit_breaker_tripped",
+                "1 while the price circuit breaker holds the requirement",
+            )?,
+            paused: int_gauge("paused", "1 while the program is paused")?,
+            staked: gauge("staked_tokens", "Tokens staked across every position")?,
+            registry,
+            collect_errors,
+            vault_balance,
+            active_verifications,
+            keeper_failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::{Encoder, TextEncoder};
+
+    use super::*;
+
+    #[test]
+    fn registers_every_metric_under_the_prefix() {
+        let metrics = Metrics::new().unwrap();
+        metrics.vault_balance.with_label_values(&["treasury", "sol"]).set(1.0);
+        metrics.active_verifications.with_label_values(&["1"]).set(2);
+        metrics.collect_errors.with_label_values(&["state"]).inc();
+        metrics.keeper_failures.with_label_values(&["CrankTooSoon"]).inc();
+        let names: Vec<String> = metrics
+            .registry
+            .gather()
+            .iter()
+            .map(|family| family.get_name().to_string())
+            .collect();
+        assert_eq!(names.len(), 17);
+        assert!(names.iter().all(|name| name.starts_with("aistm7_")), "{names:?}");
+        assert!(names.contains(&"aistm7_keeper_failures_total".to_string()));
+    }
+
+    #[test]
+    fn exports_labelled_values_as_text() {
+        let metrics = Metrics::new().unwrap();
+        metrics.requirement.set(1_500.0);
+        metrics.vault_balance.with_label_values(&["insurance", "AiSTM7"]).set(20.0);
+        let mut text = Vec::new();
+        TextEncoder::new().encode(&metrics.registry.gather(), &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("aistm7_requirement_tokens 1500\n"));
+        assert!(text.contains("aistm7_vault_balance{asset=\"AiSTM7\",vault=\"insurance\"} 20\n"));
+    }
+}
@@ -0,0 +1,124 @@
+//! `#[aistm7_gated]` for Anchor account structs. Placed above
+//! `#[derive(Accounts)]`, it appends the wallet's AISTM7 receipt and access
+//! override to the struct, constrained so the instruction fails with the
+//! program's `AccessRequired` error unless the wallet has access:
+//!
+//! ```ignore
+//! use aistm7_gated::aistm7_gated;
+//!
+//! #[aistm7_gated(wallet = user, min_tier = 2)]
+//! #[derive(Accounts)]
+//! pub struct UsePremiumFeature<'info> {
+//!     pub user: Signer<'info>,
+//! }
+//! ```
+//!
+//! Clients pass the two extra accounts, `aistm7_receipt` and
+//! `aistm7_access_override`, at the wallet's receipt and override PDAs.
+//! The expansion refers to `aistm7_interface` and `anchor_lang`, so the
+//! program depends on both. `min_tier` defaults to 1.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Error, Expr, Field, Fields, Ident, ItemStruct, Token};
+
+struct Arg {
+    name: Ident,
+    value: Expr,
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Arg { name, value })
+    }
+}
+
+struct GateArgs {
+    wallet: Ident,
+    min_tier: Expr,
+}
+
+impl Parse for GateArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut wallet = None;
+        let mut min_tier = None;
+        for arg in Punctuated::<Arg, Token![,]>::parse_terminated(input)? {
+            match arg.name.to_string().as_str() {
+                "wallet" => match arg.value {
+                    Expr::Path(path) if path.path.get_ident().is_some() => {
+                        wallet = path.path.get_ident().cloned();
+                    }
+                    value => return Err(Error::new_spanned(value, "expected a field name")),
+                },
+                "min_tier" => min_tier = Some(arg.value),
+                _ => return Err(Error::new_spanned(arg.name, "expected `wallet` or `min_tier`")),
+            }
+        }
+        Ok(GateArgs {
+            wallet: wallet.ok_or_else(|| input.error("missing `wallet = <field>`"))?,
+            min_tier: min_tier.unwrap_or_else(|| syn::parse_quote!(1)),
+        })
+    }
+}
+
+#[proc_macro_attribute]
+pub fn aistm7_gated(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as GateArgs);
+    let mut item = parse_macro_input!(input as ItemStruct);
+    match gate(&args, &mut item) {
+        Ok(()) => quote!(#item).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn gate(args: &GateArgs, item: &mut ItemStruct) -> syn::Result<()> {
+    let lifetime = item
+        .generics
+        .lifetimes()
+        .next()
+        .map(|def| def.lifetime.clone())
+        .ok_or_else(|| Error::new_spanned(&item.ident, "expected an `'info` lifetime"))?;
+    let Fields::Named(fields) = &mut item.fields else {
+        return Err(Error::new_spanned(&item.ident, "expected named fields"));
+    };
+    let wallet = &args.wallet;
+    if !fields.named.iter().any(|field| field.ident.as_ref() == Some(wallet)) {
+        return Err(Error::new_spanned(wallet, "no such field"));
+    }
+    let min_tier = &args.min_tier;
+    let access_required = quote! {
+        ::anchor_lang::prelude::ProgramError::Custom(::aistm7_interface::ACCESS_REQUIRED_ERROR)
+    };
+
+    let receipt = quote! {
+        /// CHECK: The wallet's AISTM7 receipt, checked by `#[aistm7_gated]`
+        #[account(
+            seeds = [b"receipt", #wallet.key().as_ref()],
+            bump,
+            seeds::program = ::aistm7_interface::ID,
+            constraint = ::aistm7_interface::gate::aistm7_verified(
+                &aistm7_receipt,
+                &aistm7_access_override,
+                #min_tier,
+            ) @ #access_required,
+        )]
+        pub aistm7_receipt: ::anchor_lang::prelude::AccountInfo<#lifetime>
+    };
+    let access_override = quote! {
+        /// CHECK: The wallet's AISTM7 access override, read with the receipt
+        #[account(
+            seeds = [b"access_override", #wallet.key().as_ref()],
+            bump,
+            seeds::program = ::aistm7_interface::ID,
+        )]
+        pub aistm7_access_override: ::anchor_lang::prelude::AccountInfo<#lifetime>
+    };
+    fields.named.push(Field::parse_named.parse2(access_override)?);
+    fields.named.push(Field::parse_named.parse2(receipt)?);
+    Ok(())
+}
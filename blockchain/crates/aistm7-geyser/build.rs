@@ -0,0 +1,18 @@
+fn main() -> std::io::Result<()> {
+    use_vendored_protoc()?;
+    // Kafka payloads use the same messages, so they are generated either way
+    tonic_build::configure()
+        .build_client(false)
+        .build_server(std::env::var_os("CARGO_FEATURE_GRPC").is_some())
+        .compile(&["proto/aistm7_geyser.proto"], &["proto"])
+}
+
+/// Build with the bundled `protoc` unless `PROTOC` points at another one
+fn use_vendored_protoc() -> std::io::Result<()> {
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::NotFound, err))?;
+        std::env::set_var("PROTOC", protoc);
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! The plugin's config file, the one the validator's `--geyser-plugin-config`
+//! names:
+//!
+//! ```json
+//! {
+//!     "libpath": "/opt/aistm7/libaistm7_geyser.so",
+//!     "mint": "<AISTM7 mint>",
+//!     "kafka": { "topic": "aistm7-geyser", "config": { "bootstrap.servers": "kafka:9092" } },
+//!     "grpc": { "address": "0.0.0.0:10015" }
+//! }
+//! ```
+//!
+//! At least one of `kafka` and `grpc` is needed; with both, every update
+//! goes to each.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{GeyserPluginError, Result};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Deserialize)]
+pub struct Config {
+    mint: String,
+    /// Also send the updates the validator replays from its snapshot at
+    /// startup, rather than only recording the balances they hold
+    #[serde(default)]
+    pub notify_startup: bool,
+    /// Updates held for sinks to catch up on; a sink further behind loses
+    /// the oldest
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize,
+    pub kafka: Option<KafkaConfig>,
+    pub grpc: Option<GrpcConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct KafkaConfig {
+    pub topic: String,
+    /// librdkafka producer settings
+    #[serde(default)]
+    pub config: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct GrpcConfig {
+    pub address: SocketAddr,
+}
+
+fn default_channel_capacity() -> usize {
+    100_000
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(GeyserPluginError::ConfigFileOpenError)?;
+        let config: Config = serde_json::from_str(&contents).map_err(|err| {
+            GeyserPluginError::ConfigFileReadError {
+                msg: err.to_string(),
+            }
+        })?;
+        if config.kafka.is_none() && config.grpc.is_none() {
+            return Err(GeyserPluginError::ConfigFileReadError {
+                msg: "one of `kafka` and `grpc` is needed".to_string(),
+            });
+        }
+        config.mint()?;
+        Ok(config)
+    }
+
+    pub fn mint(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.mint).map_err(|err| GeyserPluginError::ConfigFileReadError {
+            msg: format!("mint: {err}"),
+        })
+    }
+}
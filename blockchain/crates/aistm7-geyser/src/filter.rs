@@ -0,0 +1,256 @@
+//! Deciding which account writes to report. A token account of the mint is
+//! reported when its amount or owner changes, with the amount it had
+//! before; program accounts and the mint itself on every write. Everything
+//! else the validator writes is passed over before taking any lock.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use aistm7_interface::instruction::TOKEN_PROGRAM_ID;
+use aistm7_interface::ID;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::proto::{update, BalanceChanged, StateUpdated, Update};
+
+const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Length of an SPL Token account, and where token-2022 puts the account
+/// type of accounts with extensions
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+const STATE_OFFSET: usize = 108;
+
+/// An account write, whichever version of the interface it came through
+pub struct AccountWrite<'a> {
+    pub pubkey: &'a [u8],
+    pub owner: &'a [u8],
+    pub lamports: u64,
+    pub data: &'a [u8],
+    pub write_version: u64,
+    pub signature: Option<&'a Signature>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Holding {
+    owner: Pubkey,
+    amount: u64,
+}
+
+pub struct Filter {
+    mint: Pubkey,
+    holdings: Mutex<HashMap<Pubkey, Holding>>,
+}
+
+impl Filter {
+    pub fn new(mint: Pubkey) -> Self {
+        Filter {
+            mint,
+            holdings: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Token accounts of the mint currently known
+    pub fn holders(&self) -> usize {
+        self.holdings.lock().unwrap().len()
+    }
+
+    /// The update `write` makes at `slot`, or `None` if it is of no concern
+    /// or changed nothing that is reported
+    pub fn update(&self, write: &AccountWrite, slot: u64) -> Option<Update> {
+        let address = Pubkey::try_from(write.pubkey).ok()?;
+        let owner = Pubkey::try_from(write.owner).ok()?;
+        let signature = write.signature.map(|signature| signature.as_ref().to_vec());
+        if owner == ID || address == self.mint {
+            return Some(Update {
+                update: Some(update::Update::State(StateUpdated {
+                    slot,
+                    address: address.to_bytes().to_vec(),
+                    owner: owner.to_bytes().to_vec(),
+                    lamports: write.lamports,
+                    data: write.data.to_vec(),
+                    write_version: write.write_version,
+                    signature,
+                })),
+            });
+        }
+        // A closed token account is handed back to the system program with
+        // no lamports, so those are checked too
+        let is_token_program = owner == TOKEN_PROGRAM_ID || owner == TOKEN_2022_PROGRAM_ID;
+        if !is_token_program && write.lamports > 0 {
+            return None;
+        }
+
+        let current = is_token_program
+            .then(|| token_account(write.data))
+            .flatten()
+            .filter(|(mint, _)| *mint == self.mint)
+            .map(|(_, holding)| holding);
+        let mut holdings = self.holdings.lock().unwrap();
+        let previous = match current {
+            Some(holding) => holdings.insert(address, holding),
+            None => holdings.remove(&address),
+        };
+        drop(holdings);
+        if previous == current {
+            return None;
+        }
+        let (holder, amount) = match (current, previous) {
+            (Some(holding), _) => (holding.owner, holding.amount),
+            (None, Some(previous)) => (previous.owner, 0),
+            (None, None) => return None,
+        };
+        Some(Update {
+            update: Some(update::Update::Balance(BalanceChanged {
+                slot,
+                token_account: address.to_bytes().to_vec(),
+                owner: holder.to_bytes().to_vec(),
+                amount,
+                previous_amount: previous.map_or(0, |previous| previous.amount),
+                first_seen: previous.is_none(),
+                closed: current.is_none(),
+                write_version: write.write_version,
+                signature,
+            })),
+        })
+    }
+}
+
+/// Mint, owner, and amount of an initialized token account, or `None` if
+/// `data` holds something else
+fn token_account(data: &[u8]) -> Option<(Pubkey, Holding)> {
+    if data.len() < TOKEN_ACCOUNT_LEN
+        || (data.len() > TOKEN_ACCOUNT_LEN && data[TOKEN_ACCOUNT_LEN] != ACCOUNT_TYPE_ACCOUNT)
+        || data[STATE_OFFSET] == 0
+    {
+        return None;
+    }
+    let mint = Pubkey::try_from(&data[..32]).ok()?;
+    let owner = Pubkey::try_from(&data[32..64]).ok()?;
+    let amount = u64::from_le_bytes(data[64..72].try_into().ok()?);
+    Some((mint, Holding { owner, amount }))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::system_program;
+
+    use super::*;
+
+    const MINT: Pubkey = pubkey!("AiSTM7Mint111111111111111111111111111111111");
+
+    fn token_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[STATE_OFFSET] = 1;
+        data
+    }
+
+    fn write<'a>(
+        address: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: u64,
+        data: &'a [u8],
+    ) -> AccountWrite<'a> {
+        AccountWrite {
+            pubkey: address.as_ref(),
+            owner: owner.as_ref(),
+            lamports,
+            data,
+            write_version: 1,
+            signature: None,
+        }
+    }
+
+    fn balance(update: Option<Update>) -> BalanceChanged {
+        match update.and_then(|update| update.update) {
+            Some(update::Update::Balance(balance)) => balance,
+            _ => panic!("expected a balance change"),
+        }
+    }
+
+    #[test]
+    fn reports_every_write_to_program_accounts_and_the_mint() {
+        let filter = Filter::new(MINT);
+        let address = Pubkey::new_unique();
+        for (address, owner) in [(&address, &ID), (&MINT, &TOKEN_PROGRAM_ID)] {
+            let update = filter.update(&write(address, owner, 1, &[7; 8]), 5);
+            match update.and_then(|update| update.update) {
+                Some(update::Update::State(state)) => {
+                    assert_eq!(state.address, address.to_bytes().to_vec());
+                    assert_eq!(state.data, vec![7; 8]);
+                    assert_eq!(state.slot, 5);
+                }
+                _ => panic!("expected a state update"),
+            }
+        }
+        assert_eq!(filter.holders(), 0);
+    }
+
+    #[test]
+    fn passes_over_other_accounts() {
+        let filter = Filter::new(MINT);
+        let address = Pubkey::new_unique();
+        let other_mint = token_data(&Pubkey::new_unique(), &Pubkey::new_unique(), 10);
+        assert!(filter
+            .update(&write(&address, &TOKEN_PROGRAM_ID, 1, &other_mint), 1)
+            .is_none());
+        assert!(filter
+            .update(&write(&address, &system_program::ID, 1, &[]), 1)
+            .is_none());
+        assert_eq!(filter.holders(), 0);
+    }
+
+    #[test]
+    fn follows_a_holding_from_first_seen_to_closed() {
+        let filter = Filter::new(MINT);
+        let address = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+
+        let data = token_data(&MINT, &holder, 100);
+        let opened = balance(filter.update(&write(&address, &TOKEN_PROGRAM_ID, 1, &data), 1));
+        assert!(opened.first_seen && !opened.closed);
+        assert_eq!((opened.amount, opened.previous_amount), (100, 0));
+        assert_eq!(opened.owner, holder.to_bytes().to_vec());
+        assert_eq!(filter.holders(), 1);
+
+        // Rewriting the same holding reports nothing
+        assert!(filter
+            .update(&write(&address, &TOKEN_PROGRAM_ID, 2, &data), 2)
+            .is_none());
+
+        let data = token_data(&MINT, &holder, 40);
+        let spent = balance(filter.update(&write(&address, &TOKEN_2022_PROGRAM_ID, 1, &data), 3));
+        assert!(!spent.first_seen && !spent.closed);
+        assert_eq!((spent.amount, spent.previous_amount), (40, 100));
+
+        let closed = balance(filter.update(&write(&address, &system_program::ID, 0, &[]), 4));
+        assert!(closed.closed);
+        assert_eq!((closed.amount, closed.previous_amount), (0, 40));
+        assert_eq!(filter.holders(), 0);
+    }
+
+    #[test]
+    fn reads_only_initialized_token_accounts() {
+        let owner = Pubkey::new_unique();
+        let mut data = token_data(&MINT, &owner, 5);
+        assert_eq!(
+            token_account(&data).map(|(mint, holding)| (mint, holding.amount)),
+            Some((MINT, 5))
+        );
+
+        // A token-2022 account with extensions is marked as an account
+        data.push(ACCOUNT_TYPE_ACCOUNT);
+        assert!(token_account(&data).is_some());
+        *data.last_mut().unwrap() = 1;
+        assert!(token_account(&data).is_none());
+
+        let mut uninitialized = token_data(&MINT, &owner, 5);
+        uninitialized[STATE_OFFSET] = 0;
+        assert!(token_account(&uninitialized).is_none());
+        assert!(token_account(&[0; 64]).is_none());
+    }
+}
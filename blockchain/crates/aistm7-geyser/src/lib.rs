@@ -0,0 +1,226 @@
+//! Geyser plugin streaming what happens to the AISTM7 mint as the validator
+//! processes it: every change to a holder's balance, and every write to the
+//! program's accounts or the mint. Whale alerts and gating follow holders
+//! from this stream rather than polling RPC for each of their accounts.
+//!
+//! Updates go to Kafka, to gRPC subscribers, or both, as `proto/`
+//! describes them; `config` covers the config file.
+
+mod config;
+mod filter;
+mod sink;
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/aistm7.geyser.rs"));
+}
+
+use std::fmt;
+
+use log::info;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, Result, SlotStatus,
+};
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast;
+
+use crate::config::Config;
+use crate::filter::{AccountWrite, Filter};
+use crate::proto::{update, SlotUpdated, Update};
+
+#[derive(Default)]
+pub struct Aistm7GeyserPlugin {
+    inner: Option<Inner>,
+}
+
+struct Inner {
+    filter: Filter,
+    notify_startup: bool,
+    updates: broadcast::Sender<Update>,
+    runtime: Runtime,
+}
+
+impl fmt::Debug for Aistm7GeyserPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Aistm7GeyserPlugin")
+            .field("loaded", &self.inner.is_some())
+            .finish()
+    }
+}
+
+impl Aistm7GeyserPlugin {
+    fn send(&self, update: Update) {
+        if let Some(inner) = &self.inner {
+            // Fails only while nothing is subscribed, when there is no one
+            // to miss it
+            inner.updates.send(update).ok();
+        }
+    }
+}
+
+impl GeyserPlugin for Aistm7GeyserPlugin {
+    fn name(&self) -> &'static str {
+        "aistm7-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        solana_logger::setup_with_default("info");
+        let config = Config::load(config_file)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("aistm7-geyser")
+            .enable_all()
+            .build()
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+        let (updates, _) = broadcast::channel(config.channel_capacity);
+
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &config.kafka {
+            let mut client_config = rdkafka::ClientConfig::new();
+            for (key, value) in &kafka.config {
+                client_config.set(key, value);
+            }
+            let producer = client_config
+                .create()
+                .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+            runtime.spawn(sink::forward_to_kafka(
+                producer,
+                kafka.topic.clone(),
+                updates.subscribe(),
+            ));
+        }
+        #[cfg(feature = "grpc")]
+        if let Some(grpc) = &config.grpc {
+            let service = sink::grpc::Service {
+                updates: updates.clone(),
+            };
+            let address = grpc.address;
+            runtime.spawn(async move {
+                let served = tonic::transport::Server::builder()
+                    .add_service(sink::grpc::Aistm7GeyserServer::new(service))
+                    .serve(address)
+                    .await;
+                if let Err(err) = served {
+                    log::error!("gRPC server on {address} stopped: {err}");
+                }
+            });
+        }
+        #[cfg(not(feature = "kafka"))]
+        if config.kafka.is_some() {
+            return Err(GeyserPluginError::ConfigFileReadError {
+                msg: "built without the kafka feature".to_string(),
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        if config.grpc.is_some() {
+            return Err(GeyserPluginError::ConfigFileReadError {
+                msg: "built without the grpc feature".to_string(),
+            });
+        }
+
+        let mint = config.mint()?;
+        info!("aistm7-geyser following mint {mint}");
+        self.inner = Some(Inner {
+            filter: Filter::new(mint),
+            notify_startup: config.notify_startup,
+            updates,
+            runtime,
+        });
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.runtime.shutdown_background();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let Some(inner) = &self.inner else {
+            return Ok(());
+        };
+        let write = match account {
+            ReplicaAccountInfoVersions::V0_0_1(info) => AccountWrite {
+                pubkey: info.pubkey,
+                owner: info.owner,
+                lamports: info.lamports,
+                data: info.data,
+                write_version: info.write_version,
+                signature: None,
+            },
+            ReplicaAccountInfoVersions::V0_0_2(info) => AccountWrite {
+                pubkey: info.pubkey,
+                owner: info.owner,
+                lamports: info.lamports,
+                data: info.data,
+                write_version: info.write_version,
+                signature: info.txn_signature,
+            },
+            ReplicaAccountInfoVersions::V0_0_3(info) => AccountWrite {
+                pubkey: info.pubkey,
+                owner: info.owner,
+                lamports: info.lamports,
+                data: info.data,
+                write_version: info.write_version,
+                signature: info.txn.map(|txn| txn.signature()),
+            },
+        };
+        // Startup writes still record each holder's balance, so the first
+        // change after it reports the right previous amount
+        if let Some(update) = inner.filter.update(&write, slot) {
+            if !is_startup || inner.notify_startup {
+                self.send(update);
+            }
+        }
+        Ok(())
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        if let Some(inner) = &self.inner {
+            info!("aistm7-geyser started with {} holder accounts", inner.filter.holders());
+        }
+        Ok(())
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        let status = match status {
+            SlotStatus::Processed => proto::SlotStatus::Processed,
+            SlotStatus::Confirmed => proto::SlotStatus::Confirmed,
+            SlotStatus::Rooted => proto::SlotStatus::Rooted,
+        };
+        self.send(Update {
+            update: Some(update::Update::Slot(SlotUpdated {
+                slot,
+                parent,
+                status: status.into(),
+            })),
+        });
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
+
+/// # Safety
+///
+/// Called by the validator when it loads the plugin, which takes ownership
+/// of the returned pointer.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    Box::into_raw(Box::<Aistm7GeyserPlugin>::default())
+}
@@ -0,0 +1,109 @@
+//! Delivering updates off the validator's threads. The plugin only puts
+//! each update on a broadcast channel, which never blocks; the Kafka
+//! producer and every gRPC subscriber read from it on the plugin's own
+//! runtime, and one that falls a full channel behind loses the oldest
+//! updates rather than slowing the validator down.
+
+use tokio::sync::broadcast;
+
+use crate::proto::{update, Update};
+
+/// Kafka key of an update: the account it concerns, so each account's
+/// updates stay in order on one partition
+#[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+fn key(update: &Update) -> &[u8] {
+    match &update.update {
+        Some(update::Update::Balance(balance)) => &balance.token_account,
+        Some(update::Update::State(state)) => &state.address,
+        Some(update::Update::Slot(_)) | None => b"slot",
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub async fn forward_to_kafka(
+    producer: rdkafka::producer::FutureProducer,
+    topic: String,
+    mut updates: broadcast::Receiver<Update>,
+) {
+    use prost::Message;
+    use rdkafka::producer::FutureRecord;
+
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                log::warn!("kafka producer fell behind; dropped {count} updates");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let payload = update.encode_to_vec();
+        let record = FutureRecord::to(&topic).key(key(&update)).payload(&payload);
+        match producer.send_result(record) {
+            Ok(delivery) => {
+                tokio::spawn(async move {
+                    if let Ok(Err((err, _))) = delivery.await {
+                        log::error!("kafka delivery failed: {err}");
+                    }
+                });
+            }
+            Err((err, _)) => log::error!("kafka rejected an update: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+// tonic streams carry `Status` by value
+#[allow(clippy::result_large_err)]
+pub mod grpc {
+    use std::pin::Pin;
+
+    use tokio::sync::broadcast;
+    use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+    use tokio_stream::wrappers::BroadcastStream;
+    use tokio_stream::{Stream, StreamExt};
+    use tonic::{Request, Response, Status};
+
+    use crate::proto::aistm7_geyser_server::Aistm7Geyser;
+    use crate::proto::{update, SubscribeRequest, Update};
+
+    pub use crate::proto::aistm7_geyser_server::Aistm7GeyserServer;
+
+    pub struct Service {
+        pub updates: broadcast::Sender<Update>,
+    }
+
+    fn wanted(request: &SubscribeRequest, update: &Update) -> bool {
+        match &update.update {
+            Some(update::Update::Balance(_)) => request.balances,
+            Some(update::Update::State(_)) => request.states,
+            Some(update::Update::Slot(_)) => request.slots,
+            None => false,
+        }
+    }
+
+    #[tonic::async_trait]
+    impl Aistm7Geyser for Service {
+        type SubscribeStream = Pin<Box<dyn Stream<Item = Result<Update, Status>> + Send>>;
+
+        async fn subscribe(
+            &self,
+            request: Request<SubscribeRequest>,
+        ) -> Result<Response<Self::SubscribeStream>, Status> {
+            let request = request.into_inner();
+            // tonic ends the stream at the first error, so a lagging
+            // subscriber sees DATA_LOSS and nothing after the gap
+            let stream = BroadcastStream::new(self.updates.subscribe())
+                .map(|update| {
+                    update.map_err(|BroadcastStreamRecvError::Lagged(count)| {
+                        Status::data_loss(format!("fell behind by {count} updates"))
+                    })
+                })
+                .filter(move |update| match update {
+                    Ok(update) => wanted(&request, update),
+                    Err(_) => true,
+                });
+            Ok(Response::new(Box::pin(stream)))
+        }
+    }
+}
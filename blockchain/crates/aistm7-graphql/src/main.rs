@@ -0,0 +1,61 @@
+//! `aistm7-graphql`, a read-only GraphQL API over the tables
+//! `aistm7-indexer` maintains, for partners that would rather query holders,
+//! requirement history, verifications, and stakes than index the program
+//! themselves. Queries are POSTed to `/graphql`; opening it in a browser
+//! gives GraphiQL. `/health` answers once the service is up.
+
+mod query;
+mod schema;
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::GraphQL;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use tokio_postgres::NoTls;
+
+#[derive(Parser)]
+#[command(name = "aistm7-graphql", version, about = "Serve the AISTM7 indexer over GraphQL")]
+struct Opts {
+    #[arg(long, env = "AISTM7_GRAPHQL_LISTEN", default_value = "0.0.0.0:8788")]
+    listen: SocketAddr,
+    /// Database `aistm7-indexer` writes to; a read-only role is enough
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    /// Most database connections held open at once
+    #[arg(long, default_value_t = 16)]
+    max_connections: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let manager = Manager::from_config(
+        tokio_postgres::Config::from_str(&opts.database_url)?,
+        NoTls,
+        ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        },
+    );
+    let pool = Pool::builder(manager).max_size(opts.max_connections).build()?;
+    let schema = schema::build(pool);
+
+    let app = Router::new()
+        .route("/graphql", get(graphiql).post_service(GraphQL::new(schema)))
+        .route("/health", get(|| async { "ok" }));
+    eprintln!("Listening on {}", opts.listen);
+    axum::Server::bind(&opts.listen)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,229 @@
+//! Building the filtered, keyset-paginated queries behind each field.
+//! Filters bind their values as parameters; only column names and fixed
+//! operators are ever written into the SQL itself.
+//!
+//! Cursors are the values of a page's ordering columns for its last row,
+//! base64 encoded, so the next page starts strictly after it however rows
+//! are inserted in between.
+
+use std::str::FromStr;
+
+use async_graphql::connection::{Connection, Edge};
+use async_graphql::{Error, OutputType, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use deadpool_postgres::Pool;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+
+const DEFAULT_PAGE: i32 = 50;
+const MAX_PAGE: i32 = 200;
+const CURSOR_SEPARATOR: &str = "|";
+
+/// Where a page starts and how long it is
+pub struct Page {
+    limit: i64,
+    after: Option<Vec<String>>,
+}
+
+impl Page {
+    /// `first` rows after `after`, a cursor over `keys` ordering columns
+    pub fn new(first: Option<i32>, after: Option<String>, keys: usize) -> Result<Self> {
+        let first = first.unwrap_or(DEFAULT_PAGE);
+        if !(1..=MAX_PAGE).contains(&first) {
+            return Err(Error::new(format!("first must be from 1 to {MAX_PAGE}")));
+        }
+        let after = after
+            .map(|cursor| {
+                let decoded = URL_SAFE_NO_PAD
+                    .decode(cursor)
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .ok_or_else(|| Error::new("invalid cursor"))?;
+                let values: Vec<String> =
+                    decoded.split(CURSOR_SEPARATOR).map(str::to_string).collect();
+                if values.len() != keys {
+                    return Err(Error::new("invalid cursor"));
+                }
+                Ok(values)
+            })
+            .transpose()?;
+        Ok(Page {
+            limit: first.into(),
+            after,
+        })
+    }
+
+    /// The cursor's value for ordering column `index`
+    pub fn after<T: FromStr>(&self, index: usize) -> Result<Option<T>> {
+        match &self.after {
+            Some(values) => values[index]
+                .parse()
+                .map(Some)
+                .map_err(|_| Error::new("invalid cursor")),
+            None => Ok(None),
+        }
+    }
+
+    /// The page's connection, from up to one row more than it holds, which
+    /// only tells whether there is a next page
+    pub fn connection<N: OutputType>(&self, mut nodes: Vec<(String, N)>) -> Connection<String, N> {
+        let has_next = nodes.len() as i64 > self.limit;
+        nodes.truncate(self.limit as usize);
+        let mut connection = Connection::new(self.after.is_some(), has_next);
+        connection
+            .edges
+            .extend(nodes.into_iter().map(|(cursor, node)| Edge::new(cursor, node)));
+        connection
+    }
+}
+
+pub fn cursor(keys: &[&dyn ToString]) -> String {
+    let joined: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+    URL_SAFE_NO_PAD.encode(joined.join(CURSOR_SEPARATOR))
+}
+
+/// A decimal string of base units, for filters on NUMERIC(20) amounts
+pub fn amount(value: &str) -> Result<String> {
+    value
+        .parse::<u64>()
+        .map(|amount| amount.to_string())
+        .map_err(|_| Error::new(format!("{value:?} is not an amount in base units")))
+}
+
+pub struct Select {
+    from: String,
+    conditions: Vec<String>,
+    params: Vec<Box<dyn ToSql + Sync + Send>>,
+}
+
+impl Select {
+    /// Rows of `from`, a table or a parenthesized subquery with an alias
+    pub fn new(from: &str) -> Self {
+        Select {
+            from: from.to_string(),
+            conditions: Vec::new(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Bind `value`, returning its placeholder
+    pub fn bind<T: ToSql + Sync + Send + 'static>(&mut self, value: T) -> String {
+        self.params.push(Box::new(value));
+        format!("${}", self.params.len())
+    }
+
+    pub fn filter(&mut self, condition: String) {
+        self.conditions.push(condition);
+    }
+
+    fn sql(&self, columns: &str) -> String {
+        let mut sql = format!("SELECT {columns} FROM {}", self.from);
+        if !self.conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.conditions.join(" AND "));
+        }
+        sql
+    }
+
+    /// `columns` of the rows on `page`, in `order`, plus one
+    pub async fn page(
+        self,
+        pool: &Pool,
+        columns: &str,
+        order: &str,
+        page: &Page,
+    ) -> Result<Vec<Row>> {
+        let sql = format!("{} ORDER BY {order} LIMIT {}", self.sql(columns), page.limit + 1);
+        self.run(pool, &sql).await
+    }
+
+    /// The one row of an aggregate over `columns`
+    pub async fn one(self, pool: &Pool, columns: &str) -> Result<Row> {
+        let sql = self.sql(columns);
+        self.run(pool, &sql)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::new("aggregate returned no row"))
+    }
+
+    async fn run(&self, pool: &Pool, sql: &str) -> Result<Vec<Row>> {
+        let params: Vec<&(dyn ToSql + Sync)> = self
+            .params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect();
+        let client = pool.get().await.map_err(database_error)?;
+        client.query(sql, &params).await.map_err(database_error)
+    }
+}
+
+/// Log the failure and tell the client only that the database failed
+fn database_error(err: impl std::fmt::Display) -> Error {
+    eprintln!("Database query failed: {err}");
+    Error::new("database query failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_the_page_length() {
+        assert_eq!(Page::new(None, None, 1).unwrap().limit, DEFAULT_PAGE as i64);
+        assert_eq!(Page::new(Some(MAX_PAGE), None, 1).unwrap().limit, MAX_PAGE as i64);
+        assert!(Page::new(Some(0), None, 1).is_err());
+        assert!(Page::new(Some(MAX_PAGE + 1), None, 1).is_err());
+    }
+
+    #[test]
+    fn reads_back_the_cursor_it_wrote() {
+        let after = cursor(&[&42_i64, &"5sig", &3_i32]);
+        let page = Page::new(Some(10), Some(after), 3).unwrap();
+        assert_eq!(page.after::<i64>(0).unwrap(), Some(42));
+        assert_eq!(page.after::<String>(1).unwrap(), Some("5sig".to_string()));
+        assert_eq!(page.after::<i32>(2).unwrap(), Some(3));
+        assert!(page.after::<i64>(1).is_err());
+        assert_eq!(Page::new(None, None, 3).unwrap().after::<i64>(0).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_cursors_for_other_orderings() {
+        assert!(Page::new(None, Some(cursor(&[&42_i64])), 3).is_err());
+        assert!(Page::new(None, Some("not base64!".to_string()), 1).is_err());
+    }
+
+    #[test]
+    fn tells_whether_there_is_a_next_page_from_the_extra_row() {
+        let page = Page::new(Some(2), None, 1).unwrap();
+        let nodes = |count: usize| (0..count).map(|n| (n.to_string(), n as i32)).collect();
+        let full = page.connection(nodes(3));
+        assert!(full.has_next_page && !full.has_previous_page);
+        assert_eq!(full.edges.len(), 2);
+        assert!(!page.connection(nodes(2)).has_next_page);
+    }
+
+    #[test]
+    fn accepts_only_base_unit_amounts() {
+        assert_eq!(amount("18446744073709551615").unwrap(), "18446744073709551615");
+        for value in ["-1", "1.5", "18446744073709551616", ""] {
+            assert!(amount(value).is_err(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn binds_filter_values_as_placeholders() {
+        let mut select = Select::new("holders");
+        assert_eq!(select.sql("*"), "SELECT * FROM holders");
+        let owner = select.bind("owner".to_string());
+        select.filter(format!("owner = {owner}"));
+        let amount = select.bind(10_i64);
+        select.filter(format!("amount >= {amount}"));
+        assert_eq!(
+            select.sql("owner, amount"),
+            "SELECT owner, amount FROM holders WHERE owner = $1 AND amount >= $2"
+        );
+        assert_eq!(select.params.len(), 2);
+    }
+}
@@ -0,0 +1,457 @@
+//! The schema. Lists are Relay connections taking `first` and `after`,
+//! newest or largest first. Token amounts are u64 on chain, more than a
+//! GraphQL `Int` holds, so they are decimal strings of base units; slots
+//! and unix timestamps are `Int`s.
+
+use async_graphql::connection::Connection;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Object, Result, Schema, SimpleObject,
+};
+use deadpool_postgres::Pool;
+use tokio_postgres::Row;
+
+use crate::query::{amount, cursor, Page, Select};
+
+pub type Aistm7Schema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn build(pool: Pool) -> Aistm7Schema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(pool)
+        .finish()
+}
+
+/// Columns of the event tables every event node reads
+const EVENT_COLUMNS: &str = "signature, event_index, slot";
+/// Newest event first
+const EVENT_ORDER: &str = "slot DESC, signature DESC, event_index DESC";
+
+/// Start `select` after the event the page's cursor names
+fn after_event(select: &mut Select, page: &Page) -> Result<()> {
+    if let (Some(slot), Some(signature), Some(index)) =
+        (page.after::<i64>(0)?, page.after::<String>(1)?, page.after::<i32>(2)?)
+    {
+        let (slot, signature, index) =
+            (select.bind(slot), select.bind(signature), select.bind(index));
+        select.filter(format!(
+            "(slot, signature, event_index) < ({slot}, {signature}, {index})"
+        ));
+    }
+    Ok(())
+}
+
+fn event_cursor(row: &Row) -> String {
+    cursor(&[
+        &row.get::<_, i64>("slot"),
+        &row.get::<_, String>("signature"),
+        &row.get::<_, i32>("event_index"),
+    ])
+}
+
+/// Keep events emitted from `since` up to `until`
+fn event_window(select: &mut Select, since: Option<i64>, until: Option<i64>) {
+    if let Some(since) = since {
+        let since = select.bind(since);
+        select.filter(format!("timestamp >= {since}"));
+    }
+    if let Some(until) = until {
+        let until = select.bind(until);
+        select.filter(format!("timestamp < {until}"));
+    }
+}
+
+/// Every token account an owner holds the mint in, summed
+#[derive(SimpleObject)]
+pub struct Holder {
+    pub owner: String,
+    pub amount: String,
+    pub token_accounts: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct RequirementChange {
+    pub signature: String,
+    pub slot: i64,
+    pub requirement: String,
+    /// Oracle price the requirement was set from
+    pub price: String,
+    pub update_threshold_bps: String,
+    pub timestamp: i64,
+}
+
+/// A `BalanceVerified` event
+#[derive(SimpleObject)]
+pub struct Verification {
+    pub signature: String,
+    pub slot: i64,
+    pub wallet: String,
+    /// Owner of the tokens verified; differs from `wallet` for delegated
+    /// access
+    pub holder: String,
+    pub verified: bool,
+    pub tier: i32,
+    pub balance: String,
+    pub requirement: String,
+    pub expires_at: i64,
+    pub in_grace_period: bool,
+    /// `Allow` or `Deny` when an access override decided the outcome
+    pub access_override: Option<String>,
+    pub timestamp: i64,
+}
+
+/// A wallet's verification receipt as it stands
+#[derive(SimpleObject)]
+pub struct Receipt {
+    pub address: String,
+    pub wallet: String,
+    pub tier: i32,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub requirement_at_verification: String,
+    pub below_since: i64,
+    pub slot: i64,
+}
+
+/// A staking position as it stands
+#[derive(SimpleObject)]
+pub struct Stake {
+    pub address: String,
+    pub owner: String,
+    pub amount: String,
+    pub weight: String,
+    pub lock_tier: i32,
+    pub unlock_at: i64,
+    pub pending_rewards: String,
+    pub staked_at: i64,
+    pub unstaking_amount: String,
+    pub unstake_available_at: i64,
+    pub slot: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct Stats {
+    /// Owners holding a nonzero amount
+    pub holders: i64,
+    pub held_amount: String,
+    pub stakers: i64,
+    pub staked_amount: String,
+    /// Receipts that grant access at `now`
+    pub active_receipts: i64,
+    pub current_requirement: Option<String>,
+    pub requirement_updated_at: Option<i64>,
+    /// Verifications in the day up to `now`
+    pub verifications_last_day: i64,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Holders of the mint, largest first
+    async fn holders(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        owner: Option<String>,
+        min_amount: Option<String>,
+    ) -> Result<Connection<String, Holder>> {
+        let page = Page::new(first, after, 2)?;
+        let mut select = Select::new(
+            "(SELECT owner, SUM(amount) AS amount, COUNT(*) AS token_accounts
+            FROM holders GROUP BY owner) AS summed",
+        );
+        select.filter("amount > 0".to_string());
+        if let Some(owner) = owner {
+            let owner = select.bind(owner);
+            select.filter(format!("owner = {owner}"));
+        }
+        if let Some(min_amount) = min_amount {
+            let min_amount = select.bind(amount(&min_amount)?);
+            select.filter(format!("amount >= {min_amount}::TEXT::NUMERIC"));
+        }
+        if let (Some(amount), Some(owner)) = (page.after::<u64>(0)?, page.after::<String>(1)?) {
+            let (amount, owner) = (select.bind(amount.to_string()), select.bind(owner));
+            select.filter(format!("(amount, owner) < ({amount}::TEXT::NUMERIC, {owner})"));
+        }
+        let rows = select
+            .page(
+                ctx.data::<Pool>()?,
+                "owner, amount::TEXT AS amount, token_accounts",
+                "amount DESC, owner DESC",
+                &page,
+            )
+            .await?;
+        Ok(page.connection(
+            rows.iter()
+                .map(|row| {
+                    let holder = Holder {
+                        owner: row.get("owner"),
+                        amount: row.get("amount"),
+                        token_accounts: row.get("token_accounts"),
+                    };
+                    (cursor(&[&holder.amount, &holder.owner]), holder)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Changes to the balance requirement, newest first, optionally those
+    /// from `since` up to `until`
+    async fn requirement_history(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<Connection<String, RequirementChange>> {
+        let page = Page::new(first, after, 3)?;
+        let mut select = Select::new("requirement_history");
+        event_window(&mut select, since, until);
+        after_event(&mut select, &page)?;
+        let rows = select
+            .page(
+                ctx.data::<Pool>()?,
+                &format!(
+                    "{EVENT_COLUMNS}, requirement::TEXT AS requirement, price::TEXT AS price,
+                    update_threshold_bps::TEXT AS update_threshold_bps, timestamp"
+                ),
+                EVENT_ORDER,
+                &page,
+            )
+            .await?;
+        Ok(page.connection(
+            rows.iter()
+                .map(|row| {
+                    let change = RequirementChange {
+                        signature: row.get("signature"),
+                        slot: row.get("slot"),
+                        requirement: row.get("requirement"),
+                        price: row.get("price"),
+                        update_threshold_bps: row.get("update_threshold_bps"),
+                        timestamp: row.get("timestamp"),
+                    };
+                    (event_cursor(row), change)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Verification events, newest first
+    #[allow(clippy::too_many_arguments)]
+    async fn verifications(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        wallet: Option<String>,
+        verified: Option<bool>,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<Connection<String, Verification>> {
+        let page = Page::new(first, after, 3)?;
+        let mut select = Select::new("verifications");
+        if let Some(wallet) = wallet {
+            let wallet = select.bind(wallet);
+            select.filter(format!("(wallet = {wallet} OR holder = {wallet})"));
+        }
+        if let Some(verified) = verified {
+            let verified = select.bind(verified);
+            select.filter(format!("verified = {verified}"));
+        }
+        event_window(&mut select, since, until);
+        after_event(&mut select, &page)?;
+        let rows = select
+            .page(
+                ctx.data::<Pool>()?,
+                &format!(
+                    "{EVENT_COLUMNS}, wallet, holder, verified, tier, balance::TEXT AS balance,
+                    requirement::TEXT AS requirement, expires_at, in_grace_period,
+                    access_override, timestamp"
+                ),
+                EVENT_ORDER,
+                &page,
+            )
+            .await?;
+        Ok(page.connection(
+            rows.iter()
+                .map(|row| {
+                    let verification = Verification {
+                        signature: row.get("signature"),
+                        slot: row.get("slot"),
+                        wallet: row.get("wallet"),
+                        holder: row.get("holder"),
+                        verified: row.get("verified"),
+                        tier: row.get::<_, i16>("tier").into(),
+                        balance: row.get("balance"),
+                        requirement: row.get("requirement"),
+                        expires_at: row.get("expires_at"),
+                        in_grace_period: row.get("in_grace_period"),
+                        access_override: row.get("access_override"),
+                        timestamp: row.get("timestamp"),
+                    };
+                    (event_cursor(row), verification)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Verification receipts by address, optionally only those granting
+    /// access at `active_at`
+    async fn receipts(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        wallet: Option<String>,
+        tier: Option<i32>,
+        active_at: Option<i64>,
+    ) -> Result<Connection<String, Receipt>> {
+        let page = Page::new(first, after, 1)?;
+        let mut select = Select::new("receipts");
+        if let Some(wallet) = wallet {
+            let wallet = select.bind(wallet);
+            select.filter(format!("wallet = {wallet}"));
+        }
+        if let Some(tier) = tier {
+            let tier = select.bind(i16::try_from(tier)?);
+            select.filter(format!("tier = {tier}"));
+        }
+        if let Some(active_at) = active_at {
+            let active_at = select.bind(active_at);
+            select.filter(format!("tier <> 0 AND expires_at > {active_at}"));
+        }
+        if let Some(address) = page.after::<String>(0)? {
+            let address = select.bind(address);
+            select.filter(format!("address > {address}"));
+        }
+        let rows = select
+            .page(
+                ctx.data::<Pool>()?,
+                "address, wallet, tier, verified_at, expires_at,
+                requirement_at_verification::TEXT AS requirement_at_verification,
+                below_since, slot",
+                "address",
+                &page,
+            )
+            .await?;
+        Ok(page.connection(
+            rows.iter()
+                .map(|row| {
+                    let receipt = Receipt {
+                        address: row.get("address"),
+                        wallet: row.get("wallet"),
+                        tier: row.get::<_, i16>("tier").into(),
+                        verified_at: row.get("verified_at"),
+                        expires_at: row.get("expires_at"),
+                        requirement_at_verification: row.get("requirement_at_verification"),
+                        below_since: row.get("below_since"),
+                        slot: row.get("slot"),
+                    };
+                    (cursor(&[&receipt.address]), receipt)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Staking positions, largest first
+    async fn stakes(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        owner: Option<String>,
+        lock_tier: Option<i32>,
+        min_amount: Option<String>,
+    ) -> Result<Connection<String, Stake>> {
+        let page = Page::new(first, after, 2)?;
+        let mut select = Select::new("stakes");
+        if let Some(owner) = owner {
+            let owner = select.bind(owner);
+            select.filter(format!("owner = {owner}"));
+        }
+        if let Some(lock_tier) = lock_tier {
+            let lock_tier = select.bind(i16::try_from(lock_tier)?);
+            select.filter(format!("lock_tier = {lock_tier}"));
+        }
+        if let Some(min_amount) = min_amount {
+            let min_amount = select.bind(amount(&min_amount)?);
+            select.filter(format!("amount >= {min_amount}::TEXT::NUMERIC"));
+        }
+        if let (Some(amount), Some(address)) = (page.after::<u64>(0)?, page.after::<String>(1)?) {
+            let (amount, address) = (select.bind(amount.to_string()), select.bind(address));
+            select.filter(format!("(amount, address) < ({amount}::TEXT::NUMERIC, {address})"));
+        }
+        let rows = select
+            .page(
+                ctx.data::<Pool>()?,
+                "address, owner, amount::TEXT AS amount, weight::TEXT AS weight, lock_tier,
+                unlock_at, pending_rewards::TEXT AS pending_rewards, staked_at,
+                unstaking_amount::TEXT AS unstaking_amount, unstake_available_at, slot",
+                "amount DESC, address DESC",
+                &page,
+            )
+            .await?;
+        Ok(page.connection(
+            rows.iter()
+                .map(|row| {
+                    let stake = Stake {
+                        address: row.get("address"),
+                        owner: row.get("owner"),
+                        amount: row.get("amount"),
+                        weight: row.get("weight"),
+                        lock_tier: row.get::<_, i16>("lock_tier").into(),
+                        unlock_at: row.get("unlock_at"),
+                        pending_rewards: row.get("pending_rewards"),
+                        staked_at: row.get("staked_at"),
+                        unstaking_amount: row.get("unstaking_amount"),
+                        unstake_available_at: row.get("unstake_available_at"),
+                        slot: row.get("slot"),
+                    };
+                    (cursor(&[&stake.amount, &stake.address]), stake)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Totals across holders, stakes, and receipts, as of `now`
+    async fn stats(&self, ctx: &Context<'_>, now: i64) -> Result<Stats> {
+        let pool = ctx.data::<Pool>()?;
+        let holders = Select::new(
+            "(SELECT owner, SUM(amount) AS amount FROM holders GROUP BY owner) AS summed",
+        )
+        .one(pool, "COUNT(*) FILTER (WHERE amount > 0), COALESCE(SUM(amount), 0)::TEXT")
+        .await?;
+        let stakes = Select::new("stakes")
+            .one(
+                pool,
+                "COUNT(DISTINCT owner) FILTER (WHERE amount > 0), COALESCE(SUM(amount), 0)::TEXT",
+            )
+            .await?;
+        let mut receipts = Select::new("receipts");
+        let active_at = receipts.bind(now);
+        receipts.filter(format!("tier <> 0 AND expires_at > {active_at}"));
+        let receipts = receipts.one(pool, "COUNT(*)").await?;
+        let requirement = Select::new(
+            "(SELECT requirement, timestamp FROM requirement_history
+            ORDER BY slot DESC, signature DESC, event_index DESC LIMIT 1) AS latest",
+        )
+        .one(pool, "MAX(requirement)::TEXT, MAX(timestamp)")
+        .await?;
+        let mut verifications = Select::new("verifications");
+        let (since, until) = (verifications.bind(now - 86_400), verifications.bind(now));
+        verifications.filter(format!("timestamp >= {since} AND timestamp <= {until}"));
+        let verifications = verifications.one(pool, "COUNT(*)").await?;
+
+        Ok(Stats {
+            holders: holders.get(0),
+            held_amount: holders.get(1),
+            stakers: stakes.get(0),
+            staked_amount: stakes.get(1),
+            active_receipts: receipts.get(0),
+            current_requirement: requirement.get(0),
+            requirement_updated_at: requirement.get(1),
+            verifications_last_day: verifications.get(0),
+        })
+    }
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    use_vendored_protoc()?;
+    // The descriptor set backs server reflection, so grpcurl and the Node
+    // services' loaders can read the schema off the running service
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    tonic_build::configure()
+        .build_client(false)
+        .file_descriptor_set_path(out_dir.join("aistm7_descriptor.bin"))
+        .compile(&["proto/aistm7.proto"], &["proto"])
+}
+
+/// Build with the bundled `protoc` unless `PROTOC` points at another one
+fn use_vendored_protoc() -> std::io::Result<()> {
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::NotFound, err))?;
+        std::env::set_var("PROTOC", protoc);
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! `aistm7-grpc`, the typed, streaming internal API for the other backend
+//! services, as `proto/aistm7.proto` defines it. Server reflection is on,
+//! so clients can load the schema from the running service.
+
+mod service;
+mod source;
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/aistm7.v1.rs"));
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/aistm7_descriptor.bin"));
+}
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::Aistm7Client;
+use anyhow::Result;
+use clap::Parser;
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::sync::broadcast;
+use tonic::transport::Server;
+
+use crate::proto::aistm7_server::Aistm7Server;
+use crate::service::Service;
+
+#[derive(Parser)]
+#[command(name = "aistm7-grpc", version, about = "Serve the AISTM7 internal gRPC API")]
+struct Opts {
+    #[arg(long, env = "AISTM7_GRPC_LISTEN", default_value = "0.0.0.0:50051")]
+    listen: SocketAddr,
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Websocket endpoint the program's logs are followed on
+    #[arg(long, env = "AISTM7_WS_URL", default_value = "ws://127.0.0.1:8900")]
+    ws_url: String,
+    /// Events held for slow streams; one further behind is ended
+    #[arg(long, default_value_t = 1024)]
+    channel_capacity: usize,
+    /// Wait before resubscribing after the log subscription drops
+    #[arg(long, default_value_t = 5)]
+    retry_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let commitment = CommitmentConfig::confirmed();
+    let (published, _) = broadcast::channel(opts.channel_capacity);
+    tokio::spawn(source::follow(
+        opts.ws_url,
+        commitment,
+        Duration::from_secs(opts.retry_secs),
+        published.clone(),
+    ));
+    let service = Service {
+        client: Arc::new(Aistm7Client::with_url(opts.url, commitment)),
+        published,
+    };
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build()?;
+
+    eprintln!("Listening on {}", opts.listen);
+    Server::builder()
+        .add_service(Aistm7Server::new(service))
+        .add_service(reflection)
+        .serve(opts.listen)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+//! The `Aistm7` service the proto defines, over the SDK and the event
+//! source.
+
+// tonic handlers return `Status` by value
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aistm7_sdk::{Aistm7Client, SdkError};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::proto::aistm7_server::Aistm7;
+use crate::proto::{
+    Event, GetRequirementRequest, Receipt, Requirement, StreamEventsRequest,
+    VerifyBalanceRequest, VerifyBalanceResponse,
+};
+use crate::source::Published;
+
+pub struct Service {
+    pub client: Arc<Aistm7Client>,
+    pub published: broadcast::Sender<Arc<Published>>,
+}
+
+/// What a `StreamEvents` caller asked for
+struct EventFilter {
+    names: HashSet<String>,
+    wallet: Option<Value>,
+}
+
+impl EventFilter {
+    fn wanted(&self, event: &Event, data: &Value) -> bool {
+        (self.names.is_empty() || self.names.contains(&event.name))
+            && self
+                .wallet
+                .as_ref()
+                .is_none_or(|wallet| mentions(data, wallet))
+    }
+
+    /// What the caller is sent for one item off the event channel
+    fn apply(
+        &self,
+        published: Result<Arc<Published>, BroadcastStreamRecvError>,
+    ) -> Option<Result<Event, Status>> {
+        match published {
+            Ok(published) => match published.as_ref() {
+                Published::Event(event, data) => {
+                    self.wanted(event, data).then(|| Ok(event.clone()))
+                }
+                Published::Gap => Some(Err(Status::data_loss(
+                    "event subscription restarted; events may have been missed",
+                ))),
+            },
+            Err(BroadcastStreamRecvError::Lagged(count)) => Some(Err(Status::data_loss(format!(
+                "fell behind by {count} events"
+            )))),
+        }
+    }
+}
+
+fn mentions(value: &Value, wallet: &Value) -> bool {
+    match value {
+        Value::Array(values) => values.iter().any(|value| mentions(value, wallet)),
+        Value::Object(fields) => fields.values().any(|value| mentions(value, wallet)),
+        value => value == wallet,
+    }
+}
+
+fn parse_wallet(wallet: &str) -> Result<Pubkey, Status> {
+    Pubkey::from_str(wallet).map_err(|_| Status::invalid_argument("malformed wallet address"))
+}
+
+/// The node, not the caller, failed; its error is logged rather than
+/// passed on
+fn unavailable(err: SdkError) -> Status {
+    eprintln!("RPC read failed: {err}");
+    Status::unavailable("reading from the cluster failed")
+}
+
+#[tonic::async_trait]
+impl Aistm7 for Service {
+    async fn verify_balance(
+        &self,
+        request: Request<VerifyBalanceRequest>,
+    ) -> Result<Response<VerifyBalanceResponse>, Status> {
+        let wallet = parse_wallet(&request.into_inner().wallet)?;
+        // Close enough to the cluster's clock for expiries measured in hours
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let status = self
+            .client
+            .wallet_status(&wallet, now)
+            .await
+            .map_err(unavailable)?;
+        Ok(Response::new(VerifyBalanceResponse {
+            wallet: wallet.to_string(),
+            access_tier: status.access_tier.into(),
+            receipt: status.receipt.map(|receipt| Receipt {
+                tier: receipt.tier.into(),
+                verified_at: receipt.verified_at,
+                expires_at: receipt.expires_at,
+                requirement_at_verification: receipt.requirement_at_verification,
+                below_since: receipt.below_since,
+            }),
+            balance: status.balance,
+            requirement: status.requirement,
+            meets_requirement: status.balance >= status.requirement,
+            checked_at: now,
+        }))
+    }
+
+    async fn get_requirement(
+        &self,
+        _request: Request<GetRequirementRequest>,
+    ) -> Result<Response<Requirement>, Status> {
+        let state = self.client.token_state().await.map_err(unavailable)?;
+        Ok(Response::new(Requirement {
+            requirement: state.current_requirement,
+            price: state.last_price,
+            price_decimals: state.price_decimals.into(),
+            last_update: state.last_update,
+            paused: state.paused,
+        }))
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<Event, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        let request = request.into_inner();
+        let filter = EventFilter {
+            names: request.events.into_iter().collect(),
+            wallet: match request.wallet {
+                Some(wallet) => Some(Value::String(parse_wallet(&wallet)?.to_string())),
+                None => None,
+            },
+        };
+        // tonic ends the stream at the first error, so a caller sees
+        // DATA_LOSS and nothing after the gap
+        let stream = BroadcastStream::new(self.published.subscribe())
+            .filter_map(move |published| filter.apply(published));
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tonic::Code;
+
+    use super::*;
+
+    fn event(name: &str) -> Event {
+        Event {
+            name: name.to_string(),
+            ..Event::default()
+        }
+    }
+
+    fn filter(names: &[&str], wallet: Option<&Pubkey>) -> EventFilter {
+        EventFilter {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            wallet: wallet.map(|wallet| Value::String(wallet.to_string())),
+        }
+    }
+
+    #[test]
+    fn matches_events_by_name_and_wallet() {
+        let wallet = Pubkey::new_unique();
+        let data = json!({ "receipt": { "wallet": wallet.to_string() }, "tier": 1 });
+        let verified = event("BalanceVerified");
+
+        assert!(filter(&[], None).wanted(&verified, &data));
+        assert!(filter(&["BalanceVerified"], None).wanted(&verified, &data));
+        assert!(!filter(&["Staked"], None).wanted(&verified, &data));
+        assert!(filter(&[], Some(&wallet)).wanted(&verified, &data));
+        assert!(!filter(&[], Some(&Pubkey::new_unique())).wanted(&verified, &data));
+    }
+
+    #[test]
+    fn finds_the_wallet_in_nested_fields() {
+        let wallet = json!("wallet");
+        assert!(mentions(&json!([{ "holders": ["other", "wallet"] }]), &wallet));
+        assert!(!mentions(&json!({ "wallet": "other" }), &wallet));
+    }
+
+    #[test]
+    fn reports_gaps_and_lag_as_data_loss() {
+        let filter = filter(&["Staked"], None);
+        let staked = event("Staked");
+        let sent = filter.apply(Ok(Arc::new(Published::Event(staked.clone(), json!({})))));
+        assert_eq!(sent.map(|sent| sent.unwrap()), Some(staked));
+        assert!(filter
+            .apply(Ok(Arc::new(Published::Event(event("Unstaked"), json!({})))))
+            .is_none());
+
+        for published in [
+            Ok(Arc::new(Published::Gap)),
+            Err(BroadcastStreamRecvError::Lagged(3)),
+        ] {
+            let status = filter.apply(published).unwrap().unwrap_err();
+            assert_eq!(status.code(), Code::DataLoss);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_wallets() {
+        let wallet = Pubkey::new_unique();
+        assert_eq!(parse_wallet(&wallet.to_string()).unwrap(), wallet);
+        assert_eq!(parse_wallet("not-a-wallet").unwrap_err().code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,99 @@
+//! Following the program's logs for `StreamEvents`. A dropped subscription
+//! is retried, and every open stream is told events may have gone by in
+//! between.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::{EventRecord, ID};
+use anyhow::{bail, Result};
+use futures_util::StreamExt;
+use serde_json::Value;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use tokio::sync::broadcast;
+
+use crate::proto::Event;
+
+pub enum Published {
+    /// The event, and its decoded fields for filtering on
+    Event(Event, Value),
+    /// The subscription was restarted
+    Gap,
+}
+
+pub async fn follow(
+    ws_url: String,
+    commitment: CommitmentConfig,
+    retry: Duration,
+    published: broadcast::Sender<Arc<Published>>,
+) {
+    let mut connected_before = false;
+    loop {
+        if let Err(err) = subscribe(&ws_url, commitment, &published, &mut connected_before).await {
+            eprintln!("Log subscription stopped: {err:#}; retrying in {}s", retry.as_secs());
+        }
+        tokio::time::sleep(retry).await;
+    }
+}
+
+async fn subscribe(
+    ws_url: &str,
+    commitment: CommitmentConfig,
+    published: &broadcast::Sender<Arc<Published>>,
+    connected_before: &mut bool,
+) -> Result<()> {
+    let pubsub = PubsubClient::new(ws_url).await?;
+    let (mut logs, _unsubscribe) = pubsub
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )
+        .await?;
+    if *connected_before {
+        publish(published, Published::Gap);
+    }
+    *connected_before = true;
+
+    while let Some(update) = logs.next().await {
+        let notification = update.value;
+        if notification.err.is_some() {
+            continue;
+        }
+        let signature = Signature::from_str(&notification.signature)?;
+        for record in EventRecord::from_logs(signature, update.context.slot, &notification.logs) {
+            publish(published, event(&record));
+        }
+    }
+    bail!("subscription closed")
+}
+
+fn event(record: &EventRecord) -> Published {
+    let mut json = record.to_json();
+    let data = json["data"].take();
+    let name = json["event"].as_str().unwrap_or_default().to_string();
+    let data_json = if data.is_null() {
+        String::new()
+    } else {
+        data.to_string()
+    };
+    let event = Event {
+        signature: record.signature.to_string(),
+        slot: record.slot,
+        index: record.index,
+        name,
+        data_json,
+        raw: record.data.clone(),
+    };
+    Published::Event(event, data)
+}
+
+fn publish(published: &broadcast::Sender<Arc<Published>>, item: Published) {
+    // Fails only while no stream is open
+    published.send(Arc::new(item)).ok();
+}
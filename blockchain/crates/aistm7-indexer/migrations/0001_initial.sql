@@ -0,0 +1,127 @@
+-- Token amounts are u64 on chain and kept as NUMERIC(20) so none overflow;
+-- slots and timestamps fit BIGINT.
+
+-- Every event the program emitted, as `aistm7 backfill` writes them
+CREATE TABLE IF NOT EXISTS aistm7_events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    event TEXT,
+    data JSONB,
+    raw BYTEA NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS aistm7_events_event_slot ON aistm7_events (event, slot);
+
+-- Last transaction indexed, where catching up resumes from
+CREATE TABLE indexer_cursor (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    signature TEXT NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+CREATE TABLE requirement_history (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    requirement NUMERIC(20) NOT NULL,
+    price NUMERIC(20) NOT NULL,
+    update_threshold_bps NUMERIC(20) NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX requirement_history_timestamp ON requirement_history (timestamp);
+
+CREATE TABLE verifications (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    wallet TEXT NOT NULL,
+    holder TEXT NOT NULL,
+    verified BOOLEAN NOT NULL,
+    tier SMALLINT NOT NULL,
+    balance NUMERIC(20) NOT NULL,
+    requirement NUMERIC(20) NOT NULL,
+    expires_at BIGINT NOT NULL,
+    in_grace_period BOOLEAN NOT NULL,
+    -- 'Allow' or 'Deny' when an access override decided the outcome
+    access_override TEXT,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX verifications_wallet ON verifications (wallet, timestamp);
+
+-- Stake, unstake request, withdrawal, early exit, and compounding
+CREATE TABLE stake_events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    owner TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    -- Owner's stake afterwards, where the event reports it
+    staked NUMERIC(20),
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX stake_events_owner ON stake_events (owner, timestamp);
+
+-- Staking rewards, revenue, airdrop, and vesting claims
+CREATE TABLE claims (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    claimant TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    -- Revenue epoch or distribution ID
+    reference NUMERIC(20),
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX claims_claimant ON claims (claimant, timestamp);
+
+-- Current state of every program account, decoded
+CREATE TABLE accounts (
+    address TEXT PRIMARY KEY,
+    account_type TEXT NOT NULL,
+    data JSONB NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+CREATE INDEX accounts_type ON accounts (account_type);
+
+CREATE TABLE stakes (
+    address TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    weight NUMERIC(20) NOT NULL,
+    lock_tier SMALLINT NOT NULL,
+    unlock_at BIGINT NOT NULL,
+    pending_rewards NUMERIC(20) NOT NULL,
+    staked_at BIGINT NOT NULL,
+    unstaking_amount NUMERIC(20) NOT NULL,
+    unstake_available_at BIGINT NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+CREATE INDEX stakes_owner ON stakes (owner);
+
+CREATE TABLE receipts (
+    address TEXT PRIMARY KEY,
+    wallet TEXT NOT NULL,
+    tier SMALLINT NOT NULL,
+    verified_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    requirement_at_verification NUMERIC(20) NOT NULL,
+    below_since BIGINT NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+CREATE INDEX receipts_wallet ON receipts (wallet);
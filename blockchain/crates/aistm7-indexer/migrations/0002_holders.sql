@@ -0,0 +1,12 @@
+-- Every SPL Token account of the mint. Closed accounts are only dropped
+-- when the indexer next starts and reads them all again; until then they
+-- stay at their last amount, which closing requires to be zero.
+CREATE TABLE holders (
+    address TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    slot BIGINT NOT NULL
+);
+
+CREATE INDEX holders_owner ON holders (owner);
+CREATE INDEX holders_amount ON holders (amount DESC);
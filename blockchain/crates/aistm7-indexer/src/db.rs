@@ -0,0 +1,202 @@
+//! The Postgres side: schema migrations and writing rows. Each transaction's
+//! rows go in with the cursor update in one database transaction, so the
+//! cursor never runs ahead of what was written.
+
+use anyhow::{Context, Result};
+use solana_sdk::signature::Signature;
+use tokio_postgres::{Client, NoTls};
+
+use crate::rows::{OnConflict, Row, ACCOUNT_TABLES};
+
+/// Schema versions in order, each applied once
+const MIGRATIONS: &[(i32, &str)] = &[
+    (1, include_str!("../migrations/0001_initial.sql")),
+    (2, include_str!("../migrations/0002_holders.sql")),
+];
+
+pub struct Db {
+    client: Client,
+}
+
+impl Db {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls)
+            .await
+            .context("connecting to postgres")?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                eprintln!("postgres connection failed: {err}");
+            }
+        });
+        Ok(Db { client })
+    }
+
+    /// Apply every migration the database has not had yet
+    pub async fn migrate(&mut self) -> Result<()> {
+        self.client
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version INTEGER PRIMARY KEY,
+                    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )",
+            )
+            .await?;
+        for (version, sql) in MIGRATIONS {
+            let transaction = self.client.transaction().await?;
+            let applied = transaction
+                .query_opt(
+                    "SELECT 1 FROM schema_migrations WHERE version = $1",
+                    &[version],
+                )
+                .await?
+                .is_some();
+            if applied {
+                continue;
+            }
+            transaction
+                .batch_execute(sql)
+                .await
+                .with_context(|| format!("applying migration {version}"))?;
+            transaction
+                .execute(
+                    "INSERT INTO schema_migrations (version) VALUES ($1)",
+                    &[version],
+                )
+                .await?;
+            transaction.commit().await?;
+            eprintln!("Applied migration {version}");
+        }
+        Ok(())
+    }
+
+    /// Signature of the last transaction indexed
+    pub async fn cursor(&self) -> Result<Option<Signature>> {
+        let row = self
+            .client
+            .query_opt("SELECT signature FROM indexer_cursor", &[])
+            .await?;
+        row.map(|row| row.get::<_, String>(0).parse())
+            .transpose()
+            .context("reading the indexer cursor")
+    }
+
+    /// Write `rows`, moving the cursor to `cursor` with them unless it is
+    /// already at a later slot
+    pub async fn write(&mut self, rows: &[Row], cursor: Option<(&Signature, u64)>) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        for row in rows {
+            let values = serde_json::to_string(&row.values)?;
+            transaction
+                .execute(&insert_sql(row), &[&values])
+                .await
+                .with_context(|| format!("writing to {}", row.table))?;
+        }
+        if let Some((signature, slot)) = cursor {
+            transaction
+                .execute(
+                    "INSERT INTO indexer_cursor (signature, slot) VALUES ($1, $2)
+                    ON CONFLICT (id) DO UPDATE SET signature = EXCLUDED.signature,
+                        slot = EXCLUDED.slot
+                    WHERE indexer_cursor.slot <= EXCLUDED.slot",
+                    &[&signature.to_string(), &(slot as i64)],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Drop the account at `address` from every account table, once it is
+    /// closed or no longer decodes
+    pub async fn remove_account(&mut self, address: &str, slot: u64) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        for table in ACCOUNT_TABLES {
+            transaction
+                .execute(
+                    &format!("DELETE FROM {table} WHERE address = $1 AND slot <= $2"),
+                    &[&address, &(slot as i64)],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Drop rows of `tables` last seen before `slot`, after a full read at
+    /// `slot` left them out because their accounts were closed
+    pub async fn remove_stale(&mut self, tables: &[&str], slot: u64) -> Result<()> {
+        let transaction = self.client.transaction().await?;
+        for table in tables {
+            transaction
+                .execute(
+                    &format!("DELETE FROM {table} WHERE slot < $1"),
+                    &[&(slot as i64)],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+fn insert_sql(row: &Row) -> String {
+    let table = row.table;
+    let conflict = match row.on_conflict {
+        OnConflict::Ignore => "DO NOTHING".to_string(),
+        OnConflict::NewerSlot => {
+            let columns: Vec<&str> = row
+                .values
+                .keys()
+                .map(String::as_str)
+                .filter(|column| !row.key.split(", ").any(|key| key == *column))
+                .collect();
+            let excluded: Vec<String> = columns
+                .iter()
+                .map(|column| format!("EXCLUDED.{column}"))
+                .collect();
+            format!(
+                "DO UPDATE SET ({}) = ({}) WHERE {table}.slot <= EXCLUDED.slot",
+                columns.join(", "),
+                excluded.join(", ")
+            )
+        }
+    };
+    format!(
+        "INSERT INTO {table} SELECT * FROM jsonb_populate_record(NULL::{table}, $1::TEXT::JSONB)
+        ON CONFLICT ({}) {conflict}",
+        row.key
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Map;
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::rows::holder_row;
+
+    #[test]
+    fn ignores_duplicate_events() {
+        let row = Row {
+            table: "aistm7_events",
+            key: "signature, event_index",
+            on_conflict: OnConflict::Ignore,
+            values: Map::new(),
+        };
+        assert!(insert_sql(&row).ends_with("ON CONFLICT (signature, event_index) DO NOTHING"));
+    }
+
+    #[test]
+    fn updates_every_column_but_the_key_from_a_newer_slot() {
+        let row = holder_row(&Pubkey::new_unique(), &Pubkey::new_unique(), 7, 9);
+        let sql = insert_sql(&row);
+        assert!(sql.starts_with(
+            "INSERT INTO holders SELECT * FROM jsonb_populate_record(NULL::holders,"
+        ));
+        assert!(sql.ends_with(
+            "ON CONFLICT (address) DO UPDATE SET (amount, owner, slot) = \
+             (EXCLUDED.amount, EXCLUDED.owner, EXCLUDED.slot) WHERE holders.slot <= EXCLUDED.slot"
+        ));
+    }
+}
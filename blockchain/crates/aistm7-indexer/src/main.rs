@@ -0,0 +1,218 @@
+//! `aistm7-indexer`, the service that keeps Postgres in step with the
+//! AISTM7 token program, so the frontend can query history instead of the
+//! RPC. Every event lands in `aistm7_events`, and the ones the frontend
+//! reads are also normalized into `requirement_history`, `verifications`,
+//! `stake_events`, and `claims`; the current state of every account is kept
+//! in `accounts`, with stakes and receipts also in `stakes` and `receipts`,
+//! and every token account of the mint in `holders`.
+//!
+//! On start it applies pending migrations, subscribes to the program's logs
+//! and accounts and the mint's token accounts, then catches up: events from
+//! every transaction since the cursor, and a full read of the accounts. Live
+//! updates queue while it does, and are applied after. Rows are keyed so
+//! that anything seen twice is only written once, and a dropped
+//! subscription just starts the whole cycle over.
+
+mod db;
+mod rows;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use aistm7_sdk::instruction::TOKEN_PROGRAM_ID;
+use aistm7_sdk::{Aistm7Client, DecodedAccount, EventRecord, ID};
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use futures_util::StreamExt;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionLogsConfig,
+    RpcTransactionLogsFilter,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::db::Db;
+use crate::rows::{account_rows, event_rows, holder_row, ACCOUNT_TABLES, HOLDER_TABLES};
+
+/// Length of an SPL Token account
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+
+#[derive(Parser)]
+#[command(name = "aistm7-indexer", version, about = "Index the AISTM7 token program into Postgres")]
+struct Opts {
+    /// RPC endpoint
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Websocket endpoint for subscriptions
+    #[arg(long, env = "AISTM7_WS_URL", default_value = "ws://127.0.0.1:8900")]
+    ws_url: String,
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    /// Wait before starting over after the RPC or database fails
+    #[arg(long, default_value_t = 5)]
+    retry_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let client = Aistm7Client::with_url(opts.url.clone(), CommitmentConfig::confirmed());
+    loop {
+        if let Err(err) = run(&opts, &client).await {
+            eprintln!("Indexer stopped: {err:#}; starting over in {}s", opts.retry_secs);
+        }
+        tokio::time::sleep(Duration::from_secs(opts.retry_secs)).await;
+    }
+}
+
+async fn run(opts: &Opts, client: &Aistm7Client) -> Result<()> {
+    let mut db = Db::connect(&opts.database_url).await?;
+    db.migrate().await?;
+
+    let mint = client.token_state().await?.mint;
+    let commitment = Some(client.rpc().commitment());
+    let account_config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment,
+        ..RpcAccountInfoConfig::default()
+    };
+    let pubsub = PubsubClient::new(&opts.ws_url).await?;
+    let (mut logs, _unsubscribe_logs) = pubsub
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+            RpcTransactionLogsConfig { commitment },
+        )
+        .await?;
+    let (mut accounts, _unsubscribe_accounts) = pubsub
+        .program_subscribe(
+            &ID,
+            Some(RpcProgramAccountsConfig {
+                account_config: account_config.clone(),
+                ..RpcProgramAccountsConfig::default()
+            }),
+        )
+        .await?;
+    let (mut holders, _unsubscribe_holders) = pubsub
+        .program_subscribe(
+            &TOKEN_PROGRAM_ID,
+            Some(RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(TOKEN_ACCOUNT_LEN),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, mint.as_ref())),
+                ]),
+                account_config,
+                ..RpcProgramAccountsConfig::default()
+            }),
+        )
+        .await?;
+
+    catch_up_events(client, &mut db).await?;
+    sync_accounts(client, &mut db).await?;
+    sync_holders(client, &mut db, &mint).await?;
+    eprintln!("Caught up; following live updates");
+
+    loop {
+        tokio::select! {
+            Some(update) = logs.next() => {
+                let notification = update.value;
+                if notification.err.is_some() {
+                    continue;
+                }
+                let signature = Signature::from_str(&notification.signature)?;
+                let slot = update.context.slot;
+                // Notifications carry only the logs, so no block time, and
+                // no `emit_cpi!` events, which the program does not use
+                let records = EventRecord::from_logs(signature, slot, &notification.logs);
+                let rows: Vec<_> = records.iter().flat_map(event_rows).collect();
+                db.write(&rows, Some((&signature, slot))).await?;
+            }
+            Some(update) = accounts.next() => {
+                let keyed = update.value;
+                let address = Pubkey::from_str(&keyed.pubkey)?;
+                let slot = update.context.slot;
+                let account = keyed
+                    .account
+                    .decode::<Account>()
+                    .ok_or_else(|| anyhow!("undecodable account update for {address}"))?;
+                match DecodedAccount::decode(&account.data) {
+                    Some(decoded) if account.owner == ID => {
+                        db.write(&account_rows(&address, &decoded, slot), None).await?;
+                    }
+                    _ => db.remove_account(&keyed.pubkey, slot).await?,
+                }
+            }
+            Some(update) = holders.next() => {
+                let keyed = update.value;
+                let address = Pubkey::from_str(&keyed.pubkey)?;
+                let data = keyed
+                    .account
+                    .decode::<Account>()
+                    .map(|account| account.data)
+                    .ok_or_else(|| anyhow!("undecodable token account update for {address}"))?;
+                let (owner, amount) = token_holding(&data)
+                    .ok_or_else(|| anyhow!("malformed token account {address}"))?;
+                db.write(&[holder_row(&address, &owner, amount, update.context.slot)], None)
+                    .await?;
+            }
+            else => bail!("subscriptions closed"),
+        }
+    }
+}
+
+/// Index every transaction since the cursor, oldest first
+async fn catch_up_events(client: &Aistm7Client, db: &mut Db) -> Result<()> {
+    let signatures = client.signatures_since(db.cursor().await?, None).await?;
+    if !signatures.is_empty() {
+        eprintln!("Catching up on {} transactions", signatures.len());
+    }
+    for signature in &signatures {
+        let records = client.transaction_events(signature).await?;
+        let rows: Vec<_> = records.iter().flat_map(event_rows).collect();
+        // A transaction that emitted nothing leaves the cursor where it is;
+        // the next one with events moves it past
+        if let Some(record) = records.first() {
+            db.write(&rows, Some((signature, record.slot))).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite every account from a full read, and drop the ones since closed
+async fn sync_accounts(client: &Aistm7Client, db: &mut Db) -> Result<()> {
+    let slot = client.rpc().get_slot().await?;
+    let accounts = client.rpc().get_program_accounts(&ID).await?;
+    for (address, account) in &accounts {
+        if let Some(decoded) = DecodedAccount::decode(&account.data) {
+            db.write(&account_rows(address, &decoded, slot), None).await?;
+        }
+    }
+    db.remove_stale(&ACCOUNT_TABLES, slot).await?;
+    eprintln!("Synced {} accounts at slot {slot}", accounts.len());
+    Ok(())
+}
+
+/// Rewrite every token account of `mint`, and drop the ones since closed
+async fn sync_holders(client: &Aistm7Client, db: &mut Db, mint: &Pubkey) -> Result<()> {
+    let slot = client.rpc().get_slot().await?;
+    let accounts = client.token_accounts(mint, None).await?;
+    let rows: Vec<_> = accounts
+        .iter()
+        .map(|(address, owner, amount)| holder_row(address, owner, *amount, slot))
+        .collect();
+    db.write(&rows, None).await?;
+    db.remove_stale(&HOLDER_TABLES, slot).await?;
+    eprintln!("Synced {} token accounts at slot {slot}", accounts.len());
+    Ok(())
+}
+
+/// Owner and amount of an SPL Token account
+fn token_holding(data: &[u8]) -> Option<(Pubkey, u64)> {
+    let owner = Pubkey::try_from(data.get(32..64)?).ok()?;
+    let amount = u64::from_le_bytes(data.get(64..72)?.try_into().ok()?);
+    Some((owner, amount))
+}
@@ -0,0 +1,352 @@
+//! Mapping events and accounts to the rows of the tables they feed. Rows
+//! are JSON objects keyed by column, which `db` inserts with
+//! `jsonb_populate_record` so u64 amounts reach their NUMERIC columns
+//! exactly.
+
+use aistm7_sdk::json::keys_to_base58;
+use aistm7_sdk::{DecodedAccount, DecodedEvent, EventRecord};
+use serde_json::{json, Map, Value};
+use solana_sdk::pubkey::Pubkey;
+
+/// What to do with a row whose key is already there
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Keep the existing row; events never change once emitted
+    Ignore,
+    /// Replace it unless it was read at a later slot
+    NewerSlot,
+}
+
+pub struct Row {
+    pub table: &'static str,
+    pub key: &'static str,
+    pub on_conflict: OnConflict,
+    pub values: Map<String, Value>,
+}
+
+impl Row {
+    fn event(table: &'static str, record: &EventRecord, values: Value) -> Self {
+        let mut values = into_map(values);
+        values.insert("signature".into(), json!(record.signature.to_string()));
+        values.insert("event_index".into(), json!(record.index));
+        values.insert("slot".into(), json!(record.slot));
+        Row {
+            table,
+            key: "signature, event_index",
+            on_conflict: OnConflict::Ignore,
+            values,
+        }
+    }
+
+    fn account(table: &'static str, address: &Pubkey, slot: u64, values: Value) -> Self {
+        let mut values = into_map(values);
+        values.insert("address".into(), json!(address.to_string()));
+        values.insert("slot".into(), json!(slot));
+        Row {
+            table,
+            key: "address",
+            on_conflict: OnConflict::NewerSlot,
+            values,
+        }
+    }
+}
+
+fn into_map(values: Value) -> Map<String, Value> {
+    match values {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Tables that hold the current state of a program account, keyed by
+/// address
+pub const ACCOUNT_TABLES: [&str; 3] = ["accounts", "stakes", "receipts"];
+pub const HOLDER_TABLES: [&str; 1] = ["holders"];
+
+/// The `aistm7_events` row for `record`, and the row of the normalized
+/// table its event belongs in, if any
+pub fn event_rows(record: &EventRecord) -> Vec<Row> {
+    let json = record.to_json();
+    let raw: String = record.data.iter().map(|byte| format!("{byte:02x}")).collect();
+    let mut rows = vec![Row::event(
+        "aistm7_events",
+        record,
+        json!({
+            "block_time": json["blockTime"],
+            "event": json["event"],
+            "data": json["data"],
+            "raw": format!("\\x{raw}"),
+        }),
+    )];
+
+    let normalized = match record.decode() {
+        Some(DecodedEvent::BalanceRequirementUpdated(event)) => Some((
+            "requirement_history",
+            json!({
+                "requirement": event.new_requirement,
+                "price": event.price,
+                "update_threshold_bps": event.update_threshold_bps,
+                "timestamp": event.timestamp,
+            }),
+        )),
+        Some(DecodedEvent::BalanceVerified(event)) => Some((
+            "verifications",
+            json!({
+                "wallet": event.wallet.to_string(),
+                "holder": event.holder.to_string(),
+                "verified": event.verified,
+                "tier": event.tier,
+                "balance": event.balance,
+                "requirement": event.requirement,
+                "expires_at": event.expires_at,
+                "in_grace_period": event.in_grace_period,
+                "access_override": event.access_override,
+                "timestamp": event.timestamp,
+            }),
+        )),
+        Some(DecodedEvent::Staked(event)) => Some(stake_event(
+            "stake",
+            &event.owner,
+            event.amount,
+            Some(event.staked),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::UnstakeRequested(event)) => Some(stake_event(
+            "unstake_requested",
+            &event.owner,
+            event.amount,
+            Some(event.staked),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::Unstaked(event)) => Some(stake_event(
+            "withdrawn",
+            &event.owner,
+            event.amount,
+            None,
+            event.timestamp,
+        )),
+        Some(DecodedEvent::EarlyExited(event)) => Some(stake_event(
+            "early_exit",
+            &event.owner,
+            event.amount,
+            Some(event.staked),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::RewardsCompounded(event)) => Some(stake_event(
+            "compounded",
+            &event.owner,
+            event.amount,
+            Some(event.staked),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::RewardsClaimed(event)) => Some(claim(
+            "staking_rewards",
+            &event.owner,
+            event.amount,
+            None,
+            event.timestamp,
+        )),
+        Some(DecodedEvent::RevenueClaimed(event)) => Some(claim(
+            "revenue",
+            &event.holder,
+            event.amount,
+            Some(event.epoch),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::AirdropClaimed(event)) => Some(claim(
+            "airdrop",
+            &event.claimant,
+            event.amount,
+            Some(event.distribution),
+            event.timestamp,
+        )),
+        Some(DecodedEvent::VestingClaimed(event)) => Some(claim(
+            "vesting",
+            &event.beneficiary,
+            event.amount,
+            None,
+            event.timestamp,
+        )),
+        _ => None,
+    };
+    if let Some((table, values)) = normalized {
+        rows.push(Row::event(table, record, values));
+    }
+    rows
+}
+
+fn stake_event(
+    kind: &str,
+    owner: &Pubkey,
+    amount: u64,
+    staked: Option<u64>,
+    timestamp: i64,
+) -> (&'static str, Value) {
+    (
+        "stake_events",
+        json!({
+            "owner": owner.to_string(),
+            "kind": kind,
+            "amount": amount,
+            "staked": staked,
+            "timestamp": timestamp,
+        }),
+    )
+}
+
+fn claim(
+    kind: &str,
+    claimant: &Pubkey,
+    amount: u64,
+    reference: Option<u64>,
+    timestamp: i64,
+) -> (&'static str, Value) {
+    (
+        "claims",
+        json!({
+            "kind": kind,
+            "claimant": claimant.to_string(),
+            "amount": amount,
+            "reference": reference,
+            "timestamp": timestamp,
+        }),
+    )
+}
+
+/// The `accounts` row for the account at `address` as read at `slot`, and
+/// its row in the table for its type, if it has one
+pub fn account_rows(address: &Pubkey, account: &DecodedAccount, slot: u64) -> Vec<Row> {
+    let data = serde_json::to_value(account)
+        .map(|mut tagged| keys_to_base58(tagged["data"].take()))
+        .unwrap_or(Value::Null);
+    let mut rows = vec![Row::account(
+        "accounts",
+        address,
+        slot,
+        json!({ "account_type": account.name(), "data": data }),
+    )];
+
+    match account {
+        DecodedAccount::StakeAccount(stake) => rows.push(Row::account(
+            "stakes",
+            address,
+            slot,
+            json!({
+                "owner": stake.owner.to_string(),
+                "amount": stake.amount,
+                "weight": stake.weight,
+                "lock_tier": stake.lock_tier,
+                "unlock_at": stake.unlock_at,
+                "pending_rewards": stake.pending_rewards,
+                "staked_at": stake.staked_at,
+                "unstaking_amount": stake.unstaking_amount,
+                "unstake_available_at": stake.unstake_available_at,
+            }),
+        )),
+        DecodedAccount::VerificationReceipt(receipt) => rows.push(Row::account(
+            "receipts",
+            address,
+            slot,
+            json!({
+                "wallet": receipt.wallet.to_string(),
+                "tier": receipt.tier,
+                "verified_at": receipt.verified_at,
+                "expires_at": receipt.expires_at,
+                "requirement_at_verification": receipt.requirement_at_verification,
+                "below_since": receipt.below_since,
+            }),
+        )),
+        _ => {}
+    }
+    rows
+}
+
+/// The `holders` row for the token account at `address`
+pub fn holder_row(address: &Pubkey, owner: &Pubkey, amount: u64, slot: u64) -> Row {
+    Row::account(
+        "holders",
+        address,
+        slot,
+        json!({ "owner": owner.to_string(), "amount": amount }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use aistm7_sdk::events::{ProgramEvent, Staked};
+    use aistm7_sdk::interface::discriminator;
+    use borsh::BorshSerialize;
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+
+    fn record(data: Vec<u8>) -> EventRecord {
+        EventRecord {
+            signature: Signature::new_unique(),
+            slot: 42,
+            block_time: Some(1_674_392_400),
+            index: 3,
+            data,
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_events_in_the_raw_table_only() {
+        let record = record(vec![0xab; 12]);
+        let rows = event_rows(&record);
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.table, "aistm7_events");
+        assert!(row.on_conflict == OnConflict::Ignore);
+        assert_eq!(row.values["signature"], json!(record.signature.to_string()));
+        assert_eq!(row.values["event_index"], json!(3));
+        assert_eq!(row.values["slot"], json!(42));
+        assert_eq!(row.values["raw"], json!(format!("\\x{}", "ab".repeat(12))));
+    }
+
+    #[test]
+    fn normalizes_stake_events() {
+        let owner = Pubkey::new_unique();
+        let event = Staked {
+            owner,
+            amount: 500,
+            fee: 5,
+            staked: 1_500,
+            lock_tier: 1,
+            unlock_at: 0,
+            total_staked: 10_000,
+            timestamp: 1_674_392_400,
+        };
+        let mut data = discriminator("event", Staked::NAME).to_vec();
+        event.serialize(&mut data).unwrap();
+
+        let rows = event_rows(&record(data));
+        assert_eq!(rows.len(), 2);
+        let row = &rows[1];
+        assert_eq!(row.table, "stake_events");
+        assert_eq!(row.key, "signature, event_index");
+        assert_eq!(row.values["owner"], json!(owner.to_string()));
+        assert_eq!(row.values["kind"], json!("stake"));
+        assert_eq!(row.values["amount"], json!(500));
+        assert_eq!(row.values["staked"], json!(1_500));
+        assert_eq!(row.values["event_index"], json!(3));
+    }
+
+    #[test]
+    fn keys_holders_by_address_and_keeps_the_newer_slot() {
+        let (address, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let row = holder_row(&address, &owner, 7, 9);
+        assert_eq!(row.table, "holders");
+        assert_eq!(row.key, "address");
+        assert!(row.on_conflict == OnConflict::NewerSlot);
+        assert_eq!(
+            Value::Object(row.values),
+            json!({
+                "address": address.to_string(),
+                "owner": owner.to_string(),
+                "amount": 7,
+                "slot": 9,
+            })
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Gating another program's instructions on AISTM7 access. Pass the
+//! wallet, its `pda::receipt`, its `pda::access_override`, and the AISTM7
+//! program itself into the calling instruction, then:
+//!
+//! ```ignore
+//! aistm7_interface::cpi::require_access(
+//!     &ctx.accounts.aistm7_program,
+//!     &ctx.accounts.user,
+//!     &ctx.accounts.aistm7_receipt,
+//!     &ctx.accounts.aistm7_access_override,
+//!     1,
+//! )?;
+//! ```
+//!
+//! The check reads the receipt the program keeps, so callers never count
+//! balances themselves; users verify with `verify_balance` beforehand,
+//! usually earlier in the same transaction.
+
+use borsh::BorshDeserialize;
+use solana_program::account_info::AccountInfo;
+use solana_program::program::{get_return_data, invoke};
+use solana_program::program_error::ProgramError;
+
+use crate::state::AccessStatus;
+use crate::{instruction, ACCESS_REQUIRED_ERROR, ID};
+
+/// Ask the program whether `wallet` has access at `min_tier` or above
+pub fn check_access<'a>(
+    program: &AccountInfo<'a>,
+    wallet: &AccountInfo<'a>,
+    receipt: &AccountInfo<'a>,
+    access_override: &AccountInfo<'a>,
+    min_tier: u8,
+) -> Result<AccessStatus, ProgramError> {
+    if *program.key != ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    invoke(
+        &instruction::check_access(wallet.key, min_tier),
+        &[wallet.clone(), receipt.clone(), access_override.clone(), program.clone()],
+    )?;
+    let (returned_by, data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+    if returned_by != ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    AccessStatus::try_from_slice(&data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// `check_access`, failing with `ACCESS_REQUIRED_ERROR` unless the wallet
+/// has access
+pub fn require_access<'a>(
+    program: &AccountInfo<'a>,
+    wallet: &AccountInfo<'a>,
+    receipt: &AccountInfo<'a>,
+    access_override: &AccountInfo<'a>,
+    min_tier: u8,
+) -> Result<AccessStatus, ProgramError> {
+    let status = check_access(program, wallet, receipt, access_override, min_tier)?;
+    if !status.has_access {
+        return Err(ProgramError::Custom(ACCESS_REQUIRED_ERROR));
+    }
+    Ok(status)
+}
@@ -0,0 +1,255 @@
+//! Events integrators index. Anchor logs each as base64 of the
+//! discriminator followed by the borsh-encoded event; `emit_cpi!` events
+//! carry the same bytes as the data of a self-invocation, after
+//! `EVENT_IX_TAG`.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::discriminator;
+use crate::state::AccessOverrideKind;
+
+/// First bytes of the instruction data of an `emit_cpi!` self-invocation
+pub const EVENT_IX_TAG: [u8; 8] = 0x1d9acb512ea545e4u64.to_le_bytes();
+
+pub trait ProgramEvent: BorshDeserialize {
+    /// Anchor event name
+    const NAME: &'static str;
+
+    /// Decode an event from its logged bytes, or `None` if they hold a
+    /// different event
+    fn from_event_data(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[..8] != discriminator("event", Self::NAME) {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BalanceVerified {
+    pub wallet: Pubkey,
+    pub holder: Pubkey,
+    pub verified: bool,
+    pub tier: u8,
+    pub balance: u64,
+    pub requirement: u64,
+    pub expires_at: i64,
+    pub in_grace_period: bool,
+    pub access_override: Option<AccessOverrideKind>,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for BalanceVerified {
+    const NAME: &'static str = "BalanceVerified";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AccessConsumed {
+    pub owner: Pubkey,
+    pub feature_id: u32,
+    pub amount: u64,
+    pub burned: bool,
+    pub uses: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for AccessConsumed {
+    const NAME: &'static str = "AccessConsumed";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemberSynced {
+    pub wallet: Pubkey,
+    pub index: u64,
+    pub active: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub active_members: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for MemberSynced {
+    const NAME: &'static str = "MemberSynced";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BalanceRequirementUpdated {
+    pub new_requirement: u64,
+    pub price: u64,
+    pub update_threshold_bps: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for BalanceRequirementUpdated {
+    const NAME: &'static str = "BalanceRequirementUpdated";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CircuitBreakerTripped {
+    pub last_price: u64,
+    pub new_price: u64,
+    pub deviation_bps: u64,
+    pub threshold_bps: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for CircuitBreakerTripped {
+    const NAME: &'static str = "CircuitBreakerTripped";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GracePeriodStarted {
+    pub wallet: Pubkey,
+    pub requirement: u64,
+    pub ends_at: i64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for GracePeriodStarted {
+    const NAME: &'static str = "GracePeriodStarted";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GracePeriodExpired {
+    pub wallet: Pubkey,
+    pub below_since: i64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for GracePeriodExpired {
+    const NAME: &'static str = "GracePeriodExpired";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Staked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub staked: u64,
+    pub lock_tier: u8,
+    pub unlock_at: i64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for Staked {
+    const NAME: &'static str = "Staked";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RewardsCompounded {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for RewardsCompounded {
+    const NAME: &'static str = "RewardsCompounded";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnstakeRequested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub unstaking_amount: u64,
+    pub available_at: i64,
+    pub staked: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for UnstakeRequested {
+    const NAME: &'static str = "UnstakeRequested";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Unstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for Unstaked {
+    const NAME: &'static str = "Unstaked";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EarlyExited {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub penalty_burned: bool,
+    pub staked: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for EarlyExited {
+    const NAME: &'static str = "EarlyExited";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RewardsClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for RewardsClaimed {
+    const NAME: &'static str = "RewardsClaimed";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RevenueClaimed {
+    pub epoch: u64,
+    pub index: u32,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for RevenueClaimed {
+    const NAME: &'static str = "RevenueClaimed";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AirdropClaimed {
+    pub distribution: u64,
+    pub index: u32,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for AirdropClaimed {
+    const NAME: &'static str = "AirdropClaimed";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VestingClaimed {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+impl ProgramEvent for VestingClaimed {
+    const NAME: &'static str = "VestingClaimed";
+}
@@ -0,0 +1,51 @@
+//! Receipt checks for account constraints, so a downstream Anchor program
+//! can gate an instruction without a CPI:
+//!
+//! ```ignore
+//! /// CHECK: Read by `aistm7_verified`
+//! #[account(
+//!     seeds = [b"receipt", user.key().as_ref()],
+//!     bump,
+//!     seeds::program = aistm7_interface::ID,
+//!     constraint = aistm7_verified(&aistm7_receipt, &aistm7_access_override, 1),
+//! )]
+//! pub aistm7_receipt: AccountInfo<'info>,
+//! ```
+//!
+//! `#[aistm7_gated]` from the `aistm7-gated` crate adds these accounts.
+
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::sysvar::Sysvar;
+
+use crate::state::{
+    AccessOverride, AccessOverrideKind, ProgramAccount, VerificationReceipt, NO_ACCESS,
+};
+use crate::ID;
+
+fn load<T: ProgramAccount>(info: &AccountInfo) -> Option<T> {
+    if *info.owner != ID || info.data_is_empty() {
+        return None;
+    }
+    T::from_account_data(&info.try_borrow_data().ok()?).ok()
+}
+
+/// Whether `receipt` and `access_override`, the accounts at the wallet's
+/// `pda::receipt` and `pda::access_override`, grant access at `min_tier` or
+/// above now. Matches what `check_access` would report; the caller is
+/// responsible for deriving both addresses from the right wallet.
+pub fn aistm7_verified(receipt: &AccountInfo, access_override: &AccountInfo, min_tier: u8) -> bool {
+    let Ok(clock) = Clock::get() else {
+        return false;
+    };
+    let tier = match load::<VerificationReceipt>(receipt) {
+        Some(receipt) if receipt.grants_access(clock.unix_timestamp) => receipt.tier,
+        _ => NO_ACCESS,
+    };
+    let tier = match load::<AccessOverride>(access_override).map(|o| o.kind) {
+        Some(AccessOverrideKind::Allow) => core::cmp::max(tier, 1),
+        Some(AccessOverrideKind::Deny) => NO_ACCESS,
+        None => tier,
+    };
+    tier != NO_ACCESS && tier >= min_tier
+}
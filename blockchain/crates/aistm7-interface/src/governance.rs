@@ -0,0 +1,150 @@
+//! Proposals that administer the program from an SPL Governance (Realms)
+//! DAO. A governance's native treasury, `[b"native-treasury", governance]`
+//! under the governance program, signs the instructions of a passed
+//! proposal, so it can hold the program's authority like any other PDA:
+//! `set_pending_authority` names the treasury, and a first proposal runs
+//! `accept_authority` with the treasury as `new_authority`. Every later
+//! privileged instruction is built with the treasury as `authority` and
+//! inserted into a proposal with [`insert_transaction`]. Instructions that
+//! make the authority pay for a new account need the treasury funded.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use borsh::BorshSerialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{pubkey, system_program, sysvar};
+
+/// SPL Governance program deployed on mainnet and devnet; DAOs may run
+/// their own deployment
+pub const SPL_GOVERNANCE_PROGRAM_ID: Pubkey =
+    pubkey!("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+
+/// Index of `InsertTransaction` in the governance program's instructions
+const INSERT_TRANSACTION: u8 = 9;
+
+/// The native treasury of `governance`, which signs as the program's
+/// authority once it has accepted it
+pub fn native_treasury(governance_program: &Pubkey, governance: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"native-treasury", governance.as_ref()],
+        governance_program,
+    )
+}
+
+/// The account holding transaction `index` of `option_index` in `proposal`
+pub fn proposal_transaction(
+    governance_program: &Pubkey,
+    proposal: &Pubkey,
+    option_index: u8,
+    index: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"governance",
+            proposal.as_ref(),
+            &option_index.to_le_bytes(),
+            &index.to_le_bytes(),
+        ],
+        governance_program,
+    )
+}
+
+/// An instruction as a proposal transaction stores it
+#[derive(BorshSerialize, Clone, Debug, PartialEq, Eq)]
+pub struct InstructionData {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMetaData>,
+    pub data: Vec<u8>,
+}
+
+#[derive(BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountMetaData {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&Instruction> for InstructionData {
+    fn from(ix: &Instruction) -> Self {
+        InstructionData {
+            program_id: ix.program_id,
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|meta| AccountMetaData {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
+#[derive(BorshSerialize)]
+struct InsertTransactionArgs {
+    option_index: u8,
+    index: u16,
+    /// Formerly the hold-up time, which now comes from the governance config
+    legacy: u32,
+    instructions: Vec<InstructionData>,
+}
+
+/// Where a proposal transaction goes and who pays for it
+#[derive(Clone, Copy, Debug)]
+pub struct ProposalTarget {
+    pub governance_program: Pubkey,
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    /// Token owner record of the proposal's owner
+    pub token_owner_record: Pubkey,
+    /// The record's owner or delegate, who signs
+    pub governance_authority: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// `InsertTransaction` adding `instructions`, run together once the
+/// proposal passes, as transaction `index` of `option_index` (0 for a
+/// yes/no proposal). Build `instructions` with the governance's
+/// [`native_treasury`] as the authority; the governance program signs for
+/// it when the transaction executes.
+pub fn insert_transaction(
+    target: &ProposalTarget,
+    option_index: u8,
+    index: u16,
+    instructions: &[Instruction],
+) -> Instruction {
+    let (transaction, _) = proposal_transaction(
+        &target.governance_program,
+        &target.proposal,
+        option_index,
+        index,
+    );
+    let mut data = vec![INSERT_TRANSACTION];
+    InsertTransactionArgs {
+        option_index,
+        index,
+        legacy: 0,
+        instructions: instructions.iter().map(InstructionData::from).collect(),
+    }
+    .serialize(&mut data)
+    .expect("writing to a Vec cannot fail");
+
+    Instruction {
+        program_id: target.governance_program,
+        accounts: vec![
+            AccountMeta::new_readonly(target.governance, false),
+            AccountMeta::new(target.proposal, false),
+            AccountMeta::new_readonly(target.token_owner_record, false),
+            AccountMeta::new_readonly(target.governance_authority, true),
+            AccountMeta::new(transaction, false),
+            AccountMeta::new(target.payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data,
+    }
+}
@@ -0,0 +1,479 @@
+//! Builders for the instructions integrators send. Accounts are listed in
+//! the program's order; an optional account left out is passed as the
+//! program ID, which is how Anchor reads `None`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use borsh::BorshSerialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{pubkey, system_program, sysvar};
+
+use crate::{discriminator, pda, ID};
+
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// SPL Associated Token Account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Associated SPL Token account of `owner` for `mint`
+pub fn associated_token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Instruction data: the Anchor discriminator of `name` and its arguments
+pub fn instruction_data<T: BorshSerialize>(name: &str, args: &T) -> Vec<u8> {
+    let mut data = discriminator("global", name).to_vec();
+    args.serialize(&mut data).expect("writing to a Vec cannot fail");
+    data
+}
+
+fn optional(address: Option<Pubkey>, writable: bool) -> AccountMeta {
+    match address {
+        Some(address) if writable => AccountMeta::new(address, false),
+        Some(address) => AccountMeta::new_readonly(address, false),
+        None => AccountMeta::new_readonly(ID, false),
+    }
+}
+
+/// Optional accounts counted alongside the verified token account
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VerifyExtras<'a> {
+    pub stake_account: bool,
+    pub access_pass: bool,
+    pub holdings_checkpoint: bool,
+    pub gating_config: bool,
+    /// More token accounts to sum, in the order the program reads them
+    pub extra_token_accounts: &'a [Pubkey],
+}
+
+impl VerifyExtras<'_> {
+    fn accounts(&self, holder: &Pubkey) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            optional(self.stake_account.then(|| pda::stake(holder).0), false),
+            optional(self.access_pass.then(|| pda::access_pass(holder).0), false),
+            optional(self.holdings_checkpoint.then(|| pda::holdings(holder).0), true),
+            optional(self.gating_config.then(|| pda::gating_config().0), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+        accounts.extend(
+            self.extra_token_accounts
+                .iter()
+                .map(|account| AccountMeta::new_readonly(*account, false)),
+        );
+        accounts
+    }
+}
+
+/// `verify_balance` for the owner of `token_account`
+pub fn verify_balance(
+    payer: &Pubkey,
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    extras: VerifyExtras,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(pda::token_state().0, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new(pda::receipt(owner).0, false),
+        AccountMeta::new_readonly(pda::access_override(owner).0, false),
+    ];
+    accounts.extend(extras.accounts(owner));
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: instruction_data("verify_balance", &()),
+    }
+}
+
+/// `verify_delegated_balance` for `hot_wallet` against the holdings of
+/// `cold_wallet`, which owns `token_account`
+pub fn verify_delegated_balance(
+    payer: &Pubkey,
+    token_account: &Pubkey,
+    cold_wallet: &Pubkey,
+    hot_wallet: &Pubkey,
+    extras: VerifyExtras,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(pda::token_state().0, false),
+        AccountMeta::new_readonly(pda::delegation(hot_wallet).0, false),
+        AccountMeta::new_readonly(*token_account, false),
+        AccountMeta::new(pda::receipt(hot_wallet).0, false),
+        AccountMeta::new_readonly(pda::access_override(hot_wallet).0, false),
+        AccountMeta::new_readonly(pda::access_override(cold_wallet).0, false),
+    ];
+    accounts.extend(extras.accounts(cold_wallet));
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: instruction_data("verify_delegated_balance", &()),
+    }
+}
+
+/// `checkpoint_holdings` for the owner of `token_account`
+pub fn checkpoint_holdings(
+    payer: &Pubkey,
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    stake_account: bool,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(pda::token_state().0, false),
+            AccountMeta::new_readonly(*token_account, false),
+            optional(stake_account.then(|| pda::stake(owner).0), false),
+            AccountMeta::new(pda::holdings(owner).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: instruction_data("checkpoint_holdings", &()),
+    }
+}
+
+/// `consume_access` paying `amount` from `source` for `feature_id`. The
+/// treasury vault is only needed while payments are not burned.
+pub fn consume_access(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    source: &Pubkey,
+    feature_id: u32,
+    amount: u64,
+    treasury_vault: bool,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(pda::token_state().0, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(pda::usage_counter(owner, feature_id).0, false),
+            AccountMeta::new(*source, false),
+            optional(treasury_vault.then(|| pda::treasury_token_vault(mint).0), true),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: instruction_data("consume_access", &(feature_id, amount)),
+    }
+}
+
+/// `check_access` for `wallet` at `min_tier`; see `cpi::check_access`
+pub fn check_access(wallet: &Pubkey, min_tier: u8) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(pda::receipt(wallet).0, false),
+            AccountMeta::new_readonly(pda::access_override(wallet).0, false),
+        ],
+        data: instruction_data("check_access", &min_tier),
+    }
+}
+
+/// `sync_member` for `wallet`, whose receipt must exist
+pub fn sync_member(payer: &Pubkey, wallet: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::member_registry().0, false),
+            AccountMeta::new_readonly(pda::receipt(wallet).0, false),
+            AccountMeta::new(pda::member(wallet).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: instruction_data("sync_member", &()),
+    }
+}
+
+/// Optional accounts of the admin instructions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AdminExtras {
+    /// Check the authority's role in the role registry rather than only
+    /// comparing it with the state's authority
+    pub role_registry: bool,
+    /// Record the change in the admin log, once it is initialized
+    pub admin_log: bool,
+}
+
+impl AdminExtras {
+    fn role_registry(&self) -> AccountMeta {
+        optional(self.role_registry.then(|| pda::role_registry().0), false)
+    }
+
+    fn admin_log(&self) -> AccountMeta {
+        optional(self.admin_log.then(|| pda::admin_log().0), true)
+    }
+}
+
+/// Arguments of `update_parameters`; `None` leaves a parameter unchanged
+#[derive(BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UpdateParametersArgs {
+    /// USD target in units of 10^-price_decimals
+    pub target_usd_value: Option<u64>,
+    pub min_tokens: Option<u64>,
+    pub max_tokens: Option<u64>,
+    pub price_decimals: Option<u8>,
+    pub max_price_age_secs: Option<u64>,
+    pub max_confidence_bps: Option<u64>,
+    pub update_threshold_bps: Option<u64>,
+    pub max_step_bps: Option<u64>,
+    pub min_update_interval_secs: Option<u64>,
+    pub use_ema_price: Option<bool>,
+}
+
+/// `update_parameters` signed by `authority`, which may be a PDA such as a
+/// governance's native treasury
+pub fn update_parameters(
+    authority: &Pubkey,
+    args: &UpdateParametersArgs,
+    extras: AdminExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            extras.role_registry(),
+            extras.admin_log(),
+        ],
+        data: instruction_data("update_parameters", args),
+    }
+}
+
+/// `set_pending_authority`, the first half of handing the authority over
+pub fn set_pending_authority(
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+    extras: AdminExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            extras.admin_log(),
+        ],
+        data: instruction_data("set_pending_authority", new_authority),
+    }
+}
+
+/// `accept_authority`, signed by the pending authority
+pub fn accept_authority(new_authority: &Pubkey, extras: AdminExtras) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*new_authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            extras.admin_log(),
+        ],
+        data: instruction_data("accept_authority", &()),
+    }
+}
+
+/// `initialize`, creating the state, `mint`, which must sign, and the
+/// authority's associated account holding `initial_supply`
+pub fn initialize(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    price_feed: &Pubkey,
+    initial_supply: u64,
+    max_supply: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            AccountMeta::new(*mint, true),
+            AccountMeta::new(associated_token_account(authority, mint), false),
+            AccountMeta::new_readonly(*price_feed, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: instruction_data("initialize", &(initial_supply, max_supply)),
+    }
+}
+
+/// Optional accounts `crank_requirement` updates alongside the requirement
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CrankExtras {
+    /// The configured fallback feed, tried when the primary one fails
+    pub fallback_price_feed: Option<Pubkey>,
+    pub price_accumulator: bool,
+    pub requirement_history: bool,
+}
+
+/// `crank_requirement`, paying the keeper's reward to
+/// `keeper_token_account`
+pub fn crank_requirement(
+    keeper: &Pubkey,
+    price_feed: &Pubkey,
+    keeper_token_account: &Pubkey,
+    extras: CrankExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*keeper, true),
+            AccountMeta::new(pda::token_state().0, false),
+            AccountMeta::new_readonly(*price_feed, false),
+            optional(extras.fallback_price_feed, false),
+            optional(extras.price_accumulator.then(|| pda::price_accumulator().0), true),
+            optional(extras.requirement_history.then(|| pda::requirement_history().0), true),
+            AccountMeta::new(pda::keeper_vault().0, false),
+            AccountMeta::new(*keeper_token_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: instruction_data("crank_requirement", &()),
+    }
+}
+
+fn set_paused(name: &str, authority: &Pubkey, extras: AdminExtras) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            extras.role_registry(),
+            AccountMeta::new(pda::guardian_council().0, false),
+            extras.admin_log(),
+        ],
+        data: instruction_data(name, &()),
+    }
+}
+
+/// `pause`, signed by an operator
+pub fn pause(authority: &Pubkey, extras: AdminExtras) -> Instruction {
+    set_paused("pause", authority, extras)
+}
+
+pub fn unpause(authority: &Pubkey, extras: AdminExtras) -> Instruction {
+    set_paused("unpause", authority, extras)
+}
+
+/// `mint_tokens` to `destination`, signed by a minter once the mint
+/// authority has moved to `pda::mint_authority`
+pub fn mint_tokens(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    extras: AdminExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(pda::token_state().0, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(pda::mint_authority().0, false),
+            AccountMeta::new(*destination, false),
+            extras.role_registry(),
+            extras.admin_log(),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: instruction_data("mint_tokens", &amount),
+    }
+}
+
+pub fn deposit_treasury_sol(depositor: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(pda::treasury().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: instruction_data("deposit_treasury_sol", &amount),
+    }
+}
+
+/// `deposit_treasury_tokens` from `source` into the vault for `mint`
+pub fn deposit_treasury_tokens(
+    depositor: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*depositor, true),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(pda::treasury_token_vault(mint).0, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: instruction_data("deposit_treasury_tokens", &amount),
+    }
+}
+
+/// `withdraw_treasury_sol`, signed by the authority
+pub fn withdraw_treasury_sol(
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    extras: AdminExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(pda::token_state().0, false),
+            AccountMeta::new(pda::treasury().0, false),
+            AccountMeta::new(*recipient, false),
+            extras.admin_log(),
+        ],
+        data: instruction_data("withdraw_treasury_sol", &amount),
+    }
+}
+
+/// `withdraw_treasury_tokens` of `mint`, owned by `token_program`, signed
+/// by the authority
+pub fn withdraw_treasury_tokens(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    token_program: &Pubkey,
+    extras: AdminExtras,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(pda::token_state().0, false),
+            AccountMeta::new(pda::treasury().0, false),
+            AccountMeta::new(pda::treasury_token_vault(mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*recipient, false),
+            extras.admin_log(),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: instruction_data("withdraw_treasury_tokens", &amount),
+    }
+}
+
+/// `migrate_state`, upgrading the state account to the current layout.
+/// v0 states stored no price feed, so theirs has to be passed.
+pub fn migrate_state(authority: &Pubkey, price_feed: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(pda::token_state().0, false),
+            optional(price_feed, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: instruction_data("migrate_state", &()),
+    }
+}
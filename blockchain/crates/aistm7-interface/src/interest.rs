@@ -0,0 +1,120 @@
+//! The token-2022 mint's interest-bearing extension, read straight from
+//! the mint account so clients can show the balance a holder has accrued.
+//! Interest is display only: token-2022 never mints it, so a token
+//! account's raw `amount` stays the same while its UI amount grows.
+
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Where the base mint ends and the account type byte of an extended mint
+/// sits, followed by its extensions
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const DECIMALS_OFFSET: usize = 44;
+/// `ExtensionType::InterestBearingConfig`
+const INTEREST_BEARING_CONFIG: u16 = 10;
+const INTEREST_BEARING_CONFIG_LEN: usize = 52;
+
+/// Token-2022's year length for interest
+pub const SECONDS_PER_YEAR: f64 = 60.0 * 60.0 * 24.0 * 365.24;
+
+/// Rates are in basis points per year, compounded continuously
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterestBearingConfig {
+    /// The token program's mint authority; `None` once the rate is fixed
+    pub rate_authority: Option<Pubkey>,
+    pub initialization_timestamp: i64,
+    /// Average rate between initialization and the last update
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    pub current_rate: i16,
+}
+
+impl InterestBearingConfig {
+    /// The extension of a token-2022 mint, or `None` if the mint was
+    /// created without it
+    pub fn from_mint_data(data: &[u8]) -> Result<Option<Self>, ProgramError> {
+        if data.len() <= ACCOUNT_TYPE_OFFSET {
+            return Ok(None);
+        }
+        if data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+        while offset + 4 <= data.len() {
+            let extension = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let value = data
+                .get(offset + 4..offset + 4 + len)
+                .ok_or(ProgramError::InvalidAccountData)?;
+            if extension == INTEREST_BEARING_CONFIG {
+                return Self::unpack(value).map(Some);
+            }
+            offset += 4 + len;
+        }
+        Ok(None)
+    }
+
+    fn unpack(value: &[u8]) -> Result<Self, ProgramError> {
+        if value.len() != INTEREST_BEARING_CONFIG_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let i64_at = |at: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&value[at..at + 8]);
+            i64::from_le_bytes(bytes)
+        };
+        let i16_at = |at: usize| i16::from_le_bytes([value[at], value[at + 1]]);
+        let rate_authority = Pubkey::try_from(&value[..32])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Self {
+            rate_authority: (rate_authority != Pubkey::default()).then_some(rate_authority),
+            initialization_timestamp: i64_at(32),
+            pre_update_average_rate: i16_at(40),
+            last_update_timestamp: i64_at(42),
+            current_rate: i16_at(50),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl InterestBearingConfig {
+    /// What a raw amount is multiplied by at `now`, as token-2022's
+    /// `amount_to_ui_amount` computes it: the average rate continuously
+    /// compounded up to the last update, then the current rate since
+    pub fn scale(&self, now: i64) -> f64 {
+        let compounded = |rate: i16, from: i64, to: i64| {
+            let years = to.saturating_sub(from) as f64 / SECONDS_PER_YEAR;
+            (rate as f64 / 10_000.0 * years).exp()
+        };
+        compounded(
+            self.pre_update_average_rate,
+            self.initialization_timestamp,
+            self.last_update_timestamp,
+        ) * compounded(self.current_rate, self.last_update_timestamp, now)
+    }
+
+    /// `amount` of the mint as a wallet should display it at `now`,
+    /// interest included
+    pub fn ui_amount(&self, amount: u64, decimals: u8, now: i64) -> f64 {
+        amount as f64 * self.scale(now) / 10f64.powi(decimals as i32)
+    }
+}
+
+/// Decimals of any SPL or token-2022 mint
+pub fn mint_decimals(data: &[u8]) -> Result<u8, ProgramError> {
+    data.get(DECIMALS_OFFSET)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// `amount` of the mint in `data` as a wallet should display it at `now`;
+/// plain decimal shifting for mints without the interest extension
+#[cfg(feature = "std")]
+pub fn ui_amount(data: &[u8], amount: u64, now: i64) -> Result<f64, ProgramError> {
+    let decimals = mint_decimals(data)?;
+    Ok(match InterestBearingConfig::from_mint_data(data)? {
+        Some(config) => config.ui_amount(amount, decimals, now),
+        None => amount as f64 / 10f64.powi(decimals as i32),
+    })
+}
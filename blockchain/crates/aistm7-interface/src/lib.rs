@@ -0,0 +1,79 @@
+//! Client-side interface to the AISTM7 token program: its ID, the layouts
+//! of the accounts and events integrators read, PDA derivation, and
+//! builders for the instructions they send, including the proposals that
+//! administer it from a Realms DAO, and the merkle trees its claims are
+//! proven against. Depends only on `solana-program` and
+//! `borsh`, so services and other on-chain programs can use it without
+//! compiling the program or Anchor. The `std` feature
+//! adds the floating-point helpers for displaying interest-bearing amounts,
+//! and the `serde` feature makes the account layouts serializable.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use solana_program::hash::hashv;
+
+pub mod cpi;
+pub mod events;
+pub mod gate;
+pub mod governance;
+pub mod interest;
+pub mod instruction;
+pub mod merkle;
+pub mod pda;
+pub mod state;
+
+solana_program::declare_id!("AiSTM7TokenProgram11111111111111111111111111");
+
+/// The program's `ErrorCode::AccessRequired`, raised by `cpi::require_access`
+pub const ACCESS_REQUIRED_ERROR: u32 = 6075;
+
+/// First eight bytes of `sha256("<namespace>:<name>")`, as Anchor prefixes
+/// instruction data (`global`), accounts (`account`), and events (`event`)
+pub fn discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]).to_bytes();
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::ToString;
+
+    use super::*;
+
+    const PROGRAM_SOURCE: &str = include_str!("../../../programs/aistm7_token/src/lib.rs");
+    const ANCHOR_TOML: &str = include_str!("../../../Anchor.toml");
+
+    #[test]
+    fn id_matches_the_program() {
+        let declared = PROGRAM_SOURCE
+            .split("declare_id!(\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("the program declares its id");
+        assert_eq!(ID.to_string(), declared);
+    }
+
+    #[test]
+    fn id_matches_every_anchor_cluster() {
+        let ids: std::vec::Vec<&str> = ANCHOR_TOML
+            .lines()
+            .filter_map(|line| line.strip_prefix("aistm7_token = \""))
+            .map(|rest| rest.trim_end_matches('"'))
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| *id == ID.to_string()));
+    }
+
+    #[test]
+    fn discriminator_is_the_anchor_prefix() {
+        assert_eq!(
+            discriminator("global", "initialize"),
+            [175, 175, 109, 31, 13, 152, 155, 237]
+        );
+    }
+}
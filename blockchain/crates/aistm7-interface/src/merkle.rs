@@ -0,0 +1,88 @@
+//! Merkle trees the program's distributions, revenue epochs, presale
+//! whitelists, and vote snapshots are claimed against. Leaves are
+//! `leaf_hash(index, wallet, amount)`, pairs are hashed in sorted order
+//! under a separate prefix, and a node left without a sibling moves up a
+//! level unchanged.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use solana_program::hash::hashv;
+use solana_program::pubkey::Pubkey;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf_hash(index: u32, wallet: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        wallet.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Every level of a tree, from the leaves up to the root
+pub struct MerkleTree {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Tree over `(wallet, amount)` entries, the leaf of each at its index
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = (&'a Pubkey, u64)>) -> Self {
+        let leaves = entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, (wallet, amount))| leaf_hash(index as u32, wallet, amount))
+            .collect();
+        MerkleTree::new(leaves)
+    }
+
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        loop {
+            let level = &levels[levels.len() - 1];
+            if level.len() <= 1 {
+                break;
+            }
+            let parents = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node_hash(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(parents);
+        }
+        MerkleTree { levels }
+    }
+
+    /// Root of the tree; all zeroes when it has no leaves
+    pub fn root(&self) -> [u8; 32] {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Siblings from the leaf at `index` up to the root
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        let mut index = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
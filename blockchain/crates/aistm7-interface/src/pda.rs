@@ -0,0 +1,235 @@
+//! Addresses of the program's PDAs, as `(address, bump)`
+
+use solana_program::pubkey::Pubkey;
+
+use crate::ID;
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ID)
+}
+
+pub fn token_state() -> (Pubkey, u8) {
+    find(&[b"token_state"])
+}
+
+pub fn role_registry() -> (Pubkey, u8) {
+    find(&[b"role_registry"])
+}
+
+pub fn gating_config() -> (Pubkey, u8) {
+    find(&[b"gating_config"])
+}
+
+pub fn member_registry() -> (Pubkey, u8) {
+    find(&[b"member_registry"])
+}
+
+pub fn admin_log() -> (Pubkey, u8) {
+    find(&[b"admin_log"])
+}
+
+pub fn mint_authority() -> (Pubkey, u8) {
+    find(&[b"mint_authority"])
+}
+
+/// Holds the treasury's SOL and signs for its token vaults
+pub fn treasury() -> (Pubkey, u8) {
+    find(&[b"treasury"])
+}
+
+/// Pays `keeper_reward` to whoever cranks the requirement
+pub fn keeper_vault() -> (Pubkey, u8) {
+    find(&[b"keeper_vault"])
+}
+
+pub fn price_accumulator() -> (Pubkey, u8) {
+    find(&[b"price_accumulator"])
+}
+
+pub fn requirement_history() -> (Pubkey, u8) {
+    find(&[b"requirement_history"])
+}
+
+/// The token-2022 mint holders migrate to
+pub fn token_2022_mint() -> (Pubkey, u8) {
+    find(&[b"token_2022_mint"])
+}
+
+pub fn transfer_hook_config() -> (Pubkey, u8) {
+    find(&[b"transfer_hook_config"])
+}
+
+/// Wormhole emitter of the program's cross-chain access attestations
+pub fn emitter() -> (Pubkey, u8) {
+    find(&[b"emitter"])
+}
+
+/// The token-2022 mint's permanent delegate, which executes clawbacks
+pub fn clawback_authority() -> (Pubkey, u8) {
+    find(&[b"clawback_authority"])
+}
+
+pub fn sale() -> (Pubkey, u8) {
+    find(&[b"sale"])
+}
+
+pub fn presale() -> (Pubkey, u8) {
+    find(&[b"presale"])
+}
+
+pub fn stream_config() -> (Pubkey, u8) {
+    find(&[b"stream_config"])
+}
+
+pub fn swap_config() -> (Pubkey, u8) {
+    find(&[b"swap_config"])
+}
+
+pub fn dca_schedule() -> (Pubkey, u8) {
+    find(&[b"dca_schedule"])
+}
+
+pub fn liquidity_config() -> (Pubkey, u8) {
+    find(&[b"liquidity_config"])
+}
+
+pub fn auction(tranche: u64) -> (Pubkey, u8) {
+    find(&[b"auction", &tranche.to_le_bytes()])
+}
+
+/// A bidder's commitments to `auction`, the auction account's address
+pub fn auction_bid(auction: &Pubkey, bidder: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"auction_bid", auction.as_ref(), bidder.as_ref()])
+}
+
+pub fn guardian_council() -> (Pubkey, u8) {
+    find(&[b"guardian_council"])
+}
+
+pub fn governance_config() -> (Pubkey, u8) {
+    find(&[b"governance_config"])
+}
+
+pub fn proposal(id: u64) -> (Pubkey, u8) {
+    find(&[b"proposal", &id.to_le_bytes()])
+}
+
+/// A voter's ballot on `proposal`, the proposal account's address
+pub fn vote_record(proposal: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"vote", proposal.as_ref(), voter.as_ref()])
+}
+
+pub fn receipt(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"receipt", wallet.as_ref()])
+}
+
+pub fn access_override(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"access_override", wallet.as_ref()])
+}
+
+pub fn member(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"member", wallet.as_ref()])
+}
+
+pub fn stake(owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"stake", owner.as_ref()])
+}
+
+pub fn stake_pool() -> (Pubkey, u8) {
+    find(&[b"stake_pool"])
+}
+
+pub fn vesting(beneficiary: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"vesting", beneficiary.as_ref()])
+}
+
+pub fn access_pass(owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"access_pass", owner.as_ref()])
+}
+
+pub fn holdings(owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"holdings", owner.as_ref()])
+}
+
+/// Keyed by the hot wallet the access is delegated to
+pub fn delegation(hot_wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"delegation", hot_wallet.as_ref()])
+}
+
+pub fn session(user: &Pubkey, session_key: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"session", user.as_ref(), session_key.as_ref()])
+}
+
+pub fn usage_counter(owner: &Pubkey, feature_id: u32) -> (Pubkey, u8) {
+    find(&[b"usage", owner.as_ref(), &feature_id.to_le_bytes()])
+}
+
+pub fn treasury_token_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"treasury_token_vault", mint.as_ref()])
+}
+
+pub fn access_badge(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"access_badge", wallet.as_ref()])
+}
+
+/// What `buyer` has bought in the sale, against its wallet cap
+pub fn sale_purchase(buyer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"sale_purchase", buyer.as_ref()])
+}
+
+pub fn presale_contribution(contributor: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"presale_contribution", contributor.as_ref()])
+}
+
+pub fn otc_offer(maker: &Pubkey, offer_id: u64) -> (Pubkey, u8) {
+    find(&[b"otc_offer", maker.as_ref(), &offer_id.to_le_bytes()])
+}
+
+/// Escrow of the offer at `offer`
+pub fn otc_vault(offer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"otc_vault", offer.as_ref()])
+}
+
+pub fn payment_stream(payer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"payment_stream", payer.as_ref()])
+}
+
+/// Escrow of the stream at `stream`
+pub fn stream_vault(stream: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"stream_vault", stream.as_ref()])
+}
+
+pub fn subscription_plan(plan_id: u32) -> (Pubkey, u8) {
+    find(&[b"subscription_plan", &plan_id.to_le_bytes()])
+}
+
+/// Also the delegate renewals are charged through
+pub fn recurring_subscription(subscriber: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"recurring_subscription", subscriber.as_ref()])
+}
+
+pub fn bounty(bounty_id: u64) -> (Pubkey, u8) {
+    find(&[b"bounty", &bounty_id.to_le_bytes()])
+}
+
+/// Escrow of the bounty at `bounty`
+pub fn bounty_vault(bounty: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"bounty_vault", bounty.as_ref()])
+}
+
+pub fn bounty_claim(bounty: &Pubkey, claimant: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"bounty_claim", bounty.as_ref(), claimant.as_ref()])
+}
+
+/// Also the authority over the insurance vault
+pub fn insurance_fund() -> (Pubkey, u8) {
+    find(&[b"insurance_fund"])
+}
+
+pub fn insurance_vault() -> (Pubkey, u8) {
+    find(&[b"insurance_vault"])
+}
+
+pub fn insurance_incident(incident_id: u64) -> (Pubkey, u8) {
+    find(&[b"insurance_incident", &incident_id.to_le_bytes()])
+}
@@ -0,0 +1,387 @@
+//! Layouts of the accounts integrators read. Each mirrors the program's
+//! account field for field; `from_account_data` checks the discriminator.
+//! The `serde` feature makes them serializable, for tools that print them.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::discriminator;
+
+/// `VerificationReceipt::tier` of a wallet without access
+pub const NO_ACCESS: u8 = 0;
+
+/// Lengths of the fixed arrays in `TokenState`
+pub const MAX_MEDIAN_FEEDS: usize = 3;
+pub const LOCK_TIERS: usize = 4;
+pub const ACCESS_TIERS: usize = 3;
+
+pub trait ProgramAccount: BorshDeserialize {
+    /// Anchor account name
+    const NAME: &'static str;
+
+    fn from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 8 || data[..8] != discriminator("account", Self::NAME) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::deserialize(&mut &data[8..]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum OracleSource {
+    Pyth,
+    Switchboard,
+    Custom,
+    Chainlink,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OracleFeed {
+    pub source: OracleSource,
+    pub address: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LockTier {
+    pub duration_secs: u64,
+    pub reward_multiplier_bps: u64,
+    pub boost_bps: u64,
+}
+
+/// The program's configuration and running totals, at `pda::token_state`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TokenState {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub oracle_source: OracleSource,
+    pub price_feed: Pubkey,
+    pub fallback_oracle_source: OracleSource,
+    pub fallback_price_feed: Pubkey,
+    pub median_feeds: [OracleFeed; MAX_MEDIAN_FEEDS],
+    pub min_median_feeds: u8,
+    pub twap_window_secs: u64,
+    pub target_usd_value: u64,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub current_requirement: u64,
+    pub last_update: i64,
+    pub price_decimals: u8,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u64,
+    pub keeper_reward: u64,
+    pub crank_interval_secs: u64,
+    pub last_crank: i64,
+    pub last_price: u64,
+    pub circuit_breaker_bps: u64,
+    pub circuit_breaker_tripped: bool,
+    pub update_threshold_bps: u64,
+    pub max_step_bps: u64,
+    pub min_update_interval_secs: u64,
+    pub last_refresh: i64,
+    pub pull_feed_id: [u8; 32],
+    pub use_ema_price: bool,
+    pub requirement_history_enabled: bool,
+    pub timelock_delay_secs: u64,
+    pub next_change_id: u64,
+    pub paused: bool,
+    pub pending_authority: Pubkey,
+    pub admin_log_enabled: bool,
+    pub max_supply: u64,
+    pub supply_finalized: bool,
+    pub total_burned: u64,
+    pub total_bought_back: u64,
+    pub fee_bps: u64,
+    pub fee_destination: Pubkey,
+    pub fees_enabled: bool,
+    pub next_distribution_id: u64,
+    pub lock_tiers: [LockTier; LOCK_TIERS],
+    pub unstake_cooldown_secs: u64,
+    pub early_exit_penalty_bps: u64,
+    pub burn_early_exit_penalty: bool,
+    pub next_revenue_epoch: u64,
+    pub referral_bps: u64,
+    pub burn_consumed_access: bool,
+    pub receipt_validity_secs: u64,
+    pub access_tiers: [u64; ACCESS_TIERS],
+    pub grace_period_secs: u64,
+    pub min_holding_secs: u64,
+    pub migration_mint: Pubkey,
+    pub migration_started_at: i64,
+    pub migrated_amount: u64,
+    pub insurance_bps: u64,
+    pub insurance_vault: Pubkey,
+}
+
+impl ProgramAccount for TokenState {
+    const NAME: &'static str = "TokenState";
+}
+
+/// `TokenState::version` written by the current program
+pub const STATE_VERSION: u8 = 1;
+
+/// `TokenState` as first deployed, before the layout carried a version;
+/// `migrate_state` upgrades it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TokenStateV0 {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub target_usd_value: u64,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub current_requirement: u64,
+    pub last_update: i64,
+}
+
+impl TokenStateV0 {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8;
+}
+
+impl ProgramAccount for TokenStateV0 {
+    const NAME: &'static str = "TokenState";
+}
+
+/// Layout version of raw state account data, as `migrate_state` reads it:
+/// v0 is recognised by its length, later layouts store it first
+pub fn state_version(data: &[u8]) -> Option<u8> {
+    if data.len() <= 8 || data[..8] != discriminator("account", TokenState::NAME) {
+        return None;
+    }
+    if data.len() == 8 + TokenStateV0::LEN {
+        return Some(0);
+    }
+    Some(data[8])
+}
+
+pub const MAX_TREASURY_ASSETS: usize = 8;
+
+/// Per-epoch withdrawal allowance of one treasury asset; the default key
+/// stands for SOL
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TreasuryLimit {
+    pub asset: Pubkey,
+    pub per_epoch_limit: u64,
+    pub withdrawn: u64,
+}
+
+/// At `pda::treasury`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Treasury {
+    pub epoch_secs: u64,
+    pub epoch_start: i64,
+    pub limits: [TreasuryLimit; MAX_TREASURY_ASSETS],
+}
+
+impl ProgramAccount for Treasury {
+    const NAME: &'static str = "Treasury";
+}
+
+/// At `pda::stake_pool`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StakePool {
+    pub total_staked: u64,
+    pub total_weight: u64,
+    pub reward_per_token: u128,
+    pub reserved_rewards: u64,
+}
+
+impl ProgramAccount for StakePool {
+    const NAME: &'static str = "StakePool";
+}
+
+/// At `pda::stake`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub weight: u64,
+    pub lock_tier: u8,
+    pub unlock_at: i64,
+    pub reward_per_token_paid: u128,
+    pub pending_rewards: u64,
+    pub staked_at: i64,
+    pub unstaking_amount: u64,
+    pub unstake_available_at: i64,
+}
+
+impl ProgramAccount for StakeAccount {
+    const NAME: &'static str = "StakeAccount";
+}
+
+/// At `pda::vesting`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VestingSchedule {
+    pub beneficiary: Pubkey,
+    pub start_time: i64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub revocable: bool,
+    pub revoked_at: i64,
+}
+
+impl ProgramAccount for VestingSchedule {
+    const NAME: &'static str = "VestingSchedule";
+}
+
+impl VestingSchedule {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at != 0
+    }
+
+    /// Tokens vested at `now`, counting only up to the revocation
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let now = if self.is_revoked() {
+            core::cmp::min(now, self.revoked_at)
+        } else {
+            now
+        };
+        let elapsed = now.saturating_sub(self.start_time);
+        if elapsed < self.cliff_secs as i64 {
+            return 0;
+        }
+        if elapsed as u64 >= self.duration_secs {
+            return self.total_amount;
+        }
+        (self.total_amount as u128 * elapsed as u128 / self.duration_secs as u128) as u64
+    }
+
+    /// Tokens still held in escrow for the beneficiary: everything not yet
+    /// claimed, or once revoked only what had vested by then
+    pub fn unclaimed(&self) -> u64 {
+        let entitled = if self.is_revoked() {
+            self.vested_amount(self.revoked_at)
+        } else {
+            self.total_amount
+        };
+        entitled.saturating_sub(self.claimed_amount)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum AccessOverrideKind {
+    Allow,
+    Deny,
+}
+
+/// Outcome of a wallet's last verification, at `pda::receipt`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VerificationReceipt {
+    pub wallet: Pubkey,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub requirement_at_verification: u64,
+    pub tier: u8,
+    pub below_since: i64,
+}
+
+impl ProgramAccount for VerificationReceipt {
+    const NAME: &'static str = "VerificationReceipt";
+}
+
+impl VerificationReceipt {
+    /// Whether the receipt grants access at `now`
+    pub fn grants_access(&self, now: i64) -> bool {
+        self.tier != NO_ACCESS && now < self.expires_at
+    }
+}
+
+/// Return data of `check_access`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AccessStatus {
+    pub wallet: Pubkey,
+    pub has_access: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub checked_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AccessOverride {
+    pub wallet: Pubkey,
+    pub kind: AccessOverrideKind,
+    pub reason: [u8; 32],
+    pub set_by: Pubkey,
+    pub set_at: i64,
+}
+
+impl ProgramAccount for AccessOverride {
+    const NAME: &'static str = "AccessOverride";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AccessPass {
+    pub owner: Pubkey,
+    pub escrowed_amount: u64,
+    pub starts_at: i64,
+    pub expires_at: i64,
+}
+
+impl ProgramAccount for AccessPass {
+    const NAME: &'static str = "AccessPass";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Delegation {
+    pub cold_wallet: Pubkey,
+    pub hot_wallet: Pubkey,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl ProgramAccount for Delegation {
+    const NAME: &'static str = "Delegation";
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemberRecord {
+    pub index: u64,
+    pub wallet: Pubkey,
+    pub active: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub joined_at: i64,
+    pub synced_at: i64,
+}
+
+impl ProgramAccount for MemberRecord {
+    const NAME: &'static str = "MemberRecord";
+}
+
+/// Rules `aistm7_transfer_hook` applies to transfers of the token-2022 mint,
+/// at `pda::transfer_hook_config`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransferHookConfig {
+    pub max_wallet_bps: u64,
+    pub exempt: [Pubkey; 8],
+}
+
+impl ProgramAccount for TransferHookConfig {
+    const NAME: &'static str = "TransferHookConfig";
+}
+
+impl TransferHookConfig {
+    pub fn is_exempt(&self, owner: &Pubkey) -> bool {
+        *owner != Pubkey::default() && self.exempt.contains(owner)
+    }
+}
@@ -0,0 +1,86 @@
+//! `aistm7-push`, the WebSocket service that pushes requirement changes and
+//! verifications to the frontend as they land, instead of it polling for
+//! them. Connect to `/ws` and subscribe to topics as `protocol` describes;
+//! `/health` answers once the service is up.
+
+mod protocol;
+mod session;
+mod source;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::Aistm7Client;
+use anyhow::Result;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::sync::broadcast;
+
+use crate::protocol::Published;
+
+#[derive(Parser)]
+#[command(name = "aistm7-push", version, about = "Push AISTM7 program events over WebSocket")]
+struct Opts {
+    #[arg(long, env = "AISTM7_PUSH_LISTEN", default_value = "0.0.0.0:8787")]
+    listen: SocketAddr,
+    /// RPC endpoint, for the requirement snapshot sent on subscribing
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Websocket endpoint the program's logs are followed on
+    #[arg(long, env = "AISTM7_WS_URL", default_value = "ws://127.0.0.1:8900")]
+    ws_url: String,
+    /// Seconds between heartbeats to each client
+    #[arg(long, default_value_t = 15)]
+    heartbeat_secs: u64,
+    /// Events held for slow clients; one further behind is told it lagged
+    #[arg(long, default_value_t = 1024)]
+    channel_capacity: usize,
+    /// Wait before resubscribing after the log subscription drops
+    #[arg(long, default_value_t = 5)]
+    retry_secs: u64,
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub client: Arc<Aistm7Client>,
+    pub published: broadcast::Sender<Arc<Published>>,
+    pub heartbeat: Duration,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let commitment = CommitmentConfig::confirmed();
+    let (published, _) = broadcast::channel(opts.channel_capacity);
+    let state = AppState {
+        client: Arc::new(Aistm7Client::with_url(opts.url, commitment)),
+        published: published.clone(),
+        heartbeat: Duration::from_secs(opts.heartbeat_secs),
+    };
+    tokio::spawn(source::follow(
+        opts.ws_url,
+        commitment,
+        Duration::from_secs(opts.retry_secs),
+        published,
+    ));
+
+    let app = Router::new()
+        .route("/ws", get(connect))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(state);
+    eprintln!("Listening on {}", opts.listen);
+    axum::Server::bind(&opts.listen)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+async fn connect(upgrade: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    upgrade.on_upgrade(move |socket| session::run(socket, state))
+}
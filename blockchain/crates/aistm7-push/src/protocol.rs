@@ -0,0 +1,204 @@
+//! What travels over the socket, as JSON text frames. Clients send
+//!
+//! ```json
+//! { "op": "subscribe", "topics": ["requirement", "wallet:<address>"] }
+//! { "op": "unsubscribe", "topics": ["requirement"] }
+//! { "op": "ping" }
+//! ```
+//!
+//! and receive `subscribed`, `unsubscribed`, `pong`, and `error` replies,
+//! `event`s for their topics, a `snapshot` of the current requirement on
+//! subscribing to `requirement`, a `heartbeat` every interval, and `resync`
+//! when the service lost its upstream and may have missed events, or
+//! `lagged` when the client fell too far behind. After either, state held
+//! from earlier events should be fetched again.
+
+use std::fmt;
+use std::str::FromStr;
+
+use aistm7_sdk::{DecodedEvent, EventRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Every `BalanceRequirementUpdated`
+    Requirement,
+    /// Every `BalanceVerified`
+    Verifications,
+    /// `BalanceVerified` events where the wallet verified, or the holder
+    /// whose tokens it was verified with, is this one
+    Wallet(Pubkey),
+}
+
+impl FromStr for Topic {
+    type Err = String;
+
+    fn from_str(topic: &str) -> Result<Self, Self::Err> {
+        match topic {
+            "requirement" => Ok(Topic::Requirement),
+            "verifications" => Ok(Topic::Verifications),
+            _ => topic
+                .strip_prefix("wallet:")
+                .and_then(|wallet| wallet.parse().ok())
+                .map(Topic::Wallet)
+                .ok_or_else(|| format!("unknown topic {topic:?}")),
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Requirement => f.write_str("requirement"),
+            Topic::Verifications => f.write_str("verifications"),
+            Topic::Wallet(wallet) => write!(f, "wallet:{wallet}"),
+        }
+    }
+}
+
+impl Serialize for Topic {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum ClientMessage {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+    Ping,
+}
+
+/// A frame ready to fan out, serialized once: it goes to every client
+/// subscribed to any of `topics`, or to every client when there are none
+#[derive(Debug)]
+pub struct Published {
+    pub topics: Vec<Topic>,
+    pub frame: String,
+}
+
+impl Published {
+    /// `record` as published, or `None` if it is not an event any topic
+    /// carries
+    pub fn event(record: &EventRecord) -> Option<Self> {
+        let topics = match record.decode()? {
+            DecodedEvent::BalanceRequirementUpdated(_) => vec![Topic::Requirement],
+            DecodedEvent::BalanceVerified(event) => {
+                let mut topics = vec![Topic::Verifications, Topic::Wallet(event.wallet)];
+                if event.holder != event.wallet {
+                    topics.push(Topic::Wallet(event.holder));
+                }
+                topics
+            }
+            _ => return None,
+        };
+        let mut frame = record.to_json();
+        frame["type"] = json!("event");
+        frame["topics"] = json!(topics);
+        Some(Published {
+            topics,
+            frame: frame.to_string(),
+        })
+    }
+
+    /// A frame for every client, whatever it subscribed to
+    pub fn everyone(frame: Value) -> Self {
+        Published {
+            topics: Vec::new(),
+            frame: frame.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aistm7_sdk::events::{BalanceVerified, ProgramEvent};
+    use aistm7_sdk::interface::discriminator;
+    use borsh::BorshSerialize;
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+
+    fn verified(wallet: Pubkey, holder: Pubkey) -> EventRecord {
+        let event = BalanceVerified {
+            wallet,
+            holder,
+            verified: true,
+            tier: 1,
+            balance: 2_000,
+            requirement: 1_000,
+            expires_at: 1_674_478_800,
+            in_grace_period: false,
+            access_override: None,
+            timestamp: 1_674_392_400,
+        };
+        let mut data = discriminator("event", BalanceVerified::NAME).to_vec();
+        BorshSerialize::serialize(&event, &mut data).unwrap();
+        EventRecord {
+            signature: Signature::new_unique(),
+            slot: 42,
+            block_time: None,
+            index: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn topics_round_trip_through_their_names() {
+        let wallet = Pubkey::new_unique();
+        for topic in [Topic::Requirement, Topic::Verifications, Topic::Wallet(wallet)] {
+            assert_eq!(topic.to_string().parse::<Topic>(), Ok(topic));
+        }
+        assert_eq!(json!(Topic::Wallet(wallet)), json!(format!("wallet:{wallet}")));
+    }
+
+    #[test]
+    fn rejects_unknown_topics() {
+        for topic in ["requirements", "wallet:", "wallet:not-a-key", ""] {
+            assert!(topic.parse::<Topic>().is_err(), "{topic:?}");
+        }
+    }
+
+    #[test]
+    fn reads_client_messages_by_op() {
+        let message = serde_json::from_str(r#"{ "op": "subscribe", "topics": ["requirement"] }"#);
+        assert!(matches!(
+            message,
+            Ok(ClientMessage::Subscribe { topics }) if topics == ["requirement"]
+        ));
+        assert!(matches!(serde_json::from_str(r#"{ "op": "ping" }"#), Ok(ClientMessage::Ping)));
+        assert!(serde_json::from_str::<ClientMessage>(r#"{ "op": "shout" }"#).is_err());
+    }
+
+    #[test]
+    fn publishes_verifications_to_the_wallet_and_its_holder() {
+        let (wallet, holder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let published = Published::event(&verified(wallet, holder)).unwrap();
+        assert_eq!(
+            published.topics,
+            vec![Topic::Verifications, Topic::Wallet(wallet), Topic::Wallet(holder)]
+        );
+        let frame: Value = serde_json::from_str(&published.frame).unwrap();
+        assert_eq!(frame["type"], json!("event"));
+        assert_eq!(frame["topics"][1], json!(format!("wallet:{wallet}")));
+
+        let published = Published::event(&verified(wallet, wallet)).unwrap();
+        assert_eq!(published.topics, vec![Topic::Verifications, Topic::Wallet(wallet)]);
+    }
+
+    #[test]
+    fn skips_events_no_topic_carries() {
+        let record = EventRecord {
+            signature: Signature::new_unique(),
+            slot: 42,
+            block_time: None,
+            index: 0,
+            data: vec![0; 16],
+        };
+        assert!(Published::event(&record).is_none());
+        assert!(Published::everyone(json!({ "type": "resync" })).topics.is_empty());
+    }
+}
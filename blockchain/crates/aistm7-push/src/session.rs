@@ -0,0 +1,150 @@
+//! One client's connection: its subscriptions, the frames it is sent, and
+//! the heartbeat. Every interval the client gets a WebSocket ping and a
+//! `heartbeat` frame, for browsers, which cannot see pings; one that has
+//! sent nothing, pongs included, for `MISSED_HEARTBEATS` intervals is
+//! dropped.
+
+use std::collections::HashSet;
+
+use axum::extract::ws::{Message, WebSocket};
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+use crate::protocol::{ClientMessage, Topic};
+use crate::AppState;
+
+/// Most topics one client can subscribe to
+const MAX_TOPICS: usize = 64;
+const MISSED_HEARTBEATS: u32 = 3;
+
+pub async fn run(socket: WebSocket, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut published = state.published.subscribe();
+    let mut topics: HashSet<Topic> = HashSet::new();
+    let mut heartbeat = tokio::time::interval(state.heartbeat);
+    let mut last_heard = Instant::now();
+
+    loop {
+        let sent = tokio::select! {
+            message = receiver.next() => {
+                let message = match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(message)) => message,
+                };
+                last_heard = Instant::now();
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let reply = handle(&text, &mut topics, &state).await;
+                send_all(&mut sender, reply).await
+            }
+            frame = published.recv() => match frame {
+                Ok(frame) => {
+                    let wanted = frame.topics.is_empty()
+                        || frame.topics.iter().any(|topic| topics.contains(topic));
+                    if !wanted {
+                        continue;
+                    }
+                    sender.send(Message::Text(frame.frame.clone())).await
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    send_all(&mut sender, vec![json!({ "type": "lagged", "missed": missed })]).await
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > state.heartbeat * MISSED_HEARTBEATS {
+                    break;
+                }
+                match sender.send(Message::Ping(Vec::new())).await {
+                    Ok(()) => send_all(&mut sender, vec![json!({ "type": "heartbeat" })]).await,
+                    Err(err) => Err(err),
+                }
+            }
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+    sender.close().await.ok();
+}
+
+/// Replies to one frame from the client
+async fn handle(text: &str, topics: &mut HashSet<Topic>, state: &AppState) -> Vec<Value> {
+    let message: ClientMessage = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(err) => return vec![error(format!("unreadable message: {err}"))],
+    };
+    match message {
+        ClientMessage::Ping => vec![json!({ "type": "pong" })],
+        ClientMessage::Subscribe { topics: requested } => {
+            let parsed = match parse(&requested) {
+                Ok(parsed) => parsed,
+                Err(err) => return vec![error(err)],
+            };
+            let mut added: Vec<Topic> = Vec::new();
+            for topic in parsed {
+                if !topics.contains(&topic) && !added.contains(&topic) {
+                    added.push(topic);
+                }
+            }
+            if topics.len() + added.len() > MAX_TOPICS {
+                return vec![error(format!("at most {MAX_TOPICS} topics per connection"))];
+            }
+            topics.extend(added.iter().copied());
+            let mut replies = vec![json!({ "type": "subscribed", "topics": added })];
+            if added.contains(&Topic::Requirement) {
+                replies.push(requirement_snapshot(state).await);
+            }
+            replies
+        }
+        ClientMessage::Unsubscribe { topics: requested } => match parse(&requested) {
+            Ok(parsed) => {
+                let removed: Vec<Topic> = parsed
+                    .into_iter()
+                    .filter(|topic| topics.remove(topic))
+                    .collect();
+                vec![json!({ "type": "unsubscribed", "topics": removed })]
+            }
+            Err(err) => vec![error(err)],
+        },
+    }
+}
+
+fn parse(topics: &[String]) -> Result<Vec<Topic>, String> {
+    topics.iter().map(|topic| topic.parse()).collect()
+}
+
+/// The requirement as it stands, so a new subscriber need not wait for the
+/// next change
+async fn requirement_snapshot(state: &AppState) -> Value {
+    match state.client.token_state().await {
+        Ok(token_state) => json!({
+            "type": "snapshot",
+            "topic": Topic::Requirement,
+            "data": {
+                "requirement": token_state.current_requirement,
+                "price": token_state.last_price,
+                "lastUpdate": token_state.last_update,
+            },
+        }),
+        Err(err) => error(format!("reading the current requirement: {err}")),
+    }
+}
+
+fn error(message: String) -> Value {
+    json!({ "type": "error", "message": message })
+}
+
+async fn send_all(
+    sender: &mut SplitSink<WebSocket, Message>,
+    frames: Vec<Value>,
+) -> Result<(), axum::Error> {
+    for frame in frames {
+        sender.send(Message::Text(frame.to_string())).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Following the program's logs and publishing the events clients can
+//! subscribe to. A dropped subscription is retried, and every client is
+//! told to resync, since events may have gone by in between.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::{EventRecord, ID};
+use anyhow::{bail, Result};
+use futures_util::StreamExt;
+use serde_json::json;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use tokio::sync::broadcast;
+
+use crate::protocol::Published;
+
+pub async fn follow(
+    ws_url: String,
+    commitment: CommitmentConfig,
+    retry: Duration,
+    published: broadcast::Sender<Arc<Published>>,
+) {
+    let mut connected_before = false;
+    loop {
+        if let Err(err) = subscribe(&ws_url, commitment, &published, &mut connected_before).await {
+            eprintln!("Log subscription stopped: {err:#}; retrying in {}s", retry.as_secs());
+        }
+        tokio::time::sleep(retry).await;
+    }
+}
+
+async fn subscribe(
+    ws_url: &str,
+    commitment: CommitmentConfig,
+    published: &broadcast::Sender<Arc<Published>>,
+    connected_before: &mut bool,
+) -> Result<()> {
+    let pubsub = PubsubClient::new(ws_url).await?;
+    let (mut logs, _unsubscribe) = pubsub
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )
+        .await?;
+    if *connected_before {
+        publish(published, Published::everyone(json!({ "type": "resync" })));
+    }
+    *connected_before = true;
+
+    while let Some(update) = logs.next().await {
+        let notification = update.value;
+        if notification.err.is_some() {
+            continue;
+        }
+        let signature = Signature::from_str(&notification.signature)?;
+        for record in EventRecord::from_logs(signature, update.context.slot, &notification.logs) {
+            if let Some(event) = Published::event(&record) {
+                publish(published, event);
+            }
+        }
+    }
+    bail!("subscription closed")
+}
+
+fn publish(published: &broadcast::Sender<Arc<Published>>, frame: Published) {
+    // Fails only while no client is connected
+    published.send(Arc::new(frame)).ok();
+}
@@ -0,0 +1,51 @@
+//! Decoding an account of any known type, by its discriminator
+
+use aistm7_interface::state::*;
+use serde::Serialize;
+
+macro_rules! decoded_accounts {
+    ($($name:ident),* $(,)?) => {
+        /// A program account, decoded as whichever layout its discriminator
+        /// names. Layouts are boxed, since they range from a few bytes to
+        /// the state's hundreds.
+        #[derive(Clone, Debug, Serialize)]
+        #[serde(tag = "type", content = "data")]
+        pub enum DecodedAccount {
+            $($name(Box<$name>),)*
+        }
+
+        impl DecodedAccount {
+            /// Decode `data`, or `None` if its discriminator names no known
+            /// layout or the rest does not match it
+            pub fn decode(data: &[u8]) -> Option<Self> {
+                $(
+                    if let Ok(account) = $name::from_account_data(data) {
+                        return Some(DecodedAccount::$name(Box::new(account)));
+                    }
+                )*
+                None
+            }
+
+            /// Anchor account name of the layout
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(DecodedAccount::$name(_) => $name::NAME,)*
+                }
+            }
+        }
+    };
+}
+
+decoded_accounts!(
+    TokenState,
+    Treasury,
+    VerificationReceipt,
+    AccessOverride,
+    AccessPass,
+    Delegation,
+    MemberRecord,
+    StakePool,
+    StakeAccount,
+    VestingSchedule,
+    TransferHookConfig,
+);
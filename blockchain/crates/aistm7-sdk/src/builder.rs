@@ -0,0 +1,94 @@
+//! Builder for any of the program's instructions, for the ones
+//! `aistm7_interface::instruction` has no typed builder for. Accounts are
+//! pushed in the order of the instruction's accounts struct.
+//!
+//! ```ignore
+//! let ix = InstructionBuilder::new("set_insurance_share")
+//!     .signer_writable(&authority)
+//!     .writable(&pda::token_state().0)
+//!     .readonly(&mint)
+//!     .writable(&pda::insurance_fund().0)
+//!     .writable(&pda::insurance_vault().0)
+//!     .optional(None, false)
+//!     .optional(None, true)
+//!     .readonly(&TOKEN_PROGRAM_ID)
+//!     .readonly(&system_program::ID)
+//!     .readonly(&sysvar::rent::ID)
+//!     .args(&2_000u64)
+//!     .build();
+//! ```
+
+use aistm7_interface::instruction::instruction_data;
+use aistm7_interface::ID;
+use borsh::BorshSerialize;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Debug)]
+pub struct InstructionBuilder {
+    name: &'static str,
+    accounts: Vec<AccountMeta>,
+    data: Vec<u8>,
+}
+
+impl InstructionBuilder {
+    /// Start the instruction `name`, in the program's snake case
+    pub fn new(name: &'static str) -> Self {
+        InstructionBuilder {
+            name,
+            accounts: Vec::new(),
+            data: instruction_data(name, &()),
+        }
+    }
+
+    /// Set the instruction's arguments, as a tuple when it takes several
+    pub fn args<T: BorshSerialize>(mut self, args: &T) -> Self {
+        self.data = instruction_data(self.name, args);
+        self
+    }
+
+    pub fn signer(mut self, address: &Pubkey) -> Self {
+        self.accounts.push(AccountMeta::new_readonly(*address, true));
+        self
+    }
+
+    pub fn signer_writable(mut self, address: &Pubkey) -> Self {
+        self.accounts.push(AccountMeta::new(*address, true));
+        self
+    }
+
+    pub fn readonly(mut self, address: &Pubkey) -> Self {
+        self.accounts.push(AccountMeta::new_readonly(*address, false));
+        self
+    }
+
+    pub fn writable(mut self, address: &Pubkey) -> Self {
+        self.accounts.push(AccountMeta::new(*address, false));
+        self
+    }
+
+    /// An optional account; `None` is passed as the program ID, which is
+    /// how Anchor reads it
+    pub fn optional(mut self, address: Option<Pubkey>, writable: bool) -> Self {
+        self.accounts.push(match address {
+            Some(address) if writable => AccountMeta::new(address, false),
+            Some(address) => AccountMeta::new_readonly(address, false),
+            None => AccountMeta::new_readonly(ID, false),
+        });
+        self
+    }
+
+    /// Accounts read from `remaining_accounts`, after the named ones
+    pub fn remaining(mut self, accounts: impl IntoIterator<Item = AccountMeta>) -> Self {
+        self.accounts.extend(accounts);
+        self
+    }
+
+    pub fn build(self) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: self.accounts,
+            data: self.data,
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use aistm7_interface::discriminator;
+use aistm7_interface::events::ProgramEvent;
+use aistm7_interface::pda;
+use aistm7_interface::state::{
+    AccessOverride, AccessOverrideKind, ProgramAccount, TokenState, VerificationReceipt,
+    NO_ACCESS,
+};
+use aistm7_interface::instruction::{associated_token_account, TOKEN_PROGRAM_ID};
+use aistm7_interface::ID;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionAccountsConfig,
+    RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::error::{Result, SdkError};
+
+/// Length of an SPL Token account
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+use crate::logs::parse_events;
+
+/// Where a wallet stands, as [`Aistm7Client::wallet_status`] reads it
+#[derive(Clone, Debug)]
+pub struct WalletStatus {
+    /// Access tier at the time asked about, or `NO_ACCESS`
+    pub access_tier: u8,
+    pub receipt: Option<VerificationReceipt>,
+    /// Balance of the wallet's associated token account alone; verifying
+    /// also counts stake and any extra token accounts passed
+    pub balance: u64,
+    pub requirement: u64,
+}
+
+/// RPC access to the program's accounts and instructions
+pub struct Aistm7Client {
+    rpc: RpcClient,
+}
+
+impl Aistm7Client {
+    pub fn new(rpc: RpcClient) -> Self {
+        Aistm7Client { rpc }
+    }
+
+    /// Client for the node at `url`, reading at `commitment`
+    pub fn with_url(url: impl Into<String>, commitment: CommitmentConfig) -> Self {
+        Aistm7Client::new(RpcClient::new_with_commitment(url.into(), commitment))
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    /// Decode the program account `T` at `address`, or `None` if nothing
+    /// is there
+    pub async fn fetch_optional<T: ProgramAccount>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())
+            .await?
+            .value;
+        match account {
+            Some(account) if account.owner == ID => T::from_account_data(&account.data)
+                .map(Some)
+                .map_err(|_| SdkError::InvalidAccountData(*address)),
+            Some(_) => Err(SdkError::InvalidAccountData(*address)),
+            None => Ok(None),
+        }
+    }
+
+    /// Decode the program account `T` at `address`
+    pub async fn fetch<T: ProgramAccount>(&self, address: &Pubkey) -> Result<T> {
+        self.fetch_optional(address)
+            .await?
+            .ok_or(SdkError::AccountNotFound(*address))
+    }
+
+    /// Every program account of type `T`, found by its discriminator
+    pub async fn fetch_all<T: ProgramAccount>(&self) -> Result<Vec<(Pubkey, T)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &discriminator("account", T::NAME),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(self.rpc.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc
+            .get_program_accounts_with_config(&ID, config)
+            .await?
+            .into_iter()
+            .map(|(address, account)| {
+                T::from_account_data(&account.data)
+                    .map(|decoded| (address, decoded))
+                    .map_err(|_| SdkError::InvalidAccountData(address))
+            })
+            .collect()
+    }
+
+    /// Owner and amount of every SPL Token account of `mint`, read once
+    /// the node has reached `min_context_slot` when one is given
+    pub async fn token_balances(
+        &self,
+        mint: &Pubkey,
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<(Pubkey, u64)>> {
+        Ok(self
+            .token_accounts(mint, min_context_slot)
+            .await?
+            .into_iter()
+            .map(|(_, owner, amount)| (owner, amount))
+            .collect())
+    }
+
+    /// Address, owner, and amount of every SPL Token account of `mint`, as
+    /// `token_balances` reads them
+    pub async fn token_accounts(
+        &self,
+        mint: &Pubkey,
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<(Pubkey, Pubkey, u64)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(TOKEN_ACCOUNT_LEN),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, mint.as_ref())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                // Just the owner and amount
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 32,
+                    length: 40,
+                }),
+                commitment: Some(self.rpc.commitment()),
+                min_context_slot,
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(self
+            .rpc
+            .get_program_accounts_with_config(&TOKEN_PROGRAM_ID, config)
+            .await?
+            .into_iter()
+            .filter_map(|(address, account)| {
+                let owner = Pubkey::try_from(account.data.get(..32)?).ok()?;
+                let amount = u64::from_le_bytes(account.data.get(32..40)?.try_into().ok()?);
+                Some((address, owner, amount))
+            })
+            .collect())
+    }
+
+    /// Amount held by the SPL Token account at `address`, or `None` if
+    /// nothing is there
+    pub async fn token_amount(&self, address: &Pubkey) -> Result<Option<u64>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())
+            .await?
+            .value;
+        match account {
+            Some(account) if account.owner == TOKEN_PROGRAM_ID => account
+                .data
+                .get(64..72)
+                .and_then(|amount| amount.try_into().ok())
+                .map(|amount| Some(u64::from_le_bytes(amount)))
+                .ok_or(SdkError::InvalidAccountData(*address)),
+            Some(_) => Err(SdkError::InvalidAccountData(*address)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn token_state(&self) -> Result<TokenState> {
+        self.fetch(&pda::token_state().0).await
+    }
+
+    pub async fn receipt(&self, wallet: &Pubkey) -> Result<Option<VerificationReceipt>> {
+        self.fetch_optional(&pda::receipt(wallet).0).await
+    }
+
+    pub async fn access_override(&self, wallet: &Pubkey) -> Result<Option<AccessOverride>> {
+        self.fetch_optional(&pda::access_override(wallet).0).await
+    }
+
+    /// Access tier `wallet` holds at `now`, as `check_access` would report
+    /// it, or `NO_ACCESS`
+    pub async fn access_tier(&self, wallet: &Pubkey, now: i64) -> Result<u8> {
+        let tier = match self.receipt(wallet).await? {
+            Some(receipt) if receipt.grants_access(now) => receipt.tier,
+            _ => NO_ACCESS,
+        };
+        Ok(match self.access_override(wallet).await?.map(|o| o.kind) {
+            Some(AccessOverrideKind::Allow) => tier.max(1),
+            Some(AccessOverrideKind::Deny) => NO_ACCESS,
+            None => tier,
+        })
+    }
+
+    /// `wallet`'s access at `now`, its receipt, and its associated token
+    /// account's balance against the current requirement
+    pub async fn wallet_status(&self, wallet: &Pubkey, now: i64) -> Result<WalletStatus> {
+        let state = self.token_state().await?;
+        let token_account = associated_token_account(wallet, &state.mint);
+        Ok(WalletStatus {
+            access_tier: self.access_tier(wallet, now).await?,
+            receipt: self.receipt(wallet).await?,
+            balance: self.token_amount(&token_account).await?.unwrap_or(0),
+            requirement: state.current_requirement,
+        })
+    }
+
+    /// Sign `instructions` into one transaction, paid by the first signer
+    pub async fn transaction(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> Result<Transaction> {
+        let payer = signers.first().map(|signer| signer.pubkey());
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            payer.as_ref(),
+            signers,
+            blockhash,
+        ))
+    }
+
+    /// Send `instructions` in one transaction and wait for it to confirm
+    pub async fn send(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> Result<Signature> {
+        let transaction = self.transaction(instructions, signers).await?;
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// Run `instructions` without sending them, for their error, logs, and
+    /// compute units
+    pub async fn simulate(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> Result<RpcSimulateTransactionResult> {
+        let transaction = self.transaction(instructions, signers).await?;
+        Ok(self.rpc.simulate_transaction(&transaction).await?.value)
+    }
+
+    /// Run `instructions` without sending them, along with the data each of
+    /// `addresses` would hold afterwards; `None` where it would not exist
+    pub async fn simulate_with_accounts(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+        addresses: &[Pubkey],
+    ) -> Result<(RpcSimulateTransactionResult, Vec<Option<Vec<u8>>>)> {
+        let transaction = self.transaction(instructions, signers).await?;
+        let config = RpcSimulateTransactionConfig {
+            commitment: Some(self.rpc.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: addresses.iter().map(Pubkey::to_string).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(&transaction, config)
+            .await?
+            .value;
+        let accounts = result
+            .accounts
+            .iter()
+            .flatten()
+            .map(|account| {
+                account
+                    .as_ref()
+                    .and_then(|account| account.decode::<Account>())
+                    .map(|account| account.data)
+            })
+            .collect();
+        Ok((result, accounts))
+    }
+
+    /// Every `T` the program emitted in the confirmed transaction
+    /// `signature`
+    pub async fn events<T: ProgramEvent>(&self, signature: &Signature) -> Result<Vec<T>> {
+        let transaction = self
+            .rpc
+            .get_transaction(signature, UiTransactionEncoding::Json)
+            .await?;
+        let logs: Option<Vec<String>> = transaction
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages.into());
+        let logs = logs.ok_or_else(|| SdkError::LogsUnavailable(signature.to_string()))?;
+        Ok(parse_events(&logs))
+    }
+}
@@ -0,0 +1,26 @@
+use solana_client::client_error::ClientError;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SdkError {
+    /// Boxed, as the client error is large enough to bloat every `Result`
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<ClientError>),
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not the expected program account")]
+    InvalidAccountData(Pubkey),
+    #[error("transaction {0} was not found or carried no logs")]
+    LogsUnavailable(String),
+    #[error("transaction {0} could not be read: {1}")]
+    InvalidTransaction(String, &'static str),
+}
+
+impl From<ClientError> for SdkError {
+    fn from(err: ClientError) -> Self {
+        SdkError::Rpc(Box::new(err))
+    }
+}
+
+pub type Result<T> = core::result::Result<T, SdkError>;
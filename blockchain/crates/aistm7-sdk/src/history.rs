@@ -0,0 +1,196 @@
+//! Walking the program's transaction history for the events it emitted.
+//! Signatures are paged newest first, as the RPC serves them, and handed
+//! back oldest first so events can be replayed in the order they happened.
+//! Failed transactions are left out: their logs can still carry event data,
+//! but nothing they did took effect.
+
+use std::str::FromStr;
+
+use aistm7_interface::ID;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    EncodedTransaction, UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiMessage,
+    UiTransactionEncoding,
+};
+
+use crate::client::Aistm7Client;
+use crate::error::{Result, SdkError};
+use crate::json::keys_to_base58;
+use crate::logs::{cpi_event_data, program_data, DecodedEvent};
+
+/// Most signatures the RPC returns in one page
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// One event the program emitted, and the transaction it came from
+#[derive(Clone, Debug)]
+pub struct EventRecord {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Position among the transaction's events; with the signature, a key
+    /// that stays the same however often the transaction is replayed
+    pub index: u32,
+    /// Event discriminator followed by the borsh-encoded event
+    pub data: Vec<u8>,
+}
+
+impl EventRecord {
+    /// Records of the events the program logged in `logs`, as a logs
+    /// subscription delivers them, which carries no block time
+    pub fn from_logs(signature: Signature, slot: u64, logs: &[String]) -> Vec<Self> {
+        program_data(logs)
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| EventRecord {
+                signature,
+                slot,
+                block_time: None,
+                index: index as u32,
+                data,
+            })
+            .collect()
+    }
+
+    /// The event, or `None` if it is one this crate does not know
+    pub fn decode(&self) -> Option<DecodedEvent> {
+        DecodedEvent::decode(&self.data)
+    }
+
+    /// The record as JSON: the decoded event with keys in base58, and its
+    /// raw bytes base64 encoded for events this crate does not know
+    pub fn to_json(&self) -> Value {
+        let decoded = self.decode();
+        let data = decoded
+            .as_ref()
+            .and_then(|decoded| serde_json::to_value(decoded).ok())
+            .map(|mut tagged| keys_to_base58(tagged["data"].take()))
+            .unwrap_or(Value::Null);
+        json!({
+            "signature": self.signature.to_string(),
+            "slot": self.slot,
+            "blockTime": self.block_time,
+            "index": self.index,
+            "event": decoded.as_ref().map(|decoded| decoded.name()),
+            "data": data,
+            "raw": STANDARD.encode(&self.data),
+        })
+    }
+}
+
+impl Aistm7Client {
+    /// Signatures of the program's successful transactions after `until`
+    /// and before `before`, oldest first. Leaving out `until` walks back to
+    /// the oldest transaction the node still has; leaving out `before`
+    /// starts from the latest.
+    pub async fn signatures_since(
+        &self,
+        until: Option<Signature>,
+        before: Option<Signature>,
+    ) -> Result<Vec<Signature>> {
+        let mut signatures = Vec::new();
+        let mut before = before;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(SIGNATURE_PAGE),
+                commitment: Some(self.rpc().commitment()),
+            };
+            let page = self
+                .rpc()
+                .get_signatures_for_address_with_config(&ID, config)
+                .await?;
+            let full = page.len() == SIGNATURE_PAGE;
+            for status in page {
+                let signature = Signature::from_str(&status.signature).map_err(|_| {
+                    SdkError::InvalidTransaction(status.signature.clone(), "malformed signature")
+                })?;
+                before = Some(signature);
+                if status.err.is_none() {
+                    signatures.push(signature);
+                }
+            }
+            if !full {
+                break;
+            }
+        }
+        signatures.reverse();
+        Ok(signatures)
+    }
+
+    /// Every event the program emitted in the confirmed transaction
+    /// `signature`: the logged ones in log order, then those emitted with
+    /// `emit_cpi!`, in the order of their self-invocations
+    pub async fn transaction_events(&self, signature: &Signature) -> Result<Vec<EventRecord>> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(self.rpc().commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let confirmed = self
+            .rpc()
+            .get_transaction_with_config(signature, config)
+            .await?;
+        let invalid = |reason| SdkError::InvalidTransaction(signature.to_string(), reason);
+        let meta = confirmed
+            .transaction
+            .meta
+            .ok_or_else(|| SdkError::LogsUnavailable(signature.to_string()))?;
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+        let logs = logs.ok_or_else(|| SdkError::LogsUnavailable(signature.to_string()))?;
+        let mut events = program_data(&logs);
+
+        // Instructions name their program by index into the static keys
+        // followed by any loaded from lookup tables
+        let mut keys = match confirmed.transaction.transaction {
+            EncodedTransaction::Json(transaction) => match transaction.message {
+                UiMessage::Raw(message) => message.account_keys,
+                UiMessage::Parsed(_) => return Err(invalid("message is not raw")),
+            },
+            _ => return Err(invalid("transaction is not JSON encoded")),
+        };
+        let loaded: Option<UiLoadedAddresses> = meta.loaded_addresses.into();
+        if let Some(loaded) = loaded {
+            keys.extend(loaded.writable);
+            keys.extend(loaded.readonly);
+        }
+        let keys = keys
+            .iter()
+            .map(|key| Pubkey::from_str(key).map_err(|_| invalid("malformed account key")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let inner: Option<Vec<UiInnerInstructions>> = meta.inner_instructions.into();
+        for instruction in inner.iter().flatten().flat_map(|inner| &inner.instructions) {
+            let UiInstruction::Compiled(instruction) = instruction else {
+                continue;
+            };
+            if keys.get(usize::from(instruction.program_id_index)) != Some(&ID) {
+                continue;
+            }
+            let data = bs58::decode(&instruction.data)
+                .into_vec()
+                .map_err(|_| invalid("malformed instruction data"))?;
+            if let Some(event) = cpi_event_data(&data) {
+                events.push(event.to_vec());
+            }
+        }
+
+        Ok(events
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| EventRecord {
+                signature: *signature,
+                slot: confirmed.slot,
+                block_time: confirmed.block_time,
+                index: index as u32,
+                data,
+            })
+            .collect())
+    }
+}
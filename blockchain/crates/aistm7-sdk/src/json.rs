@@ -0,0 +1,32 @@
+//! JSON as services and the CLI hand it on. Keys serialize as arrays of
+//! bytes; [`keys_to_base58`] turns them into the strings everything else
+//! expects.
+
+use serde_json::Value;
+
+/// Replace every array of 32 bytes, be it a key or a hash, with its base58
+/// string
+pub fn keys_to_base58(value: Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            let bytes: Option<Vec<u8>> = (items.len() == 32)
+                .then(|| {
+                    items
+                        .iter()
+                        .map(|item| item.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                        .collect()
+                })
+                .flatten();
+            match bytes {
+                Some(bytes) => Value::String(bs58::encode(bytes).into_string()),
+                None => Value::Array(items.into_iter().map(keys_to_base58).collect()),
+            }
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, keys_to_base58(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
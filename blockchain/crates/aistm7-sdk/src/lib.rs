@@ -0,0 +1,24 @@
+//! Async client for the AISTM7 token program, for services that talk to it
+//! over RPC. [`Aistm7Client`] fetches and decodes its accounts, sends
+//! instructions, and reads back the events a transaction emitted. Layouts,
+//! PDA derivation, and the typed instruction builders come from
+//! `aistm7-interface`, re-exported here; instructions without a typed
+//! builder are assembled with [`InstructionBuilder`] rather than by hand.
+//! [`history`] walks past transactions for the events they emitted.
+
+pub mod accounts;
+pub mod builder;
+pub mod client;
+pub mod error;
+pub mod history;
+pub mod json;
+pub mod logs;
+
+pub use aistm7_interface::{self as interface, events, instruction, pda, state, ID};
+
+pub use accounts::DecodedAccount;
+pub use builder::InstructionBuilder;
+pub use client::{Aistm7Client, WalletStatus};
+pub use error::{Result, SdkError};
+pub use history::EventRecord;
+pub use logs::{cpi_event_data, parse_events, program_data, DecodedEvent};
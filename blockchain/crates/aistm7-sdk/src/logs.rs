@@ -0,0 +1,205 @@
+//! Decoding the program's events out of transaction logs. Anchor logs each
+//! event as `Program data: <base64>`; only lines logged while this program
+//! is the one executing are read, so a CPI'd program's data is skipped.
+//! Events emitted with `emit_cpi!` are read from the self-invocation's
+//! instruction data instead, with [`cpi_event_data`].
+
+use aistm7_interface::events::*;
+use aistm7_interface::ID;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+
+const INVOKE: &str = " invoke [";
+const DATA_PREFIX: &str = "Program data: ";
+
+/// Raw data of every event the program logged, in order
+pub fn program_data(logs: &[String]) -> Vec<Vec<u8>> {
+    let program = ID.to_string();
+    let mut stack: Vec<String> = Vec::new();
+    let mut data = Vec::new();
+    for line in logs {
+        if let Some(rest) = line.strip_prefix("Program ") {
+            if let Some((invoked, _)) = rest.split_once(INVOKE) {
+                stack.push(invoked.to_string());
+                continue;
+            }
+            let returned = rest
+                .strip_suffix(" success")
+                .or_else(|| rest.split_once(" failed: ").map(|(id, _)| id));
+            if returned.is_some_and(|id| !id.contains(' ')) {
+                stack.pop();
+                continue;
+            }
+        }
+        if let Some(encoded) = line.strip_prefix(DATA_PREFIX) {
+            if stack.last() == Some(&program) {
+                if let Ok(bytes) = STANDARD.decode(encoded) {
+                    data.push(bytes);
+                }
+            }
+        }
+    }
+    data
+}
+
+/// Every `T` the program logged, in order; other events are skipped
+pub fn parse_events<T: ProgramEvent>(logs: &[String]) -> Vec<T> {
+    program_data(logs)
+        .iter()
+        .filter_map(|data| T::from_event_data(data))
+        .collect()
+}
+
+/// Event data carried by an `emit_cpi!` self-invocation's instruction
+/// data, or `None` if it is some other instruction
+pub fn cpi_event_data(instruction_data: &[u8]) -> Option<&[u8]> {
+    instruction_data.strip_prefix(&EVENT_IX_TAG[..])
+}
+
+macro_rules! decoded_events {
+    ($($name:ident),* $(,)?) => {
+        /// A program event, decoded as whichever event its discriminator
+        /// names
+        #[derive(Clone, Debug, Serialize)]
+        #[serde(tag = "type", content = "data")]
+        pub enum DecodedEvent {
+            $($name($name),)*
+        }
+
+        impl DecodedEvent {
+            /// Decode `data`, or `None` if its discriminator names no known
+            /// event or the rest does not match it
+            pub fn decode(data: &[u8]) -> Option<Self> {
+                $(
+                    if let Some(event) = $name::from_event_data(data) {
+                        return Some(DecodedEvent::$name(event));
+                    }
+                )*
+                None
+            }
+
+            /// Anchor names of every event this enum decodes
+            pub const NAMES: &'static [&'static str] = &[$($name::NAME,)*];
+
+            /// Anchor event name
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(DecodedEvent::$name(_) => $name::NAME,)*
+                }
+            }
+        }
+    };
+}
+
+decoded_events!(
+    BalanceVerified,
+    AccessConsumed,
+    MemberSynced,
+    BalanceRequirementUpdated,
+    CircuitBreakerTripped,
+    GracePeriodStarted,
+    GracePeriodExpired,
+    Staked,
+    RewardsCompounded,
+    UnstakeRequested,
+    Unstaked,
+    EarlyExited,
+    RewardsClaimed,
+    RevenueClaimed,
+    AirdropClaimed,
+    VestingClaimed,
+);
+
+#[cfg(test)]
+mod tests {
+    use aistm7_interface::discriminator;
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    const OTHER: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+    fn event_data(event: &BalanceRequirementUpdated) -> Vec<u8> {
+        let mut data = discriminator("event", BalanceRequirementUpdated::NAME).to_vec();
+        BorshSerialize::serialize(event, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn reads_data_logged_by_the_program() {
+        let lines = vec![
+            format!("Program {ID} invoke [1]"),
+            format!("{DATA_PREFIX}AQID"),
+            format!("Program {ID} success"),
+        ];
+        assert_eq!(program_data(&lines), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn skips_data_logged_by_a_program_it_invokes() {
+        let lines = vec![
+            format!("Program {ID} invoke [1]"),
+            format!("Program {OTHER} invoke [2]"),
+            format!("{DATA_PREFIX}BAUG"),
+            format!("Program {OTHER} success"),
+            format!("{DATA_PREFIX}AQID"),
+            format!("Program {ID} success"),
+        ];
+        assert_eq!(program_data(&lines), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn pops_the_stack_when_an_invoked_program_fails() {
+        let lines = vec![
+            format!("Program {OTHER} invoke [1]"),
+            format!("Program {ID} invoke [2]"),
+            format!("{DATA_PREFIX}AQID"),
+            format!("Program {ID} failed: custom program error: 0x1"),
+            format!("{DATA_PREFIX}BAUG"),
+            format!("Program {OTHER} success"),
+        ];
+        assert_eq!(program_data(&lines), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn ignores_log_lines_that_only_look_like_returns() {
+        let lines = vec![
+            format!("Program {ID} invoke [1]"),
+            "Program log: transfer success".to_string(),
+            format!("{DATA_PREFIX}AQID"),
+            format!("{DATA_PREFIX}not base64!"),
+            format!("Program {ID} success"),
+        ];
+        assert_eq!(program_data(&lines), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn parses_only_the_requested_event() {
+        let event = BalanceRequirementUpdated {
+            new_requirement: 1_000,
+            price: 15_000,
+            update_threshold_bps: 500,
+            timestamp: 1_674_392_400,
+        };
+        let lines = vec![
+            format!("Program {ID} invoke [1]"),
+            format!("{DATA_PREFIX}{}", STANDARD.encode(event_data(&event))),
+            format!("{DATA_PREFIX}AQIDBAUGBwgJ"),
+            format!("Program {ID} success"),
+        ];
+        assert_eq!(parse_events::<BalanceRequirementUpdated>(&lines), vec![event]);
+        assert!(parse_events::<GracePeriodStarted>(&lines).is_empty());
+
+        let decoded = DecodedEvent::decode(&event_data(&event)).unwrap();
+        assert_eq!(decoded.name(), "BalanceRequirementUpdated");
+    }
+
+    #[test]
+    fn reads_event_data_from_a_self_invocation() {
+        let mut data = EVENT_IX_TAG.to_vec();
+        data.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(cpi_event_data(&data), Some(&[1, 2, 3][..]));
+        assert_eq!(cpi_event_data(&[1, 2, 3]), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Registering webhooks. Every route takes the service's token as a bearer
+//! token; a webhook's secret is returned once, when it is registered, and
+//! never listed afterwards.
+
+use std::sync::Arc;
+
+use aistm7_sdk::DecodedEvent;
+use axum::extract::{Path, State};
+use axum::headers::authorization::Bearer;
+use axum::headers::Authorization;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Json, TypedHeader};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::store::{Store, Webhook};
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub store: Arc<Store>,
+    pub token: Arc<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Registration {
+    url: String,
+    events: Vec<String>,
+    /// Generated when left out
+    secret: Option<String>,
+}
+
+/// A webhook as listed, without its secret
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Listed {
+    id: String,
+    url: String,
+    events: Vec<String>,
+    created_at: i64,
+}
+
+impl From<Webhook> for Listed {
+    fn from(webhook: Webhook) -> Self {
+        Listed {
+            id: webhook.id,
+            url: webhook.url,
+            events: webhook.events,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+pub struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        eprintln!("Saving webhooks failed: {err:#}");
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, "saving webhooks failed".to_string())
+    }
+}
+
+fn authorize(state: &ApiState, bearer: &Bearer) -> Result<(), ApiError> {
+    // Compared in full whatever the first difference, so timing does not
+    // reveal how much of a guess was right
+    let expected = state.token.as_bytes();
+    let given = bearer.token().as_bytes();
+    let differs = expected.len() != given.len()
+        || expected
+            .iter()
+            .zip(given)
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            != 0;
+    if differs {
+        return Err(ApiError(StatusCode::UNAUTHORIZED, "invalid token".to_string()));
+    }
+    Ok(())
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+pub async fn register(
+    State(state): State<ApiState>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    Json(registration): Json<Registration>,
+) -> Result<(StatusCode, Json<Webhook>), ApiError> {
+    authorize(&state, &bearer)?;
+    let bad_request = |message: String| ApiError(StatusCode::BAD_REQUEST, message);
+    let url = reqwest::Url::parse(&registration.url)
+        .map_err(|err| bad_request(format!("invalid url: {err}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(bad_request("url must be http or https".to_string()));
+    }
+    if registration.events.is_empty() {
+        return Err(bad_request("events must name at least one event".to_string()));
+    }
+    if let Some(unknown) = registration
+        .events
+        .iter()
+        .find(|event| !DecodedEvent::NAMES.contains(&event.as_str()))
+    {
+        return Err(bad_request(format!("unknown event {unknown:?}")));
+    }
+    let mut events = registration.events;
+    events.sort();
+    events.dedup();
+
+    let webhook = Webhook {
+        id: random_hex(8),
+        url: url.to_string(),
+        events,
+        secret: registration.secret.unwrap_or_else(|| random_hex(32)),
+        created_at: crate::unix_now(),
+    };
+    state.store.insert(webhook.clone()).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+pub async fn list(
+    State(state): State<ApiState>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<Vec<Listed>>, ApiError> {
+    authorize(&state, &bearer)?;
+    let webhooks = state.store.list().await;
+    Ok(Json(webhooks.into_iter().map(Listed::from).collect()))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    Path(id): Path<String>,
+) -> Result<Json<Listed>, ApiError> {
+    authorize(&state, &bearer)?;
+    match state.store.get(&id).await {
+        Some(webhook) => Ok(Json(webhook.into())),
+        None => Err(ApiError(StatusCode::NOT_FOUND, "no such webhook".to_string())),
+    }
+}
+
+pub async fn remove(
+    State(state): State<ApiState>,
+    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    authorize(&state, &bearer)?;
+    if state.store.remove(&id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError(StatusCode::NOT_FOUND, "no such webhook".to_string()))
+    }
+}
@@ -0,0 +1,214 @@
+//! Delivering events to the webhooks registered for them. Each delivery is
+//! a POST of the event as `EventRecord::to_json` writes it, plus an `id` of
+//! `<signature>:<index>` that stays the same across retries and replays,
+//! so receivers deduplicate on it. Deliveries are independent and can
+//! arrive out of order; `slot` and `index` give the order they happened in.
+//!
+//! The body is signed with the webhook's secret, and the signature sent as
+//!
+//! ```text
+//! Aistm7-Signature: t=<unix time>,v1=<hex HMAC-SHA256 of "<unix time>.<body>">
+//! ```
+//!
+//! Receivers recompute it over the raw body, and reject a `t` too far from
+//! their own clock so a captured delivery cannot be replayed later.
+//!
+//! A delivery is retried on connection failures, timeouts, 408, 429, and
+//! 5xx, waiting twice as long each time, and given up on after
+//! `max_attempts` or any other response; one to a webhook removed
+//! meanwhile is dropped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::EventRecord;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use reqwest::StatusCode;
+use sha2::Sha256;
+use tokio::sync::Semaphore;
+
+use crate::store::{Store, Webhook};
+
+pub struct Retry {
+    pub max_attempts: u32,
+    pub first_wait: Duration,
+    pub max_wait: Duration,
+}
+
+pub struct Deliverer {
+    pub http: reqwest::Client,
+    pub store: Arc<Store>,
+    pub retry: Retry,
+    /// Bounds the requests in flight across every webhook
+    pub permits: Semaphore,
+}
+
+/// Why an attempt failed, and whether another might succeed
+#[derive(Debug, PartialEq)]
+enum Failure {
+    Retry(String),
+    GiveUp(String),
+}
+
+impl Deliverer {
+    /// Start delivering `record` to every webhook registered for its event
+    pub async fn dispatch(self: &Arc<Self>, record: EventRecord) {
+        let Some(event) = record.decode() else {
+            return;
+        };
+        let webhooks = self.store.subscribed(event.name()).await;
+        if webhooks.is_empty() {
+            return;
+        }
+        let id = format!("{}:{}", record.signature, record.index);
+        let mut body = record.to_json();
+        body["id"] = id.clone().into();
+        let body = Arc::new(body.to_string());
+        for webhook in webhooks {
+            let deliverer = self.clone();
+            let (id, body) = (id.clone(), body.clone());
+            let event = event.name();
+            tokio::spawn(async move { deliverer.deliver(webhook, event, id, body).await });
+        }
+    }
+
+    async fn deliver(&self, webhook: Webhook, event: &str, id: String, body: Arc<String>) {
+        let mut wait = self.retry.first_wait;
+        for attempt in 1..=self.retry.max_attempts {
+            if attempt > 1 {
+                // Up to a quarter more, so deliveries that failed together
+                // do not all retry at once
+                let jitter = rand::thread_rng().gen_range(0..=wait.as_millis() as u64 / 4);
+                tokio::time::sleep(wait + Duration::from_millis(jitter)).await;
+                wait = (wait * 2).min(self.retry.max_wait);
+                if self.store.get(&webhook.id).await.is_none() {
+                    return;
+                }
+            }
+            let failure = {
+                let _permit = self.permits.acquire().await;
+                match self.send(&webhook, event, &id, &body).await {
+                    Ok(()) => return,
+                    Err(failure) => failure,
+                }
+            };
+            match failure {
+                Failure::Retry(reason) => eprintln!(
+                    "Delivery {id} to webhook {} failed, attempt {attempt}: {reason}",
+                    webhook.id
+                ),
+                Failure::GiveUp(reason) => {
+                    eprintln!("Delivery {id} to webhook {} rejected: {reason}", webhook.id);
+                    return;
+                }
+            }
+        }
+        eprintln!(
+            "Delivery {id} to webhook {} given up after {} attempts",
+            webhook.id, self.retry.max_attempts
+        );
+    }
+
+    async fn send(
+        &self,
+        webhook: &Webhook,
+        event: &str,
+        id: &str,
+        body: &str,
+    ) -> Result<(), Failure> {
+        let timestamp = crate::unix_now();
+        let response = self
+            .http
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("Aistm7-Event", event)
+            .header("Aistm7-Delivery", id)
+            .header(
+                "Aistm7-Signature",
+                format!("t={timestamp},v1={}", sign(&webhook.secret, timestamp, body)),
+            )
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|err| Failure::Retry(err.to_string()))?;
+        classify(response.status())
+    }
+}
+
+/// Whether a response means the delivery landed, may land on a retry, or
+/// never will
+fn classify(status: StatusCode) -> Result<(), Failure> {
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+    {
+        Err(Failure::Retry(status.to_string()))
+    } else {
+        Err(Failure::GiveUp(status.to_string()))
+    }
+}
+
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_the_timestamp_and_body() {
+        // What the backend's verifyProgramEventSignature recomputes
+        assert_eq!(
+            sign("test-secret", 1_674_392_400, r#"{"id":"sig:0"}"#),
+            "ed23f4c65102ca3990a2bfe48defe4558c31d1cf4510fabfd91bd79f8b326e03"
+        );
+    }
+
+    #[test]
+    fn signatures_depend_on_every_input() {
+        let signature = sign("test-secret", 1_674_392_400, "{}");
+        assert_ne!(sign("other-secret", 1_674_392_400, "{}"), signature);
+        assert_ne!(sign("test-secret", 1_674_392_401, "{}"), signature);
+        assert_ne!(sign("test-secret", 1_674_392_400, "[]"), signature);
+    }
+
+    #[test]
+    fn accepts_any_success() {
+        assert_eq!(classify(StatusCode::OK), Ok(()));
+        assert_eq!(classify(StatusCode::NO_CONTENT), Ok(()));
+    }
+
+    #[test]
+    fn retries_timeouts_rate_limits_and_server_errors() {
+        for status in [
+            StatusCode::REQUEST_TIMEOUT,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(matches!(classify(status), Err(Failure::Retry(_))), "{status}");
+        }
+    }
+
+    #[test]
+    fn gives_up_on_other_responses() {
+        for status in [
+            StatusCode::MOVED_PERMANENTLY,
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::NOT_FOUND,
+            StatusCode::GONE,
+        ] {
+            assert!(matches!(classify(status), Err(Failure::GiveUp(_))), "{status}");
+        }
+    }
+}
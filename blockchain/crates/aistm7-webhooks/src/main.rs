@@ -0,0 +1,120 @@
+//! `aistm7-webhooks`, delivering the program's events to registered URLs,
+//! so the notification stack subscribes here instead of parsing logs
+//! itself. Webhooks are registered at `/webhooks` as `api` describes, and
+//! delivered as `deliver` describes; `/health` answers once the service is
+//! up.
+
+mod api;
+mod deliver;
+mod source;
+mod store;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aistm7_sdk::Aistm7Client;
+use anyhow::Result;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::api::ApiState;
+use crate::deliver::{Deliverer, Retry};
+use crate::source::Source;
+use crate::store::Store;
+
+#[derive(Parser)]
+#[command(name = "aistm7-webhooks", version, about = "Deliver AISTM7 program events to webhooks")]
+struct Opts {
+    #[arg(long, env = "AISTM7_WEBHOOKS_LISTEN", default_value = "0.0.0.0:8790")]
+    listen: SocketAddr,
+    /// RPC endpoint, for catching up after the log subscription drops
+    #[arg(long, env = "AISTM7_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Websocket endpoint the program's logs are followed on
+    #[arg(long, env = "AISTM7_WS_URL", default_value = "ws://127.0.0.1:8900")]
+    ws_url: String,
+    /// Bearer token the registration API requires
+    #[arg(long, env = "AISTM7_WEBHOOKS_TOKEN", hide_env_values = true)]
+    token: String,
+    /// File the registered webhooks are kept in
+    #[arg(long, env = "AISTM7_WEBHOOKS_STORE", default_value = "webhooks.json")]
+    store: PathBuf,
+    /// Attempts at each delivery before it is given up on
+    #[arg(long, default_value_t = 8)]
+    max_attempts: u32,
+    /// Seconds before the first retry; each later one waits twice as long
+    #[arg(long, default_value_t = 5)]
+    first_retry_secs: u64,
+    /// Longest wait between retries, in seconds
+    #[arg(long, default_value_t = 900)]
+    max_retry_secs: u64,
+    /// Seconds a webhook has to answer a delivery
+    #[arg(long, default_value_t = 10)]
+    timeout_secs: u64,
+    /// Most deliveries in flight at once
+    #[arg(long, default_value_t = 32)]
+    concurrency: usize,
+    /// Wait before resubscribing after the log subscription drops
+    #[arg(long, default_value_t = 5)]
+    retry_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let commitment = CommitmentConfig::confirmed();
+    let store = Arc::new(Store::load(opts.store).await?);
+    let deliverer = Arc::new(Deliverer {
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(opts.timeout_secs))
+            .user_agent(concat!("aistm7-webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()?,
+        store: store.clone(),
+        retry: Retry {
+            max_attempts: opts.max_attempts,
+            first_wait: Duration::from_secs(opts.first_retry_secs),
+            max_wait: Duration::from_secs(opts.max_retry_secs),
+        },
+        permits: Semaphore::new(opts.concurrency),
+    });
+
+    let (records, mut received) = mpsc::channel(1024);
+    let source = Source {
+        client: Arc::new(Aistm7Client::with_url(opts.url, commitment)),
+        ws_url: opts.ws_url,
+        commitment,
+        retry: Duration::from_secs(opts.retry_secs),
+    };
+    tokio::spawn(source.follow(records));
+    tokio::spawn(async move {
+        while let Some(record) = received.recv().await {
+            deliverer.dispatch(record).await;
+        }
+    });
+
+    let state = ApiState {
+        store,
+        token: Arc::new(opts.token),
+    };
+    let app = Router::new()
+        .route("/webhooks", get(api::list).post(api::register))
+        .route("/webhooks/:id", get(api::get).delete(api::remove))
+        .with_state(state)
+        .route("/health", get(|| async { "ok" }));
+    eprintln!("Listening on {}", opts.listen);
+    axum::Server::bind(&opts.listen)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
@@ -0,0 +1,147 @@
+//! Following the program's logs for the events to deliver. When the
+//! subscription drops and comes back, the transactions in between are read
+//! from the node's history before live events resume, so an outage delays
+//! deliveries rather than losing them; a restart of the service starts from
+//! the present.
+
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aistm7_sdk::{Aistm7Client, EventRecord, ID};
+use anyhow::{bail, Result};
+use futures_util::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use tokio::sync::mpsc;
+
+/// Transactions remembered, so one both caught up on and then delivered
+/// live is only handled once
+const SEEN: usize = 10_000;
+
+#[derive(Default)]
+struct Seen {
+    order: VecDeque<Signature>,
+    signatures: HashSet<Signature>,
+}
+
+impl Seen {
+    /// Whether `signature` is new, remembering it
+    fn insert(&mut self, signature: Signature) -> bool {
+        if !self.signatures.insert(signature) {
+            return false;
+        }
+        self.order.push_back(signature);
+        if self.order.len() > SEEN {
+            if let Some(oldest) = self.order.pop_front() {
+                self.signatures.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+pub struct Source {
+    pub client: Arc<Aistm7Client>,
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+    pub retry: Duration,
+}
+
+impl Source {
+    pub async fn follow(self, records: mpsc::Sender<EventRecord>) {
+        let mut last: Option<Signature> = None;
+        let mut seen = Seen::default();
+        loop {
+            if let Err(err) = self.subscribe(&records, &mut last, &mut seen).await {
+                eprintln!(
+                    "Log subscription stopped: {err:#}; retrying in {}s",
+                    self.retry.as_secs()
+                );
+            }
+            tokio::time::sleep(self.retry).await;
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        records: &mpsc::Sender<EventRecord>,
+        last: &mut Option<Signature>,
+        seen: &mut Seen,
+    ) -> Result<()> {
+        let pubsub = PubsubClient::new(&self.ws_url).await?;
+        let (mut logs, _unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(self.commitment),
+                },
+            )
+            .await?;
+
+        // Subscribed first, so nothing lands between the history read and
+        // the live stream; what is in both is skipped the second time
+        match *last {
+            Some(until) => {
+                for signature in self.client.signatures_since(Some(until), None).await? {
+                    if seen.insert(signature) {
+                        for record in self.client.transaction_events(&signature).await? {
+                            send(records, record).await?;
+                        }
+                    }
+                    *last = Some(signature);
+                }
+            }
+            // The present, for a drop before any event arrives to catch up
+            // from
+            None => *last = self.newest_signature().await?,
+        }
+
+        while let Some(update) = logs.next().await {
+            let notification = update.value;
+            if notification.err.is_some() {
+                continue;
+            }
+            let signature = Signature::from_str(&notification.signature)?;
+            if !seen.insert(signature) {
+                continue;
+            }
+            let slot = update.context.slot;
+            for record in EventRecord::from_logs(signature, slot, &notification.logs) {
+                send(records, record).await?;
+            }
+            *last = Some(signature);
+        }
+        bail!("subscription closed")
+    }
+
+    async fn newest_signature(&self) -> Result<Option<Signature>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until: None,
+            limit: Some(1),
+            commitment: Some(self.commitment),
+        };
+        let newest = self
+            .client
+            .rpc()
+            .get_signatures_for_address_with_config(&ID, config)
+            .await?;
+        newest
+            .first()
+            .map(|status| Signature::from_str(&status.signature))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+async fn send(records: &mpsc::Sender<EventRecord>, record: EventRecord) -> Result<()> {
+    if records.send(record).await.is_err() {
+        bail!("dispatcher stopped");
+    }
+    Ok(())
+}
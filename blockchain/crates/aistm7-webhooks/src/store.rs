@@ -0,0 +1,94 @@
+//! Registered webhooks, kept in a JSON file so they survive restarts. The
+//! file is rewritten whole on every change, through a temporary file so a
+//! crash never leaves it half written; one instance of the service owns it.
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Anchor names of the events delivered
+    pub events: Vec<String>,
+    /// Key the deliveries' HMAC is computed with
+    pub secret: String,
+    pub created_at: i64,
+}
+
+pub struct Store {
+    path: PathBuf,
+    webhooks: RwLock<BTreeMap<String, Webhook>>,
+}
+
+impl Store {
+    /// The webhooks saved at `path`, or none if nothing is there yet
+    pub async fn load(path: PathBuf) -> Result<Self> {
+        let webhooks = match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+                let list: Vec<Webhook> = serde_json::from_slice(&bytes)
+                    .with_context(|| format!("reading webhooks from {}", path.display()))?;
+                list.into_iter().map(|webhook| (webhook.id.clone(), webhook)).collect()
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err).context(format!("reading {}", path.display())),
+        };
+        Ok(Store {
+            path,
+            webhooks: RwLock::new(webhooks),
+        })
+    }
+
+    pub async fn list(&self) -> Vec<Webhook> {
+        self.webhooks.read().await.values().cloned().collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Webhook> {
+        self.webhooks.read().await.get(id).cloned()
+    }
+
+    /// Webhooks registered for the event named `event`
+    pub async fn subscribed(&self, event: &str) -> Vec<Webhook> {
+        self.webhooks
+            .read()
+            .await
+            .values()
+            .filter(|webhook| webhook.events.iter().any(|name| name == event))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn insert(&self, webhook: Webhook) -> Result<()> {
+        let mut webhooks = self.webhooks.write().await;
+        webhooks.insert(webhook.id.clone(), webhook);
+        self.save(&webhooks).await
+    }
+
+    /// Remove the webhook `id`, returning whether there was one
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        let mut webhooks = self.webhooks.write().await;
+        if webhooks.remove(id).is_none() {
+            return Ok(false);
+        }
+        self.save(&webhooks).await?;
+        Ok(true)
+    }
+
+    /// Written under the write lock, so saves never interleave
+    async fn save(&self, webhooks: &BTreeMap<String, Webhook>) -> Result<()> {
+        let list: Vec<&Webhook> = webhooks.values().collect();
+        let temporary = self.path.with_extension("tmp");
+        tokio::fs::write(&temporary, serde_json::to_vec_pretty(&list)?)
+            .await
+            .with_context(|| format!("writing {}", temporary.display()))?;
+        tokio::fs::rename(&temporary, &self.path)
+            .await
+            .with_context(|| format!("replacing {}", self.path.display()))
+    }
+}
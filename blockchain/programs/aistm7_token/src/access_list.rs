@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessOverrideKind {
+    /// Verified at the first tier or better whatever the balance
+    Allow,
+    /// Never verified, with no grace period
+    Deny,
+}
+
+/// Compliance override for `wallet`, kept at `[b"access_override", wallet]`
+/// and consulted by every verification path before balances are counted.
+#[account]
+pub struct AccessOverride {
+    pub wallet: Pubkey,
+    pub kind: AccessOverrideKind,
+    /// Off-chain reference for why the override was set (case id, hash)
+    pub reason: [u8; 32],
+    pub set_by: Pubkey,
+    pub set_at: i64,
+}
+
+impl AccessOverride {
+    pub const LEN: usize = 32 + 1 + 32 + 32 + 8;
+}
+
+/// The override held at `info`, which verification always passes at the
+/// wallet's override address; nothing is there unless one was set.
+pub fn read_access_override(info: &AccountInfo) -> Result<Option<AccessOverrideKind>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Account::<AccessOverride>::try_from(info)?.kind))
+}
@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+
+use crate::{ErrorCode, TokenState};
+
+/// Number of admin actions kept in the log ring buffer
+pub const ADMIN_LOG_LEN: usize = 48;
+
+/// Bytes available for each recorded old and new value
+pub const LOG_VALUE_LEN: usize = 64;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    UpdateParameters,
+    SetPriceFeed,
+    SetFallbackPriceFeed,
+    SetMedianFeeds,
+    SetPullFeedId,
+    SetTwapWindow,
+    SetCircuitBreaker,
+    ResetCircuitBreaker,
+    SetKeeperConfig,
+    SetTimelockDelay,
+    QueueChange,
+    ExecuteChange,
+    CancelChange,
+    Pause,
+    Unpause,
+    SetPendingAuthority,
+    AcceptAuthority,
+    GrantRole,
+    RevokeRole,
+    TransferMintAuthority,
+    MintTokens,
+    FinalizeSupply,
+    InitializeEmissionSchedule,
+    BuybackAndBurn,
+    SetTreasuryLimit,
+    WithdrawTreasury,
+    SetFeeConfig,
+    SetFeesEnabled,
+    CreateVesting,
+    RevokeVesting,
+    CreateDistribution,
+    ClawbackDistribution,
+    SetLockTiers,
+    SetStakingConfig,
+    PublishRevenueRoot,
+    SetReferralConfig,
+    SetPayPerUseConfig,
+    SetReceiptValidity,
+    SetAccessTiers,
+    SetGracePeriod,
+    SetMinHoldingPeriod,
+    SetGatedMint,
+    SetLpPool,
+    SetAccessOverride,
+    ClearAccessOverride,
+    BeginMintMigration,
+    SetTransferFee,
+    SetMaxWallet,
+    SetMaxWalletExemption,
+    SetInterestRate,
+    CreateMetadata,
+    UpdateMetadata,
+    ProposeClawback,
+    ExecuteClawback,
+    CancelClawback,
+    MintCompressed,
+    SetBridgeLimits,
+    InitializeGovernance,
+    CreateProposal,
+    RecordSnapshot,
+    ExecuteProposal,
+    AppointGuardians,
+    OverridePrice,
+    ConfigureSale,
+    FinalizeSale,
+    StartAuction,
+    ConfigurePresale,
+    SetStreamRate,
+    SetSubscriptionPlan,
+    CreateBounty,
+    ConfigureTreasurySwap,
+    TreasurySwap,
+    SetLiquidityLimits,
+    OpenLiquidityPosition,
+    IncreaseLiquidity,
+    DecreaseLiquidity,
+    CollectPositionFees,
+    CloseLiquidityPosition,
+    SetDcaSchedule,
+    SetInsuranceShare,
+    DeclareIncident,
+    DrawInsurance,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdminLogEntry {
+    pub actor: Pubkey,
+    pub action: AdminAction,
+    pub old_value: [u8; LOG_VALUE_LEN],
+    pub new_value: [u8; LOG_VALUE_LEN],
+    pub timestamp: i64,
+}
+
+impl AdminLogEntry {
+    pub const LEN: usize = 32 + 1 + LOG_VALUE_LEN + LOG_VALUE_LEN + 8;
+}
+
+/// Ring buffer of the most recent admin actions. `running_hash` chains
+/// every entry ever recorded, including those since overwritten, so a copy
+/// of the log (or the `AdminActionRecorded` events) can be checked for gaps
+/// or edits.
+#[account]
+pub struct AdminLog {
+    /// Index the next entry will be written to
+    pub head: u16,
+    pub count: u16,
+    /// Total entries ever recorded
+    pub sequence: u64,
+    pub running_hash: [u8; 32],
+    pub entries: [AdminLogEntry; ADMIN_LOG_LEN],
+}
+
+impl AdminLog {
+    pub const LEN: usize = 2 + 2 + 8 + 32 + AdminLogEntry::LEN * ADMIN_LOG_LEN;
+
+    pub fn record(&mut self, entry: AdminLogEntry) -> Result<()> {
+        self.running_hash = hashv(&[&self.running_hash, &serialize(&entry)?]).to_bytes();
+        self.entries[self.head as usize] = entry;
+        self.head = ((self.head as usize + 1) % ADMIN_LOG_LEN) as u16;
+        self.count = std::cmp::min(self.count as usize + 1, ADMIN_LOG_LEN) as u16;
+        self.sequence = self.sequence.saturating_add(1);
+        Ok(())
+    }
+
+    /// Recorded entries from oldest to newest
+    pub fn entries(&self) -> impl Iterator<Item = &AdminLogEntry> + '_ {
+        let start = (self.head as usize + ADMIN_LOG_LEN - self.count as usize) % ADMIN_LOG_LEN;
+        (0..self.count as usize).map(move |i| &self.entries[(start + i) % ADMIN_LOG_LEN])
+    }
+}
+
+/// Encode a value for the log: its borsh encoding, zero padded, when it
+/// fits in `LOG_VALUE_LEN` bytes, otherwise the SHA-256 of that encoding.
+pub fn log_value<T: AnchorSerialize>(value: &T) -> Result<[u8; LOG_VALUE_LEN]> {
+    let bytes = serialize(value)?;
+    let bytes = if bytes.len() > LOG_VALUE_LEN {
+        hash(&bytes).to_bytes().to_vec()
+    } else {
+        bytes
+    };
+    let mut encoded = [0u8; LOG_VALUE_LEN];
+    encoded[..bytes.len()].copy_from_slice(&bytes);
+    Ok(encoded)
+}
+
+fn serialize<T: AnchorSerialize>(value: &T) -> Result<Vec<u8>> {
+    value
+        .try_to_vec()
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotSerialize))
+}
+
+/// Emit `AdminActionRecorded` and append it to the admin log, which must
+/// be passed once it has been initialized.
+pub fn record_admin_action<Old: AnchorSerialize, New: AnchorSerialize>(
+    state: &TokenState,
+    admin_log: &mut Option<Box<Account<'_, AdminLog>>>,
+    actor: Pubkey,
+    action: AdminAction,
+    old_value: &Old,
+    new_value: &New,
+) -> Result<()> {
+    let entry = AdminLogEntry {
+        actor,
+        action,
+        old_value: log_value(old_value)?,
+        new_value: log_value(new_value)?,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    match admin_log {
+        Some(admin_log) => admin_log.record(entry)?,
+        None => require!(!state.admin_log_enabled, ErrorCode::AdminLogRequired),
+    }
+
+    emit!(AdminActionRecorded {
+        entry,
+        sequence: admin_log.as_ref().map_or(0, |log| log.sequence),
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct AdminActionRecorded {
+    pub entry: AdminLogEntry,
+    /// Position in the admin log, or zero when no log is kept
+    pub sequence: u64,
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+
+use crate::ErrorCode;
+
+/// Prefix of every attestation message, so an attestor signature over
+/// anything else can never be replayed as one
+pub const ATTESTATION_DOMAIN: &[u8] = b"AISTM7 attestation v1";
+
+/// How long after signing an attestation can still be submitted
+pub const MAX_ATTESTATION_AGE_SECS: i64 = 3_600;
+
+/// Size of the offsets block that follows the ed25519 instruction header
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+
+/// Offsets pointing back into the ed25519 instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// "`wallet` met `requirement` at `tier` at `attested_at`", as signed off
+/// chain by an attestor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    pub wallet: Pubkey,
+    pub tier: u8,
+    pub requirement: u64,
+    pub attested_at: i64,
+}
+
+impl Attestation {
+    /// The exact bytes the attestor signs
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = ATTESTATION_DOMAIN.to_vec();
+        message.extend_from_slice(self.wallet.as_ref());
+        message.push(self.tier);
+        message.extend_from_slice(&self.requirement.to_le_bytes());
+        message.extend_from_slice(&self.attested_at.to_le_bytes());
+        message
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| error!(ErrorCode::InvalidAttestation))
+}
+
+fn slice(data: &[u8], offset: u16, len: usize) -> Result<&[u8]> {
+    data.get(offset as usize..offset as usize + len)
+        .ok_or_else(|| error!(ErrorCode::InvalidAttestation))
+}
+
+/// Signer and message of an ed25519 program instruction carrying exactly
+/// one signature. The runtime has already checked the signature by the time
+/// a later instruction can see it; this only reads where it points.
+pub fn ed25519_signed_message(instruction: &Instruction) -> Result<(Pubkey, Vec<u8>)> {
+    require_keys_eq!(instruction.program_id, ed25519_program::ID, ErrorCode::InvalidAttestation);
+    let data = &instruction.data;
+    require!(
+        data.len() >= 2 + SIGNATURE_OFFSETS_LEN && data[0] == 1,
+        ErrorCode::InvalidAttestation
+    );
+
+    let public_key_offset = read_u16(data, 6)?;
+    let message_offset = read_u16(data, 10)?;
+    let message_len = read_u16(data, 12)?;
+    for index_offset in [4, 8, 14] {
+        require!(
+            read_u16(data, index_offset)? == CURRENT_INSTRUCTION,
+            ErrorCode::InvalidAttestation
+        );
+    }
+
+    let public_key = Pubkey::try_from(slice(data, public_key_offset, 32)?)
+        .map_err(|_| error!(ErrorCode::InvalidAttestation))?;
+    let message = slice(data, message_offset, message_len as usize)?.to_vec();
+    Ok((public_key, message))
+}
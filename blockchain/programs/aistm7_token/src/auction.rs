@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Descending-price launch of one tranche of newly minted tokens, kept at
+/// `[b"auction", tranche]`. The price falls linearly from `start_price` to
+/// `floor_price` over `duration_secs`; prices are in lamports per whole
+/// token. Bidders commit SOL, held on this account, at the current price.
+/// The auction clears once the commitments would buy the whole supply at
+/// the current price, or when time runs out, and every bidder then pays
+/// the same clearing price.
+#[account]
+pub struct Auction {
+    pub tranche: u64,
+    /// Base units offered
+    pub supply: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start_time: i64,
+    pub duration_secs: i64,
+    /// Decimals of the token sold, which make up a whole token
+    pub token_decimals: u8,
+    /// Lamports committed by all bids
+    pub committed: u64,
+    /// Set when a bid fills the supply; zero until then
+    pub clearing_price: u64,
+    /// Base units minted and lamports paid out by settled bids
+    pub settled_tokens: u64,
+    pub proceeds: u64,
+}
+
+impl Auction {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8;
+
+    pub fn end_time(&self) -> i64 {
+        self.start_time.saturating_add(self.duration_secs)
+    }
+
+    /// Price at `now` along the descending schedule
+    pub fn current_price(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start_time).clamp(0, self.duration_secs);
+        let drop = (self.start_price - self.floor_price) as u128 * elapsed as u128
+            / self.duration_secs as u128;
+        self.start_price - drop as u64
+    }
+
+    /// Price at which the commitments buy exactly the supply
+    fn implied_price(&self) -> u64 {
+        u64::try_from(self.committed as u128 * self.unit() / self.supply as u128)
+            .unwrap_or(u64::MAX)
+    }
+
+    /// What every bidder pays, once the auction has cleared or ended.
+    /// Between bids the falling price can reach the commitments' implied
+    /// price, which then clears the auction without a filling bid.
+    pub fn final_price(&self, now: i64) -> Option<u64> {
+        if self.clearing_price > 0 {
+            return Some(self.clearing_price);
+        }
+        let implied = self.implied_price();
+        if now >= self.end_time() || (self.committed > 0 && implied >= self.current_price(now)) {
+            Some(implied.max(self.floor_price))
+        } else {
+            None
+        }
+    }
+
+    /// Commit `amount` lamports at `now`, clearing the auction at the
+    /// current price if the commitments now cover the supply. Returns the
+    /// price the bid filled at.
+    pub fn record_bid(&mut self, amount: u64, now: i64) -> Result<u64> {
+        require!(self.final_price(now).is_none(), ErrorCode::AuctionClosed);
+        let price = self.current_price(now);
+        self.committed = self.committed.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        if self.implied_price() >= price {
+            self.clearing_price = price;
+        }
+        Ok(price)
+    }
+
+    /// Tokens and cost of a bid that committed `committed` lamports, at
+    /// `price`. Demand beyond the supply is filled pro rata so the
+    /// allocations never add up to more than the supply.
+    pub fn allocation(&self, committed: u64, price: u64) -> (u64, u64) {
+        let demand = self.committed as u128 * self.unit() / price as u128;
+        let tokens = if demand > self.supply as u128 {
+            self.supply as u128 * committed as u128 / self.committed as u128
+        } else {
+            committed as u128 * self.unit() / price as u128
+        };
+        let cost = (tokens * price as u128 + self.unit() - 1) / self.unit();
+        (tokens as u64, cost as u64)
+    }
+
+    fn unit(&self) -> u128 {
+        10u128.pow(self.token_decimals as u32)
+    }
+}
+
+/// A bidder's commitments to one auction, kept at
+/// `[b"auction_bid", auction, bidder]` and closed when settled
+#[account]
+pub struct AuctionBid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub committed: u64,
+}
+
+impl AuctionBid {
+    pub const LEN: usize = 32 + 32 + 8;
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+/// Record behind a wallet's soulbound access badge, kept at
+/// `[b"access_badge", wallet]`. The badge itself is a zero-decimal token from
+/// the `[b"access_badge_mint", wallet]` mint, held frozen in the wallet's
+/// associated token account so it cannot be moved; `tier` and `expires_at`
+/// mirror the receipt it was last refreshed from.
+#[account]
+pub struct AccessBadge {
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub refreshed_at: i64,
+}
+
+impl AccessBadge {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 8;
+
+    pub fn is_valid(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
+
+/// Thaw, burn, and refreeze the badge. The badge authority burns as the
+/// delegate approved when the badge was minted.
+pub fn burn_badge<'info>(
+    badge_mint: &Account<'info, Mint>,
+    badge_token_account: &Account<'info, TokenAccount>,
+    badge_authority: &AccountInfo<'info>,
+    badge_authority_bump: u8,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let seeds: &[&[&[u8]]] = &[&[b"badge_authority", &[badge_authority_bump]]];
+    let program = token_program.to_account_info();
+    token::thaw_account(CpiContext::new_with_signer(
+        program.clone(),
+        token::ThawAccount {
+            account: badge_token_account.to_account_info(),
+            mint: badge_mint.to_account_info(),
+            authority: badge_authority.clone(),
+        },
+        seeds,
+    ))?;
+    token::burn(
+        CpiContext::new_with_signer(
+            program.clone(),
+            token::Burn {
+                mint: badge_mint.to_account_info(),
+                from: badge_token_account.to_account_info(),
+                authority: badge_authority.clone(),
+            },
+            seeds,
+        ),
+        1,
+    )?;
+    token::freeze_account(CpiContext::new_with_signer(
+        program,
+        token::FreezeAccount {
+            account: badge_token_account.to_account_info(),
+            mint: badge_mint.to_account_info(),
+            authority: badge_authority.clone(),
+        },
+        seeds,
+    ))
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+/// Time the arbiter has after a bounty's deadline to settle the claims
+/// submitted before it, after which the remainder can be reclaimed
+pub const BOUNTY_REVIEW_SECS: i64 = 7 * 86_400;
+
+/// Treasury tokens set aside for a piece of community work, kept at
+/// `[b"bounty", bounty_id]` with the escrow in `[b"bounty_vault", bounty]`.
+/// The arbiter pays claims out of the escrow, in part or in full, and
+/// whatever is left goes back to the treasury once the review period after
+/// the deadline has passed.
+#[account]
+pub struct Bounty {
+    pub bounty_id: u64,
+    pub mint: Pubkey,
+    pub arbiter: Pubkey,
+    /// Receives the escrow's rent when it is reclaimed
+    pub creator: Pubkey,
+    /// Base units escrowed at creation
+    pub amount: u64,
+    pub paid: u64,
+    /// Last moment claims can be submitted
+    pub deadline: i64,
+    pub created_at: i64,
+    pub claims: u32,
+    pub reclaimed: bool,
+}
+
+impl Bounty {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 4 + 1;
+
+    pub fn remaining(&self) -> u64 {
+        self.amount - self.paid
+    }
+
+    pub fn accepts_claims(&self, now: i64) -> bool {
+        !self.reclaimed && now <= self.deadline
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.deadline.saturating_add(BOUNTY_REVIEW_SECS)
+    }
+
+    /// Claims can be paid until the review period after the deadline ends
+    pub fn accepts_payouts(&self, now: i64) -> bool {
+        !self.reclaimed && !self.is_expired(now)
+    }
+}
+
+/// One claimant's submission for a bounty, kept at
+/// `[b"bounty_claim", bounty, claimant]`
+#[account]
+pub struct BountyClaim {
+    pub bounty: Pubkey,
+    pub claimant: Pubkey,
+    /// Hash of the off-chain submission, e.g. a pull request URL
+    pub submission_hash: [u8; 32],
+    pub submitted_at: i64,
+    /// Paid out to the claimant so far
+    pub paid: u64,
+}
+
+impl BountyClaim {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8;
+}
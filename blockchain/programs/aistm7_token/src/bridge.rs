@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+
+use crate::{BridgeLimitUpdated, ErrorCode};
+
+/// Wormhole chains the bridge can hold rate limits for
+pub const MAX_BRIDGE_CHAINS: usize = 8;
+
+/// Length of the window bridge rate limits apply to
+pub const BRIDGE_WINDOW_SECS: i64 = 86_400;
+
+/// Rate limits for one Wormhole chain, per `BRIDGE_WINDOW_SECS`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BridgeChain {
+    /// Wormhole chain ID, e.g. 2 for Ethereum
+    pub chain_id: u16,
+    pub outbound_limit: u64,
+    pub inbound_limit: u64,
+    /// Bridged since `BridgeConfig::window_start`
+    pub outbound_used: u64,
+    pub inbound_used: u64,
+}
+
+impl BridgeChain {
+    pub const LEN: usize = 2 + 8 + 8 + 8 + 8;
+
+    /// Unused slot
+    pub const EMPTY: BridgeChain = BridgeChain {
+        chain_id: 0,
+        outbound_limit: 0,
+        inbound_limit: 0,
+        outbound_used: 0,
+        inbound_used: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.chain_id == 0
+    }
+}
+
+/// Glue between the mint and a Wormhole Native Token Transfers manager in
+/// burning mode, kept at `[b"bridge_config"]`. The manager signs with
+/// `bridge_authority` (its token authority PDA) to burn tokens leaving
+/// Solana and to mint tokens arriving, while this program keeps the mint
+/// authority, the per-chain rate limits, and the supply accounting.
+#[account]
+pub struct BridgeConfig {
+    pub bridge_authority: Pubkey,
+    /// Tokens burned to other chains and not yet bridged back; inbound
+    /// mints can never exceed it, so bridging cannot grow the supply
+    pub outstanding: u64,
+    /// Counts outbound transfers, for matching events to NTT messages
+    pub sequence: u64,
+    pub window_start: i64,
+    pub chains: [BridgeChain; MAX_BRIDGE_CHAINS],
+}
+
+impl BridgeConfig {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + BridgeChain::LEN * MAX_BRIDGE_CHAINS;
+
+    /// Outbound and inbound limits for `chain_id`, zero if it has none
+    pub fn limits(&self, chain_id: u16) -> (u64, u64) {
+        self.chains
+            .iter()
+            .find(|chain| !chain.is_empty() && chain.chain_id == chain_id)
+            .map_or((0, 0), |chain| (chain.outbound_limit, chain.inbound_limit))
+    }
+
+    /// Zero limits in both directions close the chain without freeing its
+    /// slot, so what it has used this window still counts.
+    pub fn set_limits(
+        &mut self,
+        chain_id: u16,
+        outbound_limit: u64,
+        inbound_limit: u64,
+        now: i64,
+    ) -> Result<()> {
+        require!(chain_id != 0, ErrorCode::InvalidParameter);
+        let (old_outbound_limit, old_inbound_limit) = self.limits(chain_id);
+        if let Some(chain) = self
+            .chains
+            .iter_mut()
+            .find(|chain| !chain.is_empty() && chain.chain_id == chain_id)
+        {
+            chain.outbound_limit = outbound_limit;
+            chain.inbound_limit = inbound_limit;
+        } else if outbound_limit > 0 || inbound_limit > 0 {
+            let slot = self
+                .chains
+                .iter_mut()
+                .find(|chain| chain.is_empty())
+                .ok_or(ErrorCode::BridgeChainsFull)?;
+            *slot = BridgeChain {
+                chain_id,
+                outbound_limit,
+                inbound_limit,
+                outbound_used: 0,
+                inbound_used: 0,
+            };
+        }
+
+        emit!(BridgeLimitUpdated {
+            chain_id,
+            old_outbound_limit,
+            new_outbound_limit: outbound_limit,
+            old_inbound_limit,
+            new_inbound_limit: inbound_limit,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Count a transfer of `amount` to (`outbound`) or from `chain_id`
+    /// against this window's limit, starting a new window first if the
+    /// current one has ended, and update the outstanding total
+    pub fn record_transfer(
+        &mut self,
+        chain_id: u16,
+        amount: u64,
+        outbound: bool,
+        now: i64,
+    ) -> Result<()> {
+        let elapsed = now.saturating_sub(self.window_start);
+        if elapsed >= BRIDGE_WINDOW_SECS {
+            self.window_start = now - elapsed % BRIDGE_WINDOW_SECS;
+            for chain in self.chains.iter_mut() {
+                chain.outbound_used = 0;
+                chain.inbound_used = 0;
+            }
+        }
+
+        let chain = self
+            .chains
+            .iter_mut()
+            .find(|chain| !chain.is_empty() && chain.chain_id == chain_id)
+            .ok_or(ErrorCode::BridgeLimitExceeded)?;
+        let (used, limit) = if outbound {
+            (&mut chain.outbound_used, chain.outbound_limit)
+        } else {
+            (&mut chain.inbound_used, chain.inbound_limit)
+        };
+        let new_used = used.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(new_used <= limit, ErrorCode::BridgeLimitExceeded);
+        *used = new_used;
+
+        self.outstanding = if outbound {
+            self.outstanding.checked_add(amount).ok_or(ErrorCode::MathOverflow)?
+        } else {
+            self.outstanding
+                .checked_sub(amount)
+                .ok_or(ErrorCode::BridgeSupplyExceeded)?
+        };
+        Ok(())
+    }
+}
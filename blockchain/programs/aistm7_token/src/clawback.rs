@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::permanent_delegate::PermanentDelegate,
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+};
+
+/// Shortest time between proposing a clawback and executing it, during
+/// which the owner can dispute it; a longer timelock delay applies instead
+pub const CLAWBACK_DISPUTE_WINDOW_SECS: i64 = 7 * 86_400;
+
+/// How far a dispute pushes the clawback back from when it was filed,
+/// leaving time to review the evidence before anything moves
+pub const CLAWBACK_REVIEW_SECS: i64 = 14 * 86_400;
+
+/// A proposed recovery of `amount` from a token-2022 account, kept at
+/// `[b"clawback", source]`. The `[b"clawback_authority"]` PDA is the mint's
+/// permanent delegate and moves the tokens to the treasury once `eta` has
+/// passed.
+///
+/// Dispute path: until `eta`, the account's owner can call
+/// `dispute_clawback` with the hash of the evidence they have submitted
+/// off chain. The dispute is recorded on-chain and moves `eta` to
+/// `CLAWBACK_REVIEW_SECS` after it was filed. An admin then either cancels
+/// the clawback or, once the review period is over, executes it; a
+/// clawback can only be disputed once.
+#[account]
+pub struct Clawback {
+    pub source: Pubkey,
+    /// Owner of `source` when the clawback was proposed
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Hash of the off-chain case file behind the clawback
+    pub reason: [u8; 32],
+    /// Receives the rent back when the clawback is executed or cancelled
+    pub proposer: Pubkey,
+    pub proposed_at: i64,
+    pub eta: i64,
+    /// Hash of the owner's evidence; zero while undisputed
+    pub dispute: [u8; 32],
+    pub disputed_at: i64,
+}
+
+impl Clawback {
+    pub const LEN: usize = 32 + 32 + 8 + 32 + 32 + 8 + 8 + 32 + 8;
+
+    pub fn is_disputed(&self) -> bool {
+        self.disputed_at != 0
+    }
+}
+
+/// Whether `mint` names `delegate` as its permanent delegate
+pub fn has_permanent_delegate(mint: &AccountInfo, delegate: &Pubkey) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint
+        .get_extension::<PermanentDelegate>()
+        .map_or(false, |extension| {
+            Option::<Pubkey>::from(extension.delegate) == Some(*delegate)
+        }))
+}
+
+/// Move `amount` out of `source` as the permanent delegate. Token-2022
+/// still runs the transfer hook, whose accounts are passed through from
+/// `hook_accounts`.
+#[allow(clippy::too_many_arguments)]
+pub fn claw_back<'info>(
+    source: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    clawback_authority: &AccountInfo<'info>,
+    clawback_authority_bump: u8,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let mut instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source.key,
+        mint.key,
+        destination.key,
+        clawback_authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut accounts = vec![
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        clawback_authority.clone(),
+    ];
+    for account in hook_accounts {
+        instruction.accounts.push(if account.is_writable {
+            AccountMeta::new(*account.key, false)
+        } else {
+            AccountMeta::new_readonly(*account.key, false)
+        });
+        accounts.push(account.clone());
+    }
+    invoke_signed(
+        &instruction,
+        &accounts,
+        &[&[b"clawback_authority", &[clawback_authority_bump]]],
+    )?;
+    Ok(())
+}
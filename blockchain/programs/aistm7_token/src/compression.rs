@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+
+use crate::ErrorCode;
+
+/// Light Protocol compressed-token program, which mints and holds
+/// compressed balances of an SPL mint in a token pool
+pub const COMPRESSED_TOKEN_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m");
+
+/// Light system program, which the compressed-token program calls to
+/// append to the state Merkle tree
+pub const LIGHT_SYSTEM_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7");
+
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("compr6CUsB5m2jS4Y3831ztGSTnDpnKJTKS95d64XVq");
+
+pub const NOOP_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Recipients one `mint_compressed` call can pay while still fitting in a
+/// transaction
+pub const MAX_COMPRESSED_RECIPIENTS: usize = 20;
+
+/// Anchor instruction discriminator of a compressed-token instruction
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{name}").as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// Accounts of the compressed-token program's `mint_to`, in its order
+pub struct CompressedMintTo<'a, 'info> {
+    pub fee_payer: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub cpi_authority_pda: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub token_pool_pda: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub light_system_program: &'a AccountInfo<'info>,
+    pub registered_program_pda: &'a AccountInfo<'info>,
+    pub noop_program: &'a AccountInfo<'info>,
+    pub account_compression_authority: &'a AccountInfo<'info>,
+    pub account_compression_program: &'a AccountInfo<'info>,
+    pub merkle_tree: &'a AccountInfo<'info>,
+    pub compressed_token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// Mint `amounts[i]` of `accounts.mint` as a compressed balance owned by
+/// `recipients[i]`. The tokens are minted into the token pool, so the
+/// mint's supply grows as with a regular mint, and `authority` must be
+/// the mint authority, signing with `signer_seeds`.
+pub fn mint_compressed_to(
+    accounts: &CompressedMintTo,
+    recipients: &[Pubkey],
+    amounts: &[u64],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = instruction_discriminator("mint_to").to_vec();
+    (recipients.to_vec(), amounts.to_vec(), None::<u64>).serialize(&mut data)?;
+    let infos = [
+        accounts.fee_payer,
+        accounts.authority,
+        accounts.cpi_authority_pda,
+        accounts.mint,
+        accounts.token_pool_pda,
+        accounts.token_program,
+        accounts.light_system_program,
+        accounts.registered_program_pda,
+        accounts.noop_program,
+        accounts.account_compression_authority,
+        accounts.account_compression_program,
+        accounts.merkle_tree,
+        accounts.compressed_token_program,
+        accounts.system_program,
+        // No lamports are attached, so the optional SOL pool is left out
+        accounts.compressed_token_program,
+    ];
+    let metas = infos
+        .iter()
+        .enumerate()
+        .map(|(index, info)| {
+            let signer = index <= 1;
+            let writable = matches!(index, 0 | 3 | 4 | 11);
+            if writable {
+                AccountMeta::new(*info.key, signer)
+            } else {
+                AccountMeta::new_readonly(*info.key, signer)
+            }
+        })
+        .collect();
+    invoke_signed(
+        &Instruction {
+            program_id: COMPRESSED_TOKEN_PROGRAM_ID,
+            accounts: metas,
+            data,
+        },
+        &infos.map(|info| info.clone()),
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Run a compressed-token `transfer` the client built with Light's SDK,
+/// whose validity proof and Merkle tree accounts only an indexer can
+/// supply. Nothing is signed for it, so it can only spend what the
+/// transaction's own signers could.
+pub fn forward_compressed_transfer<'info>(
+    compressed_token_program: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    require!(
+        data.len() >= 8 && data[..8] == instruction_discriminator("transfer"),
+        ErrorCode::InvalidParameter
+    );
+    let metas = accounts
+        .iter()
+        .map(|account| {
+            if account.is_writable {
+                AccountMeta::new(*account.key, account.is_signer)
+            } else {
+                AccountMeta::new_readonly(*account.key, account.is_signer)
+            }
+        })
+        .collect();
+    let mut infos = accounts.to_vec();
+    infos.push(compressed_token_program.clone());
+    invoke(
+        &Instruction {
+            program_id: COMPRESSED_TOKEN_PROGRAM_ID,
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+    Ok(())
+}
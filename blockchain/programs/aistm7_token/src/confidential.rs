@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::confidential_transfer::{instruction, ConfidentialTransferAccount},
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    solana_zk_token_sdk::zk_token_elgamal::pod::ElGamalPubkey,
+};
+
+use crate::attestation::Attestation;
+use crate::ErrorCode;
+
+/// Prefix of confidential balance attestations, distinct from plain ones
+/// so neither can be submitted as the other
+pub const CONFIDENTIAL_ATTESTATION_DOMAIN: &[u8] = b"AISTM7 confidential attestation v1";
+
+/// Confidential transfers on the token-2022 mint, for holders who do not
+/// want their balance public. The `mint_authority` PDA is the extension's
+/// authority.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfidentialTransferArgs {
+    /// When false, token accounts can only be configured for confidential
+    /// transfers once `approve_confidential_account` finds their owner
+    /// verified
+    pub auto_approve_new_accounts: bool,
+    /// ElGamal key that can decrypt every confidential transfer amount
+    pub auditor_elgamal_pubkey: Option<[u8; 32]>,
+}
+
+/// "`wallet`'s confidential balance in `token_account` met `requirement`
+/// at `attested_at`", signed by an attestor the holder disclosed the
+/// balance to off chain. Nothing about the balance beyond the requirement
+/// it meets reaches the chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfidentialAttestation {
+    pub wallet: Pubkey,
+    pub token_account: Pubkey,
+    pub tier: u8,
+    pub requirement: u64,
+    pub attested_at: i64,
+}
+
+impl ConfidentialAttestation {
+    /// The exact bytes the attestor signs
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = CONFIDENTIAL_ATTESTATION_DOMAIN.to_vec();
+        message.extend_from_slice(self.wallet.as_ref());
+        message.extend_from_slice(self.token_account.as_ref());
+        message.push(self.tier);
+        message.extend_from_slice(&self.requirement.to_le_bytes());
+        message.extend_from_slice(&self.attested_at.to_le_bytes());
+        message
+    }
+
+    /// The receipt-level claim, without the token account
+    pub fn attestation(&self) -> Attestation {
+        Attestation {
+            wallet: self.wallet,
+            tier: self.tier,
+            requirement: self.requirement,
+            attested_at: self.attested_at,
+        }
+    }
+}
+
+/// Add the confidential transfer extension to a mint being created, before
+/// it is initialized
+pub fn initialize_confidential_mint<'info>(
+    mint: &AccountInfo<'info>,
+    authority: &Pubkey,
+    args: &ConfidentialTransferArgs,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    invoke(
+        &instruction::initialize_mint(
+            token_program.key,
+            mint.key,
+            Some(*authority),
+            args.auto_approve_new_accounts,
+            args.auditor_elgamal_pubkey.map(ElGamalPubkey),
+        )?,
+        &[mint.clone()],
+    )?;
+    Ok(())
+}
+
+/// Whether `token_account` has been configured for confidential transfers,
+/// and so can hold a balance its owner may want attested
+pub fn is_confidential_account(token_account: &AccountInfo) -> Result<bool> {
+    let data = token_account.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(account.get_extension::<ConfidentialTransferAccount>().is_ok())
+}
+
+/// Let a configured account send and receive confidential transfers,
+/// signed by the mint authority as the extension's authority
+pub fn approve_account<'info>(
+    token_account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    authority_bump: u8,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(is_confidential_account(token_account)?, ErrorCode::NotConfidentialAccount);
+    invoke_signed(
+        &instruction::approve_account(
+            token_program.key,
+            token_account.key,
+            mint.key,
+            authority.key,
+            &[],
+        )?,
+        &[token_account.clone(), mint.clone(), authority.clone()],
+        &[&[b"mint_authority", &[authority_bump]]],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+/// Longest a delegation can be granted for (one year)
+pub const MAX_DELEGATION_SECS: i64 = 365 * 86_400;
+
+/// Lets `hot_wallet` verify on the strength of what `cold_wallet` holds, so
+/// tokens can stay on a hardware wallet. Kept at
+/// `[b"delegation", hot_wallet]`, so a hot wallet acts for one cold wallet
+/// at a time; either side can revoke it.
+#[account]
+pub struct Delegation {
+    pub cold_wallet: Pubkey,
+    pub hot_wallet: Pubkey,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl Delegation {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
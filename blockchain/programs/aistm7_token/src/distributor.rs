@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::ErrorCode;
+
+/// Claims tracked by each `ClaimBitmap` account
+pub const CLAIMS_PER_BITMAP: u32 = 8_192;
+
+/// Domain separators so a leaf can never be passed off as an inner node
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Airdrop of `total_amount` to the leaves of a merkle tree, claimable from
+/// its `[b"distribution_vault", distribution]` vault until `deadline`.
+#[account]
+pub struct Distribution {
+    pub id: u64,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    /// Number of leaves; claim indexes run from zero to `max_claims - 1`
+    pub max_claims: u32,
+    pub deadline: i64,
+}
+
+impl Distribution {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 4 + 8;
+
+    /// Bitmap accounts needed to cover every claim index
+    pub fn bitmap_count(&self) -> u32 {
+        bitmap_count(self.max_claims)
+    }
+}
+
+/// `ClaimBitmap` accounts needed for claim indexes below `max_claims`
+pub fn bitmap_count(max_claims: u32) -> u32 {
+    ((max_claims as u64 + CLAIMS_PER_BITMAP as u64 - 1) / CLAIMS_PER_BITMAP as u64) as u32
+}
+
+/// Claimed flags for indexes `chunk * CLAIMS_PER_BITMAP` onward
+#[account]
+pub struct ClaimBitmap {
+    /// `Distribution` or `RevenueEpoch` the claims belong to
+    pub distribution: Pubkey,
+    pub chunk: u32,
+    pub bits: [u8; CLAIMS_PER_BITMAP as usize / 8],
+}
+
+impl ClaimBitmap {
+    pub const LEN: usize = 32 + 4 + CLAIMS_PER_BITMAP as usize / 8;
+
+    /// Mark `index` claimed, failing if it already was
+    pub fn set_claimed(&mut self, index: u32) -> Result<()> {
+        let offset = (index % CLAIMS_PER_BITMAP) as usize;
+        let mask = 1u8 << (offset % 8);
+        require!(self.bits[offset / 8] & mask == 0, ErrorCode::AlreadyClaimed);
+        self.bits[offset / 8] |= mask;
+        Ok(())
+    }
+}
+
+pub fn leaf_hash(index: u32, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        claimant.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Check `proof` from `leaf` up to `root`. Pairs are hashed in sorted order,
+/// so the proof does not need to say which side each sibling is on.
+pub fn verify_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (left, right) = if node <= *sibling {
+            (node, *sibling)
+        } else {
+            (*sibling, node)
+        };
+        hashv(&[NODE_PREFIX, &left, &right]).to_bytes()
+    });
+    computed == *root
+}
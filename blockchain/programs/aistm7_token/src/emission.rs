@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Vaults an emission can be split between
+pub const MAX_EMISSION_VAULTS: usize = 4;
+
+/// Fixed-point scale used when compounding the per-epoch decay
+const DECAY_SCALE: u128 = 1_000_000_000_000;
+
+/// Token account receiving a share of every epoch's emission
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmissionVault {
+    pub token_account: Pubkey,
+    pub share_bps: u16,
+}
+
+impl EmissionVault {
+    pub const LEN: usize = 32 + 2;
+
+    /// Unused slot
+    pub const EMPTY: EmissionVault = EmissionVault {
+        token_account: Pubkey::new_from_array([0; 32]),
+        share_bps: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.token_account == Pubkey::default()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EmissionScheduleArgs {
+    pub start_time: i64,
+    pub epoch_secs: u64,
+    pub initial_emission: u64,
+    pub decay_bps: u64,
+    pub vaults: Vec<EmissionVault>,
+}
+
+/// Deterministic inflation schedule: epoch `n` starts at
+/// `start_time + n * epoch_secs` and mints `initial_emission` reduced by
+/// `decay_bps` once per elapsed epoch, split between the vaults by share.
+#[account]
+pub struct EmissionSchedule {
+    pub start_time: i64,
+    pub epoch_secs: u64,
+    pub initial_emission: u64,
+    pub decay_bps: u64,
+    /// Next epoch to be minted
+    pub next_epoch: u64,
+    pub total_emitted: u64,
+    pub vaults: [EmissionVault; MAX_EMISSION_VAULTS],
+}
+
+impl EmissionSchedule {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + EmissionVault::LEN * MAX_EMISSION_VAULTS;
+
+    pub fn configure(&mut self, args: &EmissionScheduleArgs) -> Result<()> {
+        require!(args.epoch_secs > 0, ErrorCode::InvalidEmissionSchedule);
+        require!(args.decay_bps <= BPS_DENOMINATOR, ErrorCode::InvalidEmissionSchedule);
+        require!(
+            !args.vaults.is_empty() && args.vaults.len() <= MAX_EMISSION_VAULTS,
+            ErrorCode::InvalidEmissionSchedule
+        );
+
+        let mut total_bps: u64 = 0;
+        for (i, vault) in args.vaults.iter().enumerate() {
+            require!(
+                !vault.is_empty() && vault.share_bps > 0,
+                ErrorCode::InvalidEmissionSchedule
+            );
+            require!(
+                !args.vaults[..i]
+                    .iter()
+                    .any(|other| other.token_account == vault.token_account),
+                ErrorCode::InvalidEmissionSchedule
+            );
+            total_bps += vault.share_bps as u64;
+        }
+        require!(total_bps == BPS_DENOMINATOR, ErrorCode::InvalidEmissionSchedule);
+
+        self.start_time = args.start_time;
+        self.epoch_secs = args.epoch_secs;
+        self.initial_emission = args.initial_emission;
+        self.decay_bps = args.decay_bps;
+        self.next_epoch = 0;
+        self.total_emitted = 0;
+        self.vaults = [EmissionVault::EMPTY; MAX_EMISSION_VAULTS];
+        self.vaults[..args.vaults.len()].copy_from_slice(&args.vaults);
+        Ok(())
+    }
+
+    pub fn vaults(&self) -> impl Iterator<Item = &EmissionVault> + '_ {
+        self.vaults.iter().filter(|vault| !vault.is_empty())
+    }
+
+    /// Unix time at which `epoch` can be minted
+    pub fn epoch_start(&self, epoch: u64) -> Result<i64> {
+        epoch
+            .checked_mul(self.epoch_secs)
+            .and_then(|offset| i64::try_from(offset).ok())
+            .and_then(|offset| self.start_time.checked_add(offset))
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Tokens minted in `epoch`: the initial emission compounded down by
+    /// the decay once per earlier epoch, rounded down.
+    pub fn emission_for(&self, epoch: u64) -> u64 {
+        let retained = (BPS_DENOMINATOR - self.decay_bps) as u128 * DECAY_SCALE
+            / BPS_DENOMINATOR as u128;
+
+        // Square-and-multiply so the cost is logarithmic in the epoch
+        let mut factor = DECAY_SCALE;
+        let mut base = retained;
+        let mut exponent = epoch;
+        while exponent > 0 && factor > 0 {
+            if exponent & 1 == 1 {
+                factor = factor * base / DECAY_SCALE;
+            }
+            base = base * base / DECAY_SCALE;
+            exponent >>= 1;
+        }
+
+        (self.initial_emission as u128 * factor / DECAY_SCALE) as u64
+    }
+
+    /// Split `amount` by vault share; the last vault takes the rounding
+    /// remainder so the parts always add up to `amount`.
+    pub fn split(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        let vaults: Vec<&EmissionVault> = self.vaults().collect();
+        let mut remaining = amount;
+        vaults
+            .iter()
+            .enumerate()
+            .map(|(i, vault)| {
+                let share = if i + 1 == vaults.len() {
+                    remaining
+                } else {
+                    (amount as u128 * vault.share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+                };
+                remaining -= share;
+                (vault.token_account, share)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+use crate::{
+    ErrorCode, FeeCollected, InsuranceFunded, Referral, ReferralRewardPaid, TokenState,
+    BPS_DENOMINATOR,
+};
+
+/// Highest protocol fee that can be configured (10%)
+pub const MAX_FEE_BPS: u64 = 1_000;
+
+/// Value-moving operations the protocol fee applies to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeOperation {
+    Subscription,
+    AccessPass,
+    StakingDeposit,
+}
+
+/// Move the protocol fee on `amount` from `source` to the fee destination
+/// and return what is left for the operation itself. The destination only
+/// has to be passed while a fee is being charged. When the payer's
+/// `Referral` is passed, the referrer's share of the fee goes to
+/// `referrer_account` instead. The insurance fund's share goes to
+/// `insurance_vault`, which only has to be passed while that share is set.
+#[allow(clippy::too_many_arguments)]
+pub fn collect_fee<'info>(
+    state: &TokenState,
+    operation: FeeOperation,
+    amount: u64,
+    source: &Account<'info, TokenAccount>,
+    fee_destination: Option<&Account<'info, TokenAccount>>,
+    insurance_vault: Option<&Account<'info, TokenAccount>>,
+    referral: Option<&mut Account<'info, Referral>>,
+    referrer_account: Option<&Account<'info, TokenAccount>>,
+    payer: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<u64> {
+    let fee = state.fee_for(amount);
+    if fee == 0 {
+        return Ok(amount);
+    }
+
+    let destination = fee_destination
+        .filter(|destination| destination.key() == state.fee_destination)
+        .ok_or(ErrorCode::InvalidFeeDestination)?;
+    let transfer = |to: &Account<'info, TokenAccount>, amount: u64| {
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token::Transfer {
+                    from: source.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: payer.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    };
+
+    let mut reward = 0;
+    if let Some(referral) = referral {
+        let referrer_account = referrer_account
+            .filter(|account| account.owner == referral.referrer && account.mint == source.mint)
+            .ok_or(ErrorCode::InvalidReferral)?;
+        require_keys_eq!(referral.user, payer.key(), ErrorCode::InvalidReferral);
+        reward = (fee as u128 * state.referral_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        if reward > 0 {
+            transfer(referrer_account, reward)?;
+            referral.rewards_paid = referral
+                .rewards_paid
+                .checked_add(reward)
+                .ok_or(ErrorCode::MathOverflow)?;
+            emit!(ReferralRewardPaid {
+                operation,
+                user: referral.user,
+                referrer: referral.referrer,
+                fee,
+                reward,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+    }
+
+    let insurance = (fee as u128 * state.insurance_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+    if insurance > 0 {
+        let insurance_vault = insurance_vault
+            .filter(|vault| vault.key() == state.insurance_vault)
+            .ok_or(ErrorCode::InvalidInsuranceVault)?;
+        transfer(insurance_vault, insurance)?;
+        emit!(InsuranceFunded {
+            operation,
+            payer: payer.key(),
+            fee,
+            amount: insurance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    transfer(destination, fee - reward - insurance)?;
+
+    emit!(FeeCollected {
+        operation,
+        payer: payer.key(),
+        amount,
+        fee,
+        destination: destination.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(amount - fee)
+}
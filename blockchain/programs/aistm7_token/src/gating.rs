@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use anchor_spl::token_interface;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Mints the gating basket can hold besides the token itself
+pub const MAX_GATED_MINTS: usize = 8;
+
+/// Liquidity pools whose LP tokens count towards access
+pub const MAX_LP_POOLS: usize = 4;
+
+/// Additional token accounts a verification can sum next to the main one
+pub const MAX_EXTRA_TOKEN_ACCOUNTS: usize = 8;
+
+/// A mint accepted towards access and what one of its base units is worth
+/// in base units of the token
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GatedMint {
+    pub mint: Pubkey,
+    pub weight_bps: u64,
+}
+
+impl GatedMint {
+    pub const LEN: usize = 32 + 8;
+
+    /// Unused slot
+    pub const EMPTY: GatedMint = GatedMint {
+        mint: Pubkey::new_from_array([0; 32]),
+        weight_bps: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+}
+
+/// Constant-product pool (Raydium AMM, Orca token-swap) whose fungible LP
+/// token is a claim on `reserve_vault`, the pool's vault of the token.
+/// Concentrated-liquidity positions are NFTs and cannot be listed. The
+/// reserve and LP supply last observed, and the slot they were observed in,
+/// cap what LP tokens are worth so a swing within one transaction cannot
+/// raise it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LpPool {
+    pub lp_mint: Pubkey,
+    pub reserve_vault: Pubkey,
+    pub observed_reserve: u64,
+    pub observed_lp_supply: u64,
+    /// Zero until the pool is first observed
+    pub observed_slot: u64,
+}
+
+impl LpPool {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8;
+
+    /// Unused slot
+    pub const EMPTY: LpPool = LpPool {
+        lp_mint: Pubkey::new_from_array([0; 32]),
+        reserve_vault: Pubkey::new_from_array([0; 32]),
+        observed_reserve: 0,
+        observed_lp_supply: 0,
+        observed_slot: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.lp_mint == Pubkey::default()
+    }
+
+    /// The token underlying `lp_amount`, at the lower of the pool as it
+    /// stands and as last observed. The observation must come from an
+    /// earlier slot than `slot`, so one made within the same transaction as
+    /// a flash loan or swap cannot vouch for the swing.
+    pub fn value_of(&self, lp_amount: u64, lp_supply: u64, reserve: u64, slot: u64) -> Result<u64> {
+        require!(
+            self.observed_slot != 0 && self.observed_slot < slot,
+            ErrorCode::LpPoolUnobserved
+        );
+        Ok(std::cmp::min(
+            lp_share(lp_amount, lp_supply, reserve),
+            lp_share(lp_amount, self.observed_lp_supply, self.observed_reserve),
+        ))
+    }
+}
+
+/// Basket of partner mints (wrapped or derivative versions of the token)
+/// that count towards access at their weight, and LP pools whose providers
+/// count their share of the pool's tokens, kept at `[b"gating_config"]`.
+/// The token itself always counts at full weight and is never listed.
+#[account]
+pub struct GatingConfig {
+    pub mints: [GatedMint; MAX_GATED_MINTS],
+    pub lp_pools: [LpPool; MAX_LP_POOLS],
+}
+
+impl GatingConfig {
+    pub const LEN: usize = GatedMint::LEN * MAX_GATED_MINTS + LpPool::LEN * MAX_LP_POOLS;
+
+    pub fn lp_pool(&self, lp_mint: &Pubkey) -> Option<&LpPool> {
+        self.lp_pools
+            .iter()
+            .find(|pool| !pool.is_empty() && pool.lp_mint == *lp_mint)
+    }
+
+    /// Record the reserve and LP supply of the pool behind `lp_mint` as
+    /// they stand in `slot`
+    pub fn observe_lp_pool(
+        &mut self,
+        lp_mint: &Pubkey,
+        lp_supply: u64,
+        reserve: u64,
+        slot: u64,
+    ) -> Result<()> {
+        let pool = self
+            .lp_pools
+            .iter_mut()
+            .find(|pool| !pool.is_empty() && pool.lp_mint == *lp_mint)
+            .ok_or(ErrorCode::InvalidLpPool)?;
+        pool.observed_reserve = reserve;
+        pool.observed_lp_supply = lp_supply;
+        pool.observed_slot = slot;
+        Ok(())
+    }
+
+    /// List the pool behind `lp_mint`, or delist it when `reserve_vault` is
+    /// `None`
+    pub fn set_lp_pool(&mut self, lp_mint: Pubkey, reserve_vault: Option<Pubkey>) -> Result<()> {
+        require!(lp_mint != Pubkey::default(), ErrorCode::InvalidParameter);
+        let existing = self.lp_pools.iter_mut().find(|pool| pool.lp_mint == lp_mint);
+        // A new or moved vault has to be observed afresh
+        let entry = reserve_vault.map(|reserve_vault| LpPool {
+            lp_mint,
+            reserve_vault,
+            ..LpPool::EMPTY
+        });
+        match (existing, entry) {
+            (Some(slot), Some(entry)) if slot.reserve_vault == entry.reserve_vault => {}
+            (Some(slot), entry) => *slot = entry.unwrap_or(LpPool::EMPTY),
+            (None, Some(entry)) => {
+                let slot = self
+                    .lp_pools
+                    .iter_mut()
+                    .find(|pool| pool.is_empty())
+                    .ok_or(ErrorCode::GatingConfigFull)?;
+                *slot = entry;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    pub fn weight_of(&self, mint: &Pubkey) -> Option<u64> {
+        self.mints
+            .iter()
+            .find(|gated| !gated.is_empty() && gated.mint == *mint)
+            .map(|gated| gated.weight_bps)
+    }
+
+    /// Add `mint` or change its weight; a zero weight removes it
+    pub fn set(&mut self, mint: Pubkey, weight_bps: u64) -> Result<()> {
+        require!(mint != Pubkey::default(), ErrorCode::InvalidParameter);
+        require!(weight_bps <= BPS_DENOMINATOR, ErrorCode::InvalidParameter);
+        if let Some(slot) = self.mints.iter_mut().find(|gated| gated.mint == mint) {
+            *slot = if weight_bps == 0 {
+                GatedMint::EMPTY
+            } else {
+                GatedMint { mint, weight_bps }
+            };
+            return Ok(());
+        }
+        if weight_bps == 0 {
+            return Ok(());
+        }
+        let slot = self
+            .mints
+            .iter_mut()
+            .find(|gated| gated.is_empty())
+            .ok_or(ErrorCode::GatingConfigFull)?;
+        *slot = GatedMint { mint, weight_bps };
+        Ok(())
+    }
+}
+
+/// The token underlying `lp_amount` of an LP mint with `lp_supply`
+/// outstanding over a pool holding `reserve` of the token
+pub fn lp_share(lp_amount: u64, lp_supply: u64, reserve: u64) -> u64 {
+    if lp_supply == 0 {
+        return 0;
+    }
+    (lp_amount as u128 * reserve as u128 / lp_supply as u128) as u64
+}
+
+/// Weighted balance of `accounts`, which must be distinct token accounts of
+/// the same owner as `primary`, holding its mint, one in the basket, or a
+/// listed LP mint. Token-2022 accounts are read as well, and those of
+/// `migration_mint` count at full weight like the legacy mint. An LP token
+/// account is followed by the pool's LP mint and reserve vault, and is
+/// worth no more than at the pool's last observation before `slot`.
+pub fn extra_token_balance(
+    primary: &Account<TokenAccount>,
+    accounts: &[AccountInfo],
+    gating_config: Option<&GatingConfig>,
+    migration_mint: &Pubkey,
+    slot: u64,
+) -> Result<u64> {
+    let mut seen: Vec<Pubkey> = vec![primary.key()];
+    let mut balance: u64 = 0;
+    let mut accounts = accounts.iter();
+    while let Some(info) = accounts.next() {
+        require!(
+            !seen.contains(info.key) && seen.len() <= MAX_EXTRA_TOKEN_ACCOUNTS,
+            ErrorCode::InvalidTokenAccount
+        );
+        seen.push(*info.key);
+        let account = InterfaceAccount::<token_interface::TokenAccount>::try_from(info)?;
+        require!(account.owner == primary.owner, ErrorCode::InvalidTokenAccount);
+        let migrated = *migration_mint != Pubkey::default() && account.mint == *migration_mint;
+        let held = if account.mint == primary.mint || migrated {
+            account.amount
+        } else if let Some(pool) = gating_config.and_then(|config| config.lp_pool(&account.mint)) {
+            let (Some(lp_mint), Some(reserve_vault)) = (accounts.next(), accounts.next()) else {
+                return err!(ErrorCode::InvalidLpPool);
+            };
+            require!(
+                lp_mint.key() == pool.lp_mint && reserve_vault.key() == pool.reserve_vault,
+                ErrorCode::InvalidLpPool
+            );
+            let lp_mint = InterfaceAccount::<token_interface::Mint>::try_from(lp_mint)?;
+            let reserve_vault =
+                InterfaceAccount::<token_interface::TokenAccount>::try_from(reserve_vault)?;
+            pool.value_of(account.amount, lp_mint.supply, reserve_vault.amount, slot)?
+        } else {
+            let weight_bps = gating_config
+                .and_then(|config| config.weight_of(&account.mint))
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            (account.amount as u128 * weight_bps as u128 / BPS_DENOMINATOR as u128) as u64
+        };
+        balance = balance.saturating_add(held);
+    }
+    Ok(balance)
+}
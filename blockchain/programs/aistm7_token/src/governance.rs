@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use crate::timelock::ParameterChange;
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Longest a proposal's voting may run
+pub const MAX_VOTING_PERIOD_SECS: u64 = 30 * 86_400;
+
+/// Token holder votes on parameter changes, for deployments that do not
+/// hand the authority to a Realms DAO, kept at `[b"governance_config"]`.
+#[account]
+pub struct GovernanceConfig {
+    pub voting_period_secs: u64,
+    /// Share of a proposal's snapshot weight that must vote, for or against
+    pub quorum_bps: u64,
+    pub next_proposal_id: u64,
+    /// Tokens, held or staked, a wallet needs to put a proposal forward
+    pub proposal_threshold: u64,
+}
+
+impl GovernanceConfig {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+}
+
+/// A parameter change put to a vote, kept at `[b"proposal", id]`. Voting
+/// weights are `leaf_hash(index, voter, weight)` leaves computed off-chain
+/// from token and stake balances at `snapshot_slot`, the slot the proposal
+/// was created in, as revenue epochs are. Tokens moved after the snapshot
+/// cannot vote twice, and tokens borrowed to vote arrive too late to
+/// count. The snapshot only stands once a guardian quorum has approved it.
+/// A passed proposal executes once the timelock delay has run from the end
+/// of voting.
+#[account]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub change: ParameterChange,
+    pub snapshot_slot: u64,
+    pub vote_root: [u8; 32],
+    /// Sum of the snapshot's weights, which the quorum is a share of; zero
+    /// until the snapshot is recorded
+    pub total_weight: u64,
+    pub yes_weight: u64,
+    pub no_weight: u64,
+    pub created_at: i64,
+    /// Set, with `eta`, when the snapshot is recorded
+    pub voting_ends_at: i64,
+    pub eta: i64,
+    pub executed: bool,
+    /// Set by the guardian council, which keeps the proposal from executing
+    pub vetoed: bool,
+}
+
+impl Proposal {
+    pub const LEN: usize =
+        8 + 32 + ParameterChange::MAX_LEN + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn has_snapshot(&self) -> bool {
+        self.total_weight > 0
+    }
+
+    pub fn record_vote(&mut self, support: bool, weight: u64) -> Result<()> {
+        let tally = if support {
+            &mut self.yes_weight
+        } else {
+            &mut self.no_weight
+        };
+        *tally = tally.checked_add(weight).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Turnout reached the quorum and more weight voted for than against
+    pub fn passed(&self, quorum_bps: u64) -> bool {
+        let turnout = self.yes_weight as u128 + self.no_weight as u128;
+        turnout * BPS_DENOMINATOR as u128 >= self.total_weight as u128 * quorum_bps as u128
+            && self.yes_weight > self.no_weight
+    }
+}
+
+/// A voter's ballot, kept at `[b"vote", proposal, voter]` so each snapshot
+/// leaf is counted once
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub voted_at: i64,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 8;
+}
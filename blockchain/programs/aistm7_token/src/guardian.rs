@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Largest guardian council
+pub const MAX_GUARDIANS: usize = 10;
+
+/// Longest a council may serve before its powers lapse
+pub const MAX_GUARDIAN_TERM_SECS: i64 = 365 * 86_400;
+
+/// How long a guardian pause holds before anyone can lift it
+pub const GUARDIAN_PAUSE_SECS: i64 = 3 * 86_400;
+
+/// An M-of-N council of guardian keys, kept at `[b"guardian_council"]`,
+/// with emergency powers only: vetoing queued changes and governance
+/// proposals, and pausing for `GUARDIAN_PAUSE_SECS`. It can neither move
+/// funds nor change parameters, and its powers lapse at `expires_at`
+/// unless the authority appoints a new term.
+#[account]
+pub struct GuardianCouncil {
+    pub members: [Pubkey; MAX_GUARDIANS],
+    /// Members who must sign each guardian action
+    pub threshold: u8,
+    pub expires_at: i64,
+    /// When the council's pause may be lifted; zero while it has none
+    pub paused_until: i64,
+    /// Whether the program is paused because of the council, so lifting the
+    /// council's pause never ends one the operators put in place
+    pub holds_pause: bool,
+}
+
+impl GuardianCouncil {
+    pub const LEN: usize = 32 * MAX_GUARDIANS + 1 + 8 + 8 + 1;
+
+    /// Appoint `members` for `term_secs` from `now`
+    pub fn appoint(
+        &mut self,
+        members: &[Pubkey],
+        threshold: u8,
+        term_secs: i64,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            !members.is_empty()
+                && members.len() <= MAX_GUARDIANS
+                && threshold >= 1
+                && threshold as usize <= members.len()
+                && (1..=MAX_GUARDIAN_TERM_SECS).contains(&term_secs),
+            ErrorCode::InvalidGuardianCouncil
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                *member != Pubkey::default() && !members[..i].contains(member),
+                ErrorCode::InvalidGuardianCouncil
+            );
+        }
+        self.members = [Pubkey::default(); MAX_GUARDIANS];
+        self.members[..members.len()].copy_from_slice(members);
+        self.threshold = threshold;
+        self.expires_at = now.checked_add(term_secs).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.members.contains(key)
+    }
+
+    /// Check that at least `threshold` distinct members signed as
+    /// `approvers` while the council's term lasts, returning how many did
+    pub fn check_approvals(&self, approvers: &[AccountInfo], now: i64) -> Result<u8> {
+        require!(now < self.expires_at, ErrorCode::GuardianCouncilExpired);
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(
+                approver.is_signer && self.is_member(approver.key),
+                ErrorCode::NotAGuardian
+            );
+            require!(
+                !approvers[..i].iter().any(|other| other.key == approver.key),
+                ErrorCode::DuplicateGuardianApproval
+            );
+        }
+        require!(
+            approvers.len() >= self.threshold as usize,
+            ErrorCode::GuardianThresholdNotMet
+        );
+        Ok(approvers.len() as u8)
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+/// Number of requirement changes kept in the history ring buffer
+pub const HISTORY_LEN: usize = 64;
+
+/// Requirement in effect from `timestamp`, and the price it was derived from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: i64,
+    pub price: u64,
+    pub requirement: u64,
+}
+
+impl HistoryEntry {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// Ring buffer of the most recent requirement changes, so other programs
+/// and clients can look up the requirement at a given time on-chain.
+#[account]
+pub struct RequirementHistory {
+    /// Index the next entry will be written to
+    pub head: u16,
+    pub count: u16,
+    pub entries: [HistoryEntry; HISTORY_LEN],
+}
+
+impl RequirementHistory {
+    pub const LEN: usize = 2 + 2 + HistoryEntry::LEN * HISTORY_LEN;
+
+    pub fn record(&mut self, timestamp: i64, price: u64, requirement: u64) {
+        self.entries[self.head as usize] = HistoryEntry {
+            timestamp,
+            price,
+            requirement,
+        };
+        self.head = ((self.head as usize + 1) % HISTORY_LEN) as u16;
+        self.count = std::cmp::min(self.count as usize + 1, HISTORY_LEN) as u16;
+    }
+
+    /// Recorded entries from oldest to newest
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> + '_ {
+        let start = (self.head as usize + HISTORY_LEN - self.count as usize) % HISTORY_LEN;
+        (0..self.count as usize).map(move |i| &self.entries[(start + i) % HISTORY_LEN])
+    }
+
+    pub fn latest(&self) -> Option<&HistoryEntry> {
+        self.entries().last()
+    }
+
+    /// The entry in effect at `timestamp`, or `None` if it predates the
+    /// oldest entry still held.
+    pub fn requirement_at(&self, timestamp: i64) -> Option<&HistoryEntry> {
+        self.entries()
+            .take_while(|entry| entry.timestamp <= timestamp)
+            .last()
+    }
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+/// Longest minimum holding period that can be configured (30 days)
+pub const MAX_MIN_HOLDING_SECS: u64 = 30 * 86_400;
+
+/// What a wallet has been seen holding, kept at `[b"holdings", owner]` for
+/// the minimum holding period rule. Holdings are the tracked token account
+/// plus the owner's stake, so staking does not restart the clock. Tokens
+/// only mature once a checkpoint has seen them for `min_holding_secs`;
+/// holdings first seen at verification time never count straight away.
+#[account]
+pub struct HoldingsCheckpoint {
+    pub owner: Pubkey,
+    /// The only token account checkpoints and verifications may read
+    pub token_account: Pubkey,
+    /// Held for at least the minimum holding period
+    pub matured_amount: u64,
+    /// Seen more recently, maturing together at `pending_since` plus the period
+    pub pending_amount: u64,
+    pub pending_since: i64,
+    pub updated_at: i64,
+}
+
+impl HoldingsCheckpoint {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8;
+
+    /// Bring the checkpoint up to date with `holdings` seen at `now`.
+    /// Decreases come out of pending tokens first; increases join the
+    /// pending bucket and restart its clock.
+    pub fn update(&mut self, holdings: u64, min_holding_secs: u64, now: i64) {
+        if self.pending_amount > 0
+            && now >= self.pending_since.saturating_add(min_holding_secs as i64)
+        {
+            self.matured_amount = self.matured_amount.saturating_add(self.pending_amount);
+            self.pending_amount = 0;
+        }
+
+        let tracked = self.matured_amount.saturating_add(self.pending_amount);
+        if holdings < tracked {
+            let deficit = tracked - holdings;
+            let from_pending = std::cmp::min(deficit, self.pending_amount);
+            self.pending_amount -= from_pending;
+            self.matured_amount -= deficit - from_pending;
+        } else if holdings > tracked {
+            self.pending_amount = self.pending_amount.saturating_add(holdings - tracked);
+            self.pending_since = now;
+        }
+        self.updated_at = now;
+    }
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Reserve against protocol incidents, kept at `[b"insurance_fund"]` with
+/// its tokens in `[b"insurance_vault"]`. It is funded by `insurance_bps` of
+/// every protocol fee, and only drawn down by the authority against an
+/// incident it has declared.
+#[account]
+pub struct InsuranceFund {
+    pub mint: Pubkey,
+    /// Incidents declared so far
+    pub incidents: u64,
+    /// Paid out over all incidents
+    pub total_drawn: u64,
+    pub created_at: i64,
+}
+
+impl InsuranceFund {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+}
+
+/// A declared incident the fund may pay out for, kept at
+/// `[b"insurance_incident", incident_id]`. Draws only open once the
+/// timelock delay has passed since the declaration, so holders see a
+/// payout coming, and never add up to more than `max_amount`.
+#[account]
+pub struct Incident {
+    pub incident_id: u64,
+    /// Hash of the off-chain incident report
+    pub report_hash: [u8; 32],
+    /// Most the fund may pay out for this incident
+    pub max_amount: u64,
+    pub drawn: u64,
+    pub declared_at: i64,
+    pub drawable_at: i64,
+}
+
+impl Incident {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8;
+
+    pub fn remaining(&self) -> u64 {
+        self.max_amount - self.drawn
+    }
+
+    /// Record a payout of `amount` at `now`, returning the total drawn for
+    /// the incident
+    pub fn record_draw(&mut self, amount: u64, now: i64) -> Result<u64> {
+        require!(now >= self.drawable_at, ErrorCode::IncidentNotDrawable);
+        require!(amount <= self.remaining(), ErrorCode::InsuranceDrawExceeded);
+        self.drawn += amount;
+        Ok(self.drawn)
+    }
+}
@@ -1,8 +1,131 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::{self as token_metadata, Metadata, MetadataAccount};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
-use pyth_sdk_solana::load_price_feed_from_account_info;
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_interface;
 
-declare_id!("AISTM7TokenProgramID11111111111111111111111111111111");
+pub mod access_list;
+pub mod admin_log;
+pub mod attestation;
+pub mod auction;
+pub mod badge;
+pub mod bounty;
+pub mod bridge;
+pub mod clawback;
+pub mod compression;
+pub mod confidential;
+pub mod delegation;
+pub mod distributor;
+pub mod emission;
+pub mod fees;
+pub mod gating;
+pub mod governance;
+pub mod guardian;
+pub mod history;
+pub mod holdings;
+pub mod insurance;
+pub mod liquidity;
+pub mod members;
+pub mod metadata;
+pub mod migration;
+pub mod oracle;
+pub mod otc;
+pub mod presale;
+pub mod receipt;
+pub mod referral;
+pub mod revenue;
+pub mod roles;
+pub mod sale;
+pub mod session;
+pub mod staking;
+pub mod stream;
+pub mod subscription;
+pub mod swap;
+pub mod timelock;
+pub mod transfer_fee;
+pub mod transfer_hook;
+pub mod treasury;
+pub mod usage;
+pub mod vesting;
+pub mod wormhole;
+pub mod twap;
+
+use access_list::*;
+use admin_log::*;
+use attestation::*;
+use auction::*;
+use badge::*;
+use bounty::*;
+use bridge::*;
+use clawback::*;
+use compression::*;
+use confidential::*;
+use delegation::*;
+use distributor::*;
+use emission::*;
+use fees::*;
+use gating::*;
+use governance::*;
+use guardian::*;
+use history::*;
+use holdings::*;
+use insurance::*;
+use liquidity::*;
+use members::*;
+use metadata::*;
+use migration::*;
+use oracle::*;
+use otc::*;
+use presale::*;
+use receipt::*;
+use referral::*;
+use revenue::*;
+use roles::*;
+use sale::*;
+use session::*;
+use staking::*;
+use stream::*;
+use subscription::*;
+use swap::*;
+use timelock::*;
+use transfer_fee::*;
+use transfer_hook::*;
+use treasury::*;
+use usage::*;
+use vesting::*;
+use wormhole::*;
+use twap::*;
+
+declare_id!("AiSTM7TokenProgram11111111111111111111111111");
+
+/// Default internal USD precision (6 decimals, i.e. millionths of USD)
+pub const DEFAULT_PRICE_DECIMALS: u8 = 6;
+
+/// Default maximum age of an oracle price before it is considered stale
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u64 = 60;
+
+/// Default maximum confidence interval relative to price (2%)
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u64 = 200;
+
+/// Default minimum time between permissionless cranks
+pub const DEFAULT_CRANK_INTERVAL_SECS: u64 = 300;
+
+/// Default price move from the last recorded price that trips the circuit breaker (50%)
+pub const DEFAULT_CIRCUIT_BREAKER_BPS: u64 = 5_000;
+
+/// Default minimum requirement change that triggers an update (1%)
+pub const DEFAULT_UPDATE_THRESHOLD_BPS: u64 = 100;
+
+/// Basis point denominator
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Largest precision we accept so that `10^decimals` always fits in a u64
+pub const MAX_PRICE_DECIMALS: u8 = 18;
 
 #[program]
 pub mod aistm7_token {
@@ -11,6 +134,7 @@ pub mod aistm7_token {
     pub fn initialize(
         ctx: Context<Initialize>,
         initial_supply: u64,
+        max_supply: u64,
     ) -> Result<()> {
         let mint_info = &ctx.accounts.mint;
         let mint = &mut ctx.accounts.mint;
@@ -32,13 +156,13 @@ pub mod aistm7_token {
         )?;
 
         // Set initial parameters
-        let state = &mut ctx.accounts.state;
-        state.authority = authority.key();
-        state.mint = mint.key();
-        state.target_usd_value = 15_000_000; // $15 USD in millionths
-        state.min_tokens = 100; // Minimum 100 tokens regardless of price
-        state.max_tokens = 10_000; // Maximum 10,000 tokens regardless of price
-        state.current_requirement = 750; // Initial requirement (at $0.02 per token)
+        ctx.accounts.state.set_inner(TokenState::new(
+            authority.key(),
+            mint.key(),
+            ctx.accounts.price_feed.key(),
+            max_supply,
+        ));
+        ctx.accounts.state.check_mint(0, initial_supply)?;
         
         // Mint initial supply to authority
         token::mint_to(
@@ -57,142 +181,13740 @@ pub mod aistm7_token {
         Ok(())
     }
 
-    pub fn update_balance_requirement(
-        ctx: Context<UpdateBalanceRequirement>,
-        price_feed: Pubkey,
+    pub fn update_balance_requirement(ctx: Context<UpdateBalanceRequirement>) -> Result<()> {
+        let clock = Clock::get()?;
+        
+        refresh_requirement(
+            &mut ctx.accounts.state,
+            &ctx.accounts.price_feed,
+            ctx.accounts.fallback_price_feed.as_ref(),
+            Recorders {
+                price_accumulator: ctx.accounts.price_accumulator.as_deref_mut(),
+                requirement_history: ctx.accounts.requirement_history.as_deref_mut().map(|h| &mut **h),
+            },
+            clock.unix_timestamp,
+        )?;
+        
+        Ok(())
+    }
+
+    /// Update the requirement from a posted Pyth pull-oracle price update
+    /// whose feed id matches `pull_feed_id`.
+    pub fn update_balance_requirement_pull(
+        ctx: Context<UpdateBalanceRequirementPull>,
     ) -> Result<()> {
+        let clock = Clock::get()?;
         let state = &mut ctx.accounts.state;
-        let price_feed_acc = &ctx.accounts.price_feed;
         
-        // Get current price from Pyth (in USD with 6 decimals)
-        let price_feed = load_price_feed_from_account_info(price_feed_acc)?;
-        let current_price = price_feed.get_current_price()
-            .ok_or(ErrorCode::NoPriceFound)?
-            .price as u64;
+        require!(state.pull_feed_id != [0u8; 32], ErrorCode::PriceFeedIdMismatch);
+        state.begin_refresh(clock.unix_timestamp)?;
         
-        // Calculate new requirement based on $15 USD target
-        // target_usd_value is in millionths of USD (e.g., 15_000_000 for $15)
-        // current_price is in millionths of USD per token
-        let new_requirement = state.target_usd_value
-            .checked_div(current_price)
-            .ok_or(ErrorCode::MathOverflow)?;
+        let price = read_pyth_pull_price(
+            &ctx.accounts.price_update,
+            &state.pull_feed_id,
+            state.use_ema_price,
+        )?
+        .check_fresh(clock.unix_timestamp, state.max_price_age_secs)?;
+        
+        apply_oracle_price(
+            state,
+            &price,
+            Recorders {
+                price_accumulator: ctx.accounts.price_accumulator.as_deref_mut(),
+                requirement_history: ctx.accounts.requirement_history.as_deref_mut().map(|h| &mut **h),
+            },
+            clock.unix_timestamp,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn set_pull_feed_id(ctx: Context<SetPullFeedId>, feed_id: [u8; 32]) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_feed_id = state.pull_feed_id;
+        state.set_pull_feed_id(feed_id, Clock::get()?.unix_timestamp);
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetPullFeedId,
+            &old_feed_id,
+            &feed_id,
+        )
+    }
+
+    /// Permissionless requirement update, rate limited by `crank_interval_secs`
+    /// and paying the caller `keeper_reward` tokens from the keeper vault
+    /// when the update changed the requirement. A crank whose price was
+    /// rejected, stayed within the hysteresis, or tripped the breaker earns
+    /// nothing.
+    pub fn crank_requirement(ctx: Context<CrankRequirement>) -> Result<()> {
+        let clock = Clock::get()?;
+        let state = &mut ctx.accounts.state;
         
-        // Apply min/max bounds
-        let new_requirement = std::cmp::max(
-            state.min_tokens,
-            std::cmp::min(state.max_tokens, new_requirement),
+        require!(
+            clock.unix_timestamp.saturating_sub(state.last_crank) >= state.crank_interval_secs as i64,
+            ErrorCode::CrankTooSoon
         );
         
-        // Update only if change is significant (>1%)
-        let requirement_change = if state.current_requirement > 0 {
-            ((new_requirement as i128 - state.current_requirement as i128) * 100)
-                .checked_div(state.current_requirement as i128)
-                .unwrap_or(100)
+        let changed = refresh_requirement(
+            state,
+            &ctx.accounts.price_feed,
+            ctx.accounts.fallback_price_feed.as_ref(),
+            Recorders {
+                price_accumulator: ctx.accounts.price_accumulator.as_deref_mut(),
+                requirement_history: ctx.accounts.requirement_history.as_deref_mut().map(|h| &mut **h),
+            },
+            clock.unix_timestamp,
+        )?;
+        state.last_crank = clock.unix_timestamp;
+        
+        // An empty vault should not stop the crank, only the reward
+        let reward = if changed {
+            std::cmp::min(state.keeper_reward, ctx.accounts.keeper_vault.amount)
         } else {
-            100
+            0
         };
+        if reward > 0 {
+            let bump = ctx
+                .bumps
+                .get("state")
+                .copied()
+                .ok_or(anchor_lang::error::ErrorCode::ConstraintSeeds)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.keeper_vault.to_account_info(),
+                        to: ctx.accounts.keeper_token_account.to_account_info(),
+                        authority: ctx.accounts.state.to_account_info(),
+                    },
+                    &[&[b"token_state", &[bump]]],
+                ),
+                reward,
+            )?;
+        }
         
-        if requirement_change.abs() >= 1 {
-            state.current_requirement = new_requirement;
-            state.last_update = Clock::get()?.unix_timestamp;
-            emit!(BalanceRequirementUpdated {
-                new_requirement,
-                price: current_price,
-                timestamp: state.last_update,
-            });
+        emit!(KeeperRewarded {
+            keeper: ctx.accounts.keeper.key(),
+            reward,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_keeper_vault(_ctx: Context<InitializeKeeperVault>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn set_keeper_config(
+        ctx: Context<SetKeeperConfig>,
+        keeper_reward: u64,
+        crank_interval_secs: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_config = (state.keeper_reward, state.crank_interval_secs);
+        state.set_keeper_config(keeper_reward, crank_interval_secs, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetKeeperConfig,
+            &old_config,
+            &(keeper_reward, crank_interval_secs),
+        )
+    }
+
+    /// Price the requirement from the median of the registered feeds passed
+    /// as remaining accounts. Unreadable, stale, or low-confidence feeds are
+    /// left out, as long as `min_median_feeds` prices remain.
+    pub fn update_balance_requirement_median(
+        ctx: Context<UpdateBalanceRequirementMedian>,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let clock = Clock::get()?;
+        let feeds = ctx.remaining_accounts;
+        
+        state.begin_refresh(clock.unix_timestamp)?;
+        require!(
+            !feeds.is_empty() && feeds.len() <= MAX_MEDIAN_FEEDS,
+            ErrorCode::InvalidOracleCount
+        );
+        
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(feeds.len());
+        let mut prices: Vec<u64> = Vec::with_capacity(feeds.len());
+        
+        for account in feeds {
+            require!(!seen.contains(account.key), ErrorCode::DuplicateOracle);
+            seen.push(*account.key);
+            
+            let feed = state
+                .median_feeds
+                .iter()
+                .find(|feed| !feed.is_empty() && feed.address == *account.key)
+                .copied()
+                .ok_or(ErrorCode::InvalidPriceFeed)?;
+            
+            let Ok(price) = read_fresh_price(
+                feed.source,
+                account,
+                clock.unix_timestamp,
+                state.max_price_age_secs,
+                state.use_ema_price,
+            ) else {
+                continue;
+            };
+            
+            if let Ok(Some(current_price)) = state.accept_price(&price, clock.unix_timestamp) {
+                prices.push(current_price);
+            }
         }
         
+        require!(
+            prices.len() >= state.min_median_feeds.max(1) as usize,
+            ErrorCode::NotEnoughOraclePrices
+        );
+        
+        let median = median_price(&mut prices);
+        apply_normalized_price(
+            state,
+            median,
+            Recorders {
+                price_accumulator: ctx.accounts.price_accumulator.as_deref_mut(),
+                requirement_history: ctx.accounts.requirement_history.as_deref_mut().map(|h| &mut **h),
+            },
+            clock.unix_timestamp,
+        )?;
+        
         Ok(())
     }
 
-    pub fn verify_balance(ctx: Context<VerifyBalance>) -> Result<bool> {
-        let state = &ctx.accounts.state;
-        let token_account = &ctx.accounts.token_account;
+    pub fn set_price_feed(ctx: Context<SetPriceFeed>, source: OracleSource) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_feed = (state.oracle_source, state.price_feed);
+        state.set_price_feed(
+            source,
+            ctx.accounts.price_feed.key(),
+            Clock::get()?.unix_timestamp,
+        );
         
-        Ok(token_account.amount >= state.current_requirement)
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetPriceFeed,
+            &old_feed,
+            &(state.oracle_source, state.price_feed),
+        )
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + TokenState::LEN,
-        seeds = [b"token_state"],
-        bump
-    )]
-    pub state: Account<'info, TokenState>,
-    
-    #[account(
-        init,
-        payer = authority,
-        mint::decimals = 9,
-        mint::authority = authority,
-    )]
-    pub mint: Account<'info, Mint>,
-    
-    #[account(
-        init,
-        payer = authority,
-        associated_token::mint = mint,
-        associated_token::authority = authority,
-    )]
-    pub authority_token_account: Account<'info, TokenAccount>,
-    
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Configure the secondary oracle; omitting the feed account disables fallback.
+    pub fn set_fallback_price_feed(
+        ctx: Context<SetFallbackPriceFeed>,
+        source: OracleSource,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_feed = (state.fallback_oracle_source, state.fallback_price_feed);
+        state.set_fallback_price_feed(
+            source,
+            ctx.accounts.price_feed.as_ref().map(|feed| feed.key()),
+            Clock::get()?.unix_timestamp,
+        );
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetFallbackPriceFeed,
+            &old_feed,
+            &(state.fallback_oracle_source, state.fallback_price_feed),
+        )
+    }
 
-#[derive(Accounts)]
-pub struct UpdateBalanceRequirement<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"token_state"],
-        bump,
-        has_one = authority,
-    )]
-    pub state: Account<'info, TokenState>,
-    
-    /// CHECK: Verified in instruction logic
-    pub price_feed: AccountInfo<'info>,
-}
+    pub fn set_median_feeds(
+        ctx: Context<SetMedianFeeds>,
+        feeds: Vec<OracleFeed>,
+        min_feeds: u8,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_feeds = (state.median_feeds, state.min_median_feeds);
+        state.set_median_feeds(feeds, min_feeds, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetMedianFeeds,
+            &old_feeds,
+            &(state.median_feeds, state.min_median_feeds),
+        )
+    }
 
-#[derive(Accounts)]
-pub struct VerifyBalance<'info> {
-    #[account(seeds = [b"token_state"], bump)]
-    pub state: Account<'info, TokenState>,
-    
-    #[account(constraint = token_account.mint == state.mint)]
-    pub token_account: Account<'info, TokenAccount>,
-}
+    pub fn initialize_custom_price_feed(
+        ctx: Context<InitializeCustomPriceFeed>,
+        expo: i32,
+    ) -> Result<()> {
+        let feed = &mut ctx.accounts.custom_price_feed;
+        feed.authority = ctx.accounts.authority.key();
+        feed.expo = expo;
+        feed.price = 0;
+        feed.conf = 0;
+        feed.publish_time = 0;
+        
+        Ok(())
+    }
 
-#[account]
-pub struct TokenState {
-    pub authority: Pubkey,
-    pub mint: Pubkey,
-    pub target_usd_value: u64,
-    pub min_tokens: u64,
-    pub max_tokens: u64,
-    pub current_requirement: u64,
-    pub last_update: i64,
-}
+    pub fn submit_custom_price(
+        ctx: Context<SubmitCustomPrice>,
+        price: i64,
+        conf: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        let feed = &mut ctx.accounts.custom_price_feed;
+        feed.price = price;
+        feed.conf = conf;
+        feed.publish_time = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
 
-impl TokenState {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8;
-}
+    pub fn initialize_price_accumulator(ctx: Context<InitializePriceAccumulator>) -> Result<()> {
+        let accumulator = &mut ctx.accounts.price_accumulator;
+        accumulator.last_price = 0;
+        accumulator.last_timestamp = 0;
+        accumulator.cumulative_price = 0;
+        accumulator.head = 0;
+        accumulator.count = 0;
+        accumulator.observations = [PriceObservation::default(); PRICE_OBSERVATIONS];
+        
+        Ok(())
+    }
 
-#[event]
-pub struct BalanceRequirementUpdated {
-    pub new_requirement: u64,
-    pub price: u64,
+    pub fn initialize_requirement_history(ctx: Context<InitializeRequirementHistory>) -> Result<()> {
+        let history = &mut ctx.accounts.requirement_history;
+        history.head = 0;
+        history.count = 0;
+        
+        // From here on every update path must record into the history
+        ctx.accounts.state.requirement_history_enabled = true;
+        
+        Ok(())
+    }
+
+    /// Set the TWAP window used for requirement pricing; zero prices from spot.
+    pub fn set_twap_window(ctx: Context<SetTwapWindow>, window_secs: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_window_secs = state.twap_window_secs;
+        state.set_twap_window(window_secs, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetTwapWindow,
+            &old_window_secs,
+            &window_secs,
+        )
+    }
+
+    /// Set the price move that trips the circuit breaker; zero disables it.
+    pub fn set_circuit_breaker(ctx: Context<SetCircuitBreaker>, threshold_bps: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_threshold_bps = state.circuit_breaker_bps;
+        state.set_circuit_breaker(threshold_bps, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetCircuitBreaker,
+            &old_threshold_bps,
+            &threshold_bps,
+        )
+    }
+
+    /// Resume requirement updates after the circuit breaker tripped. The next
+    /// accepted price becomes the new reference price.
+    pub fn reset_circuit_breaker(ctx: Context<SetCircuitBreaker>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.circuit_breaker_tripped, ErrorCode::CircuitBreakerNotTripped);
+        
+        state.circuit_breaker_tripped = false;
+        state.last_price = 0;
+        
+        emit!(CircuitBreakerReset {
+            authority: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ResetCircuitBreaker,
+            &true,
+            &false,
+        )
+    }
+
+    /// Hold the requirement at what `price` implies while the circuit
+    /// breaker is tripped, for `duration_secs`. Only the authority, which
+    /// is the governance once handed over, or a guardian quorum signing as
+    /// the remaining accounts may override. A later override before the
+    /// expiry corrects the price but keeps the expiry.
+    pub fn override_price(
+        ctx: Context<OverridePrice>,
+        price: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let caller = ctx.accounts.caller.key();
+        let state = &mut ctx.accounts.state;
+        require!(state.circuit_breaker_tripped, ErrorCode::CircuitBreakerNotTripped);
+        let guardian_approvals = if caller == state.authority {
+            0
+        } else {
+            ctx.accounts
+                .guardian_council
+                .as_ref()
+                .ok_or(ErrorCode::Unauthorized)?
+                .check_approvals(ctx.remaining_accounts, now)?
+        };
+        require!(price > 0, ErrorCode::InvalidPrice);
+        
+        let price_override = &mut ctx.accounts.price_override;
+        if price_override.expires_at == 0 {
+            require!(
+                (1..=MAX_PRICE_OVERRIDE_SECS).contains(&duration_secs),
+                ErrorCode::InvalidParameter
+            );
+            price_override.expires_at = now
+                .checked_add(duration_secs)
+                .ok_or(ErrorCode::MathOverflow)?;
+        } else {
+            require!(now < price_override.expires_at, ErrorCode::PriceOverrideExpired);
+        }
+        let old_price = price_override.price;
+        price_override.price = price;
+        price_override.set_by = caller;
+        price_override.set_at = now;
+        
+        let requirement = state.spot_requirement(price)?;
+        state.current_requirement = requirement;
+        state.last_update = now;
+        
+        emit!(PriceOverridden {
+            price,
+            requirement,
+            expires_at: price_override.expires_at,
+            set_by: caller,
+            guardian_approvals,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            caller,
+            AdminAction::OverridePrice,
+            &old_price,
+            &(price, price_override.expires_at),
+        )
+    }
+
+    /// End an expired manual price and reset the circuit breaker, so the
+    /// next accepted oracle price sets the requirement again. Anyone may
+    /// call this.
+    pub fn resume_oracle_pricing(ctx: Context<ResumeOraclePricing>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let price_override = &mut ctx.accounts.price_override;
+        require!(
+            price_override.expires_at != 0 && now >= price_override.expires_at,
+            ErrorCode::PriceOverrideActive
+        );
+        price_override.expires_at = 0;
+        
+        let state = &mut ctx.accounts.state;
+        state.circuit_breaker_tripped = false;
+        state.last_price = 0;
+        
+        emit!(OraclePricingResumed {
+            override_price: price_override.price,
+            resumed_by: ctx.accounts.payer.key(),
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Update tunable requirement parameters; fields left as `None` are unchanged.
+    pub fn update_parameters(
+        ctx: Context<UpdateParameters>,
+        params: UpdateParametersArgs,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(
+            state.timelock_delay_secs == 0 || !params.is_timelocked(),
+            ErrorCode::TimelockRequired
+        );
+        let old_values = state.parameter_values();
+        state.apply_parameters(
+            &params,
+            ctx.accounts.authority.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::UpdateParameters,
+            &old_values,
+            &state.parameter_values(),
+        )
+    }
+
+    /// Lengthen the timelock immediately; shortening it is itself a
+    /// timelocked change.
+    pub fn set_timelock_delay(ctx: Context<SetTimelockDelay>, delay_secs: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(delay_secs >= state.timelock_delay_secs, ErrorCode::TimelockRequired);
+        let old_delay_secs = state.timelock_delay_secs;
+        state.set_timelock_delay(delay_secs, Clock::get()?.unix_timestamp);
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetTimelockDelay,
+            &old_delay_secs,
+            &delay_secs,
+        )
+    }
+
+    /// Queue a formula change that anyone may execute after `timelock_delay_secs`.
+    /// The change is validated against the state at execution time.
+    pub fn queue_pending_change(
+        ctx: Context<QueuePendingChange>,
+        change: ParameterChange,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let state = &mut ctx.accounts.state;
+        let pending = &mut ctx.accounts.pending_change;
+        pending.id = state.next_change_id;
+        pending.proposer = ctx.accounts.authority.key();
+        pending.queued_at = now;
+        pending.eta = now
+            .checked_add(state.timelock_delay_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pending.change = change;
+        state.next_change_id = state
+            .next_change_id
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(PendingChangeQueued {
+            id: pending.id,
+            change: pending.change.clone(),
+            eta: pending.eta,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::QueueChange,
+            &(),
+            &(pending.id, pending.eta, pending.change.clone()),
+        )
+    }
+
+    /// Apply a queued change once its delay has passed. Oracle changes must
+    /// pass the new feed account so its owner can be checked.
+    pub fn execute_pending_change(ctx: Context<ExecutePendingChange>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pending = &ctx.accounts.pending_change;
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(now >= pending.eta, ErrorCode::TimelockNotElapsed);
+        
+        let state = &mut ctx.accounts.state;
+        apply_parameter_change(
+            state,
+            &pending.change,
+            pending.proposer,
+            now,
+            ChangeAccounts {
+                price_feed: ctx.accounts.price_feed.as_ref(),
+                treasury: ctx.accounts.treasury.as_deref_mut(),
+                bridge_config: ctx.accounts.bridge_config.as_deref_mut(),
+                liquidity_config: ctx.accounts.liquidity_config.as_deref_mut(),
+                interest_mint: ctx
+                    .accounts
+                    .interest_mint
+                    .as_ref()
+                    .map(|mint| mint.to_account_info()),
+                mint_authority: ctx.accounts.mint_authority.as_ref().map(|mint_authority| {
+                    (
+                        mint_authority.to_account_info(),
+                        *ctx.bumps.get("mint_authority").unwrap(),
+                    )
+                }),
+                token_2022_program: ctx
+                    .accounts
+                    .token_2022_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+            },
+        )?;
+        
+        emit!(PendingChangeExecuted {
+            id: pending.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.executor.key(),
+            AdminAction::ExecuteChange,
+            &pending.id,
+            &pending.change,
+        )
+    }
+
+    /// Halt requirement updates and queued change execution. Balance
+    /// verification keeps working against the last requirement.
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        set_paused(ctx.accounts, true)
+    }
+
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        set_paused(ctx.accounts, false)
+    }
+
+    /// Start handing the authority to `new_authority`, which takes over once
+    /// it calls `accept_authority`. Either side may be a program-derived
+    /// address (a Squads vault, a Realms governance's native treasury)
+    /// signing through CPI, since admin instructions only require the
+    /// authority to sign, not to be a keypair.
+    pub fn set_pending_authority(
+        ctx: Context<SetPendingAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let old_pending_authority = state.pending_authority;
+        state.pending_authority = new_authority;
+        
+        emit!(AuthorityTransferStarted {
+            authority: state.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetPendingAuthority,
+            &old_pending_authority,
+            &new_authority,
+        )
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let old_authority = state.authority;
+        state.authority = state.pending_authority;
+        state.pending_authority = Pubkey::default();
+        
+        emit!(AuthorityTransferred {
+            old_authority,
+            new_authority: state.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.new_authority.key(),
+            AdminAction::AcceptAuthority,
+            &old_authority,
+            &state.authority,
+        )
+    }
+
+    /// Upgrade the state account to the current layout, reallocating it to
+    /// fit. Each known version gets its own arm; add one whenever the
+    /// layout changes.
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        let state_info = ctx.accounts.state.to_account_info();
+        let from_version = state_version(&state_info.try_borrow_data()?)?;
+        
+        let state = match from_version {
+            0 => {
+                let legacy = TokenStateV0::deserialize(&mut &state_info.try_borrow_data()?[8..])
+                    .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotDeserialize))?;
+                let price_feed = ctx
+                    .accounts
+                    .price_feed
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidPriceFeed)?;
+                legacy.upgrade(price_feed.key())
+            }
+            STATE_VERSION => return err!(ErrorCode::StateAlreadyCurrent),
+            _ => return err!(ErrorCode::UnknownStateVersion),
+        };
+        require_keys_eq!(
+            state.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::Unauthorized
+        );
+        
+        resize_account(
+            &state_info,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            8 + TokenState::LEN,
+        )?;
+        state.try_serialize(&mut &mut state_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(StateMigrated {
+            from_version,
+            to_version: STATE_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Decommission a devnet or test deployment, returning rent from the
+    /// state and any auxiliary accounts passed to `recipient`. Only allowed
+    /// once nothing is staked and no more tokens can be minted, or none
+    /// exist. Every vault the state signs for would be stranded with it, so
+    /// mainnet builds refuse outright.
+    pub fn close_state(ctx: Context<CloseState>) -> Result<()> {
+        require!(cfg!(feature = "devnet"), ErrorCode::CloseStateDisabled);
+        if !ctx.accounts.stake_pool.data_is_empty() {
+            let stake_pool = Account::<StakePool>::try_from(&ctx.accounts.stake_pool)?;
+            require!(stake_pool.total_staked == 0, ErrorCode::StakeStillOpen);
+        }
+        
+        let mint = &ctx.accounts.mint;
+        require!(
+            mint.mint_authority.is_none() || mint.supply == 0,
+            ErrorCode::SupplyStillMintable
+        );
+        
+        // The vault is owned by the token program, so it is swept and closed
+        // through CPI rather than with `close =`
+        if let Some(keeper_vault) = &ctx.accounts.keeper_vault {
+            let bump = *ctx.bumps.get("state").unwrap();
+            if keeper_vault.amount > 0 {
+                let destination = ctx
+                    .accounts
+                    .recipient_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::KeeperVaultNotEmpty)?;
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Transfer {
+                            from: keeper_vault.to_account_info(),
+                            to: destination.to_account_info(),
+                            authority: ctx.accounts.state.to_account_info(),
+                        },
+                        &[&[b"token_state", &[bump]]],
+                    ),
+                    keeper_vault.amount,
+                )?;
+            }
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::CloseAccount {
+                    account: keeper_vault.to_account_info(),
+                    destination: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ))?;
+        }
+        
+        emit!(StateClosed {
+            authority: ctx.accounts.authority.key(),
+            recipient: ctx.accounts.recipient.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_role_registry(ctx: Context<InitializeRoleRegistry>) -> Result<()> {
+        ctx.accounts.role_registry.members = [RoleMember::EMPTY; MAX_ROLE_MEMBERS];
+        Ok(())
+    }
+
+    /// Admins manage every role, so hot keys can be rotated without the
+    /// authority key.
+    pub fn grant_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+        ctx.accounts.role_registry.grant(role, member)?;
+        
+        emit!(RoleGranted {
+            role,
+            member,
+            granted_by: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::GrantRole,
+            &(),
+            &(role, member),
+        )
+    }
+
+    pub fn revoke_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+        ctx.accounts.role_registry.revoke(role, &member)?;
+        
+        emit!(RoleRevoked {
+            role,
+            member,
+            revoked_by: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::RevokeRole,
+            &(role, member),
+            &(),
+        )
+    }
+
+    pub fn cancel_pending_change(ctx: Context<CancelPendingChange>) -> Result<()> {
+        let pending = &ctx.accounts.pending_change;
+        emit!(PendingChangeCancelled {
+            id: pending.id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CancelChange,
+            &(pending.id, pending.change.clone()),
+            &(),
+        )
+    }
+
+    /// Hand the mint authority from the authority wallet to the program's
+    /// `mint_authority` PDA, after which tokens can only be minted through
+    /// `mint_tokens`.
+    pub fn transfer_mint_authority(ctx: Context<TransferMintAuthority>) -> Result<()> {
+        let mint_authority = ctx.accounts.mint_authority.key();
+        token::set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::SetAuthority {
+                    current_authority: ctx.accounts.authority.to_account_info(),
+                    account_or_mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            AuthorityType::MintTokens,
+            Some(mint_authority),
+        )?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::TransferMintAuthority,
+            &ctx.accounts.authority.key(),
+            &mint_authority,
+        )
+    }
+
+    /// Create the mint's Metaplex metadata so wallets show its name and
+    /// logo. The `mint_authority` PDA signs as mint authority, so this has
+    /// to happen after `transfer_mint_authority` and before
+    /// `finalize_supply`, and stays the update authority.
+    pub fn create_metadata(ctx: Context<CreateMetadata>, args: TokenMetadataArgs) -> Result<()> {
+        args.validate()?;
+        let accounts = &ctx.accounts;
+        let mint_authority = accounts.mint_authority.to_account_info();
+        token_metadata::create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                accounts.token_metadata_program.to_account_info(),
+                token_metadata::CreateMetadataAccountsV3 {
+                    metadata: accounts.metadata.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    mint_authority: mint_authority.clone(),
+                    payer: accounts.authority.to_account_info(),
+                    update_authority: mint_authority,
+                    system_program: accounts.system_program.to_account_info(),
+                    rent: accounts.rent.to_account_info(),
+                },
+                &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+            ),
+            args.to_data(),
+            true,
+            true,
+            None,
+        )?;
+        
+        emit!(MetadataUpdated {
+            mint: accounts.mint.key(),
+            metadata: accounts.metadata.key(),
+            name: args.name.clone(),
+            symbol: args.symbol.clone(),
+            uri: args.uri.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CreateMetadata,
+            &(),
+            &args,
+        )
+    }
+
+    /// Replace the name, symbol, and URI in the mint's metadata. Works
+    /// after `finalize_supply` too, since only the update authority signs.
+    pub fn update_metadata(ctx: Context<UpdateMetadata>, args: TokenMetadataArgs) -> Result<()> {
+        args.validate()?;
+        let accounts = &ctx.accounts;
+        let old_args = TokenMetadataArgs::from_metadata(&accounts.metadata);
+        token_metadata::update_metadata_accounts_v2(
+            CpiContext::new_with_signer(
+                accounts.token_metadata_program.to_account_info(),
+                token_metadata::UpdateMetadataAccountsV2 {
+                    metadata: accounts.metadata.to_account_info(),
+                    update_authority: accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+            ),
+            None,
+            Some(args.to_data()),
+            None,
+            None,
+        )?;
+        
+        emit!(MetadataUpdated {
+            mint: accounts.mint.key(),
+            metadata: accounts.metadata.key(),
+            name: args.name.clone(),
+            symbol: args.symbol.clone(),
+            uri: args.uri.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::UpdateMetadata,
+            &old_args,
+            &args,
+        )
+    }
+
+    pub fn mint_tokens(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
+        let state = &ctx.accounts.state;
+        require!(!state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        
+        let old_supply = ctx.accounts.mint.supply;
+        let new_supply = state.check_mint(old_supply, amount)?;
+        
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(TokensMinted {
+            minter: ctx.accounts.authority.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            supply: new_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::MintTokens,
+            &old_supply,
+            &(new_supply, ctx.accounts.destination.key()),
+        )
+    }
+
+    /// Mint to many wallets at once as Light Protocol compressed tokens,
+    /// which need no token account, so airdrops and micro-rewards cost no
+    /// rent per recipient. Counts against the supply cap like `mint_tokens`.
+    /// The mint needs a compressed-token pool, created once with the
+    /// compressed-token program's `create_token_pool`.
+    pub fn mint_compressed(
+        ctx: Context<MintCompressed>,
+        recipients: Vec<Pubkey>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        let state = &ctx.accounts.state;
+        require!(!state.paused, ErrorCode::ProgramPaused);
+        require!(
+            !recipients.is_empty()
+                && recipients.len() == amounts.len()
+                && recipients.len() <= MAX_COMPRESSED_RECIPIENTS
+                && amounts.iter().all(|amount| *amount > 0),
+            ErrorCode::InvalidParameter
+        );
+        let total = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let old_supply = ctx.accounts.mint.supply;
+        let new_supply = state.check_mint(old_supply, total)?;
+        
+        let accounts = &ctx.accounts;
+        mint_compressed_to(
+            &CompressedMintTo {
+                fee_payer: &accounts.authority.to_account_info(),
+                authority: &accounts.mint_authority.to_account_info(),
+                cpi_authority_pda: &accounts.cpi_authority_pda,
+                mint: &accounts.mint.to_account_info(),
+                token_pool_pda: &accounts.token_pool_pda,
+                token_program: &accounts.token_program.to_account_info(),
+                light_system_program: &accounts.light_system_program,
+                registered_program_pda: &accounts.registered_program_pda,
+                noop_program: &accounts.noop_program,
+                account_compression_authority: &accounts.account_compression_authority,
+                account_compression_program: &accounts.account_compression_program,
+                merkle_tree: &accounts.merkle_tree,
+                compressed_token_program: &accounts.compressed_token_program,
+                system_program: &accounts.system_program.to_account_info(),
+            },
+            &recipients,
+            &amounts,
+            &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+        )?;
+        
+        emit!(CompressedTokensMinted {
+            minter: accounts.authority.key(),
+            recipients: recipients.len() as u16,
+            amount: total,
+            supply: new_supply,
+            merkle_tree: accounts.merkle_tree.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::MintCompressed,
+            &old_supply,
+            &(new_supply, recipients.len() as u16),
+        )
+    }
+
+    /// Move compressed tokens into the owner's associated token account of
+    /// the mint, where they count towards verification like any other
+    /// balance; send `verify_balance` after this in the same transaction to
+    /// refresh the receipt at once. `transfer_data` and the remaining
+    /// accounts are the compressed-token `transfer` Light's SDK builds for
+    /// the decompression, validity proof included.
+    pub fn decompress<'info>(
+        ctx: Context<'_, '_, '_, 'info, Decompress<'info>>,
+        transfer_data: Vec<u8>,
+    ) -> Result<()> {
+        let before = ctx.accounts.destination.amount;
+        forward_compressed_transfer(
+            &ctx.accounts.compressed_token_program,
+            ctx.remaining_accounts,
+            transfer_data,
+        )?;
+        ctx.accounts.destination.reload()?;
+        let balance = ctx.accounts.destination.amount;
+        require!(balance > before, ErrorCode::NothingDecompressed);
+        
+        emit!(TokensDecompressed {
+            owner: ctx.accounts.owner.key(),
+            destination: ctx.accounts.destination.key(),
+            amount: balance - before,
+            balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Permanently end minting by revoking the mint authority held by the
+    /// program PDA; the current supply becomes the final supply.
+    pub fn finalize_supply(ctx: Context<FinalizeSupply>) -> Result<()> {
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::SetAuthority {
+                    current_authority: ctx.accounts.mint_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.mint.to_account_info(),
+                },
+                &[&[b"mint_authority", &[bump]]],
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+        
+        let state = &mut ctx.accounts.state;
+        state.supply_finalized = true;
+        
+        emit!(SupplyFinalized {
+            authority: ctx.accounts.authority.key(),
+            supply: ctx.accounts.mint.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::FinalizeSupply,
+            &false,
+            &true,
+        )
+    }
+
+    /// Start moving holders to a token-2022 mint created at
+    /// `[b"token_2022_mint"]` with the legacy mint's decimals, the program's
+    /// mint authority, and the extensions `args` asks for. The treasury's
+    /// vault of the new mint, which receives the transfer fees, is created
+    /// alongside. Holders then swap 1:1 through `migrate_tokens`; both
+    /// mints count towards access meanwhile.
+    pub fn begin_mint_migration(
+        ctx: Context<BeginMintMigration>,
+        args: Token2022MintArgs,
+    ) -> Result<()> {
+        require!(
+            args.transfer_fee_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let accounts = &ctx.accounts;
+        create_fee_mint(
+            &accounts.new_mint,
+            *ctx.bumps.get("new_mint").unwrap(),
+            &accounts.authority,
+            &accounts.mint_authority.key(),
+            &accounts.treasury.key(),
+            accounts.mint.decimals,
+            &args,
+            &accounts.token_2022_program,
+            &accounts.system_program,
+        )?;
+        create_fee_vault(
+            &accounts.treasury_vault,
+            *ctx.bumps.get("treasury_vault").unwrap(),
+            &accounts.new_mint,
+            &accounts.treasury.key(),
+            &accounts.authority,
+            &accounts.token_2022_program,
+            &accounts.system_program,
+        )?;
+        if args.frozen_by_default {
+            token_2022::thaw_account(CpiContext::new_with_signer(
+                accounts.token_2022_program.to_account_info(),
+                token_2022::ThawAccount {
+                    account: accounts.treasury_vault.to_account_info(),
+                    mint: accounts.new_mint.to_account_info(),
+                    authority: accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+            ))?;
+        }
+        
+        let state = &mut ctx.accounts.state;
+        let new_mint = ctx.accounts.new_mint.key();
+        let now = Clock::get()?.unix_timestamp;
+        state.migration_mint = new_mint;
+        state.migration_started_at = now;
+        
+        emit!(MintMigrationStarted {
+            authority: ctx.accounts.authority.key(),
+            legacy_mint: state.mint,
+            new_mint,
+            args,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::BeginMintMigration,
+            &Pubkey::default(),
+            &(new_mint, args),
+        )
+    }
+
+    /// Swap `amount` legacy tokens for the token-2022 mint 1:1, burning
+    /// them from the holder's account and minting the same amount to
+    /// `destination` under the program's mint authority. The swap leaves
+    /// the combined supply unchanged, so it works after the legacy supply
+    /// is finalized and does not count towards `total_burned`.
+    pub fn migrate_tokens(ctx: Context<MigrateTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.source.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_2022_program.to_account_info(),
+                token_2022::MintTo {
+                    mint: ctx.accounts.new_mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        let state = &mut ctx.accounts.state;
+        state.migrated_amount = state
+            .migrated_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(TokensMigrated {
+            owner: ctx.accounts.owner.key(),
+            source: ctx.accounts.source.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            migrated_amount: state.migrated_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Sweep the transfer fees withheld in the token accounts passed as
+    /// remaining accounts into the token-2022 mint. Anyone can call this.
+    pub fn harvest_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, HarvestFees<'info>>,
+    ) -> Result<()> {
+        require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InvalidParameter);
+        let mint = ctx.accounts.new_mint.to_account_info();
+        let before = u64::from(transfer_fee_config(&mint)?.withheld_amount);
+        harvest_to_mint(
+            &mint,
+            ctx.remaining_accounts,
+            &ctx.accounts.token_2022_program,
+        )?;
+        let withheld = u64::from(transfer_fee_config(&mint)?.withheld_amount);
+        
+        emit!(FeesHarvested {
+            mint: mint.key(),
+            accounts: ctx.remaining_accounts.len() as u16,
+            harvested: withheld.saturating_sub(before),
+            withheld,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw the fees harvested into the token-2022 mint to the
+    /// treasury's vault of it. Anyone can call this, as the fees can only
+    /// go to the treasury; they leave it through `withdraw_treasury_tokens`.
+    pub fn withdraw_withheld(ctx: Context<WithdrawWithheld>) -> Result<()> {
+        let mint = ctx.accounts.new_mint.to_account_info();
+        let amount = u64::from(transfer_fee_config(&mint)?.withheld_amount);
+        require!(amount > 0, ErrorCode::NoWithheldFees);
+        
+        withdraw_from_mint(
+            &mint,
+            &ctx.accounts.treasury_vault.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            *ctx.bumps.get("treasury").unwrap(),
+            &ctx.accounts.token_2022_program,
+        )?;
+        
+        ctx.accounts.treasury_vault.reload()?;
+        emit!(WithheldFeesWithdrawn {
+            mint: mint.key(),
+            vault: ctx.accounts.treasury_vault.key(),
+            amount,
+            vault_balance: ctx.accounts.treasury_vault.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Change the token-2022 mint's transfer fee. Token-2022 keeps charging
+    /// the old fee until two epochs have passed.
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        transfer_fee_bps: u16,
+        max_transfer_fee: u64,
+    ) -> Result<()> {
+        require!(
+            transfer_fee_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let mint = ctx.accounts.new_mint.to_account_info();
+        let old_fee = transfer_fee_config(&mint)?.newer_transfer_fee;
+        let old_fee = (
+            u16::from(old_fee.transfer_fee_basis_points),
+            u64::from(old_fee.maximum_fee),
+        );
+        
+        set_fee(
+            &mint,
+            &ctx.accounts.mint_authority,
+            *ctx.bumps.get("mint_authority").unwrap(),
+            transfer_fee_bps,
+            max_transfer_fee,
+            &ctx.accounts.token_2022_program,
+        )?;
+        
+        emit!(TransferFeeUpdated {
+            old_transfer_fee_bps: old_fee.0,
+            old_max_transfer_fee: old_fee.1,
+            new_transfer_fee_bps: transfer_fee_bps,
+            new_max_transfer_fee: max_transfer_fee,
+            effective_epoch: u64::from(transfer_fee_config(&mint)?.newer_transfer_fee.epoch),
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetTransferFee,
+            &old_fee,
+            &(transfer_fee_bps, max_transfer_fee),
+        )
+    }
+
+    /// Change the yearly rate the token-2022 mint's interest-bearing
+    /// extension accrues holders' UI amounts at. With a timelock set this
+    /// goes through `queue_change` instead.
+    pub fn set_interest_rate(ctx: Context<SetInterestRate>, rate_bps: i16) -> Result<()> {
+        require!(
+            ctx.accounts.state.timelock_delay_secs == 0,
+            ErrorCode::TimelockRequired
+        );
+        let old_rate_bps = apply_interest_rate(
+            &ctx.accounts.new_mint.to_account_info(),
+            &ctx.accounts.mint_authority,
+            *ctx.bumps.get("mint_authority").unwrap(),
+            rate_bps,
+            &ctx.accounts.token_2022_program,
+        )?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetInterestRate,
+            &old_rate_bps,
+            &rate_bps,
+        )
+    }
+
+    /// Thaw the token-2022 account of a wallet that has access. In
+    /// compliance mode the mint creates every token account frozen, so only
+    /// verified wallets can hold, migrate into, or move the token. Anyone
+    /// can call this for any such account.
+    pub fn thaw_on_verification(ctx: Context<ThawOnVerification>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let wallet = accounts.token_account.owner;
+        let now = Clock::get()?.unix_timestamp;
+        let status = access_status(
+            wallet,
+            &accounts.receipt,
+            &accounts.access_override,
+            1,
+            now,
+        )?;
+        require!(status.has_access, ErrorCode::AccessRequired);
+        
+        token_2022::thaw_account(CpiContext::new_with_signer(
+            accounts.token_2022_program.to_account_info(),
+            token_2022::ThawAccount {
+                account: accounts.token_account.to_account_info(),
+                mint: accounts.new_mint.to_account_info(),
+                authority: accounts.mint_authority.to_account_info(),
+            },
+            &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+        ))?;
+        
+        emit!(TokenAccountThawed {
+            wallet,
+            token_account: accounts.token_account.key(),
+            tier: status.tier,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Approve a wallet's token-2022 account for confidential transfers
+    /// once the wallet has access, when the mint does not approve new
+    /// accounts on its own. Anyone can call this for any such account.
+    pub fn approve_confidential_account(ctx: Context<ApproveConfidentialAccount>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let wallet = accounts.token_account.owner;
+        let now = Clock::get()?.unix_timestamp;
+        let status = access_status(
+            wallet,
+            &accounts.receipt,
+            &accounts.access_override,
+            1,
+            now,
+        )?;
+        require!(status.has_access, ErrorCode::AccessRequired);
+        
+        approve_account(
+            &accounts.token_account.to_account_info(),
+            &accounts.new_mint.to_account_info(),
+            &accounts.mint_authority,
+            *ctx.bumps.get("mint_authority").unwrap(),
+            &accounts.token_2022_program,
+        )?;
+        
+        emit!(ConfidentialAccountApproved {
+            wallet,
+            token_account: accounts.token_account.key(),
+            tier: status.tier,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Propose recovering `amount` from a compromised or fraudulent
+    /// token-2022 account. It can be executed once the dispute window, or
+    /// the timelock delay if longer, has passed; see `Clawback` for how
+    /// the owner disputes it.
+    pub fn propose_clawback(
+        ctx: Context<ProposeClawback>,
+        amount: u64,
+        reason: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        require!(
+            has_permanent_delegate(
+                &ctx.accounts.new_mint.to_account_info(),
+                ctx.accounts.clawback_authority.key,
+            )?,
+            ErrorCode::ClawbackUnavailable
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let delay = CLAWBACK_DISPUTE_WINDOW_SECS.max(ctx.accounts.state.timelock_delay_secs as i64);
+        
+        let clawback = &mut ctx.accounts.clawback;
+        clawback.source = ctx.accounts.source.key();
+        clawback.owner = ctx.accounts.source.owner;
+        clawback.amount = amount;
+        clawback.reason = reason;
+        clawback.proposer = ctx.accounts.authority.key();
+        clawback.proposed_at = now;
+        clawback.eta = now.checked_add(delay).ok_or(ErrorCode::MathOverflow)?;
+        clawback.dispute = [0; 32];
+        clawback.disputed_at = 0;
+        
+        emit!(ClawbackProposed {
+            source: clawback.source,
+            owner: clawback.owner,
+            amount,
+            reason,
+            eta: clawback.eta,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ProposeClawback,
+            &(),
+            &(clawback.source, amount, reason),
+        )
+    }
+
+    /// Dispute a clawback of the caller's account before it can run,
+    /// citing the hash of the evidence submitted off chain. This records
+    /// the dispute and holds the clawback for the review period.
+    pub fn dispute_clawback(ctx: Context<DisputeClawback>, evidence: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let clawback = &mut ctx.accounts.clawback;
+        require!(evidence != [0; 32], ErrorCode::InvalidParameter);
+        require!(
+            now < clawback.eta && !clawback.is_disputed(),
+            ErrorCode::ClawbackDisputeClosed
+        );
+        clawback.dispute = evidence;
+        clawback.disputed_at = now;
+        clawback.eta = clawback.eta.max(now.saturating_add(CLAWBACK_REVIEW_SECS));
+        
+        emit!(ClawbackDisputed {
+            source: clawback.source,
+            owner: clawback.owner,
+            evidence,
+            eta: clawback.eta,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Move a proposed clawback's tokens to the treasury vault as the
+    /// mint's permanent delegate, up to what the account still holds. Pass
+    /// the transfer hook's extra accounts as remaining accounts.
+    pub fn execute_clawback<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteClawback<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &ctx.accounts;
+        let clawback = &accounts.clawback;
+        require!(now >= clawback.eta, ErrorCode::ClawbackNotReady);
+        
+        let recovered = clawback.amount.min(accounts.source.amount);
+        let source = accounts.source.to_account_info();
+        let mint = accounts.new_mint.to_account_info();
+        let mint_authority_seeds: &[&[&[u8]]] =
+            &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]];
+        let frozen = accounts.source.is_frozen();
+        if frozen {
+            token_2022::thaw_account(CpiContext::new_with_signer(
+                accounts.token_2022_program.to_account_info(),
+                token_2022::ThawAccount {
+                    account: source.clone(),
+                    mint: mint.clone(),
+                    authority: accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ))?;
+        }
+        claw_back(
+            &source,
+            &mint,
+            &accounts.treasury_vault.to_account_info(),
+            &accounts.clawback_authority,
+            *ctx.bumps.get("clawback_authority").unwrap(),
+            ctx.remaining_accounts,
+            recovered,
+            accounts.new_mint.decimals,
+            &accounts.token_2022_program,
+        )?;
+        if frozen {
+            token_2022::freeze_account(CpiContext::new_with_signer(
+                accounts.token_2022_program.to_account_info(),
+                token_2022::FreezeAccount {
+                    account: source,
+                    mint,
+                    authority: accounts.mint_authority.to_account_info(),
+                },
+                mint_authority_seeds,
+            ))?;
+        }
+        
+        let amount = clawback.amount;
+        emit!(ClawbackExecuted {
+            source: clawback.source,
+            owner: clawback.owner,
+            amount,
+            recovered,
+            destination: accounts.treasury_vault.key(),
+            disputed: clawback.is_disputed(),
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ExecuteClawback,
+            &amount,
+            &recovered,
+        )
+    }
+
+    /// Drop a proposed clawback, typically after upholding a dispute
+    pub fn cancel_clawback(ctx: Context<CancelClawback>) -> Result<()> {
+        let clawback = &ctx.accounts.clawback;
+        emit!(ClawbackCancelled {
+            source: clawback.source,
+            owner: clawback.owner,
+            amount: clawback.amount,
+            disputed: clawback.is_disputed(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CancelClawback,
+            &(clawback.source, clawback.amount),
+            &(),
+        )
+    }
+
+    pub fn initialize_transfer_hook_config(
+        ctx: Context<InitializeTransferHookConfig>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.transfer_hook_config;
+        config.max_wallet_bps = 0;
+        config.exempt = [Pubkey::default(); MAX_HOOK_EXEMPTIONS];
+        Ok(())
+    }
+
+    /// Cap what one wallet may hold after receiving a transfer of the
+    /// token-2022 mint at `max_wallet_bps` of the supply; zero lifts the cap
+    pub fn set_max_wallet(ctx: Context<SetTransferHookConfig>, max_wallet_bps: u64) -> Result<()> {
+        require!(max_wallet_bps <= BPS_DENOMINATOR, ErrorCode::InvalidParameter);
+        let config = &mut ctx.accounts.transfer_hook_config;
+        let old_max_wallet = config.max_wallet_bps;
+        config.max_wallet_bps = max_wallet_bps;
+        
+        emit!(MaxWalletUpdated {
+            old_max_wallet_bps: old_max_wallet,
+            new_max_wallet_bps: max_wallet_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetMaxWallet,
+            &old_max_wallet,
+            &max_wallet_bps,
+        )
+    }
+
+    /// Exempt `owner`'s accounts from the max-wallet cap, or end the
+    /// exemption
+    pub fn set_max_wallet_exemption(
+        ctx: Context<SetTransferHookConfig>,
+        owner: Pubkey,
+        exempt: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.transfer_hook_config;
+        let was_exempt = config.is_exempt(&owner);
+        config.set_exemption(owner, exempt)?;
+        
+        emit!(MaxWalletExemptionUpdated {
+            owner,
+            exempt,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetMaxWalletExemption,
+            &(owner, was_exempt),
+            &(owner, exempt),
+        )
+    }
+
+    /// Plug the mint into a Wormhole NTT manager in burning mode, which
+    /// signs bridge transfers as `bridge_authority`. Every chain starts
+    /// closed until `set_bridge_limits` opens it.
+    pub fn initialize_bridge_config(
+        ctx: Context<InitializeBridgeConfig>,
+        bridge_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.bridge_config;
+        config.bridge_authority = bridge_authority;
+        config.outstanding = 0;
+        config.sequence = 0;
+        config.window_start = Clock::get()?.unix_timestamp;
+        config.chains = [BridgeChain::EMPTY; MAX_BRIDGE_CHAINS];
+        Ok(())
+    }
+
+    /// Set how much may be bridged to and from `chain_id` per window.
+    /// Raising either limit waits out the timelock when one is set.
+    pub fn set_bridge_limits(
+        ctx: Context<SetBridgeLimits>,
+        chain_id: u16,
+        outbound_limit: u64,
+        inbound_limit: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.bridge_config;
+        let old_limits = config.limits(chain_id);
+        require!(
+            (outbound_limit <= old_limits.0 && inbound_limit <= old_limits.1)
+                || ctx.accounts.state.timelock_delay_secs == 0,
+            ErrorCode::TimelockRequired
+        );
+        config.set_limits(chain_id, outbound_limit, inbound_limit, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetBridgeLimits,
+            &(chain_id, old_limits),
+            &(chain_id, (outbound_limit, inbound_limit)),
+        )
+    }
+
+    /// Burn `amount` leaving for `recipient_chain`, as the NTT manager's
+    /// outbound transfer. The owner signs the burn and the manager signs
+    /// as `bridge_authority` before sending the message.
+    pub fn bridge_burn(
+        ctx: Context<BridgeBurn>,
+        amount: u64,
+        recipient_chain: u16,
+        recipient: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.bridge_config;
+        config.record_transfer(recipient_chain, amount, true, now)?;
+        config.sequence = config.sequence.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.source.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(BridgeTransferOut {
+            sender: ctx.accounts.owner.key(),
+            source: ctx.accounts.source.key(),
+            recipient_chain,
+            recipient,
+            amount,
+            sequence: config.sequence,
+            outstanding: config.outstanding,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Mint `amount` arriving from `source_chain`, as the NTT manager's
+    /// release of an inbound transfer it has verified. Only tokens burned
+    /// to other chains can come back, so this never grows the supply past
+    /// what was issued here. Needs the mint authority, so it stops working
+    /// once the supply is finalized.
+    pub fn bridge_mint(
+        ctx: Context<BridgeMint>,
+        amount: u64,
+        source_chain: u16,
+        sender: [u8; 32],
+        message_id: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.bridge_config;
+        config.record_transfer(source_chain, amount, false, now)?;
+        
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[*ctx.bumps.get("mint_authority").unwrap()]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(BridgeTransferIn {
+            source_chain,
+            sender,
+            recipient: ctx.accounts.destination.owner,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            message_id,
+            outstanding: config.outstanding,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Post a Wormhole message attesting that the signing wallet has
+    /// access, so a verifier contract on `target_chain` can grant it to
+    /// `target_address`, the same user's address there. The payload is
+    /// `AccessAttestation::payload()`; the message is finalized before
+    /// guardians sign it.
+    pub fn post_access_attestation(
+        ctx: Context<PostAccessAttestation>,
+        nonce: u32,
+        target_chain: u16,
+        target_address: [u8; 32],
+    ) -> Result<()> {
+        require!(target_address != [0; 32], ErrorCode::InvalidParameter);
+        let accounts = &ctx.accounts;
+        let clock = Clock::get()?;
+        let status = access_status(
+            accounts.wallet.key(),
+            &accounts.receipt,
+            &accounts.access_override,
+            1,
+            clock.unix_timestamp,
+        )?;
+        require!(status.has_access, ErrorCode::AccessRequired);
+        
+        let attestation = AccessAttestation::new(
+            &status,
+            target_chain,
+            target_address,
+            accounts.state.current_requirement,
+            clock.slot,
+        );
+        let sequence = next_sequence(&accounts.wormhole_sequence)?;
+        post_message(
+            &PostMessage {
+                bridge: &accounts.wormhole_bridge,
+                message: &accounts.wormhole_message.to_account_info(),
+                emitter: &accounts.emitter,
+                sequence: &accounts.wormhole_sequence,
+                payer: &accounts.payer.to_account_info(),
+                fee_collector: &accounts.wormhole_fee_collector,
+                clock: &accounts.clock.to_account_info(),
+                rent: &accounts.rent.to_account_info(),
+                system_program: &accounts.system_program.to_account_info(),
+                wormhole_program: &accounts.wormhole_program,
+            },
+            *ctx.bumps.get("emitter").unwrap(),
+            nonce,
+            attestation.payload(),
+        )?;
+        
+        emit!(AccessAttestationPosted {
+            wallet: attestation.wallet,
+            target_chain,
+            target_address,
+            tier: attestation.tier,
+            slot: attestation.slot,
+            expires_at: attestation.expires_at,
+            sequence,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Let token holders vote on parameter changes. Votes close
+    /// `voting_period_secs` after a proposal is created and count once at
+    /// least `quorum_bps` of its snapshot weight has voted. Wallets holding
+    /// or staking `proposal_threshold` tokens can put changes forward.
+    pub fn initialize_governance(
+        ctx: Context<InitializeGovernance>,
+        voting_period_secs: u64,
+        quorum_bps: u64,
+        proposal_threshold: u64,
+    ) -> Result<()> {
+        require!(
+            (1..=MAX_VOTING_PERIOD_SECS).contains(&voting_period_secs)
+                && quorum_bps <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let config = &mut ctx.accounts.governance_config;
+        config.voting_period_secs = voting_period_secs;
+        config.quorum_bps = quorum_bps;
+        config.next_proposal_id = 0;
+        config.proposal_threshold = proposal_threshold;
+        
+        emit!(GovernanceInitialized {
+            voting_period_secs,
+            quorum_bps,
+            proposal_threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::InitializeGovernance,
+            &(),
+            &(voting_period_secs, quorum_bps, proposal_threshold),
+        )
+    }
+
+    /// Put `change` to a vote of the holders as of the current slot, which
+    /// becomes the proposal's snapshot slot. The proposer must hold, with
+    /// their stake, at least the proposal threshold. Voting opens once
+    /// `record_snapshot` has committed to the balances at that slot.
+    pub fn create_proposal(ctx: Context<CreateProposal>, change: ParameterChange) -> Result<()> {
+        let clock = Clock::get()?;
+        let staked = ctx.accounts.stake_account.as_ref().map_or(0, |stake| stake.amount);
+        let holdings = ctx.accounts.proposer_token_account.amount.saturating_add(staked);
+        let config = &mut ctx.accounts.governance_config;
+        require!(
+            holdings >= config.proposal_threshold,
+            ErrorCode::ProposalThresholdNotMet
+        );
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = config.next_proposal_id;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.change = change;
+        proposal.snapshot_slot = clock.slot;
+        proposal.vote_root = [0; 32];
+        proposal.total_weight = 0;
+        proposal.yes_weight = 0;
+        proposal.no_weight = 0;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.voting_ends_at = 0;
+        proposal.eta = 0;
+        proposal.executed = false;
+        proposal.vetoed = false;
+        config.next_proposal_id = config
+            .next_proposal_id
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(ProposalCreated {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            change: proposal.change.clone(),
+            snapshot_slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.proposer.key(),
+            AdminAction::CreateProposal,
+            &(),
+            &(proposal.id, proposal.change.clone()),
+        )
+    }
+
+    /// Commit a proposal to the voting weights of its snapshot slot:
+    /// `vote_root` over `leaf_hash(index, voter, weight)` leaves, each the
+    /// wallet's token balance plus its stake at that slot, computed off-chain
+    /// like revenue roots and weighing `total_weight` in all. Tokens bought
+    /// or borrowed after the proposal was created carry no weight. An
+    /// attestor posts the snapshot and a guardian quorum, the remaining
+    /// accounts, approves it, so no single key decides the weights. Opens
+    /// the voting period.
+    pub fn record_snapshot(
+        ctx: Context<RecordSnapshot>,
+        vote_root: [u8; 32],
+        total_weight: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let approvals = ctx
+            .accounts
+            .guardian_council
+            .check_approvals(ctx.remaining_accounts, now)?;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.has_snapshot(), ErrorCode::SnapshotAlreadyRecorded);
+        require!(total_weight > 0, ErrorCode::InvalidProposal);
+        
+        proposal.vote_root = vote_root;
+        proposal.total_weight = total_weight;
+        proposal.voting_ends_at = now
+            .checked_add(ctx.accounts.governance_config.voting_period_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        proposal.eta = proposal
+            .voting_ends_at
+            .checked_add(ctx.accounts.state.timelock_delay_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(SnapshotRecorded {
+            proposal: proposal.id,
+            snapshot_slot: proposal.snapshot_slot,
+            vote_root,
+            total_weight,
+            voting_ends_at: proposal.voting_ends_at,
+            eta: proposal.eta,
+            approvals,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::RecordSnapshot,
+            &proposal.id,
+            &(proposal.snapshot_slot, vote_root, total_weight),
+        )
+    }
+
+    /// Vote the `weight` the voter's snapshot leaf at `index` gives them,
+    /// whatever they hold now
+    pub fn cast_vote(
+        ctx: Context<CastVote>,
+        support: bool,
+        index: u32,
+        weight: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.vetoed, ErrorCode::ProposalVetoed);
+        require!(proposal.has_snapshot(), ErrorCode::SnapshotNotRecorded);
+        require!(now < proposal.voting_ends_at, ErrorCode::ProposalVotingClosed);
+        let leaf = leaf_hash(index, &ctx.accounts.voter.key(), weight);
+        require!(
+            weight > 0 && verify_proof(&proof, &proposal.vote_root, leaf),
+            ErrorCode::InvalidProof
+        );
+        proposal.record_vote(support, weight)?;
+        
+        let vote = &mut ctx.accounts.vote_record;
+        vote.proposal = proposal.key();
+        vote.voter = ctx.accounts.voter.key();
+        vote.support = support;
+        vote.weight = weight;
+        vote.voted_at = now;
+        
+        emit!(VoteCast {
+            proposal: proposal.id,
+            voter: vote.voter,
+            support,
+            weight,
+            yes_weight: proposal.yes_weight,
+            no_weight: proposal.no_weight,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Apply a passed proposal's change once the timelock delay has run
+    /// from the end of voting. Anyone may execute it, passing the accounts
+    /// the change needs as for `execute_pending_change`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(!proposal.vetoed, ErrorCode::ProposalVetoed);
+        require!(proposal.has_snapshot(), ErrorCode::SnapshotNotRecorded);
+        require!(now >= proposal.voting_ends_at, ErrorCode::ProposalVotingOpen);
+        require!(
+            proposal.passed(ctx.accounts.governance_config.quorum_bps),
+            ErrorCode::ProposalNotPassed
+        );
+        require!(now >= proposal.eta, ErrorCode::TimelockNotElapsed);
+        
+        let state = &mut ctx.accounts.state;
+        apply_parameter_change(
+            state,
+            &proposal.change,
+            proposal.proposer,
+            now,
+            ChangeAccounts {
+                price_feed: ctx.accounts.price_feed.as_ref(),
+                treasury: ctx.accounts.treasury.as_deref_mut(),
+                bridge_config: ctx.accounts.bridge_config.as_deref_mut(),
+                liquidity_config: ctx.accounts.liquidity_config.as_deref_mut(),
+                interest_mint: ctx
+                    .accounts
+                    .interest_mint
+                    .as_ref()
+                    .map(|mint| mint.to_account_info()),
+                mint_authority: ctx.accounts.mint_authority.as_ref().map(|mint_authority| {
+                    (
+                        mint_authority.to_account_info(),
+                        *ctx.bumps.get("mint_authority").unwrap(),
+                    )
+                }),
+                token_2022_program: ctx
+                    .accounts
+                    .token_2022_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+            },
+        )?;
+        proposal.executed = true;
+        
+        emit!(ProposalExecuted {
+            id: proposal.id,
+            executor: ctx.accounts.executor.key(),
+            yes_weight: proposal.yes_weight,
+            no_weight: proposal.no_weight,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.executor.key(),
+            AdminAction::ExecuteProposal,
+            &proposal.id,
+            &proposal.change,
+        )
+    }
+
+    /// Appoint the guardian council for `term_secs`, replacing any current
+    /// one. Guardians act only when `threshold` of them sign together.
+    pub fn appoint_guardian_council(
+        ctx: Context<AppointGuardianCouncil>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        term_secs: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let council = &mut ctx.accounts.guardian_council;
+        let old_members = council.members;
+        council.appoint(&members, threshold, term_secs, now)?;
+        
+        emit!(GuardianCouncilAppointed {
+            members: members.clone(),
+            threshold,
+            expires_at: council.expires_at,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::AppointGuardians,
+            &old_members,
+            &(members, threshold, council.expires_at),
+        )
+    }
+
+    /// Veto a queued change before it executes, returning its rent to the
+    /// proposer. The remaining accounts are the approving guardians.
+    pub fn guardian_veto_change(ctx: Context<GuardianVetoChange>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let approvals = ctx
+            .accounts
+            .guardian_council
+            .check_approvals(ctx.remaining_accounts, now)?;
+        
+        let pending = &ctx.accounts.pending_change;
+        emit!(PendingChangeVetoed {
+            id: pending.id,
+            change: pending.change.clone(),
+            approvals,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Veto a governance proposal that has not executed. The remaining
+    /// accounts are the approving guardians.
+    pub fn guardian_veto_proposal(ctx: Context<GuardianVetoProposal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let approvals = ctx
+            .accounts
+            .guardian_council
+            .check_approvals(ctx.remaining_accounts, now)?;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(!proposal.vetoed, ErrorCode::ProposalVetoed);
+        proposal.vetoed = true;
+        
+        emit!(ProposalVetoed {
+            id: proposal.id,
+            approvals,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Pause the program for `GUARDIAN_PAUSE_SECS`, after which anyone may
+    /// lift the pause. Operators can unpause sooner. The remaining accounts
+    /// are the approving guardians.
+    pub fn guardian_pause(ctx: Context<GuardianPause>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let council = &mut ctx.accounts.guardian_council;
+        let approvals = council.check_approvals(ctx.remaining_accounts, now)?;
+        council.paused_until = now
+            .checked_add(GUARDIAN_PAUSE_SECS)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // A pause the operators already hold stays theirs
+        council.holds_pause = council.holds_pause || !ctx.accounts.state.paused;
+        ctx.accounts.state.paused = true;
+        
+        emit!(GuardianPaused {
+            approvals,
+            paused_until: council.paused_until,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Lift a guardian pause once it has run its course. The program only
+    /// unpauses if the pause is still the council's; operators who paused
+    /// or unpaused since took it over.
+    pub fn lift_guardian_pause(ctx: Context<LiftGuardianPause>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let council = &mut ctx.accounts.guardian_council;
+        require!(
+            council.paused_until != 0 && now >= council.paused_until,
+            ErrorCode::GuardianPauseActive
+        );
+        council.paused_until = 0;
+        if council.holds_pause {
+            council.holds_pause = false;
+            ctx.accounts.state.paused = false;
+        }
+        
+        emit!(GuardianPauseLifted {
+            lifted_by: ctx.accounts.payer.key(),
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+        args: EmissionScheduleArgs,
+    ) -> Result<()> {
+        ctx.accounts.emission_schedule.configure(&args)?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::InitializeEmissionSchedule,
+            &(),
+            &args,
+        )
+    }
+
+    /// Mint the next scheduled epoch into the schedule's vaults, passed as
+    /// remaining accounts in schedule order. Anyone can call this once the
+    /// epoch has started; missed epochs are caught up one call at a time.
+    pub fn mint_epoch_emission<'info>(
+        ctx: Context<'_, '_, '_, 'info, MintEpochEmission<'info>>,
+    ) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let schedule = &mut ctx.accounts.emission_schedule;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!state.paused, ErrorCode::ProgramPaused);
+        
+        let epoch = schedule.next_epoch;
+        require!(now >= schedule.epoch_start(epoch)?, ErrorCode::EpochNotReached);
+        
+        let amount = schedule.emission_for(epoch);
+        let supply = state.check_mint(ctx.accounts.mint.supply, amount)?;
+        
+        let shares = schedule.split(amount);
+        require!(
+            ctx.remaining_accounts.len() == shares.len(),
+            ErrorCode::InvalidEmissionVault
+        );
+        
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        for ((vault_key, share), account) in shares.iter().zip(ctx.remaining_accounts) {
+            require!(account.key == vault_key, ErrorCode::InvalidEmissionVault);
+            let vault = Account::<TokenAccount>::try_from(account)?;
+            require!(vault.mint == state.mint, ErrorCode::InvalidEmissionVault);
+            if *share == 0 {
+                continue;
+            }
+            
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::MintTo {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: account.clone(),
+                        authority: ctx.accounts.mint_authority.to_account_info(),
+                    },
+                    &[&[b"mint_authority", &[bump]]],
+                ),
+                *share,
+            )?;
+        }
+        
+        schedule.next_epoch = epoch.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        schedule.total_emitted = schedule
+            .total_emitted
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(EpochEmissionMinted {
+            epoch,
+            amount,
+            supply,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Start recording admin actions; once enabled, every admin instruction
+    /// must pass the log.
+    pub fn initialize_admin_log(ctx: Context<InitializeAdminLog>) -> Result<()> {
+        ctx.accounts.state.admin_log_enabled = true;
+        Ok(())
+    }
+
+    /// Burn tokens from the caller's own account through the program, so
+    /// the burn counts towards `total_burned`.
+    pub fn burn(ctx: Context<Burn>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.token_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let state = &mut ctx.accounts.state;
+        state.total_burned = state
+            .total_burned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        ctx.accounts.mint.reload()?;
+        emit!(TokensBurned {
+            owner: ctx.accounts.owner.key(),
+            token_account: ctx.accounts.token_account.key(),
+            amount,
+            total_burned: state.total_burned,
+            supply: ctx.accounts.mint.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_treasury_vault(_ctx: Context<InitializeTreasuryVault>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Burn `amount` from the treasury vault. When `source` is passed the
+    /// tokens bought back are first moved into the vault from it, so the
+    /// deposit and the burn happen atomically.
+    pub fn buyback_and_burn(ctx: Context<BuybackAndBurn>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        
+        if let Some(source) = &ctx.accounts.source {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: source.to_account_info(),
+                        to: ctx.accounts.treasury_vault.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+        
+        let bump = *ctx.bumps.get("state").unwrap();
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.treasury_vault.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        let state = &mut ctx.accounts.state;
+        let old_total = state.total_bought_back;
+        state.total_bought_back = old_total
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        state.total_burned = state
+            .total_burned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(BuybackBurned {
+            authority: ctx.accounts.authority.key(),
+            amount,
+            total_bought_back: state.total_bought_back,
+            price: state.last_price,
+            price_decimals: state.price_decimals,
+            current_requirement: state.current_requirement,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::BuybackAndBurn,
+            &old_total,
+            &state.total_bought_back,
+        )
+    }
+
+    /// Set the route program, input mint, and limits of treasury swaps. The
+    /// route must be Jupiter and every swap must have an oracle floor, and
+    /// since the treasury signs the route, changes wait for deployments
+    /// without a timelock.
+    pub fn configure_treasury_swap(
+        ctx: Context<ConfigureTreasurySwap>,
+        swap_program: Pubkey,
+        input_mint: Pubkey,
+        daily_volume_cap: u64,
+        max_slippage_bps: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        require_keys_eq!(swap_program, JUPITER_PROGRAM_ID, ErrorCode::UnsupportedSwapProgram);
+        require!(
+            max_slippage_bps > 0 && max_slippage_bps <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let config = &mut ctx.accounts.swap_config;
+        let old_config = (
+            config.swap_program,
+            config.input_mint,
+            config.daily_volume_cap,
+            config.max_slippage_bps,
+        );
+        config.swap_program = swap_program;
+        config.input_mint = input_mint;
+        config.daily_volume_cap = daily_volume_cap;
+        config.max_slippage_bps = max_slippage_bps;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ConfigureTreasurySwap,
+            &old_config,
+            &(swap_program, input_mint, daily_volume_cap, max_slippage_bps),
+        )
+    }
+
+    /// Sell up to `amount_in` of the treasury's input mint for tokens
+    /// through the configured route, whose accounts are passed as remaining
+    /// accounts and `data` as its instruction data. The tokens bought land
+    /// in the buyback vault for `buyback_and_burn`. The swap must fill for
+    /// at least `min_amount_out` and the oracle floor, and stay within the
+    /// daily volume cap.
+    pub fn treasury_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, TreasurySwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        require!(amount_in > 0, ErrorCode::InvalidParameter);
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        let (amount_spent, amount_out) = swap_from_treasury(
+            SwapAccounts {
+                state: &ctx.accounts.state,
+                swap_config: &mut ctx.accounts.swap_config,
+                input_mint: &ctx.accounts.input_mint,
+                mint: &ctx.accounts.mint,
+                treasury: ctx.accounts.treasury.to_account_info(),
+                treasury_bump: bump,
+                input_vault: &mut ctx.accounts.input_vault,
+                output_vault: &mut ctx.accounts.output_vault,
+                swap_program: &ctx.accounts.swap_program,
+            },
+            ctx.remaining_accounts,
+            data,
+            amount_in,
+            min_amount_out,
+        )?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::TreasurySwap,
+            &(),
+            &(amount_spent, amount_out),
+        )
+    }
+
+    /// Start a DCA buyback schedule at `start_time`, replacing any earlier
+    /// one. Tranches go through the swap route checks, with the oracle
+    /// floor required since crankers choose their routes.
+    pub fn set_dca_schedule(
+        ctx: Context<SetDcaSchedule>,
+        amount_per_interval: u64,
+        interval_secs: u64,
+        total_budget: u64,
+        start_time: i64,
+    ) -> Result<()> {
+        let schedule = &mut ctx.accounts.dca_schedule;
+        let old_schedule = (
+            schedule.amount_per_interval,
+            schedule.interval_secs,
+            schedule.total_budget,
+        );
+        schedule.configure(amount_per_interval, interval_secs, total_budget, start_time)?;
+        
+        emit!(DcaScheduleSet {
+            amount_per_interval,
+            interval_secs,
+            total_budget,
+            start_time,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetDcaSchedule,
+            &old_schedule,
+            &(amount_per_interval, interval_secs, total_budget),
+        )
+    }
+
+    /// Sell the tranche that is due through the route passed as remaining
+    /// accounts, as `treasury_swap` does. The caller picks the route while
+    /// the treasury signs it, so only crankers may run this, e.g. a keeper
+    /// watching `next_execution_at`.
+    pub fn execute_dca_buyback<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteDcaBuyback<'info>>,
+        min_amount_out: u64,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.swap_config.max_slippage_bps > 0,
+            ErrorCode::DcaRequiresOracleBound
+        );
+        let tranche = ctx
+            .accounts
+            .dca_schedule
+            .due_tranche(now)
+            .ok_or(ErrorCode::DcaNotDue)?;
+        
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        let (amount_spent, amount_out) = swap_from_treasury(
+            SwapAccounts {
+                state: &ctx.accounts.state,
+                swap_config: &mut ctx.accounts.swap_config,
+                input_mint: &ctx.accounts.input_mint,
+                mint: &ctx.accounts.mint,
+                treasury: ctx.accounts.treasury.to_account_info(),
+                treasury_bump: bump,
+                input_vault: &mut ctx.accounts.input_vault,
+                output_vault: &mut ctx.accounts.output_vault,
+                swap_program: &ctx.accounts.swap_program,
+            },
+            ctx.remaining_accounts,
+            data,
+            tranche,
+            min_amount_out,
+        )?;
+        
+        let schedule = &mut ctx.accounts.dca_schedule;
+        schedule.record_execution(amount_spent, now)?;
+        
+        emit!(DcaBuybackExecuted {
+            amount_in: amount_spent,
+            amount_out,
+            spent: schedule.spent,
+            next_execution_at: schedule.next_execution_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Point protocol-owned liquidity at `whirlpool` and bound its
+    /// position's ticks and liquidity. Changing the pool or widening a
+    /// limit waits out the timelock when one is set.
+    pub fn set_liquidity_limits(
+        ctx: Context<SetLiquidityLimits>,
+        whirlpool: Pubkey,
+        min_tick_lower: i32,
+        max_tick_upper: i32,
+        max_liquidity: u128,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.liquidity_config;
+        require!(
+            config.tightens(whirlpool, min_tick_lower, max_tick_upper, max_liquidity)
+                || ctx.accounts.state.timelock_delay_secs == 0,
+            ErrorCode::TimelockRequired
+        );
+        let old_limits = (
+            config.whirlpool,
+            config.min_tick_lower,
+            config.max_tick_upper,
+            config.max_liquidity,
+        );
+        config.set_limits(
+            whirlpool,
+            min_tick_lower,
+            max_tick_upper,
+            max_liquidity,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetLiquidityLimits,
+            &old_limits,
+            &(whirlpool, min_tick_lower, max_tick_upper, max_liquidity),
+        )
+    }
+
+    /// Open the treasury's position in the configured pool, owned by the
+    /// treasury. `position_mint` is a fresh keypair that becomes the
+    /// position NFT.
+    pub fn open_liquidity_position(
+        ctx: Context<OpenLiquidityPosition>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        let config = &ctx.accounts.liquidity_config;
+        require!(!config.has_position(), ErrorCode::LiquidityPositionOpen);
+        config.check_range(tick_lower_index, tick_upper_index)?;
+        
+        open_position(
+            &ctx.accounts.whirlpool_program,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.position,
+            &ctx.accounts.position_mint.to_account_info(),
+            &ctx.accounts.position_token_account,
+            &ctx.accounts.whirlpool,
+            [
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent.to_account_info(),
+                &ctx.accounts.associated_token_program.to_account_info(),
+            ],
+            *ctx.bumps.get("position").unwrap(),
+            tick_lower_index,
+            tick_upper_index,
+        )?;
+        
+        let config = &mut ctx.accounts.liquidity_config;
+        config.position = ctx.accounts.position.key();
+        config.tick_lower_index = tick_lower_index;
+        config.tick_upper_index = tick_upper_index;
+        config.liquidity = 0;
+        
+        emit!(LiquidityPositionOpened {
+            whirlpool: config.whirlpool,
+            position: config.position,
+            tick_lower_index,
+            tick_upper_index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::OpenLiquidityPosition,
+            &(),
+            &(config.position, tick_lower_index, tick_upper_index),
+        )
+    }
+
+    /// Add `liquidity` to the treasury's position, spending at most
+    /// `token_max_a` and `token_max_b` from its vaults
+    pub fn increase_liquidity_position(
+        ctx: Context<ManageLiquidityPosition>,
+        liquidity: u128,
+        token_max_a: u64,
+        token_max_b: u64,
+    ) -> Result<()> {
+        require!(liquidity > 0, ErrorCode::InvalidParameter);
+        ctx.accounts.liquidity_config.add_liquidity(liquidity)?;
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        let (amount_a, amount_b) =
+            on_treasury_position(ctx.accounts, bump, |position, seeds| {
+                modify_liquidity(position, true, liquidity, token_max_a, token_max_b, seeds)
+            })?;
+        
+        let config = &ctx.accounts.liquidity_config;
+        emit!(LiquidityPositionChanged {
+            position: config.position,
+            increased: true,
+            liquidity_delta: liquidity,
+            amount_a,
+            amount_b,
+            liquidity: config.liquidity,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::IncreaseLiquidity,
+            &(),
+            &(liquidity, amount_a, amount_b),
+        )
+    }
+
+    /// Withdraw `liquidity` from the treasury's position into its vaults,
+    /// receiving at least `token_min_a` and `token_min_b`
+    pub fn decrease_liquidity_position(
+        ctx: Context<ManageLiquidityPosition>,
+        liquidity: u128,
+        token_min_a: u64,
+        token_min_b: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.liquidity_config;
+        require!(
+            liquidity > 0 && liquidity <= config.liquidity,
+            ErrorCode::InvalidParameter
+        );
+        config.liquidity -= liquidity;
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        let (amount_a, amount_b) =
+            on_treasury_position(ctx.accounts, bump, |position, seeds| {
+                modify_liquidity(position, false, liquidity, token_min_a, token_min_b, seeds)
+            })?;
+        
+        let config = &ctx.accounts.liquidity_config;
+        emit!(LiquidityPositionChanged {
+            position: config.position,
+            increased: false,
+            liquidity_delta: liquidity,
+            amount_a,
+            amount_b,
+            liquidity: config.liquidity,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::DecreaseLiquidity,
+            &(),
+            &(liquidity, amount_a, amount_b),
+        )
+    }
+
+    /// Collect the trading fees the treasury's position has earned into
+    /// its vaults
+    pub fn collect_position_fees(ctx: Context<ManageLiquidityPosition>) -> Result<()> {
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        let (amount_a, amount_b) = on_treasury_position(ctx.accounts, bump, collect_fees)?;
+        
+        emit!(PositionFeesCollected {
+            position: ctx.accounts.liquidity_config.position,
+            amount_a,
+            amount_b,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CollectPositionFees,
+            &(),
+            &(amount_a, amount_b),
+        )
+    }
+
+    /// Close the treasury's emptied position, so one with a new range can
+    /// be opened. Its liquidity and fees must have been withdrawn first.
+    pub fn close_liquidity_position(ctx: Context<CloseLiquidityPosition>) -> Result<()> {
+        require!(
+            ctx.accounts.liquidity_config.liquidity == 0,
+            ErrorCode::LiquidityPositionNotEmpty
+        );
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        close_position(
+            &ctx.accounts.whirlpool_program,
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.position,
+            &ctx.accounts.position_mint,
+            &ctx.accounts.position_token_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &[&[b"treasury", &[bump]]],
+        )?;
+        
+        let config = &mut ctx.accounts.liquidity_config;
+        let position = config.position;
+        config.position = Pubkey::default();
+        
+        emit!(LiquidityPositionClosed {
+            position,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CloseLiquidityPosition,
+            &position,
+            &(),
+        )
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>, epoch_secs: u64) -> Result<()> {
+        require!(epoch_secs > 0, ErrorCode::InvalidParameter);
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.epoch_secs = epoch_secs;
+        treasury.epoch_start = Clock::get()?.unix_timestamp;
+        treasury.limits = [TreasuryLimit::EMPTY; MAX_TREASURY_ASSETS];
+        
+        Ok(())
+    }
+
+    pub fn initialize_treasury_token_vault(
+        _ctx: Context<InitializeTreasuryTokenVault>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set the per-epoch withdrawal limit for `asset` (a mint, or
+    /// `NATIVE_SOL`). Raising a limit waits out the timelock when one is set.
+    pub fn set_treasury_limit(
+        ctx: Context<SetTreasuryLimit>,
+        asset: Pubkey,
+        per_epoch_limit: u64,
+    ) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        let old_limit = treasury.limit(&asset);
+        require!(
+            per_epoch_limit <= old_limit || ctx.accounts.state.timelock_delay_secs == 0,
+            ErrorCode::TimelockRequired
+        );
+        treasury.set_limit(asset, per_epoch_limit, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetTreasuryLimit,
+            &(asset, old_limit),
+            &(asset, per_epoch_limit),
+        )
+    }
+
+    /// Fund the treasury with SOL. Tokens are deposited with
+    /// `deposit_treasury_tokens` so every inflow is recorded.
+    pub fn deposit_treasury_sol(ctx: Context<DepositTreasurySol>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(TreasuryDeposited {
+            asset: NATIVE_SOL,
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn deposit_treasury_tokens(
+        ctx: Context<DepositTreasuryTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(TreasuryDeposited {
+            asset: ctx.accounts.vault.mint,
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw SOL within this epoch's limit. The treasury always keeps
+    /// enough lamports to stay rent exempt.
+    pub fn withdraw_treasury_sol(ctx: Context<WithdrawTreasurySol>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let withdrawn = ctx
+            .accounts
+            .treasury
+            .record_withdrawal(&NATIVE_SOL, amount, now)?;
+        
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(treasury.data_len());
+        require!(
+            treasury.lamports().saturating_sub(rent_exempt) >= amount,
+            ErrorCode::InsufficientTreasuryFunds
+        );
+        **treasury.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+        
+        emit!(TreasuryWithdrawn {
+            asset: NATIVE_SOL,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            withdrawn_this_epoch: withdrawn,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::WithdrawTreasury,
+            &(),
+            &(NATIVE_SOL, ctx.accounts.recipient.key(), amount),
+        )
+    }
+
+    pub fn withdraw_treasury_tokens(
+        ctx: Context<WithdrawTreasuryTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let asset = ctx.accounts.vault.mint;
+        let withdrawn = ctx
+            .accounts
+            .treasury
+            .record_withdrawal(&asset, amount, now)?;
+        require!(
+            ctx.accounts.vault.amount >= amount,
+            ErrorCode::InsufficientTreasuryFunds
+        );
+        
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[&[b"treasury", &[bump]]],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        
+        emit!(TreasuryWithdrawn {
+            asset,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            withdrawn_this_epoch: withdrawn,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::WithdrawTreasury,
+            &(),
+            &(asset, ctx.accounts.recipient.key(), amount),
+        )
+    }
+
+    /// Escrow `amount` of the treasury's tokens for a community bounty that
+    /// `arbiter` pays out. Counts against the treasury's withdrawal limit.
+    pub fn create_bounty(
+        ctx: Context<CreateBounty>,
+        bounty_id: u64,
+        amount: u64,
+        deadline: i64,
+        arbiter: Pubkey,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(amount > 0 && deadline > now, ErrorCode::InvalidBounty);
+        let mint = ctx.accounts.mint.key();
+        let withdrawn = ctx
+            .accounts
+            .treasury
+            .record_withdrawal(&mint, amount, now)?;
+        require!(
+            ctx.accounts.treasury_vault.amount >= amount,
+            ErrorCode::InsufficientTreasuryFunds
+        );
+        
+        let bump = *ctx.bumps.get("treasury").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.treasury_vault.to_account_info(),
+                    to: ctx.accounts.bounty_vault.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[&[b"treasury", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        let bounty = &mut ctx.accounts.bounty;
+        bounty.bounty_id = bounty_id;
+        bounty.mint = mint;
+        bounty.arbiter = arbiter;
+        bounty.creator = ctx.accounts.authority.key();
+        bounty.amount = amount;
+        bounty.deadline = deadline;
+        bounty.created_at = now;
+        
+        emit!(BountyCreated {
+            bounty_id,
+            mint,
+            arbiter,
+            amount,
+            deadline,
+            withdrawn_this_epoch: withdrawn,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CreateBounty,
+            &(),
+            &(bounty_id, amount, arbiter, deadline),
+        )
+    }
+
+    /// Submit work for a bounty before its deadline. The submission itself
+    /// lives off-chain; only its hash is recorded.
+    pub fn submit_claim(ctx: Context<SubmitClaim>, submission_hash: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bounty = &mut ctx.accounts.bounty;
+        require!(bounty.accepts_claims(now), ErrorCode::BountyClosed);
+        bounty.claims = bounty.claims.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        
+        let claim = &mut ctx.accounts.claim;
+        claim.bounty = bounty.key();
+        claim.claimant = ctx.accounts.claimant.key();
+        claim.submission_hash = submission_hash;
+        claim.submitted_at = now;
+        
+        emit!(BountyClaimSubmitted {
+            bounty_id: bounty.bounty_id,
+            claimant: claim.claimant,
+            submission_hash,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Pay `amount` of a bounty's escrow to a claimant. Several claims can
+    /// share a bounty, and one claim can be paid in several parts, until
+    /// the review period after the deadline ends.
+    pub fn approve_payout(ctx: Context<ApprovePayout>, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bounty = &ctx.accounts.bounty;
+        require!(bounty.accepts_payouts(now), ErrorCode::BountyClosed);
+        require!(
+            amount > 0 && amount <= bounty.remaining(),
+            ErrorCode::InsufficientBountyEscrow
+        );
+        
+        let bump = *ctx.bumps.get("bounty").unwrap();
+        let bounty_id = bounty.bounty_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"bounty", &bounty_id, &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.bounty_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: bounty.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        
+        let bounty = &mut ctx.accounts.bounty;
+        bounty.paid = bounty.paid.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        let claim = &mut ctx.accounts.claim;
+        claim.paid = claim.paid.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(BountyPaid {
+            bounty_id: bounty.bounty_id,
+            claimant: claim.claimant,
+            amount,
+            remaining: bounty.remaining(),
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Return what is left of a bounty to the treasury once its review
+    /// period has passed. Anyone may call this.
+    pub fn reclaim_expired(ctx: Context<ReclaimExpired>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bounty = &ctx.accounts.bounty;
+        require!(!bounty.reclaimed, ErrorCode::BountyClosed);
+        require!(bounty.is_expired(now), ErrorCode::BountyNotExpired);
+        let amount = bounty.remaining();
+        
+        let bump = *ctx.bumps.get("bounty").unwrap();
+        let bounty_id = bounty.bounty_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"bounty", &bounty_id, &[bump]];
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.bounty_vault.to_account_info(),
+                        to: ctx.accounts.treasury_vault.to_account_info(),
+                        authority: bounty.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.bounty_vault.to_account_info(),
+                destination: ctx.accounts.creator.to_account_info(),
+                authority: bounty.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        
+        let bounty = &mut ctx.accounts.bounty;
+        bounty.reclaimed = true;
+        
+        emit!(BountyReclaimed {
+            bounty_id: bounty.bounty_id,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Open a public sale of newly minted tokens priced along `args.curve`.
+    /// Payments go to the treasury, which needs a token vault for
+    /// `args.payment_mint` unless buyers pay in SOL.
+    pub fn initialize_sale(ctx: Context<InitializeSale>, args: SaleArgs) -> Result<()> {
+        ctx.accounts
+            .sale
+            .configure(&args, ctx.accounts.mint.decimals)?;
+        
+        emit!(SaleConfigured {
+            args,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ConfigureSale,
+            &(),
+            &args,
+        )
+    }
+
+    /// Change the sale's terms. Only possible before it starts, so buyers
+    /// always pay along the curve they saw.
+    pub fn update_sale(ctx: Context<UpdateSale>, args: SaleArgs) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let sale = &mut ctx.accounts.sale;
+        require!(!sale.finalized, ErrorCode::SaleClosed);
+        require!(now < sale.start_time, ErrorCode::SaleAlreadyStarted);
+        let old_args = sale.args();
+        let token_decimals = sale.token_decimals;
+        sale.configure(&args, token_decimals)?;
+        
+        emit!(SaleConfigured {
+            args,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ConfigureSale,
+            &old_args,
+            &args,
+        )
+    }
+
+    /// Buy `amount` base units at the curve price, paying at most
+    /// `max_cost` so a purchase landing after others cannot pay more than
+    /// the buyer agreed to. SOL payments need no payment accounts.
+    pub fn buy_from_sale(ctx: Context<BuyFromSale>, amount: u64, max_cost: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let state = &ctx.accounts.state;
+        let sale = &mut ctx.accounts.sale;
+        require!(!state.paused, ErrorCode::ProgramPaused);
+        require!(!sale.finalized && now < sale.end_time, ErrorCode::SaleClosed);
+        require!(now >= sale.start_time, ErrorCode::SaleNotStarted);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        
+        let sold = sale.sold.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(sold <= sale.hard_cap, ErrorCode::SaleHardCapExceeded);
+        let purchase = &mut ctx.accounts.purchase;
+        let purchased = purchase
+            .purchased
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(purchased <= sale.wallet_cap, ErrorCode::SaleWalletCapExceeded);
+        let supply = state.check_mint(ctx.accounts.mint.supply, amount)?;
+        
+        let cost = sale.cost(amount)?;
+        require!(cost > 0, ErrorCode::InvalidParameter);
+        require!(cost <= max_cost, ErrorCode::SaleSlippageExceeded);
+        
+        if sale.payment_mint == NATIVE_SOL {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                cost,
+            )?;
+        } else {
+            let (Some(source), Some(vault)) =
+                (&ctx.accounts.payment_source, &ctx.accounts.treasury_vault)
+            else {
+                return err!(ErrorCode::SalePaymentAccountRequired);
+            };
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: source.to_account_info(),
+                        to: vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                cost,
+            )?;
+        }
+        
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        sale.sold = sold;
+        sale.raised = sale.raised.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+        purchase.buyer = ctx.accounts.buyer.key();
+        purchase.purchased = purchased;
+        purchase.paid = purchase.paid.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(SalePurchased {
+            buyer: purchase.buyer,
+            amount,
+            cost,
+            next_price: sale.price()?,
+            sold,
+            raised: sale.raised,
+            supply,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Close the sale for good, locking its curve and totals
+    pub fn finalize_sale(ctx: Context<FinalizeSale>) -> Result<()> {
+        let sale = &mut ctx.accounts.sale;
+        require!(!sale.finalized, ErrorCode::SaleClosed);
+        sale.finalized = true;
+        
+        emit!(SaleFinalized {
+            sold: sale.sold,
+            raised: sale.raised,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::FinalizeSale,
+            &(),
+            &(sale.sold, sale.raised),
+        )
+    }
+
+    /// Start a Dutch auction of `supply` newly minted base units, its
+    /// price falling from `start_price` to `floor_price` over
+    /// `duration_secs`. Proceeds go to the treasury as bids settle.
+    pub fn start_auction(
+        ctx: Context<StartAuction>,
+        tranche: u64,
+        supply: u64,
+        start_price: u64,
+        floor_price: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require!(
+            supply > 0 && floor_price > 0 && start_price >= floor_price && duration_secs > 0,
+            ErrorCode::InvalidAuction
+        );
+        ctx.accounts
+            .state
+            .check_mint(ctx.accounts.mint.supply, supply)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &mut ctx.accounts.auction;
+        auction.tranche = tranche;
+        auction.supply = supply;
+        auction.start_price = start_price;
+        auction.floor_price = floor_price;
+        auction.start_time = now;
+        auction.duration_secs = duration_secs;
+        auction.token_decimals = ctx.accounts.mint.decimals;
+        
+        emit!(AuctionStarted {
+            tranche,
+            supply,
+            start_price,
+            floor_price,
+            end_time: auction.end_time(),
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::StartAuction,
+            &(),
+            &(tranche, supply, start_price, floor_price, duration_secs),
+        )
+    }
+
+    /// Commit `amount` lamports to an auction at its current price. Every
+    /// bidder ends up paying the clearing price, at most the price they bid
+    /// at, and the rest is refunded at settlement.
+    pub fn bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let price = ctx.accounts.auction.record_bid(amount, now)?;
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.bidder.to_account_info(),
+                    to: ctx.accounts.auction.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let bid = &mut ctx.accounts.bid;
+        bid.auction = ctx.accounts.auction.key();
+        bid.bidder = ctx.accounts.bidder.key();
+        bid.committed = bid.committed.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        
+        let auction = &ctx.accounts.auction;
+        emit!(AuctionBidPlaced {
+            tranche: auction.tranche,
+            bidder: bid.bidder,
+            amount,
+            price,
+            committed: auction.committed,
+            cleared: auction.clearing_price > 0,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Settle a bid once its auction has cleared or ended: mint the
+    /// bidder's allocation at the clearing price, pay its cost to the
+    /// treasury, and refund the rest of the commitment. Anyone may call
+    /// this.
+    pub fn settle_bid(ctx: Context<SettleBid>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.auction;
+        let price = auction.final_price(now).ok_or(ErrorCode::AuctionNotConcluded)?;
+        let committed = ctx.accounts.bid.committed;
+        let (tokens, cost) = auction.allocation(committed, price);
+        let refund = committed - cost;
+        let supply = ctx.accounts.state.check_mint(ctx.accounts.mint.supply, tokens)?;
+        
+        if tokens > 0 {
+            let bump = *ctx.bumps.get("mint_authority").unwrap();
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::MintTo {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                        authority: ctx.accounts.mint_authority.to_account_info(),
+                    },
+                    &[&[b"mint_authority", &[bump]]],
+                ),
+                tokens,
+            )?;
+        }
+        
+        let auction_info = ctx.accounts.auction.to_account_info();
+        let treasury = ctx.accounts.treasury.to_account_info();
+        **auction_info.try_borrow_mut_lamports()? -= committed;
+        **treasury.try_borrow_mut_lamports()? += cost;
+        **ctx.accounts.bidder.try_borrow_mut_lamports()? += refund;
+        
+        let auction = &mut ctx.accounts.auction;
+        auction.settled_tokens = auction
+            .settled_tokens
+            .checked_add(tokens)
+            .ok_or(ErrorCode::MathOverflow)?;
+        auction.proceeds = auction.proceeds.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(AuctionBidSettled {
+            tranche: auction.tranche,
+            bidder: ctx.accounts.bid.bidder,
+            clearing_price: price,
+            tokens,
+            cost,
+            refund,
+            supply,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Open a presale to the wallets in `args.whitelist_root` at a fixed
+    /// price per token
+    pub fn initialize_presale(ctx: Context<InitializePresale>, args: PresaleArgs) -> Result<()> {
+        ctx.accounts
+            .presale
+            .configure(&args, ctx.accounts.mint.decimals)?;
+        
+        emit!(PresaleConfigured {
+            args,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ConfigurePresale,
+            &(),
+            &args,
+        )
+    }
+
+    /// Escrow `amount` lamports towards the presale, proving the
+    /// contributor's whitelist leaf `(index, contributor, allocation)`
+    pub fn contribute(
+        ctx: Context<Contribute>,
+        amount: u64,
+        index: u32,
+        allocation: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let contributor = ctx.accounts.contributor.key();
+        let presale = &mut ctx.accounts.presale;
+        require!(presale.is_open(now), ErrorCode::PresaleNotOpen);
+        let leaf = leaf_hash(index, &contributor, allocation);
+        require!(
+            verify_proof(&proof, &presale.whitelist_root, leaf),
+            ErrorCode::InvalidProof
+        );
+        
+        let contribution = &mut ctx.accounts.contribution;
+        let contributed = contribution
+            .contributed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        presale.check_contribution(contributed, allocation)?;
+        let raised = presale.raised.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(raised <= presale.hard_cap, ErrorCode::PresaleHardCapExceeded);
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.contributor.to_account_info(),
+                    to: presale.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        presale.raised = raised;
+        contribution.contributor = contributor;
+        contribution.contributed = contributed;
+        
+        emit!(PresaleContributed {
+            contributor,
+            amount,
+            contributed,
+            raised,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Let contributors reclaim their escrow when the deadline passed short
+    /// of the soft cap. Anyone may call this.
+    pub fn enable_refunds(ctx: Context<EnableRefunds>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let presale = &mut ctx.accounts.presale;
+        require!(now >= presale.deadline, ErrorCode::PresaleStillOpen);
+        require!(presale.raised < presale.soft_cap, ErrorCode::PresaleSoftCapMet);
+        require!(!presale.refunds_enabled, ErrorCode::InvalidParameter);
+        presale.refunds_enabled = true;
+        
+        emit!(PresaleRefundsEnabled {
+            raised: presale.raised,
+            soft_cap: presale.soft_cap,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Return a contributor's escrow once refunds are enabled
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        require!(presale.refunds_enabled, ErrorCode::RefundsNotEnabled);
+        let amount = ctx.accounts.contribution.contributed;
+        presale.raised = presale.raised.saturating_sub(amount);
+        
+        **presale.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.contributor.try_borrow_mut_lamports()? += amount;
+        
+        emit!(PresaleRefunded {
+            contributor: ctx.accounts.contributor.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Move a successful presale's proceeds to the treasury once it has
+    /// closed, which opens token claims. Anyone may call this.
+    pub fn finalize_presale(ctx: Context<FinalizePresale>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let presale = &mut ctx.accounts.presale;
+        require!(!presale.finalized, ErrorCode::InvalidParameter);
+        require!(presale.has_closed(now), ErrorCode::PresaleStillOpen);
+        require!(presale.raised >= presale.soft_cap, ErrorCode::PresaleSoftCapNotMet);
+        presale.finalized = true;
+        
+        let raised = presale.raised;
+        **presale.to_account_info().try_borrow_mut_lamports()? -= raised;
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += raised;
+        
+        emit!(PresaleFinalized {
+            raised,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Mint a contributor's tokens at the presale price once it is final
+    pub fn claim_presale_tokens(ctx: Context<ClaimPresaleTokens>) -> Result<()> {
+        let presale = &ctx.accounts.presale;
+        require!(presale.finalized, ErrorCode::PresaleNotFinalized);
+        let contributed = ctx.accounts.contribution.contributed;
+        let tokens = presale.tokens_for(contributed)?;
+        let supply = ctx.accounts.state.check_mint(ctx.accounts.mint.supply, tokens)?;
+        
+        let bump = *ctx.bumps.get("mint_authority").unwrap();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                &[&[b"mint_authority", &[bump]]],
+            ),
+            tokens,
+        )?;
+        
+        emit!(PresaleTokensClaimed {
+            contributor: ctx.accounts.contributor.key(),
+            contributed,
+            tokens,
+            supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Escrow `amount` tokens for sale at `price` units of `payment_mint`
+    /// per whole token, to `counterparty` alone when set, until
+    /// `expires_at`. A nonzero `max_deviation_bps` holds the price to that
+    /// distance from the oracle price, checked now and on acceptance.
+    pub fn create_otc_offer(
+        ctx: Context<CreateOtcOffer>,
+        offer_id: u64,
+        amount: u64,
+        price: u64,
+        counterparty: Option<Pubkey>,
+        expires_at: i64,
+        max_deviation_bps: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            amount > 0 && price > 0 && expires_at > now && max_deviation_bps <= BPS_DENOMINATOR,
+            ErrorCode::InvalidOtcOffer
+        );
+        
+        let offer = &mut ctx.accounts.offer;
+        offer.maker = ctx.accounts.maker.key();
+        offer.offer_id = offer_id;
+        offer.amount = amount;
+        offer.payment_mint = ctx.accounts.payment_mint.key();
+        offer.price = price;
+        offer.counterparty = counterparty;
+        offer.expires_at = expires_at;
+        offer.max_deviation_bps = max_deviation_bps;
+        offer.created_at = now;
+        offer.check_oracle_bound(&ctx.accounts.state, ctx.accounts.payment_mint.decimals, now)?;
+        let total_price = offer.total_price(ctx.accounts.mint.decimals)?;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.maker.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(OtcOfferCreated {
+            offer: offer.key(),
+            maker: offer.maker,
+            amount,
+            payment_mint: offer.payment_mint,
+            price,
+            total_price,
+            counterparty,
+            expires_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Take an OTC offer whole, paying the maker and receiving the escrow
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        let offer = &ctx.accounts.offer;
+        let taker = ctx.accounts.taker.key();
+        require!(now < offer.expires_at, ErrorCode::OtcOfferExpired);
+        require!(
+            offer.counterparty.map_or(true, |counterparty| counterparty == taker),
+            ErrorCode::OtcNotCounterparty
+        );
+        offer.check_oracle_bound(&ctx.accounts.state, ctx.accounts.payment_mint.decimals, now)?;
+        let total_price = offer.total_price(ctx.accounts.mint.decimals)?;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.payment_source.to_account_info(),
+                    to: ctx.accounts.maker_payment_account.to_account_info(),
+                    authority: ctx.accounts.taker.to_account_info(),
+                },
+            ),
+            total_price,
+        )?;
+        
+        let bump = *ctx.bumps.get("offer").unwrap();
+        let offer_id = offer.offer_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"otc_offer", offer.maker.as_ref(), &offer_id, &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: offer.to_account_info(),
+                },
+                &[seeds],
+            ),
+            offer.amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.maker.to_account_info(),
+                authority: offer.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        
+        emit!(OtcOfferAccepted {
+            offer: offer.key(),
+            maker: offer.maker,
+            taker,
+            amount: offer.amount,
+            total_price,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw an OTC offer and its escrow, expired or not
+    pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let bump = *ctx.bumps.get("offer").unwrap();
+        let offer_id = offer.offer_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"otc_offer", offer.maker.as_ref(), &offer_id, &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: offer.to_account_info(),
+                },
+                &[seeds],
+            ),
+            offer.amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.maker.to_account_info(),
+                authority: offer.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        
+        emit!(OtcOfferCancelled {
+            offer: offer.key(),
+            maker: offer.maker,
+            amount: offer.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Configure the protocol fee charged on subscriptions, access-pass
+    /// purchases, and staking deposits. Takes effect once fees are enabled.
+    pub fn set_fee_config(ctx: Context<SetFeeConfig>, fee_bps: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_config = (state.fee_bps, state.fee_destination);
+        state.set_fee_config(
+            fee_bps,
+            ctx.accounts.fee_destination.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetFeeConfig,
+            &old_config,
+            &(fee_bps, state.fee_destination),
+        )
+    }
+
+    pub fn set_fees_enabled(ctx: Context<SetFeesEnabled>, enabled: bool) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(
+            !enabled || state.fee_destination != Pubkey::default(),
+            ErrorCode::InvalidFeeDestination
+        );
+        let was_enabled = state.fees_enabled;
+        state.fees_enabled = enabled;
+        
+        emit!(FeeConfigUpdated {
+            fee_bps: state.fee_bps,
+            fee_destination: state.fee_destination,
+            fees_enabled: enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetFeesEnabled,
+            &was_enabled,
+            &enabled,
+        )
+    }
+
+    pub fn initialize_vesting_escrow(_ctx: Context<InitializeVestingEscrow>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Lock `total_amount` from the funder's account into the vesting
+    /// escrow for `beneficiary`.
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        beneficiary: Pubkey,
+        start_time: i64,
+        cliff_secs: u64,
+        duration_secs: u64,
+        total_amount: u64,
+        revocable: bool,
+    ) -> Result<()> {
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        schedule.beneficiary = beneficiary;
+        schedule.start_time = start_time;
+        schedule.cliff_secs = cliff_secs;
+        schedule.duration_secs = duration_secs;
+        schedule.total_amount = total_amount;
+        schedule.claimed_amount = 0;
+        schedule.revocable = revocable;
+        schedule.revoked_at = 0;
+        schedule.validate()?;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.vesting_escrow.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            total_amount,
+        )?;
+        
+        emit!(VestingCreated {
+            beneficiary,
+            start_time,
+            cliff_secs,
+            duration_secs,
+            total_amount,
+            revocable,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CreateVesting,
+            &(),
+            &(beneficiary, start_time, cliff_secs, duration_secs, total_amount, revocable),
+        )
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        let amount = schedule.claimable(now);
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        schedule.claimed_amount = schedule
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let bump = *ctx.bumps.get("state").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vesting_escrow.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(VestingClaimed {
+            beneficiary: schedule.beneficiary,
+            amount,
+            claimed_amount: schedule.claimed_amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Stop a revocable schedule: what has vested stays claimable by the
+    /// beneficiary and the unvested remainder goes to `refund_destination`.
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        require!(schedule.revocable, ErrorCode::VestingNotRevocable);
+        require!(!schedule.is_revoked(), ErrorCode::VestingAlreadyRevoked);
+        
+        let unvested = schedule.total_amount - schedule.vested_amount(now);
+        schedule.revoked_at = now;
+        
+        if unvested > 0 {
+            let bump = *ctx.bumps.get("state").unwrap();
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.vesting_escrow.to_account_info(),
+                        to: ctx.accounts.refund_destination.to_account_info(),
+                        authority: ctx.accounts.state.to_account_info(),
+                    },
+                    &[&[b"token_state", &[bump]]],
+                ),
+                unvested,
+            )?;
+        }
+        
+        emit!(VestingRevoked {
+            beneficiary: schedule.beneficiary,
+            refunded: unvested,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::RevokeVesting,
+            &schedule.beneficiary,
+            &unvested,
+        )
+    }
+
+    /// Fund a merkle airdrop of `total_amount` from the funder's account.
+    /// Leaves are `leaf_hash(index, claimant, amount)`.
+    pub fn create_distribution(
+        ctx: Context<CreateDistribution>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        max_claims: u32,
+        deadline: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            total_amount > 0 && max_claims > 0 && deadline > now,
+            ErrorCode::InvalidDistribution
+        );
+        
+        let state = &mut ctx.accounts.state;
+        let distribution = &mut ctx.accounts.distribution;
+        distribution.id = state.next_distribution_id;
+        distribution.merkle_root = merkle_root;
+        distribution.total_amount = total_amount;
+        distribution.claimed_amount = 0;
+        distribution.max_claims = max_claims;
+        distribution.deadline = deadline;
+        state.next_distribution_id = state
+            .next_distribution_id
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.distribution_vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            total_amount,
+        )?;
+        
+        emit!(DistributionCreated {
+            id: distribution.id,
+            merkle_root,
+            total_amount,
+            max_claims,
+            deadline,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::CreateDistribution,
+            &(),
+            &(distribution.id, merkle_root, total_amount, max_claims, deadline),
+        )
+    }
+
+    /// Create one of the bitmaps recording claims; anyone can pay for it.
+    pub fn initialize_claim_bitmap(ctx: Context<InitializeClaimBitmap>, chunk: u32) -> Result<()> {
+        require!(
+            chunk < ctx.accounts.distribution.bitmap_count(),
+            ErrorCode::InvalidDistribution
+        );
+        let bitmap = &mut ctx.accounts.claim_bitmap;
+        bitmap.distribution = ctx.accounts.distribution.key();
+        bitmap.chunk = chunk;
+        
+        Ok(())
+    }
+
+    pub fn claim(
+        ctx: Context<Claim>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let distribution = &mut ctx.accounts.distribution;
+        require!(now < distribution.deadline, ErrorCode::DistributionExpired);
+        require!(index < distribution.max_claims, ErrorCode::InvalidProof);
+        
+        let leaf = leaf_hash(index, ctx.accounts.claimant.key, amount);
+        require!(
+            verify_proof(&proof, &distribution.merkle_root, leaf),
+            ErrorCode::InvalidProof
+        );
+        ctx.accounts.claim_bitmap.set_claimed(index)?;
+        
+        distribution.claimed_amount = distribution
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let id = distribution.id.to_le_bytes();
+        let bump = *ctx.bumps.get("distribution").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.distribution_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: distribution.to_account_info(),
+                },
+                &[&[b"distribution", id.as_ref(), &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(AirdropClaimed {
+            distribution: distribution.id,
+            index,
+            claimant: ctx.accounts.claimant.key(),
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Return whatever is left in the vault once the claim deadline passes.
+    pub fn clawback_unclaimed(ctx: Context<ClawbackUnclaimed>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let distribution = &ctx.accounts.distribution;
+        require!(now >= distribution.deadline, ErrorCode::DistributionActive);
+        
+        let amount = ctx.accounts.distribution_vault.amount;
+        let id = distribution.id.to_le_bytes();
+        let bump = *ctx.bumps.get("distribution").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.distribution_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: distribution.to_account_info(),
+                },
+                &[&[b"distribution", id.as_ref(), &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(DistributionClawedBack {
+            distribution: distribution.id,
+            amount,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ClawbackDistribution,
+            &distribution.id,
+            &amount,
+        )
+    }
+
+    pub fn initialize_staking(ctx: Context<InitializeStaking>) -> Result<()> {
+        let pool = &mut ctx.accounts.stake_pool;
+        pool.total_staked = 0;
+        pool.total_weight = 0;
+        pool.reward_per_token = 0;
+        pool.reserved_rewards = 0;
+        
+        Ok(())
+    }
+
+    pub fn open_stake_account(ctx: Context<OpenStakeAccount>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        stake_account.owner = ctx.accounts.owner.key();
+        stake_account.amount = 0;
+        stake_account.weight = 0;
+        stake_account.lock_tier = NO_LOCK;
+        stake_account.unlock_at = 0;
+        stake_account.reward_per_token_paid = ctx.accounts.stake_pool.reward_per_token;
+        stake_account.pending_rewards = 0;
+        stake_account.staked_at = 0;
+        stake_account.unstaking_amount = 0;
+        stake_account.unstake_available_at = 0;
+        
+        Ok(())
+    }
+
+    /// Stake `amount` from the owner's account, less the protocol fee on
+    /// staking deposits when one is charged, optionally locking the whole
+    /// stake for one of `lock_tiers` (or `NO_LOCK`).
+    pub fn stake(ctx: Context<Stake>, amount: u64, lock_tier: u8) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        stake_account.settle(pool)?;
+        
+        let net_amount = collect_fee(
+            &ctx.accounts.state,
+            FeeOperation::StakingDeposit,
+            amount,
+            &ctx.accounts.source,
+            ctx.accounts.fee_destination.as_ref(),
+            ctx.accounts.insurance_vault.as_ref(),
+            ctx.accounts.referral.as_mut(),
+            ctx.accounts.referrer_token_account.as_ref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            net_amount,
+        )?;
+        
+        if stake_account.amount == 0 {
+            stake_account.staked_at = now;
+        }
+        stake_account.amount = stake_account
+            .amount
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool
+            .total_staked
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let tiers = &ctx.accounts.state.lock_tiers;
+        stake_account.lock(lock_tier, tiers, now)?;
+        stake_account.reweight(pool, tiers, now)?;
+        
+        emit!(Staked {
+            owner: stake_account.owner,
+            amount: net_amount,
+            fee: amount - net_amount,
+            staked: stake_account.amount,
+            lock_tier: stake_account.lock_tier,
+            unlock_at: stake_account.unlock_at,
+            total_staked: pool.total_staked,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Re-stake a stake account's pending rewards. Anyone can call this;
+    /// the rewards stay with the account's owner.
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        let amount = stake_account.compound(pool, &ctx.accounts.state.lock_tiers, now)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        
+        let bump = *ctx.bumps.get("state").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(RewardsCompounded {
+            owner: stake_account.owner,
+            amount,
+            staked: stake_account.amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// `compound_rewards` for every stake account passed as a writable
+    /// remaining account. Accounts with nothing pending are skipped.
+    pub fn compound_rewards_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompoundRewardsBatch<'info>>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.stake_pool;
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        
+        let mut total: u64 = 0;
+        for info in ctx.remaining_accounts {
+            require!(info.is_writable, ErrorCode::InvalidParameter);
+            // Loaded and written back one at a time, so a repeated account
+            // sees its own earlier update
+            let mut stake_account = Account::<StakeAccount>::try_from(info)?;
+            let amount = stake_account.compound(pool, &ctx.accounts.state.lock_tiers, now)?;
+            stake_account.exit(ctx.program_id)?;
+            if amount == 0 {
+                continue;
+            }
+            total = total.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+            
+            emit!(RewardsCompounded {
+                owner: stake_account.owner,
+                amount,
+                staked: stake_account.amount,
+                timestamp: now,
+            });
+        }
+        
+        if total > 0 {
+            let bump = *ctx.bumps.get("state").unwrap();
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.stake_vault.to_account_info(),
+                        authority: ctx.accounts.state.to_account_info(),
+                    },
+                    &[&[b"token_state", &[bump]]],
+                ),
+                total,
+            )?;
+        }
+        
+        Ok(())
+    }
+
+    /// Start the cooldown on `amount` of unlocked stake. It stops earning
+    /// and counting towards access straight away; a further request adds to
+    /// the amount and restarts the cooldown.
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let state = &ctx.accounts.state;
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        require!(amount <= stake_account.amount, ErrorCode::InsufficientStake);
+        require!(!stake_account.is_locked(now), ErrorCode::StakeLocked);
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        stake_account.settle(pool)?;
+        
+        stake_account.amount -= amount;
+        pool.total_staked -= amount;
+        stake_account.reweight(pool, &state.lock_tiers, now)?;
+        stake_account.unstaking_amount = stake_account
+            .unstaking_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        stake_account.unstake_available_at = now
+            .checked_add(state.unstake_cooldown_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(UnstakeRequested {
+            owner: stake_account.owner,
+            amount,
+            unstaking_amount: stake_account.unstaking_amount,
+            available_at: stake_account.unstake_available_at,
+            staked: stake_account.amount,
+            total_staked: pool.total_staked,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw everything requested once the cooldown has passed.
+    pub fn withdraw_unstaked(ctx: Context<WithdrawUnstaked>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stake_account = &mut ctx.accounts.stake_account;
+        let amount = stake_account.unstaking_amount;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        require!(
+            now >= stake_account.unstake_available_at,
+            ErrorCode::UnstakeCooldown
+        );
+        stake_account.unstaking_amount = 0;
+        
+        let bump = *ctx.bumps.get("state").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(Unstaked {
+            owner: stake_account.owner,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Leave at once, skipping any lock and the cooldown, for a penalty of
+    /// `early_exit_penalty_bps` of `amount`. The penalty is burned or paid
+    /// to the reward vault, where it accrues to the remaining stakers.
+    pub fn early_exit(ctx: Context<EarlyExit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let penalty_bps = ctx.accounts.state.early_exit_penalty_bps;
+        require!(penalty_bps > 0, ErrorCode::EarlyExitDisabled);
+        
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        require!(amount <= stake_account.amount, ErrorCode::InsufficientStake);
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        stake_account.settle(pool)?;
+        
+        stake_account.amount -= amount;
+        pool.total_staked -= amount;
+        stake_account.reweight(pool, &ctx.accounts.state.lock_tiers, now)?;
+        
+        let penalty = (amount as u128 * penalty_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let burn_penalty = ctx.accounts.state.burn_early_exit_penalty;
+        let bump = *ctx.bumps.get("state").unwrap();
+        let signer: &[&[&[u8]]] = &[&[b"token_state", &[bump]]];
+        if penalty > 0 {
+            if burn_penalty {
+                token::burn(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Burn {
+                            mint: ctx.accounts.mint.to_account_info(),
+                            from: ctx.accounts.stake_vault.to_account_info(),
+                            authority: ctx.accounts.state.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    penalty,
+                )?;
+                let state = &mut ctx.accounts.state;
+                state.total_burned = state
+                    .total_burned
+                    .checked_add(penalty)
+                    .ok_or(ErrorCode::MathOverflow)?;
+            } else {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Transfer {
+                            from: ctx.accounts.stake_vault.to_account_info(),
+                            to: ctx.accounts.reward_vault.to_account_info(),
+                            authority: ctx.accounts.state.to_account_info(),
+                        },
+                        signer,
+                    ),
+                    penalty,
+                )?;
+            }
+        }
+        
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                signer,
+            ),
+            amount - penalty,
+        )?;
+        
+        emit!(EarlyExited {
+            owner: stake_account.owner,
+            amount,
+            penalty,
+            penalty_burned: burn_penalty,
+            staked: stake_account.amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_staking_config(
+        ctx: Context<SetStakingConfig>,
+        unstake_cooldown_secs: u64,
+        early_exit_penalty_bps: u64,
+        burn_early_exit_penalty: bool,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_config = (
+            state.unstake_cooldown_secs,
+            state.early_exit_penalty_bps,
+            state.burn_early_exit_penalty,
+        );
+        state.set_staking_config(
+            unstake_cooldown_secs,
+            early_exit_penalty_bps,
+            burn_early_exit_penalty,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetStakingConfig,
+            &old_config,
+            &(unstake_cooldown_secs, early_exit_penalty_bps, burn_early_exit_penalty),
+        )
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        pool.accrue(ctx.accounts.reward_vault.amount)?;
+        stake_account.settle(pool)?;
+        // Drops the multiplier of a lock that has run out
+        stake_account.reweight(pool, &ctx.accounts.state.lock_tiers, now)?;
+        
+        let amount = stake_account.pending_rewards;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        stake_account.pending_rewards = 0;
+        pool.reserved_rewards = pool.reserved_rewards.saturating_sub(amount);
+        
+        let bump = *ctx.bumps.get("state").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.state.to_account_info(),
+                },
+                &[&[b"token_state", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(RewardsClaimed {
+            owner: stake_account.owner,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Replace the lock tier table. Existing stake keeps the weight it was
+    /// given until its next stake, unstake, or claim.
+    pub fn set_lock_tiers(
+        ctx: Context<SetLockTiers>,
+        tiers: [LockTier; LOCK_TIERS],
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_tiers = state.lock_tiers;
+        state.set_lock_tiers(tiers, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetLockTiers,
+            &old_tiers,
+            &tiers,
+        )
+    }
+
+    /// Publish the merkle root of the next revenue epoch and fund its vault
+    /// with `total_amount` from the funder's account. Epochs are numbered
+    /// consecutively and revenue stays claimable indefinitely.
+    pub fn publish_revenue_root(
+        ctx: Context<PublishRevenueRoot>,
+        merkle_root: [u8; 32],
+        snapshot_slot: u64,
+        total_amount: u64,
+        max_claims: u32,
+    ) -> Result<()> {
+        let now = Clock::get()?;
+        require!(
+            total_amount > 0 && max_claims > 0 && snapshot_slot <= now.slot,
+            ErrorCode::InvalidDistribution
+        );
+        
+        let state = &mut ctx.accounts.state;
+        let revenue_epoch = &mut ctx.accounts.revenue_epoch;
+        revenue_epoch.epoch = state.next_revenue_epoch;
+        revenue_epoch.revenue_mint = ctx.accounts.revenue_mint.key();
+        revenue_epoch.merkle_root = merkle_root;
+        revenue_epoch.snapshot_slot = snapshot_slot;
+        revenue_epoch.total_amount = total_amount;
+        revenue_epoch.claimed_amount = 0;
+        revenue_epoch.max_claims = max_claims;
+        revenue_epoch.published_at = now.unix_timestamp;
+        state.next_revenue_epoch = state
+            .next_revenue_epoch
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.revenue_vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            total_amount,
+        )?;
+        
+        emit!(RevenueRootPublished {
+            epoch: revenue_epoch.epoch,
+            revenue_mint: revenue_epoch.revenue_mint,
+            merkle_root,
+            snapshot_slot,
+            total_amount,
+            max_claims,
+            timestamp: now.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::PublishRevenueRoot,
+            &(),
+            &(revenue_epoch.epoch, merkle_root, total_amount, max_claims),
+        )
+    }
+
+    /// Create one of the bitmaps recording revenue claims; anyone can pay for it.
+    pub fn initialize_revenue_bitmap(
+        ctx: Context<InitializeRevenueBitmap>,
+        chunk: u32,
+    ) -> Result<()> {
+        require!(
+            chunk < ctx.accounts.revenue_epoch.bitmap_count(),
+            ErrorCode::InvalidDistribution
+        );
+        let bitmap = &mut ctx.accounts.claim_bitmap;
+        bitmap.distribution = ctx.accounts.revenue_epoch.key();
+        bitmap.chunk = chunk;
+        
+        Ok(())
+    }
+
+    /// Claim a holder's share of an epoch's revenue
+    pub fn claim_revenue(
+        ctx: Context<ClaimRevenue>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let revenue_epoch = &mut ctx.accounts.revenue_epoch;
+        require!(index < revenue_epoch.max_claims, ErrorCode::InvalidProof);
+        
+        let leaf = leaf_hash(index, ctx.accounts.holder.key, amount);
+        require!(
+            verify_proof(&proof, &revenue_epoch.merkle_root, leaf),
+            ErrorCode::InvalidProof
+        );
+        ctx.accounts.claim_bitmap.set_claimed(index)?;
+        
+        revenue_epoch.claimed_amount = revenue_epoch
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let epoch = revenue_epoch.epoch.to_le_bytes();
+        let bump = *ctx.bumps.get("revenue_epoch").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.revenue_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: revenue_epoch.to_account_info(),
+                },
+                &[&[b"revenue_epoch", epoch.as_ref(), &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        emit!(RevenueClaimed {
+            epoch: revenue_epoch.epoch,
+            index,
+            holder: ctx.accounts.holder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Record who referred the signing user. Each user can be referred
+    /// once, never by themselves and never by someone they referred.
+    pub fn register_referral(ctx: Context<RegisterReferral>, referrer: Pubkey) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        require!(
+            referrer != user && referrer != Pubkey::default(),
+            ErrorCode::SelfReferral
+        );
+        check_referral_loop(&ctx.accounts.referrer_referral, &user)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let referral = &mut ctx.accounts.referral;
+        referral.user = user;
+        referral.referrer = referrer;
+        referral.registered_at = now;
+        referral.rewards_paid = 0;
+        
+        emit!(ReferralRegistered {
+            user,
+            referrer,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the share of protocol fees paid to referrers
+    pub fn set_referral_config(ctx: Context<SetReferralConfig>, referral_bps: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_bps = state.referral_bps;
+        state.set_referral_bps(referral_bps, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetReferralConfig,
+            &old_bps,
+            &referral_bps,
+        )
+    }
+
+    /// Set the share of protocol fees set aside in the insurance fund,
+    /// creating the fund on first use
+    pub fn set_insurance_share(ctx: Context<SetInsuranceShare>, insurance_bps: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let now = Clock::get()?.unix_timestamp;
+        let fund = &mut ctx.accounts.insurance_fund;
+        if fund.created_at == 0 {
+            fund.mint = ctx.accounts.mint.key();
+            fund.created_at = now;
+        }
+        let old_bps = state.insurance_bps;
+        state.insurance_vault = ctx.accounts.insurance_vault.key();
+        state.set_insurance_bps(insurance_bps, now)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetInsuranceShare,
+            &old_bps,
+            &insurance_bps,
+        )
+    }
+
+    /// Declare an incident the insurance fund may pay out up to
+    /// `max_amount` for. Draws open once the timelock delay has passed.
+    pub fn declare_incident(
+        ctx: Context<DeclareIncident>,
+        incident_id: u64,
+        report_hash: [u8; 32],
+        max_amount: u64,
+    ) -> Result<()> {
+        require!(max_amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        let drawable_at = now.saturating_add(ctx.accounts.state.timelock_delay_secs as i64);
+        let incident = &mut ctx.accounts.incident;
+        incident.incident_id = incident_id;
+        incident.report_hash = report_hash;
+        incident.max_amount = max_amount;
+        incident.declared_at = now;
+        incident.drawable_at = drawable_at;
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.incidents = fund.incidents.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(IncidentDeclared {
+            incident_id,
+            report_hash,
+            max_amount,
+            declared_by: ctx.accounts.authority.key(),
+            drawable_at,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::DeclareIncident,
+            &(),
+            &(incident_id, report_hash, max_amount),
+        )
+    }
+
+    /// Pay `amount` out of the insurance fund to `recipient` for a declared
+    /// incident
+    pub fn draw_insurance(
+        ctx: Context<DrawInsurance>,
+        incident_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.insurance_vault.amount >= amount,
+            ErrorCode::InsufficientInsuranceFunds
+        );
+        let drawn = ctx.accounts.incident.record_draw(amount, now)?;
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_drawn = fund.total_drawn.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        
+        let bump = *ctx.bumps.get("insurance_fund").unwrap();
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.insurance_vault.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.insurance_fund.to_account_info(),
+                },
+                &[&[b"insurance_fund", &[bump]]],
+            ),
+            amount,
+        )?;
+        
+        let incident = &ctx.accounts.incident;
+        emit!(InsuranceDrawn {
+            incident_id,
+            report_hash: incident.report_hash,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            drawn_for_incident: drawn,
+            incident_remaining: incident.remaining(),
+            fund_remaining: ctx.accounts.insurance_vault.amount - amount,
+            total_drawn: ctx.accounts.insurance_fund.total_drawn,
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::DrawInsurance,
+            &(),
+            &(incident_id, ctx.accounts.recipient.key(), amount),
+        )
+    }
+
+    /// Escrow the current requirement for `duration_secs` in exchange for
+    /// an access pass, paying the subscription fee on top when one is
+    /// charged.
+    pub fn subscribe(ctx: Context<Subscribe>, duration_secs: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(
+            (MIN_SUBSCRIPTION_SECS..=MAX_SUBSCRIPTION_SECS).contains(&duration_secs),
+            ErrorCode::InvalidParameter
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let amount = ctx.accounts.state.current_requirement;
+        let expires_at = now
+            .checked_add(duration_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        // The fee is charged on top; the full requirement is escrowed
+        let after_fee = collect_fee(
+            &ctx.accounts.state,
+            FeeOperation::Subscription,
+            amount,
+            &ctx.accounts.source,
+            ctx.accounts.fee_destination.as_ref(),
+            ctx.accounts.insurance_vault.as_ref(),
+            ctx.accounts.referral.as_mut(),
+            ctx.accounts.referrer_token_account.as_ref(),
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+        let fee = amount.checked_sub(after_fee).ok_or(ErrorCode::MathOverflow)?;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.subscription_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let access_pass = &mut ctx.accounts.access_pass;
+        access_pass.owner = ctx.accounts.owner.key();
+        access_pass.escrowed_amount = amount;
+        access_pass.starts_at = now;
+        access_pass.expires_at = expires_at;
+        
+        emit!(Subscribed {
+            owner: access_pass.owner,
+            amount,
+            fee,
+            expires_at: access_pass.expires_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Return an expired subscription's escrow and close its pass
+    pub fn unsubscribe(ctx: Context<Unsubscribe>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let access_pass = &ctx.accounts.access_pass;
+        require!(!access_pass.is_active(now), ErrorCode::SubscriptionActive);
+        
+        let vault = &ctx.accounts.subscription_vault;
+        let amount = vault.amount;
+        let owner = access_pass.owner;
+        let bump = *ctx.bumps.get("access_pass").unwrap();
+        let seeds: &[&[u8]] = &[b"access_pass", owner.as_ref(), &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: access_pass.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: vault.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: access_pass.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        
+        emit!(Unsubscribed {
+            owner,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// List `mint` for streamed billing at no less than `min_rate_per_sec`,
+    /// or delist it with zero. Open streams keep their rate.
+    pub fn set_stream_rate(
+        ctx: Context<SetStreamRate>,
+        mint: Pubkey,
+        min_rate_per_sec: u64,
+    ) -> Result<()> {
+        let old_rate = ctx
+            .accounts
+            .stream_config
+            .set_rate(mint, min_rate_per_sec)?;
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetStreamRate,
+            &(mint, old_rate),
+            &(mint, min_rate_per_sec),
+        )
+    }
+
+    /// Escrow `amount` of a listed mint and start streaming it to the
+    /// treasury at `rate_per_sec`
+    pub fn open_stream(ctx: Context<OpenStream>, rate_per_sec: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let mint = ctx.accounts.mint.key();
+        let min_rate = ctx
+            .accounts
+            .stream_config
+            .min_rate(&mint)
+            .ok_or(ErrorCode::StreamMintNotListed)?;
+        require!(rate_per_sec >= min_rate, ErrorCode::StreamRateTooLow);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.stream_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let stream = &mut ctx.accounts.stream;
+        stream.payer = ctx.accounts.payer.key();
+        stream.mint = mint;
+        stream.rate_per_sec = rate_per_sec;
+        stream.top_up(amount, now)?;
+        
+        emit!(StreamOpened {
+            payer: stream.payer,
+            mint,
+            rate_per_sec,
+            amount,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Add to a stream's escrow, reviving it if it ran dry
+    pub fn top_up_stream(ctx: Context<TopUpStream>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.stream_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let stream = &mut ctx.accounts.stream;
+        stream.top_up(amount, now)?;
+        
+        emit!(StreamToppedUp {
+            payer: stream.payer,
+            amount,
+            deposited: stream.deposited,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Pay what a stream has accrued into the treasury's vault for its
+    /// mint. Anyone may call this.
+    pub fn withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stream = &ctx.accounts.stream;
+        let amount = stream.claimable(now)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        
+        let bump = *ctx.bumps.get("stream").unwrap();
+        let seeds: &[&[u8]] = &[b"payment_stream", stream.payer.as_ref(), &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.stream_vault.to_account_info(),
+                    to: ctx.accounts.treasury_vault.to_account_info(),
+                    authority: stream.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        
+        let stream = &mut ctx.accounts.stream;
+        stream.withdrawn = stream
+            .withdrawn
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(StreamWithdrawn {
+            payer: stream.payer,
+            amount,
+            withdrawn: stream.withdrawn,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// End a stream: pay the treasury what has accrued, refund the rest of
+    /// the escrow to `destination`, and close the stream
+    pub fn close_stream(ctx: Context<CloseStream>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stream = &ctx.accounts.stream;
+        let owed = stream.claimable(now)?;
+        let refund = stream
+            .deposited
+            .checked_sub(stream.accrued(now))
+            .ok_or(ErrorCode::MathOverflow)?;
+        let paid = stream
+            .withdrawn
+            .checked_add(owed)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let bump = *ctx.bumps.get("stream").unwrap();
+        let seeds: &[&[u8]] = &[b"payment_stream", stream.payer.as_ref(), &[bump]];
+        for (to, amount) in [
+            (&ctx.accounts.treasury_vault, owed),
+            (&ctx.accounts.destination, refund),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.stream_vault.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: stream.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.stream_vault.to_account_info(),
+                destination: ctx.accounts.payer.to_account_info(),
+                authority: stream.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        
+        emit!(StreamClosed {
+            payer: stream.payer,
+            paid,
+            refund,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Create or change the recurring plan `plan_id`. Changes apply to
+    /// existing subscribers from their next renewal.
+    pub fn set_subscription_plan(
+        ctx: Context<SetSubscriptionPlan>,
+        plan_id: u32,
+        args: SubscriptionPlanArgs,
+    ) -> Result<()> {
+        let plan = &mut ctx.accounts.plan;
+        let old_args = plan.args();
+        plan.configure(plan_id, &args)?;
+        
+        emit!(SubscriptionPlanSet {
+            plan_id,
+            args,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetSubscriptionPlan,
+            &old_args,
+            &args,
+        )
+    }
+
+    /// Subscribe to `plan_id`, charging the first period into the treasury.
+    /// Later periods are charged by `process_renewal` from the same source.
+    pub fn create_subscription(ctx: Context<CreateSubscription>, plan_id: u32) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        let plan = &ctx.accounts.plan;
+        require!(plan.active, ErrorCode::SubscriptionPlanInactive);
+        let now = Clock::get()?.unix_timestamp;
+        // The account outlives an expired subscription, which can be renewed
+        require!(
+            !ctx.accounts.subscription.is_active(now),
+            ErrorCode::SubscriptionActive
+        );
+        
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.treasury_vault.to_account_info(),
+                    authority: ctx.accounts.subscriber.to_account_info(),
+                },
+            ),
+            plan.price,
+        )?;
+        
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.subscriber = ctx.accounts.subscriber.key();
+        subscription.plan = plan.key();
+        subscription.source = ctx.accounts.source.key();
+        subscription.next_renewal_at = plan.period_end(now)?;
+        subscription.renewals = 0;
+        subscription.expired = false;
+        
+        emit!(SubscriptionCreated {
+            subscriber: subscription.subscriber,
+            plan_id,
+            amount: plan.price,
+            next_renewal_at: subscription.next_renewal_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Charge the next period of a subscription once its renewal window
+    /// opens, or expire it if the period ended and the source no longer
+    /// covers the price. Anyone may crank this.
+    pub fn process_renewal(ctx: Context<ProcessRenewal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let plan = &ctx.accounts.plan;
+        let source = &ctx.accounts.source;
+        let subscription = &ctx.accounts.subscription;
+        require!(!subscription.expired, ErrorCode::SubscriptionAlreadyExpired);
+        require!(
+            now >= subscription.renewal_opens_at(plan),
+            ErrorCode::RenewalNotDue
+        );
+        
+        let chargeable = plan.active
+            && source.delegate == COption::Some(subscription.key())
+            && source.delegated_amount >= plan.price
+            && source.amount >= plan.price;
+        if !chargeable {
+            require!(
+                now >= subscription.next_renewal_at,
+                ErrorCode::RenewalNotAuthorized
+            );
+            let subscription = &mut ctx.accounts.subscription;
+            subscription.expired = true;
+            emit!(SubscriptionExpired {
+                subscriber: subscription.subscriber,
+                plan_id: plan.plan_id,
+                timestamp: now,
+            });
+            return Ok(());
+        }
+        
+        let subscriber = subscription.subscriber;
+        let bump = *ctx.bumps.get("subscription").unwrap();
+        let seeds: &[&[u8]] = &[b"recurring_subscription", subscriber.as_ref(), &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: source.to_account_info(),
+                    to: ctx.accounts.treasury_vault.to_account_info(),
+                    authority: subscription.to_account_info(),
+                },
+                &[seeds],
+            ),
+            plan.price,
+        )?;
+        
+        // A late crank starts the new period now instead of back-dating it
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.next_renewal_at = plan.period_end(subscription.next_renewal_at.max(now))?;
+        subscription.renewals = subscription
+            .renewals
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        emit!(SubscriptionRenewed {
+            subscriber,
+            plan_id: plan.plan_id,
+            amount: plan.price,
+            next_renewal_at: subscription.next_renewal_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn open_usage_counter(ctx: Context<OpenUsageCounter>, feature_id: u32) -> Result<()> {
+        let counter = &mut ctx.accounts.usage_counter;
+        counter.owner = ctx.accounts.owner.key();
+        counter.feature_id = feature_id;
+        counter.uses = 0;
+        counter.consumed = 0;
+        counter.last_used_at = 0;
+        
+        Ok(())
+    }
+
+    /// Pay `amount` for one use of `feature_id`, as an alternative to
+    /// holding the requirement. The payment is burned or sent to the
+    /// treasury token vault depending on `burn_consumed_access`.
+    pub fn consume_access(ctx: Context<ConsumeAccess>, _feature_id: u32, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        
+        let burned = pay_for_access(
+            &mut ctx.accounts.state,
+            &ctx.accounts.mint,
+            &ctx.accounts.source,
+            ctx.accounts.treasury_vault.as_ref(),
+            ctx.accounts.owner.to_account_info(),
+            &[],
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        let counter = &mut ctx.accounts.usage_counter;
+        counter.record(amount, now)?;
+        
+        emit!(AccessConsumed {
+            owner: counter.owner,
+            feature_id: counter.feature_id,
+            amount,
+            burned,
+            uses: counter.uses,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Let `session_key` act for the signer within `permissions` until
+    /// `expires_at`. Granting `SESSION_CONSUME_ACCESS` approves the session
+    /// to spend up to `spend_limit` from `token_account`.
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        session_key: Pubkey,
+        permissions: u8,
+        expires_at: i64,
+        spend_limit: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let user = ctx.accounts.user.key();
+        require!(
+            session_key != user
+                && permissions != 0
+                && permissions & !SESSION_PERMISSIONS == 0
+                && expires_at > now
+                && expires_at <= now.saturating_add(MAX_SESSION_SECS),
+            ErrorCode::InvalidSessionKey
+        );
+        
+        let session = &mut ctx.accounts.session;
+        session.user = user;
+        session.session_key = session_key;
+        session.permissions = permissions;
+        session.created_at = now;
+        session.expires_at = expires_at;
+        session.spend_limit = 0;
+        session.spent = 0;
+        
+        if permissions & SESSION_CONSUME_ACCESS != 0 && spend_limit > 0 {
+            let token_account = ctx
+                .accounts
+                .token_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidSessionKey)?;
+            token::approve(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Approve {
+                        to: token_account.to_account_info(),
+                        delegate: session.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                spend_limit,
+            )?;
+            session.spend_limit = spend_limit;
+        }
+        
+        emit!(SessionKeyCreated {
+            user,
+            session_key,
+            permissions,
+            expires_at,
+            spend_limit: session.spend_limit,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Close a session; the user or the session key itself can sign. The
+    /// SPL approval is left behind on the token account, but nothing can
+    /// use it once the session account is gone.
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        let session = &ctx.accounts.session;
+        let signer = ctx.accounts.signer.key();
+        require!(
+            signer == session.user || signer == session.session_key,
+            ErrorCode::Unauthorized
+        );
+        
+        emit!(SessionKeyRevoked {
+            user: session.user,
+            session_key: session.session_key,
+            revoked_by: signer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// `consume_access` signed by a session key holding
+    /// `SESSION_CONSUME_ACCESS`, paid from the user's account under the
+    /// session's approval.
+    pub fn consume_access_with_session(
+        ctx: Context<ConsumeAccessWithSession>,
+        _feature_id: u32,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidParameter);
+        let now = Clock::get()?.unix_timestamp;
+        
+        let session = &mut ctx.accounts.session;
+        require!(
+            session.authorizes(SESSION_CONSUME_ACCESS, now),
+            ErrorCode::SessionKeyUnauthorized
+        );
+        session.spend(amount)?;
+        
+        let user = session.user;
+        let session_key = session.session_key;
+        let bump = *ctx.bumps.get("session").unwrap();
+        let burned = pay_for_access(
+            &mut ctx.accounts.state,
+            &ctx.accounts.mint,
+            &ctx.accounts.source,
+            ctx.accounts.treasury_vault.as_ref(),
+            ctx.accounts.session.to_account_info(),
+            &[&[b"session", user.as_ref(), session_key.as_ref(), &[bump]]],
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        let counter = &mut ctx.accounts.usage_counter;
+        counter.record(amount, now)?;
+        
+        emit!(AccessConsumed {
+            owner: counter.owner,
+            feature_id: counter.feature_id,
+            amount,
+            burned,
+            uses: counter.uses,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_pay_per_use_config(
+        ctx: Context<SetPayPerUseConfig>,
+        burn_consumed_access: bool,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let old_burn = state.burn_consumed_access;
+        state.burn_consumed_access = burn_consumed_access;
+        
+        emit!(PayPerUseConfigUpdated {
+            burn_consumed_access,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetPayPerUseConfig,
+            &old_burn,
+            &burn_consumed_access,
+        )
+    }
+
+    pub fn set_receipt_validity(
+        ctx: Context<SetReceiptValidity>,
+        receipt_validity_secs: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_validity = state.receipt_validity_secs;
+        state.set_receipt_validity(receipt_validity_secs, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetReceiptValidity,
+            &old_validity,
+            &receipt_validity_secs,
+        )
+    }
+
+    pub fn set_access_tiers(ctx: Context<SetAccessTiers>, tiers: [u64; ACCESS_TIERS]) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_tiers = state.access_tiers;
+        state.set_access_tiers(tiers, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetAccessTiers,
+            &old_tiers,
+            &tiers,
+        )
+    }
+
+    /// Set how long a verified wallet keeps access after falling below the
+    /// requirement; zero revokes access at the first failed verification.
+    pub fn set_grace_period(ctx: Context<SetGracePeriod>, grace_period_secs: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(state.timelock_delay_secs == 0, ErrorCode::TimelockRequired);
+        let old_grace_period = state.grace_period_secs;
+        state.set_grace_period(grace_period_secs, Clock::get()?.unix_timestamp)?;
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetGracePeriod,
+            &old_grace_period,
+            &grace_period_secs,
+        )
+    }
+
+    pub fn set_min_holding_period(
+        ctx: Context<SetMinHoldingPeriod>,
+        min_holding_secs: u64,
+    ) -> Result<()> {
+        require!(min_holding_secs <= MAX_MIN_HOLDING_SECS, ErrorCode::InvalidParameter);
+        let state = &mut ctx.accounts.state;
+        let old_min_holding = state.min_holding_secs;
+        state.min_holding_secs = min_holding_secs;
+        
+        emit!(MinHoldingPeriodUpdated {
+            old_min_holding_secs: old_min_holding,
+            new_min_holding_secs: min_holding_secs,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetMinHoldingPeriod,
+            &old_min_holding,
+            &min_holding_secs,
+        )
+    }
+
+    /// Record what the owner of `token_account` holds there and in their
+    /// stake, starting the minimum holding clock on anything new. Anyone
+    /// can checkpoint a wallet; the first checkpoint fixes which token
+    /// account is tracked.
+    pub fn checkpoint_holdings(ctx: Context<CheckpointHoldings>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let token_account = &ctx.accounts.token_account;
+        let checkpoint = &mut ctx.accounts.holdings_checkpoint;
+        if checkpoint.owner == Pubkey::default() {
+            checkpoint.owner = token_account.owner;
+            checkpoint.token_account = token_account.key();
+        }
+        require_keys_eq!(
+            checkpoint.token_account,
+            token_account.key(),
+            ErrorCode::InvalidHoldingsCheckpoint
+        );
+        
+        let staked = ctx.accounts.stake_account.as_ref().map_or(0, |stake| stake.amount);
+        let holdings = token_account.amount.saturating_add(staked);
+        checkpoint.update(holdings, ctx.accounts.state.min_holding_secs, now);
+        
+        emit!(HoldingsCheckpointed {
+            owner: checkpoint.owner,
+            matured_amount: checkpoint.matured_amount,
+            pending_amount: checkpoint.pending_amount,
+            pending_since: checkpoint.pending_since,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_gating_config(ctx: Context<InitializeGatingConfig>) -> Result<()> {
+        let gating_config = &mut ctx.accounts.gating_config;
+        gating_config.mints = [GatedMint::EMPTY; MAX_GATED_MINTS];
+        gating_config.lp_pools = [LpPool::EMPTY; MAX_LP_POOLS];
+        Ok(())
+    }
+
+    /// Count `gated_mint` towards access at `weight_bps` of the token, or
+    /// stop counting it with a zero weight. The mint must share the
+    /// token's decimals so weights compare like for like.
+    pub fn set_gated_mint(ctx: Context<SetGatedMint>, weight_bps: u64) -> Result<()> {
+        let gated_mint = ctx.accounts.gated_mint.key();
+        require!(
+            gated_mint != ctx.accounts.state.mint
+                && ctx.accounts.gated_mint.decimals == ctx.accounts.token_mint.decimals,
+            ErrorCode::InvalidParameter
+        );
+        let gating_config = &mut ctx.accounts.gating_config;
+        let old_weight = gating_config.weight_of(&gated_mint).unwrap_or(0);
+        gating_config.set(gated_mint, weight_bps)?;
+        
+        emit!(GatedMintUpdated {
+            mint: gated_mint,
+            old_weight_bps: old_weight,
+            new_weight_bps: weight_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetGatedMint,
+            &(gated_mint, old_weight),
+            &(gated_mint, weight_bps),
+        )
+    }
+
+    /// Count providers of the pool behind `lp_mint` at their share of its
+    /// `reserve_vault`; without a vault the pool is delisted.
+    pub fn set_lp_pool(ctx: Context<SetLpPool>, listed: bool) -> Result<()> {
+        let lp_mint = ctx.accounts.lp_mint.key();
+        let reserve_vault = ctx.accounts.reserve_vault.key();
+        let token_mint = ctx.accounts.state.mint;
+        require!(
+            lp_mint != token_mint && ctx.accounts.reserve_vault.mint == token_mint,
+            ErrorCode::InvalidLpPool
+        );
+        let gating_config = &mut ctx.accounts.gating_config;
+        let old_pool = gating_config.lp_pool(&lp_mint).copied().unwrap_or(LpPool::EMPTY);
+        gating_config.set_lp_pool(lp_mint, listed.then_some(reserve_vault))?;
+        
+        emit!(LpPoolUpdated {
+            lp_mint,
+            reserve_vault,
+            listed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetLpPool,
+            &old_pool,
+            &gating_config.lp_pool(&lp_mint).copied().unwrap_or(LpPool::EMPTY),
+        )
+    }
+
+    /// Record the pool's reserve and LP supply as they stand, capping what
+    /// its LP tokens count for from the next slot on. Anyone can observe a
+    /// listed pool; a later observation replaces one that caught the
+    /// reserves low.
+    pub fn observe_lp_pool(ctx: Context<ObserveLpPool>) -> Result<()> {
+        let clock = Clock::get()?;
+        let lp_mint = ctx.accounts.lp_mint.key();
+        let pool = ctx
+            .accounts
+            .gating_config
+            .lp_pool(&lp_mint)
+            .copied()
+            .ok_or(ErrorCode::InvalidLpPool)?;
+        require_keys_eq!(
+            ctx.accounts.reserve_vault.key(),
+            pool.reserve_vault,
+            ErrorCode::InvalidLpPool
+        );
+        let lp_supply = ctx.accounts.lp_mint.supply;
+        let reserve = ctx.accounts.reserve_vault.amount;
+        ctx.accounts
+            .gating_config
+            .observe_lp_pool(&lp_mint, lp_supply, reserve, clock.slot)?;
+        
+        emit!(LpPoolObserved {
+            lp_mint,
+            reserve,
+            lp_supply,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_member_registry(ctx: Context<InitializeMemberRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.member_registry;
+        registry.total_members = 0;
+        registry.active_members = 0;
+        Ok(())
+    }
+
+    /// Bring the wallet's member record in line with its receipt, creating
+    /// it with the next index on first sync. Anyone can sync, so lapsed
+    /// members drop out of the active set without their cooperation.
+    pub fn sync_member(ctx: Context<SyncMember>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let receipt = &ctx.accounts.receipt;
+        let registry = &mut ctx.accounts.member_registry;
+        let member = &mut ctx.accounts.member;
+        
+        if member.wallet == Pubkey::default() {
+            member.index = registry.total_members;
+            member.wallet = receipt.wallet;
+            member.active = false;
+            member.joined_at = now;
+            registry.total_members = registry
+                .total_members
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        
+        let active = receipt.is_valid(now) && receipt.tier != NO_ACCESS;
+        if active != member.active {
+            registry.active_members = if active {
+                registry.active_members.checked_add(1).ok_or(ErrorCode::MathOverflow)?
+            } else {
+                registry.active_members.saturating_sub(1)
+            };
+        }
+        member.active = active;
+        member.tier = receipt.tier;
+        member.expires_at = receipt.expires_at;
+        member.synced_at = now;
+        
+        emit!(MemberSynced {
+            wallet: member.wallet,
+            index: member.index,
+            active,
+            tier: member.tier,
+            expires_at: member.expires_at,
+            active_members: registry.active_members,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// CPI entrypoint for programs gating their own instructions on AISTM7
+    /// access. Reads the wallet's receipt and override without writing
+    /// anything and returns the outcome as return data; lacking access is
+    /// reported, not raised. `aistm7_interface::cpi::require_access` wraps
+    /// the call for Rust programs.
+    pub fn check_access(ctx: Context<CheckAccess>, min_tier: u8) -> Result<AccessStatus> {
+        access_status(
+            ctx.accounts.wallet.key(),
+            &ctx.accounts.receipt,
+            &ctx.accounts.access_override,
+            min_tier,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Record a receipt from an attestor's off-chain verification. The
+    /// instruction before this one must be an ed25519 program instruction
+    /// in which a key holding `Role::Attestor` signs `attestation.message()`.
+    /// The receipt runs from `attested_at`, so it is no fresher than the
+    /// check behind it, and never replaces a more recent verification.
+    pub fn submit_attestation(
+        ctx: Context<SubmitAttestation>,
+        attestation: Attestation,
+    ) -> Result<()> {
+        let accounts = &mut ctx.accounts;
+        accept_attestation(
+            &accounts.state,
+            accounts.role_registry.as_deref(),
+            &mut accounts.receipt,
+            &accounts.access_override,
+            &accounts.instructions,
+            &attestation,
+            &attestation.message(),
+        )
+    }
+
+    /// Record a receipt for a holder whose balance is in a confidential
+    /// token-2022 account. The holder discloses the balance to an attestor
+    /// off chain, who signs `attestation.message()` in the ed25519
+    /// instruction before this one; the chain only learns that the
+    /// requirement was met.
+    pub fn submit_confidential_attestation(
+        ctx: Context<SubmitConfidentialAttestation>,
+        attestation: ConfidentialAttestation,
+    ) -> Result<()> {
+        require!(
+            is_confidential_account(&ctx.accounts.token_account.to_account_info())?,
+            ErrorCode::NotConfidentialAccount
+        );
+        
+        let accounts = &mut ctx.accounts;
+        accept_attestation(
+            &accounts.state,
+            accounts.role_registry.as_deref(),
+            &mut accounts.receipt,
+            &accounts.access_override,
+            &accounts.instructions,
+            &attestation.attestation(),
+            &attestation.message(),
+        )
+    }
+
+    /// Force `wallet` to be allowed or denied in every verification path
+    /// regardless of its balance, replacing any override it already has.
+    pub fn set_access_override(
+        ctx: Context<SetAccessOverride>,
+        wallet: Pubkey,
+        kind: AccessOverrideKind,
+        reason: [u8; 32],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let access_override = &mut ctx.accounts.access_override;
+        let old_kind = (access_override.wallet == wallet).then_some(access_override.kind);
+        access_override.wallet = wallet;
+        access_override.kind = kind;
+        access_override.reason = reason;
+        access_override.set_by = ctx.accounts.authority.key();
+        access_override.set_at = now;
+        
+        emit!(AccessOverrideSet {
+            wallet,
+            kind,
+            reason,
+            set_by: ctx.accounts.authority.key(),
+            timestamp: now,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::SetAccessOverride,
+            &(wallet, old_kind),
+            &(wallet, Some(kind)),
+        )
+    }
+
+    /// Return `wallet` to ordinary balance-based verification
+    pub fn clear_access_override(ctx: Context<ClearAccessOverride>) -> Result<()> {
+        let access_override = &ctx.accounts.access_override;
+        let wallet = access_override.wallet;
+        let old_kind = access_override.kind;
+        
+        emit!(AccessOverrideCleared {
+            wallet,
+            kind: old_kind,
+            cleared_by: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_admin_action(
+            &ctx.accounts.state,
+            &mut ctx.accounts.admin_log,
+            ctx.accounts.authority.key(),
+            AdminAction::ClearAccessOverride,
+            &(wallet, Some(old_kind)),
+            &(wallet, None::<AccessOverrideKind>),
+        )
+    }
+
+    /// Whether the holder meets the requirement, recorded in their
+    /// verification receipt. Passing their stake account counts staked
+    /// tokens too, boosted by the lock tier while locked, and an active
+    /// access pass meets the requirement on its own. Anyone can pay to
+    /// refresh a receipt, so one outlives a dropped balance by at most
+    /// `receipt_validity_secs`. The receipt also records the highest
+    /// `access_tiers` level reached; an access pass counts as the lowest.
+    /// A wallet that falls short after verifying keeps its tier for
+    /// `grace_period_secs`, counted from the first verification that
+    /// found it short. While a minimum holding period is set, tokens the
+    /// holdings checkpoint has not seen mature do not count.
+    pub fn verify_balance(ctx: Context<VerifyBalance>) -> Result<bool> {
+        let accounts = &mut *ctx.accounts;
+        let wallet = accounts.token_account.owner;
+        let extra_balance = extra_token_balance(
+            &accounts.token_account,
+            ctx.remaining_accounts,
+            accounts.gating_config.as_deref(),
+            &accounts.state.migration_mint,
+            Clock::get()?.slot,
+        )?;
+        Ok(verify_holder(
+            &accounts.state,
+            accounts.state.current_requirement,
+            &accounts.token_account,
+            HolderExtras {
+                access_override: read_access_override(&accounts.access_override)?,
+                extra_balance,
+                stake_account: accounts.stake_account.as_deref(),
+                access_pass: accounts.access_pass.as_deref(),
+                payment_stream: accounts.payment_stream.as_deref(),
+                recurring_subscription: accounts.recurring_subscription.as_deref(),
+                holdings_checkpoint: accounts.holdings_checkpoint.as_deref_mut(),
+            },
+            &mut accounts.receipt,
+            wallet,
+            i64::MAX,
+            Clock::get()?.unix_timestamp,
+        ))
+    }
+
+    /// `verify_balance` against the USD target at the oracle's current
+    /// price rather than the cached requirement, so access tracks the
+    /// market between cranks. The requirement this implies still respects
+    /// the configured token bounds.
+    pub fn verify_balance_usd(ctx: Context<VerifyBalanceUsd>) -> Result<bool> {
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &mut *ctx.accounts;
+        let state = &accounts.state;
+        require!(!state.circuit_breaker_tripped, ErrorCode::CircuitBreakerActive);
+        let price = read_oracle(
+            state,
+            &accounts.price_feed,
+            accounts.fallback_price_feed.as_ref(),
+            now,
+        )?;
+        let price = state
+            .accept_price(&price, now)?
+            .ok_or(ErrorCode::PriceUncertain)?;
+        let requirement = state.spot_requirement(price)?;
+        
+        let wallet = accounts.token_account.owner;
+        let extra_balance = extra_token_balance(
+            &accounts.token_account,
+            ctx.remaining_accounts,
+            accounts.gating_config.as_deref(),
+            &accounts.state.migration_mint,
+            Clock::get()?.slot,
+        )?;
+        Ok(verify_holder(
+            state,
+            requirement,
+            &accounts.token_account,
+            HolderExtras {
+                access_override: read_access_override(&accounts.access_override)?,
+                extra_balance,
+                stake_account: accounts.stake_account.as_deref(),
+                access_pass: accounts.access_pass.as_deref(),
+                payment_stream: accounts.payment_stream.as_deref(),
+                recurring_subscription: accounts.recurring_subscription.as_deref(),
+                holdings_checkpoint: accounts.holdings_checkpoint.as_deref_mut(),
+            },
+            &mut accounts.receipt,
+            wallet,
+            i64::MAX,
+            now,
+        ))
+    }
+
+    /// Mint the wallet's soulbound badge from a valid receipt, or carry the
+    /// receipt's tier and expiry onto the badge it already holds. A wallet
+    /// whose receipt has lapsed has its badge burned instead. Meant to
+    /// follow `verify_balance` in the same transaction.
+    pub fn refresh_access_badge(ctx: Context<RefreshAccessBadge>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bump = *ctx.bumps.get("badge_authority").unwrap();
+        let accounts = &mut *ctx.accounts;
+        let receipt = &accounts.receipt;
+        let active = receipt.is_valid(now) && receipt.tier != NO_ACCESS;
+        let held = accounts.badge_token_account.amount > 0;
+        let seeds: &[&[&[u8]]] = &[&[b"badge_authority", &[bump]]];
+        let token_program = accounts.token_program.to_account_info();
+        
+        if active && !held {
+            if accounts.badge_token_account.is_frozen() {
+                token::thaw_account(CpiContext::new_with_signer(
+                    token_program.clone(),
+                    token::ThawAccount {
+                        account: accounts.badge_token_account.to_account_info(),
+                        mint: accounts.badge_mint.to_account_info(),
+                        authority: accounts.badge_authority.to_account_info(),
+                    },
+                    seeds,
+                ))?;
+            }
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    token::MintTo {
+                        mint: accounts.badge_mint.to_account_info(),
+                        to: accounts.badge_token_account.to_account_info(),
+                        authority: accounts.badge_authority.to_account_info(),
+                    },
+                    seeds,
+                ),
+                1,
+            )?;
+            // Lets the badge be burned without the wallet once it lapses
+            token::approve(
+                CpiContext::new(
+                    token_program.clone(),
+                    token::Approve {
+                        to: accounts.badge_token_account.to_account_info(),
+                        delegate: accounts.badge_authority.to_account_info(),
+                        authority: accounts.wallet.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+            token::freeze_account(CpiContext::new_with_signer(
+                token_program,
+                token::FreezeAccount {
+                    account: accounts.badge_token_account.to_account_info(),
+                    mint: accounts.badge_mint.to_account_info(),
+                    authority: accounts.badge_authority.to_account_info(),
+                },
+                seeds,
+            ))?;
+        } else if !active && held {
+            burn_badge(
+                &accounts.badge_mint,
+                &accounts.badge_token_account,
+                &accounts.badge_authority,
+                bump,
+                &accounts.token_program,
+            )?;
+        }
+        
+        let badge = &mut accounts.badge;
+        badge.wallet = accounts.wallet.key();
+        badge.mint = accounts.badge_mint.key();
+        badge.tier = if active { receipt.tier } else { NO_ACCESS };
+        badge.expires_at = receipt.expires_at;
+        badge.refreshed_at = now;
+        
+        emit!(AccessBadgeRefreshed {
+            wallet: badge.wallet,
+            mint: badge.mint,
+            tier: badge.tier,
+            expires_at: badge.expires_at,
+            held: active,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Burn a badge whose receipt has lapsed; anyone can call this so a
+    /// stale badge never outlives the access it stood for.
+    pub fn revoke_access_badge(ctx: Context<RevokeAccessBadge>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let receipt = &ctx.accounts.receipt;
+        require!(
+            !receipt.is_valid(now) || receipt.tier == NO_ACCESS,
+            ErrorCode::AccessBadgeActive
+        );
+        require!(ctx.accounts.badge_token_account.amount > 0, ErrorCode::InvalidParameter);
+        
+        burn_badge(
+            &ctx.accounts.badge_mint,
+            &ctx.accounts.badge_token_account,
+            &ctx.accounts.badge_authority,
+            *ctx.bumps.get("badge_authority").unwrap(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        let badge = &mut ctx.accounts.badge;
+        badge.tier = NO_ACCESS;
+        badge.refreshed_at = now;
+        
+        emit!(AccessBadgeRefreshed {
+            wallet: badge.wallet,
+            mint: badge.mint,
+            tier: NO_ACCESS,
+            expires_at: badge.expires_at,
+            held: false,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Let `hot_wallet` verify against the signer's holdings until
+    /// `expires_at`.
+    pub fn delegate_access(
+        ctx: Context<DelegateAccess>,
+        hot_wallet: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let cold_wallet = ctx.accounts.cold_wallet.key();
+        require_keys_neq!(hot_wallet, cold_wallet, ErrorCode::InvalidDelegation);
+        require!(
+            expires_at > now && expires_at <= now.saturating_add(MAX_DELEGATION_SECS),
+            ErrorCode::InvalidDelegation
+        );
+        
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.cold_wallet = cold_wallet;
+        delegation.hot_wallet = hot_wallet;
+        delegation.created_at = now;
+        delegation.expires_at = expires_at;
+        
+        emit!(AccessDelegated {
+            cold_wallet,
+            hot_wallet,
+            expires_at,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Close a delegation; either wallet can sign.
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        let delegation = &ctx.accounts.delegation;
+        let signer = ctx.accounts.signer.key();
+        require!(
+            signer == delegation.cold_wallet || signer == delegation.hot_wallet,
+            ErrorCode::Unauthorized
+        );
+        
+        emit!(DelegationRevoked {
+            cold_wallet: delegation.cold_wallet,
+            hot_wallet: delegation.hot_wallet,
+            revoked_by: signer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Verify a hot wallet against the holdings of the cold wallet that
+    /// delegated to it, recording the outcome in the hot wallet's receipt.
+    /// The receipt never outlives the delegation.
+    pub fn verify_delegated_balance(ctx: Context<VerifyDelegatedBalance>) -> Result<bool> {
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &mut *ctx.accounts;
+        require!(accounts.delegation.is_active(now), ErrorCode::DelegationExpired);
+        let wallet = accounts.delegation.hot_wallet;
+        // A denied holder cannot lend access out through a hot wallet
+        let access_override = match read_access_override(&accounts.holder_override)? {
+            Some(AccessOverrideKind::Deny) => Some(AccessOverrideKind::Deny),
+            _ => read_access_override(&accounts.access_override)?,
+        };
+        let extra_balance = extra_token_balance(
+            &accounts.token_account,
+            ctx.remaining_accounts,
+            accounts.gating_config.as_deref(),
+            &accounts.state.migration_mint,
+            Clock::get()?.slot,
+        )?;
+        Ok(verify_holder(
+            &accounts.state,
+            accounts.state.current_requirement,
+            &accounts.token_account,
+            HolderExtras {
+                access_override,
+                extra_balance,
+                stake_account: accounts.stake_account.as_deref(),
+                access_pass: accounts.access_pass.as_deref(),
+                payment_stream: accounts.payment_stream.as_deref(),
+                recurring_subscription: accounts.recurring_subscription.as_deref(),
+                holdings_checkpoint: accounts.holdings_checkpoint.as_deref_mut(),
+            },
+            &mut accounts.receipt,
+            wallet,
+            accounts.delegation.expires_at,
+            now,
+        ))
+    }
+}
+
+/// Optional accounts that count towards a holder's verification next to
+/// their token account
+pub struct HolderExtras<'a> {
+    /// Compliance override for the wallet, applied before anything else
+    pub access_override: Option<AccessOverrideKind>,
+    /// Summed balance of the holder's other token accounts
+    pub extra_balance: u64,
+    pub stake_account: Option<&'a StakeAccount>,
+    pub access_pass: Option<&'a AccessPass>,
+    /// Counts like an access pass while solvent
+    pub payment_stream: Option<&'a PaymentStream>,
+    pub recurring_subscription: Option<&'a RecurringSubscription>,
+    pub holdings_checkpoint: Option<&'a mut HoldingsCheckpoint>,
+}
+
+/// Check the holder of `token_account` against `requirement` and record
+/// the outcome in `receipt` on behalf of `wallet`, never valid past
+/// `valid_until`. Returns whether access was granted.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_holder(
+    state: &TokenState,
+    requirement: u64,
+    token_account: &TokenAccount,
+    extras: HolderExtras,
+    receipt: &mut VerificationReceipt,
+    wallet: Pubkey,
+    valid_until: i64,
+    now: i64,
+) -> bool {
+    let staked = extras
+        .stake_account
+        .map_or(0, |stake| stake.effective_balance(&state.lock_tiers, now));
+    let held = token_account.amount.saturating_add(extras.extra_balance);
+    let mut balance = held.saturating_add(staked);
+    if state.min_holding_secs > 0 {
+        let holdings = held.saturating_add(extras.stake_account.map_or(0, |stake| stake.amount));
+        let immature = match extras.holdings_checkpoint {
+            Some(checkpoint) => {
+                checkpoint.update(holdings, state.min_holding_secs, now);
+                checkpoint.pending_amount
+            }
+            None => holdings,
+        };
+        balance = balance.saturating_sub(immature);
+    }
+    let has_pass = extras
+        .access_pass
+        .map_or(false, |access_pass| access_pass.is_active(now))
+        || extras
+            .payment_stream
+            .map_or(false, |stream| stream.is_solvent(now))
+        || extras
+            .recurring_subscription
+            .map_or(false, |subscription| subscription.is_active(now));
+    let mut tier = access_tier(balance, requirement, &state.access_tiers);
+    if has_pass {
+        tier = std::cmp::max(tier, 1);
+    }
+    let mut grace_period_secs = state.grace_period_secs;
+    match extras.access_override {
+        Some(AccessOverrideKind::Allow) => tier = std::cmp::max(tier, 1),
+        Some(AccessOverrideKind::Deny) => {
+            tier = NO_ACCESS;
+            grace_period_secs = 0;
+        }
+        None => {}
+    }
+    
+    let tier = receipt.record(
+        wallet,
+        tier,
+        requirement,
+        state.receipt_validity_secs,
+        grace_period_secs,
+        now,
+    );
+    receipt.expires_at = std::cmp::min(receipt.expires_at, std::cmp::max(valid_until, now));
+    let verified = tier != NO_ACCESS && receipt.is_valid(now);
+    
+    emit!(BalanceVerified {
+        wallet,
+        holder: token_account.owner,
+        verified,
+        tier,
+        balance,
+        requirement,
+        expires_at: receipt.expires_at,
+        in_grace_period: receipt.below_since != 0,
+        access_override: extras.access_override,
+        timestamp: now,
+    });
+    
+    verified
+}
+
+/// Read the configured oracle (falling back to the secondary feed when the
+/// primary is stale or has no price) and recompute the requirement from it.
+pub fn refresh_requirement(
+    state: &mut TokenState,
+    price_feed: &AccountInfo,
+    fallback_price_feed: Option<&AccountInfo>,
+    recorders: Recorders,
+    now: i64,
+) -> Result<bool> {
+    state.begin_refresh(now)?;
+    let price = read_oracle(state, price_feed, fallback_price_feed, now)?;
+    apply_oracle_price(state, &price, recorders, now)
+}
+
+/// Read the configured oracle, falling back to the secondary feed when the
+/// primary is stale or has no price. A primary price that was read but is
+/// invalid fails the read instead, so a bad primary is never silently
+/// replaced by the secondary.
+pub fn read_oracle(
+    state: &TokenState,
+    price_feed: &AccountInfo,
+    fallback_price_feed: Option<&AccountInfo>,
+    now: i64,
+) -> Result<OraclePrice> {
+    let price = match read_fresh_price(
+        state.oracle_source,
+        price_feed,
+        now,
+        state.max_price_age_secs,
+        state.use_ema_price,
+    ) {
+        Ok(price) => price,
+        Err(primary_err) if is_unavailable(&primary_err) => {
+            let fallback_feed = fallback_price_feed
+                .filter(|_| state.fallback_price_feed != Pubkey::default())
+                .ok_or(primary_err)?;
+            let price = read_fresh_price(
+                state.fallback_oracle_source,
+                fallback_feed,
+                now,
+                state.max_price_age_secs,
+                state.use_ema_price,
+            )?;
+            emit!(OracleFallbackUsed {
+                source: state.fallback_oracle_source,
+                price_feed: fallback_feed.key(),
+                timestamp: now,
+            });
+            price
+        }
+        Err(primary_err) => return Err(primary_err),
+    };
+
+    Ok(price)
+}
+
+fn set_paused(accounts: &mut SetPaused, paused: bool) -> Result<()> {
+    // The operators' call replaces any guardian pause, which lifting it
+    // later must not undo
+    if !accounts.guardian_council.data_is_empty() {
+        let mut council = Account::<GuardianCouncil>::try_from(&accounts.guardian_council)?;
+        council.paused_until = 0;
+        council.holds_pause = false;
+        council.exit(&crate::ID)?;
+    }
+    let state = &mut accounts.state;
+    let was_paused = state.paused;
+    state.paused = paused;
+    
+    emit!(PauseUpdated {
+        authority: accounts.authority.key(),
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    record_admin_action(
+        state,
+        &mut accounts.admin_log,
+        accounts.authority.key(),
+        if paused { AdminAction::Pause } else { AdminAction::Unpause },
+        &was_paused,
+        &paused,
+    )
+}
+
+/// Check an attestor-signed claim about `attestation.wallet` and write it
+/// to the wallet's receipt. `message` is what the attestor must have
+/// signed in the ed25519 instruction just before the current one.
+fn accept_attestation(
+    state: &TokenState,
+    role_registry: Option<&RoleRegistry>,
+    receipt: &mut VerificationReceipt,
+    access_override: &AccountInfo,
+    instructions: &AccountInfo,
+    attestation: &Attestation,
+    message: &[u8],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        attestation.tier != NO_ACCESS
+            && attestation.tier as usize <= ACCESS_TIERS
+            && attestation.attested_at <= now
+            && now.saturating_sub(attestation.attested_at) <= MAX_ATTESTATION_AGE_SECS
+            && attestation.requirement >= state.current_requirement,
+        ErrorCode::InvalidAttestation
+    );
+    require!(
+        read_access_override(access_override)? != Some(AccessOverrideKind::Deny),
+        ErrorCode::WalletDenied
+    );
+    
+    let current = instructions_sysvar::load_current_index_checked(instructions)?;
+    require!(current > 0, ErrorCode::InvalidAttestation);
+    let signature_ix =
+        instructions_sysvar::load_instruction_at_checked(current as usize - 1, instructions)?;
+    let (attestor, signed) = ed25519_signed_message(&signature_ix)?;
+    require!(signed == message, ErrorCode::InvalidAttestation);
+    require!(
+        state.has_role(Role::Attestor, &attestor, role_registry),
+        ErrorCode::Unauthorized
+    );
+    
+    require!(attestation.attested_at > receipt.verified_at, ErrorCode::InvalidAttestation);
+    receipt.wallet = attestation.wallet;
+    receipt.verified_at = attestation.attested_at;
+    receipt.expires_at = attestation
+        .attested_at
+        .saturating_add(state.receipt_validity_secs as i64);
+    receipt.requirement_at_verification = attestation.requirement;
+    receipt.tier = attestation.tier;
+    receipt.below_since = 0;
+    
+    emit!(AttestationAccepted {
+        wallet: attestation.wallet,
+        attestor,
+        tier: attestation.tier,
+        requirement: attestation.requirement,
+        attested_at: attestation.attested_at,
+        expires_at: receipt.expires_at,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+/// Set the token-2022 mint's interest rate, returning the previous one
+fn apply_interest_rate<'info>(
+    mint: &AccountInfo<'info>,
+    mint_authority: &AccountInfo<'info>,
+    mint_authority_bump: u8,
+    rate_bps: i16,
+    token_program: &AccountInfo<'info>,
+) -> Result<i16> {
+    let old_rate_bps = interest_rate(mint)?;
+    update_interest_rate(mint, mint_authority, mint_authority_bump, rate_bps, token_program)?;
+    
+    emit!(InterestRateUpdated {
+        mint: mint.key(),
+        old_rate_bps,
+        new_rate_bps: rate_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(old_rate_bps)
+}
+
+/// Accounts a timelocked change may need besides the state, passed by the
+/// queue and by governance proposals alike
+pub struct ChangeAccounts<'a, 'info> {
+    /// The new feed of an oracle change, for its owner check
+    pub price_feed: Option<&'a AccountInfo<'info>>,
+    pub treasury: Option<&'a mut Treasury>,
+    pub bridge_config: Option<&'a mut BridgeConfig>,
+    pub liquidity_config: Option<&'a mut LiquidityConfig>,
+    pub interest_mint: Option<AccountInfo<'info>>,
+    pub mint_authority: Option<(AccountInfo<'info>, u8)>,
+    pub token_2022_program: Option<AccountInfo<'info>>,
+}
+
+/// Apply a change that has waited out the timelock, checked against the
+/// state as it is now
+fn apply_parameter_change(
+    state: &mut TokenState,
+    change: &ParameterChange,
+    proposer: Pubkey,
+    now: i64,
+    accounts: ChangeAccounts,
+) -> Result<()> {
+    if let Some((source, price_feed)) = change.price_feed() {
+        let feed = accounts
+            .price_feed
+            .filter(|feed| feed.key() == price_feed)
+            .ok_or(ErrorCode::InvalidPriceFeed)?;
+        require!(source.owns(feed), ErrorCode::InvalidOracleOwner);
+    }
+    
+    match change.clone() {
+        ParameterChange::Parameters(params) => state.apply_parameters(&params, proposer, now)?,
+        ParameterChange::PriceFeed { source, price_feed } => {
+            state.set_price_feed(source, price_feed, now)
+        }
+        ParameterChange::FallbackPriceFeed { source, price_feed } => {
+            state.set_fallback_price_feed(source, price_feed, now)
+        }
+        ParameterChange::MedianFeeds { feeds, min_feeds } => {
+            state.set_median_feeds(feeds, min_feeds, now)?
+        }
+        ParameterChange::PullFeedId(feed_id) => state.set_pull_feed_id(feed_id, now),
+        ParameterChange::TimelockDelay(delay_secs) => state.set_timelock_delay(delay_secs, now),
+        ParameterChange::TreasuryLimit {
+            asset,
+            per_epoch_limit,
+        } => accounts
+            .treasury
+            .ok_or(ErrorCode::TreasuryRequired)?
+            .set_limit(asset, per_epoch_limit, now)?,
+        ParameterChange::BridgeLimits {
+            chain_id,
+            outbound_limit,
+            inbound_limit,
+        } => accounts
+            .bridge_config
+            .ok_or(ErrorCode::BridgeConfigRequired)?
+            .set_limits(chain_id, outbound_limit, inbound_limit, now)?,
+        ParameterChange::LiquidityLimits {
+            whirlpool,
+            min_tick_lower,
+            max_tick_upper,
+            max_liquidity,
+        } => accounts
+            .liquidity_config
+            .ok_or(ErrorCode::LiquidityConfigRequired)?
+            .set_limits(whirlpool, min_tick_lower, max_tick_upper, max_liquidity, now)?,
+        ParameterChange::InterestRate(rate_bps) => {
+            let (Some(mint), Some((mint_authority, bump)), Some(token_program)) = (
+                accounts.interest_mint,
+                accounts.mint_authority,
+                accounts.token_2022_program,
+            ) else {
+                return err!(ErrorCode::Token2022MintRequired);
+            };
+            apply_interest_rate(&mint, &mint_authority, bump, rate_bps, &token_program)?;
+        }
+        ParameterChange::TwapWindow(window_secs) => state.set_twap_window(window_secs, now)?,
+        ParameterChange::CircuitBreaker(threshold_bps) => {
+            state.set_circuit_breaker(threshold_bps, now)?
+        }
+        ParameterChange::KeeperConfig {
+            keeper_reward,
+            crank_interval_secs,
+        } => state.set_keeper_config(keeper_reward, crank_interval_secs, now)?,
+        ParameterChange::FeeConfig {
+            fee_bps,
+            fee_destination,
+        } => state.set_fee_config(fee_bps, fee_destination, now)?,
+        ParameterChange::StakingConfig {
+            unstake_cooldown_secs,
+            early_exit_penalty_bps,
+            burn_early_exit_penalty,
+        } => state.set_staking_config(
+            unstake_cooldown_secs,
+            early_exit_penalty_bps,
+            burn_early_exit_penalty,
+            now,
+        )?,
+        ParameterChange::LockTiers(tiers) => state.set_lock_tiers(tiers, now)?,
+        ParameterChange::ReferralBps(referral_bps) => state.set_referral_bps(referral_bps, now)?,
+        ParameterChange::ReceiptValidity(validity_secs) => {
+            state.set_receipt_validity(validity_secs, now)?
+        }
+        ParameterChange::AccessTiers(tiers) => state.set_access_tiers(tiers, now)?,
+        ParameterChange::GracePeriod(grace_period_secs) => {
+            state.set_grace_period(grace_period_secs, now)?
+        }
+        ParameterChange::InsuranceShare(insurance_bps) => {
+            // A queued change can only retune a fund created while no delay was set
+            require!(
+                state.insurance_vault != Pubkey::default(),
+                ErrorCode::InvalidInsuranceVault
+            );
+            state.set_insurance_bps(insurance_bps, now)?
+        }
+    }
+    Ok(())
+}
+
+/// Accounts a treasury swap moves funds between, shared by admin swaps and
+/// the DCA crank
+pub struct SwapAccounts<'a, 'info> {
+    pub state: &'a TokenState,
+    pub swap_config: &'a mut SwapConfig,
+    pub input_mint: &'a Mint,
+    pub mint: &'a Mint,
+    pub treasury: AccountInfo<'info>,
+    pub treasury_bump: u8,
+    pub input_vault: &'a mut Account<'info, TokenAccount>,
+    /// The buyback vault
+    pub output_vault: &'a mut Account<'info, TokenAccount>,
+    pub swap_program: &'a AccountInfo<'info>,
+}
+
+/// Sell up to `amount_in` of the treasury's input mint through the route in
+/// `route_accounts`, within the daily volume cap and for at least
+/// `min_amount_out` and the oracle floor. Returns what was spent and bought.
+fn swap_from_treasury<'info>(
+    accounts: SwapAccounts<'_, 'info>,
+    route_accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<(u64, u64)> {
+    let now = Clock::get()?.unix_timestamp;
+    let swapped_today = accounts.swap_config.record_volume(amount_in, now)?;
+    let min_amount_out = accounts
+        .swap_config
+        .min_output(
+            accounts.state,
+            amount_in,
+            accounts.input_mint.decimals,
+            accounts.mint.decimals,
+            now,
+        )?
+        .max(min_amount_out);
+    
+    let input_before = accounts.input_vault.amount;
+    let output_before = accounts.output_vault.amount;
+    forward_swap(
+        accounts.swap_program,
+        &accounts.treasury,
+        [&accounts.input_vault.key(), &accounts.output_vault.key()],
+        route_accounts,
+        data,
+        &[&[b"treasury", &[accounts.treasury_bump]]],
+    )?;
+    accounts.input_vault.reload()?;
+    accounts.output_vault.reload()?;
+    
+    let amount_spent = input_before.saturating_sub(accounts.input_vault.amount);
+    require!(amount_spent <= amount_in, ErrorCode::SwapInputExceeded);
+    let amount_out = accounts.output_vault.amount.saturating_sub(output_before);
+    require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+    
+    emit!(TreasurySwapped {
+        input_mint: accounts.input_vault.mint,
+        amount_in: amount_spent,
+        amount_out,
+        swapped_today,
+        timestamp: now,
+    });
+    
+    Ok((amount_spent, amount_out))
+}
+
+/// Run `call` on the treasury's Whirlpool position, signed by the
+/// treasury, and return how much of token A and token B it moved into or
+/// out of the treasury's vaults
+fn on_treasury_position(
+    accounts: &mut ManageLiquidityPosition<'_>,
+    treasury_bump: u8,
+    call: impl FnOnce(&PositionAccounts<'_>, &[&[&[u8]]]) -> Result<()>,
+) -> Result<(u64, u64)> {
+    let balance_a = accounts.treasury_vault_a.amount;
+    let balance_b = accounts.treasury_vault_b.amount;
+    let position = PositionAccounts {
+        whirlpool: accounts.whirlpool.to_account_info(),
+        position: accounts.position.to_account_info(),
+        position_token_account: accounts.position_token_account.to_account_info(),
+        position_authority: accounts.treasury.to_account_info(),
+        treasury_vault_a: accounts.treasury_vault_a.to_account_info(),
+        treasury_vault_b: accounts.treasury_vault_b.to_account_info(),
+        whirlpool_vault_a: accounts.whirlpool_vault_a.to_account_info(),
+        whirlpool_vault_b: accounts.whirlpool_vault_b.to_account_info(),
+        tick_array_lower: accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: accounts.tick_array_upper.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+        whirlpool_program: accounts.whirlpool_program.to_account_info(),
+    };
+    call(&position, &[&[b"treasury", &[treasury_bump]]])?;
+    accounts.treasury_vault_a.reload()?;
+    accounts.treasury_vault_b.reload()?;
+    Ok((
+        balance_a.abs_diff(accounts.treasury_vault_a.amount),
+        balance_b.abs_diff(accounts.treasury_vault_b.amount),
+    ))
+}
+
+/// Optional accounts that observe every accepted price on the update paths
+pub struct Recorders<'a> {
+    pub price_accumulator: Option<&'a mut PriceAccumulator>,
+    pub requirement_history: Option<&'a mut RequirementHistory>,
+}
+
+/// Run an oracle reading through the confidence check, then recompute the
+/// requirement from it.
+pub fn apply_oracle_price(
+    state: &mut TokenState,
+    price: &OraclePrice,
+    recorders: Recorders,
+    now: i64,
+) -> Result<bool> {
+    match state.accept_price(price, now)? {
+        Some(current_price) => apply_normalized_price(state, current_price, recorders, now),
+        None => Ok(false),
+    }
+}
+
+/// Feed a price in internal precision through the TWAP accumulator, apply
+/// it to the requirement, and record any resulting change in the history.
+/// Returns whether the requirement changed.
+pub fn apply_normalized_price(
+    state: &mut TokenState,
+    current_price: u64,
+    recorders: Recorders,
+    now: i64,
+) -> Result<bool> {
+    require!(
+        !state.requirement_history_enabled || recorders.requirement_history.is_some(),
+        ErrorCode::RequirementHistoryRequired
+    );
+    
+    let current_price = observe_price(
+        recorders.price_accumulator,
+        state.twap_window_secs,
+        current_price,
+        now,
+    )?;
+    
+    let changed = state.apply_price(current_price, now)?;
+    if changed {
+        if let Some(history) = recorders.requirement_history {
+            history.record(now, current_price, state.current_requirement);
+        }
+    }
+    
+    Ok(changed)
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenState::LEN,
+        seeds = [b"token_state"],
+        bump
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 9,
+        mint::authority = authority,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = authority,
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Owner checked against the Pyth program; contents parsed on update
+    #[account(owner = PYTH_PROGRAM_ID @ ErrorCode::InvalidOracleOwner)]
+    pub price_feed: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBalanceRequirement<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Cranker, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Pinned to the configured feed and owned by its oracle program; parsed in instruction logic
+    #[account(
+        address = state.price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.oracle_source.owns(&price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub price_feed: AccountInfo<'info>,
+    
+    /// CHECK: Pinned to the configured fallback feed and owned by its oracle program
+    #[account(
+        address = state.fallback_price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.fallback_oracle_source.owns(&fallback_price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub fallback_price_feed: Option<AccountInfo<'info>>,
+    
+    #[account(mut, seeds = [b"price_accumulator"], bump)]
+    pub price_accumulator: Option<Account<'info, PriceAccumulator>>,
+    
+    #[account(mut, seeds = [b"requirement_history"], bump)]
+    pub requirement_history: Option<Box<Account<'info, RequirementHistory>>>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBalanceRequirementPull<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Cranker, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Owner, discriminator, verification level, and feed id checked in instruction logic
+    #[account(owner = PYTH_RECEIVER_PROGRAM_ID @ ErrorCode::InvalidOracleOwner)]
+    pub price_update: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"price_accumulator"], bump)]
+    pub price_accumulator: Option<Account<'info, PriceAccumulator>>,
+    
+    #[account(mut, seeds = [b"requirement_history"], bump)]
+    pub requirement_history: Option<Box<Account<'info, RequirementHistory>>>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPullFeedId<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct CrankRequirement<'info> {
+    pub keeper: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Pinned to the configured feed and owned by its oracle program; parsed in instruction logic
+    #[account(
+        address = state.price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.oracle_source.owns(&price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub price_feed: AccountInfo<'info>,
+    
+    /// CHECK: Pinned to the configured fallback feed and owned by its oracle program
+    #[account(
+        address = state.fallback_price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.fallback_oracle_source.owns(&fallback_price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub fallback_price_feed: Option<AccountInfo<'info>>,
+    
+    #[account(mut, seeds = [b"price_accumulator"], bump)]
+    pub price_accumulator: Option<Account<'info, PriceAccumulator>>,
+    
+    #[account(mut, seeds = [b"requirement_history"], bump)]
+    pub requirement_history: Option<Box<Account<'info, RequirementHistory>>>,
+    
+    #[account(
+        mut,
+        seeds = [b"keeper_vault"],
+        bump,
+        token::mint = state.mint,
+        token::authority = state,
+    )]
+    pub keeper_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, constraint = keeper_token_account.mint == state.mint)]
+    pub keeper_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeKeeperVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"keeper_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub keeper_vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetKeeperConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBalanceRequirementMedian<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Cranker, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"price_accumulator"], bump)]
+    pub price_accumulator: Option<Account<'info, PriceAccumulator>>,
+    
+    #[account(mut, seeds = [b"requirement_history"], bump)]
+    pub requirement_history: Option<Box<Account<'info, RequirementHistory>>>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    // Registered median feeds are passed as remaining accounts
+}
+
+#[derive(Accounts)]
+#[instruction(source: OracleSource)]
+pub struct SetPriceFeed<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Owner checked against the selected oracle program; contents parsed on update
+    #[account(constraint = source.owns(&price_feed) @ ErrorCode::InvalidOracleOwner)]
+    pub price_feed: AccountInfo<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(source: OracleSource)]
+pub struct SetFallbackPriceFeed<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Owner checked against the selected oracle program; contents parsed on update
+    #[account(constraint = source.owns(&price_feed) @ ErrorCode::InvalidOracleOwner)]
+    pub price_feed: Option<AccountInfo<'info>>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetMedianFeeds<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCustomPriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CustomPriceFeed::LEN,
+        seeds = [b"custom_price_feed"],
+        bump
+    )]
+    pub custom_price_feed: Account<'info, CustomPriceFeed>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitCustomPrice<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"custom_price_feed"],
+        bump,
+        has_one = authority,
+    )]
+    pub custom_price_feed: Account<'info, CustomPriceFeed>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceAccumulator<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceAccumulator::LEN,
+        seeds = [b"price_accumulator"],
+        bump
+    )]
+    pub price_accumulator: Account<'info, PriceAccumulator>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRequirementHistory<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RequirementHistory::LEN,
+        seeds = [b"requirement_history"],
+        bump
+    )]
+    pub requirement_history: Box<Account<'info, RequirementHistory>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTwapWindow<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitBreaker<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct OverridePrice<'info> {
+    /// The authority, or any payer when guardians approve
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    /// Required when the caller is not the authority
+    #[account(seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Option<Account<'info, GuardianCouncil>>,
+    
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + PriceOverride::LEN,
+        seeds = [b"price_override"],
+        bump
+    )]
+    pub price_override: Account<'info, PriceOverride>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeOraclePricing<'info> {
+    pub payer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"price_override"], bump)]
+    pub price_override: Account<'info, PriceOverride>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateParameters<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct UpdateParametersArgs {
+    /// USD target in units of 10^-price_decimals
+    pub target_usd_value: Option<u64>,
+    pub min_tokens: Option<u64>,
+    pub max_tokens: Option<u64>,
+    /// Internal USD precision; changing it requires a new `target_usd_value`
+    pub price_decimals: Option<u8>,
+    pub max_price_age_secs: Option<u64>,
+    pub max_confidence_bps: Option<u64>,
+    pub update_threshold_bps: Option<u64>,
+    /// Largest requirement move per update in bps; zero moves straight to the target
+    pub max_step_bps: Option<u64>,
+    /// Minimum seconds between requirement refreshes on any update path
+    pub min_update_interval_secs: Option<u64>,
+    /// Price from the oracle's EMA rather than its spot price, where available
+    pub use_ema_price: Option<bool>,
+}
+
+impl UpdateParametersArgs {
+    pub const LEN: usize = 9 * 3 // target_usd_value, min_tokens, max_tokens
+        + 2 // price_decimals
+        + 9 * 5 // max_price_age_secs .. min_update_interval_secs
+        + 2; // use_ema_price
+
+    /// Whether the update touches the requirement formula, and so must be
+    /// queued while a timelock is configured
+    pub fn is_timelocked(&self) -> bool {
+        self.target_usd_value.is_some()
+            || self.min_tokens.is_some()
+            || self.max_tokens.is_some()
+            || self.price_decimals.is_some()
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetTimelockDelay<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct QueuePendingChange<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingChange::LEN,
+        seeds = [b"pending_change", state.next_change_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingChange>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecutePendingChange<'info> {
+    pub executor: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        mut,
+        seeds = [b"pending_change", pending_change.id.to_le_bytes().as_ref()],
+        bump,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub pending_change: Account<'info, PendingChange>,
+    
+    /// CHECK: Rent refund destination, matched against the pending change
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+    
+    /// CHECK: Required for oracle changes; key and owner checked against the change
+    pub price_feed: Option<AccountInfo<'info>>,
+    
+    /// Required for treasury limit changes
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+    
+    /// Required for bridge limit changes
+    #[account(mut, seeds = [b"bridge_config"], bump)]
+    pub bridge_config: Option<Account<'info, BridgeConfig>>,
+    
+    /// Required for liquidity limit changes
+    #[account(mut, seeds = [b"liquidity_config"], bump)]
+    pub liquidity_config: Option<Account<'info, LiquidityConfig>>,
+    
+    /// Required, with the two accounts below, for interest rate changes
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub interest_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: Option<UncheckedAccount<'info>>,
+    
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Operator, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    /// CHECK: The guardian council's address; updated only if one was
+    /// appointed
+    #[account(mut, seeds = [b"guardian_council"], bump)]
+    pub guardian_council: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPendingAuthority<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = authority,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.pending_authority == new_authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Discriminator and version checked in instruction logic; older
+    /// layouts do not deserialize as the current `TokenState`
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: AccountInfo<'info>,
+    
+    /// CHECK: Owner checked against the Pyth program; required for v0 states, which stored no feed
+    #[account(owner = PYTH_PROGRAM_ID @ ErrorCode::InvalidOracleOwner)]
+    pub price_feed: Option<AccountInfo<'info>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseState<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = authority,
+        has_one = mint,
+        close = recipient,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Only receives the reclaimed rent
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"custom_price_feed"], bump, close = recipient)]
+    pub custom_price_feed: Option<Account<'info, CustomPriceFeed>>,
+    
+    #[account(mut, seeds = [b"price_accumulator"], bump, close = recipient)]
+    pub price_accumulator: Option<Box<Account<'info, PriceAccumulator>>>,
+    
+    #[account(mut, seeds = [b"requirement_history"], bump, close = recipient)]
+    pub requirement_history: Option<Box<Account<'info, RequirementHistory>>>,
+    
+    #[account(mut, seeds = [b"role_registry"], bump, close = recipient)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    /// CHECK: The stake pool's address; read only if staking was set up
+    #[account(seeds = [b"stake_pool"], bump)]
+    pub stake_pool: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"keeper_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub keeper_vault: Option<Account<'info, TokenAccount>>,
+    
+    /// Receives any tokens left in the keeper vault
+    #[account(mut, token::mint = mint)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TransferMintAuthority<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Created by the metadata program at its PDA for the mint
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct MintCompressed<'info> {
+    /// Pays for the new Merkle tree leaves
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Minter, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: Checked by the compressed-token program
+    #[account(seeds = [b"cpi_authority"], bump, seeds::program = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub cpi_authority_pda: AccountInfo<'info>,
+    
+    /// CHECK: The mint's compressed-token pool, which holds the minted tokens
+    #[account(
+        mut,
+        seeds = [b"pool", mint.key().as_ref()],
+        bump,
+        seeds::program = COMPRESSED_TOKEN_PROGRAM_ID
+    )]
+    pub token_pool_pda: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the light system program
+    pub registered_program_pda: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the light system program
+    pub account_compression_authority: AccountInfo<'info>,
+    
+    /// CHECK: State Merkle tree the compressed balances are appended to;
+    /// checked by the account compression program
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    /// CHECK: Address checked
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = LIGHT_SYSTEM_PROGRAM_ID)]
+    pub light_system_program: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub account_compression_program: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Decompress<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, associated_token::mint = mint, associated_token::authority = owner)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    /// CHECK: Address checked
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostAccessAttestation<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub wallet: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: The wallet's receipt address; empty if it was never verified
+    #[account(seeds = [b"receipt", wallet.key().as_ref()], bump)]
+    pub receipt: AccountInfo<'info>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", wallet.key().as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: The core bridge's config, read for the message fee
+    #[account(mut, seeds = [b"Bridge"], bump, seeds::program = WORMHOLE_PROGRAM_ID)]
+    pub wormhole_bridge: AccountInfo<'info>,
+    
+    /// Fresh account the core bridge writes the message into
+    #[account(mut)]
+    pub wormhole_message: Signer<'info>,
+    
+    /// CHECK: Signing PDA only; the emitter EVM verifiers trust
+    #[account(seeds = [b"emitter"], bump)]
+    pub emitter: AccountInfo<'info>,
+    
+    /// CHECK: The emitter's sequence, kept by the core bridge
+    #[account(
+        mut,
+        seeds = [b"Sequence", emitter.key().as_ref()],
+        bump,
+        seeds::program = WORMHOLE_PROGRAM_ID
+    )]
+    pub wormhole_sequence: AccountInfo<'info>,
+    
+    /// CHECK: Receives the message fee
+    #[account(mut, seeds = [b"fee_collector"], bump, seeds::program = WORMHOLE_PROGRAM_ID)]
+    pub wormhole_fee_collector: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = WORMHOLE_PROGRAM_ID)]
+    pub wormhole_program: AccountInfo<'info>,
+    
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGovernance<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GovernanceConfig::LEN,
+        seeds = [b"governance_config"],
+        bump
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+    
+    #[account(
+        constraint = proposer_token_account.owner == proposer.key()
+            && proposer_token_account.mint == state.mint
+            @ ErrorCode::InvalidTokenAccount,
+    )]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"stake", proposer.key().as_ref()],
+        bump,
+        constraint = stake_account.owner == proposer.key(),
+    )]
+    pub stake_account: Option<Account<'info, StakeAccount>>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::LEN,
+        seeds = [b"proposal", governance_config.next_proposal_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordSnapshot<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Attestor, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+    
+    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    #[account(seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    
+    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    pub executor: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"governance_config"], bump)]
+    pub governance_config: Account<'info, GovernanceConfig>,
+    
+    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    /// CHECK: Required for oracle changes; key and owner checked against the change
+    pub price_feed: Option<AccountInfo<'info>>,
+    
+    /// Required for treasury limit changes
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+    
+    /// Required for bridge limit changes
+    #[account(mut, seeds = [b"bridge_config"], bump)]
+    pub bridge_config: Option<Account<'info, BridgeConfig>>,
+    
+    /// Required for liquidity limit changes
+    #[account(mut, seeds = [b"liquidity_config"], bump)]
+    pub liquidity_config: Option<Account<'info, LiquidityConfig>>,
+    
+    /// Required, with the two accounts below, for interest rate changes
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub interest_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: Option<UncheckedAccount<'info>>,
+    
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct AppointGuardianCouncil<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + GuardianCouncil::LEN,
+        seeds = [b"guardian_council"],
+        bump
+    )]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianVetoChange<'info> {
+    #[account(seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    #[account(
+        mut,
+        seeds = [b"pending_change", pending_change.id.to_le_bytes().as_ref()],
+        bump,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub pending_change: Account<'info, PendingChange>,
+    
+    /// CHECK: Rent refund destination, matched against the pending change
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianVetoProposal<'info> {
+    #[account(seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Box<Account<'info, Proposal>>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianPause<'info> {
+    #[account(mut, seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+}
+
+#[derive(Accounts)]
+pub struct LiftGuardianPause<'info> {
+    pub payer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"guardian_council"], bump)]
+    pub guardian_council: Account<'info, GuardianCouncil>,
+    
+    #[account(mut, seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeBridgeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BridgeConfig::LEN,
+        seeds = [b"bridge_config"],
+        bump
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBridgeLimits<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"bridge_config"], bump)]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct BridgeBurn<'info> {
+    pub bridge_authority: Signer<'info>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"bridge_config"], bump, has_one = bridge_authority)]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub source: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BridgeMint<'info> {
+    pub bridge_authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"bridge_config"], bump, has_one = bridge_authority)]
+    pub bridge_config: Account<'info, BridgeConfig>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MintTokens<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Minter, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSupply<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = authority,
+        has_one = mint,
+        constraint = !state.supply_finalized @ ErrorCode::SupplyFinalized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BeginMintMigration<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+        constraint = state.migration_mint == Pubkey::default()
+            @ ErrorCode::MigrationAlreadyStarted,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Created by the instruction with its transfer fee extension
+    #[account(mut, seeds = [b"token_2022_mint"], bump)]
+    pub new_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Created by the instruction, sized for the fee extension
+    #[account(mut, seeds = [b"treasury_token_vault", new_mint.key().as_ref()], bump)]
+    pub treasury_vault: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTokens<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.migration_mint != Pubkey::default()
+            @ ErrorCode::MigrationNotStarted,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = new_mint, token::token_program = token_2022_program)]
+    pub destination: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestFees<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithheld<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", new_mint.key().as_ref()], bump)]
+    pub treasury_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct SetInterestRate<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ThawOnVerification<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(mut, token::mint = new_mint)]
+    pub token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    /// CHECK: The owner's receipt address; empty if it was never verified
+    #[account(seeds = [b"receipt", token_account.owner.as_ref()], bump)]
+    pub receipt: AccountInfo<'info>,
+    
+    /// CHECK: The owner's override address; read only if an override is set
+    #[account(seeds = [b"access_override", token_account.owner.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: Signing PDA only; the new mint's freeze authority in
+    /// compliance mode
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveConfidentialAccount<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(mut, token::mint = new_mint)]
+    pub token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    /// CHECK: The owner's receipt address; empty if it was never verified
+    #[account(seeds = [b"receipt", token_account.owner.as_ref()], bump)]
+    pub receipt: AccountInfo<'info>,
+    
+    /// CHECK: The owner's override address; read only if an override is set
+    #[account(seeds = [b"access_override", token_account.owner.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: Signing PDA only; the confidential transfer authority
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeClawback<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(token::mint = new_mint)]
+    pub source: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Clawback::LEN,
+        seeds = [b"clawback", source.key().as_ref()],
+        bump
+    )]
+    pub clawback: Account<'info, Clawback>,
+    
+    /// CHECK: Signing PDA only; matched against the mint's permanent delegate
+    #[account(seeds = [b"clawback_authority"], bump)]
+    pub clawback_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeClawback<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(mut, seeds = [b"clawback", clawback.source.as_ref()], bump, has_one = owner)]
+    pub clawback: Account<'info, Clawback>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteClawback<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        mut,
+        seeds = [b"clawback", source.key().as_ref()],
+        bump,
+        has_one = source,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub clawback: Account<'info, Clawback>,
+    
+    /// CHECK: Rent refund destination, matched against the clawback
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+    
+    #[account(address = state.migration_mint @ ErrorCode::MigrationNotStarted)]
+    pub new_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(mut, token::mint = new_mint)]
+    pub source: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", new_mint.key().as_ref()], bump)]
+    pub treasury_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    /// CHECK: Signing PDA only; the mint's permanent delegate
+    #[account(seeds = [b"clawback_authority"], bump)]
+    pub clawback_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: Signing PDA only; thaws and refreezes a frozen source
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CancelClawback<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        mut,
+        seeds = [b"clawback", clawback.source.as_ref()],
+        bump,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub clawback: Account<'info, Clawback>,
+    
+    /// CHECK: Rent refund destination, matched against the clawback
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTransferHookConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TransferHookConfig::LEN,
+        seeds = [b"transfer_hook_config"],
+        bump
+    )]
+    pub transfer_hook_config: Account<'info, TransferHookConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTransferHookConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"transfer_hook_config"], bump)]
+    pub transfer_hook_config: Account<'info, TransferHookConfig>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EmissionSchedule::LEN,
+        seeds = [b"emission_schedule"],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintEpochEmission<'info> {
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"emission_schedule"], bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    // Emission vaults are passed as remaining accounts
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdminLog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AdminLog::LEN,
+        seeds = [b"admin_log"],
+        bump
+    )]
+    pub admin_log: Box<Account<'info, AdminLog>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRoleRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RoleRegistry::LEN,
+        seeds = [b"role_registry"],
+        bump
+    )]
+    pub role_registry: Account<'info, RoleRegistry>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRole<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, Some(&*role_registry))
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"role_registry"], bump)]
+    pub role_registry: Account<'info, RoleRegistry>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPendingChange<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        mut,
+        seeds = [b"pending_change", pending_change.id.to_le_bytes().as_ref()],
+        bump,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub pending_change: Account<'info, PendingChange>,
+    
+    /// CHECK: Rent refund destination, matched against the pending change
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct Burn<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"treasury_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, seeds = [b"treasury_vault"], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint, token::authority = authority)]
+    pub source: Option<Account<'info, TokenAccount>>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureTreasurySwap<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SwapConfig::LEN,
+        seeds = [b"swap_config"],
+        bump
+    )]
+    pub swap_config: Account<'info, SwapConfig>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TreasurySwap<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, seeds = [b"swap_config"], bump)]
+    pub swap_config: Account<'info, SwapConfig>,
+    
+    #[account(address = swap_config.input_mint)]
+    pub input_mint: Account<'info, Mint>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", input_mint.key().as_ref()], bump)]
+    pub input_vault: Account<'info, TokenAccount>,
+    
+    /// The buyback vault `buyback_and_burn` burns from
+    #[account(mut, seeds = [b"treasury_vault"], bump)]
+    pub output_vault: Account<'info, TokenAccount>,
+    
+    /// CHECK: Only the configured route program can be called
+    #[account(executable, address = swap_config.swap_program)]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetDcaSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + DcaSchedule::LEN,
+        seeds = [b"dca_schedule"],
+        bump
+    )]
+    pub dca_schedule: Account<'info, DcaSchedule>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDcaBuyback<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Cranker, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, seeds = [b"swap_config"], bump)]
+    pub swap_config: Account<'info, SwapConfig>,
+    
+    #[account(mut, seeds = [b"dca_schedule"], bump)]
+    pub dca_schedule: Account<'info, DcaSchedule>,
+    
+    #[account(address = swap_config.input_mint)]
+    pub input_mint: Account<'info, Mint>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", input_mint.key().as_ref()], bump)]
+    pub input_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_vault"], bump)]
+    pub output_vault: Account<'info, TokenAccount>,
+    
+    /// CHECK: Only the configured route program can be called
+    #[account(executable, address = swap_config.swap_program)]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+}
+
+#[derive(Accounts)]
+pub struct SetLiquidityLimits<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + LiquidityConfig::LEN,
+        seeds = [b"liquidity_config"],
+        bump
+    )]
+    pub liquidity_config: Account<'info, LiquidityConfig>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLiquidityPosition<'info> {
+    /// Pays for the position and its NFT
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"liquidity_config"], bump)]
+    pub liquidity_config: Account<'info, LiquidityConfig>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(address = liquidity_config.whirlpool)]
+    pub whirlpool: AccountInfo<'info>,
+    
+    /// CHECK: Created by the Whirlpool program
+    #[account(
+        mut,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump,
+        seeds::program = WHIRLPOOL_PROGRAM_ID
+    )]
+    pub position: AccountInfo<'info>,
+    
+    /// A fresh keypair the Whirlpool program makes the position NFT
+    #[account(mut)]
+    pub position_mint: Signer<'info>,
+    
+    /// CHECK: Created by the Whirlpool program as the treasury's associated
+    /// token account for `position_mint`
+    #[account(mut)]
+    pub position_token_account: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = WHIRLPOOL_PROGRAM_ID)]
+    pub whirlpool_program: AccountInfo<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ManageLiquidityPosition<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"liquidity_config"], bump)]
+    pub liquidity_config: Account<'info, LiquidityConfig>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Box<Account<'info, Treasury>>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut, address = liquidity_config.whirlpool)]
+    pub whirlpool: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut, address = liquidity_config.position)]
+    pub position: AccountInfo<'info>,
+    
+    #[account(token::authority = treasury)]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", treasury_vault_a.mint.as_ref()], bump)]
+    pub treasury_vault_a: Box<Account<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", treasury_vault_b.mint.as_ref()], bump)]
+    pub treasury_vault_b: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub whirlpool_vault_a: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub whirlpool_vault_b: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub tick_array_lower: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub tick_array_upper: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = WHIRLPOOL_PROGRAM_ID)]
+    pub whirlpool_program: AccountInfo<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLiquidityPosition<'info> {
+    /// Receives the position's rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"liquidity_config"], bump)]
+    pub liquidity_config: Account<'info, LiquidityConfig>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut, address = liquidity_config.position)]
+    pub position: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>,
+    
+    /// CHECK: Checked by the Whirlpool program
+    #[account(mut)]
+    pub position_token_account: AccountInfo<'info>,
+    
+    /// CHECK: Address checked
+    #[account(address = WHIRLPOOL_PROGRAM_ID)]
+    pub whirlpool_program: AccountInfo<'info>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Treasury::LEN,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryTokenVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"treasury_token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasuryLimit<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTreasurySol<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTreasuryTokens<'info> {
+    pub depositor: Signer<'info>,
+    
+    #[account(mut, token::authority = depositor)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", vault.mint.as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasurySol<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Any account can receive SOL
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryTokens<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", vault.mint.as_ref()], bump)]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(mut, token::mint = vault.mint)]
+    pub recipient: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Interface<'info, token_interface::TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(bounty_id: u64)]
+pub struct CreateBounty<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", mint.key().as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Bounty::LEN,
+        seeds = [b"bounty", bounty_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"bounty_vault", bounty.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = bounty,
+    )]
+    pub bounty_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitClaim<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    
+    #[account(mut, seeds = [b"bounty", bounty.bounty_id.to_le_bytes().as_ref()], bump)]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + BountyClaim::LEN,
+        seeds = [b"bounty_claim", bounty.key().as_ref(), claimant.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, BountyClaim>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApprovePayout<'info> {
+    pub arbiter: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.bounty_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = arbiter @ ErrorCode::Unauthorized,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    #[account(
+        mut,
+        seeds = [b"bounty_claim", bounty.key().as_ref(), claim.claimant.as_ref()],
+        bump,
+        has_one = bounty,
+    )]
+    pub claim: Account<'info, BountyClaim>,
+    
+    #[account(mut, seeds = [b"bounty_vault", bounty.key().as_ref()], bump)]
+    pub bounty_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = bounty.mint, token::authority = claim.claimant)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpired<'info> {
+    #[account(
+        mut,
+        seeds = [b"bounty", bounty.bounty_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = creator,
+    )]
+    pub bounty: Account<'info, Bounty>,
+    
+    /// Paid for the escrow and gets its rent back
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"bounty_vault", bounty.key().as_ref()], bump)]
+    pub bounty_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", bounty.mint.as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSale<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Sale::LEN,
+        seeds = [b"sale"],
+        bump
+    )]
+    pub sale: Account<'info, Sale>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSale<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"sale"], bump)]
+    pub sale: Account<'info, Sale>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct BuyFromSale<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"sale"], bump)]
+    pub sale: Account<'info, Sale>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + SalePurchase::LEN,
+        seeds = [b"sale_purchase", buyer.key().as_ref()],
+        bump
+    )]
+    pub purchase: Account<'info, SalePurchase>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// The buyer's account of the payment mint; omitted for SOL sales
+    #[account(mut, token::mint = sale.payment_mint, token::authority = buyer)]
+    pub payment_source: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", sale.payment_mint.as_ref()], bump)]
+    pub treasury_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSale<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"sale"], bump)]
+    pub sale: Account<'info, Sale>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(tranche: u64)]
+pub struct StartAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Auction::LEN,
+        seeds = [b"auction", &tranche.to_le_bytes()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"auction", &auction.tranche.to_le_bytes()], bump)]
+    pub auction: Account<'info, Auction>,
+    
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + AuctionBid::LEN,
+        seeds = [b"auction_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, AuctionBid>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBid<'info> {
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"auction", &auction.tranche.to_le_bytes()], bump)]
+    pub auction: Account<'info, Auction>,
+    
+    #[account(
+        mut,
+        close = bidder,
+        has_one = auction,
+        has_one = bidder,
+        seeds = [b"auction_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, AuctionBid>,
+    
+    /// CHECK: Receives the refund and the bid's rent; checked against the bid
+    #[account(mut)]
+    pub bidder: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint, token::authority = bidder)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePresale<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Presale::LEN,
+        seeds = [b"presale"],
+        bump
+    )]
+    pub presale: Account<'info, Presale>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"presale"], bump)]
+    pub presale: Account<'info, Presale>,
+    
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + PresaleContribution::LEN,
+        seeds = [b"presale_contribution", contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, PresaleContribution>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnableRefunds<'info> {
+    pub payer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"presale"], bump)]
+    pub presale: Account<'info, Presale>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    
+    #[account(mut, seeds = [b"presale"], bump)]
+    pub presale: Account<'info, Presale>,
+    
+    #[account(
+        mut,
+        close = contributor,
+        has_one = contributor,
+        seeds = [b"presale_contribution", contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, PresaleContribution>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizePresale<'info> {
+    pub payer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"presale"], bump)]
+    pub presale: Account<'info, Presale>,
+    
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPresaleTokens<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"presale"], bump)]
+    pub presale: Account<'info, Presale>,
+    
+    #[account(
+        mut,
+        close = contributor,
+        has_one = contributor,
+        seeds = [b"presale_contribution", contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, PresaleContribution>,
+    
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(mint_authority.key())
+            @ ErrorCode::MintAuthorityNotTransferred,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct CreateOtcOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    pub payment_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + OtcOffer::LEN,
+        seeds = [b"otc_offer", maker.key().as_ref(), &offer_id.to_le_bytes()],
+        bump
+    )]
+    pub offer: Account<'info, OtcOffer>,
+    
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"otc_vault", offer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = offer,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint, token::authority = maker)]
+    pub source: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    pub taker: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(address = offer.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"otc_offer", maker.key().as_ref(), &offer.offer_id.to_le_bytes()],
+        bump
+    )]
+    pub offer: Account<'info, OtcOffer>,
+    
+    /// CHECK: Receives the rent of the offer and its vault; checked against the offer
+    #[account(mut)]
+    pub maker: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"otc_vault", offer.key().as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = payment_mint, token::authority = taker)]
+    pub payment_source: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = payment_mint, token::authority = maker)]
+    pub maker_payment_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"otc_offer", maker.key().as_ref(), &offer.offer_id.to_le_bytes()],
+        bump
+    )]
+    pub offer: Account<'info, OtcOffer>,
+    
+    #[account(mut, seeds = [b"otc_vault", offer.key().as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(token::mint = state.mint)]
+    pub fee_destination: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeesEnabled<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVestingEscrow<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"vesting_escrow"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub vesting_escrow: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey)]
+pub struct CreateVesting<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VestingSchedule::LEN,
+        seeds = [b"vesting", beneficiary.as_ref()],
+        bump
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut, seeds = [b"vesting_escrow"], bump)]
+    pub vesting_escrow: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::authority = authority)]
+    pub funder: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    pub beneficiary: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", beneficiary.key().as_ref()],
+        bump,
+        has_one = beneficiary,
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut, seeds = [b"vesting_escrow"], bump)]
+    pub vesting_escrow: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = state.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"vesting", vesting_schedule.beneficiary.as_ref()], bump)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut, seeds = [b"vesting_escrow"], bump)]
+    pub vesting_escrow: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = state.mint)]
+    pub refund_destination: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDistribution<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Distribution::LEN,
+        seeds = [b"distribution", state.next_distribution_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub distribution: Account<'info, Distribution>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"distribution_vault", distribution.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = distribution,
+    )]
+    pub distribution_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::authority = authority)]
+    pub funder: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk: u32)]
+pub struct InitializeClaimBitmap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"distribution", distribution.id.to_le_bytes().as_ref()], bump)]
+    pub distribution: Account<'info, Distribution>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClaimBitmap::LEN,
+        seeds = [b"claim_bitmap", distribution.key().as_ref(), chunk.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u32)]
+pub struct Claim<'info> {
+    pub claimant: Signer<'info>,
+    
+    #[account(mut, seeds = [b"distribution", distribution.id.to_le_bytes().as_ref()], bump)]
+    pub distribution: Account<'info, Distribution>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"claim_bitmap",
+            distribution.key().as_ref(),
+            (index / CLAIMS_PER_BITMAP).to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+    
+    #[account(mut, seeds = [b"distribution_vault", distribution.key().as_ref()], bump)]
+    pub distribution_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = distribution_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClawbackUnclaimed<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"distribution", distribution.id.to_le_bytes().as_ref()], bump)]
+    pub distribution: Account<'info, Distribution>,
+    
+    #[account(mut, seeds = [b"distribution_vault", distribution.key().as_ref()], bump)]
+    pub distribution_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = distribution_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStaking<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StakePool::LEN,
+        seeds = [b"stake_pool"],
+        bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"stake_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"reward_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct OpenStakeAccount<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StakeAccount::LEN,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"stake", owner.key().as_ref()], bump, has_one = owner)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, token::mint = state.mint, token::authority = owner)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    /// Required while a protocol fee is charged
+    #[account(mut)]
+    pub fee_destination: Option<Account<'info, TokenAccount>>,
+    
+    /// Required while a share of the fee goes to the insurance fund
+    #[account(mut)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"referral", owner.key().as_ref()], bump)]
+    pub referral: Option<Account<'info, Referral>>,
+    
+    /// Receives the referrer's share of the fee; required with `referral`
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"stake", stake_account.owner.as_ref()], bump)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundRewardsBatch<'info> {
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    // Stake accounts to compound are passed as remaining accounts
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"stake", owner.key().as_ref()], bump, has_one = owner)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnstaked<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake", owner.key().as_ref()], bump, has_one = owner)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = state.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EarlyExit<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"stake", owner.key().as_ref()], bump, has_one = owner)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetStakingConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"stake_pool"], bump)]
+    pub stake_pool: Account<'info, StakePool>,
+    
+    #[account(mut, seeds = [b"stake", owner.key().as_ref()], bump, has_one = owner)]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = state.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetLockTiers<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct PublishRevenueRoot<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub revenue_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RevenueEpoch::LEN,
+        seeds = [b"revenue_epoch", state.next_revenue_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub revenue_epoch: Account<'info, RevenueEpoch>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"revenue_vault", revenue_epoch.key().as_ref()],
+        bump,
+        token::mint = revenue_mint,
+        token::authority = revenue_epoch,
+    )]
+    pub revenue_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::authority = authority)]
+    pub funder: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk: u32)]
+pub struct InitializeRevenueBitmap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"revenue_epoch", revenue_epoch.epoch.to_le_bytes().as_ref()], bump)]
+    pub revenue_epoch: Account<'info, RevenueEpoch>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClaimBitmap::LEN,
+        seeds = [b"claim_bitmap", revenue_epoch.key().as_ref(), chunk.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u32)]
+pub struct ClaimRevenue<'info> {
+    pub holder: Signer<'info>,
+    
+    #[account(mut, seeds = [b"revenue_epoch", revenue_epoch.epoch.to_le_bytes().as_ref()], bump)]
+    pub revenue_epoch: Account<'info, RevenueEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"claim_bitmap",
+            revenue_epoch.key().as_ref(),
+            (index / CLAIMS_PER_BITMAP).to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+    
+    #[account(mut, seeds = [b"revenue_vault", revenue_epoch.key().as_ref()], bump)]
+    pub revenue_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = revenue_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(referrer: Pubkey)]
+pub struct RegisterReferral<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Referral::LEN,
+        seeds = [b"referral", user.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+    
+    /// CHECK: the referrer's own referral PDA, read only if it exists
+    #[account(seeds = [b"referral", referrer.as_ref()], bump)]
+    pub referrer_referral: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferralConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetInsuranceShare<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        has_one = mint,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + InsuranceFund::LEN,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"insurance_vault"],
+        bump,
+        token::mint = mint,
+        token::authority = insurance_fund,
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(incident_id: u64)]
+pub struct DeclareIncident<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Incident::LEN,
+        seeds = [b"insurance_incident", incident_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub incident: Account<'info, Incident>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(incident_id: u64)]
+pub struct DrawInsurance<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    
+    #[account(mut, seeds = [b"insurance_vault"], bump)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"insurance_incident", incident_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub incident: Account<'info, Incident>,
+    
+    #[account(mut, token::mint = insurance_vault.mint)]
+    pub recipient: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AccessPass::LEN,
+        seeds = [b"access_pass", owner.key().as_ref()],
+        bump
+    )]
+    pub access_pass: Account<'info, AccessPass>,
+    
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"subscription_vault", owner.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = access_pass,
+    )]
+    pub subscription_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub source: Account<'info, TokenAccount>,
+    
+    /// Required while a protocol fee is charged
+    #[account(mut)]
+    pub fee_destination: Option<Account<'info, TokenAccount>>,
+    
+    /// Required while a share of the fee goes to the insurance fund
+    #[account(mut)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"referral", owner.key().as_ref()], bump)]
+    pub referral: Option<Account<'info, Referral>>,
+    
+    /// Receives the referrer's share of the fee; required with `referral`
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Unsubscribe<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"access_pass", owner.key().as_ref()],
+        bump,
+        has_one = owner,
+        close = owner,
+    )]
+    pub access_pass: Account<'info, AccessPass>,
+    
+    #[account(mut, seeds = [b"subscription_vault", owner.key().as_ref()], bump)]
+    pub subscription_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = subscription_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetStreamRate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + StreamConfig::LEN,
+        seeds = [b"stream_config"],
+        bump
+    )]
+    pub stream_config: Account<'info, StreamConfig>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenStream<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"stream_config"], bump)]
+    pub stream_config: Account<'info, StreamConfig>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentStream::LEN,
+        seeds = [b"payment_stream", payer.key().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"stream_vault", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = stream,
+    )]
+    pub stream_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint, token::authority = payer)]
+    pub source: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpStream<'info> {
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        has_one = payer,
+        seeds = [b"payment_stream", payer.key().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(mut, seeds = [b"stream_vault", stream.key().as_ref()], bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = stream.mint, token::authority = payer)]
+    pub source: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    #[account(mut, seeds = [b"payment_stream", stream.payer.as_ref()], bump)]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(mut, seeds = [b"stream_vault", stream.key().as_ref()], bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", stream.mint.as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStream<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"payment_stream", payer.key().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(mut, seeds = [b"stream_vault", stream.key().as_ref()], bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", stream.mint.as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = stream.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u32)]
+pub struct SetSubscriptionPlan<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SubscriptionPlan::LEN,
+        seeds = [b"subscription_plan", plan_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u32)]
+pub struct CreateSubscription<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"subscription_plan", plan_id.to_le_bytes().as_ref()], bump)]
+    pub plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + RecurringSubscription::LEN,
+        seeds = [b"recurring_subscription", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, RecurringSubscription>,
+    
+    /// Pays the first period, and later ones once this subscription is
+    /// approved as its delegate
+    #[account(mut, token::mint = plan.mint, token::authority = subscriber)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", plan.mint.as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessRenewal<'info> {
+    #[account(
+        mut,
+        has_one = plan,
+        has_one = source,
+        seeds = [b"recurring_subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, RecurringSubscription>,
+    
+    pub plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"treasury_token_vault", plan.mint.as_ref()], bump)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(feature_id: u32)]
+pub struct OpenUsageCounter<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + UsageCounter::LEN,
+        seeds = [b"usage", owner.key().as_ref(), feature_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub usage_counter: Account<'info, UsageCounter>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(feature_id: u32)]
+pub struct ConsumeAccess<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"usage", owner.key().as_ref(), feature_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner,
+    )]
+    pub usage_counter: Account<'info, UsageCounter>,
+    
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub source: Account<'info, TokenAccount>,
+    
+    /// Required unless payments are burned
+    #[account(mut, seeds = [b"treasury_token_vault", mint.key().as_ref()], bump)]
+    pub treasury_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSessionKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SessionKey::LEN,
+        seeds = [b"session", user.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    /// Approved for the spend limit; required with a nonzero limit
+    #[account(mut, token::mint = state.mint, token::authority = user)]
+    pub token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"session", session.user.as_ref(), session.session_key.as_ref()],
+        bump,
+        close = user,
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    /// CHECK: receives the session's rent
+    #[account(mut, address = session.user)]
+    pub user: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(feature_id: u32)]
+pub struct ConsumeAccessWithSession<'info> {
+    pub session_key: Signer<'info>,
+    
+    #[account(mut, seeds = [b"token_state"], bump, has_one = mint)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"session", session.user.as_ref(), session_key.key().as_ref()],
+        bump,
+        has_one = session_key,
+    )]
+    pub session: Account<'info, SessionKey>,
+    
+    #[account(
+        mut,
+        seeds = [b"usage", session.user.as_ref(), feature_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub usage_counter: Account<'info, UsageCounter>,
+    
+    #[account(mut, token::mint = mint, token::authority = session.user)]
+    pub source: Account<'info, TokenAccount>,
+    
+    /// Required unless payments are burned
+    #[account(mut, seeds = [b"treasury_token_vault", mint.key().as_ref()], bump)]
+    pub treasury_vault: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayPerUseConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetReceiptValidity<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetAccessTiers<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetGracePeriod<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinHoldingPeriod<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointHoldings<'info> {
+    /// Pays for the checkpoint the first time a wallet is checkpointed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(constraint = token_account.mint == state.mint)]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"stake", token_account.owner.as_ref()],
+        bump,
+        constraint = stake_account.owner == token_account.owner,
+    )]
+    pub stake_account: Option<Account<'info, StakeAccount>>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + HoldingsCheckpoint::LEN,
+        seeds = [b"holdings", token_account.owner.as_ref()],
+        bump
+    )]
+    pub holdings_checkpoint: Account<'info, HoldingsCheckpoint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGatingConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GatingConfig::LEN,
+        seeds = [b"gating_config"],
+        bump
+    )]
+    pub gating_config: Account<'info, GatingConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetGatedMint<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"gating_config"], bump)]
+    pub gating_config: Account<'info, GatingConfig>,
+    
+    #[account(address = state.mint)]
+    pub token_mint: Account<'info, Mint>,
+    
+    pub gated_mint: Account<'info, Mint>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct SetLpPool<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(mut, seeds = [b"gating_config"], bump)]
+    pub gating_config: Account<'info, GatingConfig>,
+    
+    pub lp_mint: Account<'info, Mint>,
+    
+    /// The pool's vault of the token
+    pub reserve_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct ObserveLpPool<'info> {
+    #[account(mut, seeds = [b"gating_config"], bump)]
+    pub gating_config: Account<'info, GatingConfig>,
+    
+    pub lp_mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    /// The pool's vault of the token
+    pub reserve_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMemberRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump, has_one = authority)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MemberRegistry::LEN,
+        seeds = [b"member_registry"],
+        bump
+    )]
+    pub member_registry: Account<'info, MemberRegistry>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncMember<'info> {
+    /// Pays for the member record on first sync
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"member_registry"], bump)]
+    pub member_registry: Account<'info, MemberRegistry>,
+    
+    #[account(seeds = [b"receipt", receipt.wallet.as_ref()], bump)]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MemberRecord::LEN,
+        seeds = [b"member", receipt.wallet.as_ref()],
+        bump
+    )]
+    pub member: Account<'info, MemberRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAccess<'info> {
+    /// CHECK: Any wallet; only its key is used
+    pub wallet: AccountInfo<'info>,
+    
+    /// CHECK: The wallet's receipt address; empty if it was never verified
+    #[account(seeds = [b"receipt", wallet.key().as_ref()], bump)]
+    pub receipt: AccountInfo<'info>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", wallet.key().as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(attestation: Attestation)]
+pub struct SubmitAttestation<'info> {
+    /// Pays for the receipt the first time a wallet is verified
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [b"receipt", attestation.wallet.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", attestation.wallet.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: The instructions sysvar, read for the ed25519 instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(attestation: ConfidentialAttestation)]
+pub struct SubmitConfidentialAttestation<'info> {
+    /// Pays for the receipt the first time a wallet is verified
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        address = attestation.token_account @ ErrorCode::InvalidAttestation,
+        constraint = token_account.mint == state.migration_mint @ ErrorCode::InvalidAttestation,
+        constraint = token_account.owner == attestation.wallet @ ErrorCode::InvalidAttestation,
+    )]
+    pub token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [b"receipt", attestation.wallet.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", attestation.wallet.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: The instructions sysvar, read for the ed25519 instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SetAccessOverride<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + AccessOverride::LEN,
+        seeds = [b"access_override", wallet.as_ref()],
+        bump
+    )]
+    pub access_override: Account<'info, AccessOverride>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearAccessOverride<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"token_state"],
+        bump,
+        constraint = state.has_role(Role::Admin, authority.key, role_registry.as_deref())
+            @ ErrorCode::Unauthorized,
+    )]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"role_registry"], bump)]
+    pub role_registry: Option<Account<'info, RoleRegistry>>,
+    
+    #[account(
+        mut,
+        seeds = [b"access_override", access_override.wallet.as_ref()],
+        bump,
+        close = authority,
+    )]
+    pub access_override: Account<'info, AccessOverride>,
+    
+    #[account(mut, seeds = [b"admin_log"], bump)]
+    pub admin_log: Option<Box<Account<'info, AdminLog>>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyBalance<'info> {
+    /// Pays for the receipt the first time a wallet is verified
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(constraint = token_account.mint == state.mint)]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [b"receipt", token_account.owner.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", token_account.owner.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"stake", token_account.owner.as_ref()],
+        bump,
+        constraint = stake_account.owner == token_account.owner,
+    )]
+    pub stake_account: Option<Account<'info, StakeAccount>>,
+    
+    #[account(seeds = [b"access_pass", token_account.owner.as_ref()], bump)]
+    pub access_pass: Option<Account<'info, AccessPass>>,
+    
+    #[account(seeds = [b"payment_stream", token_account.owner.as_ref()], bump)]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(seeds = [b"recurring_subscription", token_account.owner.as_ref()], bump)]
+    pub recurring_subscription: Option<Account<'info, RecurringSubscription>>,
+    
+    /// Required for tokens to count while a minimum holding period is set
+    #[account(
+        mut,
+        seeds = [b"holdings", token_account.owner.as_ref()],
+        bump,
+        constraint = holdings_checkpoint.token_account == token_account.key()
+            @ ErrorCode::InvalidHoldingsCheckpoint,
+    )]
+    pub holdings_checkpoint: Option<Account<'info, HoldingsCheckpoint>>,
+    
+    /// Required for basket mints among the extra token accounts
+    #[account(seeds = [b"gating_config"], bump)]
+    pub gating_config: Option<Account<'info, GatingConfig>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyBalanceUsd<'info> {
+    /// Pays for the receipt the first time a wallet is verified
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    /// CHECK: Pinned to the configured feed and owned by its oracle program; parsed in instruction logic
+    #[account(
+        address = state.price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.oracle_source.owns(&price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub price_feed: AccountInfo<'info>,
+    
+    /// CHECK: Pinned to the configured fallback feed and owned by its oracle program
+    #[account(
+        address = state.fallback_price_feed @ ErrorCode::InvalidPriceFeed,
+        constraint = state.fallback_oracle_source.owns(&fallback_price_feed) @ ErrorCode::InvalidOracleOwner,
+    )]
+    pub fallback_price_feed: Option<AccountInfo<'info>>,
+    
+    #[account(constraint = token_account.mint == state.mint)]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [b"receipt", token_account.owner.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    /// CHECK: The wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", token_account.owner.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"stake", token_account.owner.as_ref()],
+        bump,
+        constraint = stake_account.owner == token_account.owner,
+    )]
+    pub stake_account: Option<Account<'info, StakeAccount>>,
+    
+    #[account(seeds = [b"access_pass", token_account.owner.as_ref()], bump)]
+    pub access_pass: Option<Account<'info, AccessPass>>,
+    
+    #[account(seeds = [b"payment_stream", token_account.owner.as_ref()], bump)]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(seeds = [b"recurring_subscription", token_account.owner.as_ref()], bump)]
+    pub recurring_subscription: Option<Account<'info, RecurringSubscription>>,
+    
+    /// Required for tokens to count while a minimum holding period is set
+    #[account(
+        mut,
+        seeds = [b"holdings", token_account.owner.as_ref()],
+        bump,
+        constraint = holdings_checkpoint.token_account == token_account.key()
+            @ ErrorCode::InvalidHoldingsCheckpoint,
+    )]
+    pub holdings_checkpoint: Option<Account<'info, HoldingsCheckpoint>>,
+    
+    /// Required for basket mints among the extra token accounts
+    #[account(seeds = [b"gating_config"], bump)]
+    pub gating_config: Option<Account<'info, GatingConfig>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshAccessBadge<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    #[account(seeds = [b"receipt", wallet.key().as_ref()], bump)]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = 8 + AccessBadge::LEN,
+        seeds = [b"access_badge", wallet.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, AccessBadge>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        seeds = [b"access_badge_mint", wallet.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = badge_authority,
+        mint::freeze_authority = badge_authority,
+    )]
+    pub badge_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = badge_mint,
+        associated_token::authority = wallet,
+    )]
+    pub badge_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that mints, freezes, and burns badges
+    #[account(seeds = [b"badge_authority"], bump)]
+    pub badge_authority: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccessBadge<'info> {
+    #[account(seeds = [b"receipt", badge.wallet.as_ref()], bump)]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    #[account(
+        mut,
+        seeds = [b"access_badge", badge.wallet.as_ref()],
+        bump,
+        constraint = badge.mint == badge_mint.key(),
+    )]
+    pub badge: Account<'info, AccessBadge>,
+    
+    #[account(mut)]
+    pub badge_mint: Account<'info, Mint>,
+    
+    #[account(mut, token::mint = badge_mint, token::authority = badge.wallet)]
+    pub badge_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: PDA that mints, freezes, and burns badges
+    #[account(seeds = [b"badge_authority"], bump)]
+    pub badge_authority: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(hot_wallet: Pubkey)]
+pub struct DelegateAccess<'info> {
+    #[account(mut)]
+    pub cold_wallet: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = cold_wallet,
+        space = 8 + Delegation::LEN,
+        seeds = [b"delegation", hot_wallet.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegation", delegation.hot_wallet.as_ref()],
+        bump,
+        close = cold_wallet,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    /// CHECK: receives the delegation's rent
+    #[account(mut, address = delegation.cold_wallet)]
+    pub cold_wallet: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyDelegatedBalance<'info> {
+    /// Pays for the hot wallet's receipt the first time it is verified
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"token_state"], bump)]
+    pub state: Account<'info, TokenState>,
+    
+    #[account(seeds = [b"delegation", delegation.hot_wallet.as_ref()], bump)]
+    pub delegation: Account<'info, Delegation>,
+    
+    /// Held by the cold wallet
+    #[account(
+        constraint = token_account.mint == state.mint,
+        constraint = token_account.owner == delegation.cold_wallet @ ErrorCode::InvalidDelegation,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [b"receipt", delegation.hot_wallet.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    
+    /// CHECK: The hot wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", delegation.hot_wallet.as_ref()], bump)]
+    pub access_override: AccountInfo<'info>,
+    
+    /// CHECK: The cold wallet's override address; read only if an override is set
+    #[account(seeds = [b"access_override", token_account.owner.as_ref()], bump)]
+    pub holder_override: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"stake", token_account.owner.as_ref()],
+        bump,
+        constraint = stake_account.owner == token_account.owner,
+    )]
+    pub stake_account: Option<Account<'info, StakeAccount>>,
+    
+    #[account(seeds = [b"access_pass", token_account.owner.as_ref()], bump)]
+    pub access_pass: Option<Account<'info, AccessPass>>,
+    
+    #[account(seeds = [b"payment_stream", token_account.owner.as_ref()], bump)]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(seeds = [b"recurring_subscription", token_account.owner.as_ref()], bump)]
+    pub recurring_subscription: Option<Account<'info, RecurringSubscription>>,
+    
+    #[account(
+        mut,
+        seeds = [b"holdings", token_account.owner.as_ref()],
+        bump,
+        constraint = holdings_checkpoint.token_account == token_account.key()
+            @ ErrorCode::InvalidHoldingsCheckpoint,
+    )]
+    pub holdings_checkpoint: Option<Account<'info, HoldingsCheckpoint>>,
+    
+    /// Required for basket mints among the extra token accounts
+    #[account(seeds = [b"gating_config"], bump)]
+    pub gating_config: Option<Account<'info, GatingConfig>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct TokenState {
+    /// Layout version; kept first so `migrate_state` can read it from any
+    /// later layout
+    pub version: u8,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub oracle_source: OracleSource,
+    pub price_feed: Pubkey,
+    pub fallback_oracle_source: OracleSource,
+    pub fallback_price_feed: Pubkey,
+    pub median_feeds: [OracleFeed; MAX_MEDIAN_FEEDS],
+    pub min_median_feeds: u8,
+    pub twap_window_secs: u64,
+    pub target_usd_value: u64,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub current_requirement: u64,
+    pub last_update: i64,
+    pub price_decimals: u8,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u64,
+    pub keeper_reward: u64,
+    pub crank_interval_secs: u64,
+    pub last_crank: i64,
+    pub last_price: u64,
+    pub circuit_breaker_bps: u64,
+    pub circuit_breaker_tripped: bool,
+    pub update_threshold_bps: u64,
+    pub max_step_bps: u64,
+    pub min_update_interval_secs: u64,
+    pub last_refresh: i64,
+    pub pull_feed_id: [u8; 32],
+    pub use_ema_price: bool,
+    pub requirement_history_enabled: bool,
+    pub timelock_delay_secs: u64,
+    pub next_change_id: u64,
+    pub paused: bool,
+    pub pending_authority: Pubkey,
+    pub admin_log_enabled: bool,
+    pub max_supply: u64,
+    pub supply_finalized: bool,
+    pub total_burned: u64,
+    pub total_bought_back: u64,
+    pub fee_bps: u64,
+    pub fee_destination: Pubkey,
+    pub fees_enabled: bool,
+    pub next_distribution_id: u64,
+    pub lock_tiers: [LockTier; LOCK_TIERS],
+    pub unstake_cooldown_secs: u64,
+    /// Share of an early exit withheld; zero disables early exits
+    pub early_exit_penalty_bps: u64,
+    /// Burn the withheld share rather than paying it to the reward vault
+    pub burn_early_exit_penalty: bool,
+    pub next_revenue_epoch: u64,
+    /// Share of each protocol fee paid to the payer's referrer
+    pub referral_bps: u64,
+    /// Burn pay-per-use payments rather than sending them to the treasury
+    pub burn_consumed_access: bool,
+    pub receipt_validity_secs: u64,
+    /// Tier thresholds in basis points of `current_requirement`
+    pub access_tiers: [u64; ACCESS_TIERS],
+    pub grace_period_secs: u64,
+    /// How long tokens must be held before they count; zero disables the rule
+    pub min_holding_secs: u64,
+    /// Token-2022 mint holders are migrating to; default until a migration
+    /// begins
+    pub migration_mint: Pubkey,
+    pub migration_started_at: i64,
+    /// Legacy tokens swapped for the new mint so far
+    pub migrated_amount: u64,
+    /// Share of each protocol fee set aside in the insurance fund
+    pub insurance_bps: u64,
+    pub insurance_vault: Pubkey,
+}
+
+impl TokenState {
+    pub const LEN: usize = 1 // version
+        + 32 // authority
+        + 32 // mint
+        + 1 + 32 // oracle_source, price_feed
+        + 1 + 32 // fallback_oracle_source, fallback_price_feed
+        + OracleFeed::LEN * MAX_MEDIAN_FEEDS + 1 // median_feeds, min_median_feeds
+        + 8 // twap_window_secs
+        + 8 + 8 + 8 // target_usd_value, min_tokens, max_tokens
+        + 8 + 8 // current_requirement, last_update
+        + 1 + 8 + 8 // price_decimals, max_price_age_secs, max_confidence_bps
+        + 8 + 8 + 8 // keeper_reward, crank_interval_secs, last_crank
+        + 8 + 8 + 1 // last_price, circuit_breaker_bps, circuit_breaker_tripped
+        + 8 + 8 // update_threshold_bps, max_step_bps
+        + 8 + 8 // min_update_interval_secs, last_refresh
+        + 32 // pull_feed_id
+        + 1 // use_ema_price
+        + 1 // requirement_history_enabled
+        + 8 + 8 // timelock_delay_secs, next_change_id
+        + 1 // paused
+        + 32 // pending_authority
+        + 1 // admin_log_enabled
+        + 8 + 1 // max_supply, supply_finalized
+        + 8 // total_burned
+        + 8 // total_bought_back
+        + 8 + 32 + 1 // fee_bps, fee_destination, fees_enabled
+        + 8 // next_distribution_id
+        + LockTier::LEN * LOCK_TIERS // lock_tiers
+        + 8 + 8 + 1 // unstake_cooldown_secs, early_exit_penalty_bps, burn_early_exit_penalty
+        + 8 // next_revenue_epoch
+        + 8 // referral_bps
+        + 1 // burn_consumed_access
+        + 8 // receipt_validity_secs
+        + 8 * ACCESS_TIERS // access_tiers
+        + 8 // grace_period_secs
+        + 8 // min_holding_secs
+        + 32 + 8 + 8 // migration_mint, migration_started_at, migrated_amount
+        + 8 + 32; // insurance_bps, insurance_vault
+
+    /// State for a freshly initialized program, at the current layout version
+    pub fn new(
+        authority: Pubkey,
+        mint: Pubkey,
+        price_feed: Pubkey,
+        max_supply: u64,
+    ) -> Self {
+        TokenState {
+            version: STATE_VERSION,
+            authority,
+            mint,
+            oracle_source: OracleSource::Pyth,
+            price_feed,
+            fallback_oracle_source: OracleSource::Switchboard,
+            fallback_price_feed: Pubkey::default(),
+            median_feeds: [OracleFeed::EMPTY; MAX_MEDIAN_FEEDS],
+            min_median_feeds: 1,
+            twap_window_secs: 0,
+            price_decimals: DEFAULT_PRICE_DECIMALS,
+            max_price_age_secs: DEFAULT_MAX_PRICE_AGE_SECS,
+            max_confidence_bps: DEFAULT_MAX_CONFIDENCE_BPS,
+            target_usd_value: 15_000_000, // $15 USD in millionths
+            min_tokens: 100, // Minimum 100 tokens regardless of price
+            max_tokens: 10_000, // Maximum 10,000 tokens regardless of price
+            current_requirement: 750, // Initial requirement (at $0.02 per token)
+            keeper_reward: 0,
+            crank_interval_secs: DEFAULT_CRANK_INTERVAL_SECS,
+            last_crank: 0,
+            last_price: 0,
+            circuit_breaker_bps: DEFAULT_CIRCUIT_BREAKER_BPS,
+            circuit_breaker_tripped: false,
+            update_threshold_bps: DEFAULT_UPDATE_THRESHOLD_BPS,
+            max_step_bps: 0,
+            min_update_interval_secs: 0,
+            last_refresh: 0,
+            pull_feed_id: [0u8; 32],
+            use_ema_price: false,
+            requirement_history_enabled: false,
+            timelock_delay_secs: 0,
+            next_change_id: 0,
+            paused: false,
+            pending_authority: Pubkey::default(),
+            admin_log_enabled: false,
+            max_supply,
+            supply_finalized: false,
+            total_burned: 0,
+            total_bought_back: 0,
+            fee_bps: 0,
+            fee_destination: Pubkey::default(),
+            fees_enabled: false,
+            next_distribution_id: 0,
+            lock_tiers: DEFAULT_LOCK_TIERS,
+            unstake_cooldown_secs: DEFAULT_UNSTAKE_COOLDOWN_SECS,
+            early_exit_penalty_bps: 0,
+            burn_early_exit_penalty: false,
+            next_revenue_epoch: 0,
+            referral_bps: 0,
+            burn_consumed_access: true,
+            receipt_validity_secs: DEFAULT_RECEIPT_VALIDITY_SECS,
+            access_tiers: DEFAULT_ACCESS_TIERS,
+            grace_period_secs: DEFAULT_GRACE_PERIOD_SECS,
+            min_holding_secs: 0,
+            migration_mint: Pubkey::default(),
+            migration_started_at: 0,
+            migrated_amount: 0,
+            insurance_bps: 0,
+            insurance_vault: Pubkey::default(),
+        }
+    }
+
+    /// Supply after minting `amount` on top of `supply`, if the cap allows it
+    pub fn check_mint(&self, supply: u64, amount: u64) -> Result<u64> {
+        require!(!self.supply_finalized, ErrorCode::SupplyFinalized);
+        let new_supply = supply.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(new_supply <= self.max_supply, ErrorCode::SupplyCapExceeded);
+        Ok(new_supply)
+    }
+
+    /// Protocol fee owed on `amount`, zero while the fee switch is off
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        if !self.fees_enabled {
+            return 0;
+        }
+        (amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// The authority holds every role; other keys need an entry in the registry.
+    pub fn has_role(&self, role: Role, key: &Pubkey, registry: Option<&RoleRegistry>) -> bool {
+        *key == self.authority || registry.map_or(false, |registry| registry.has_role(role, key))
+    }
+
+    /// Normalize an oracle reading to internal precision, or return `None`
+    /// (emitting `PriceRejected`) when its confidence interval is too wide.
+    pub fn accept_price(&self, price: &OraclePrice, now: i64) -> Result<Option<u64>> {
+        if !confidence_within_bounds(price.price, price.conf, self.max_confidence_bps) {
+            emit!(PriceRejected {
+                price: price.price,
+                conf: price.conf,
+                expo: price.expo,
+                max_confidence_bps: self.max_confidence_bps,
+                timestamp: now,
+            });
+            return Ok(None);
+        }
+        
+        normalize_price(price.price, price.expo, self.price_decimals).map(Some)
+    }
+
+    /// Whole tokens `target_usd_value` buys at `current_price`, rounded
+    /// down and held within the token bounds. Every pricing path derives
+    /// the requirement this way.
+    pub fn spot_requirement(&self, current_price: u64) -> Result<u64> {
+        require!(current_price > 0, ErrorCode::InvalidPrice);
+        let requirement = self.target_usd_value / current_price;
+        Ok(requirement.clamp(self.min_tokens, self.max_tokens))
+    }
+
+    /// Recompute `current_requirement` from a price in internal precision,
+    /// returning whether the requirement changed.
+    pub fn apply_price(&mut self, current_price: u64, now: i64) -> Result<bool> {
+        require!(!self.circuit_breaker_tripped, ErrorCode::CircuitBreakerActive);
+        
+        // Freeze updates instead of following an extreme move; the state
+        // change must persist, so this returns Ok rather than an error
+        if let Some(deviation_bps) = self.price_deviation_bps(current_price) {
+            if self.circuit_breaker_bps > 0 && deviation_bps > self.circuit_breaker_bps {
+                self.circuit_breaker_tripped = true;
+                emit!(CircuitBreakerTripped {
+                    last_price: self.last_price,
+                    new_price: current_price,
+                    deviation_bps,
+                    threshold_bps: self.circuit_breaker_bps,
+                    timestamp: now,
+                });
+                return Ok(false);
+            }
+        }
+        self.last_price = current_price;
+        
+        // Calculate new requirement based on the USD target
+        // target_usd_value is in units of 10^-price_decimals USD (e.g., 15_000_000 for $15)
+        // current_price is in the same units per token
+        let target_requirement = self.spot_requirement(current_price)?;
+        
+        // Update only if the change reaches the hysteresis threshold
+        let change_bps = if self.current_requirement > 0 {
+            (target_requirement as u128).abs_diff(self.current_requirement as u128)
+                * BPS_DENOMINATOR as u128
+                / self.current_requirement as u128
+        } else {
+            BPS_DENOMINATOR as u128
+        };
+        
+        if change_bps >= self.update_threshold_bps as u128 {
+            // Move at most max_step_bps toward the target per update
+            let new_requirement = self.ramp_toward(target_requirement);
+            if new_requirement != target_requirement {
+                emit!(RequirementRamping {
+                    previous_requirement: self.current_requirement,
+                    new_requirement,
+                    target_requirement,
+                    max_step_bps: self.max_step_bps,
+                    timestamp: now,
+                });
+            }
+            
+            self.current_requirement = new_requirement;
+            self.last_update = now;
+            emit!(BalanceRequirementUpdated {
+                new_requirement,
+                price: current_price,
+                update_threshold_bps: self.update_threshold_bps,
+                timestamp: self.last_update,
+            });
+            return Ok(true);
+        }
+        
+        Ok(false)
+    }
+
+    /// Validate and apply a parameter update on behalf of `authority`,
+    /// keeping current values for any fields left unset.
+    pub fn apply_parameters(
+        &mut self,
+        params: &UpdateParametersArgs,
+        authority: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        // Validate target and bounds as a combination, against current
+        // values for any fields that are not being changed
+        let target_usd_value = params.target_usd_value.unwrap_or(self.target_usd_value);
+        let min_tokens = params.min_tokens.unwrap_or(self.min_tokens);
+        let max_tokens = params.max_tokens.unwrap_or(self.max_tokens);
+        require!(target_usd_value > 0, ErrorCode::InvalidTargetValue);
+        require!(min_tokens < max_tokens, ErrorCode::InvalidTokenBounds);
+        self.target_usd_value = target_usd_value;
+        self.min_tokens = min_tokens;
+        self.max_tokens = max_tokens;
+        
+        if let Some(price_decimals) = params.price_decimals {
+            require!(price_decimals <= MAX_PRICE_DECIMALS, ErrorCode::InvalidPriceDecimals);
+            // target_usd_value is denominated in price_decimals, so the two
+            // must change together
+            require!(
+                price_decimals == self.price_decimals || params.target_usd_value.is_some(),
+                ErrorCode::InvalidTargetValue
+            );
+            if price_decimals != self.price_decimals {
+                // The circuit breaker reference price is in the old units
+                self.last_price = 0;
+            }
+            self.price_decimals = price_decimals;
+        }
+        
+        if let Some(max_price_age_secs) = params.max_price_age_secs {
+            require!(max_price_age_secs > 0, ErrorCode::InvalidParameter);
+            self.max_price_age_secs = max_price_age_secs;
+        }
+        
+        if let Some(max_confidence_bps) = params.max_confidence_bps {
+            require!(max_confidence_bps <= BPS_DENOMINATOR, ErrorCode::InvalidParameter);
+            self.max_confidence_bps = max_confidence_bps;
+        }
+        
+        if let Some(update_threshold_bps) = params.update_threshold_bps {
+            require!(
+                update_threshold_bps <= BPS_DENOMINATOR,
+                ErrorCode::InvalidParameter
+            );
+            self.update_threshold_bps = update_threshold_bps;
+        }
+        
+        if let Some(max_step_bps) = params.max_step_bps {
+            require!(max_step_bps <= BPS_DENOMINATOR, ErrorCode::InvalidParameter);
+            self.max_step_bps = max_step_bps;
+        }
+        
+        if let Some(min_update_interval_secs) = params.min_update_interval_secs {
+            self.min_update_interval_secs = min_update_interval_secs;
+        }
+        
+        if let Some(use_ema_price) = params.use_ema_price {
+            self.use_ema_price = use_ema_price;
+        }
+        
+        emit!(ParametersUpdated {
+            authority,
+            target_usd_value: self.target_usd_value,
+            min_tokens: self.min_tokens,
+            max_tokens: self.max_tokens,
+            price_decimals: self.price_decimals,
+            max_price_age_secs: self.max_price_age_secs,
+            max_confidence_bps: self.max_confidence_bps,
+            update_threshold_bps: self.update_threshold_bps,
+            max_step_bps: self.max_step_bps,
+            min_update_interval_secs: self.min_update_interval_secs,
+            use_ema_price: self.use_ema_price,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    /// Current values of every field `update_parameters` can change
+    pub fn parameter_values(&self) -> UpdateParametersArgs {
+        UpdateParametersArgs {
+            target_usd_value: Some(self.target_usd_value),
+            min_tokens: Some(self.min_tokens),
+            max_tokens: Some(self.max_tokens),
+            price_decimals: Some(self.price_decimals),
+            max_price_age_secs: Some(self.max_price_age_secs),
+            max_confidence_bps: Some(self.max_confidence_bps),
+            update_threshold_bps: Some(self.update_threshold_bps),
+            max_step_bps: Some(self.max_step_bps),
+            min_update_interval_secs: Some(self.min_update_interval_secs),
+            use_ema_price: Some(self.use_ema_price),
+        }
+    }
+
+    pub fn set_price_feed(&mut self, source: OracleSource, price_feed: Pubkey, now: i64) {
+        let old_price_feed = self.price_feed;
+        self.oracle_source = source;
+        self.price_feed = price_feed;
+
+        emit!(PriceFeedUpdated {
+            source,
+            old_price_feed,
+            new_price_feed: price_feed,
+            fallback: false,
+            timestamp: now,
+        });
+    }
+
+    /// Configure the secondary oracle; `None` disables fallback.
+    pub fn set_fallback_price_feed(
+        &mut self,
+        source: OracleSource,
+        price_feed: Option<Pubkey>,
+        now: i64,
+    ) {
+        let old_price_feed = self.fallback_price_feed;
+        self.fallback_oracle_source = source;
+        self.fallback_price_feed = price_feed.unwrap_or_default();
+
+        emit!(PriceFeedUpdated {
+            source,
+            old_price_feed,
+            new_price_feed: self.fallback_price_feed,
+            fallback: true,
+            timestamp: now,
+        });
+    }
+
+    pub fn set_median_feeds(
+        &mut self,
+        feeds: Vec<OracleFeed>,
+        min_feeds: u8,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            !feeds.is_empty() && feeds.len() <= MAX_MEDIAN_FEEDS,
+            ErrorCode::InvalidOracleCount
+        );
+        require!(
+            min_feeds >= 1 && min_feeds as usize <= feeds.len(),
+            ErrorCode::InvalidOracleCount
+        );
+        for (i, feed) in feeds.iter().enumerate() {
+            require!(!feed.is_empty(), ErrorCode::InvalidPriceFeed);
+            require!(
+                !feeds[..i].iter().any(|other| other.address == feed.address),
+                ErrorCode::DuplicateOracle
+            );
+        }
+        
+        self.median_feeds = [OracleFeed::EMPTY; MAX_MEDIAN_FEEDS];
+        self.median_feeds[..feeds.len()].copy_from_slice(&feeds);
+        self.min_median_feeds = min_feeds;
+        
+        emit!(MedianFeedsUpdated {
+            feeds,
+            min_feeds,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_pull_feed_id(&mut self, feed_id: [u8; 32], now: i64) {
+        let old_feed_id = self.pull_feed_id;
+        self.pull_feed_id = feed_id;
+        
+        emit!(PullFeedIdUpdated {
+            old_feed_id,
+            new_feed_id: feed_id,
+            timestamp: now,
+        });
+    }
+
+    /// Set the TWAP window; zero prices from spot. The window must fit in
+    /// the accumulator at the crank interval.
+    pub fn set_twap_window(&mut self, window_secs: u64, now: i64) -> Result<()> {
+        require!(
+            window_secs <= max_twap_window_secs(self.crank_interval_secs),
+            ErrorCode::InvalidParameter
+        );
+        let old_window_secs = self.twap_window_secs;
+        self.twap_window_secs = window_secs;
+        
+        emit!(TwapWindowUpdated {
+            old_window_secs,
+            new_window_secs: window_secs,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Set the price move that trips the circuit breaker; zero disables it.
+    /// Above a 100% move the breaker could never trip on a fall.
+    pub fn set_circuit_breaker(&mut self, threshold_bps: u64, now: i64) -> Result<()> {
+        require!(threshold_bps <= BPS_DENOMINATOR, ErrorCode::InvalidParameter);
+        self.circuit_breaker_bps = threshold_bps;
+        
+        emit!(CircuitBreakerConfigured {
+            threshold_bps,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Set the crank reward and interval, which must still leave the TWAP
+    /// window covered by the accumulator.
+    pub fn set_keeper_config(
+        &mut self,
+        keeper_reward: u64,
+        crank_interval_secs: u64,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            self.twap_window_secs <= max_twap_window_secs(crank_interval_secs),
+            ErrorCode::InvalidParameter
+        );
+        self.keeper_reward = keeper_reward;
+        self.crank_interval_secs = crank_interval_secs;
+        
+        emit!(KeeperConfigUpdated {
+            keeper_reward,
+            crank_interval_secs,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Set the protocol fee and the token account it is paid to
+    pub fn set_fee_config(
+        &mut self,
+        fee_bps: u64,
+        fee_destination: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidParameter);
+        self.fee_bps = fee_bps;
+        self.fee_destination = fee_destination;
+        
+        emit!(FeeConfigUpdated {
+            fee_bps,
+            fee_destination,
+            fees_enabled: self.fees_enabled,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Set the unstake cooldown and the share of an early exit withheld,
+    /// which is burned rather than paid to the reward vault if
+    /// `burn_early_exit_penalty`
+    pub fn set_staking_config(
+        &mut self,
+        unstake_cooldown_secs: u64,
+        early_exit_penalty_bps: u64,
+        burn_early_exit_penalty: bool,
+        now: i64,
+    ) -> Result<()> {
+        require!(
+            early_exit_penalty_bps <= BPS_DENOMINATOR
+                && i64::try_from(unstake_cooldown_secs).is_ok(),
+            ErrorCode::InvalidParameter
+        );
+        self.unstake_cooldown_secs = unstake_cooldown_secs;
+        self.early_exit_penalty_bps = early_exit_penalty_bps;
+        self.burn_early_exit_penalty = burn_early_exit_penalty;
+        
+        emit!(StakingConfigUpdated {
+            unstake_cooldown_secs,
+            early_exit_penalty_bps,
+            burn_early_exit_penalty,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_lock_tiers(&mut self, tiers: [LockTier; LOCK_TIERS], now: i64) -> Result<()> {
+        validate_lock_tiers(&tiers)?;
+        self.lock_tiers = tiers;
+        
+        emit!(LockTiersUpdated {
+            tiers,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Set the share of protocol fees paid to referrers, which together
+    /// with the insurance share cannot exceed the whole fee
+    pub fn set_referral_bps(&mut self, referral_bps: u64, now: i64) -> Result<()> {
+        require!(
+            referral_bps.saturating_add(self.insurance_bps) <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let old_bps = self.referral_bps;
+        self.referral_bps = referral_bps;
+        
+        emit!(ReferralConfigUpdated {
+            old_bps,
+            new_bps: referral_bps,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_insurance_bps(&mut self, insurance_bps: u64, now: i64) -> Result<()> {
+        require!(
+            insurance_bps.saturating_add(self.referral_bps) <= BPS_DENOMINATOR,
+            ErrorCode::InvalidParameter
+        );
+        let old_bps = self.insurance_bps;
+        self.insurance_bps = insurance_bps;
+        
+        emit!(InsuranceShareUpdated {
+            old_bps,
+            new_bps: insurance_bps,
+            vault: self.insurance_vault,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_receipt_validity(&mut self, receipt_validity_secs: u64, now: i64) -> Result<()> {
+        require!(
+            (1..=MAX_RECEIPT_VALIDITY_SECS).contains(&receipt_validity_secs),
+            ErrorCode::InvalidParameter
+        );
+        let old_validity = self.receipt_validity_secs;
+        self.receipt_validity_secs = receipt_validity_secs;
+        
+        emit!(ReceiptValidityUpdated {
+            old_validity_secs: old_validity,
+            new_validity_secs: receipt_validity_secs,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_access_tiers(&mut self, tiers: [u64; ACCESS_TIERS], now: i64) -> Result<()> {
+        validate_access_tiers(&tiers)?;
+        self.access_tiers = tiers;
+        
+        emit!(AccessTiersUpdated {
+            tiers,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_grace_period(&mut self, grace_period_secs: u64, now: i64) -> Result<()> {
+        require!(grace_period_secs <= MAX_GRACE_PERIOD_SECS, ErrorCode::InvalidParameter);
+        let old_grace_period = self.grace_period_secs;
+        self.grace_period_secs = grace_period_secs;
+        
+        emit!(GracePeriodUpdated {
+            old_grace_period_secs: old_grace_period,
+            new_grace_period_secs: grace_period_secs,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_timelock_delay(&mut self, delay_secs: u64, now: i64) {
+        let old_delay_secs = self.timelock_delay_secs;
+        self.timelock_delay_secs = delay_secs;
+        
+        emit!(TimelockDelayUpdated {
+            old_delay_secs,
+            new_delay_secs: delay_secs,
+            timestamp: now,
+        });
+    }
+
+    /// Enforce the pause switch and `min_update_interval_secs` between
+    /// requirement refreshes on every update path, and record this refresh.
+    pub fn begin_refresh(&mut self, now: i64) -> Result<()> {
+        require!(!self.paused, ErrorCode::ProgramPaused);
+        require!(
+            now.saturating_sub(self.last_refresh) >= self.min_update_interval_secs as i64,
+            ErrorCode::UpdateTooFrequent
+        );
+        self.last_refresh = now;
+        Ok(())
+    }
+
+    /// Requirement after one step toward `target`, limited to `max_step_bps`
+    /// of the current requirement (and at least one token).
+    pub fn ramp_toward(&self, target: u64) -> u64 {
+        if self.max_step_bps == 0 || self.current_requirement == 0 {
+            return target;
+        }
+        
+        let max_step = (self.current_requirement as u128 * self.max_step_bps as u128
+            / BPS_DENOMINATOR as u128)
+            .max(1) as u64;
+        
+        if target > self.current_requirement {
+            std::cmp::min(target, self.current_requirement.saturating_add(max_step))
+        } else {
+            std::cmp::max(target, self.current_requirement.saturating_sub(max_step))
+        }
+    }
+
+    /// Relative move from the last recorded price in basis points, or
+    /// `None` before any price has been recorded.
+    pub fn price_deviation_bps(&self, current_price: u64) -> Option<u64> {
+        if self.last_price == 0 {
+            return None;
+        }
+        let diff = (current_price as u128).abs_diff(self.last_price as u128);
+        let deviation = diff * BPS_DENOMINATOR as u128 / self.last_price as u128;
+        Some(u64::try_from(deviation).unwrap_or(u64::MAX))
+    }
+}
+
+#[event]
+pub struct BalanceRequirementUpdated {
+    pub new_requirement: u64,
+    pub price: u64,
+    pub update_threshold_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RequirementRamping {
+    pub previous_requirement: u64,
+    pub new_requirement: u64,
+    pub target_requirement: u64,
+    pub max_step_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceRejected {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub max_confidence_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub source: OracleSource,
+    pub old_price_feed: Pubkey,
+    pub new_price_feed: Pubkey,
+    pub fallback: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PullFeedIdUpdated {
+    pub old_feed_id: [u8; 32],
+    pub new_feed_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleFallbackUsed {
+    pub source: OracleSource,
+    pub price_feed: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MedianFeedsUpdated {
+    pub feeds: Vec<OracleFeed>,
+    pub min_feeds: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TwapWindowUpdated {
+    pub old_window_secs: u64,
+    pub new_window_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperRewarded {
+    pub keeper: Pubkey,
+    pub reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperConfigUpdated {
+    pub keeper_reward: u64,
+    pub crank_interval_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub last_price: u64,
+    pub new_price: u64,
+    pub deviation_bps: u64,
+    pub threshold_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerConfigured {
+    pub threshold_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerReset {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParametersUpdated {
+    pub authority: Pubkey,
+    pub target_usd_value: u64,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub price_decimals: u8,
+    pub max_price_age_secs: u64,
+    pub max_confidence_bps: u64,
+    pub update_threshold_bps: u64,
+    pub max_step_bps: u64,
+    pub min_update_interval_secs: u64,
+    pub use_ema_price: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TimelockDelayUpdated {
+    pub old_delay_secs: u64,
+    pub new_delay_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PendingChangeQueued {
+    pub id: u64,
+    pub change: ParameterChange,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PendingChangeExecuted {
+    pub id: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PendingChangeCancelled {
+    pub id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockTiersUpdated {
+    pub tiers: [LockTier; LOCK_TIERS],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BalanceVerified {
+    pub wallet: Pubkey,
+    /// Owner of the tokens verified; differs from `wallet` for delegated access
+    pub holder: Pubkey,
+    pub verified: bool,
+    pub tier: u8,
+    /// Wallet balance plus boosted stake
+    pub balance: u64,
+    pub requirement: u64,
+    pub expires_at: i64,
+    /// Verified only because the grace period has not run out
+    pub in_grace_period: bool,
+    pub access_override: Option<AccessOverrideKind>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessDelegated {
+    pub cold_wallet: Pubkey,
+    pub hot_wallet: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationRevoked {
+    pub cold_wallet: Pubkey,
+    pub hot_wallet: Pubkey,
+    pub revoked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HoldingsCheckpointed {
+    pub owner: Pubkey,
+    pub matured_amount: u64,
+    pub pending_amount: u64,
+    pub pending_since: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinHoldingPeriodUpdated {
+    pub old_min_holding_secs: u64,
+    pub new_min_holding_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GracePeriodStarted {
+    pub wallet: Pubkey,
+    pub requirement: u64,
+    pub ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GracePeriodExpired {
+    pub wallet: Pubkey,
+    pub below_since: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GracePeriodUpdated {
+    pub old_grace_period_secs: u64,
+    pub new_grace_period_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessTiersUpdated {
+    pub tiers: [u64; ACCESS_TIERS],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptValidityUpdated {
+    pub old_validity_secs: u64,
+    pub new_validity_secs: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessConsumed {
+    pub owner: Pubkey,
+    pub feature_id: u32,
+    pub amount: u64,
+    /// Burned rather than sent to the treasury
+    pub burned: bool,
+    pub uses: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GatedMintUpdated {
+    pub mint: Pubkey,
+    pub old_weight_bps: u64,
+    pub new_weight_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpPoolUpdated {
+    pub lp_mint: Pubkey,
+    pub reserve_vault: Pubkey,
+    pub listed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpPoolObserved {
+    pub lp_mint: Pubkey,
+    pub reserve: u64,
+    pub lp_supply: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AttestationAccepted {
+    pub wallet: Pubkey,
+    pub attestor: Pubkey,
+    pub tier: u8,
+    pub requirement: u64,
+    pub attested_at: i64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemberSynced {
+    pub wallet: Pubkey,
+    pub index: u64,
+    pub active: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub active_members: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessOverrideSet {
+    pub wallet: Pubkey,
+    pub kind: AccessOverrideKind,
+    pub reason: [u8; 32],
+    pub set_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessOverrideCleared {
+    pub wallet: Pubkey,
+    pub kind: AccessOverrideKind,
+    pub cleared_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessBadgeRefreshed {
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub tier: u8,
+    pub expires_at: i64,
+    /// Whether the wallet holds the badge afterwards
+    pub held: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyCreated {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub permissions: u8,
+    pub expires_at: i64,
+    pub spend_limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyRevoked {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub revoked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayPerUseConfigUpdated {
+    pub burn_consumed_access: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Subscribed {
+    pub owner: Pubkey,
+    /// Escrowed for the term of the subscription
+    pub amount: u64,
+    pub fee: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unsubscribed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPlanSet {
+    pub plan_id: u32,
+    pub args: SubscriptionPlanArgs,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionCreated {
+    pub subscriber: Pubkey,
+    pub plan_id: u32,
+    /// Charged for the first period
+    pub amount: u64,
+    pub next_renewal_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionRenewed {
+    pub subscriber: Pubkey,
+    pub plan_id: u32,
+    pub amount: u64,
+    pub next_renewal_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionExpired {
+    pub subscriber: Pubkey,
+    pub plan_id: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamOpened {
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_sec: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamToppedUp {
+    pub payer: Pubkey,
+    pub amount: u64,
+    /// Total escrowed over the stream's life
+    pub deposited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamWithdrawn {
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub withdrawn: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamClosed {
+    pub payer: Pubkey,
+    /// Paid to the treasury over the stream's life
+    pub paid: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Staked {
+    pub owner: Pubkey,
+    /// Staked after the protocol fee
+    pub amount: u64,
+    pub fee: u64,
+    pub staked: u64,
+    pub lock_tier: u8,
+    pub unlock_at: i64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsCompounded {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeRequested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Total awaiting withdrawal, including earlier requests
+    pub unstaking_amount: u64,
+    pub available_at: i64,
+    pub staked: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyExited {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub penalty_burned: bool,
+    pub staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakingConfigUpdated {
+    pub unstake_cooldown_secs: u64,
+    pub early_exit_penalty_bps: u64,
+    pub burn_early_exit_penalty: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueRootPublished {
+    pub epoch: u64,
+    pub revenue_mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub snapshot_slot: u64,
+    pub total_amount: u64,
+    pub max_claims: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueClaimed {
+    pub epoch: u64,
+    pub index: u32,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionCreated {
+    pub id: u64,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub max_claims: u32,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct AirdropClaimed {
+    pub distribution: u64,
+    pub index: u32,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionClawedBack {
+    pub distribution: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub beneficiary: Pubkey,
+    pub start_time: i64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+    pub total_amount: u64,
+    pub revocable: bool,
+}
+
+#[event]
+pub struct VestingClaimed {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingRevoked {
+    pub beneficiary: Pubkey,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeConfigUpdated {
+    pub fee_bps: u64,
+    pub fee_destination: Pubkey,
+    pub fees_enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRegistered {
+    pub user: Pubkey,
+    pub referrer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralConfigUpdated {
+    pub old_bps: u64,
+    pub new_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRewardPaid {
+    pub operation: FeeOperation,
+    pub user: Pubkey,
+    pub referrer: Pubkey,
+    /// Protocol fee the reward was taken from
+    pub fee: u64,
+    pub reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeCollected {
+    pub operation: FeeOperation,
+    pub payer: Pubkey,
+    /// Gross amount of the operation the fee was taken from
+    pub amount: u64,
+    pub fee: u64,
+    pub destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceShareUpdated {
+    pub old_bps: u64,
+    pub new_bps: u64,
+    pub vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceFunded {
+    pub operation: FeeOperation,
+    pub payer: Pubkey,
+    pub fee: u64,
+    /// Share of `fee` paid into the fund
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct IncidentDeclared {
+    pub incident_id: u64,
+    pub report_hash: [u8; 32],
+    pub max_amount: u64,
+    pub declared_by: Pubkey,
+    pub drawable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceDrawn {
+    pub incident_id: u64,
+    pub report_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    /// Paid out for the incident so far, including this draw
+    pub drawn_for_incident: u64,
+    pub incident_remaining: u64,
+    /// Left in the fund after this draw
+    pub fund_remaining: u64,
+    pub total_drawn: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryLimitUpdated {
+    pub asset: Pubkey,
+    pub old_limit: u64,
+    pub new_limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryDeposited {
+    /// Token mint, or `NATIVE_SOL`
+    pub asset: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    /// Token mint, or `NATIVE_SOL`
+    pub asset: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub withdrawn_this_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuybackBurned {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub total_bought_back: u64,
+    /// Last accepted oracle price, in `price_decimals` precision
+    pub price: u64,
+    pub price_decimals: u8,
+    pub current_requirement: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensBurned {
+    pub owner: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+    pub total_burned: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensMinted {
+    pub minter: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochEmissionMinted {
+    pub epoch: u64,
+    pub amount: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SupplyFinalized {
+    pub authority: Pubkey,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintMigrationStarted {
+    pub authority: Pubkey,
+    pub legacy_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub args: Token2022MintArgs,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensMigrated {
+    pub owner: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub migrated_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesHarvested {
+    pub mint: Pubkey,
+    /// Token accounts swept
+    pub accounts: u16,
+    pub harvested: u64,
+    /// Held in the mint awaiting `withdraw_withheld`
+    pub withheld: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithheldFeesWithdrawn {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferFeeUpdated {
+    pub old_transfer_fee_bps: u16,
+    pub old_max_transfer_fee: u64,
+    pub new_transfer_fee_bps: u16,
+    pub new_max_transfer_fee: u64,
+    pub effective_epoch: u64,
+}
+
+#[event]
+pub struct TokenAccountThawed {
+    pub wallet: Pubkey,
+    pub token_account: Pubkey,
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxWalletUpdated {
+    pub old_max_wallet_bps: u64,
+    pub new_max_wallet_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxWalletExemptionUpdated {
+    pub owner: Pubkey,
+    pub exempt: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InterestRateUpdated {
+    pub mint: Pubkey,
+    pub old_rate_bps: i16,
+    pub new_rate_bps: i16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdated {
+    pub mint: Pubkey,
+    pub metadata: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfidentialAccountApproved {
+    pub wallet: Pubkey,
+    pub token_account: Pubkey,
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackProposed {
+    pub source: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reason: [u8; 32],
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackDisputed {
+    pub source: Pubkey,
+    pub owner: Pubkey,
+    pub evidence: [u8; 32],
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackExecuted {
+    pub source: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Less than `amount` if the account no longer held it all
+    pub recovered: u64,
+    pub destination: Pubkey,
+    pub disputed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackCancelled {
+    pub source: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub disputed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompressedTokensMinted {
+    pub minter: Pubkey,
+    pub recipients: u16,
+    pub amount: u64,
+    pub supply: u64,
+    pub merkle_tree: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensDecompressed {
+    pub owner: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BridgeLimitUpdated {
+    pub chain_id: u16,
+    pub old_outbound_limit: u64,
+    pub new_outbound_limit: u64,
+    pub old_inbound_limit: u64,
+    pub new_inbound_limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BridgeTransferOut {
+    pub sender: Pubkey,
+    pub source: Pubkey,
+    pub recipient_chain: u16,
+    /// Recipient address on that chain, left-padded to 32 bytes
+    pub recipient: [u8; 32],
+    pub amount: u64,
+    pub sequence: u64,
+    pub outstanding: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BridgeTransferIn {
+    pub source_chain: u16,
+    pub sender: [u8; 32],
+    pub recipient: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// The NTT message the manager released this for
+    pub message_id: [u8; 32],
+    pub outstanding: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessAttestationPosted {
+    pub wallet: Pubkey,
+    pub target_chain: u16,
+    pub target_address: [u8; 32],
+    pub tier: u8,
+    pub slot: u64,
+    pub expires_at: i64,
+    /// Wormhole sequence of the message, for fetching its signed VAA
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceInitialized {
+    pub voting_period_secs: u64,
+    pub quorum_bps: u64,
+    pub proposal_threshold: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub change: ParameterChange,
+    pub snapshot_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SnapshotRecorded {
+    pub proposal: u64,
+    pub snapshot_slot: u64,
+    pub vote_root: [u8; 32],
+    pub total_weight: u64,
+    pub voting_ends_at: i64,
+    pub eta: i64,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteCast {
+    pub proposal: u64,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    /// Tallies including this vote
+    pub yes_weight: u64,
+    pub no_weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub id: u64,
+    pub executor: Pubkey,
+    pub yes_weight: u64,
+    pub no_weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianCouncilAppointed {
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PendingChangeVetoed {
+    pub id: u64,
+    pub change: ParameterChange,
+    /// Guardians who signed the veto
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalVetoed {
+    pub id: u64,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianPaused {
+    pub approvals: u8,
+    pub paused_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianPauseLifted {
+    pub lifted_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceOverridden {
+    pub price: u64,
+    pub requirement: u64,
+    pub expires_at: i64,
+    pub set_by: Pubkey,
+    /// Guardians who signed, or zero when the authority overrode
+    pub guardian_approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OraclePricingResumed {
+    /// The manual price that expired
+    pub override_price: u64,
+    pub resumed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SaleConfigured {
+    pub args: SaleArgs,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SalePurchased {
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub cost: u64,
+    /// Curve price of the next whole token
+    pub next_price: u64,
+    pub sold: u64,
+    pub raised: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SaleFinalized {
+    pub sold: u64,
+    pub raised: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionStarted {
+    pub tranche: u64,
+    pub supply: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub end_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionBidPlaced {
+    pub tranche: u64,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    /// Price at the time of the bid
+    pub price: u64,
+    pub committed: u64,
+    /// Whether this bid filled the supply
+    pub cleared: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionBidSettled {
+    pub tranche: u64,
+    pub bidder: Pubkey,
+    pub clearing_price: u64,
+    pub tokens: u64,
+    pub cost: u64,
+    pub refund: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleConfigured {
+    pub args: PresaleArgs,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleContributed {
+    pub contributor: Pubkey,
+    pub amount: u64,
+    /// The contributor's total so far
+    pub contributed: u64,
+    pub raised: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleRefundsEnabled {
+    pub raised: u64,
+    pub soft_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleRefunded {
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleFinalized {
+    pub raised: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleTokensClaimed {
+    pub contributor: Pubkey,
+    pub contributed: u64,
+    pub tokens: u64,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OtcOfferCreated {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub amount: u64,
+    pub payment_mint: Pubkey,
+    pub price: u64,
+    pub total_price: u64,
+    pub counterparty: Option<Pubkey>,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OtcOfferAccepted {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub total_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OtcOfferCancelled {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyCreated {
+    pub bounty_id: u64,
+    pub mint: Pubkey,
+    pub arbiter: Pubkey,
+    pub amount: u64,
+    pub deadline: i64,
+    /// Treasury withdrawals of the mint so far this epoch, this one included
+    pub withdrawn_this_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyClaimSubmitted {
+    pub bounty_id: u64,
+    pub claimant: Pubkey,
+    pub submission_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyPaid {
+    pub bounty_id: u64,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    /// Left in escrow after this payout
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyReclaimed {
+    pub bounty_id: u64,
+    /// Returned to the treasury
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasurySwapped {
+    pub input_mint: Pubkey,
+    /// Spent from the treasury's vault
+    pub amount_in: u64,
+    /// Bought into the buyback vault
+    pub amount_out: u64,
+    /// Input swapped so far today, this swap included
+    pub swapped_today: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaScheduleSet {
+    pub amount_per_interval: u64,
+    pub interval_secs: u64,
+    pub total_budget: u64,
+    pub start_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaBuybackExecuted {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Budget spent so far, this tranche included
+    pub spent: u64,
+    pub next_execution_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityLimitsUpdated {
+    pub old_whirlpool: Pubkey,
+    pub new_whirlpool: Pubkey,
+    pub old_min_tick_lower: i32,
+    pub new_min_tick_lower: i32,
+    pub old_max_tick_upper: i32,
+    pub new_max_tick_upper: i32,
+    pub old_max_liquidity: u128,
+    pub new_max_liquidity: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityPositionOpened {
+    pub whirlpool: Pubkey,
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityPositionChanged {
+    pub position: Pubkey,
+    pub increased: bool,
+    pub liquidity_delta: u128,
+    /// Moved between the treasury's vaults and the pool
+    pub amount_a: u64,
+    pub amount_b: u64,
+    /// Held by the position afterwards
+    pub liquidity: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionFeesCollected {
+    pub position: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityPositionClosed {
+    pub position: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StateClosed {
+    pub authority: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StateMigrated {
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferStarted {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub role: Role,
+    pub member: Pubkey,
+    pub granted_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub role: Role,
+    pub member: Pubkey,
+    pub revoked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub authority: Pubkey,
+    pub paused: bool,
     pub timestamp: i64,
 }
 
@@ -202,4 +13924,338 @@ pub enum ErrorCode {
     NoPriceFound,
     #[msg("Math operation overflow")]
     MathOverflow,
+    #[msg("Price feed account could not be parsed")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is zero, negative, or below internal precision")]
+    InvalidPrice,
+    #[msg("Price decimals exceed the supported precision")]
+    InvalidPriceDecimals,
+    #[msg("Oracle price is older than the maximum allowed age")]
+    StalePrice,
+    #[msg("Price feed account is not owned by the oracle program")]
+    InvalidOracleOwner,
+    #[msg("Invalid number of oracle feeds")]
+    InvalidOracleCount,
+    #[msg("The same oracle feed was supplied more than once")]
+    DuplicateOracle,
+    #[msg("Not enough usable oracle prices to compute a median")]
+    NotEnoughOraclePrices,
+    #[msg("A price accumulator is required while TWAP pricing is enabled")]
+    PriceAccumulatorRequired,
+    #[msg("Crank called before the minimum interval elapsed")]
+    CrankTooSoon,
+    #[msg("Requirement updates are halted by the circuit breaker")]
+    CircuitBreakerActive,
+    #[msg("Circuit breaker is not tripped")]
+    CircuitBreakerNotTripped,
+    #[msg("Parameter value is out of range")]
+    InvalidParameter,
+    #[msg("Requirement update attempted before the minimum interval elapsed")]
+    UpdateTooFrequent,
+    #[msg("Price update is not fully verified by Wormhole guardians")]
+    UnverifiedPriceUpdate,
+    #[msg("Price update feed id does not match the configured feed")]
+    PriceFeedIdMismatch,
+    #[msg("The requirement history account is required once history is enabled")]
+    RequirementHistoryRequired,
+    #[msg("Target USD value must be greater than zero")]
+    InvalidTargetValue,
+    #[msg("Minimum tokens must be less than maximum tokens")]
+    InvalidTokenBounds,
+    #[msg("This change must be queued while a timelock is configured")]
+    TimelockRequired,
+    #[msg("The pending change's timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("Signer does not hold the role required for this instruction")]
+    Unauthorized,
+    #[msg("The key already holds this role")]
+    RoleAlreadyGranted,
+    #[msg("The role registry has no free slots")]
+    RoleRegistryFull,
+    #[msg("The key does not hold this role")]
+    RoleNotGranted,
+    #[msg("The state account is already at the current version")]
+    StateAlreadyCurrent,
+    #[msg("The state account has an unrecognised layout version")]
+    UnknownStateVersion,
+    #[msg("Tokens can still be minted; revoke the mint authority or burn the supply first")]
+    SupplyStillMintable,
+    #[msg("The keeper vault holds tokens and no account was given to receive them")]
+    KeeperVaultNotEmpty,
+    #[msg("The admin log account is required once the log is enabled")]
+    AdminLogRequired,
+    #[msg("The mint authority has not been transferred to the program")]
+    MintAuthorityNotTransferred,
+    #[msg("Minting would exceed the maximum supply")]
+    SupplyCapExceeded,
+    #[msg("The supply has been finalized; no further minting is possible")]
+    SupplyFinalized,
+    #[msg("Emission schedule parameters are invalid")]
+    InvalidEmissionSchedule,
+    #[msg("The next emission epoch has not started yet")]
+    EpochNotReached,
+    #[msg("Emission vaults do not match the schedule")]
+    InvalidEmissionVault,
+    #[msg("The withdrawal would exceed this epoch's treasury limit")]
+    TreasuryLimitExceeded,
+    #[msg("The treasury has no free slot for another asset limit")]
+    TreasuryAssetsFull,
+    #[msg("The treasury does not hold enough of the asset")]
+    InsufficientTreasuryFunds,
+    #[msg("The treasury account is required for this change")]
+    TreasuryRequired,
+    #[msg("The fee destination is missing or does not match the fee configuration")]
+    InvalidFeeDestination,
+    #[msg("Vesting schedule parameters are invalid")]
+    InvalidVestingSchedule,
+    #[msg("No vested tokens are available to claim")]
+    NothingToClaim,
+    #[msg("This vesting schedule cannot be revoked")]
+    VestingNotRevocable,
+    #[msg("This vesting schedule has already been revoked")]
+    VestingAlreadyRevoked,
+    #[msg("Distribution parameters are invalid")]
+    InvalidDistribution,
+    #[msg("The merkle proof does not match the distribution")]
+    InvalidProof,
+    #[msg("This index has already been claimed")]
+    AlreadyClaimed,
+    #[msg("The distribution's claim deadline has passed")]
+    DistributionExpired,
+    #[msg("The distribution can still be claimed from")]
+    DistributionActive,
+    #[msg("Not enough tokens are staked")]
+    InsufficientStake,
+    #[msg("Lock tier is invalid")]
+    InvalidLockTier,
+    #[msg("The stake is still locked")]
+    StakeLocked,
+    #[msg("The unstake cooldown has not finished")]
+    UnstakeCooldown,
+    #[msg("Early exits are disabled")]
+    EarlyExitDisabled,
+    #[msg("A user cannot be referred by themselves or by someone they referred")]
+    SelfReferral,
+    #[msg("Referral or referrer token account does not match the payer")]
+    InvalidReferral,
+    #[msg("The subscription has not expired yet")]
+    SubscriptionActive,
+    #[msg("Access tiers must start at the requirement and rise strictly")]
+    InvalidAccessTier,
+    #[msg("Holdings checkpoint tracks a different token account")]
+    InvalidHoldingsCheckpoint,
+    #[msg("Invalid delegation")]
+    InvalidDelegation,
+    #[msg("The delegation has expired")]
+    DelegationExpired,
+    #[msg("Invalid session key")]
+    InvalidSessionKey,
+    #[msg("The session key has expired or lacks the permission")]
+    SessionKeyUnauthorized,
+    #[msg("The session's spend limit is exhausted")]
+    SessionSpendLimitExceeded,
+    #[msg("The access badge is still backed by a valid receipt")]
+    AccessBadgeActive,
+    #[msg("Extra token accounts must be distinct and share the owner and mint")]
+    InvalidTokenAccount,
+    #[msg("The oracle price's confidence interval is too wide")]
+    PriceUncertain,
+    #[msg("The gating basket is full")]
+    GatingConfigFull,
+    #[msg("LP token accounts must be followed by their listed mint and reserve vault")]
+    InvalidLpPool,
+    #[msg("The wallet is denied access by an override")]
+    WalletDenied,
+    #[msg("Invalid or unsigned attestation")]
+    InvalidAttestation,
+    /// Raised by `aistm7_interface::cpi::require_access` for `check_access`
+    /// callers, and by `thaw_on_verification`
+    #[msg("The wallet does not have the required access")]
+    AccessRequired,
+    #[msg("A mint migration has already started")]
+    MigrationAlreadyStarted,
+    #[msg("No mint migration has started")]
+    MigrationNotStarted,
+    #[msg("No transfer fees are withheld in the mint")]
+    NoWithheldFees,
+    #[msg("The max-wallet exemption list is full")]
+    HookExemptionsFull,
+    #[msg("The token-2022 mint, mint authority, and token-2022 program are required")]
+    Token2022MintRequired,
+    #[msg("The metadata name, symbol, or URI is too long")]
+    InvalidMetadata,
+    #[msg("The token account is not configured for confidential transfers")]
+    NotConfidentialAccount,
+    #[msg("The token-2022 mint was created without the clawback authority")]
+    ClawbackUnavailable,
+    #[msg("The clawback can no longer be disputed")]
+    ClawbackDisputeClosed,
+    #[msg("The clawback's dispute window or review period has not passed")]
+    ClawbackNotReady,
+    #[msg("The compressed-token transfer did not decompress into the account")]
+    NothingDecompressed,
+    #[msg("The bridge can hold limits for no more chains")]
+    BridgeChainsFull,
+    #[msg("The transfer exceeds the chain's bridge limit for this window")]
+    BridgeLimitExceeded,
+    #[msg("More would be bridged in than was bridged out")]
+    BridgeSupplyExceeded,
+    #[msg("The bridge config account is required for this change")]
+    BridgeConfigRequired,
+    #[msg("A proposal's snapshot must carry some voting weight")]
+    InvalidProposal,
+    #[msg("Voting on the proposal has closed")]
+    ProposalVotingClosed,
+    #[msg("Voting on the proposal is still open")]
+    ProposalVotingOpen,
+    #[msg("The proposal did not reach the quorum or a majority in favour")]
+    ProposalNotPassed,
+    #[msg("The proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("The proposal's snapshot has not been recorded")]
+    SnapshotNotRecorded,
+    #[msg("The proposal's snapshot has already been recorded")]
+    SnapshotAlreadyRecorded,
+    #[msg("The proposal was vetoed by the guardian council")]
+    ProposalVetoed,
+    #[msg("Guardians must be distinct, with a threshold and term in range")]
+    InvalidGuardianCouncil,
+    #[msg("The guardian council's term has ended")]
+    GuardianCouncilExpired,
+    #[msg("Approver is not a signing member of the guardian council")]
+    NotAGuardian,
+    #[msg("The same guardian approved more than once")]
+    DuplicateGuardianApproval,
+    #[msg("Not enough guardians approved the action")]
+    GuardianThresholdNotMet,
+    #[msg("There is no guardian pause, or it has not run its course")]
+    GuardianPauseActive,
+    #[msg("There is no manual price, or it has not expired")]
+    PriceOverrideActive,
+    #[msg("The manual price has expired; oracle pricing must resume first")]
+    PriceOverrideExpired,
+    #[msg("Invalid sale configuration")]
+    InvalidSaleConfig,
+    #[msg("The sale has not started")]
+    SaleNotStarted,
+    #[msg("The sale has ended or been finalized")]
+    SaleClosed,
+    #[msg("The sale's terms cannot change once it has started")]
+    SaleAlreadyStarted,
+    #[msg("Purchase would exceed the sale's hard cap")]
+    SaleHardCapExceeded,
+    #[msg("Purchase would exceed the per-wallet cap")]
+    SaleWalletCapExceeded,
+    #[msg("The purchase costs more than the buyer's maximum")]
+    SaleSlippageExceeded,
+    #[msg("Token payments need the payment source and treasury vault")]
+    SalePaymentAccountRequired,
+    #[msg("Invalid auction parameters")]
+    InvalidAuction,
+    #[msg("The auction has cleared or ended")]
+    AuctionClosed,
+    #[msg("The auction has neither cleared nor ended")]
+    AuctionNotConcluded,
+    #[msg("Invalid presale configuration")]
+    InvalidPresaleConfig,
+    #[msg("The presale is not accepting contributions")]
+    PresaleNotOpen,
+    #[msg("Contribution is below the presale minimum")]
+    PresaleContributionTooSmall,
+    #[msg("Contribution exceeds the wallet's allocation")]
+    PresaleContributionTooLarge,
+    #[msg("Contribution would exceed the presale's hard cap")]
+    PresaleHardCapExceeded,
+    #[msg("The presale has not closed")]
+    PresaleStillOpen,
+    #[msg("The presale met its soft cap")]
+    PresaleSoftCapMet,
+    #[msg("The presale fell short of its soft cap")]
+    PresaleSoftCapNotMet,
+    #[msg("Refunds have not been enabled")]
+    RefundsNotEnabled,
+    #[msg("The presale has not been finalized")]
+    PresaleNotFinalized,
+    #[msg("Invalid OTC offer")]
+    InvalidOtcOffer,
+    #[msg("The OTC offer has expired")]
+    OtcOfferExpired,
+    #[msg("The OTC offer is reserved for another counterparty")]
+    OtcNotCounterparty,
+    #[msg("No fresh oracle price to bound the OTC price against")]
+    OtcOraclePriceUnavailable,
+    #[msg("The OTC price is too far from the oracle price")]
+    OtcPriceOutOfBounds,
+    #[msg("Too many mints listed for streams")]
+    StreamMintsFull,
+    #[msg("The mint is not listed for streams")]
+    StreamMintNotListed,
+    #[msg("The stream rate is below the mint's minimum")]
+    StreamRateTooLow,
+    #[msg("Invalid subscription plan")]
+    InvalidSubscriptionPlan,
+    #[msg("The subscription plan is not active")]
+    SubscriptionPlanInactive,
+    #[msg("The subscription's renewal window has not opened")]
+    RenewalNotDue,
+    #[msg("The subscription's source does not allow the renewal charge")]
+    RenewalNotAuthorized,
+    #[msg("The subscription has expired")]
+    SubscriptionAlreadyExpired,
+    #[msg("Invalid bounty")]
+    InvalidBounty,
+    #[msg("The bounty no longer accepts claims or payouts")]
+    BountyClosed,
+    #[msg("The bounty's review period has not passed")]
+    BountyNotExpired,
+    #[msg("The payout exceeds what is left in the bounty's escrow")]
+    InsufficientBountyEscrow,
+    #[msg("The swap exceeds today's volume cap")]
+    SwapVolumeExceeded,
+    #[msg("No fresh oracle price to bound the swap")]
+    SwapOraclePriceUnavailable,
+    #[msg("The swap spent more than its input amount")]
+    SwapInputExceeded,
+    #[msg("The swap filled below its minimum output")]
+    SlippageExceeded,
+    #[msg("Liquidity limit changes require the liquidity config account")]
+    LiquidityConfigRequired,
+    #[msg("The treasury already has a liquidity position open")]
+    LiquidityPositionOpen,
+    #[msg("The position's range is outside the liquidity limits")]
+    LiquidityRangeOutOfBounds,
+    #[msg("The position would exceed the liquidity cap")]
+    LiquidityCapExceeded,
+    #[msg("The position still holds liquidity")]
+    LiquidityPositionNotEmpty,
+    #[msg("Invalid DCA schedule")]
+    InvalidDcaSchedule,
+    #[msg("No DCA tranche is due")]
+    DcaNotDue,
+    #[msg("DCA buybacks need an oracle-bounded swap config")]
+    DcaRequiresOracleBound,
+    #[msg("Insurance vault missing or not the configured one")]
+    InvalidInsuranceVault,
+    #[msg("Incident is not drawable yet")]
+    IncidentNotDrawable,
+    #[msg("Draw exceeds what was declared for the incident")]
+    InsuranceDrawExceeded,
+    #[msg("Insufficient funds in the insurance fund")]
+    InsufficientInsuranceFunds,
+    #[msg("The state can only be closed on devnet and test deployments")]
+    CloseStateDisabled,
+    #[msg("Tokens are still staked; every stake must be withdrawn first")]
+    StakeStillOpen,
+    #[msg("The LP pool has not been observed in an earlier slot")]
+    LpPoolUnobserved,
+    #[msg("The proposer holds less than the proposal threshold")]
+    ProposalThresholdNotMet,
+    #[msg("Treasury swaps can only route through Jupiter")]
+    UnsupportedSwapProgram,
+    #[msg("Treasury swaps need an oracle-bounded swap config")]
+    SwapRequiresOracleBound,
+    #[msg("The swap moved treasury funds other than its two vaults")]
+    SwapTouchedTreasury,
 }
\ No newline at end of file
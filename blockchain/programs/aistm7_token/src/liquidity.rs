@@ -0,0 +1,291 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::compression::instruction_discriminator;
+use crate::{ErrorCode, LiquidityLimitsUpdated};
+
+/// Orca Whirlpools concentrated-liquidity program
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// Protocol-owned liquidity, kept at `[b"liquidity_config"]`. The treasury
+/// holds at most one position in `whirlpool`, owning its NFT and funding it
+/// from its token vaults, and its range and size stay within the limits
+/// here. Widening the limits waits out the timelock.
+#[account]
+pub struct LiquidityConfig {
+    pub whirlpool: Pubkey,
+    /// Bounds the position's ticks must fall within
+    pub min_tick_lower: i32,
+    pub max_tick_upper: i32,
+    /// Most liquidity the position may hold
+    pub max_liquidity: u128,
+    /// The open position, or the default key when there is none
+    pub position: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    /// Liquidity the position holds
+    pub liquidity: u128,
+}
+
+impl LiquidityConfig {
+    pub const LEN: usize = 32 + 4 + 4 + 16 + 32 + 4 + 4 + 16;
+
+    pub fn has_position(&self) -> bool {
+        self.position != Pubkey::default()
+    }
+
+    /// Whether the new limits keep the pool and only narrow the range and
+    /// lower the cap
+    pub fn tightens(
+        &self,
+        whirlpool: Pubkey,
+        min_tick_lower: i32,
+        max_tick_upper: i32,
+        max_liquidity: u128,
+    ) -> bool {
+        whirlpool == self.whirlpool
+            && min_tick_lower >= self.min_tick_lower
+            && max_tick_upper <= self.max_tick_upper
+            && max_liquidity <= self.max_liquidity
+    }
+
+    /// The pool can only change while no position is open. An open
+    /// position keeps its range, but can no longer grow past a lowered cap.
+    pub fn set_limits(
+        &mut self,
+        whirlpool: Pubkey,
+        min_tick_lower: i32,
+        max_tick_upper: i32,
+        max_liquidity: u128,
+        now: i64,
+    ) -> Result<()> {
+        require!(min_tick_lower < max_tick_upper, ErrorCode::InvalidParameter);
+        require!(
+            whirlpool == self.whirlpool || !self.has_position(),
+            ErrorCode::LiquidityPositionOpen
+        );
+        emit!(LiquidityLimitsUpdated {
+            old_whirlpool: self.whirlpool,
+            new_whirlpool: whirlpool,
+            old_min_tick_lower: self.min_tick_lower,
+            new_min_tick_lower: min_tick_lower,
+            old_max_tick_upper: self.max_tick_upper,
+            new_max_tick_upper: max_tick_upper,
+            old_max_liquidity: self.max_liquidity,
+            new_max_liquidity: max_liquidity,
+            timestamp: now,
+        });
+        self.whirlpool = whirlpool;
+        self.min_tick_lower = min_tick_lower;
+        self.max_tick_upper = max_tick_upper;
+        self.max_liquidity = max_liquidity;
+        Ok(())
+    }
+
+    pub fn check_range(&self, tick_lower_index: i32, tick_upper_index: i32) -> Result<()> {
+        require!(
+            self.min_tick_lower <= tick_lower_index
+                && tick_lower_index < tick_upper_index
+                && tick_upper_index <= self.max_tick_upper,
+            ErrorCode::LiquidityRangeOutOfBounds
+        );
+        Ok(())
+    }
+
+    pub fn add_liquidity(&mut self, amount: u128) -> Result<()> {
+        let liquidity = self
+            .liquidity
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(liquidity <= self.max_liquidity, ErrorCode::LiquidityCapExceeded);
+        self.liquidity = liquidity;
+        Ok(())
+    }
+}
+
+/// Accounts of a Whirlpool position the treasury modifies or collects
+/// from. Token A and B follow the pool's mint order.
+pub struct PositionAccounts<'info> {
+    pub whirlpool: AccountInfo<'info>,
+    pub position: AccountInfo<'info>,
+    pub position_token_account: AccountInfo<'info>,
+    /// The treasury, which owns the position NFT and the vaults
+    pub position_authority: AccountInfo<'info>,
+    pub treasury_vault_a: AccountInfo<'info>,
+    pub treasury_vault_b: AccountInfo<'info>,
+    pub whirlpool_vault_a: AccountInfo<'info>,
+    pub whirlpool_vault_b: AccountInfo<'info>,
+    pub tick_array_lower: AccountInfo<'info>,
+    pub tick_array_upper: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub whirlpool_program: AccountInfo<'info>,
+}
+
+/// Whirlpool's `open_position`, minting the position NFT to
+/// `position_token_account`, the treasury's associated token account for
+/// `position_mint`
+#[allow(clippy::too_many_arguments)]
+pub fn open_position<'info>(
+    whirlpool_program: &AccountInfo<'info>,
+    funder: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    position: &AccountInfo<'info>,
+    position_mint: &AccountInfo<'info>,
+    position_token_account: &AccountInfo<'info>,
+    whirlpool: &AccountInfo<'info>,
+    programs: [&AccountInfo<'info>; 4],
+    position_bump: u8,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<()> {
+    let mut data = instruction_discriminator("open_position").to_vec();
+    (position_bump, tick_lower_index, tick_upper_index).serialize(&mut data)?;
+    let [token_program, system_program, rent, associated_token_program] = programs;
+    invoke_whirlpool(
+        whirlpool_program,
+        &[
+            (funder, true, true),
+            (owner, false, false),
+            (position, false, true),
+            (position_mint, true, true),
+            (position_token_account, false, true),
+            (whirlpool, false, false),
+            (token_program, false, false),
+            (system_program, false, false),
+            (rent, false, false),
+            (associated_token_program, false, false),
+        ],
+        data,
+        &[],
+    )
+}
+
+/// Whirlpool's `increase_liquidity`, or `decrease_liquidity` when
+/// `increase` is false, with the token bounds as its slippage limits
+pub fn modify_liquidity(
+    accounts: &PositionAccounts<'_>,
+    increase: bool,
+    liquidity: u128,
+    token_bound_a: u64,
+    token_bound_b: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let name = if increase {
+        "increase_liquidity"
+    } else {
+        "decrease_liquidity"
+    };
+    let mut data = instruction_discriminator(name).to_vec();
+    (liquidity, token_bound_a, token_bound_b).serialize(&mut data)?;
+    invoke_whirlpool(
+        &accounts.whirlpool_program,
+        &[
+            (&accounts.whirlpool, false, true),
+            (&accounts.token_program, false, false),
+            (&accounts.position_authority, true, false),
+            (&accounts.position, false, true),
+            (&accounts.position_token_account, false, false),
+            (&accounts.treasury_vault_a, false, true),
+            (&accounts.treasury_vault_b, false, true),
+            (&accounts.whirlpool_vault_a, false, true),
+            (&accounts.whirlpool_vault_b, false, true),
+            (&accounts.tick_array_lower, false, true),
+            (&accounts.tick_array_upper, false, true),
+        ],
+        data,
+        signer_seeds,
+    )
+}
+
+/// Bring the position's fees up to date, then collect them into the
+/// treasury's vaults
+pub fn collect_fees(accounts: &PositionAccounts<'_>, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    invoke_whirlpool(
+        &accounts.whirlpool_program,
+        &[
+            (&accounts.whirlpool, false, true),
+            (&accounts.position, false, true),
+            (&accounts.tick_array_lower, false, false),
+            (&accounts.tick_array_upper, false, false),
+        ],
+        instruction_discriminator("update_fees_and_rewards").to_vec(),
+        &[],
+    )?;
+    invoke_whirlpool(
+        &accounts.whirlpool_program,
+        &[
+            (&accounts.whirlpool, false, false),
+            (&accounts.position_authority, true, false),
+            (&accounts.position, false, true),
+            (&accounts.position_token_account, false, false),
+            (&accounts.treasury_vault_a, false, true),
+            (&accounts.whirlpool_vault_a, false, true),
+            (&accounts.treasury_vault_b, false, true),
+            (&accounts.whirlpool_vault_b, false, true),
+            (&accounts.token_program, false, false),
+        ],
+        instruction_discriminator("collect_fees").to_vec(),
+        signer_seeds,
+    )
+}
+
+/// Whirlpool's `close_position`, burning the NFT of an empty position
+#[allow(clippy::too_many_arguments)]
+pub fn close_position<'info>(
+    whirlpool_program: &AccountInfo<'info>,
+    position_authority: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+    position: &AccountInfo<'info>,
+    position_mint: &AccountInfo<'info>,
+    position_token_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_whirlpool(
+        whirlpool_program,
+        &[
+            (position_authority, true, false),
+            (receiver, false, true),
+            (position, false, true),
+            (position_mint, false, true),
+            (position_token_account, false, true),
+            (token_program, false, false),
+        ],
+        instruction_discriminator("close_position").to_vec(),
+        signer_seeds,
+    )
+}
+
+/// Call the Whirlpool program with `accounts` as `(info, signer, writable)`
+fn invoke_whirlpool<'info>(
+    whirlpool_program: &AccountInfo<'info>,
+    accounts: &[(&AccountInfo<'info>, bool, bool)],
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let metas = accounts
+        .iter()
+        .map(|(info, signer, writable)| {
+            if *writable {
+                AccountMeta::new(*info.key, *signer)
+            } else {
+                AccountMeta::new_readonly(*info.key, *signer)
+            }
+        })
+        .collect();
+    let mut infos: Vec<AccountInfo<'info>> =
+        accounts.iter().map(|(info, _, _)| (*info).clone()).collect();
+    infos.push(whirlpool_program.clone());
+    invoke_signed(
+        &Instruction {
+            program_id: WHIRLPOOL_PROGRAM_ID,
+            accounts: metas,
+            data,
+        },
+        &infos,
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+/// Byte offset of `MemberRecord::index`, after the account discriminator
+pub const MEMBER_INDEX_OFFSET: usize = 8;
+
+/// Byte offset of `MemberRecord::wallet`
+pub const MEMBER_WALLET_OFFSET: usize = MEMBER_INDEX_OFFSET + 8;
+
+/// Byte offset of `MemberRecord::active`, for `getProgramAccounts` memcmp
+/// filters listing current members
+pub const MEMBER_ACTIVE_OFFSET: usize = MEMBER_WALLET_OFFSET + 32;
+
+/// Counters behind the member records, kept at `[b"member_registry"]`
+#[account]
+pub struct MemberRegistry {
+    /// Records ever created; the next record takes this index
+    pub total_members: u64,
+    /// Records currently marked active
+    pub active_members: u64,
+}
+
+impl MemberRegistry {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// A wallet's entry in the member registry, kept at `[b"member", wallet]`
+/// and brought up to date from its receipt by `sync_member`. Field order is
+/// part of the interface: clients filter on the offsets above.
+#[account]
+pub struct MemberRecord {
+    /// Assigned once, in creation order
+    pub index: u64,
+    pub wallet: Pubkey,
+    /// Whether the receipt granted access when last synced
+    pub active: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub joined_at: i64,
+    pub synced_at: i64,
+}
+
+impl MemberRecord {
+    pub const LEN: usize = 8 + 32 + 1 + 1 + 8 + 8 + 8;
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::mpl_token_metadata::state::{
+    DataV2, Metadata, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
+};
+
+use crate::ErrorCode;
+
+/// What wallets and explorers show for the mint, held in its Metaplex
+/// metadata account with the `mint_authority` PDA as update authority
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TokenMetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    /// Off-chain JSON with the logo and description
+    pub uri: String,
+}
+
+impl TokenMetadataArgs {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.name.len() <= MAX_NAME_LENGTH
+                && self.symbol.len() <= MAX_SYMBOL_LENGTH
+                && self.uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::InvalidMetadata
+        );
+        Ok(())
+    }
+
+    /// The fungible token's data: no royalties, creators, or collection
+    pub fn to_data(&self) -> DataV2 {
+        DataV2 {
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            uri: self.uri.clone(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        }
+    }
+
+    /// Read back from a metadata account, which pads each field with NULs
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let trim = |field: &str| field.trim_end_matches('\0').to_string();
+        Self {
+            name: trim(&metadata.data.name),
+            symbol: trim(&metadata.data.symbol),
+            uri: trim(&metadata.data.uri),
+        }
+    }
+}
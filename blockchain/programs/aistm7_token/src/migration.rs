@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+use crate::TokenState;
+
+/// Layout version written by this build of the program
+pub const STATE_VERSION: u8 = 1;
+
+/// `TokenState` as first deployed, before the layout carried a version
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct TokenStateV0 {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub target_usd_value: u64,
+    pub min_tokens: u64,
+    pub max_tokens: u64,
+    pub current_requirement: u64,
+    pub last_update: i64,
+}
+
+impl TokenStateV0 {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8;
+
+    /// Carry the v0 fields over and take defaults for everything added
+    /// since. v0 took its Pyth feed as an instruction argument, so the feed
+    /// to pin has to be supplied; it had no supply cap, so none is imposed.
+    pub fn upgrade(self, price_feed: Pubkey) -> TokenState {
+        TokenState {
+            target_usd_value: self.target_usd_value,
+            min_tokens: self.min_tokens,
+            max_tokens: self.max_tokens,
+            current_requirement: self.current_requirement,
+            last_update: self.last_update,
+            ..TokenState::new(self.authority, self.mint, price_feed, u64::MAX)
+        }
+    }
+}
+
+/// Layout version of a raw state account. v0 predates the version field
+/// and is recognised by its length; later layouts store it first.
+pub fn state_version(data: &[u8]) -> Result<u8> {
+    require!(
+        data.len() > 8 && data[..8] == TokenState::DISCRIMINATOR,
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    if data.len() == 8 + TokenStateV0::LEN {
+        return Ok(0);
+    }
+    Ok(data[8])
+}
+
+/// Grow or shrink `account` to `new_len`, topping up rent from `payer`.
+pub fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(new_len);
+    let shortfall = required.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(new_len, false)?;
+    Ok(())
+}
@@ -0,0 +1,382 @@
+use anchor_lang::prelude::*;
+use chainlink_solana::v2::read_feed_v2;
+use pyth_sdk_solana::load_price_feed_from_account_info;
+use switchboard_v2::{AggregatorAccountData, SwitchboardDecimal, SWITCHBOARD_PROGRAM_ID};
+
+use crate::{ErrorCode, BPS_DENOMINATOR, MAX_PRICE_DECIMALS};
+
+/// Owner of Pyth price accounts (oracle program) on the target cluster
+#[cfg(feature = "devnet")]
+pub const PYTH_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+#[cfg(not(feature = "devnet"))]
+pub const PYTH_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+/// Chainlink OCR2 store program, owner of Chainlink feed accounts
+pub const CHAINLINK_STORE_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny");
+
+/// Pyth Solana receiver program, owner of posted `PriceUpdateV2` accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Anchor discriminator of `PriceUpdateV2`, i.e. `sha256("account:PriceUpdateV2")[..8]`
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Maximum number of feeds that can take part in median pricing
+pub const MAX_MEDIAN_FEEDS: usize = 3;
+
+/// Oracle programs the requirement can be priced from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleSource {
+    Pyth,
+    Switchboard,
+    /// Authority-published `CustomPriceFeed` owned by this program
+    Custom,
+    Chainlink,
+}
+
+impl OracleSource {
+    /// Program expected to own price accounts for this source
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            OracleSource::Pyth => PYTH_PROGRAM_ID,
+            OracleSource::Switchboard => SWITCHBOARD_PROGRAM_ID,
+            OracleSource::Custom => crate::ID,
+            OracleSource::Chainlink => CHAINLINK_STORE_PROGRAM_ID,
+        }
+    }
+
+    pub fn owns(&self, account: &AccountInfo) -> bool {
+        *account.owner == self.program_id()
+    }
+}
+
+/// A registered oracle feed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleFeed {
+    pub source: OracleSource,
+    pub address: Pubkey,
+}
+
+impl OracleFeed {
+    pub const LEN: usize = 1 + 32;
+
+    pub const EMPTY: OracleFeed = OracleFeed {
+        source: OracleSource::Pyth,
+        address: Pubkey::new_from_array([0; 32]),
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.address == Pubkey::default()
+    }
+}
+
+/// Price published directly by the program authority, for tokens or
+/// clusters without a third-party feed
+#[account]
+pub struct CustomPriceFeed {
+    pub authority: Pubkey,
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl CustomPriceFeed {
+    pub const LEN: usize = 32 + 8 + 8 + 4 + 8;
+}
+
+/// Longest a manual price may stand in for the oracles
+pub const MAX_PRICE_OVERRIDE_SECS: i64 = 86_400;
+
+/// Manual price set by `override_price` while the circuit breaker is
+/// tripped, kept at `[b"price_override"]`. Once it expires nothing more
+/// can be overridden until `resume_oracle_pricing` resets the breaker, so
+/// the requirement goes back to following the oracles.
+#[account]
+pub struct PriceOverride {
+    /// USD per token in units of 10^-price_decimals, like `last_price`
+    pub price: u64,
+    pub set_by: Pubkey,
+    pub set_at: i64,
+    /// Zero when no override is in effect
+    pub expires_at: i64,
+}
+
+impl PriceOverride {
+    pub const LEN: usize = 8 + 32 + 8 + 8;
+}
+
+/// Oracle reading expressed Pyth-style as `price * 10^expo`, with the
+/// confidence interval in the same exponent.
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Reject the price if it is older than `max_age_secs` at `now`.
+    pub fn check_fresh(self, now: i64, max_age_secs: u64) -> Result<Self> {
+        let price_age = now.saturating_sub(self.publish_time);
+        require!(price_age <= max_age_secs as i64, ErrorCode::StalePrice);
+        Ok(self)
+    }
+}
+
+// Mirrors of the pyth-solana-receiver-sdk account layout. The SDK itself
+// depends on a newer Anchor than this program, so it can't be used directly.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+/// Read the latest price from `account` and reject it if it is older
+/// than `max_age_secs` at `now`. Switchboard prices go through the
+/// aggregator's own staleness check on its latest confirmed round.
+pub fn read_fresh_price(
+    source: OracleSource,
+    account: &AccountInfo,
+    now: i64,
+    max_age_secs: u64,
+    use_ema: bool,
+) -> Result<OraclePrice> {
+    let price = read_price(source, account, use_ema)?;
+    if source != OracleSource::Switchboard {
+        return price.check_fresh(now, max_age_secs);
+    }
+
+    let aggregator = AggregatorAccountData::new(account)
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+    let max_staleness = i64::try_from(max_age_secs).unwrap_or(i64::MAX);
+    aggregator
+        .check_staleness(now, max_staleness)
+        .map_err(|_| error!(ErrorCode::StalePrice))?;
+    Ok(price)
+}
+
+/// Whether `err` from `read_fresh_price` means the feed has no current
+/// price to offer, as opposed to one that was read and found invalid.
+/// Only the former may be replaced by another feed's price.
+pub fn is_unavailable(err: &anchor_lang::error::Error) -> bool {
+    [ErrorCode::StalePrice, ErrorCode::NoPriceFound]
+        .into_iter()
+        .any(|code| *err == code.into())
+}
+
+/// Read the latest price from `account`. With `use_ema`, sources that
+/// publish an exponential moving average (Pyth) report it instead of the
+/// instantaneous price; other sources always report their latest answer.
+pub fn read_price(source: OracleSource, account: &AccountInfo, use_ema: bool) -> Result<OraclePrice> {
+    require!(source.owns(account), ErrorCode::InvalidOracleOwner);
+
+    match source {
+        OracleSource::Pyth => read_pyth_price(account, use_ema),
+        OracleSource::Switchboard => read_switchboard_price(account),
+        OracleSource::Custom => read_custom_price(account),
+        OracleSource::Chainlink => read_chainlink_price(account),
+    }
+}
+
+fn read_pyth_price(account: &AccountInfo, use_ema: bool) -> Result<OraclePrice> {
+    let price_feed = load_price_feed_from_account_info(account)
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+    let price = if use_ema {
+        price_feed.get_ema_price_unchecked()
+    } else {
+        price_feed.get_price_unchecked()
+    };
+
+    Ok(OraclePrice {
+        price: price.price,
+        conf: price.conf,
+        expo: price.expo,
+        publish_time: price.publish_time,
+    })
+}
+
+fn read_chainlink_price(account: &AccountInfo) -> Result<OraclePrice> {
+    let data = account.try_borrow_data()?;
+    let feed = read_feed_v2(data, account.owner.to_bytes())
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+    let round = feed
+        .latest_round_data()
+        .ok_or(ErrorCode::NoPriceFound)?;
+
+    // OCR2 feeds publish no confidence interval; the answer is the median
+    // of the oracle network's observations
+    Ok(OraclePrice {
+        price: i64::try_from(round.answer).map_err(|_| error!(ErrorCode::MathOverflow))?,
+        conf: 0,
+        expo: -(feed.decimals() as i32),
+        publish_time: round.timestamp as i64,
+    })
+}
+
+/// Read a fully verified Pyth pull-oracle `PriceUpdateV2` account and check
+/// that it carries the expected feed.
+pub fn read_pyth_pull_price(
+    account: &AccountInfo,
+    feed_id: &[u8; 32],
+    use_ema: bool,
+) -> Result<OraclePrice> {
+    require!(
+        *account.owner == PYTH_RECEIVER_PROGRAM_ID,
+        ErrorCode::InvalidOracleOwner
+    );
+
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        ErrorCode::InvalidPriceFeed
+    );
+    let update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+
+    require!(
+        update.verification_level == VerificationLevel::Full,
+        ErrorCode::UnverifiedPriceUpdate
+    );
+    require!(
+        update.price_message.feed_id == *feed_id,
+        ErrorCode::PriceFeedIdMismatch
+    );
+
+    let message = &update.price_message;
+    let (price, conf) = if use_ema {
+        (message.ema_price, message.ema_conf)
+    } else {
+        (message.price, message.conf)
+    };
+
+    Ok(OraclePrice {
+        price,
+        conf,
+        expo: message.exponent,
+        publish_time: message.publish_time,
+    })
+}
+
+fn read_switchboard_price(account: &AccountInfo) -> Result<OraclePrice> {
+    let aggregator = AggregatorAccountData::new(account)
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+    let result = aggregator
+        .get_result()
+        .map_err(|_| error!(ErrorCode::NoPriceFound))?;
+    let round = &aggregator.latest_confirmed_round;
+
+    // Report the round's standard deviation at the result's scale so it
+    // can be compared against the price like a Pyth confidence interval
+    let conf = rescale_decimal(&round.std_deviation, result.scale)?;
+
+    Ok(OraclePrice {
+        price: i64::try_from(result.mantissa).map_err(|_| error!(ErrorCode::MathOverflow))?,
+        conf: u64::try_from(conf.unsigned_abs()).map_err(|_| error!(ErrorCode::MathOverflow))?,
+        expo: -i32::try_from(result.scale).map_err(|_| error!(ErrorCode::MathOverflow))?,
+        // Rounds record only when they opened; it is what the aggregator's
+        // own staleness check measures from
+        publish_time: round.round_open_timestamp,
+    })
+}
+
+fn read_custom_price(account: &AccountInfo) -> Result<OraclePrice> {
+    let data = account.try_borrow_data()?;
+    let feed = CustomPriceFeed::try_deserialize(&mut &data[..])
+        .map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+
+    Ok(OraclePrice {
+        price: feed.price,
+        conf: feed.conf,
+        expo: feed.expo,
+        publish_time: feed.publish_time,
+    })
+}
+
+fn rescale_decimal(value: &SwitchboardDecimal, scale: u32) -> Result<i128> {
+    if value.scale <= scale {
+        let factor = 10i128
+            .checked_pow(scale - value.scale)
+            .ok_or(ErrorCode::MathOverflow)?;
+        value.mantissa.checked_mul(factor).ok_or(error!(ErrorCode::MathOverflow))
+    } else {
+        Ok(10i128
+            .checked_pow(value.scale - scale)
+            .map_or(0, |divisor| value.mantissa / divisor))
+    }
+}
+
+/// Convert a Pyth `price * 10^expo` into an integer amount of
+/// `10^-decimals` USD, using checked math throughout.
+pub fn normalize_price(price: i64, expo: i32, decimals: u8) -> Result<u64> {
+    require!(price > 0, ErrorCode::InvalidPrice);
+    require!(decimals <= MAX_PRICE_DECIMALS, ErrorCode::InvalidPriceDecimals);
+
+    let price = price as u64;
+    let shift = expo
+        .checked_add(decimals as i32)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let normalized = if shift >= 0 {
+        let factor = 10u64
+            .checked_pow(shift as u32)
+            .ok_or(ErrorCode::MathOverflow)?;
+        price.checked_mul(factor).ok_or(ErrorCode::MathOverflow)?
+    } else {
+        // A divisor beyond u64 range means the price rounds down to zero
+        match 10u64.checked_pow(shift.unsigned_abs()) {
+            Some(divisor) => price / divisor,
+            None => 0,
+        }
+    };
+
+    // A price that vanishes at our precision would divide by zero later
+    require!(normalized > 0, ErrorCode::InvalidPrice);
+    Ok(normalized)
+}
+
+/// Whether `conf / price` stays within `max_confidence_bps`. Price and
+/// confidence share the feed's exponent, so no rescaling is needed.
+pub fn confidence_within_bounds(price: i64, conf: u64, max_confidence_bps: u64) -> bool {
+    if price <= 0 {
+        return false;
+    }
+    (conf as u128) * (BPS_DENOMINATOR as u128) <= (price as u128) * (max_confidence_bps as u128)
+}
+
+/// Median of a non-empty set of prices; even-sized sets average the two
+/// middle values.
+pub fn median_price(prices: &mut [u64]) -> u64 {
+    prices.sort_unstable();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        prices[mid]
+    } else {
+        ((prices[mid - 1] as u128 + prices[mid] as u128) / 2) as u64
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, TokenState};
+
+/// An offer to sell escrowed tokens for a stablecoin, kept at
+/// `[b"otc_offer", maker, offer_id]` with the tokens in
+/// `[b"otc_vault", offer]`. The whole amount changes hands at once.
+#[account]
+pub struct OtcOffer {
+    pub maker: Pubkey,
+    pub offer_id: u64,
+    /// Base units escrowed
+    pub amount: u64,
+    /// Stablecoin mint the maker is paid in
+    pub payment_mint: Pubkey,
+    /// Base units of the payment mint per whole token
+    pub price: u64,
+    /// Only this wallet may accept, when set
+    pub counterparty: Option<Pubkey>,
+    pub expires_at: i64,
+    /// Furthest the price may sit from the oracle price when the offer is
+    /// accepted; zero skips the check
+    pub max_deviation_bps: u64,
+    pub created_at: i64,
+}
+
+impl OtcOffer {
+    pub const LEN: usize = 32 + 8 + 8 + 32 + 8 + (1 + 32) + 8 + 8 + 8;
+
+    /// Payment for the whole amount, rounded up
+    pub fn total_price(&self, token_decimals: u8) -> Result<u64> {
+        let unit = 10u128.pow(token_decimals as u32);
+        let total = (self.amount as u128 * self.price as u128 + unit - 1) / unit;
+        u64::try_from(total).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Check the price against the last accepted oracle price, treating
+    /// one whole unit of the payment mint as one dollar
+    pub fn check_oracle_bound(
+        &self,
+        state: &TokenState,
+        payment_decimals: u8,
+        now: i64,
+    ) -> Result<()> {
+        if self.max_deviation_bps == 0 {
+            return Ok(());
+        }
+        require!(
+            state.last_price > 0
+                && !state.circuit_breaker_tripped
+                && now.saturating_sub(state.last_update) <= state.max_price_age_secs as i64,
+            ErrorCode::OtcOraclePriceUnavailable
+        );
+        let usd_price = self.price as u128 * 10u128.pow(state.price_decimals as u32)
+            / 10u128.pow(payment_decimals as u32);
+        let deviation = state
+            .price_deviation_bps(u64::try_from(usd_price).unwrap_or(u64::MAX))
+            .unwrap_or(u64::MAX);
+        require!(
+            deviation <= self.max_deviation_bps,
+            ErrorCode::OtcPriceOutOfBounds
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PresaleArgs {
+    /// Root over `leaf_hash(index, wallet, allocation)`, the most lamports
+    /// each whitelisted wallet may contribute
+    pub whitelist_root: [u8; 32],
+    /// Lamports per whole token
+    pub price: u64,
+    /// Lamports that must be raised by the deadline, or contributions are
+    /// refunded
+    pub soft_cap: u64,
+    pub hard_cap: u64,
+    /// Bounds on each wallet's total contribution; allocations above
+    /// `max_contribution` are held to it
+    pub min_contribution: u64,
+    pub max_contribution: u64,
+    pub start_time: i64,
+    pub deadline: i64,
+}
+
+/// Whitelisted presale, kept at `[b"presale"]`. Contributions are escrowed
+/// on this account until the deadline. If the soft cap was met they go to
+/// the treasury at `finalize_presale` and contributors claim their tokens;
+/// otherwise `enable_refunds` lets every contributor take theirs back.
+#[account]
+pub struct Presale {
+    pub whitelist_root: [u8; 32],
+    pub price: u64,
+    pub soft_cap: u64,
+    pub hard_cap: u64,
+    pub min_contribution: u64,
+    pub max_contribution: u64,
+    pub start_time: i64,
+    pub deadline: i64,
+    /// Decimals of the token sold, which make up a whole token
+    pub token_decimals: u8,
+    /// Lamports contributed and not refunded
+    pub raised: u64,
+    pub refunds_enabled: bool,
+    /// Set once the proceeds have moved to the treasury
+    pub finalized: bool,
+}
+
+impl Presale {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 1 + 1;
+
+    pub fn configure(&mut self, args: &PresaleArgs, token_decimals: u8) -> Result<()> {
+        require!(
+            args.price > 0
+                && args.soft_cap <= args.hard_cap
+                && args.hard_cap > 0
+                && args.min_contribution <= args.max_contribution
+                && args.max_contribution > 0
+                && args.start_time < args.deadline,
+            ErrorCode::InvalidPresaleConfig
+        );
+        self.whitelist_root = args.whitelist_root;
+        self.price = args.price;
+        self.soft_cap = args.soft_cap;
+        self.hard_cap = args.hard_cap;
+        self.min_contribution = args.min_contribution;
+        self.max_contribution = args.max_contribution;
+        self.start_time = args.start_time;
+        self.deadline = args.deadline;
+        self.token_decimals = token_decimals;
+        Ok(())
+    }
+
+    pub fn is_open(&self, now: i64) -> bool {
+        !self.refunds_enabled && (self.start_time..self.deadline).contains(&now)
+    }
+
+    /// Contributions can no longer change: the deadline passed or the hard
+    /// cap was reached
+    pub fn has_closed(&self, now: i64) -> bool {
+        now >= self.deadline || self.raised >= self.hard_cap
+    }
+
+    /// Check that a wallet allotted `allocation` may bring its total to
+    /// `contributed`
+    pub fn check_contribution(&self, contributed: u64, allocation: u64) -> Result<()> {
+        require!(
+            contributed >= self.min_contribution,
+            ErrorCode::PresaleContributionTooSmall
+        );
+        require!(
+            contributed <= allocation.min(self.max_contribution),
+            ErrorCode::PresaleContributionTooLarge
+        );
+        Ok(())
+    }
+
+    /// Base units bought by `contributed` lamports, rounded down
+    pub fn tokens_for(&self, contributed: u64) -> Result<u64> {
+        let tokens =
+            contributed as u128 * 10u128.pow(self.token_decimals as u32) / self.price as u128;
+        u64::try_from(tokens).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// A wallet's escrowed contribution, kept at
+/// `[b"presale_contribution", contributor]` and closed once it is claimed
+/// or refunded
+#[account]
+pub struct PresaleContribution {
+    pub contributor: Pubkey,
+    pub contributed: u64,
+}
+
+impl PresaleContribution {
+    pub const LEN: usize = 32 + 8;
+}
@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    read_access_override, AccessOverrideKind, ErrorCode, GracePeriodExpired, GracePeriodStarted,
+    BPS_DENOMINATOR,
+};
+
+/// Default time a successful verification stays valid (one day)
+pub const DEFAULT_RECEIPT_VALIDITY_SECS: u64 = 86_400;
+
+/// Longest validity that can be configured (30 days)
+pub const MAX_RECEIPT_VALIDITY_SECS: u64 = 30 * 86_400;
+
+/// Default time a verified wallet keeps access after falling short (72h)
+pub const DEFAULT_GRACE_PERIOD_SECS: u64 = 72 * 3_600;
+
+/// Longest grace period that can be configured (30 days)
+pub const MAX_GRACE_PERIOD_SECS: u64 = 30 * 86_400;
+
+/// Number of access levels above no access
+pub const ACCESS_TIERS: usize = 3;
+
+/// `VerificationReceipt::tier` of a wallet that did not verify
+pub const NO_ACCESS: u8 = 0;
+
+/// Highest threshold a tier can have (100x the requirement)
+pub const MAX_ACCESS_TIER_BPS: u64 = 1_000_000;
+
+/// Basic, Pro, and Whale at 1x, 3x, and 10x the requirement
+pub const DEFAULT_ACCESS_TIERS: [u64; ACCESS_TIERS] = [10_000, 30_000, 100_000];
+
+/// Thresholds must rise strictly, starting at the requirement itself so
+/// the lowest tier is exactly what `verify_balance` checks
+pub fn validate_access_tiers(tiers: &[u64; ACCESS_TIERS]) -> Result<()> {
+    require!(tiers[0] == BPS_DENOMINATOR, ErrorCode::InvalidAccessTier);
+    for (i, threshold) in tiers.iter().enumerate() {
+        require!(
+            *threshold <= MAX_ACCESS_TIER_BPS && (i == 0 || *threshold > tiers[i - 1]),
+            ErrorCode::InvalidAccessTier
+        );
+    }
+    Ok(())
+}
+
+/// Highest tier (counting from 1) whose threshold, in basis points of
+/// `requirement`, `balance` reaches, or `NO_ACCESS`
+pub fn access_tier(balance: u64, requirement: u64, tiers: &[u64; ACCESS_TIERS]) -> u8 {
+    tiers
+        .iter()
+        .take_while(|threshold| {
+            balance as u128 * BPS_DENOMINATOR as u128 >= requirement as u128 * **threshold as u128
+        })
+        .count() as u8
+}
+
+/// Outcome of the last `verify_balance` for a wallet, kept at
+/// `[b"receipt", wallet]` so the backend and other programs can check
+/// access without re-reading token accounts. A failed verification is
+/// recorded as a receipt that expires the moment it is written, unless the
+/// wallet was verified before and is still within its grace period.
+#[account]
+pub struct VerificationReceipt {
+    pub wallet: Pubkey,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub requirement_at_verification: u64,
+    /// Access level reached, from `NO_ACCESS` up to `ACCESS_TIERS`
+    pub tier: u8,
+    /// When a verified wallet was first seen below the requirement; zero
+    /// while it meets it
+    pub below_since: i64,
+}
+
+/// What `check_access` returns to the calling program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccessStatus {
+    pub wallet: Pubkey,
+    /// Whether the wallet has access at `min_tier` or above
+    pub has_access: bool,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub checked_at: i64,
+}
+
+impl VerificationReceipt {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 1 + 8;
+
+    pub fn is_valid(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+
+    /// Record a verification reaching `tier` and return the tier granted.
+    /// A wallet that held a tier and now falls short keeps it for
+    /// `grace_period_secs` from the first verification that saw it short.
+    pub fn record(
+        &mut self,
+        wallet: Pubkey,
+        tier: u8,
+        requirement: u64,
+        validity_secs: u64,
+        grace_period_secs: u64,
+        now: i64,
+    ) -> u8 {
+        let mut granted = tier;
+        let mut expires_at = if tier != NO_ACCESS {
+            now.saturating_add(validity_secs as i64)
+        } else {
+            now
+        };
+
+        if tier != NO_ACCESS || grace_period_secs == 0 {
+            self.below_since = 0;
+        } else {
+            if self.below_since == 0 && self.tier != NO_ACCESS {
+                self.below_since = now;
+                emit!(GracePeriodStarted {
+                    wallet,
+                    requirement,
+                    ends_at: now.saturating_add(grace_period_secs as i64),
+                    timestamp: now,
+                });
+            }
+            if self.below_since != 0 {
+                let ends_at = self.below_since.saturating_add(grace_period_secs as i64);
+                if now < ends_at {
+                    granted = self.tier;
+                    expires_at = std::cmp::min(ends_at, now.saturating_add(validity_secs as i64));
+                } else {
+                    emit!(GracePeriodExpired {
+                        wallet,
+                        below_since: self.below_since,
+                        timestamp: now,
+                    });
+                    self.below_since = 0;
+                }
+            }
+        }
+
+        self.wallet = wallet;
+        self.verified_at = now;
+        self.expires_at = expires_at;
+        self.requirement_at_verification = requirement;
+        self.tier = granted;
+        granted
+    }
+}
+
+/// Access `wallet` has at `now` according to its receipt and override
+/// accounts, either of which may be empty
+pub fn access_status(
+    wallet: Pubkey,
+    receipt: &AccountInfo,
+    access_override: &AccountInfo,
+    min_tier: u8,
+    now: i64,
+) -> Result<AccessStatus> {
+    let (mut tier, mut expires_at) = if receipt.data_is_empty() {
+        (NO_ACCESS, 0)
+    } else {
+        let receipt = Account::<VerificationReceipt>::try_from(receipt)?;
+        let tier = if receipt.is_valid(now) { receipt.tier } else { NO_ACCESS };
+        (tier, receipt.expires_at)
+    };
+    match read_access_override(access_override)? {
+        Some(AccessOverrideKind::Allow) => {
+            tier = std::cmp::max(tier, 1);
+            expires_at = i64::MAX;
+        }
+        Some(AccessOverrideKind::Deny) => tier = NO_ACCESS,
+        None => {}
+    }
+
+    Ok(AccessStatus {
+        wallet,
+        has_access: tier != NO_ACCESS && tier >= min_tier,
+        tier,
+        expires_at,
+        checked_at: now,
+    })
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Binds a user to the referrer who brought them in. Created once per user
+/// at `[b"referral", user]`; the referrer is paid `TokenState::referral_bps`
+/// of every protocol fee the user's operations are charged.
+#[account]
+pub struct Referral {
+    pub user: Pubkey,
+    pub referrer: Pubkey,
+    pub registered_at: i64,
+    /// Fee share paid to the referrer so far
+    pub rewards_paid: u64,
+}
+
+impl Referral {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+}
+
+/// Reject referral loops: `referrer_referral` is the referrer's own
+/// `[b"referral", referrer]` account, which must not point back at `user`.
+pub fn check_referral_loop(referrer_referral: &AccountInfo, user: &Pubkey) -> Result<()> {
+    if referrer_referral.owner != &crate::ID || referrer_referral.data_is_empty() {
+        return Ok(());
+    }
+    let referral = Referral::try_deserialize(&mut &referrer_referral.data.borrow()[..])?;
+    require!(referral.referrer != *user, ErrorCode::SelfReferral);
+    Ok(())
+}
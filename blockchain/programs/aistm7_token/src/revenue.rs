@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::distributor::bitmap_count;
+
+/// One epoch of protocol revenue, shared pro rata between holders by merkle
+/// claims. Leaves are `leaf_hash(index, holder, amount)` computed off-chain
+/// from a snapshot of balances at `snapshot_slot`; the revenue itself sits in
+/// the `[b"revenue_vault", revenue_epoch]` vault until claimed. Claims are
+/// recorded in `ClaimBitmap`s keyed by the epoch account.
+#[account]
+pub struct RevenueEpoch {
+    pub epoch: u64,
+    /// Mint revenue is paid in, which need not be the program's own token
+    pub revenue_mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub snapshot_slot: u64,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    /// Number of leaves; claim indexes run from zero to `max_claims - 1`
+    pub max_claims: u32,
+    pub published_at: i64,
+}
+
+impl RevenueEpoch {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 4 + 8;
+
+    /// Bitmap accounts needed to cover every claim index
+    pub fn bitmap_count(&self) -> u32 {
+        bitmap_count(self.max_claims)
+    }
+}
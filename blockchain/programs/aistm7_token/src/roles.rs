@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Total role assignments the registry can hold, across all roles
+pub const MAX_ROLE_MEMBERS: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Changes parameters, oracle configuration, and role assignments
+    Admin,
+    /// Pauses and unpauses the program
+    Operator,
+    /// Triggers signed requirement updates and DCA buyback tranches
+    Cranker,
+    /// Mints tokens through the program's mint authority
+    Minter,
+    /// Signs off-chain verification attestations
+    Attestor,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoleMember {
+    pub role: Role,
+    pub key: Pubkey,
+}
+
+impl RoleMember {
+    pub const LEN: usize = 1 + 32;
+
+    /// Unused slot
+    pub const EMPTY: RoleMember = RoleMember {
+        role: Role::Admin,
+        key: Pubkey::new_from_array([0; 32]),
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.key == Pubkey::default()
+    }
+}
+
+/// Keys holding each role in addition to `TokenState::authority`, which
+/// implicitly holds every role and stays the recovery key.
+#[account]
+pub struct RoleRegistry {
+    pub members: [RoleMember; MAX_ROLE_MEMBERS],
+}
+
+impl RoleRegistry {
+    pub const LEN: usize = RoleMember::LEN * MAX_ROLE_MEMBERS;
+
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        self.members
+            .iter()
+            .any(|member| !member.is_empty() && member.role == role && member.key == *key)
+    }
+
+    pub fn grant(&mut self, role: Role, key: Pubkey) -> Result<()> {
+        require!(key != Pubkey::default(), ErrorCode::InvalidParameter);
+        require!(!self.has_role(role, &key), ErrorCode::RoleAlreadyGranted);
+        let slot = self
+            .members
+            .iter_mut()
+            .find(|member| member.is_empty())
+            .ok_or(ErrorCode::RoleRegistryFull)?;
+        *slot = RoleMember { role, key };
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, role: Role, key: &Pubkey) -> Result<()> {
+        let slot = self
+            .members
+            .iter_mut()
+            .find(|member| !member.is_empty() && member.role == role && member.key == *key)
+            .ok_or(ErrorCode::RoleNotGranted)?;
+        *slot = RoleMember::EMPTY;
+        Ok(())
+    }
+}
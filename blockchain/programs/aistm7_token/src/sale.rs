@@ -0,0 +1,194 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Fixed-point scale of the exponential curve's growth factor
+const GROWTH_SCALE: u128 = 1_000_000_000_000;
+
+/// How the sale price moves as tokens are sold. Prices are in base units
+/// of the payment asset per whole token.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaleCurve {
+    /// The price rises by `slope` for every whole token sold
+    Linear { slope: u64 },
+    /// The price rises by `growth_bps` after every `step` base units sold,
+    /// and is constant within a step
+    Exponential { growth_bps: u64, step: u64 },
+}
+
+impl SaleCurve {
+    pub const LEN: usize = 1 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaleArgs {
+    /// Mint buyers pay in, or `NATIVE_SOL`
+    pub payment_mint: Pubkey,
+    pub curve: SaleCurve,
+    /// Price of the first token
+    pub start_price: u64,
+    /// Most base units the sale can ever sell
+    pub hard_cap: u64,
+    /// Most base units one wallet can buy
+    pub wallet_cap: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// Public token sale, kept at `[b"sale"]`. Tokens are minted to buyers at
+/// the curve's price for the amount already sold, and payments go straight
+/// to the treasury. The terms are fixed once the sale starts, and
+/// `finalize_sale` closes it for good.
+#[account]
+pub struct Sale {
+    pub payment_mint: Pubkey,
+    pub curve: SaleCurve,
+    pub start_price: u64,
+    pub hard_cap: u64,
+    pub wallet_cap: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Decimals of the token sold, which make up a whole token
+    pub token_decimals: u8,
+    pub sold: u64,
+    /// Payments received, in base units of the payment asset
+    pub raised: u64,
+    pub finalized: bool,
+}
+
+impl Sale {
+    pub const LEN: usize = 32 + SaleCurve::LEN + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 1;
+
+    pub fn configure(&mut self, args: &SaleArgs, token_decimals: u8) -> Result<()> {
+        require!(
+            args.start_price > 0
+                && args.hard_cap > 0
+                && (1..=args.hard_cap).contains(&args.wallet_cap)
+                && args.start_time < args.end_time,
+            ErrorCode::InvalidSaleConfig
+        );
+        if let SaleCurve::Exponential { growth_bps, step } = args.curve {
+            require!(
+                (1..=BPS_DENOMINATOR).contains(&growth_bps) && step > 0,
+                ErrorCode::InvalidSaleConfig
+            );
+        }
+
+        self.payment_mint = args.payment_mint;
+        self.curve = args.curve;
+        self.start_price = args.start_price;
+        self.hard_cap = args.hard_cap;
+        self.wallet_cap = args.wallet_cap;
+        self.start_time = args.start_time;
+        self.end_time = args.end_time;
+        self.token_decimals = token_decimals;
+
+        // Selling out must be payable, so no purchase can overflow later
+        u64::try_from(self.cumulative_cost(args.hard_cap)?)
+            .map_err(|_| error!(ErrorCode::InvalidSaleConfig))?;
+        Ok(())
+    }
+
+    pub fn args(&self) -> SaleArgs {
+        SaleArgs {
+            payment_mint: self.payment_mint,
+            curve: self.curve,
+            start_price: self.start_price,
+            hard_cap: self.hard_cap,
+            wallet_cap: self.wallet_cap,
+            start_time: self.start_time,
+            end_time: self.end_time,
+        }
+    }
+
+    /// What buying `amount` base units costs now, rounded up
+    pub fn cost(&self, amount: u64) -> Result<u64> {
+        let end = self.sold.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        let cost = self
+            .cumulative_cost(end)?
+            .saturating_sub(self.cumulative_cost(self.sold)?);
+        u64::try_from(cost).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Price of the next whole token
+    pub fn price(&self) -> Result<u64> {
+        let price = match self.curve {
+            SaleCurve::Linear { slope } => {
+                self.start_price as u128 + slope as u128 * self.sold as u128 / self.unit()
+            }
+            SaleCurve::Exponential { growth_bps, step } => {
+                let factor = growth_factor(growth_bps, self.sold / step)?;
+                checked_mul(self.start_price as u128, factor)? / GROWTH_SCALE
+            }
+        };
+        u64::try_from(price).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Total paid for the first `sold` base units, rounded up
+    fn cumulative_cost(&self, sold: u64) -> Result<u128> {
+        let unit = self.unit();
+        let sold = sold as u128;
+        let scaled = match self.curve {
+            // Area under `start_price + slope * x / unit`, in units of 1/unit
+            SaleCurve::Linear { slope } => {
+                let ramp = checked_mul(sold * sold / unit, slope as u128)? / 2;
+                checked_mul(self.start_price as u128, sold)?
+                    .checked_add(ramp)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+            // Whole steps as a geometric series, then the current step
+            SaleCurve::Exponential { growth_bps, step } => {
+                let steps = sold / step as u128;
+                let factor = growth_factor(growth_bps, steps as u64)?;
+                let series = checked_mul(
+                    checked_mul(self.start_price as u128, factor - GROWTH_SCALE)?,
+                    BPS_DENOMINATOR as u128,
+                )? / (GROWTH_SCALE * growth_bps as u128);
+                let current = checked_mul(self.start_price as u128, factor)? / GROWTH_SCALE;
+                checked_mul(series, step as u128)?
+                    .checked_add(checked_mul(current, sold % step as u128)?)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+        };
+        Ok((scaled + unit - 1) / unit)
+    }
+
+    fn unit(&self) -> u128 {
+        10u128.pow(self.token_decimals as u32)
+    }
+}
+
+/// `(1 + growth_bps)^steps` in `GROWTH_SCALE` fixed point
+fn growth_factor(growth_bps: u64, steps: u64) -> Result<u128> {
+    let mut factor = GROWTH_SCALE;
+    let mut base =
+        (BPS_DENOMINATOR + growth_bps) as u128 * GROWTH_SCALE / BPS_DENOMINATOR as u128;
+    let mut exponent = steps;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            factor = checked_mul(factor, base)? / GROWTH_SCALE;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = checked_mul(base, base)? / GROWTH_SCALE;
+        }
+    }
+    Ok(factor)
+}
+
+fn checked_mul(a: u128, b: u128) -> Result<u128> {
+    a.checked_mul(b).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// What one wallet has bought, kept at `[b"sale_purchase", buyer]` to
+/// enforce the wallet cap
+#[account]
+pub struct SalePurchase {
+    pub buyer: Pubkey,
+    pub purchased: u64,
+    pub paid: u64,
+}
+
+impl SalePurchase {
+    pub const LEN: usize = 32 + 8 + 8;
+}
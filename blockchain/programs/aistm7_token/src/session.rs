@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Session may refresh verification on the user's behalf; integrators that
+/// accept a session key in place of the user check for this bit
+pub const SESSION_VERIFY: u8 = 1 << 0;
+
+/// Session may pay for pay-per-use access from the user's token account
+pub const SESSION_CONSUME_ACCESS: u8 = 1 << 1;
+
+/// Every permission a session can be granted
+pub const SESSION_PERMISSIONS: u8 = SESSION_VERIFY | SESSION_CONSUME_ACCESS;
+
+/// Longest a session key can live (one week)
+pub const MAX_SESSION_SECS: i64 = 7 * 86_400;
+
+/// Ephemeral keypair allowed to act for `user` within `permissions` until
+/// `expires_at`, kept at `[b"session", user, session_key]`. Spending goes
+/// through an SPL approval of the user's token account to this account,
+/// capped again here by `spend_limit`.
+#[account]
+pub struct SessionKey {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub permissions: u8,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub spend_limit: u64,
+    pub spent: u64,
+}
+
+impl SessionKey {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 8 + 8 + 8;
+
+    pub fn authorizes(&self, permission: u8, now: i64) -> bool {
+        self.permissions & permission == permission && now < self.expires_at
+    }
+
+    /// Count `amount` against the spend limit
+    pub fn spend(&mut self, amount: u64) -> Result<()> {
+        let spent = self.spent.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(spent <= self.spend_limit, ErrorCode::SessionSpendLimitExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Fixed-point scale of `StakePool::reward_per_token`
+pub const REWARD_SCALE: u128 = 1_000_000_000_000;
+
+/// Number of selectable lock durations
+pub const LOCK_TIERS: usize = 4;
+
+/// `StakeAccount::lock_tier` of stake that is not locked
+pub const NO_LOCK: u8 = u8::MAX;
+
+/// Largest reward multiplier or access boost a tier can have (10x)
+pub const MAX_TIER_MULTIPLIER_BPS: u64 = 100_000;
+
+const DAY_SECS: u64 = 86_400;
+
+/// Default wait between requesting an unstake and withdrawing it
+pub const DEFAULT_UNSTAKE_COOLDOWN_SECS: u64 = 7 * DAY_SECS;
+
+/// A lock duration and what locking for it earns
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockTier {
+    pub duration_secs: u64,
+    /// Weight of locked stake in reward distribution
+    pub reward_multiplier_bps: u64,
+    /// Weight of locked stake in `verify_balance`
+    pub boost_bps: u64,
+}
+
+impl LockTier {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// 30, 90, 180, and 365 day locks
+pub const DEFAULT_LOCK_TIERS: [LockTier; LOCK_TIERS] = [
+    LockTier {
+        duration_secs: 30 * DAY_SECS,
+        reward_multiplier_bps: 11_000,
+        boost_bps: 11_000,
+    },
+    LockTier {
+        duration_secs: 90 * DAY_SECS,
+        reward_multiplier_bps: 12_500,
+        boost_bps: 12_500,
+    },
+    LockTier {
+        duration_secs: 180 * DAY_SECS,
+        reward_multiplier_bps: 15_000,
+        boost_bps: 15_000,
+    },
+    LockTier {
+        duration_secs: 365 * DAY_SECS,
+        reward_multiplier_bps: 20_000,
+        boost_bps: 20_000,
+    },
+];
+
+/// Tiers must get strictly longer and never weigh locked stake below 1x
+pub fn validate_lock_tiers(tiers: &[LockTier; LOCK_TIERS]) -> Result<()> {
+    for (i, tier) in tiers.iter().enumerate() {
+        require!(
+            tier.duration_secs > 0
+                && i64::try_from(tier.duration_secs).is_ok()
+                && (i == 0 || tier.duration_secs > tiers[i - 1].duration_secs),
+            ErrorCode::InvalidLockTier
+        );
+        for multiplier in [tier.reward_multiplier_bps, tier.boost_bps] {
+            require!(
+                (BPS_DENOMINATOR..=MAX_TIER_MULTIPLIER_BPS).contains(&multiplier),
+                ErrorCode::InvalidLockTier
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Global staking accumulator. Rewards are whatever arrives in the
+/// `[b"reward_vault"]` account (emission epochs, buybacks, manual top-ups);
+/// each new deposit is shared out pro rata to the reward weight staked at
+/// the time it is first seen.
+#[account]
+pub struct StakePool {
+    pub total_staked: u64,
+    /// Sum of every stake account's `weight`
+    pub total_weight: u64,
+    /// Rewards per unit of weight ever distributed, scaled by `REWARD_SCALE`
+    pub reward_per_token: u128,
+    /// Reward vault balance already distributed but not yet claimed
+    pub reserved_rewards: u64,
+}
+
+impl StakePool {
+    pub const LEN: usize = 8 + 8 + 16 + 8;
+
+    /// Distribute reward vault deposits made since the last call
+    pub fn accrue(&mut self, reward_vault_balance: u64) -> Result<()> {
+        let new_rewards = reward_vault_balance.saturating_sub(self.reserved_rewards);
+        if new_rewards == 0 || self.total_weight == 0 {
+            return Ok(());
+        }
+        let increment = new_rewards as u128 * REWARD_SCALE / self.total_weight as u128;
+        self.reward_per_token = self
+            .reward_per_token
+            .checked_add(increment)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.reserved_rewards = reward_vault_balance;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// `amount` scaled by the lock's reward multiplier
+    pub weight: u64,
+    /// Index into `TokenState::lock_tiers`, or `NO_LOCK`
+    pub lock_tier: u8,
+    pub unlock_at: i64,
+    /// `StakePool::reward_per_token` as of the last settlement
+    pub reward_per_token_paid: u128,
+    /// Rewards earned but not yet claimed
+    pub pending_rewards: u64,
+    pub staked_at: i64,
+    /// Requested for withdrawal; earns nothing and no longer counts as stake
+    pub unstaking_amount: u64,
+    pub unstake_available_at: i64,
+}
+
+impl StakeAccount {
+    pub const LEN: usize = 32 + 8 + 8 + 1 + 8 + 16 + 8 + 8 + 8 + 8;
+
+    pub fn is_locked(&self, now: i64) -> bool {
+        self.lock_tier != NO_LOCK && now < self.unlock_at
+    }
+
+    /// Lock the stake for `tier`. A lock can be extended or moved to a
+    /// longer tier but never shortened; `NO_LOCK` leaves it as it is.
+    pub fn lock(&mut self, tier: u8, tiers: &[LockTier; LOCK_TIERS], now: i64) -> Result<()> {
+        if tier == NO_LOCK {
+            return Ok(());
+        }
+        let lock = tiers.get(tier as usize).ok_or(ErrorCode::InvalidLockTier)?;
+        let unlock_at = now
+            .checked_add(lock.duration_secs as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if !self.is_locked(now) || tier > self.lock_tier {
+            self.lock_tier = tier;
+        }
+        self.unlock_at = std::cmp::max(self.unlock_at, unlock_at);
+        Ok(())
+    }
+
+    /// Tier multiplier while locked, 1x otherwise
+    fn multiplier(
+        &self,
+        tiers: &[LockTier; LOCK_TIERS],
+        now: i64,
+        select: impl Fn(&LockTier) -> u64,
+    ) -> u64 {
+        if self.is_locked(now) {
+            select(&tiers[self.lock_tier as usize])
+        } else {
+            BPS_DENOMINATOR
+        }
+    }
+
+    /// Recompute `weight` for the current amount and lock, keeping the
+    /// pool total in step. Call after `settle`. An expired lock keeps its
+    /// multiplier until the next stake, unstake, or claim.
+    pub fn reweight(
+        &mut self,
+        pool: &mut StakePool,
+        tiers: &[LockTier; LOCK_TIERS],
+        now: i64,
+    ) -> Result<()> {
+        let multiplier = self.multiplier(tiers, now, |tier| tier.reward_multiplier_bps);
+        let weight = self.amount as u128 * multiplier as u128 / BPS_DENOMINATOR as u128;
+        let weight = u64::try_from(weight).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        pool.total_weight = pool
+            .total_weight
+            .checked_sub(self.weight)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_add(weight)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.weight = weight;
+        Ok(())
+    }
+
+    /// Staked balance counted towards the access requirement, boosted
+    /// while the stake is locked
+    pub fn effective_balance(&self, tiers: &[LockTier; LOCK_TIERS], now: i64) -> u64 {
+        let boost = self.multiplier(tiers, now, |tier| tier.boost_bps);
+        let balance = self.amount as u128 * boost as u128 / BPS_DENOMINATOR as u128;
+        u64::try_from(balance).unwrap_or(u64::MAX)
+    }
+
+    /// Settle and move every pending reward into the stake, returning the
+    /// amount compounded. The caller moves the tokens between vaults.
+    pub fn compound(
+        &mut self,
+        pool: &mut StakePool,
+        tiers: &[LockTier; LOCK_TIERS],
+        now: i64,
+    ) -> Result<u64> {
+        self.settle(pool)?;
+        let amount = self.pending_rewards;
+        self.pending_rewards = 0;
+        self.amount = self.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool
+            .total_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.reserved_rewards = pool.reserved_rewards.saturating_sub(amount);
+        self.reweight(pool, tiers, now)?;
+        Ok(amount)
+    }
+
+    /// Credit rewards earned since the last settlement; call after
+    /// `StakePool::accrue` and before the staked amount changes.
+    pub fn settle(&mut self, pool: &StakePool) -> Result<()> {
+        let earned = (self.weight as u128)
+            .checked_mul(pool.reward_per_token - self.reward_per_token_paid)
+            .ok_or(ErrorCode::MathOverflow)?
+            / REWARD_SCALE;
+        self.pending_rewards = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| self.pending_rewards.checked_add(earned))
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.reward_per_token_paid = pool.reward_per_token;
+        Ok(())
+    }
+}
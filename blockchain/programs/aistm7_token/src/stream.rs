@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Payment mints streams can be opened in
+pub const MAX_STREAM_MINTS: usize = 4;
+
+/// Lowest rate a stream in `mint` may pay, for it to count as access
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamRate {
+    pub mint: Pubkey,
+    pub min_rate_per_sec: u64,
+}
+
+impl StreamRate {
+    pub const LEN: usize = 32 + 8;
+
+    /// Unused slot
+    pub const EMPTY: StreamRate = StreamRate {
+        mint: Pubkey::new_from_array([0; 32]),
+        min_rate_per_sec: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.min_rate_per_sec == 0
+    }
+}
+
+/// Mints accepted for streamed billing, kept at `[b"stream_config"]`
+#[account]
+pub struct StreamConfig {
+    pub rates: [StreamRate; MAX_STREAM_MINTS],
+}
+
+impl StreamConfig {
+    pub const LEN: usize = StreamRate::LEN * MAX_STREAM_MINTS;
+
+    /// List `mint` at `min_rate_per_sec`, or delist it with zero, returning
+    /// the previous rate
+    pub fn set_rate(&mut self, mint: Pubkey, min_rate_per_sec: u64) -> Result<u64> {
+        if let Some(rate) = self
+            .rates
+            .iter_mut()
+            .find(|rate| !rate.is_empty() && rate.mint == mint)
+        {
+            let old_rate = rate.min_rate_per_sec;
+            rate.min_rate_per_sec = min_rate_per_sec;
+            return Ok(old_rate);
+        }
+        if min_rate_per_sec > 0 {
+            let slot = self
+                .rates
+                .iter_mut()
+                .find(|rate| rate.is_empty())
+                .ok_or(ErrorCode::StreamMintsFull)?;
+            *slot = StreamRate {
+                mint,
+                min_rate_per_sec,
+            };
+        }
+        Ok(0)
+    }
+
+    pub fn min_rate(&self, mint: &Pubkey) -> Option<u64> {
+        self.rates
+            .iter()
+            .find(|rate| !rate.is_empty() && rate.mint == *mint)
+            .map(|rate| rate.min_rate_per_sec)
+    }
+}
+
+/// A payer's pay-as-you-go subscription, kept at
+/// `[b"payment_stream", payer]` with its escrow in
+/// `[b"stream_vault", stream]`. The escrow accrues to the treasury at
+/// `rate_per_sec` until it runs out, and the stream grants access like an
+/// access pass for as long as it has not.
+#[account]
+pub struct PaymentStream {
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_sec: u64,
+    /// Total escrowed over the stream's life
+    pub deposited: u64,
+    /// Accrued to the treasury as of `settled_at`
+    pub settled: u64,
+    pub settled_at: i64,
+    /// Total paid out to the treasury
+    pub withdrawn: u64,
+}
+
+impl PaymentStream {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8;
+
+    /// Total accrued to the treasury by `now`, never more than deposited
+    pub fn accrued(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.settled_at).max(0) as u64;
+        self.settled
+            .saturating_add(self.rate_per_sec.saturating_mul(elapsed))
+            .min(self.deposited)
+    }
+
+    /// Accrued but not yet paid to the treasury
+    pub fn claimable(&self, now: i64) -> Result<u64> {
+        self.accrued(now)
+            .checked_sub(self.withdrawn)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn is_solvent(&self, now: i64) -> bool {
+        self.accrued(now) < self.deposited
+    }
+
+    /// Add `amount` to the escrow. Accrual is settled first, so a stream
+    /// that ran dry is not billed for the time it was insolvent.
+    pub fn top_up(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.settled = self.accrued(now);
+        self.settled_at = now;
+        self.deposited = self
+            .deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
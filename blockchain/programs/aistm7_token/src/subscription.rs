@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Shortest subscription that can be taken out (one day)
+pub const MIN_SUBSCRIPTION_SECS: u64 = 86_400;
+
+/// Longest subscription that can be taken out (one year)
+pub const MAX_SUBSCRIPTION_SECS: u64 = 365 * 86_400;
+
+/// Access bought by locking the balance requirement for a fixed term. The
+/// tokens sit in the owner's `[b"subscription_vault", owner]` vault, which
+/// this pass is the authority of, until `unsubscribe` returns them after
+/// expiry. While active the pass satisfies `verify_balance` on its own.
+#[account]
+pub struct AccessPass {
+    pub owner: Pubkey,
+    /// Requirement at the time of subscribing
+    pub escrowed_amount: u64,
+    pub starts_at: i64,
+    pub expires_at: i64,
+}
+
+impl AccessPass {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubscriptionPlanArgs {
+    /// Mint each period is charged in
+    pub mint: Pubkey,
+    /// Base units of `mint` per period
+    pub price: u64,
+    pub period_secs: u64,
+    /// How long before a period ends its renewal may be charged
+    pub renewal_window_secs: u64,
+    /// Inactive plans take no new subscribers and stop renewing
+    pub active: bool,
+}
+
+/// A recurring billing plan, kept at `[b"subscription_plan", plan_id]`.
+/// Periods are charged up front into the treasury's vault for `mint`.
+#[account]
+pub struct SubscriptionPlan {
+    pub plan_id: u32,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub period_secs: u64,
+    pub renewal_window_secs: u64,
+    pub active: bool,
+}
+
+impl SubscriptionPlan {
+    pub const LEN: usize = 4 + 32 + 8 + 8 + 8 + 1;
+
+    pub fn configure(&mut self, plan_id: u32, args: &SubscriptionPlanArgs) -> Result<()> {
+        // The period bounds also keep periods and windows within `i64`
+        require!(
+            args.price > 0
+                && (MIN_SUBSCRIPTION_SECS..=MAX_SUBSCRIPTION_SECS).contains(&args.period_secs)
+                && i64::try_from(args.period_secs).is_ok()
+                && args.renewal_window_secs <= args.period_secs,
+            ErrorCode::InvalidSubscriptionPlan
+        );
+        self.plan_id = plan_id;
+        self.mint = args.mint;
+        self.price = args.price;
+        self.period_secs = args.period_secs;
+        self.renewal_window_secs = args.renewal_window_secs;
+        self.active = args.active;
+        Ok(())
+    }
+
+    /// End of a period starting at `start`
+    pub fn period_end(&self, start: i64) -> Result<i64> {
+        i64::try_from(self.period_secs)
+            .ok()
+            .and_then(|period_secs| start.checked_add(period_secs))
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn args(&self) -> SubscriptionPlanArgs {
+        SubscriptionPlanArgs {
+            mint: self.mint,
+            price: self.price,
+            period_secs: self.period_secs,
+            renewal_window_secs: self.renewal_window_secs,
+            active: self.active,
+        }
+    }
+}
+
+/// A wallet's place on a plan, kept at
+/// `[b"recurring_subscription", subscriber]`. Renewals are charged from
+/// `source`, on which this account has to be approved as delegate for at
+/// least the plan's price; revoking the approval lets the subscription
+/// lapse at the end of the period. While active it satisfies
+/// `verify_balance` like an access pass.
+#[account]
+pub struct RecurringSubscription {
+    pub subscriber: Pubkey,
+    pub plan: Pubkey,
+    pub source: Pubkey,
+    /// End of the period paid for
+    pub next_renewal_at: i64,
+    pub renewals: u64,
+    /// Set by `process_renewal` once a period ends unpaid
+    pub expired: bool,
+}
+
+impl RecurringSubscription {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 1;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.expired && now < self.next_renewal_at
+    }
+
+    /// Earliest the next renewal can be charged
+    pub fn renewal_opens_at(&self, plan: &SubscriptionPlan) -> i64 {
+        self.next_renewal_at.saturating_sub(plan.renewal_window_secs as i64)
+    }
+}
@@ -0,0 +1,208 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface;
+
+use crate::{ErrorCode, TokenState, BPS_DENOMINATOR};
+
+/// Jupiter v6 aggregator, the only route treasury swaps may take
+pub const JUPITER_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Window the swap volume cap applies to
+pub const SWAP_DAY_SECS: i64 = 86_400;
+
+/// Terms of treasury buyback swaps, kept at `[b"swap_config"]`. The
+/// treasury sells `input_mint` from its vault through `swap_program` and
+/// the tokens bought land in the buyback vault, ready to be burned.
+#[account]
+pub struct SwapConfig {
+    /// Program every swap route goes through, always `JUPITER_PROGRAM_ID`
+    pub swap_program: Pubkey,
+    /// Mint the treasury pays in, e.g. USDC
+    pub input_mint: Pubkey,
+    /// Most of `input_mint` sold per day, in its base units
+    pub daily_volume_cap: u64,
+    /// Furthest below the oracle price a swap may fill; never zero, so no
+    /// swap rests on the caller's minimum alone
+    pub max_slippage_bps: u64,
+    pub day_start: i64,
+    pub swapped_today: u64,
+}
+
+impl SwapConfig {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8;
+
+    /// Count `amount` against today's cap, starting a new day first if the
+    /// current one has ended. Returns the amount swapped so far today.
+    pub fn record_volume(&mut self, amount: u64, now: i64) -> Result<u64> {
+        if now.saturating_sub(self.day_start) >= SWAP_DAY_SECS {
+            self.day_start = now;
+            self.swapped_today = 0;
+        }
+        let swapped = self
+            .swapped_today
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(swapped <= self.daily_volume_cap, ErrorCode::SwapVolumeExceeded);
+        self.swapped_today = swapped;
+        Ok(swapped)
+    }
+
+    /// Least output a swap of `amount_in` may fill for at the last accepted
+    /// oracle price, treating one whole unit of the input mint as one
+    /// dollar
+    pub fn min_output(
+        &self,
+        state: &TokenState,
+        amount_in: u64,
+        input_decimals: u8,
+        token_decimals: u8,
+        now: i64,
+    ) -> Result<u64> {
+        require!(self.max_slippage_bps > 0, ErrorCode::SwapRequiresOracleBound);
+        require!(
+            state.last_price > 0
+                && !state.circuit_breaker_tripped
+                && now.saturating_sub(state.last_update) <= state.max_price_age_secs as i64,
+            ErrorCode::SwapOraclePriceUnavailable
+        );
+        let expected = amount_in as u128
+            * 10u128.pow(state.price_decimals as u32 + token_decimals as u32)
+            / (state.last_price as u128 * 10u128.pow(input_decimals as u32));
+        let floor = expected * BPS_DENOMINATOR.saturating_sub(self.max_slippage_bps) as u128
+            / BPS_DENOMINATOR as u128;
+        Ok(u64::try_from(floor).unwrap_or(u64::MAX))
+    }
+}
+
+/// Buyback policy run by the Cranker role, kept at `[b"dca_schedule"]`.
+/// Every `interval_secs` one tranche of `amount_per_interval` of the swap
+/// input is sold under the same checks as `treasury_swap`, until
+/// `total_budget` is spent. Missed intervals are skipped rather than caught
+/// up, so no tranche is larger or comes sooner than scheduled.
+#[account]
+pub struct DcaSchedule {
+    pub amount_per_interval: u64,
+    pub interval_secs: u64,
+    pub total_budget: u64,
+    /// Input sold so far
+    pub spent: u64,
+    pub next_execution_at: i64,
+    pub executions: u64,
+}
+
+impl DcaSchedule {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8;
+
+    /// Start a new schedule at `start_time`, forgetting what earlier ones
+    /// spent
+    pub fn configure(
+        &mut self,
+        amount_per_interval: u64,
+        interval_secs: u64,
+        total_budget: u64,
+        start_time: i64,
+    ) -> Result<()> {
+        require!(
+            amount_per_interval > 0
+                && interval_secs > 0
+                && i64::try_from(interval_secs).is_ok()
+                && total_budget >= amount_per_interval,
+            ErrorCode::InvalidDcaSchedule
+        );
+        self.amount_per_interval = amount_per_interval;
+        self.interval_secs = interval_secs;
+        self.total_budget = total_budget;
+        self.spent = 0;
+        self.next_execution_at = start_time;
+        self.executions = 0;
+        Ok(())
+    }
+
+    /// Size of the tranche due at `now`, if one is
+    pub fn due_tranche(&self, now: i64) -> Option<u64> {
+        if now < self.next_execution_at || self.spent >= self.total_budget {
+            return None;
+        }
+        Some(self.amount_per_interval.min(self.total_budget - self.spent))
+    }
+
+    /// Record a tranche that sold `spent`, moving on to the first interval
+    /// after `now`
+    pub fn record_execution(&mut self, spent: u64, now: i64) -> Result<()> {
+        self.spent = self.spent.checked_add(spent).ok_or(ErrorCode::MathOverflow)?;
+        self.executions = self.executions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        let next_execution_at = self.next_execution_at;
+        self.next_execution_at = i64::try_from(self.interval_secs)
+            .ok()
+            .and_then(|interval| {
+                let missed = now.saturating_sub(next_execution_at) / interval;
+                missed.checked_add(1)?.checked_mul(interval)?.checked_add(next_execution_at)
+            })
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Run a swap the client routed off-chain, a Jupiter quote's swap
+/// instruction, with `authority` signing for the treasury. `accounts` are
+/// the route's accounts in its order. Apart from `vaults`, whose balances
+/// the caller bounds, none of the authority's token accounts in the route
+/// may change balance.
+pub fn forward_swap<'info>(
+    swap_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    vaults: [&Pubkey; 2],
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require_keys_eq!(*swap_program.key, JUPITER_PROGRAM_ID, ErrorCode::UnsupportedSwapProgram);
+    let balances = owned_balances(accounts, authority.key, vaults);
+    let metas = accounts
+        .iter()
+        .map(|account| {
+            let signer = account.is_signer || account.key == authority.key;
+            if account.is_writable {
+                AccountMeta::new(*account.key, signer)
+            } else {
+                AccountMeta::new_readonly(*account.key, signer)
+            }
+        })
+        .collect();
+    let mut infos = accounts.to_vec();
+    infos.push(swap_program.clone());
+    invoke_signed(
+        &Instruction {
+            program_id: *swap_program.key,
+            accounts: metas,
+            data,
+        },
+        &infos,
+        signer_seeds,
+    )?;
+    require!(
+        owned_balances(accounts, authority.key, vaults) == balances,
+        ErrorCode::SwapTouchedTreasury
+    );
+    Ok(())
+}
+
+/// Balances of the token accounts among `accounts` that `owner` owns,
+/// other than `vaults`, in route order
+fn owned_balances(
+    accounts: &[AccountInfo<'_>],
+    owner: &Pubkey,
+    vaults: [&Pubkey; 2],
+) -> Vec<(Pubkey, u64)> {
+    accounts
+        .iter()
+        .filter(|account| !vaults.contains(&account.key))
+        .filter_map(|account| {
+            let token_account =
+                InterfaceAccount::<token_interface::TokenAccount>::try_from(account).ok()?;
+            (token_account.owner == *owner).then(|| (*account.key, token_account.amount))
+        })
+        .collect()
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+use crate::oracle::{OracleFeed, OracleSource, MAX_MEDIAN_FEEDS};
+use crate::receipt::ACCESS_TIERS;
+use crate::staking::{LockTier, LOCK_TIERS};
+use crate::UpdateParametersArgs;
+
+/// A change to the requirement formula that must wait out the timelock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum ParameterChange {
+    Parameters(UpdateParametersArgs),
+    PriceFeed {
+        source: OracleSource,
+        price_feed: Pubkey,
+    },
+    /// `None` disables the fallback oracle
+    FallbackPriceFeed {
+        source: OracleSource,
+        price_feed: Option<Pubkey>,
+    },
+    MedianFeeds {
+        feeds: Vec<OracleFeed>,
+        min_feeds: u8,
+    },
+    PullFeedId([u8; 32]),
+    TimelockDelay(u64),
+    /// Raises a treasury withdrawal limit; lowering one needs no timelock
+    TreasuryLimit {
+        asset: Pubkey,
+        per_epoch_limit: u64,
+    },
+    /// Needs the token-2022 mint once the migration has started
+    InterestRate(i16),
+    /// Raises a chain's bridge limits; lowering them needs no timelock
+    BridgeLimits {
+        chain_id: u16,
+        outbound_limit: u64,
+        inbound_limit: u64,
+    },
+    /// Changes the treasury's liquidity pool or widens its position
+    /// limits; tightening them needs no timelock
+    LiquidityLimits {
+        whirlpool: Pubkey,
+        min_tick_lower: i32,
+        max_tick_upper: i32,
+        max_liquidity: u128,
+    },
+    /// Zero prices from spot
+    TwapWindow(u64),
+    /// Zero disables the circuit breaker
+    CircuitBreaker(u64),
+    KeeperConfig {
+        keeper_reward: u64,
+        crank_interval_secs: u64,
+    },
+    FeeConfig {
+        fee_bps: u64,
+        fee_destination: Pubkey,
+    },
+    StakingConfig {
+        unstake_cooldown_secs: u64,
+        early_exit_penalty_bps: u64,
+        burn_early_exit_penalty: bool,
+    },
+    /// Existing stake keeps its weight until its next stake, unstake, or
+    /// claim
+    LockTiers([LockTier; LOCK_TIERS]),
+    /// Share of protocol fees paid to referrers
+    ReferralBps(u64),
+    /// How long a verification receipt stays valid
+    ReceiptValidity(u64),
+    /// Balance thresholds for each access tier
+    AccessTiers([u64; ACCESS_TIERS]),
+    /// How long a wallet keeps access after falling below the requirement
+    GracePeriod(u64),
+    /// Share of protocol fees set aside in an existing insurance fund
+    InsuranceShare(u64),
+}
+
+const fn max_len(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+impl ParameterChange {
+    /// Serialized size of the largest variant
+    pub const MAX_LEN: usize = 1 + max_len(
+        UpdateParametersArgs::LEN,
+        max_len(
+            4 + OracleFeed::LEN * MAX_MEDIAN_FEEDS + 1, // MedianFeeds
+            LockTier::LEN * LOCK_TIERS,
+        ),
+    );
+
+    /// Whether applying the change needs the new feed account for its
+    /// owner check
+    pub fn price_feed(&self) -> Option<(OracleSource, Pubkey)> {
+        match self {
+            ParameterChange::PriceFeed { source, price_feed } => Some((*source, *price_feed)),
+            ParameterChange::FallbackPriceFeed {
+                source,
+                price_feed: Some(price_feed),
+            } => Some((*source, *price_feed)),
+            _ => None,
+        }
+    }
+}
+
+/// A queued change, executable by anyone once `eta` has passed. Keeping it
+/// in its own account makes upcoming changes visible on-chain in advance.
+#[account]
+pub struct PendingChange {
+    pub id: u64,
+    /// Receives the rent back when the change is executed or cancelled
+    pub proposer: Pubkey,
+    pub queued_at: i64,
+    pub eta: i64,
+    pub change: ParameterChange,
+}
+
+impl PendingChange {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + ParameterChange::MAX_LEN;
+}
@@ -0,0 +1,310 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::transfer_fee::{instruction as fee_instruction, TransferFeeConfig},
+    extension::default_account_state::instruction as default_state_instruction,
+    extension::interest_bearing_mint::{instruction as interest_instruction, InterestBearingConfig},
+    extension::transfer_hook::instruction as hook_instruction,
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::AccountState,
+};
+
+use crate::confidential::{initialize_confidential_mint, ConfidentialTransferArgs};
+
+/// How `begin_mint_migration` sets up the token-2022 mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token2022MintArgs {
+    /// Share of every transfer withheld for the treasury
+    pub transfer_fee_bps: u16,
+    pub max_transfer_fee: u64,
+    /// Program run on every transfer, normally `aistm7_transfer_hook`
+    pub transfer_hook_program: Option<Pubkey>,
+    /// Compliance mode: token accounts are created frozen until
+    /// `thaw_on_verification` finds their owner verified
+    pub frozen_by_default: bool,
+    /// Yearly rate, in basis points, at which holders' UI amounts accrue
+    pub interest_rate_bps: Option<i16>,
+    pub confidential_transfers: Option<ConfidentialTransferArgs>,
+    /// Make the `[b"clawback_authority"]` PDA the permanent delegate, so
+    /// `execute_clawback` can recover tokens from any account
+    pub clawback: bool,
+}
+
+/// Create the token-2022 mint at `[b"token_2022_mint"]` with the extensions
+/// `args` asks for. `authority` mints, sets the fee and interest rate, can
+/// change the hook, and in compliance mode freezes and thaws; `treasury`
+/// withdraws what is withheld.
+#[allow(clippy::too_many_arguments)]
+pub fn create_fee_mint<'info>(
+    mint: &AccountInfo<'info>,
+    mint_bump: u8,
+    payer: &AccountInfo<'info>,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    decimals: u8,
+    args: &Token2022MintArgs,
+    token_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+) -> Result<()> {
+    let mut extensions = vec![ExtensionType::TransferFeeConfig];
+    if args.transfer_hook_program.is_some() {
+        extensions.push(ExtensionType::TransferHook);
+    }
+    if args.frozen_by_default {
+        extensions.push(ExtensionType::DefaultAccountState);
+    }
+    if args.interest_rate_bps.is_some() {
+        extensions.push(ExtensionType::InterestBearingConfig);
+    }
+    if args.confidential_transfers.is_some() {
+        extensions.push(ExtensionType::ConfidentialTransferMint);
+    }
+    if args.clawback {
+        extensions.push(ExtensionType::PermanentDelegate);
+    }
+    let space = ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&extensions);
+    create_owned_account(
+        mint,
+        &[b"token_2022_mint", &[mint_bump]],
+        payer,
+        space,
+        token_program,
+        system,
+    )?;
+    invoke(
+        &fee_instruction::initialize_transfer_fee_config(
+            token_program.key,
+            mint.key,
+            Some(authority),
+            Some(treasury),
+            args.transfer_fee_bps,
+            args.max_transfer_fee,
+        )?,
+        &[mint.clone()],
+    )?;
+    if args.transfer_hook_program.is_some() {
+        invoke(
+            &hook_instruction::initialize(
+                token_program.key,
+                mint.key,
+                Some(*authority),
+                args.transfer_hook_program,
+            )?,
+            &[mint.clone()],
+        )?;
+    }
+    if args.frozen_by_default {
+        invoke(
+            &default_state_instruction::initialize_default_account_state(
+                token_program.key,
+                mint.key,
+                &AccountState::Frozen,
+            )?,
+            &[mint.clone()],
+        )?;
+    }
+    if let Some(rate) = args.interest_rate_bps {
+        invoke(
+            &interest_instruction::initialize(
+                token_program.key,
+                *mint.key,
+                Some(*authority),
+                rate,
+            )?,
+            &[mint.clone()],
+        )?;
+    }
+    if let Some(confidential) = &args.confidential_transfers {
+        initialize_confidential_mint(mint, authority, confidential, token_program)?;
+    }
+    if args.clawback {
+        let (clawback_authority, _) =
+            Pubkey::find_program_address(&[b"clawback_authority"], &crate::ID);
+        invoke(
+            &spl_token_2022::instruction::initialize_permanent_delegate(
+                token_program.key,
+                mint.key,
+                &clawback_authority,
+            )?,
+            &[mint.clone()],
+        )?;
+    }
+    invoke(
+        &spl_token_2022::instruction::initialize_mint2(
+            token_program.key,
+            mint.key,
+            authority,
+            args.frozen_by_default.then_some(authority),
+            decimals,
+        )?,
+        &[mint.clone()],
+    )?;
+    Ok(())
+}
+
+/// Create the treasury's `[b"treasury_token_vault", mint]` vault for a
+/// transfer fee mint, sized for the fee extension its accounts carry.
+pub fn create_fee_vault<'info>(
+    vault: &AccountInfo<'info>,
+    vault_bump: u8,
+    mint: &AccountInfo<'info>,
+    treasury: &Pubkey,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+) -> Result<()> {
+    let space = ExtensionType::get_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::TransferFeeAmount,
+    ]);
+    create_owned_account(
+        vault,
+        &[b"treasury_token_vault", mint.key.as_ref(), &[vault_bump]],
+        payer,
+        space,
+        token_program,
+        system,
+    )?;
+    invoke(
+        &spl_token_2022::instruction::initialize_account3(
+            token_program.key,
+            vault.key,
+            mint.key,
+            treasury,
+        )?,
+        &[vault.clone(), mint.clone()],
+    )?;
+    Ok(())
+}
+
+fn create_owned_account<'info>(
+    account: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    payer: &AccountInfo<'info>,
+    space: usize,
+    token_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+) -> Result<()> {
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system.clone(),
+            system_program::CreateAccount {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+            &[seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        token_program.key,
+    )
+}
+
+/// The mint's transfer fee extension: the fee schedule and what has been
+/// harvested into the mint but not yet withdrawn
+pub fn transfer_fee_config(mint: &AccountInfo) -> Result<TransferFeeConfig> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(*mint.get_extension::<TransferFeeConfig>()?)
+}
+
+/// Move the fees withheld in `sources` into the mint. Token-2022 skips
+/// accounts it cannot harvest from rather than failing.
+pub fn harvest_to_mint<'info>(
+    mint: &AccountInfo<'info>,
+    sources: &[AccountInfo<'info>],
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let source_keys: Vec<&Pubkey> = sources.iter().map(|source| source.key).collect();
+    let mut accounts = vec![mint.clone()];
+    accounts.extend_from_slice(sources);
+    invoke(
+        &fee_instruction::harvest_withheld_tokens_to_mint(
+            token_program.key,
+            mint.key,
+            &source_keys,
+        )?,
+        &accounts,
+    )?;
+    Ok(())
+}
+
+/// Withdraw everything withheld in the mint to `destination`, signed by
+/// the treasury as the mint's withdraw authority
+pub fn withdraw_from_mint<'info>(
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    treasury_bump: u8,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    invoke_signed(
+        &fee_instruction::withdraw_withheld_tokens_from_mint(
+            token_program.key,
+            mint.key,
+            destination.key,
+            treasury.key,
+            &[],
+        )?,
+        &[mint.clone(), destination.clone(), treasury.clone()],
+        &[&[b"treasury", &[treasury_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Schedule a new transfer fee, signed by the mint authority, which is
+/// also the fee authority. Token-2022 applies it two epochs later.
+pub fn set_fee<'info>(
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    authority_bump: u8,
+    transfer_fee_bps: u16,
+    max_transfer_fee: u64,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    invoke_signed(
+        &fee_instruction::set_transfer_fee(
+            token_program.key,
+            mint.key,
+            authority.key,
+            &[],
+            transfer_fee_bps,
+            max_transfer_fee,
+        )?,
+        &[mint.clone(), authority.clone()],
+        &[&[b"mint_authority", &[authority_bump]]],
+    )?;
+    Ok(())
+}
+
+/// The mint's current interest rate in basis points per year
+pub fn interest_rate(mint: &AccountInfo) -> Result<i16> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(i16::from(mint.get_extension::<InterestBearingConfig>()?.current_rate))
+}
+
+/// Change the rate holders accrue at from now on, signed by the mint
+/// authority, which is also the rate authority. What has accrued so far is
+/// kept.
+pub fn update_interest_rate<'info>(
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    authority_bump: u8,
+    rate_bps: i16,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    invoke_signed(
+        &interest_instruction::update_rate(
+            token_program.key,
+            mint.key,
+            authority.key,
+            &[],
+            rate_bps,
+        )?,
+        &[mint.clone(), authority.clone()],
+        &[&[b"mint_authority", &[authority_bump]]],
+    )?;
+    Ok(())
+}
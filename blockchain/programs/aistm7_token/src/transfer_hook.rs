@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Owners the transfer hook's max-wallet rule can exempt
+pub const MAX_HOOK_EXEMPTIONS: usize = 8;
+
+/// Rules the `aistm7_transfer_hook` program applies to every transfer of
+/// the token-2022 mint, kept at `[b"transfer_hook_config"]`. The hook reads
+/// this and the senders' access overrides straight from this program, so
+/// a wallet denied access cannot move the token either.
+#[account]
+pub struct TransferHookConfig {
+    /// Largest share of the supply a wallet may hold after receiving a
+    /// transfer; zero disables the rule
+    pub max_wallet_bps: u64,
+    /// Owners the rule does not apply to: pools, vaults, the treasury
+    pub exempt: [Pubkey; MAX_HOOK_EXEMPTIONS],
+}
+
+impl TransferHookConfig {
+    pub const LEN: usize = 8 + 32 * MAX_HOOK_EXEMPTIONS;
+
+    pub fn is_exempt(&self, owner: &Pubkey) -> bool {
+        *owner != Pubkey::default() && self.exempt.contains(owner)
+    }
+
+    pub fn set_exemption(&mut self, owner: Pubkey, exempt: bool) -> Result<()> {
+        require!(owner != Pubkey::default(), ErrorCode::InvalidParameter);
+        let existing = self.exempt.iter_mut().find(|entry| **entry == owner);
+        match (existing, exempt) {
+            (Some(slot), false) => *slot = Pubkey::default(),
+            (None, true) => {
+                let slot = self
+                    .exempt
+                    .iter_mut()
+                    .find(|entry| **entry == Pubkey::default())
+                    .ok_or(ErrorCode::HookExemptionsFull)?;
+                *slot = owner;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, TreasuryLimitUpdated};
+
+/// Assets the treasury can hold withdrawal limits for, SOL included
+pub const MAX_TREASURY_ASSETS: usize = 8;
+
+/// Asset key standing for the SOL held by the treasury account itself
+pub const NATIVE_SOL: Pubkey = Pubkey::new_from_array([0; 32]);
+
+/// Per-epoch withdrawal allowance for one asset
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreasuryLimit {
+    /// Token mint, or `NATIVE_SOL`
+    pub asset: Pubkey,
+    pub per_epoch_limit: u64,
+    /// Withdrawn since `Treasury::epoch_start`
+    pub withdrawn: u64,
+}
+
+impl TreasuryLimit {
+    pub const LEN: usize = 32 + 8 + 8;
+
+    /// Unused slot
+    pub const EMPTY: TreasuryLimit = TreasuryLimit {
+        asset: NATIVE_SOL,
+        per_epoch_limit: 0,
+        withdrawn: 0,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.per_epoch_limit == 0 && self.withdrawn == 0
+    }
+}
+
+/// Protocol treasury. Holds SOL as lamports on this account and SPL tokens
+/// in `[b"treasury_token_vault", mint]` vaults it is the authority of. An
+/// asset without a limit cannot be withdrawn at all.
+#[account]
+pub struct Treasury {
+    pub epoch_secs: u64,
+    pub epoch_start: i64,
+    pub limits: [TreasuryLimit; MAX_TREASURY_ASSETS],
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + 8 + TreasuryLimit::LEN * MAX_TREASURY_ASSETS;
+
+    pub fn set_limit(&mut self, asset: Pubkey, per_epoch_limit: u64, now: i64) -> Result<()> {
+        let old_limit = self.limit(&asset);
+        if let Some(limit) = self
+            .limits
+            .iter_mut()
+            .find(|limit| !limit.is_empty() && limit.asset == asset)
+        {
+            limit.per_epoch_limit = per_epoch_limit;
+        } else if per_epoch_limit > 0 {
+            let slot = self
+                .limits
+                .iter_mut()
+                .find(|limit| limit.is_empty())
+                .ok_or(ErrorCode::TreasuryAssetsFull)?;
+            *slot = TreasuryLimit {
+                asset,
+                per_epoch_limit,
+                withdrawn: 0,
+            };
+        }
+
+        emit!(TreasuryLimitUpdated {
+            asset,
+            old_limit,
+            new_limit: per_epoch_limit,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn limit(&self, asset: &Pubkey) -> u64 {
+        self.limits
+            .iter()
+            .find(|limit| !limit.is_empty() && limit.asset == *asset)
+            .map_or(0, |limit| limit.per_epoch_limit)
+    }
+
+    /// Count `amount` of `asset` against this epoch's allowance, starting a
+    /// new epoch first if the current one has ended. Returns the amount
+    /// withdrawn so far this epoch.
+    pub fn record_withdrawal(&mut self, asset: &Pubkey, amount: u64, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.epoch_start);
+        if elapsed >= self.epoch_secs as i64 {
+            self.epoch_start = now - elapsed % self.epoch_secs as i64;
+            for limit in self.limits.iter_mut() {
+                limit.withdrawn = 0;
+            }
+        }
+
+        let limit = self
+            .limits
+            .iter_mut()
+            .find(|limit| !limit.is_empty() && limit.asset == *asset)
+            .ok_or(ErrorCode::TreasuryLimitExceeded)?;
+        let withdrawn = limit
+            .withdrawn
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(withdrawn <= limit.per_epoch_limit, ErrorCode::TreasuryLimitExceeded);
+        limit.withdrawn = withdrawn;
+        Ok(withdrawn)
+    }
+}
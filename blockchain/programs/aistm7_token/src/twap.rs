@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Number of observations kept in the accumulator ring buffer
+pub const PRICE_OBSERVATIONS: usize = 32;
+
+/// Longest TWAP window the buffer still covers when cranks record a price
+/// every `crank_interval_secs`
+pub fn max_twap_window_secs(crank_interval_secs: u64) -> u64 {
+    (PRICE_OBSERVATIONS as u64 - 1)
+        .saturating_mul(crank_interval_secs)
+        .min(i64::MAX as u64)
+}
+
+/// Cumulative price at a point in time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct PriceObservation {
+    pub timestamp: i64,
+    pub cumulative_price: u128,
+}
+
+impl PriceObservation {
+    pub const LEN: usize = 8 + 16;
+}
+
+/// Running sum of `price * seconds`, sampled each time the requirement is
+/// cranked, so averages over any window covered by the buffer can be taken.
+#[account]
+pub struct PriceAccumulator {
+    pub last_price: u64,
+    pub last_timestamp: i64,
+    pub cumulative_price: u128,
+    /// Index of the most recent observation
+    pub head: u8,
+    pub count: u8,
+    pub observations: [PriceObservation; PRICE_OBSERVATIONS],
+}
+
+impl PriceAccumulator {
+    pub const LEN: usize = 8 + 8 + 16 + 1 + 1 + PriceObservation::LEN * PRICE_OBSERVATIONS;
+
+    /// Record `price` (internal precision) as the price from `now` onward.
+    pub fn record(&mut self, price: u64, now: i64) -> Result<()> {
+        if self.count > 0 {
+            let elapsed = now.saturating_sub(self.last_timestamp);
+            if elapsed <= 0 {
+                // Several cranks in the same second only move the latest price
+                self.last_price = price;
+                return Ok(());
+            }
+            self.cumulative_price = (self.last_price as u128)
+                .checked_mul(elapsed as u128)
+                .and_then(|weighted| self.cumulative_price.checked_add(weighted))
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.head = ((self.head as usize + 1) % PRICE_OBSERVATIONS) as u8;
+        }
+
+        self.observations[self.head as usize] = PriceObservation {
+            timestamp: now,
+            cumulative_price: self.cumulative_price,
+        };
+        self.count = std::cmp::min(self.count as usize + 1, PRICE_OBSERVATIONS) as u8;
+        self.last_price = price;
+        self.last_timestamp = now;
+
+        Ok(())
+    }
+
+    /// Time-weighted average over the last `window_secs`, or over the whole
+    /// buffer if it does not reach back that far. `None` until some time
+    /// has been observed.
+    pub fn twap(&self, window_secs: u64, now: i64) -> Option<u64> {
+        let start = now.saturating_sub(window_secs as i64);
+
+        // Walk back from the newest observation to the first one at or
+        // before the window start, stopping at the oldest we still hold
+        let mut oldest = &self.observations[self.head as usize];
+        for i in 1..self.count as usize {
+            let index = (self.head as usize + PRICE_OBSERVATIONS - i) % PRICE_OBSERVATIONS;
+            oldest = &self.observations[index];
+            if oldest.timestamp <= start {
+                break;
+            }
+        }
+
+        let span = self.last_timestamp.checked_sub(oldest.timestamp)?;
+        if span <= 0 {
+            return None;
+        }
+        let average = self
+            .cumulative_price
+            .checked_sub(oldest.cumulative_price)?
+            / span as u128;
+
+        u64::try_from(average).ok().filter(|price| *price > 0)
+    }
+}
+
+/// Feed a fresh spot price into the accumulator (when one is configured)
+/// and return the price the requirement should be computed from.
+pub fn observe_price(
+    accumulator: Option<&mut PriceAccumulator>,
+    twap_window_secs: u64,
+    spot_price: u64,
+    now: i64,
+) -> Result<u64> {
+    let Some(accumulator) = accumulator else {
+        require!(twap_window_secs == 0, ErrorCode::PriceAccumulatorRequired);
+        return Ok(spot_price);
+    };
+
+    accumulator.record(spot_price, now)?;
+
+    if twap_window_secs == 0 {
+        return Ok(spot_price);
+    }
+    Ok(accumulator.twap(twap_window_secs, now).unwrap_or(spot_price))
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::{ErrorCode, TokenState};
+
+/// Metered use of one platform feature by one wallet, kept at
+/// `[b"usage", owner, feature_id]`. Paid for by `consume_access` instead of
+/// holding the full requirement.
+#[account]
+pub struct UsageCounter {
+    pub owner: Pubkey,
+    pub feature_id: u32,
+    pub uses: u64,
+    /// Tokens spent on the feature so far
+    pub consumed: u64,
+    pub last_used_at: i64,
+}
+
+impl UsageCounter {
+    pub const LEN: usize = 32 + 4 + 8 + 8 + 8;
+
+    pub fn record(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.uses = self.uses.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.consumed = self
+            .consumed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.last_used_at = now;
+        Ok(())
+    }
+}
+
+/// Burn `amount` from `source`, or move it to the treasury vault when
+/// payments are not burned, with `authority` signing as the account's owner
+/// or delegate. Returns whether the payment was burned.
+#[allow(clippy::too_many_arguments)]
+pub fn pay_for_access<'info>(
+    state: &mut TokenState,
+    mint: &Account<'info, Mint>,
+    source: &Account<'info, TokenAccount>,
+    treasury_vault: Option<&Account<'info, TokenAccount>>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<bool> {
+    if state.burn_consumed_access {
+        token::burn(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                token::Burn {
+                    mint: mint.to_account_info(),
+                    from: source.to_account_info(),
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+        state.total_burned = state
+            .total_burned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        return Ok(true);
+    }
+
+    let treasury_vault = treasury_vault.ok_or(ErrorCode::TreasuryRequired)?;
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: source.to_account_info(),
+                to: treasury_vault.to_account_info(),
+                authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+    Ok(false)
+}
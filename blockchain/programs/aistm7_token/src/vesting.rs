@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Token allocation released linearly from `start_time` over
+/// `duration_secs`, with nothing claimable before the cliff. Tokens are
+/// held in the shared `[b"vesting_escrow"]` vault until claimed.
+#[account]
+pub struct VestingSchedule {
+    pub beneficiary: Pubkey,
+    pub start_time: i64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub revocable: bool,
+    /// Vesting stops at this time once revoked; zero while active
+    pub revoked_at: i64,
+}
+
+impl VestingSchedule {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.duration_secs > 0
+                && self.cliff_secs <= self.duration_secs
+                && self.total_amount > 0
+                && i64::try_from(self.duration_secs)
+                    .ok()
+                    .and_then(|duration| self.start_time.checked_add(duration))
+                    .is_some(),
+            ErrorCode::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at != 0
+    }
+
+    /// Tokens vested at `now`, counting only up to the revocation
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let now = if self.is_revoked() {
+            std::cmp::min(now, self.revoked_at)
+        } else {
+            now
+        };
+        let elapsed = now.saturating_sub(self.start_time);
+        if elapsed < self.cliff_secs as i64 {
+            return 0;
+        }
+        if elapsed as u64 >= self.duration_secs {
+            return self.total_amount;
+        }
+        (self.total_amount as u128 * elapsed as u128 / self.duration_secs as u128) as u64
+    }
+
+    /// Vested tokens not yet claimed
+    pub fn claimable(&self, now: i64) -> u64 {
+        self.vested_amount(now).saturating_sub(self.claimed_amount)
+    }
+}
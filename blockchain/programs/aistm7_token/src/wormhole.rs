@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+
+use crate::receipt::AccessStatus;
+use crate::ErrorCode;
+
+/// Wormhole core bridge on the target cluster
+#[cfg(feature = "devnet")]
+pub const WORMHOLE_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+#[cfg(not(feature = "devnet"))]
+pub const WORMHOLE_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+
+/// First byte of every access attestation payload, bumped if the layout
+/// ever changes
+pub const ACCESS_ATTESTATION_VERSION: u8 = 1;
+
+/// Finalized, so guardians only sign once the slot cannot be rolled back
+const CONSISTENCY_FINALIZED: u8 = 1;
+
+/// The core bridge's `PostMessage` instruction
+const POST_MESSAGE: u8 = 1;
+
+/// Offset of the message fee in the core bridge's `[b"Bridge"]` account
+const BRIDGE_FEE_OFFSET: usize = 16;
+
+/// "`wallet` had access at `tier` at `slot`", posted through Wormhole so a
+/// verifier contract on `target_chain` can grant `target_address` access.
+/// The wallet signs the post, so only it can choose where access goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccessAttestation {
+    pub wallet: Pubkey,
+    pub target_chain: u16,
+    /// Left-padded to 32 bytes, as Wormhole carries EVM addresses
+    pub target_address: [u8; 32],
+    pub tier: u8,
+    pub requirement: u64,
+    pub slot: u64,
+    pub attested_at: i64,
+    pub expires_at: i64,
+}
+
+impl AccessAttestation {
+    pub fn new(
+        status: &AccessStatus,
+        target_chain: u16,
+        target_address: [u8; 32],
+        requirement: u64,
+        slot: u64,
+    ) -> Self {
+        Self {
+            wallet: status.wallet,
+            target_chain,
+            target_address,
+            tier: status.tier,
+            requirement,
+            slot,
+            attested_at: status.checked_at,
+            expires_at: status.expires_at,
+        }
+    }
+
+    /// Big-endian, as EVM contracts decode Wormhole payloads
+    pub fn payload(&self) -> Vec<u8> {
+        let mut payload = vec![ACCESS_ATTESTATION_VERSION];
+        payload.extend_from_slice(self.wallet.as_ref());
+        payload.extend_from_slice(&self.target_chain.to_be_bytes());
+        payload.extend_from_slice(&self.target_address);
+        payload.push(self.tier);
+        payload.extend_from_slice(&self.requirement.to_be_bytes());
+        payload.extend_from_slice(&self.slot.to_be_bytes());
+        payload.extend_from_slice(&self.attested_at.to_be_bytes());
+        payload.extend_from_slice(&self.expires_at.to_be_bytes());
+        payload
+    }
+}
+
+/// Accounts of the core bridge's `PostMessage`
+pub struct PostMessage<'a, 'info> {
+    pub bridge: &'a AccountInfo<'info>,
+    /// Fresh keypair account the bridge writes the message into
+    pub message: &'a AccountInfo<'info>,
+    pub emitter: &'a AccountInfo<'info>,
+    pub sequence: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub fee_collector: &'a AccountInfo<'info>,
+    pub clock: &'a AccountInfo<'info>,
+    pub rent: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub wormhole_program: &'a AccountInfo<'info>,
+}
+
+/// The sequence number the next message from the emitter will get
+pub fn next_sequence(sequence: &AccountInfo) -> Result<u64> {
+    if sequence.data_is_empty() {
+        return Ok(0);
+    }
+    let data = sequence.try_borrow_data()?;
+    let bytes = data.get(..8).ok_or(ErrorCode::InvalidParameter)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Pay the bridge's message fee and post `payload` as the program's
+/// `[b"emitter"]` PDA
+pub fn post_message(
+    accounts: &PostMessage,
+    emitter_bump: u8,
+    nonce: u32,
+    payload: Vec<u8>,
+) -> Result<()> {
+    let fee = {
+        let data = accounts.bridge.try_borrow_data()?;
+        let bytes = data
+            .get(BRIDGE_FEE_OFFSET..BRIDGE_FEE_OFFSET + 8)
+            .ok_or(ErrorCode::InvalidParameter)?;
+        u64::from_le_bytes(bytes.try_into().unwrap())
+    };
+    if fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.clone(),
+                system_program::Transfer {
+                    from: accounts.payer.clone(),
+                    to: accounts.fee_collector.clone(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    let mut data = vec![POST_MESSAGE];
+    (nonce, payload, CONSISTENCY_FINALIZED).serialize(&mut data)?;
+    invoke_signed(
+        &Instruction {
+            program_id: WORMHOLE_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*accounts.bridge.key, false),
+                AccountMeta::new(*accounts.message.key, true),
+                AccountMeta::new_readonly(*accounts.emitter.key, true),
+                AccountMeta::new(*accounts.sequence.key, false),
+                AccountMeta::new(*accounts.payer.key, true),
+                AccountMeta::new(*accounts.fee_collector.key, false),
+                AccountMeta::new_readonly(*accounts.clock.key, false),
+                AccountMeta::new_readonly(*accounts.rent.key, false),
+                AccountMeta::new_readonly(*accounts.system_program.key, false),
+            ],
+            data,
+        },
+        &[
+            accounts.bridge.clone(),
+            accounts.message.clone(),
+            accounts.emitter.clone(),
+            accounts.sequence.clone(),
+            accounts.payer.clone(),
+            accounts.fee_collector.clone(),
+            accounts.clock.clone(),
+            accounts.rent.clone(),
+            accounts.system_program.clone(),
+            accounts.wormhole_program.clone(),
+        ],
+        &[&[b"emitter", &[emitter_bump]]],
+    )?;
+    Ok(())
+}
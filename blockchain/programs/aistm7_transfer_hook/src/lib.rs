@@ -0,0 +1,287 @@
+use aistm7_interface::state::{
+    AccessOverride, AccessOverrideKind, ProgramAccount, TransferHookConfig,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::TransferFeeConfig, transfer_hook::TransferHookAccount,
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+declare_id!("HzJgzk9dEHRwemwnii3Z2ohDQpGSmQTuPuKBhmcrcjS5");
+
+/// Token-2022 transfer hook for the AISTM7 token-2022 mint. On every
+/// transfer it refuses senders the token program has denied access, caps
+/// what a wallet may end up holding, and keeps a count of holders. Both
+/// rules step aside for the token program's clawbacks, which move tokens
+/// as the mint's permanent delegate. The
+/// rules live in the token program (`[b"transfer_hook_config"]` and the
+/// `[b"access_override", wallet]` denylist), which this program only reads.
+#[program]
+pub mod aistm7_transfer_hook {
+    use super::*;
+
+    /// Record the accounts token-2022 adds to every transfer of `mint` and
+    /// start its holder count. Anyone can call this once per mint; only
+    /// mints issued by the token program are accepted.
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas()?,
+        )?;
+
+        let stats = &mut ctx.accounts.holder_stats;
+        stats.mint = ctx.accounts.mint.key();
+        stats.holders = 0;
+        stats.transfers = 0;
+        stats.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Run by token-2022 after it has moved `amount` out of `source`, so
+    /// both token accounts already show their new balances.
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        require!(
+            is_transferring(&accounts.source.to_account_info())?,
+            TransferHookError::NotTransferring
+        );
+
+        let clawback = accounts.authority.key() == aistm7_interface::pda::clawback_authority().0;
+        if !clawback && !accounts.source_override.data_is_empty() {
+            let source_override =
+                AccessOverride::from_account_data(&accounts.source_override.try_borrow_data()?)?;
+            require!(
+                source_override.kind != AccessOverrideKind::Deny,
+                TransferHookError::SenderDenied
+            );
+        }
+
+        if !clawback && !accounts.config.data_is_empty() {
+            let config =
+                TransferHookConfig::from_account_data(&accounts.config.try_borrow_data()?)?;
+            if config.max_wallet_bps > 0 && !config.is_exempt(&accounts.destination.owner) {
+                let max_wallet = (accounts.mint.supply as u128 * config.max_wallet_bps as u128
+                    / BPS_DENOMINATOR as u128) as u64;
+                require!(
+                    accounts.destination.amount <= max_wallet,
+                    TransferHookError::MaxWalletExceeded
+                );
+            }
+        }
+
+        let fee = transfer_fee(&accounts.mint.to_account_info(), amount)?;
+        let received = amount.saturating_sub(fee);
+        let moved = accounts.source.key() != accounts.destination.key();
+        let opened = moved && received > 0 && accounts.destination.amount == received;
+        let emptied = moved && amount > 0 && accounts.source.amount == 0;
+
+        let stats = &mut ctx.accounts.holder_stats;
+        if opened {
+            stats.holders = stats.holders.saturating_add(1);
+        }
+        if emptied {
+            stats.holders = stats.holders.saturating_sub(1);
+        }
+        stats.transfers = stats.transfers.saturating_add(1);
+        stats.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Token-2022 calls hooks with the transfer hook interface's
+    /// discriminator rather than Anchor's, so route `Execute` by hand.
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                __private::__global::transfer_hook(program_id, accounts, &amount.to_le_bytes())
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+}
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Accounts appended to `Execute` after source, mint, destination,
+/// authority, and the meta list itself (indices 0 to 4)
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![
+        // 5: the token program the rules are read from
+        ExtraAccountMeta::new_with_pubkey(&aistm7_interface::ID, false, false)?,
+        // 6: its transfer hook config
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[Seed::Literal {
+                bytes: b"transfer_hook_config".to_vec(),
+            }],
+            false,
+            false,
+        )?,
+        // 7: the access override of the source account's owner
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal {
+                    bytes: b"access_override".to_vec(),
+                },
+                Seed::AccountData {
+                    account_index: 0,
+                    data_index: 32,
+                    length: 32,
+                },
+            ],
+            false,
+            false,
+        )?,
+        // 8: this program's holder stats for the mint
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"holder_stats".to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )?,
+    ])
+}
+
+/// Whether token-2022 is mid-transfer out of `source`, so the hook cannot
+/// be invoked directly to skew the counters
+fn is_transferring(source: &AccountInfo) -> Result<bool> {
+    let data = source.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(bool::from(account.get_extension::<TransferHookAccount>()?.transferring))
+}
+
+/// Fee withheld from a transfer of `amount`, which never reaches the
+/// destination
+fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    Ok(fee_config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Written by the instruction in the transfer hook interface's
+    /// layout
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = mint.mint_authority
+            == COption::Some(aistm7_interface::pda::mint_authority().0)
+            @ TransferHookError::InvalidMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HolderStats::LEN,
+        seeds = [b"holder_stats", mint.key().as_ref()],
+        bump
+    )]
+    pub holder_stats: Account<'info, HolderStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: The source's owner or delegate, already checked by token-2022
+    pub authority: UncheckedAccount<'info>,
+
+    /// CHECK: Only read by token-2022
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: Address checked; only used to derive the accounts below
+    #[account(address = aistm7_interface::ID)]
+    pub aistm7_program: UncheckedAccount<'info>,
+
+    /// CHECK: Read with the interface layout; empty until the token program
+    /// initializes it, which leaves the max-wallet rule off
+    #[account(seeds = [b"transfer_hook_config"], bump, seeds::program = aistm7_interface::ID)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Read with the interface layout; empty unless an override was
+    /// set for the source's owner
+    #[account(
+        seeds = [b"access_override", source.owner.as_ref()],
+        bump,
+        seeds::program = aistm7_interface::ID
+    )]
+    pub source_override: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"holder_stats", mint.key().as_ref()], bump)]
+    pub holder_stats: Account<'info, HolderStats>,
+}
+
+/// Running totals for a mint, kept at `[b"holder_stats", mint]`. Only
+/// transfers are seen, not minting or burning, so accounts funded by
+/// `migrate_tokens` count once a transfer first empties or refills them.
+#[account]
+pub struct HolderStats {
+    pub mint: Pubkey,
+    /// Token accounts transfers have opened, less those they have emptied
+    pub holders: u64,
+    pub transfers: u64,
+    pub updated_at: i64,
+}
+
+impl HolderStats {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+}
+
+#[error_code]
+pub enum TransferHookError {
+    #[msg("The hook only runs as part of a token-2022 transfer")]
+    NotTransferring,
+    #[msg("The sender is denied by the token program")]
+    SenderDenied,
+    #[msg("The transfer would leave the recipient above the max-wallet cap")]
+    MaxWalletExceeded,
+    #[msg("The mint is not issued by the token program")]
+    InvalidMint,
+}
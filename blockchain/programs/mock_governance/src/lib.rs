@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+
+declare_id!("7wChs526eRSbcGVGRUybRoeLnPmbphSVMaAUkgDeFXgV");
+
+/// Largest number of instructions a proposal can carry
+pub const MAX_PROPOSAL_INSTRUCTIONS: usize = 4;
+
+/// A token-voted governance whose native treasury PDA signs the
+/// instructions of passed proposals through CPI, the way an SPL Governance
+/// (Realms) treasury does. Voting is simplified: a voter's weight is their
+/// current balance of the governing mint rather than a deposit, and a
+/// proposal passes once the yes votes reach the threshold. Used by the
+/// integration tests to administer the token program by token holder vote.
+#[program]
+pub mod mock_governance {
+    use super::*;
+
+    pub fn create_governance(
+        ctx: Context<CreateGovernance>,
+        governing_token_mint: Pubkey,
+        vote_threshold: u64,
+    ) -> Result<()> {
+        require!(vote_threshold > 0, GovernanceError::InvalidThreshold);
+
+        let governance = &mut ctx.accounts.governance;
+        governance.governing_token_mint = governing_token_mint;
+        governance.vote_threshold = vote_threshold;
+        governance.treasury_bump = *ctx.bumps.get("native_treasury").unwrap();
+
+        Ok(())
+    }
+
+    /// Record `instructions` to run, in order, once the proposal passes
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        instructions: Vec<ProposalInstruction>,
+    ) -> Result<()> {
+        require!(
+            !instructions.is_empty() && instructions.len() <= MAX_PROPOSAL_INSTRUCTIONS,
+            GovernanceError::InvalidInstructions
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.governance = ctx.accounts.governance.key();
+        proposal.instructions = instructions;
+        proposal.yes_votes = 0;
+        proposal.executed = false;
+
+        Ok(())
+    }
+
+    /// Vote yes with the voter's balance of the governing mint. The vote
+    /// record makes a second vote from the same wallet fail.
+    pub fn cast_vote(ctx: Context<CastVote>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, GovernanceError::AlreadyExecuted);
+
+        let weight = ctx.accounts.voter_token_account.amount;
+        proposal.yes_votes = proposal.yes_votes.saturating_add(weight);
+        ctx.accounts.vote_record.weight = weight;
+
+        Ok(())
+    }
+
+    /// Invoke a passed proposal's instructions with the native treasury as
+    /// a signer. The remaining accounts are every account and program the
+    /// instructions use, as with Realms' `ExecuteTransaction`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let governance = &ctx.accounts.governance;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, GovernanceError::AlreadyExecuted);
+        require!(
+            proposal.yes_votes >= governance.vote_threshold,
+            GovernanceError::VoteThresholdNotMet
+        );
+
+        let treasury = ctx.accounts.native_treasury.key();
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.native_treasury.to_account_info());
+
+        let governance_key = governance.key();
+        for stored in &proposal.instructions {
+            let ix = Instruction {
+                program_id: stored.program_id,
+                accounts: stored
+                    .accounts
+                    .iter()
+                    .map(|account| AccountMeta {
+                        pubkey: account.pubkey,
+                        is_signer: account.is_signer || account.pubkey == treasury,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: stored.data.clone(),
+            };
+            invoke_signed(
+                &ix,
+                &account_infos,
+                &[&[
+                    b"native-treasury",
+                    governance_key.as_ref(),
+                    &[governance.treasury_bump],
+                ]],
+            )?;
+        }
+        proposal.executed = true;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateGovernance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(init, payer = payer, space = 8 + Governance::LEN)]
+    pub governance: Account<'info, Governance>,
+
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"native-treasury", governance.key().as_ref()], bump)]
+    pub native_treasury: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(instructions: Vec<ProposalInstruction>)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub governance: Account<'info, Governance>,
+
+    #[account(init, payer = proposer, space = 8 + Proposal::space(&instructions))]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        constraint = voter_token_account.mint == governance.governing_token_mint
+            @ GovernanceError::WrongGoverningMint,
+        constraint = voter_token_account.owner == voter.key() @ GovernanceError::NotTheOwner,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Signing PDA only; holds no data
+    #[account(
+        mut,
+        seeds = [b"native-treasury", governance.key().as_ref()],
+        bump = governance.treasury_bump
+    )]
+    pub native_treasury: UncheckedAccount<'info>,
+}
+
+#[account]
+pub struct Governance {
+    pub governing_token_mint: Pubkey,
+    /// Yes votes, in raw units of the governing mint, a proposal needs
+    pub vote_threshold: u64,
+    pub treasury_bump: u8,
+}
+
+impl Governance {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+#[account]
+pub struct Proposal {
+    pub governance: Pubkey,
+    pub instructions: Vec<ProposalInstruction>,
+    pub yes_votes: u64,
+    pub executed: bool,
+}
+
+impl Proposal {
+    pub fn space(instructions: &[ProposalInstruction]) -> usize {
+        32 + 4 + instructions.iter().map(ProposalInstruction::space).sum::<usize>() + 8 + 1
+    }
+}
+
+#[account]
+pub struct VoteRecord {
+    pub weight: u64,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 8;
+}
+
+/// An instruction as a proposal stores it, in SPL Governance's layout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccount>,
+    pub data: Vec<u8>,
+}
+
+impl ProposalInstruction {
+    fn space(&self) -> usize {
+        32 + 4 + self.accounts.len() * ProposalAccount::LEN + 4 + self.data.len()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccount {
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+#[error_code]
+pub enum GovernanceError {
+    #[msg("The vote threshold must be above zero")]
+    InvalidThreshold,
+    #[msg("A proposal carries between one and four instructions")]
+    InvalidInstructions,
+    #[msg("The token account does not hold the governing mint")]
+    WrongGoverningMint,
+    #[msg("Only the token account's owner can vote with it")]
+    NotTheOwner,
+    #[msg("The proposal has not reached the vote threshold")]
+    VoteThresholdNotMet,
+    #[msg("The proposal has already been executed")]
+    AlreadyExecuted,
+}
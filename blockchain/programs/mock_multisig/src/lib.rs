@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("HAavcW2DcxdQqbbdGmDk7TjiWBpYxG6NDA9eR3YySW81");
+
+/// Largest member set a multisig can hold
+pub const MAX_MEMBERS: usize = 10;
+
+/// An M-of-N multisig whose vault PDA signs approved instructions through
+/// CPI, the way a Squads vault does. Used by the integration tests to
+/// administer the token program without a raw keypair.
+#[program]
+pub mod mock_multisig {
+    use super::*;
+
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_MEMBERS,
+            MultisigError::InvalidMembers
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= members.len(),
+            MultisigError::InvalidThreshold
+        );
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.members = members;
+        multisig.threshold = threshold;
+        multisig.vault_bump = *ctx.bumps.get("vault").unwrap();
+
+        Ok(())
+    }
+
+    /// Invoke `data` on `target_program` with the vault as a signer. The
+    /// first `approver_count` remaining accounts are approving members; the
+    /// rest are the accounts of the target instruction.
+    pub fn execute(ctx: Context<Execute>, data: Vec<u8>, approver_count: u8) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        require!(
+            approver_count as usize <= ctx.remaining_accounts.len(),
+            MultisigError::ThresholdNotMet
+        );
+        let (approvers, accounts) = ctx.remaining_accounts.split_at(approver_count as usize);
+
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(
+                approver.is_signer && multisig.members.contains(approver.key),
+                MultisigError::NotAMember
+            );
+            require!(
+                !approvers[..i].iter().any(|other| other.key == approver.key),
+                MultisigError::DuplicateApproval
+            );
+        }
+        require!(
+            approvers.len() >= multisig.threshold as usize,
+            MultisigError::ThresholdNotMet
+        );
+
+        let vault = ctx.accounts.vault.key();
+        let ix = Instruction {
+            program_id: ctx.accounts.target_program.key(),
+            accounts: accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: *account.key,
+                    is_signer: account.is_signer || *account.key == vault,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data,
+        };
+
+        let mut account_infos = accounts.to_vec();
+        account_infos.push(ctx.accounts.vault.to_account_info());
+        account_infos.push(ctx.accounts.target_program.to_account_info());
+
+        let multisig_key = multisig.key();
+        invoke_signed(
+            &ix,
+            &account_infos,
+            &[&[b"vault", multisig_key.as_ref(), &[multisig.vault_bump]]],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(init, payer = payer, space = 8 + Multisig::LEN)]
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: Signing PDA only; holds no data
+    #[account(seeds = [b"vault", multisig.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: Signing PDA only; holds no data
+    #[account(mut, seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: Any program; the vault signs whatever the members approve
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
+
+#[account]
+pub struct Multisig {
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub vault_bump: u8,
+}
+
+impl Multisig {
+    pub const LEN: usize = 4 + 32 * MAX_MEMBERS + 1 + 1;
+}
+
+#[error_code]
+pub enum MultisigError {
+    #[msg("Member list is empty or too long")]
+    InvalidMembers,
+    #[msg("Threshold must be between one and the number of members")]
+    InvalidThreshold,
+    #[msg("Approver is not a signing member of this multisig")]
+    NotAMember,
+    #[msg("The same member approved more than once")]
+    DuplicateApproval,
+    #[msg("Not enough members approved the instruction")]
+    ThresholdNotMet,
+}
@@ -1,15 +1,42 @@
 import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID, createMint, getOrCreateAssociatedTokenAccount } from "@solana/spl-token";
+import {
+  Ed25519Program,
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  approve,
+  createAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddress,
+  getInterestBearingMintConfigState,
+  getMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transfer,
+  transferCheckedWithTransferHook,
+} from "@solana/spl-token";
 import { assert } from "chai";
+import { createHash } from "crypto";
+import { readFileSync } from "fs";
 import { Aistm7Token } from "../target/types/aistm7_token";
+import { Aistm7TransferHook } from "../target/types/aistm7_transfer_hook";
+import { MockGovernance } from "../target/types/mock_governance";
+import { MockMultisig } from "../target/types/mock_multisig";
 
 describe("AISTM7 Token", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
 
   const program = anchor.workspace.Aistm7Token as Program<Aistm7Token>;
+  const hookProgram = anchor.workspace.Aistm7TransferHook as Program<Aistm7TransferHook>;
   const authority = Keypair.generate();
   let mint: PublicKey;
   let authorityTokenAccount: PublicKey;
@@ -17,6 +44,131 @@ describe("AISTM7 Token", () => {
   
   const TARGET_USD_VALUE = new anchor.BN(20); // $20 USD
   const INITIAL_SUPPLY = new anchor.BN(1_000_000_000); // 1 billion tokens
+  const MAX_SUPPLY = new anchor.BN(2_000_000_000);
+  // Fee charged on transfers of the token-2022 mint, in basis points
+  const TRANSFER_FEE_BPS = 100;
+  const MAX_TRANSFER_FEE = new anchor.BN(1_000_000_000);
+  const INTEREST_RATE_BPS = 500;
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+    "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+  );
+  // Devnet Pyth SOL/USD feed cloned into the local validator (see Anchor.toml);
+  // `yarn test` builds the program with `devnet` so it accepts the feed's owner
+  const PRICE_FEED = new PublicKey("AHtgzX45WTKfkPG53L6WYhGEXwQkN1BVknET3sVsLL8J");
+  const SWITCHBOARD_FEED = new PublicKey("GvDMxPzN1sCj7L26YDK2HnMRXEQmQ2aemov8YBtPS7vR");
+  const CHAINLINK_FEED = new PublicKey("99B2bTijsU6f1GCT73HmdR7HCFFjGMBcPZY6jZ96ynrR");
+  // Sponsored SOL/USD price update account of the Pyth receiver and its feed id
+  const PULL_PRICE_UPDATE = new PublicKey("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+  const SOL_USD_FEED_ID = Array.from(
+    Buffer.from("ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d", "hex")
+  );
+
+  // Every field is optional on-chain, but the client must pass them all
+  const NO_LOCK = 255;
+
+  const NO_CHANGES = {
+    targetUsdValue: null,
+    minTokens: null,
+    maxTokens: null,
+    priceDecimals: null,
+    maxPriceAgeSecs: null,
+    maxConfidenceBps: null,
+    updateThresholdBps: null,
+    maxStepBps: null,
+    minUpdateIntervalSecs: null,
+    useEmaPrice: null,
+  };
+
+  const accessOverridePda = (wallet: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("access_override"), wallet.toBuffer()],
+      program.programId
+    )[0];
+  // Pausing and unpausing take over any guardian pause
+  const [guardianCouncilPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("guardian_council")],
+    program.programId
+  );
+
+  // Verify the wallet behind `tokenAccount` and report whether the receipt
+  // it leaves grants access
+  const verify = async (
+    tokenAccount: PublicKey,
+    accounts: Record<string, PublicKey | null> = {},
+    extraTokenAccounts: PublicKey[] = []
+  ) => {
+    const { owner } = await getAccount(provider.connection, tokenAccount);
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), owner.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .verifyBalance()
+      .accounts({
+        payer: authority.publicKey,
+        state: tokenState,
+        tokenAccount,
+        receipt,
+        accessOverride: accessOverridePda(owner),
+        systemProgram: SystemProgram.programId,
+        ...accounts,
+      })
+      .remainingAccounts(
+        extraTokenAccounts.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+      )
+      .signers([authority])
+      .rpc();
+    const { verifiedAt, expiresAt } = await program.account.verificationReceipt.fetch(receipt);
+    return expiresAt.gt(verifiedAt);
+  };
+
+  // Requirement updates from the custom feed once it drives pricing and the
+  // requirement history is enabled
+  const [customPriceFeedPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("custom_price_feed")],
+    program.programId
+  );
+  const [requirementHistoryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("requirement_history")],
+    program.programId
+  );
+  const submitCustomPrice = (price: number | string, conf = 1) =>
+    program.methods
+      .submitCustomPrice(new anchor.BN(price), new anchor.BN(conf))
+      .accounts({ authority: authority.publicKey, customPriceFeed: customPriceFeedPda })
+      .signers([authority])
+      .rpc();
+  const updateFromCustomPrice = () =>
+    program.methods
+      .updateBalanceRequirement()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: customPriceFeedPda,
+        fallbackPriceFeed: null,
+        priceAccumulator: null,
+        requirementHistory: requirementHistoryPda,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+  const setPricingParameters = (params: Record<string, any>) =>
+    program.methods
+      .updateParameters({ ...NO_CHANGES, ...params })
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+  const currentRequirement = async () =>
+    (await program.account.tokenState.fetch(tokenState)).currentRequirement.toNumber();
+
+  // Events a confirmed transaction emitted, decoded from its logs
+  const emittedEvents = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    return [...parser.parseLogs(tx.meta.logMessages)];
+  };
 
   before(async () => {
     // Airdrop SOL to authority
@@ -54,12 +206,13 @@ describe("AISTM7 Token", () => {
 
     // Initialize token program
     await program.methods
-      .initialize(INITIAL_SUPPLY, TARGET_USD_VALUE)
+      .initialize(INITIAL_SUPPLY, MAX_SUPPLY)
       .accounts({
         authority: authority.publicKey,
         state: tokenState,
         mint,
         authorityTokenAccount,
+        priceFeed: PRICE_FEED,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -71,25 +224,45 @@ describe("AISTM7 Token", () => {
     const state = await program.account.tokenState.fetch(tokenState);
     assert.ok(state.authority.equals(authority.publicKey));
     assert.ok(state.mint.equals(mint));
+    assert.ok(state.priceFeed.equals(PRICE_FEED));
+    assert.equal(state.version, 1);
     assert.equal(state.targetUsdValue.toNumber(), TARGET_USD_VALUE.toNumber());
     assert.equal(state.currentRequirement.toNumber(), 700_000);
   });
 
-  it("Updates balance requirement based on price", async () => {
-    // Mock Pyth price feed for testing
-    const mockPriceFeed = Keypair.generate();
-    await provider.connection.requestAirdrop(
-      mockPriceFeed.publicKey,
-      anchor.web3.LAMPORTS_PER_SOL
-    );
+  it("Matches the id, discriminators, and error codes of the interface crate", async () => {
+    // The interface crate builds instructions without the program, so it
+    // mirrors these by hand
+    const source = readFileSync(`${__dirname}/../crates/aistm7-interface/src/lib.rs`, "utf8");
+    const declaredId = source.match(/declare_id!\("(\w+)"\)/)[1];
+    assert.equal(program.programId.toBase58(), declaredId);
+    assert.equal(declaredId, "AiSTM7TokenProgram11111111111111111111111111");
+
+    const discriminator = (namespace: string, name: string) =>
+      createHash("sha256").update(`${namespace}:${name}`).digest().subarray(0, 8);
+    const data = program.coder.instruction.encode("verifyBalance", {});
+    assert.deepEqual(data.subarray(0, 8), discriminator("global", "verify_balance"));
+    const account = await program.coder.accounts.encode("tokenState", {
+      ...(await program.account.tokenState.fetch(tokenState)),
+    });
+    assert.deepEqual(account.subarray(0, 8), discriminator("account", "TokenState"));
+
+    const accessRequired = source.match(/ACCESS_REQUIRED_ERROR: u32 = (\d+);/)[1];
+    const idlError = program.idl.errors.find((error) => error.name === "AccessRequired");
+    assert.equal(idlError.code, Number(accessRequired));
+  });
 
+  it("Updates balance requirement based on price", async () => {
     // Update balance requirement
     await program.methods
-      .updateBalanceRequirement(mockPriceFeed.publicKey)
+      .updateBalanceRequirement()
       .accounts({
         authority: authority.publicKey,
         state: tokenState,
-        priceFeed: mockPriceFeed.publicKey,
+        priceFeed: PRICE_FEED,
+        fallbackPriceFeed: null,
+        priceAccumulator: null,
+        requirementHistory: null,
       })
       .signers([authority])
       .rpc();
@@ -99,32 +272,6602 @@ describe("AISTM7 Token", () => {
     assert.isTrue(state.lastUpdate > 0);
   });
 
-  it("Verifies token balance meets requirement", async () => {
-    // Create a test user
-    const user = Keypair.generate();
-    await provider.connection.requestAirdrop(
-      user.publicKey,
+  it("Rejects price feeds other than the configured one", async () => {
+    const otherFeed = Keypair.generate();
+
+    try {
+      await program.methods
+        .updateBalanceRequirement()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed: otherFeed.publicKey,
+          fallbackPriceFeed: null,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected update with an unconfigured feed to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidPriceFeed");
+    }
+  });
+
+  it("Rejects a fallback feed not owned by the selected oracle", async () => {
+    try {
+      await program.methods
+        .setFallbackPriceFeed({ switchboard: {} })
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed: PRICE_FEED,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a Pyth account to be rejected as a Switchboard feed");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidOracleOwner");
+    }
+  });
+
+  it("Accepts a Chainlink feed as fallback oracle", async () => {
+    await program.methods
+      .setFallbackPriceFeed({ chainlink: {} })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: CHAINLINK_FEED,
+      })
+      .signers([authority])
+      .rpc();
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.ok(state.fallbackPriceFeed.equals(CHAINLINK_FEED));
+    assert.deepEqual(state.fallbackOracleSource, { chainlink: {} });
+  });
+
+  it("Configures a Switchboard fallback feed", async () => {
+    await program.methods
+      .setFallbackPriceFeed({ switchboard: {} })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: SWITCHBOARD_FEED,
+      })
+      .signers([authority])
+      .rpc();
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.ok(state.fallbackPriceFeed.equals(SWITCHBOARD_FEED));
+    assert.deepEqual(state.fallbackOracleSource, { switchboard: {} });
+  });
+
+  it("Fails over to the fallback feed when the primary is stale", async () => {
+    const listener = program.addEventListener("OracleFallbackUsed", (event) => {
+      assert.ok(event.priceFeed.equals(SWITCHBOARD_FEED));
+    });
+
+    try {
+      await program.methods
+        .updateBalanceRequirement()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed: PRICE_FEED,
+          fallbackPriceFeed: SWITCHBOARD_FEED,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .signers([authority])
+        .rpc();
+    } catch (err: any) {
+      // Cloned feeds are frozen snapshots, so the fallback can be stale too;
+      // the error must then come from the fallback rather than a missing account
+      assert.equal(err.error.errorCode.code, "StalePrice");
+    } finally {
+      await program.removeEventListener(listener);
+    }
+  });
+
+  it("Rejects a fallback feed other than the configured one", async () => {
+    try {
+      await program.methods
+        .updateBalanceRequirement()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed: PRICE_FEED,
+          fallbackPriceFeed: Keypair.generate().publicKey,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected update with an unconfigured fallback feed to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidPriceFeed");
+    }
+  });
+
+  it("Disables the fallback feed when none is provided", async () => {
+    await program.methods
+      .setFallbackPriceFeed({ switchboard: {} })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: null,
+      })
+      .signers([authority])
+      .rpc();
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.ok(state.fallbackPriceFeed.equals(PublicKey.default));
+  });
+
+  it("Prices the requirement from the median of registered feeds", async () => {
+    const [customPriceFeed] = await PublicKey.findProgramAddress(
+      [Buffer.from("custom_price_feed")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeCustomPriceFeed(-6)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        customPriceFeed,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    // $0.01 per token with a tight confidence interval
+    await program.methods
+      .submitCustomPrice(new anchor.BN(10_000), new anchor.BN(10))
+      .accounts({
+        authority: authority.publicKey,
+        customPriceFeed,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .setMedianFeeds([{ source: { custom: {} }, address: customPriceFeed }], 1)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .updateBalanceRequirementMedian()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceAccumulator: null,
+        requirementHistory: null,
+      })
+      .remainingAccounts([
+        { pubkey: customPriceFeed, isWritable: false, isSigner: false },
+      ])
+      .signers([authority])
+      .rpc();
+
+    // $15 target / $0.01 per token
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.currentRequirement.toNumber(), 1_500);
+  });
+
+  it("Rejects unregistered feeds in median pricing", async () => {
+    try {
+      await program.methods
+        .updateBalanceRequirementMedian()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .remainingAccounts([
+          { pubkey: PRICE_FEED, isWritable: false, isSigner: false },
+        ])
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected an unregistered feed to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidPriceFeed");
+    }
+  });
+
+  it("Requires the price accumulator while TWAP pricing is enabled", async () => {
+    const [priceAccumulator] = await PublicKey.findProgramAddress(
+      [Buffer.from("price_accumulator")],
+      program.programId
+    );
+    const [customPriceFeed] = await PublicKey.findProgramAddress(
+      [Buffer.from("custom_price_feed")],
+      program.programId
+    );
+    const customFeedAccount = {
+      pubkey: customPriceFeed,
+      isWritable: false,
+      isSigner: false,
+    };
+
+    await program.methods
+      .initializePriceAccumulator()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceAccumulator,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .setTwapWindow(new anchor.BN(3600))
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+
+    // The 32 observations span 31 crank intervals of 300 seconds
+    try {
+      await program.methods
+        .setTwapWindow(new anchor.BN(31 * 300 + 1))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a window longer than the accumulator covers to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+
+    try {
+      await program.methods
+        .updateBalanceRequirementMedian()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .remainingAccounts([customFeedAccount])
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected update without the accumulator to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "PriceAccumulatorRequired");
+    }
+
+    await program.methods
+      .updateBalanceRequirementMedian()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceAccumulator,
+        requirementHistory: null,
+      })
+      .remainingAccounts([customFeedAccount])
+      .signers([authority])
+      .rpc();
+
+    const accumulator = await program.account.priceAccumulator.fetch(priceAccumulator);
+    assert.equal(accumulator.count, 1);
+    assert.equal(accumulator.lastPrice.toNumber(), 10_000);
+  });
+
+  it("Pays keepers for permissionless cranks that change the requirement", async () => {
+    const keeper = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      keeper.publicKey,
       anchor.web3.LAMPORTS_PER_SOL
     );
+    await provider.connection.confirmTransaction(airdrop);
 
-    // Create user's token account
-    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+    const [customPriceFeed] = await PublicKey.findProgramAddress(
+      [Buffer.from("custom_price_feed")],
+      program.programId
+    );
+    const [priceAccumulator] = await PublicKey.findProgramAddress(
+      [Buffer.from("price_accumulator")],
+      program.programId
+    );
+    const [keeperVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("keeper_vault")],
+      program.programId
+    );
+
+    // Drive the crank from the custom feed so the price is fresh
+    await program.methods
+      .setPriceFeed({ custom: {} })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: customPriceFeed,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .submitCustomPrice(new anchor.BN(10_000), new anchor.BN(10))
+      .accounts({ authority: authority.publicKey, customPriceFeed })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .initializeKeeperVault()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        keeperVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    await transfer(
       provider.connection,
-      user,
+      authority,
+      authorityTokenAccount,
+      keeperVault,
+      authority,
+      1_000
+    );
+    const setKeeperConfig = (crankIntervalSecs: number) =>
+      program.methods
+        .setKeeperConfig(new anchor.BN(10), new anchor.BN(crankIntervalSecs))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+    // Price from spot so each crank moves the requirement all the way, which
+    // also lets cranks run back to back
+    await program.methods
+      .setTwapWindow(new anchor.BN(0))
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+    await setKeeperConfig(0);
+
+    const keeperTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      keeper,
       mint,
-      user.publicKey
+      keeper.publicKey
     );
+    const crank = () =>
+      program.methods
+        .crankRequirement()
+        .accounts({
+          keeper: keeper.publicKey,
+          state: tokenState,
+          priceFeed: customPriceFeed,
+          fallbackPriceFeed: null,
+          priceAccumulator,
+          requirementHistory: null,
+          keeperVault,
+          keeperTokenAccount: keeperTokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper])
+        .rpc();
 
-    // Check balance requirement
-    const hasAccess = await program.methods
-      .verifyBalance()
+    const keeperBalance = async () =>
+      Number((await getAccount(provider.connection, keeperTokenAccount.address)).amount);
+
+    // The feed still reports the price the requirement was last set from,
+    // so the crank changes nothing and earns nothing
+    await crank();
+    assert.equal(await keeperBalance(), 0);
+
+    const crankAt = async (price: number) => {
+      await program.methods
+        .submitCustomPrice(new anchor.BN(price), new anchor.BN(10))
+        .accounts({ authority: authority.publicKey, customPriceFeed })
+        .signers([authority])
+        .rpc();
+      await crank();
+      return (await program.account.tokenState.fetch(tokenState)).currentRequirement.toNumber();
+    };
+    assert.equal(await crankAt(12_000), 1_250);
+    assert.equal(await keeperBalance(), 10);
+    assert.equal(await crankAt(10_000), 1_500);
+    assert.equal(await keeperBalance(), 20);
+
+    await setKeeperConfig(3600);
+    try {
+      await crank();
+      assert.fail("Expected a second crank within the interval to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "CrankTooSoon");
+    }
+  });
+
+  it("Trips the circuit breaker on extreme price moves", async () => {
+    const [customPriceFeed] = await PublicKey.findProgramAddress(
+      [Buffer.from("custom_price_feed")],
+      program.programId
+    );
+    const [priceAccumulator] = await PublicKey.findProgramAddress(
+      [Buffer.from("price_accumulator")],
+      program.programId
+    );
+    const updateFromCustomFeed = () =>
+      program.methods
+        .updateBalanceRequirementMedian()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceAccumulator,
+          requirementHistory: null,
+        })
+        .remainingAccounts([
+          { pubkey: customPriceFeed, isWritable: false, isSigner: false },
+        ])
+        .signers([authority])
+        .rpc();
+
+    // Price from spot so the crash is not averaged away
+    await program.methods
+      .setTwapWindow(new anchor.BN(0))
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await program.methods
+        .setCircuitBreaker(new anchor.BN(10_001))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a threshold above a 100% move to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+
+    // 90% crash from the last recorded $0.01
+    await program.methods
+      .submitCustomPrice(new anchor.BN(1_000), new anchor.BN(1))
+      .accounts({ authority: authority.publicKey, customPriceFeed })
+      .signers([authority])
+      .rpc();
+    await updateFromCustomFeed();
+
+    let state = await program.account.tokenState.fetch(tokenState);
+    assert.isTrue(state.circuitBreakerTripped);
+    assert.equal(state.currentRequirement.toNumber(), 1_500);
+
+    try {
+      await updateFromCustomFeed();
+      assert.fail("Expected updates to halt while the breaker is tripped");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "CircuitBreakerActive");
+    }
+
+    // Price by hand while the feeds are distrusted
+    const [priceOverride] = await PublicKey.findProgramAddress(
+      [Buffer.from("price_override")],
+      program.programId
+    );
+    const overridePrice = (caller: Keypair, price: number) =>
+      program.methods
+        .overridePrice(new anchor.BN(price), new anchor.BN(3600))
+        .accounts({
+          caller: caller.publicKey,
+          state: tokenState,
+          guardianCouncil: null,
+          priceOverride,
+          systemProgram: SystemProgram.programId,
+          adminLog: null,
+        })
+        .signers([caller])
+        .rpc();
+    await overridePrice(authority, 5_000);
+
+    // $15 / $0.005
+    state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.currentRequirement.toNumber(), 3_000);
+    const manual = await program.account.priceOverride.fetch(priceOverride);
+    assert.equal(manual.price.toNumber(), 5_000);
+    assert.isTrue(manual.expiresAt.toNumber() > 0);
+
+    try {
+      await overridePrice(Keypair.generate(), 1);
+      assert.fail("Expected an override without guardians to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    try {
+      await program.methods
+        .resumeOraclePricing()
+        .accounts({ payer: authority.publicKey, state: tokenState, priceOverride })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected oracle pricing to stay off until the override expires");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "PriceOverrideActive");
+    }
+
+    await program.methods
+      .resetCircuitBreaker()
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+    await updateFromCustomFeed();
+
+    // $15 / $0.001, clamped to max_tokens
+    state = await program.account.tokenState.fetch(tokenState);
+    assert.isFalse(state.circuitBreakerTripped);
+    assert.equal(state.currentRequirement.toNumber(), 10_000);
+  });
+
+  it("Records requirement changes in the history ring buffer", async () => {
+    const [requirementHistory] = await PublicKey.findProgramAddress(
+      [Buffer.from("requirement_history")],
+      program.programId
+    );
+    const [customPriceFeed] = await PublicKey.findProgramAddress(
+      [Buffer.from("custom_price_feed")],
+      program.programId
+    );
+    const [priceAccumulator] = await PublicKey.findProgramAddress(
+      [Buffer.from("price_accumulator")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeRequirementHistory()
       .accounts({
+        authority: authority.publicKey,
         state: tokenState,
-        tokenAccount: userTokenAccount.address,
+        requirementHistory,
+        systemProgram: SystemProgram.programId,
       })
-      .view();
+      .signers([authority])
+      .rpc();
 
-    // Should be false since user has no tokens
-    assert.isFalse(hasAccess);
+    const submitPrice = (price: number) =>
+      program.methods
+        .submitCustomPrice(new anchor.BN(price), new anchor.BN(1))
+        .accounts({ authority: authority.publicKey, customPriceFeed })
+        .signers([authority])
+        .rpc();
+
+    const update = (history: PublicKey | null) =>
+      program.methods
+        .updateBalanceRequirementMedian()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceAccumulator,
+          requirementHistory: history,
+        })
+        .remainingAccounts([
+          { pubkey: customPriceFeed, isWritable: false, isSigner: false },
+        ])
+        .signers([authority])
+        .rpc();
+
+    try {
+      await update(null);
+      assert.fail("Expected update without the history account to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "RequirementHistoryRequired");
+    }
+
+    // Still clamped to max_tokens, so nothing is recorded
+    await submitPrice(1_400);
+    await update(requirementHistory);
+
+    // Stay inside the circuit breaker band while stepping to $0.002
+    await submitPrice(2_000);
+    await update(requirementHistory);
+
+    const history = await program.account.requirementHistory.fetch(requirementHistory);
+    assert.equal(history.count, 1);
+    assert.equal(history.entries[0].price.toNumber(), 2_000);
+    assert.equal(history.entries[0].requirement.toNumber(), 7_500);
+  });
+
+  it("Normalizes feed exponents to the configured price precision", async () => {
+    // The custom feed reports at 10^-6; at 8 decimals $0.004 is 400_000
+    await setPricingParameters({
+      priceDecimals: 8,
+      targetUsdValue: new anchor.BN(1_500_000_000),
+    });
+    await submitCustomPrice(4_000);
+    await updateFromCustomPrice();
+
+    let state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.priceDecimals, 8);
+    assert.equal(state.lastPrice.toNumber(), 400_000);
+    assert.equal(state.currentRequirement.toNumber(), 3_750);
+
+    // Scaling up by 10^12 overflows u64 instead of wrapping
+    await setPricingParameters({
+      priceDecimals: 18,
+      targetUsdValue: new anchor.BN("15000000000000000000"),
+    });
+    await submitCustomPrice("9223372036854775807");
+    try {
+      await updateFromCustomPrice();
+      assert.fail("Expected a price beyond u64 at 18 decimals to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "MathOverflow");
+    }
+
+    // Back to $15 at 10^-6 and $0.002
+    await setPricingParameters({
+      priceDecimals: 6,
+      targetUsdValue: new anchor.BN(15_000_000),
+    });
+    state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.lastPrice.toNumber(), 0);
+    await submitCustomPrice(2_000);
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 7_500);
+  });
+
+  it("Rejects prices older than the maximum price age", async () => {
+    try {
+      await setPricingParameters({ maxPriceAgeSecs: new anchor.BN(0) });
+      assert.fail("Expected a zero price age to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+
+    await setPricingParameters({ maxPriceAgeSecs: new anchor.BN(1) });
+    await submitCustomPrice(2_000);
+    await new Promise((resolve) => setTimeout(resolve, 3_000));
+    try {
+      await updateFromCustomPrice();
+      assert.fail("Expected a price older than a second to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "StalePrice");
+    }
+
+    await setPricingParameters({ maxPriceAgeSecs: new anchor.BN(60) });
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 7_500);
+  });
+
+  it("Skips prices whose confidence interval is too wide", async () => {
+    // +-4% at $0.0025 against the default 2% limit
+    await submitCustomPrice(2_500, 100);
+    const events = await emittedEvents(await updateFromCustomPrice());
+    const rejected = events.find((event) => event.name === "PriceRejected");
+    assert.ok(rejected, "Expected a PriceRejected event");
+    assert.equal(rejected.data.price.toNumber(), 2_500);
+    assert.equal(rejected.data.conf.toNumber(), 100);
+    assert.equal(rejected.data.maxConfidenceBps.toNumber(), 200);
+    assert.isUndefined(events.find((event) => event.name === "BalanceRequirementUpdated"));
+
+    let state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.currentRequirement.toNumber(), 7_500);
+    assert.equal(state.lastPrice.toNumber(), 2_000);
+
+    await setPricingParameters({ maxConfidenceBps: new anchor.BN(500) });
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 6_000);
+
+    try {
+      await setPricingParameters({ maxConfidenceBps: new anchor.BN(10_001) });
+      assert.fail("Expected a confidence limit above 100% to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+
+    await setPricingParameters({ maxConfidenceBps: new anchor.BN(200) });
+    await submitCustomPrice(2_000);
+    await updateFromCustomPrice();
+    state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.maxConfidenceBps.toNumber(), 200);
+    assert.equal(state.currentRequirement.toNumber(), 7_500);
+  });
+
+  it("Holds the requirement until it moves past the update threshold", async () => {
+    await setPricingParameters({ updateThresholdBps: new anchor.BN(2_000) });
+
+    // 6_818 is only 9% below 7_500
+    await submitCustomPrice(2_200);
+    let events = await emittedEvents(await updateFromCustomPrice());
+    assert.isUndefined(events.find((event) => event.name === "BalanceRequirementUpdated"));
+    assert.equal(await currentRequirement(), 7_500);
+
+    // 5_769 is 23% below
+    await submitCustomPrice(2_600);
+    events = await emittedEvents(await updateFromCustomPrice());
+    const updated = events.find((event) => event.name === "BalanceRequirementUpdated");
+    assert.ok(updated, "Expected a BalanceRequirementUpdated event");
+    assert.equal(updated.data.newRequirement.toNumber(), 5_769);
+    assert.equal(updated.data.price.toNumber(), 2_600);
+    assert.equal(updated.data.updateThresholdBps.toNumber(), 2_000);
+
+    await setPricingParameters({ updateThresholdBps: new anchor.BN(100) });
+    await submitCustomPrice(2_000);
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 7_500);
+  });
+
+  it("Ramps the requirement toward its target in bounded steps", async () => {
+    await setPricingParameters({ maxStepBps: new anchor.BN(1_000) });
+
+    // Heading for 5_357 at most 10% of the current requirement at a time
+    await submitCustomPrice(2_800);
+    const events = await emittedEvents(await updateFromCustomPrice());
+    const ramping = events.find((event) => event.name === "RequirementRamping");
+    assert.ok(ramping, "Expected a RequirementRamping event");
+    assert.equal(ramping.data.previousRequirement.toNumber(), 7_500);
+    assert.equal(ramping.data.newRequirement.toNumber(), 6_750);
+    assert.equal(ramping.data.targetRequirement.toNumber(), 5_357);
+    assert.equal(ramping.data.maxStepBps.toNumber(), 1_000);
+    assert.equal(await currentRequirement(), 6_750);
+
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 6_075);
+
+    // Without a step limit the requirement jumps straight to the target
+    await setPricingParameters({ maxStepBps: new anchor.BN(0) });
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 5_357);
+
+    await submitCustomPrice(2_000);
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 7_500);
+  });
+
+  it("Spaces requirement refreshes by the minimum update interval", async () => {
+    // The last refresh was just now; every update path counts towards it
+    await setPricingParameters({ minUpdateIntervalSecs: new anchor.BN(3600) });
+
+    try {
+      await updateFromCustomPrice();
+      assert.fail("Expected a refresh within the interval to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "UpdateTooFrequent");
+    }
+    try {
+      await program.methods
+        .updateBalanceRequirementMedian()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceAccumulator: null,
+          requirementHistory: requirementHistoryPda,
+        })
+        .remainingAccounts([
+          { pubkey: customPriceFeedPda, isWritable: false, isSigner: false },
+        ])
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a median refresh within the interval to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "UpdateTooFrequent");
+    }
+
+    await setPricingParameters({ minUpdateIntervalSecs: new anchor.BN(0) });
+    await updateFromCustomPrice();
+    assert.equal(await currentRequirement(), 7_500);
+  });
+
+  it("Only prices from pull-oracle updates for the configured feed id", async () => {
+    const setPullFeedId = (feedId: number[]) =>
+      program.methods
+        .setPullFeedId(feedId)
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+    const expectPullError = async (priceUpdate: PublicKey, code: string) => {
+      try {
+        await program.methods
+          .updateBalanceRequirementPull()
+          .accounts({
+            authority: authority.publicKey,
+            state: tokenState,
+            priceUpdate,
+            priceAccumulator: null,
+            requirementHistory: requirementHistoryPda,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail(`Expected the pull update to fail with ${code}`);
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, code);
+      }
+    };
+
+    // Pull pricing stays off until a feed id is configured
+    await expectPullError(PULL_PRICE_UPDATE, "PriceFeedIdMismatch");
+
+    await setPullFeedId(new Array(32).fill(1));
+    await expectPullError(customPriceFeedPda, "InvalidOracleOwner");
+    await expectPullError(PULL_PRICE_UPDATE, "PriceFeedIdMismatch");
+
+    // The cloned update is a snapshot, so the matching feed's price is stale
+    await setPullFeedId(SOL_USD_FEED_ID);
+    await expectPullError(PULL_PRICE_UPDATE, "StalePrice");
+
+    await setPullFeedId(new Array(32).fill(0));
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.deepEqual(state.pullFeedId, new Array(32).fill(0));
+    assert.equal(state.currentRequirement.toNumber(), 7_500);
+  });
+
+  it("Prices from the Pyth EMA price when configured", async () => {
+    const setPriceFeed = (source: Record<string, {}>, priceFeed: PublicKey) =>
+      program.methods
+        .setPriceFeed(source)
+        .accounts({ authority: authority.publicKey, state: tokenState, priceFeed })
+        .signers([authority])
+        .rpc();
+    const setCircuitBreaker = (thresholdBps: number) =>
+      program.methods
+        .setCircuitBreaker(new anchor.BN(thresholdBps))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+    // The cloned feed is a frozen snapshot at a price far from the custom
+    // feed's, so accept any age and move without the breaker
+    await setPriceFeed({ pyth: {} }, PRICE_FEED);
+    await setCircuitBreaker(0);
+    await setPricingParameters({
+      maxPriceAgeSecs: new anchor.BN("9223372036854775807"),
+      maxConfidenceBps: new anchor.BN(10_000),
+      useEmaPrice: true,
+    });
+
+    await program.methods
+      .updateBalanceRequirement()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        priceFeed: PRICE_FEED,
+        fallbackPriceFeed: null,
+        priceAccumulator: null,
+        requirementHistory: requirementHistoryPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    // Pyth v2 price account: exponent at 20, EMA price at 48
+    const { data } = await provider.connection.getAccountInfo(PRICE_FEED);
+    const shift = data.readInt32LE(20) + 6;
+    const emaPrice = new anchor.BN(data.subarray(48, 56), "le");
+    const scale = new anchor.BN(10).pow(new anchor.BN(Math.abs(shift)));
+    const expected = shift >= 0 ? emaPrice.mul(scale) : emaPrice.div(scale);
+    let state = await program.account.tokenState.fetch(tokenState);
+    assert.isTrue(state.useEmaPrice);
+    assert.equal(state.lastPrice.toString(), expected.toString());
+
+    await setPriceFeed({ custom: {} }, customPriceFeedPda);
+    await setPricingParameters({
+      maxPriceAgeSecs: new anchor.BN(60),
+      maxConfidenceBps: new anchor.BN(200),
+      useEmaPrice: false,
+    });
+    await submitCustomPrice(2_000);
+    await updateFromCustomPrice();
+    await setCircuitBreaker(5_000);
+    state = await program.account.tokenState.fetch(tokenState);
+    assert.isFalse(state.useEmaPrice);
+    assert.equal(state.currentRequirement.toNumber(), 7_500);
+  });
+
+  describe("update_parameters", () => {
+    const updateParameters = (params: Record<string, any>) =>
+      program.methods
+        .updateParameters({ ...NO_CHANGES, ...params })
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+    const expectError = async (params: Record<string, any>, code: string) => {
+      try {
+        await updateParameters(params);
+        assert.fail(`Expected ${JSON.stringify(params)} to be rejected`);
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, code);
+      }
+    };
+
+    it("Updates target and bounds", async () => {
+      await updateParameters({
+        targetUsdValue: new anchor.BN(20_000_000),
+        minTokens: new anchor.BN(200),
+        maxTokens: new anchor.BN(20_000),
+      });
+
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.equal(state.targetUsdValue.toNumber(), 20_000_000);
+      assert.equal(state.minTokens.toNumber(), 200);
+      assert.equal(state.maxTokens.toNumber(), 20_000);
+    });
+
+    it("Rejects a zero target", async () => {
+      await expectError({ targetUsdValue: new anchor.BN(0) }, "InvalidTargetValue");
+    });
+
+    it("Rejects min tokens at or above max tokens", async () => {
+      await expectError(
+        { minTokens: new anchor.BN(500), maxTokens: new anchor.BN(500) },
+        "InvalidTokenBounds"
+      );
+      // Checked against the stored max when only min changes
+      await expectError({ minTokens: new anchor.BN(50_000) }, "InvalidTokenBounds");
+    });
+
+    it("Rejects a precision change without a matching target", async () => {
+      await expectError({ priceDecimals: 8 }, "InvalidTargetValue");
+      await expectError({ priceDecimals: 19 }, "InvalidPriceDecimals");
+    });
+
+    it("Rejects out-of-range basis point parameters", async () => {
+      await expectError({ updateThresholdBps: new anchor.BN(10_001) }, "InvalidParameter");
+      await expectError({ maxStepBps: new anchor.BN(10_001) }, "InvalidParameter");
+    });
+
+    it("Rejects updates from a non-authority signer", async () => {
+      const intruder = Keypair.generate();
+      try {
+        await program.methods
+          .updateParameters({ ...NO_CHANGES, targetUsdValue: new anchor.BN(1) })
+          .accounts({ authority: intruder.publicKey, state: tokenState })
+          .signers([intruder])
+          .rpc();
+        assert.fail("Expected a non-authority update to fail");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "Unauthorized");
+      }
+    });
+
+    after(async () => {
+      // Restore the defaults the remaining tests expect
+      await updateParameters({
+        targetUsdValue: new anchor.BN(15_000_000),
+        minTokens: new anchor.BN(100),
+        maxTokens: new anchor.BN(10_000),
+      });
+    });
+  });
+
+  it("Halts requirement updates while paused", async () => {
+    await program.methods
+      .pause()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        guardianCouncil: guardianCouncilPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const { priceFeed } = await program.account.tokenState.fetch(tokenState);
+    try {
+      await program.methods
+        .updateBalanceRequirement()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed,
+          fallbackPriceFeed: null,
+          priceAccumulator: null,
+          requirementHistory: null,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected the update to fail while paused");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProgramPaused");
+    }
+
+    // Verification stays available during an incident
+    assert.isTrue(await verify(authorityTokenAccount));
+
+    await program.methods
+      .unpause()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        guardianCouncil: guardianCouncilPda,
+      })
+      .signers([authority])
+      .rpc();
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.isFalse(state.paused);
+  });
+
+  it("Grants and revokes roles in the registry", async () => {
+    const operator = Keypair.generate();
+    const [roleRegistry] = await PublicKey.findProgramAddress(
+      [Buffer.from("role_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeRoleRegistry()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        roleRegistry,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .grantRole({ operator: {} }, operator.publicKey)
+      .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry })
+      .signers([authority])
+      .rpc();
+
+    const setPaused = (paused: boolean) =>
+      (paused ? program.methods.pause() : program.methods.unpause())
+        .accounts({
+          authority: operator.publicKey,
+          state: tokenState,
+          roleRegistry,
+          guardianCouncil: guardianCouncilPda,
+        })
+        .signers([operator])
+        .rpc();
+
+    // The operator key can pause without the authority
+    await setPaused(true);
+    assert.isTrue((await program.account.tokenState.fetch(tokenState)).paused);
+    await setPaused(false);
+
+    // ...but holds no admin rights
+    try {
+      await program.methods
+        .setTwapWindow(new anchor.BN(0))
+        .accounts({ authority: operator.publicKey, state: tokenState, roleRegistry })
+        .signers([operator])
+        .rpc();
+      assert.fail("Expected an operator parameter change to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    await program.methods
+      .revokeRole({ operator: {} }, operator.publicKey)
+      .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await setPaused(true);
+      assert.fail("Expected a revoked operator to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+  });
+
+  it("Refuses to close the state while tokens can be minted", async () => {
+    const [stakePool] = await PublicKey.findProgramAddress(
+      [Buffer.from("stake_pool")],
+      program.programId
+    );
+    try {
+      await program.methods
+        .closeState()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          mint,
+          recipient: authority.publicKey,
+          customPriceFeed: null,
+          priceAccumulator: null,
+          requirementHistory: null,
+          roleRegistry: null,
+          stakePool,
+          keeperVault: null,
+          recipientTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected close to fail while the mint authority is live");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SupplyStillMintable");
+    }
+  });
+
+  it("Refuses to migrate a state that is already current", async () => {
+    try {
+      await program.methods
+        .migrateState()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          priceFeed: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected migration of a current state to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "StateAlreadyCurrent");
+    }
+  });
+
+  describe("multisig administration", () => {
+    const multisigProgram = anchor.workspace.MockMultisig as Program<MockMultisig>;
+    const multisig = Keypair.generate();
+    const members = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    let vault: PublicKey;
+
+    // Run a token program instruction with the multisig vault as signer
+    const throughMultisig = (ix: TransactionInstruction, approvers: Keypair[]) =>
+      multisigProgram.methods
+        .execute(ix.data, approvers.length)
+        .accounts({
+          multisig: multisig.publicKey,
+          vault,
+          targetProgram: program.programId,
+        })
+        .remainingAccounts([
+          ...approvers.map((member) => ({
+            pubkey: member.publicKey,
+            isSigner: true,
+            isWritable: false,
+          })),
+          // The vault cannot sign the outer transaction; the multisig signs for it
+          ...ix.keys.map((key) => ({ ...key, isSigner: false })),
+        ])
+        .signers(approvers)
+        .rpc();
+
+    before(async () => {
+      [vault] = await PublicKey.findProgramAddress(
+        [Buffer.from("vault"), multisig.publicKey.toBuffer()],
+        multisigProgram.programId
+      );
+
+      await multisigProgram.methods
+        .createMultisig(
+          members.map((member) => member.publicKey),
+          2
+        )
+        .accounts({
+          payer: provider.wallet.publicKey,
+          multisig: multisig.publicKey,
+          vault,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([multisig])
+        .rpc();
+    });
+
+    it("Hands the authority to a multisig vault", async () => {
+      await program.methods
+        .setPendingAuthority(vault)
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+      const accept = await program.methods
+        .acceptAuthority()
+        .accounts({ newAuthority: vault, state: tokenState })
+        .instruction();
+
+      try {
+        await throughMultisig(accept, members.slice(0, 1));
+        assert.fail("Expected a single approval to fall short of the threshold");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "ThresholdNotMet");
+      }
+
+      await throughMultisig(accept, members.slice(0, 2));
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.ok(state.authority.equals(vault));
+    });
+
+    it("Changes parameters through the multisig", async () => {
+      const ix = await program.methods
+        .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(500) })
+        .accounts({ authority: vault, state: tokenState, roleRegistry: null })
+        .instruction();
+      await throughMultisig(ix, members.slice(1, 3));
+
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.equal(state.maxStepBps.toNumber(), 500);
+
+      // The previous keypair no longer administers the state
+      try {
+        await program.methods
+          .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(0) })
+          .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry: null })
+          .signers([authority])
+          .rpc();
+        assert.fail("Expected the old authority to be rejected");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "Unauthorized");
+      }
+    });
+
+    after(async () => {
+      // Hand the authority back for the remaining tests
+      const restore = await program.methods
+        .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(0) })
+        .accounts({ authority: vault, state: tokenState, roleRegistry: null })
+        .instruction();
+      await throughMultisig(restore, members.slice(0, 2));
+
+      const handBack = await program.methods
+        .setPendingAuthority(authority.publicKey)
+        .accounts({ authority: vault, state: tokenState })
+        .instruction();
+      await throughMultisig(handBack, members.slice(0, 2));
+
+      await program.methods
+        .acceptAuthority()
+        .accounts({ newAuthority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+    });
+  });
+
+  describe("governance administration", () => {
+    const governanceProgram = anchor.workspace.MockGovernance as Program<MockGovernance>;
+    const governance = Keypair.generate();
+    const smallHolder = Keypair.generate();
+    const VOTE_THRESHOLD = new anchor.BN(1_000_000);
+    let nativeTreasury: PublicKey;
+    let smallHolderTokenAccount: PublicKey;
+
+    // Propose `ixs`, which the native treasury signs once the proposal passes
+    const propose = async (ixs: TransactionInstruction[]) => {
+      const proposal = Keypair.generate();
+      await governanceProgram.methods
+        .createProposal(
+          ixs.map((ix) => ({ programId: ix.programId, accounts: ix.keys, data: ix.data }))
+        )
+        .accounts({
+          proposer: authority.publicKey,
+          governance: governance.publicKey,
+          proposal: proposal.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority, proposal])
+        .rpc();
+      return { proposal: proposal.publicKey, ixs };
+    };
+
+    const vote = async (proposal: PublicKey, voter: Keypair, voterTokenAccount: PublicKey) => {
+      const [voteRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vote"), proposal.toBuffer(), voter.publicKey.toBuffer()],
+        governanceProgram.programId
+      );
+      await governanceProgram.methods
+        .castVote()
+        .accounts({
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          proposal,
+          voterTokenAccount,
+          voteRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+
+    const execute = ({ proposal, ixs }: { proposal: PublicKey; ixs: TransactionInstruction[] }) =>
+      governanceProgram.methods
+        .executeProposal()
+        .accounts({ governance: governance.publicKey, proposal, nativeTreasury })
+        .remainingAccounts([
+          // The treasury cannot sign the outer transaction; the governance signs for it
+          ...ixs.flatMap((ix) => ix.keys.map((key) => ({ ...key, isSigner: false }))),
+          ...ixs.map((ix) => ({ pubkey: ix.programId, isSigner: false, isWritable: false })),
+        ])
+        .rpc();
+
+    before(async () => {
+      [nativeTreasury] = PublicKey.findProgramAddressSync(
+        [Buffer.from("native-treasury"), governance.publicKey.toBuffer()],
+        governanceProgram.programId
+      );
+
+      await governanceProgram.methods
+        .createGovernance(mint, VOTE_THRESHOLD)
+        .accounts({
+          payer: provider.wallet.publicKey,
+          governance: governance.publicKey,
+          nativeTreasury,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([governance])
+        .rpc();
+
+      const signature = await provider.connection.requestAirdrop(
+        smallHolder.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(signature);
+      smallHolderTokenAccount = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          smallHolder,
+          mint,
+          smallHolder.publicKey
+        )
+      ).address;
+      await transfer(
+        provider.connection,
+        authority,
+        authorityTokenAccount,
+        smallHolderTokenAccount,
+        authority,
+        1_000
+      );
+    });
+
+    it("Hands the authority to a governance's native treasury", async () => {
+      await program.methods
+        .setPendingAuthority(nativeTreasury)
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+      const accept = await propose([
+        await program.methods
+          .acceptAuthority()
+          .accounts({ newAuthority: nativeTreasury, state: tokenState })
+          .instruction(),
+      ]);
+
+      await vote(accept.proposal, smallHolder, smallHolderTokenAccount);
+      try {
+        await execute(accept);
+        assert.fail("Expected a small holder's vote to fall short of the threshold");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "VoteThresholdNotMet");
+      }
+
+      await vote(accept.proposal, authority, authorityTokenAccount);
+      await execute(accept);
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.ok(state.authority.equals(nativeTreasury));
+
+      try {
+        await execute(accept);
+        assert.fail("Expected a proposal to execute only once");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "AlreadyExecuted");
+      }
+    });
+
+    it("Passes a requirement parameter change through a proposal", async () => {
+      const change = await propose([
+        await program.methods
+          .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(250) })
+          .accounts({ authority: nativeTreasury, state: tokenState, roleRegistry: null })
+          .instruction(),
+      ]);
+
+      // Nothing changes before the vote passes
+      try {
+        await execute(change);
+        assert.fail("Expected an unvoted proposal to be rejected");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "VoteThresholdNotMet");
+      }
+
+      await vote(change.proposal, authority, authorityTokenAccount);
+      await execute(change);
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.equal(state.maxStepBps.toNumber(), 250);
+    });
+
+    after(async () => {
+      // Hand the authority back for the remaining tests
+      const restore = await propose([
+        await program.methods
+          .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(0) })
+          .accounts({ authority: nativeTreasury, state: tokenState, roleRegistry: null })
+          .instruction(),
+        await program.methods
+          .setPendingAuthority(authority.publicKey)
+          .accounts({ authority: nativeTreasury, state: tokenState })
+          .instruction(),
+      ]);
+      await vote(restore.proposal, authority, authorityTokenAccount);
+      await execute(restore);
+
+      await program.methods
+        .acceptAuthority()
+        .accounts({ newAuthority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+    });
+  });
+
+  it("Verifies token balance meets requirement", async () => {
+    // Create a test user
+    const user = Keypair.generate();
+    await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+
+    // Create user's token account
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+
+    // Check balance requirement
+    const hasAccess = await verify(userTokenAccount.address);
+
+    // Should be false since user has no tokens
+    assert.isFalse(hasAccess);
+
+    // The failed check is still recorded, as an already expired receipt
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const state = await program.account.tokenState.fetch(tokenState);
+    const recorded = await program.account.verificationReceipt.fetch(receipt);
+    assert.ok(recorded.wallet.equals(user.publicKey));
+    assert.equal(
+      recorded.requirementAtVerification.toNumber(),
+      state.currentRequirement.toNumber()
+    );
+    assert.equal(recorded.expiresAt.toNumber(), recorded.verifiedAt.toNumber());
+
+    // A holder's receipt stays valid for the configured window
+    assert.isTrue(await verify(authorityTokenAccount));
+    const [authorityReceipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const valid = await program.account.verificationReceipt.fetch(authorityReceipt);
+    assert.equal(
+      valid.expiresAt.sub(valid.verifiedAt).toNumber(),
+      state.receiptValiditySecs.toNumber()
+    );
+  });
+
+  it("Reports verification in a BalanceVerified event and checks the receipt seeds", async () => {
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const verifyWith = (tokenAccount: PublicKey, receiptAccount: PublicKey) =>
+      program.methods
+        .verifyBalance()
+        .accounts({
+          payer: authority.publicKey,
+          state: tokenState,
+          tokenAccount,
+          receipt: receiptAccount,
+          accessOverride: accessOverridePda(authority.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    const events = await emittedEvents(await verifyWith(authorityTokenAccount, receipt));
+    const verified = events.find((event) => event.name === "BalanceVerified");
+    assert.ok(verified, "Expected a BalanceVerified event");
+    const state = await program.account.tokenState.fetch(tokenState);
+    const recorded = await program.account.verificationReceipt.fetch(receipt);
+    assert.ok(verified.data.wallet.equals(authority.publicKey));
+    assert.ok(verified.data.holder.equals(authority.publicKey));
+    assert.isTrue(verified.data.verified);
+    assert.equal(verified.data.requirement.toNumber(), state.currentRequirement.toNumber());
+    assert.equal(verified.data.expiresAt.toNumber(), recorded.expiresAt.toNumber());
+
+    // Receipts live at the token account owner's address only
+    const [otherReceipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), Keypair.generate().publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await verifyWith(authorityTokenAccount, otherReceipt);
+      assert.fail("Expected a receipt for another wallet to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ConstraintSeeds");
+    }
+
+    // ...and only tokens of the program's mint count
+    const otherMint = await createMint(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      null,
+      9
+    );
+    const otherTokenAccount = await createAccount(
+      provider.connection,
+      authority,
+      otherMint,
+      authority.publicKey,
+      Keypair.generate()
+    );
+    try {
+      await verifyWith(otherTokenAccount, receipt);
+      assert.fail("Expected a token account of another mint to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ConstraintRaw");
+    }
+  });
+
+  it("Verifies against the USD target at the live oracle price", async () => {
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const state = await program.account.tokenState.fetch(tokenState);
+
+    await program.methods
+      .verifyBalanceUsd()
+      .accounts({
+        payer: authority.publicKey,
+        state: tokenState,
+        priceFeed: state.priceFeed,
+        fallbackPriceFeed: null,
+        tokenAccount: authorityTokenAccount,
+        receipt,
+        accessOverride: accessOverridePda(authority.publicKey),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const recorded = await program.account.verificationReceipt.fetch(receipt);
+    assert.isTrue(recorded.expiresAt.gt(recorded.verifiedAt));
+    const requirement = recorded.requirementAtVerification.toNumber();
+    assert.isAtLeast(requirement, state.minTokens.toNumber());
+    assert.isAtMost(requirement, state.maxTokens.toNumber());
+  });
+
+  it("Keeps access through the grace period after falling short", async () => {
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const setGracePeriod = (secs: number) =>
+      program.methods
+        .setGracePeriod(new anchor.BN(secs))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString())
+    );
+    assert.isTrue(await verify(userTokenAccount.address));
+
+    // Drop one token below the requirement
+    await program.methods
+      .burn(new anchor.BN(1))
+      .accounts({
+        owner: user.publicKey,
+        state: tokenState,
+        mint,
+        tokenAccount: userTokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    assert.isTrue(await verify(userTokenAccount.address));
+    const inGrace = await program.account.verificationReceipt.fetch(receipt);
+    assert.isAbove(inGrace.belowSince.toNumber(), 0);
+    assert.equal(inGrace.tier, 1);
+
+    // Without a grace period the next check revokes access
+    await setGracePeriod(0);
+    assert.isFalse(await verify(userTokenAccount.address));
+    const revoked = await program.account.verificationReceipt.fetch(receipt);
+    assert.equal(revoked.belowSince.toNumber(), 0);
+    assert.equal(revoked.tier, 0);
+
+    await setGracePeriod(72 * 3_600);
+  });
+
+  it("Ignores tokens held for less than the minimum holding period", async () => {
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const [holdingsCheckpoint] = await PublicKey.findProgramAddress(
+      [Buffer.from("holdings"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    const setMinHoldingPeriod = (secs: number) =>
+      program.methods
+        .setMinHoldingPeriod(new anchor.BN(secs))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+    const checkpoint = (tokenAccount: PublicKey) =>
+      program.methods
+        .checkpointHoldings()
+        .accounts({
+          payer: authority.publicKey,
+          state: tokenState,
+          tokenAccount,
+          stakeAccount: null,
+          holdingsCheckpoint,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    await setMinHoldingPeriod(3_600);
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString())
+    );
+
+    // Freshly bought tokens count neither with nor without a checkpoint
+    assert.isFalse(await verify(userTokenAccount.address));
+    await checkpoint(userTokenAccount.address);
+    assert.isFalse(await verify(userTokenAccount.address, { holdingsCheckpoint }));
+    const recorded = await program.account.holdingsCheckpoint.fetch(holdingsCheckpoint);
+    assert.equal(recorded.maturedAmount.toNumber(), 0);
+    assert.equal(recorded.pendingAmount.toNumber(), currentRequirement.toNumber());
+
+    // The checkpoint stays bound to the first token account it saw
+    const secondAccount = await createAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey,
+      Keypair.generate()
+    );
+    try {
+      await checkpoint(secondAccount);
+      assert.fail("Expected a checkpoint of another token account to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidHoldingsCheckpoint");
+    }
+
+    await setMinHoldingPeriod(0);
+    assert.isTrue(await verify(userTokenAccount.address, { holdingsCheckpoint }));
+  });
+
+  it("Verifies a hot wallet against its cold wallet's holdings", async () => {
+    const hot = Keypair.generate();
+    const [delegation] = await PublicKey.findProgramAddress(
+      [Buffer.from("delegation"), hot.publicKey.toBuffer()],
+      program.programId
+    );
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), hot.publicKey.toBuffer()],
+      program.programId
+    );
+    const delegate = (hotWallet: PublicKey, delegationAccount: PublicKey) =>
+      program.methods
+        .delegateAccess(hotWallet, new anchor.BN(Math.floor(Date.now() / 1000) + 3_600))
+        .accounts({
+          coldWallet: authority.publicKey,
+          delegation: delegationAccount,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    const [selfDelegation] = await PublicKey.findProgramAddress(
+      [Buffer.from("delegation"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await delegate(authority.publicKey, selfDelegation);
+      assert.fail("Expected delegating to the holding wallet itself to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidDelegation");
+    }
+
+    await delegate(hot.publicKey, delegation);
+    await program.methods
+      .verifyDelegatedBalance()
+      .accounts({
+        payer: authority.publicKey,
+        state: tokenState,
+        delegation,
+        tokenAccount: authorityTokenAccount,
+        receipt,
+        accessOverride: accessOverridePda(hot.publicKey),
+        holderOverride: accessOverridePda(authority.publicKey),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const { expiresAt: delegationExpiry } = await program.account.delegation.fetch(delegation);
+    const hotReceipt = await program.account.verificationReceipt.fetch(receipt);
+    assert.ok(hotReceipt.wallet.equals(hot.publicKey));
+    assert.isTrue(hotReceipt.expiresAt.gt(hotReceipt.verifiedAt));
+    assert.isTrue(hotReceipt.expiresAt.lte(delegationExpiry));
+
+    // The hot wallet can walk away from a delegation too
+    await program.methods
+      .revokeDelegation()
+      .accounts({ signer: hot.publicKey, delegation, coldWallet: authority.publicKey })
+      .signers([hot])
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(delegation));
+  });
+
+  it("Records the access tier a balance reaches", async () => {
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const setTiers = (tiers: number[]) =>
+      program.methods
+        .setAccessTiers(tiers.map((tier) => new anchor.BN(tier)))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+    // The authority holds far more than 10x the requirement
+    await verify(authorityTokenAccount);
+    assert.equal((await program.account.verificationReceipt.fetch(receipt)).tier, 3);
+
+    try {
+      await setTiers([20_000, 30_000, 100_000]);
+      assert.fail("Expected tiers not starting at the requirement to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidAccessTier");
+    }
+    try {
+      await setTiers([10_000, 30_000, 30_000]);
+      assert.fail("Expected non-increasing tiers to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidAccessTier");
+    }
+
+    // Twice the requirement is Basic by default and Pro at a 2x threshold
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString()) * BigInt(2)
+    );
+    const [userReceipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    await verify(userTokenAccount.address);
+    assert.equal((await program.account.verificationReceipt.fetch(userReceipt)).tier, 1);
+
+    await setTiers([10_000, 20_000, 30_000]);
+    await verify(userTokenAccount.address);
+    assert.equal((await program.account.verificationReceipt.fetch(userReceipt)).tier, 2);
+
+    await setTiers([10_000, 30_000, 100_000]);
+  });
+
+  it("Sums balances across the holder's token accounts", async () => {
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const auxTokenAccount = await createAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey,
+      Keypair.generate()
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    const half = BigInt(currentRequirement.toString()) / BigInt(2) + BigInt(1);
+    for (const destination of [userTokenAccount.address, auxTokenAccount]) {
+      await transfer(
+        provider.connection,
+        authority,
+        authorityTokenAccount,
+        destination,
+        authority,
+        half
+      );
+    }
+
+    assert.isFalse(await verify(userTokenAccount.address));
+    assert.isTrue(await verify(userTokenAccount.address, {}, [auxTokenAccount]));
+
+    try {
+      await verify(userTokenAccount.address, {}, [userTokenAccount.address]);
+      assert.fail("Expected counting the main token account twice to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidTokenAccount");
+    }
+    try {
+      await verify(userTokenAccount.address, {}, [authorityTokenAccount]);
+      assert.fail("Expected another owner's token account to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidTokenAccount");
+    }
+  });
+
+  it("Counts approved partner mints at their basket weight", async () => {
+    const [gatingConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("gating_config")],
+      program.programId
+    );
+    await program.methods
+      .initializeGatingConfig()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        gatingConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const partnerMint = await createMint(
+      provider.connection,
+      authority,
+      authority.publicKey,
+      null,
+      9
+    );
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const partnerTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      partnerMint,
+      user.publicKey
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await mintTo(
+      provider.connection,
+      authority,
+      partnerMint,
+      partnerTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString()) * BigInt(2)
+    );
+
+    // Unlisted mints are rejected outright
+    try {
+      await verify(userTokenAccount.address, { gatingConfig }, [partnerTokenAccount.address]);
+      assert.fail("Expected an unlisted mint to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidTokenAccount");
+    }
+
+    await program.methods
+      .setGatedMint(new anchor.BN(8_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        gatingConfig,
+        tokenMint: mint,
+        gatedMint: partnerMint,
+      })
+      .signers([authority])
+      .rpc();
+    const config = await program.account.gatingConfig.fetch(gatingConfig);
+    assert.ok(config.mints.some((gated: any) => gated.mint.equals(partnerMint)));
+
+    // Twice the requirement at 0.8 clears it
+    assert.isTrue(
+      await verify(userTokenAccount.address, { gatingConfig }, [partnerTokenAccount.address])
+    );
+  });
+
+  it("Counts a liquidity provider's share of pool reserves", async () => {
+    const [gatingConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("gating_config")],
+      program.programId
+    );
+    // A stand-in constant-product pool: its vault of the token and LP mint
+    const pool = Keypair.generate();
+    const reserveVault = await createAccount(
+      provider.connection,
+      authority,
+      mint,
+      pool.publicKey,
+      Keypair.generate()
+    );
+    const lpMint = await createMint(provider.connection, authority, authority.publicKey, null, 9);
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    const requirement = BigInt(currentRequirement.toString());
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      reserveVault,
+      authority,
+      requirement * BigInt(2) + BigInt(2)
+    );
+
+    const user = Keypair.generate();
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      user.publicKey
+    );
+    const userLpAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      lpMint,
+      user.publicKey
+    );
+    const otherLpAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      lpMint,
+      pool.publicKey
+    );
+    // The user holds half the LP supply
+    for (const destination of [userLpAccount.address, otherLpAccount.address]) {
+      await mintTo(provider.connection, authority, lpMint, destination, authority, 1_000);
+    }
+
+    await program.methods
+      .setLpPool(true)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        gatingConfig,
+        lpMint,
+        reserveVault,
+      })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await verify(userTokenAccount.address, { gatingConfig }, [userLpAccount.address]);
+      assert.fail("Expected an LP account without its pool accounts to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidLpPool");
+    }
+    const lpAccounts = [userLpAccount.address, lpMint, reserveVault];
+    try {
+      await verify(userTokenAccount.address, { gatingConfig }, lpAccounts);
+      assert.fail("Expected an LP account in an unobserved pool to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "LpPoolUnobserved");
+    }
+
+    const observe = () =>
+      program.methods.observeLpPool().accounts({ gatingConfig, lpMint, reserveVault });
+    // An observation made in the same transaction, as a flash loan would
+    // need, does not count
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), user.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .verifyBalance()
+        .accounts({
+          payer: authority.publicKey,
+          state: tokenState,
+          tokenAccount: userTokenAccount.address,
+          receipt,
+          accessOverride: accessOverridePda(user.publicKey),
+          gatingConfig,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          lpAccounts.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+        )
+        .preInstructions([await observe().instruction()])
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected an observation from the same slot to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "LpPoolUnobserved");
+    }
+
+    await observe().rpc();
+    assert.isTrue(await verify(userTokenAccount.address, { gatingConfig }, lpAccounts));
+  });
+
+  it("Lists verified members through the member registry", async () => {
+    const [memberRegistry] = await PublicKey.findProgramAddress(
+      [Buffer.from("member_registry")],
+      program.programId
+    );
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    const [member] = await PublicKey.findProgramAddress(
+      [Buffer.from("member"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeMemberRegistry()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        memberRegistry,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    await verify(authorityTokenAccount);
+    const sync = () =>
+      program.methods
+        .syncMember()
+        .accounts({
+          payer: authority.publicKey,
+          memberRegistry,
+          receipt,
+          member,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    await sync();
+    await sync();
+
+    const registry = await program.account.memberRegistry.fetch(memberRegistry);
+    assert.equal(registry.totalMembers.toNumber(), 1);
+    assert.equal(registry.activeMembers.toNumber(), 1);
+
+    // Active members are found by a memcmp on the active flag
+    const active = await program.account.memberRecord.all([
+      { memcmp: { offset: 48, bytes: anchor.utils.bytes.bs58.encode(Buffer.from([1])) } },
+    ]);
+    assert.ok(active.some(({ account }) => account.wallet.equals(authority.publicKey)));
+    assert.equal(active.find(({ publicKey }) => publicKey.equals(member))!.account.index.toNumber(), 0);
+  });
+
+  it("Reports access to calling programs through check_access", async () => {
+    const check = (wallet: PublicKey, minTier: number) =>
+      program.methods
+        .checkAccess(minTier)
+        .accounts({
+          wallet,
+          receipt: PublicKey.findProgramAddressSync(
+            [Buffer.from("receipt"), wallet.toBuffer()],
+            program.programId
+          )[0],
+          accessOverride: accessOverridePda(wallet),
+        })
+        .view();
+
+    await verify(authorityTokenAccount);
+    const status = await check(authority.publicKey, 1);
+    assert.isTrue(status.hasAccess);
+    assert.ok(status.wallet.equals(authority.publicKey));
+    assert.isAtLeast(status.tier, 1);
+
+    // A wallet that was never verified is reported, not rejected
+    const stranger = await check(Keypair.generate().publicKey, 1);
+    assert.isFalse(stranger.hasAccess);
+    assert.equal(stranger.tier, 0);
+  });
+
+  it("Gates on the minimum tier and the wallet's own receipt", async () => {
+    const receiptOf = (wallet: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), wallet.toBuffer()],
+        program.programId
+      )[0];
+    const check = (wallet: PublicKey, minTier: number) =>
+      program.methods
+        .checkAccess(minTier)
+        .accounts({
+          wallet,
+          receipt: receiptOf(wallet),
+          accessOverride: accessOverridePda(wallet),
+        })
+        .view();
+
+    await verify(authorityTokenAccount);
+    const { tier } = await check(authority.publicKey, 1);
+
+    // A tier above the receipt's is reported as no access, as #[aistm7_gated]
+    // with that min_tier would refuse the instruction
+    const above = await check(authority.publicKey, tier + 1);
+    assert.isFalse(above.hasAccess);
+    assert.equal(above.tier, tier);
+
+    // Another wallet cannot present the authority's receipt
+    const stranger = Keypair.generate().publicKey;
+    try {
+      await program.methods
+        .checkAccess(1)
+        .accounts({
+          wallet: stranger,
+          receipt: receiptOf(authority.publicKey),
+          accessOverride: accessOverridePda(stranger),
+        })
+        .rpc();
+      assert.fail("Expected another wallet's receipt to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ConstraintSeeds");
+    }
+  });
+
+  it("Records receipts from signed off-chain attestations", async () => {
+    const [roleRegistry] = await PublicKey.findProgramAddress(
+      [Buffer.from("role_registry")],
+      program.programId
+    );
+    const attestor = Keypair.generate();
+    await program.methods
+      .grantRole({ attestor: {} }, attestor.publicKey)
+      .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry })
+      .signers([authority])
+      .rpc();
+
+    const wallet = Keypair.generate().publicKey;
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), wallet.toBuffer()],
+      program.programId
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    const attestation = {
+      wallet,
+      tier: 1,
+      requirement: currentRequirement,
+      attestedAt: new anchor.BN(Math.floor(Date.now() / 1000) - 10),
+    };
+    const message = Buffer.concat([
+      Buffer.from("AISTM7 attestation v1"),
+      wallet.toBuffer(),
+      Buffer.from([attestation.tier]),
+      attestation.requirement.toArrayLike(Buffer, "le", 8),
+      attestation.attestedAt.toArrayLike(Buffer, "le", 8),
+    ]);
+    const submit = (signer: Keypair) =>
+      program.methods
+        .submitAttestation(attestation)
+        .accounts({
+          payer: authority.publicKey,
+          state: tokenState,
+          roleRegistry,
+          receipt,
+          accessOverride: accessOverridePda(wallet),
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: signer.secretKey,
+            message,
+          }),
+        ])
+        .signers([authority])
+        .rpc();
+
+    try {
+      await submit(Keypair.generate());
+      assert.fail("Expected an attestation from an unregistered key to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    await submit(attestor);
+    const recorded = await program.account.verificationReceipt.fetch(receipt);
+    assert.ok(recorded.wallet.equals(wallet));
+    assert.equal(recorded.tier, 1);
+    assert.equal(recorded.verifiedAt.toNumber(), attestation.attestedAt.toNumber());
+    assert.isTrue(recorded.expiresAt.gt(recorded.verifiedAt));
+
+    // The same attestation cannot be replayed
+    try {
+      await submit(attestor);
+      assert.fail("Expected a replayed attestation to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidAttestation");
+    }
+  });
+
+  it("Applies allow and deny overrides ahead of balances", async () => {
+    const reason = Array.from(createHash("sha256").update("case-1").digest());
+    const setOverride = (wallet: PublicKey, kind: object) =>
+      program.methods
+        .setAccessOverride(wallet, kind as any, reason)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          accessOverride: accessOverridePda(wallet),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    // An empty wallet is let in by an allow override
+    const partner = Keypair.generate();
+    const partnerTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      partner.publicKey
+    );
+    assert.isFalse(await verify(partnerTokenAccount.address));
+    await setOverride(partner.publicKey, { allow: {} });
+    assert.isTrue(await verify(partnerTokenAccount.address));
+
+    // A holder is shut out by a deny override until it is cleared
+    const holder = Keypair.generate();
+    const holderTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      holder.publicKey
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      holderTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString())
+    );
+    assert.isTrue(await verify(holderTokenAccount.address));
+    await setOverride(holder.publicKey, { deny: {} });
+    assert.isFalse(await verify(holderTokenAccount.address));
+
+    await program.methods
+      .clearAccessOverride()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        accessOverride: accessOverridePda(holder.publicKey),
+      })
+      .signers([authority])
+      .rpc();
+    assert.isTrue(await verify(holderTokenAccount.address));
+  });
+
+  it("Mints a soulbound access badge from a valid receipt", async () => {
+    const seed = (label: string) =>
+      PublicKey.findProgramAddress(
+        [Buffer.from(label), authority.publicKey.toBuffer()],
+        program.programId
+      ).then(([address]) => address);
+    const receipt = await seed("receipt");
+    const badge = await seed("access_badge");
+    const badgeMint = await seed("access_badge_mint");
+    const [badgeAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("badge_authority")],
+      program.programId
+    );
+    const badgeTokenAccount = await getAssociatedTokenAddress(badgeMint, authority.publicKey);
+
+    await verify(authorityTokenAccount);
+    await program.methods
+      .refreshAccessBadge()
+      .accounts({
+        wallet: authority.publicKey,
+        receipt,
+        badge,
+        badgeMint,
+        badgeTokenAccount,
+        badgeAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    const held = await getAccount(provider.connection, badgeTokenAccount);
+    assert.equal(held.amount, BigInt(1));
+    assert.isTrue(held.isFrozen);
+    const recorded = await program.account.accessBadge.fetch(badge);
+    const verified = await program.account.verificationReceipt.fetch(receipt);
+    assert.equal(recorded.tier, verified.tier);
+    assert.equal(recorded.expiresAt.toNumber(), verified.expiresAt.toNumber());
+
+    // The receipt is still valid, so the badge cannot be burned
+    try {
+      await program.methods
+        .revokeAccessBadge()
+        .accounts({
+          receipt,
+          badge,
+          badgeMint,
+          badgeTokenAccount,
+          badgeAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Expected burning a live badge to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AccessBadgeActive");
+    }
+  });
+
+  it("Tracks tokens burned through the program", async () => {
+    const before = await getAccount(provider.connection, authorityTokenAccount);
+    await program.methods
+      .burn(new anchor.BN(500))
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        mint,
+        tokenAccount: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const after = await getAccount(provider.connection, authorityTokenAccount);
+    assert.equal(before.amount - after.amount, BigInt(500));
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.totalBurned.toNumber(), 500);
+  });
+
+  it("Buys back and burns through the treasury vault", async () => {
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_vault")],
+      program.programId
+    );
+    await program.methods
+      .initializeTreasuryVault()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        treasuryVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    const before = await program.account.tokenState.fetch(tokenState);
+    await program.methods
+      .buybackAndBurn(new anchor.BN(1_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        treasuryVault,
+        source: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const after = await program.account.tokenState.fetch(tokenState);
+    assert.equal(after.totalBoughtBack.toNumber(), 1_000);
+    assert.equal(after.totalBurned.sub(before.totalBurned).toNumber(), 1_000);
+    const vault = await getAccount(provider.connection, treasuryVault);
+    assert.equal(vault.amount, BigInt(0));
+  });
+
+  it("Limits treasury withdrawals per epoch", async () => {
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const recipient = Keypair.generate().publicKey;
+    const lamports = anchor.web3.LAMPORTS_PER_SOL / 10;
+
+    await program.methods
+      .initializeTreasury(new anchor.BN(86_400))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        treasury,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .depositTreasurySol(new anchor.BN(lamports))
+      .accounts({
+        depositor: authority.publicKey,
+        treasury,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .setTreasuryLimit(PublicKey.default, new anchor.BN(lamports / 2))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        treasury,
+      })
+      .signers([authority])
+      .rpc();
+
+    const withdraw = (amount: number) =>
+      program.methods
+        .withdrawTreasurySol(new anchor.BN(amount))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          treasury,
+          recipient,
+        })
+        .signers([authority])
+        .rpc();
+
+    await withdraw(lamports / 2);
+    assert.equal(await provider.connection.getBalance(recipient), lamports / 2);
+    try {
+      await withdraw(1);
+      assert.fail("Expected the epoch limit to be enforced");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "TreasuryLimitExceeded");
+    }
+  });
+
+  it("Configures and toggles the protocol fee", async () => {
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const [feeVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), mint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeTreasuryTokenVault()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        treasury,
+        mint,
+        vault: feeVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await program.methods
+        .setFeeConfig(new anchor.BN(1_001))
+        .accounts({ authority: authority.publicKey, state: tokenState, feeDestination: feeVault })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a fee above the maximum to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+
+    await program.methods
+      .setFeeConfig(new anchor.BN(250))
+      .accounts({ authority: authority.publicKey, state: tokenState, feeDestination: feeVault })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .setFeesEnabled(true)
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.feeBps.toNumber(), 250);
+    assert.isTrue(state.feeDestination.equals(feeVault));
+    assert.isTrue(state.feesEnabled);
+  });
+
+  it("Settles OTC offers through escrow", async () => {
+    const taker = Keypair.generate();
+    const intruder = Keypair.generate();
+    const usdc = await createMint(provider.connection, authority, authority.publicKey, null, 6);
+    const paymentAccount = async (owner: PublicKey, amount: number) => {
+      const account = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        usdc,
+        owner
+      );
+      if (amount > 0) {
+        await mintTo(provider.connection, authority, usdc, account.address, authority, amount);
+      }
+      return account.address;
+    };
+    const makerPayment = await paymentAccount(authority.publicKey, 0);
+    const takerPayment = await paymentAccount(taker.publicKey, 10_000_000);
+    const intruderPayment = await paymentAccount(intruder.publicKey, 10_000_000);
+    const takerTokens = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      taker.publicKey
+    );
+
+    const offerPdas = (offerId: anchor.BN) => {
+      const [offer] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("otc_offer"),
+          authority.publicKey.toBuffer(),
+          offerId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      const [vault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("otc_vault"), offer.toBuffer()],
+        program.programId
+      );
+      return { offer, vault };
+    };
+    // 0.001 token at 5,000 USDC per token, for the taker only
+    const createOffer = async (offerId: anchor.BN) => {
+      const { offer, vault } = offerPdas(offerId);
+      await program.methods
+        .createOtcOffer(
+          offerId,
+          new anchor.BN(1_000_000),
+          new anchor.BN(5_000_000_000),
+          taker.publicKey,
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3_600),
+          new anchor.BN(0)
+        )
+        .accounts({
+          maker: authority.publicKey,
+          state: tokenState,
+          mint,
+          paymentMint: usdc,
+          offer,
+          vault,
+          source: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([authority])
+        .rpc();
+      return { offer, vault };
+    };
+    const accept = (
+      { offer, vault }: { offer: PublicKey; vault: PublicKey },
+      signer: Keypair,
+      paymentSource: PublicKey,
+      destination: PublicKey
+    ) =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          taker: signer.publicKey,
+          state: tokenState,
+          mint,
+          paymentMint: usdc,
+          offer,
+          maker: authority.publicKey,
+          vault,
+          paymentSource,
+          makerPaymentAccount: makerPayment,
+          destination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    const first = await createOffer(new anchor.BN(1));
+    const intruderTokens = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      intruder.publicKey
+    );
+    try {
+      await accept(first, intruder, intruderPayment, intruderTokens.address);
+      assert.fail("Expected a reserved offer to refuse other takers");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "OtcNotCounterparty");
+    }
+
+    await accept(first, taker, takerPayment, takerTokens.address);
+    const received = await getAccount(provider.connection, takerTokens.address);
+    assert.equal(received.amount, BigInt(1_000_000));
+    const paid = await getAccount(provider.connection, makerPayment);
+    assert.equal(paid.amount, BigInt(5_000_000));
+    assert.isNull(await provider.connection.getAccountInfo(first.offer));
+    assert.isNull(await provider.connection.getAccountInfo(first.vault));
+
+    const second = await createOffer(new anchor.BN(2));
+    const before = await getAccount(provider.connection, authorityTokenAccount);
+    await program.methods
+      .cancelOffer()
+      .accounts({
+        maker: authority.publicKey,
+        offer: second.offer,
+        vault: second.vault,
+        destination: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    const after = await getAccount(provider.connection, authorityTokenAccount);
+    assert.equal(after.amount - before.amount, BigInt(1_000_000));
+    assert.isNull(await provider.connection.getAccountInfo(second.offer));
+  });
+
+  it("Releases vested tokens linearly and refunds on revocation", async () => {
+    const beneficiary = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      beneficiary.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const beneficiaryTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      beneficiary.publicKey
+    );
+    const [vestingEscrow] = await PublicKey.findProgramAddress(
+      [Buffer.from("vesting_escrow")],
+      program.programId
+    );
+    const [vestingSchedule] = await PublicKey.findProgramAddress(
+      [Buffer.from("vesting"), beneficiary.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeVestingEscrow()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        vestingEscrow,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    // Halfway through a 1,000 second schedule with no cliff
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createVesting(
+        beneficiary.publicKey,
+        new anchor.BN(now - 500),
+        new anchor.BN(0),
+        new anchor.BN(1_000),
+        new anchor.BN(10_000),
+        true
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        vestingSchedule,
+        vestingEscrow,
+        funder: authorityTokenAccount,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .claimVested()
+      .accounts({
+        beneficiary: beneficiary.publicKey,
+        state: tokenState,
+        vestingSchedule,
+        vestingEscrow,
+        destination: beneficiaryTokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([beneficiary])
+      .rpc();
+
+    const claimed = (await getAccount(provider.connection, beneficiaryTokenAccount.address))
+      .amount;
+    assert.isTrue(claimed >= BigInt(4_000) && claimed < BigInt(10_000));
+
+    const revoke = () =>
+      program.methods
+        .revokeVesting()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          vestingSchedule,
+          vestingEscrow,
+          refundDestination: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    await revoke();
+    const schedule = await program.account.vestingSchedule.fetch(vestingSchedule);
+    assert.notEqual(schedule.revokedAt.toNumber(), 0);
+    try {
+      await revoke();
+      assert.fail("Expected a second revocation to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "VestingAlreadyRevoked");
+    }
+  });
+
+  it("Airdrops through a merkle distribution", async () => {
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (index: number, claimant: PublicKey, amount: number) => {
+      const indexBytes = Buffer.alloc(4);
+      indexBytes.writeUInt32LE(index);
+      return sha256(
+        Buffer.from([0]),
+        indexBytes,
+        claimant.toBuffer(),
+        new anchor.BN(amount).toArrayLike(Buffer, "le", 8)
+      );
+    };
+    const node = (a: Buffer, b: Buffer) =>
+      Buffer.compare(a, b) <= 0
+        ? sha256(Buffer.from([1]), a, b)
+        : sha256(Buffer.from([1]), b, a);
+
+    const other = Keypair.generate().publicKey;
+    const leaves = [leaf(0, authority.publicKey, 700), leaf(1, other, 300)];
+    const root = node(leaves[0], leaves[1]);
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    const [distribution] = await PublicKey.findProgramAddress(
+      [Buffer.from("distribution"), state.nextDistributionId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [distributionVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("distribution_vault"), distribution.toBuffer()],
+      program.programId
+    );
+    const chunk = Buffer.alloc(4);
+    const [claimBitmap] = await PublicKey.findProgramAddress(
+      [Buffer.from("claim_bitmap"), distribution.toBuffer(), chunk],
+      program.programId
+    );
+
+    await program.methods
+      .createDistribution(
+        Array.from(root),
+        new anchor.BN(1_000),
+        2,
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3_600)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        distribution,
+        distributionVault,
+        funder: authorityTokenAccount,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .initializeClaimBitmap(0)
+      .accounts({
+        payer: authority.publicKey,
+        distribution,
+        claimBitmap,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const claim = (amount: number) =>
+      program.methods
+        .claim(0, new anchor.BN(amount), [Array.from(leaves[1])])
+        .accounts({
+          claimant: authority.publicKey,
+          distribution,
+          claimBitmap,
+          distributionVault,
+          destination: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    try {
+      await claim(1_000);
+      assert.fail("Expected a claim for the wrong amount to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidProof");
+    }
+    await claim(700);
+    try {
+      await claim(700);
+      assert.fail("Expected a second claim to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AlreadyClaimed");
+    }
+
+    const vault = await getAccount(provider.connection, distributionVault);
+    assert.equal(vault.amount, BigInt(300));
+  });
+
+  it("Shares epoch revenue through merkle claims", async () => {
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (index: number, holder: PublicKey, amount: number) => {
+      const indexBytes = Buffer.alloc(4);
+      indexBytes.writeUInt32LE(index);
+      return sha256(
+        Buffer.from([0]),
+        indexBytes,
+        holder.toBuffer(),
+        new anchor.BN(amount).toArrayLike(Buffer, "le", 8)
+      );
+    };
+    const node = (a: Buffer, b: Buffer) =>
+      Buffer.compare(a, b) <= 0
+        ? sha256(Buffer.from([1]), a, b)
+        : sha256(Buffer.from([1]), b, a);
+
+    const other = Keypair.generate().publicKey;
+    const leaves = [leaf(0, other, 400), leaf(1, authority.publicKey, 600)];
+    const root = node(leaves[0], leaves[1]);
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    const [revenueEpoch] = await PublicKey.findProgramAddress(
+      [Buffer.from("revenue_epoch"), state.nextRevenueEpoch.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [revenueVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("revenue_vault"), revenueEpoch.toBuffer()],
+      program.programId
+    );
+    const [claimBitmap] = await PublicKey.findProgramAddress(
+      [Buffer.from("claim_bitmap"), revenueEpoch.toBuffer(), Buffer.alloc(4)],
+      program.programId
+    );
+
+    const slot = await provider.connection.getSlot();
+    await program.methods
+      .publishRevenueRoot(Array.from(root), new anchor.BN(slot), new anchor.BN(1_000), 2)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        revenueMint: mint,
+        revenueEpoch,
+        revenueVault,
+        funder: authorityTokenAccount,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .initializeRevenueBitmap(0)
+      .accounts({
+        payer: authority.publicKey,
+        revenueEpoch,
+        claimBitmap,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const claim = (index: number, amount: number) =>
+      program.methods
+        .claimRevenue(index, new anchor.BN(amount), [Array.from(leaves[0])])
+        .accounts({
+          holder: authority.publicKey,
+          revenueEpoch,
+          claimBitmap,
+          revenueVault,
+          destination: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    try {
+      await claim(0, 600);
+      assert.fail("Expected a claim under the wrong index to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidProof");
+    }
+    await claim(1, 600);
+    try {
+      await claim(1, 600);
+      assert.fail("Expected a second claim to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AlreadyClaimed");
+    }
+
+    const epoch = await program.account.revenueEpoch.fetch(revenueEpoch);
+    assert.equal(epoch.claimedAmount.toNumber(), 600);
+    const vault = await getAccount(provider.connection, revenueVault);
+    assert.equal(vault.amount, BigInt(400));
+  });
+
+  it("Passes parameter changes by snapshot-weighted holder votes", async () => {
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (index: number, voter: PublicKey, weight: number) => {
+      const indexBytes = Buffer.alloc(4);
+      indexBytes.writeUInt32LE(index);
+      return sha256(
+        Buffer.from([0]),
+        indexBytes,
+        voter.toBuffer(),
+        new anchor.BN(weight).toArrayLike(Buffer, "le", 8)
+      );
+    };
+    const node = (a: Buffer, b: Buffer) =>
+      Buffer.compare(a, b) <= 0
+        ? sha256(Buffer.from([1]), a, b)
+        : sha256(Buffer.from([1]), b, a);
+
+    const latecomer = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(
+      latecomer.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(signature);
+    const leaves = [leaf(0, authority.publicKey, 600), leaf(1, latecomer.publicKey, 400)];
+    const root = node(leaves[0], leaves[1]);
+
+    const [governanceConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("governance_config")],
+      program.programId
+    );
+    await program.methods
+      .initializeGovernance(new anchor.BN(2), new anchor.BN(5_000), new anchor.BN(1_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        governanceConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    // Snapshots stand once a guardian quorum approves them
+    const guardians = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const [guardianCouncil] = await PublicKey.findProgramAddress(
+      [Buffer.from("guardian_council")],
+      program.programId
+    );
+    await program.methods
+      .appointGuardianCouncil(
+        guardians.map((guardian) => guardian.publicKey),
+        2,
+        new anchor.BN(86_400)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        guardianCouncil,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const proposalPda = async (id: number) =>
+      (
+        await PublicKey.findProgramAddress(
+          [Buffer.from("proposal"), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+          program.programId
+        )
+      )[0];
+    const propose = (proposer: Keypair, proposerTokenAccount: PublicKey, proposal: PublicKey) =>
+      program.methods
+        .createProposal({
+          parameters: { 0: { ...NO_CHANGES, maxStepBps: new anchor.BN(300) } },
+        })
+        .accounts({
+          proposer: proposer.publicKey,
+          state: tokenState,
+          governanceConfig,
+          proposerTokenAccount,
+          stakeAccount: null,
+          proposal,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc();
+    const recordSnapshot = (proposal: PublicKey, approvers: Keypair[]) =>
+      program.methods
+        .recordSnapshot(Array.from(root), new anchor.BN(1_000))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          governanceConfig,
+          proposal,
+          guardianCouncil,
+          roleRegistry: null,
+        })
+        .remainingAccounts(
+          approvers.map((guardian) => ({
+            pubkey: guardian.publicKey,
+            isSigner: true,
+            isWritable: false,
+          }))
+        )
+        .signers([authority, ...approvers])
+        .rpc();
+
+    const proposal = await proposalPda(0);
+    const latecomerTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      latecomer.publicKey
+    );
+    try {
+      await propose(latecomer, latecomerTokenAccount.address, proposal);
+      assert.fail("Expected a proposer below the threshold to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProposalThresholdNotMet");
+    }
+    await propose(authority, authorityTokenAccount, proposal);
+
+    const vote = (voter: Keypair, index: number, weight: number, sibling: Buffer) => {
+      const [voteRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vote"), proposal.toBuffer(), voter.publicKey.toBuffer()],
+        program.programId
+      );
+      return program.methods
+        .castVote(true, index, new anchor.BN(weight), [Array.from(sibling)])
+        .accounts({
+          voter: voter.publicKey,
+          proposal,
+          voteRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+    const execute = () =>
+      program.methods
+        .executeProposal()
+        .accounts({
+          executor: provider.wallet.publicKey,
+          state: tokenState,
+          governanceConfig,
+          proposal,
+          priceFeed: null,
+        })
+        .rpc();
+
+    // Votes wait for the balances at the proposal's slot to be recorded
+    try {
+      await vote(authority, 0, 600, leaves[1]);
+      assert.fail("Expected a vote before the snapshot to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SnapshotNotRecorded");
+    }
+    try {
+      await recordSnapshot(proposal, guardians.slice(0, 1));
+      assert.fail("Expected a snapshot without a guardian quorum to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "GuardianThresholdNotMet");
+    }
+    await recordSnapshot(proposal, guardians.slice(0, 2));
+    const created = await program.account.proposal.fetch(proposal);
+    assert.isAtMost(created.snapshotSlot.toNumber(), await provider.connection.getSlot());
+    assert.deepEqual(created.voteRoot, Array.from(root));
+    assert.equal(created.totalWeight.toNumber(), 1_000);
+
+    // The recorded weights cannot be swapped out once voting has begun
+    try {
+      await recordSnapshot(proposal, guardians.slice(1, 3));
+      assert.fail("Expected a second snapshot to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SnapshotAlreadyRecorded");
+    }
+
+    // Weight counts only for the wallet that held it at the snapshot
+    try {
+      await vote(latecomer, 0, 600, leaves[1]);
+      assert.fail("Expected a vote with another holder's snapshot weight to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidProof");
+    }
+
+    try {
+      await vote(authority, 0, 1_000, leaves[1]);
+      assert.fail("Expected a vote above the snapshot weight to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidProof");
+    }
+    await vote(authority, 0, 600, leaves[1]);
+
+    try {
+      await execute();
+      assert.fail("Expected execution while voting is open to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProposalVotingOpen");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3_000));
+    try {
+      await vote(latecomer, 1, 400, leaves[0]);
+      assert.fail("Expected a vote after the voting period to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProposalVotingClosed");
+    }
+
+    await execute();
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.equal(state.maxStepBps.toNumber(), 300);
+    const passed = await program.account.proposal.fetch(proposal);
+    assert.isTrue(passed.executed);
+    assert.equal(passed.yesWeight.toNumber(), 600);
+
+    try {
+      await execute();
+      assert.fail("Expected a proposal to execute only once");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProposalAlreadyExecuted");
+    }
+
+    // A vetoed proposal takes no more votes
+    const vetoed = await proposalPda(1);
+    await propose(authority, authorityTokenAccount, vetoed);
+    await recordSnapshot(vetoed, guardians.slice(1, 3));
+    await program.methods
+      .guardianVetoProposal()
+      .accounts({ guardianCouncil, proposal: vetoed })
+      .remainingAccounts(
+        guardians.slice(0, 2).map((guardian) => ({
+          pubkey: guardian.publicKey,
+          isSigner: true,
+          isWritable: false,
+        }))
+      )
+      .signers(guardians.slice(0, 2))
+      .rpc();
+    const [vetoedVote] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vote"), vetoed.toBuffer(), authority.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .castVote(true, 0, new anchor.BN(600), [Array.from(leaves[1])])
+        .accounts({
+          voter: authority.publicKey,
+          proposal: vetoed,
+          voteRecord: vetoedVote,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a vote on a vetoed proposal to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ProposalVetoed");
+    }
+
+    await program.methods
+      .updateParameters({ ...NO_CHANGES, maxStepBps: new anchor.BN(0) })
+      .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry: null })
+      .signers([authority])
+      .rpc();
+  });
+
+  it("Lets a guardian quorum veto queued changes and pause for a bounded time", async () => {
+    const guardians = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const [guardianCouncil] = await PublicKey.findProgramAddress(
+      [Buffer.from("guardian_council")],
+      program.programId
+    );
+    await program.methods
+      .appointGuardianCouncil(
+        guardians.map((guardian) => guardian.publicKey),
+        2,
+        new anchor.BN(86_400)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        guardianCouncil,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    const approvals = (signers: Keypair[]) =>
+      signers.map((guardian) => ({
+        pubkey: guardian.publicKey,
+        isSigner: true,
+        isWritable: false,
+      }));
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    const [pendingChange] = await PublicKey.findProgramAddress(
+      [Buffer.from("pending_change"), state.nextChangeId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .queuePendingChange({
+        parameters: { 0: { ...NO_CHANGES, maxStepBps: new anchor.BN(900) } },
+      })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        pendingChange,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const veto = (signers: Keypair[]) =>
+      program.methods
+        .guardianVetoChange()
+        .accounts({ guardianCouncil, pendingChange, proposer: authority.publicKey })
+        .remainingAccounts(approvals(signers))
+        .signers(signers)
+        .rpc();
+    try {
+      await veto(guardians.slice(0, 1));
+      assert.fail("Expected a single guardian to fall short of the threshold");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "GuardianThresholdNotMet");
+    }
+    try {
+      await veto([guardians[0], Keypair.generate()]);
+      assert.fail("Expected an outsider's approval to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "NotAGuardian");
+    }
+    await veto(guardians.slice(1, 3));
+    assert.isNull(await provider.connection.getAccountInfo(pendingChange));
+
+    await program.methods
+      .guardianPause()
+      .accounts({ guardianCouncil, state: tokenState })
+      .remainingAccounts(approvals(guardians.slice(0, 2)))
+      .signers(guardians.slice(0, 2))
+      .rpc();
+    assert.isTrue((await program.account.tokenState.fetch(tokenState)).paused);
+    const council = await program.account.guardianCouncil.fetch(guardianCouncil);
+    assert.isAbove(council.pausedUntil.toNumber(), 0);
+
+    // The pause holds until it expires, though operators can end it sooner
+    try {
+      await program.methods
+        .liftGuardianPause()
+        .accounts({ payer: provider.wallet.publicKey, guardianCouncil, state: tokenState })
+        .rpc();
+      assert.fail("Expected the guardian pause to hold until it expires");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "GuardianPauseActive");
+    }
+    await program.methods
+      .unpause()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        guardianCouncil: guardianCouncilPda,
+      })
+      .signers([authority])
+      .rpc();
+    assert.isFalse((await program.account.tokenState.fetch(tokenState)).paused);
+    // The operators took the pause over, so it is no longer the council's
+    // to lift
+    const lifted = await program.account.guardianCouncil.fetch(guardianCouncil);
+    assert.equal(lifted.pausedUntil.toNumber(), 0);
+    assert.isFalse(lifted.holdsPause);
+  });
+
+  it("Stakes, accrues rewards, and unstakes", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const stakeAccount = await pda(Buffer.from("stake"), authority.publicKey.toBuffer());
+    // The protocol fee enabled earlier applies to staking deposits
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    await program.methods
+      .initializeStaking()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        stakePool,
+        stakeVault,
+        rewardVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .openStakeAccount()
+      .accounts({
+        owner: authority.publicKey,
+        stakePool,
+        stakeAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .stake(new anchor.BN(10_000), NO_LOCK)
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        source: authorityTokenAccount,
+        stakeVault,
+        rewardVault,
+        feeDestination: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    let stake = await program.account.stakeAccount.fetch(stakeAccount);
+    assert.equal(stake.amount.toNumber(), 9_750);
+
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      rewardVault,
+      authority,
+      1_000
+    );
+    await program.methods
+      .claimRewards()
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        rewardVault,
+        destination: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    const rewardsLeft = (await getAccount(provider.connection, rewardVault)).amount;
+    assert.isTrue(rewardsLeft <= BigInt(1));
+
+    // No cooldown, so the request can be withdrawn straight away
+    await program.methods
+      .setStakingConfig(new anchor.BN(0), new anchor.BN(0), false)
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .requestUnstake(new anchor.BN(9_750))
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        rewardVault,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .withdrawUnstaked()
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakeAccount,
+        stakeVault,
+        destination: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    stake = await program.account.stakeAccount.fetch(stakeAccount);
+    assert.equal(stake.amount.toNumber(), 0);
+    assert.equal(stake.unstakingAmount.toNumber(), 0);
+  });
+
+  it("Locks stake for a tier and boosts its weight", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const stakeAccount = await pda(Buffer.from("stake"), authority.publicKey.toBuffer());
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    // 30 day tier at 1.1x
+    await program.methods
+      .stake(new anchor.BN(10_000), 0)
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        source: authorityTokenAccount,
+        stakeVault,
+        rewardVault,
+        feeDestination: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const stake = await program.account.stakeAccount.fetch(stakeAccount);
+    assert.equal(stake.lockTier, 0);
+    assert.equal(stake.weight.toNumber(), 10_725);
+
+    try {
+      await program.methods
+        .requestUnstake(stake.amount)
+        .accounts({
+          owner: authority.publicKey,
+          state: tokenState,
+          stakePool,
+          stakeAccount,
+          rewardVault,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected locked stake to stay put");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "StakeLocked");
+    }
+
+    try {
+      await program.methods
+        .setLockTiers([
+          { durationSecs: new anchor.BN(90), rewardMultiplierBps: new anchor.BN(11_000), boostBps: new anchor.BN(11_000) },
+          { durationSecs: new anchor.BN(30), rewardMultiplierBps: new anchor.BN(12_000), boostBps: new anchor.BN(12_000) },
+          { durationSecs: new anchor.BN(180), rewardMultiplierBps: new anchor.BN(15_000), boostBps: new anchor.BN(15_000) },
+          { durationSecs: new anchor.BN(365), rewardMultiplierBps: new anchor.BN(20_000), boostBps: new anchor.BN(20_000) },
+        ])
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected tiers out of order to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidLockTier");
+    }
+  });
+
+  it("Charges a penalty for leaving a lock early", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const stakeAccount = await pda(Buffer.from("stake"), authority.publicKey.toBuffer());
+
+    // 10% penalty, paid to the reward vault
+    await program.methods
+      .setStakingConfig(new anchor.BN(7 * 86_400), new anchor.BN(1_000), false)
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+
+    const stake = await program.account.stakeAccount.fetch(stakeAccount);
+    const rewardsBefore = (await getAccount(provider.connection, rewardVault)).amount;
+    await program.methods
+      .earlyExit(stake.amount)
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        mint,
+        stakePool,
+        stakeAccount,
+        stakeVault,
+        rewardVault,
+        destination: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const rewardsAfter = (await getAccount(provider.connection, rewardVault)).amount;
+    assert.equal(rewardsAfter - rewardsBefore, BigInt(stake.amount.toNumber() / 10));
+    const after = await program.account.stakeAccount.fetch(stakeAccount);
+    assert.equal(after.amount.toNumber(), 0);
+  });
+
+  it("Compounds rewards back into the stake", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const stakeAccount = await pda(Buffer.from("stake"), authority.publicKey.toBuffer());
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    await program.methods
+      .stake(new anchor.BN(1_000), NO_LOCK)
+      .accounts({
+        owner: authority.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        source: authorityTokenAccount,
+        stakeVault,
+        rewardVault,
+        feeDestination: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      rewardVault,
+      authority,
+      500
+    );
+
+    const before = await program.account.stakeAccount.fetch(stakeAccount);
+    // Permissionless: the provider wallet pays, the owner does not sign
+    await program.methods
+      .compoundRewards()
+      .accounts({
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        rewardVault,
+        stakeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const after = await program.account.stakeAccount.fetch(stakeAccount);
+    assert.isTrue(after.amount.sub(before.amount).gten(500));
+    assert.equal(after.pendingRewards.toNumber(), 0);
+  });
+
+  it("Counts boosted stake towards the balance requirement", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    const stakeAccount = await pda(Buffer.from("stake"), user.publicKey.toBuffer());
+
+    // Staking exactly the requirement leaves the wallet empty, and the fee
+    // leaves the stake itself short until the 1.1x lock boost applies
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      BigInt(currentRequirement.toString())
+    );
+    await program.methods
+      .openStakeAccount()
+      .accounts({
+        owner: user.publicKey,
+        stakePool,
+        stakeAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    await program.methods
+      .stake(currentRequirement, 0)
+      .accounts({
+        owner: user.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        source: userTokenAccount.address,
+        stakeVault,
+        rewardVault,
+        feeDestination: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    assert.isFalse(await verify(userTokenAccount.address, { stakeAccount: null }));
+    assert.isTrue(await verify(userTokenAccount.address, { stakeAccount }));
+  });
+
+  it("Refuses to close the state while stake is open", async () => {
+    const [stakePool] = await PublicKey.findProgramAddress(
+      [Buffer.from("stake_pool")],
+      program.programId
+    );
+    assert.isAbove((await program.account.stakePool.fetch(stakePool)).totalStaked.toNumber(), 0);
+
+    try {
+      await program.methods
+        .closeState()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          mint,
+          recipient: authority.publicKey,
+          customPriceFeed: null,
+          priceAccumulator: null,
+          requirementHistory: null,
+          roleRegistry: null,
+          stakePool,
+          keeperVault: null,
+          recipientTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected close to fail while tokens are staked");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "StakeStillOpen");
+    }
+  });
+
+  it("Pays referrers a share of the fees their referrals generate", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      10_000
+    );
+
+    const referralOf = (key: PublicKey) => pda(Buffer.from("referral"), key.toBuffer());
+    const registerAs = async (signer: Keypair, referrer: PublicKey) =>
+      program.methods
+        .registerReferral(referrer)
+        .accounts({
+          user: signer.publicKey,
+          referral: await referralOf(signer.publicKey),
+          referrerReferral: await referralOf(referrer),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+
+    try {
+      await registerAs(user, user.publicKey);
+      assert.fail("Expected a self-referral to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SelfReferral");
+    }
+    await registerAs(user, authority.publicKey);
+    try {
+      await registerAs(authority, user.publicKey);
+      assert.fail("Expected a referral loop to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SelfReferral");
+    }
+
+    await program.methods
+      .setReferralConfig(new anchor.BN(5_000))
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+
+    const stakeAccount = await pda(Buffer.from("stake"), user.publicKey.toBuffer());
+    await program.methods
+      .openStakeAccount()
+      .accounts({
+        owner: user.publicKey,
+        stakePool,
+        stakeAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const referrerBefore = await getAccount(provider.connection, authorityTokenAccount);
+    const feeBefore = await getAccount(provider.connection, feeVault);
+    await program.methods
+      .stake(new anchor.BN(10_000), NO_LOCK)
+      .accounts({
+        owner: user.publicKey,
+        state: tokenState,
+        stakePool,
+        stakeAccount,
+        source: userTokenAccount.address,
+        stakeVault,
+        rewardVault,
+        feeDestination: feeVault,
+        referral: await referralOf(user.publicKey),
+        referrerTokenAccount: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    // 2.5% fee on 10,000, split evenly with the referrer
+    const referrerAfter = await getAccount(provider.connection, authorityTokenAccount);
+    const feeAfter = await getAccount(provider.connection, feeVault);
+    assert.equal(referrerAfter.amount - referrerBefore.amount, BigInt(125));
+    assert.equal(feeAfter.amount - feeBefore.amount, BigInt(125));
+    const referral = await program.account.referral.fetch(await referralOf(user.publicKey));
+    assert.equal(referral.rewardsPaid.toNumber(), 125);
+  });
+
+  it("Grants access for the term of a subscription", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    const accessPass = await pda(Buffer.from("access_pass"), user.publicKey.toBuffer());
+    const subscriptionVault = await pda(
+      Buffer.from("subscription_vault"),
+      user.publicKey.toBuffer()
+    );
+
+    // The requirement plus the 2.5% fee charged on top of it
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    const requirement = BigInt(currentRequirement.toString());
+    const fee = (requirement * BigInt(250)) / BigInt(10_000);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      requirement + fee
+    );
+
+    await program.methods
+      .subscribe(new anchor.BN(30 * 86_400))
+      .accounts({
+        owner: user.publicKey,
+        state: tokenState,
+        mint,
+        accessPass,
+        subscriptionVault,
+        source: userTokenAccount.address,
+        feeDestination: feeVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([user])
+      .rpc();
+
+    const vault = await getAccount(provider.connection, subscriptionVault);
+    assert.equal(vault.amount, requirement);
+    const wallet = await getAccount(provider.connection, userTokenAccount.address);
+    assert.equal(wallet.amount, BigInt(0));
+
+    assert.isTrue(await verify(userTokenAccount.address, { accessPass }));
+
+    try {
+      await program.methods
+        .unsubscribe()
+        .accounts({
+          owner: user.publicKey,
+          accessPass,
+          subscriptionVault,
+          destination: userTokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Expected unsubscribing before expiry to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SubscriptionActive");
+    }
+  });
+
+  it("Streams payments to the treasury while granting access", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const streamConfig = await pda(Buffer.from("stream_config"));
+    const treasuryVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    const deposit = BigInt(1_000_000);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      deposit
+    );
+    const paymentStream = await pda(Buffer.from("payment_stream"), user.publicKey.toBuffer());
+    const streamVault = await pda(Buffer.from("stream_vault"), paymentStream.toBuffer());
+
+    await program.methods
+      .setStreamRate(mint, new anchor.BN(1_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        streamConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const openStream = (rate: number) =>
+      program.methods
+        .openStream(new anchor.BN(rate), new anchor.BN(deposit.toString()))
+        .accounts({
+          payer: user.publicKey,
+          state: tokenState,
+          streamConfig,
+          mint,
+          stream: paymentStream,
+          streamVault,
+          source: userTokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
+
+    try {
+      await openStream(999);
+      assert.fail("Expected a rate below the minimum to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "StreamRateTooLow");
+    }
+    await openStream(1_000);
+    assert.isTrue(await verify(userTokenAccount.address, { paymentStream }));
+
+    await new Promise((resolve) => setTimeout(resolve, 2_000));
+    const before = (await getAccount(provider.connection, treasuryVault)).amount;
+    await program.methods
+      .withdrawStream()
+      .accounts({
+        stream: paymentStream,
+        streamVault,
+        treasuryVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const { withdrawn } = await program.account.paymentStream.fetch(paymentStream);
+    assert.isTrue(withdrawn.gtn(0));
+    const afterWithdraw = (await getAccount(provider.connection, treasuryVault)).amount;
+    assert.equal(afterWithdraw - before, BigInt(withdrawn.toString()));
+
+    await program.methods
+      .closeStream()
+      .accounts({
+        payer: user.publicKey,
+        stream: paymentStream,
+        streamVault,
+        treasuryVault,
+        destination: userTokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    // Whatever the treasury was not paid comes back to the payer
+    const paid = (await getAccount(provider.connection, treasuryVault)).amount - before;
+    const refund = (await getAccount(provider.connection, userTokenAccount.address)).amount;
+    assert.isTrue(refund > BigInt(0));
+    assert.equal(paid + refund, deposit);
+    assert.isNull(await provider.connection.getAccountInfo(paymentStream));
+  });
+
+  it("Renews recurring subscriptions from a delegated allowance", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const planId = 1;
+    const planBytes = Buffer.alloc(4);
+    planBytes.writeUInt32LE(planId);
+    const plan = await pda(Buffer.from("subscription_plan"), planBytes);
+    const treasuryVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+    const price = BigInt(50_000);
+    const period = 30 * 86_400;
+
+    await program.methods
+      .setSubscriptionPlan(planId, {
+        mint,
+        price: new anchor.BN(price.toString()),
+        periodSecs: new anchor.BN(period),
+        // Renewals can be charged for the whole period, so the test needn't wait
+        renewalWindowSecs: new anchor.BN(period),
+        active: true,
+      })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        plan,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      price * BigInt(2)
+    );
+    const recurringSubscription = await pda(
+      Buffer.from("recurring_subscription"),
+      user.publicKey.toBuffer()
+    );
+
+    const before = (await getAccount(provider.connection, treasuryVault)).amount;
+    await program.methods
+      .createSubscription(planId)
+      .accounts({
+        subscriber: user.publicKey,
+        state: tokenState,
+        plan,
+        subscription: recurringSubscription,
+        source: userTokenAccount.address,
+        treasuryVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    const created = await program.account.recurringSubscription.fetch(recurringSubscription);
+    assert.isTrue(await verify(userTokenAccount.address, { recurringSubscription }));
+
+    const processRenewal = () =>
+      program.methods
+        .processRenewal()
+        .accounts({
+          subscription: recurringSubscription,
+          plan,
+          source: userTokenAccount.address,
+          treasuryVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    // Nothing is charged until the subscriber approves the allowance
+    try {
+      await processRenewal();
+      assert.fail("Expected a renewal without an allowance to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "RenewalNotAuthorized");
+    }
+
+    await approve(
+      provider.connection,
+      user,
+      userTokenAccount.address,
+      recurringSubscription,
+      user,
+      price * BigInt(12)
+    );
+    await processRenewal();
+    const renewed = await program.account.recurringSubscription.fetch(recurringSubscription);
+    assert.equal(renewed.renewals.toNumber(), 1);
+    assert.equal(renewed.nextRenewalAt.sub(created.nextRenewalAt).toNumber(), period);
+    const after = (await getAccount(provider.connection, treasuryVault)).amount;
+    assert.equal(after - before, price * BigInt(2));
+
+    // The next window only opens once the renewed period begins
+    try {
+      await processRenewal();
+      assert.fail("Expected a second renewal in the same period to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "RenewalNotDue");
+    }
+  });
+
+  it("Pays bounties out of treasury escrow at the arbiter's word", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const treasury = await pda(Buffer.from("treasury"));
+    const treasuryVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+    const bountyId = new anchor.BN(1);
+    const bounty = await pda(Buffer.from("bounty"), bountyId.toArrayLike(Buffer, "le", 8));
+    const bountyVault = await pda(Buffer.from("bounty_vault"), bounty.toBuffer());
+    const amount = 1_000_000;
+
+    await program.methods
+      .depositTreasuryTokens(new anchor.BN(amount))
+      .accounts({
+        depositor: authority.publicKey,
+        source: authorityTokenAccount,
+        vault: treasuryVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .setTreasuryLimit(mint, new anchor.BN(amount))
+      .accounts({ authority: authority.publicKey, state: tokenState, treasury })
+      .signers([authority])
+      .rpc();
+
+    const arbiter = Keypair.generate();
+    const deadline = Math.floor(Date.now() / 1000) + 86_400;
+    await program.methods
+      .createBounty(bountyId, new anchor.BN(amount), new anchor.BN(deadline), arbiter.publicKey)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        treasury,
+        treasuryVault,
+        mint,
+        bounty,
+        bountyVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    assert.equal((await getAccount(provider.connection, bountyVault)).amount, BigInt(amount));
+
+    const claimant = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      claimant.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const claimantTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      claimant,
+      mint,
+      claimant.publicKey
+    );
+    const claim = await pda(
+      Buffer.from("bounty_claim"),
+      bounty.toBuffer(),
+      claimant.publicKey.toBuffer()
+    );
+    const submissionHash = Array.from(
+      createHash("sha256").update("https://example.com/pull/1").digest()
+    );
+    await program.methods
+      .submitClaim(submissionHash)
+      .accounts({
+        claimant: claimant.publicKey,
+        bounty,
+        claim,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([claimant])
+      .rpc();
+
+    const payout = (signer: Keypair, payoutAmount: number) =>
+      program.methods
+        .approvePayout(new anchor.BN(payoutAmount))
+        .accounts({
+          arbiter: signer.publicKey,
+          bounty,
+          claim,
+          bountyVault,
+          destination: claimantTokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    try {
+      await payout(claimant, amount);
+      assert.fail("Expected only the arbiter to approve payouts");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    // A partial payout leaves the rest in escrow for other claims
+    await payout(arbiter, amount / 4);
+    const paid = await program.account.bountyClaim.fetch(claim);
+    assert.equal(paid.paid.toNumber(), amount / 4);
+    const claimantAccount = await getAccount(provider.connection, claimantTokenAccount.address);
+    assert.equal(claimantAccount.amount, BigInt(amount / 4));
+    try {
+      await payout(arbiter, amount);
+      assert.fail("Expected payouts to be held to the escrow");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InsufficientBountyEscrow");
+    }
+
+    try {
+      await program.methods
+        .reclaimExpired()
+        .accounts({
+          bounty,
+          creator: authority.publicKey,
+          bountyVault,
+          treasuryVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Expected reclaiming before the review period ends to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "BountyNotExpired");
+    }
+  });
+
+  it("Pins treasury swaps to Jupiter with an oracle floor and a volume cap", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const jupiter = new PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+    const treasury = await pda(Buffer.from("treasury"));
+    const swapConfig = await pda(Buffer.from("swap_config"));
+    const outputVault = await pda(Buffer.from("treasury_vault"));
+    const usdc = await createMint(provider.connection, authority, authority.publicKey, null, 6);
+    const inputVault = await pda(Buffer.from("treasury_token_vault"), usdc.toBuffer());
+    await program.methods
+      .initializeTreasuryTokenVault()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        treasury,
+        mint: usdc,
+        vault: inputVault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+    await mintTo(provider.connection, authority, usdc, inputVault, authority, 1_000_000_000);
+
+    const expectError = async (call: Promise<string>, code: string) => {
+      try {
+        await call;
+        assert.fail(`Expected ${code}`);
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, code);
+      }
+    };
+    const configure = (swapProgram: PublicKey, maxSlippageBps: number) =>
+      program.methods
+        .configureTreasurySwap(
+          swapProgram,
+          usdc,
+          new anchor.BN(500_000_000),
+          new anchor.BN(maxSlippageBps)
+        )
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          swapConfig,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    // A route through any other program could spend whatever the treasury
+    // signs for, so only Jupiter is accepted, and never without a floor
+    await expectError(configure(TOKEN_PROGRAM_ID, 100), "UnsupportedSwapProgram");
+    await expectError(configure(jupiter, 0), "InvalidParameter");
+    await configure(jupiter, 100);
+    const config = await program.account.swapConfig.fetch(swapConfig);
+    assert.isTrue(config.swapProgram.equals(jupiter));
+    assert.equal(config.maxSlippageBps.toNumber(), 100);
+
+    const swap = (amountIn: number, swapProgram: PublicKey) =>
+      program.methods
+        .treasurySwap(new anchor.BN(amountIn), new anchor.BN(0), Buffer.alloc(0))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          mint,
+          treasury,
+          swapConfig,
+          inputMint: usdc,
+          inputVault,
+          outputVault,
+          swapProgram,
+        })
+        .signers([authority])
+        .rpc();
+    await expectError(swap(100_000_000, TOKEN_PROGRAM_ID), "ConstraintAddress");
+    await expectError(swap(600_000_000, jupiter), "SwapVolumeExceeded");
+    assert.equal((await getAccount(provider.connection, inputVault)).amount, BigInt(1_000_000_000));
+  });
+
+  it("Only lets crankers run DCA buybacks once a tranche is due", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const treasury = await pda(Buffer.from("treasury"));
+    const swapConfig = await pda(Buffer.from("swap_config"));
+    const dcaSchedule = await pda(Buffer.from("dca_schedule"));
+    const { inputMint, swapProgram } = await program.account.swapConfig.fetch(swapConfig);
+
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .setDcaSchedule(
+        new anchor.BN(10_000_000),
+        new anchor.BN(3_600),
+        new anchor.BN(50_000_000),
+        new anchor.BN(now + 3_600)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        dcaSchedule,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    const schedule = await program.account.dcaSchedule.fetch(dcaSchedule);
+    assert.equal(schedule.spent.toNumber(), 0);
+    assert.equal(schedule.nextExecutionAt.toNumber(), now + 3_600);
+
+    const crank = async (cranker: Keypair) =>
+      program.methods
+        .executeDcaBuyback(new anchor.BN(0), Buffer.alloc(0))
+        .accounts({
+          authority: cranker.publicKey,
+          state: tokenState,
+          mint,
+          treasury,
+          swapConfig,
+          dcaSchedule,
+          inputMint,
+          inputVault: await pda(Buffer.from("treasury_token_vault"), inputMint.toBuffer()),
+          outputVault: await pda(Buffer.from("treasury_vault")),
+          swapProgram,
+          roleRegistry: null,
+        })
+        .signers([cranker])
+        .rpc();
+
+    // The cranker picks the route the treasury signs, so a wallet without
+    // the Cranker role cannot run a tranche
+    try {
+      await crank(Keypair.generate());
+      assert.fail("Expected a DCA buyback from a non-cranker to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+    try {
+      await crank(authority);
+      assert.fail("Expected a DCA buyback before its first tranche to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "DcaNotDue");
+    }
+  });
+
+  it("Keeps the treasury's liquidity position within its limits", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const whirlpoolProgram = new PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+    const treasury = await pda(Buffer.from("treasury"));
+    const liquidityConfig = await pda(Buffer.from("liquidity_config"));
+    const whirlpool = Keypair.generate().publicKey;
+
+    await program.methods
+      .setLiquidityLimits(whirlpool, -1_024, 1_024, new anchor.BN(1_000_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        liquidityConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    const config = await program.account.liquidityConfig.fetch(liquidityConfig);
+    assert.isTrue(config.whirlpool.equals(whirlpool));
+    assert.equal(config.maxLiquidity.toNumber(), 1_000_000);
+
+    // The range is checked before anything reaches the pool
+    const positionMint = Keypair.generate();
+    const [position] = await PublicKey.findProgramAddress(
+      [Buffer.from("position"), positionMint.publicKey.toBuffer()],
+      whirlpoolProgram
+    );
+    const positionTokenAccount = await getAssociatedTokenAddress(
+      positionMint.publicKey,
+      treasury,
+      true
+    );
+    try {
+      await program.methods
+        .openLiquidityPosition(-2_048, 512)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          liquidityConfig,
+          treasury,
+          whirlpool,
+          position,
+          positionMint: positionMint.publicKey,
+          positionTokenAccount,
+          whirlpoolProgram,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([authority, positionMint])
+        .rpc();
+      assert.fail("Expected a range outside the limits to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "LiquidityRangeOutOfBounds");
+    }
+  });
+
+  it("Funds the insurance fund from fees and draws it for declared incidents", async () => {
+    const pda = async (...seeds: Buffer[]) =>
+      (await PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const insuranceFund = await pda(Buffer.from("insurance_fund"));
+    const insuranceVault = await pda(Buffer.from("insurance_vault"));
+    const feeVault = await pda(Buffer.from("treasury_token_vault"), mint.toBuffer());
+    const stakePool = await pda(Buffer.from("stake_pool"));
+    const stakeVault = await pda(Buffer.from("stake_vault"));
+    const rewardVault = await pda(Buffer.from("reward_vault"));
+    const setShare = (bps: number) =>
+      program.methods
+        .setInsuranceShare(new anchor.BN(bps))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          mint,
+          insuranceFund,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([authority])
+        .rpc();
+
+    // Referrers already take half of each fee
+    try {
+      await setShare(6_000);
+      assert.fail("Expected shares above the whole fee to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidParameter");
+    }
+    await setShare(2_000);
+
+    const user = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      user.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const userTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      user,
+      mint,
+      user.publicKey
+    );
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      userTokenAccount.address,
+      authority,
+      10_000
+    );
+    const stakeAccount = await pda(Buffer.from("stake"), user.publicKey.toBuffer());
+    await program.methods
+      .openStakeAccount()
+      .accounts({
+        owner: user.publicKey,
+        stakePool,
+        stakeAccount,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    const stake = (accounts: Record<string, PublicKey>) =>
+      program.methods
+        .stake(new anchor.BN(10_000), NO_LOCK)
+        .accounts({
+          owner: user.publicKey,
+          state: tokenState,
+          stakePool,
+          stakeAccount,
+          source: userTokenAccount.address,
+          stakeVault,
+          rewardVault,
+          feeDestination: feeVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          ...accounts,
+        })
+        .signers([user])
+        .rpc();
+
+    try {
+      await stake({});
+      assert.fail("Expected a fee without the insurance vault to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidInsuranceVault");
+    }
+    const feeBefore = await getAccount(provider.connection, feeVault);
+    await stake({ insuranceVault });
+
+    // 2.5% fee on 10,000, a fifth of it insured
+    const feeAfter = await getAccount(provider.connection, feeVault);
+    assert.equal(feeAfter.amount - feeBefore.amount, BigInt(200));
+    assert.equal((await getAccount(provider.connection, insuranceVault)).amount, BigInt(50));
+
+    const incidentId = new anchor.BN(1);
+    const incident = await pda(
+      Buffer.from("insurance_incident"),
+      incidentId.toArrayLike(Buffer, "le", 8)
+    );
+    await program.methods
+      .declareIncident(incidentId, Array(32).fill(7), new anchor.BN(40))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        insuranceFund,
+        incident,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const draw = (amount: number) =>
+      program.methods
+        .drawInsurance(incidentId, new anchor.BN(amount))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          insuranceFund,
+          insuranceVault,
+          incident,
+          recipient: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+    try {
+      await draw(45);
+      assert.fail("Expected a draw above the declared amount to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InsuranceDrawExceeded");
+    }
+    await draw(30);
+
+    assert.equal((await getAccount(provider.connection, insuranceVault)).amount, BigInt(20));
+    const declared = await program.account.incident.fetch(incident);
+    assert.equal(declared.drawn.toNumber(), 30);
+    const fund = await program.account.insuranceFund.fetch(insuranceFund);
+    assert.equal(fund.incidents.toNumber(), 1);
+    assert.equal(fund.totalDrawn.toNumber(), 30);
+
+    await setShare(0);
+  });
+
+  it("Meters pay-per-use access", async () => {
+    const featureId = 7;
+    const featureBytes = Buffer.alloc(4);
+    featureBytes.writeUInt32LE(featureId);
+    const [usageCounter] = await PublicKey.findProgramAddress(
+      [Buffer.from("usage"), authority.publicKey.toBuffer(), featureBytes],
+      program.programId
+    );
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), mint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .openUsageCounter(featureId)
+      .accounts({
+        owner: authority.publicKey,
+        usageCounter,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const consume = (amount: number) =>
+      program.methods
+        .consumeAccess(featureId, new anchor.BN(amount))
+        .accounts({
+          owner: authority.publicKey,
+          state: tokenState,
+          mint,
+          usageCounter,
+          source: authorityTokenAccount,
+          treasuryVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    const before = await program.account.tokenState.fetch(tokenState);
+    await consume(10);
+    const after = await program.account.tokenState.fetch(tokenState);
+    assert.equal(after.totalBurned.sub(before.totalBurned).toNumber(), 10);
+
+    await program.methods
+      .setPayPerUseConfig(false)
+      .accounts({ authority: authority.publicKey, state: tokenState })
+      .signers([authority])
+      .rpc();
+    const vaultBefore = await getAccount(provider.connection, treasuryVault);
+    await consume(5);
+    const vaultAfter = await getAccount(provider.connection, treasuryVault);
+    assert.equal(vaultAfter.amount - vaultBefore.amount, BigInt(5));
+
+    const counter = await program.account.usageCounter.fetch(usageCounter);
+    assert.equal(counter.uses.toNumber(), 2);
+    assert.equal(counter.consumed.toNumber(), 15);
+  });
+
+  it("Pays for access with a scoped session key", async () => {
+    const featureId = 7;
+    const featureBytes = Buffer.alloc(4);
+    featureBytes.writeUInt32LE(featureId);
+    const [usageCounter] = await PublicKey.findProgramAddress(
+      [Buffer.from("usage"), authority.publicKey.toBuffer(), featureBytes],
+      program.programId
+    );
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), mint.toBuffer()],
+      program.programId
+    );
+    const sessionKey = Keypair.generate();
+    const [session] = await PublicKey.findProgramAddress(
+      [Buffer.from("session"), authority.publicKey.toBuffer(), sessionKey.publicKey.toBuffer()],
+      program.programId
+    );
+    const now = Math.floor(Date.now() / 1000);
+
+    await program.methods
+      .createSessionKey(sessionKey.publicKey, 2, new anchor.BN(now + 3600), new anchor.BN(8))
+      .accounts({
+        user: authority.publicKey,
+        state: tokenState,
+        session,
+        tokenAccount: authorityTokenAccount,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const consume = (amount: number) =>
+      program.methods
+        .consumeAccessWithSession(featureId, new anchor.BN(amount))
+        .accounts({
+          sessionKey: sessionKey.publicKey,
+          state: tokenState,
+          mint,
+          session,
+          usageCounter,
+          source: authorityTokenAccount,
+          treasuryVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([sessionKey])
+        .rpc();
+
+    await consume(5);
+    assert.equal((await program.account.sessionKey.fetch(session)).spent.toNumber(), 5);
+
+    try {
+      await consume(5);
+      assert.fail("Expected spending past the session limit to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SessionSpendLimitExceeded");
+    }
+
+    await program.methods
+      .revokeSessionKey()
+      .accounts({ signer: sessionKey.publicKey, session, user: authority.publicKey })
+      .signers([sessionKey])
+      .rpc();
+    assert.isNull(await program.account.sessionKey.fetchNullable(session));
+  });
+
+  // Leaves a timelock in place that cannot be lifted within the test run,
+  // so this runs after the other configuration tests
+  describe("timelock", () => {
+    const pendingChangePda = async () => {
+      const state = await program.account.tokenState.fetch(tokenState);
+      const [pendingChange] = await PublicKey.findProgramAddress(
+        [Buffer.from("pending_change"), state.nextChangeId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      return pendingChange;
+    };
+
+    const queue = async (change: Record<string, any>) => {
+      const pendingChange = await pendingChangePda();
+      await program.methods
+        .queuePendingChange(change)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          pendingChange,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      return pendingChange;
+    };
+
+    const execute = (pendingChange: PublicKey) =>
+      program.methods
+        .executePendingChange()
+        .accounts({
+          executor: provider.wallet.publicKey,
+          state: tokenState,
+          pendingChange,
+          proposer: authority.publicKey,
+          priceFeed: null,
+        })
+        .rpc();
+
+    it("Executes a queued change immediately without a delay", async () => {
+      const pendingChange = await queue({
+        parameters: { 0: { ...NO_CHANGES, targetUsdValue: new anchor.BN(16_000_000) } },
+      });
+      await execute(pendingChange);
+
+      const state = await program.account.tokenState.fetch(tokenState);
+      assert.equal(state.targetUsdValue.toNumber(), 16_000_000);
+      assert.isNull(await provider.connection.getAccountInfo(pendingChange));
+    });
+
+    it("Holds formula changes until the delay has passed", async () => {
+      await program.methods
+        .setTimelockDelay(new anchor.BN(3600))
+        .accounts({ authority: authority.publicKey, state: tokenState })
+        .signers([authority])
+        .rpc();
+
+      try {
+        await program.methods
+          .updateParameters({ ...NO_CHANGES, targetUsdValue: new anchor.BN(15_000_000) })
+          .accounts({ authority: authority.publicKey, state: tokenState })
+          .signers([authority])
+          .rpc();
+        assert.fail("Expected a direct target change to be rejected");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "TimelockRequired");
+      }
+
+      const pendingChange = await queue({
+        parameters: { 0: { ...NO_CHANGES, targetUsdValue: new anchor.BN(15_000_000) } },
+      });
+      const pending = await program.account.pendingChange.fetch(pendingChange);
+      assert.equal(pending.eta.sub(pending.queuedAt).toNumber(), 3600);
+
+      try {
+        await execute(pendingChange);
+        assert.fail("Expected execution before the eta to fail");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "TimelockNotElapsed");
+      }
+
+      await program.methods
+        .cancelPendingChange()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          pendingChange,
+          proposer: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(pendingChange));
+    });
+
+    it("Holds oracle pricing settings until the delay has passed", async () => {
+      const direct = [
+        program.methods.setTwapWindow(new anchor.BN(60)),
+        program.methods.setCircuitBreaker(new anchor.BN(2_000)),
+        program.methods.setKeeperConfig(new anchor.BN(10), new anchor.BN(600)),
+      ];
+      for (const call of direct) {
+        try {
+          await call
+            .accounts({ authority: authority.publicKey, state: tokenState })
+            .signers([authority])
+            .rpc();
+          assert.fail("Expected a direct pricing change to be rejected");
+        } catch (err: any) {
+          assert.equal(err.error.errorCode.code, "TimelockRequired");
+        }
+      }
+
+      const pendingChange = await queue({ circuitBreaker: { 0: new anchor.BN(2_000) } });
+      const pending = await program.account.pendingChange.fetch(pendingChange);
+      assert.equal(pending.change.circuitBreaker[0].toNumber(), 2_000);
+      try {
+        await execute(pendingChange);
+        assert.fail("Expected execution before the eta to fail");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "TimelockNotElapsed");
+      }
+
+      await program.methods
+        .cancelPendingChange()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          pendingChange,
+          proposer: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("Holds fee, staking, and access settings until the delay has passed", async () => {
+      const { lockTiers, accessTiers } = await program.account.tokenState.fetch(tokenState);
+      const direct: [string, () => Promise<string>][] = [
+        [
+          "setFeeConfig",
+          () =>
+            program.methods
+              .setFeeConfig(new anchor.BN(100))
+              .accounts({
+                authority: authority.publicKey,
+                state: tokenState,
+                feeDestination: authorityTokenAccount,
+                roleRegistry: null,
+                adminLog: null,
+              })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setStakingConfig",
+          () =>
+            program.methods
+              .setStakingConfig(new anchor.BN(0), new anchor.BN(0), true)
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setLockTiers",
+          async () =>
+            program.methods
+              .setLockTiers((await program.account.tokenState.fetch(tokenState)).lockTiers)
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setReferralConfig",
+          () =>
+            program.methods
+              .setReferralConfig(new anchor.BN(1_000))
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setReceiptValidity",
+          () =>
+            program.methods
+              .setReceiptValidity(new anchor.BN(3_600))
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setAccessTiers",
+          () =>
+            program.methods
+              .setAccessTiers(accessTiers)
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setGracePeriod",
+          () =>
+            program.methods
+              .setGracePeriod(new anchor.BN(0))
+              .accounts({ authority: authority.publicKey, state: tokenState })
+              .signers([authority])
+              .rpc(),
+        ],
+        [
+          "setInsuranceShare",
+          async () =>
+            program.methods
+              .setInsuranceShare(new anchor.BN(500))
+              .accounts({
+                authority: authority.publicKey,
+                state: tokenState,
+                mint,
+                insuranceFund: (
+                  await PublicKey.findProgramAddress(
+                    [Buffer.from("insurance_fund")],
+                    program.programId
+                  )
+                )[0],
+                insuranceVault: (
+                  await PublicKey.findProgramAddress(
+                    [Buffer.from("insurance_vault")],
+                    program.programId
+                  )
+                )[0],
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+              })
+              .signers([authority])
+              .rpc(),
+        ],
+      ];
+      for (const [name, call] of direct) {
+        try {
+          await call();
+          assert.fail(`Expected a direct ${name} to be rejected`);
+        } catch (err: any) {
+          assert.equal(err.error.errorCode.code, "TimelockRequired", name);
+        }
+      }
+
+      const queued = [
+        { feeConfig: { feeBps: new anchor.BN(100), feeDestination: authorityTokenAccount } },
+        {
+          stakingConfig: {
+            unstakeCooldownSecs: new anchor.BN(0),
+            earlyExitPenaltyBps: new anchor.BN(0),
+            burnEarlyExitPenalty: true,
+          },
+        },
+        { lockTiers: { 0: lockTiers } },
+        { referralBps: { 0: new anchor.BN(1_000) } },
+        { receiptValidity: { 0: new anchor.BN(3_600) } },
+        { accessTiers: { 0: accessTiers } },
+        { gracePeriod: { 0: new anchor.BN(0) } },
+        { insuranceShare: { 0: new anchor.BN(500) } },
+      ];
+      for (const change of queued) {
+        const pendingChange = await queue(change);
+        try {
+          await execute(pendingChange);
+          assert.fail("Expected execution before the eta to fail");
+        } catch (err: any) {
+          assert.equal(err.error.errorCode.code, "TimelockNotElapsed");
+        }
+        await program.methods
+          .cancelPendingChange()
+          .accounts({
+            authority: authority.publicKey,
+            state: tokenState,
+            pendingChange,
+            proposer: authority.publicKey,
+          })
+          .signers([authority])
+          .rpc();
+      }
+    });
+
+    it("Refuses treasury swap route changes while a delay is set", async () => {
+      const [swapConfig] = await PublicKey.findProgramAddress(
+        [Buffer.from("swap_config")],
+        program.programId
+      );
+      const { swapProgram, inputMint } = await program.account.swapConfig.fetch(swapConfig);
+      try {
+        await program.methods
+          .configureTreasurySwap(swapProgram, inputMint, new anchor.BN(1), new anchor.BN(10_000))
+          .accounts({
+            authority: authority.publicKey,
+            state: tokenState,
+            swapConfig,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Expected a swap config change to need the timelock");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "TimelockRequired");
+      }
+    });
+  });
+
+  it("Mints through the program's mint authority", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+
+    await program.methods
+      .transferMintAuthority()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const before = await getAccount(provider.connection, authorityTokenAccount);
+    await program.methods
+      .mintTokens(new anchor.BN(1_000))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        mintAuthority,
+        destination: authorityTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const after = await getAccount(provider.connection, authorityTokenAccount);
+    assert.equal(after.amount - before.amount, BigInt(1_000));
+  });
+
+  // Needs the mint authority PDA, so this runs before the supply is finalized
+  it("Creates and updates the mint's token metadata", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [metadata] = await PublicKey.findProgramAddress(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+    const accounts = {
+      authority: authority.publicKey,
+      state: tokenState,
+      roleRegistry: null,
+      mint,
+      metadata,
+      mintAuthority,
+      adminLog: null,
+      tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+    };
+    const createAccounts = {
+      ...accounts,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+    // Name, symbol, and URI are stored NUL-padded after the length prefixes
+    const storedName = async () => {
+      const { data } = await provider.connection.getAccountInfo(metadata);
+      const nameLen = data.readUInt32LE(65);
+      return data.subarray(69, 69 + nameLen).toString().replace(/\0+$/, "");
+    };
+
+    try {
+      await program.methods
+        .createMetadata({ name: "A".repeat(33), symbol: "AISTM7", uri: "" })
+        .accounts(createAccounts)
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected an overlong name to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "InvalidMetadata");
+    }
+
+    await program.methods
+      .createMetadata({
+        name: "AISTM7",
+        symbol: "AISTM7",
+        uri: "https://aistm7.com/token.json",
+      })
+      .accounts(createAccounts)
+      .signers([authority])
+      .rpc();
+    assert.equal(await storedName(), "AISTM7");
+
+    await program.methods
+      .updateMetadata({
+        name: "AISTM7 Access Token",
+        symbol: "AISTM7",
+        uri: "https://aistm7.com/token.json",
+      })
+      .accounts(accounts)
+      .signers([authority])
+      .rpc();
+    assert.equal(await storedName(), "AISTM7 Access Token");
+  });
+
+  it("Checks compressed mints against the recipients and supply cap", async () => {
+    const compressedTokenProgram = new PublicKey("cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m");
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const mintCompressed = (recipients: PublicKey[], amounts: anchor.BN[]) =>
+      program.methods
+        .mintCompressed(recipients, amounts)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          roleRegistry: null,
+          mint,
+          mintAuthority,
+          cpiAuthorityPda: PublicKey.findProgramAddressSync(
+            [Buffer.from("cpi_authority")],
+            compressedTokenProgram
+          )[0],
+          tokenPoolPda: PublicKey.findProgramAddressSync(
+            [Buffer.from("pool"), mint.toBuffer()],
+            compressedTokenProgram
+          )[0],
+          registeredProgramPda: Keypair.generate().publicKey,
+          accountCompressionAuthority: Keypair.generate().publicKey,
+          merkleTree: Keypair.generate().publicKey,
+          adminLog: null,
+          compressedTokenProgram,
+          lightSystemProgram: new PublicKey("SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7"),
+          accountCompressionProgram: new PublicKey("compr6CUsB5m2jS4Y3831ztGSTnDpnKJTKS95d64XVq"),
+          noopProgram: new PublicKey("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV"),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+    for (const amounts of [[new anchor.BN(1)], [new anchor.BN(1), new anchor.BN(0)]]) {
+      try {
+        await mintCompressed(recipients, amounts);
+        assert.fail("Expected a malformed distribution to be rejected");
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, "InvalidParameter");
+      }
+    }
+    try {
+      await mintCompressed(recipients, [MAX_SUPPLY, MAX_SUPPLY]);
+      assert.fail("Expected a distribution past the cap to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SupplyCapExceeded");
+    }
+  });
+
+  it("Keeps bridge transfers within each chain's limits", async () => {
+    const ETHEREUM = 2;
+    // Stands in for the NTT manager's token authority PDA
+    const bridgeAuthority = Keypair.generate();
+    const [bridgeConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("bridge_config")],
+      program.programId
+    );
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    await program.methods
+      .initializeBridgeConfig(bridgeAuthority.publicKey)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        bridgeConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    // The timelock test above left a delay in place, so opening a chain
+    // has to be queued
+    try {
+      await program.methods
+        .setBridgeLimits(ETHEREUM, new anchor.BN(1_000), new anchor.BN(1_000))
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          bridgeConfig,
+          roleRegistry: null,
+          adminLog: null,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected raising a bridge limit to need the timelock");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "TimelockRequired");
+    }
+
+    try {
+      await program.methods
+        .bridgeBurn(new anchor.BN(100), ETHEREUM, Array(32).fill(1))
+        .accounts({
+          bridgeAuthority: bridgeAuthority.publicKey,
+          owner: authority.publicKey,
+          state: tokenState,
+          bridgeConfig,
+          mint,
+          source: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bridgeAuthority, authority])
+        .rpc();
+      assert.fail("Expected a transfer to a closed chain to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "BridgeLimitExceeded");
+    }
+
+    try {
+      await program.methods
+        .bridgeMint(new anchor.BN(100), ETHEREUM, Array(32).fill(1), Array(32).fill(2))
+        .accounts({
+          bridgeAuthority: authority.publicKey,
+          state: tokenState,
+          bridgeConfig,
+          mint,
+          mintAuthority,
+          destination: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a mint signed by another key to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+    }
+
+    const config = await program.account.bridgeConfig.fetch(bridgeConfig);
+    assert.equal(config.outstanding.toNumber(), 0);
+  });
+
+  it("Only posts cross-chain attestations for wallets with access", async () => {
+    // The devnet core bridge, which the test build of the program expects
+    const wormhole = new PublicKey("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+    const [emitter] = await PublicKey.findProgramAddress(
+      [Buffer.from("emitter")],
+      program.programId
+    );
+    const wallet = Keypair.generate();
+    const message = Keypair.generate();
+    try {
+      await program.methods
+        .postAccessAttestation(0, 2, Array(12).fill(0).concat(Array(20).fill(0xab)))
+        .accounts({
+          payer: authority.publicKey,
+          wallet: wallet.publicKey,
+          state: tokenState,
+          receipt: PublicKey.findProgramAddressSync(
+            [Buffer.from("receipt"), wallet.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          accessOverride: accessOverridePda(wallet.publicKey),
+          wormholeBridge: PublicKey.findProgramAddressSync([Buffer.from("Bridge")], wormhole)[0],
+          wormholeMessage: message.publicKey,
+          emitter,
+          wormholeSequence: PublicKey.findProgramAddressSync(
+            [Buffer.from("Sequence"), emitter.toBuffer()],
+            wormhole
+          )[0],
+          wormholeFeeCollector: PublicKey.findProgramAddressSync(
+            [Buffer.from("fee_collector")],
+            wormhole
+          )[0],
+          wormholeProgram: wormhole,
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority, wallet, message])
+        .rpc();
+      assert.fail("Expected an attestation for a wallet without access to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AccessRequired");
+    }
+  });
+
+  it("Mints scheduled emissions into the designated vaults", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [emissionSchedule] = await PublicKey.findProgramAddress(
+      [Buffer.from("emission_schedule")],
+      program.programId
+    );
+    const treasury = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      Keypair.generate().publicKey
+    );
+
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .initializeEmissionSchedule({
+        startTime: new anchor.BN(now - 60),
+        epochSecs: new anchor.BN(86_400),
+        initialEmission: new anchor.BN(10_000),
+        decayBps: new anchor.BN(1_000),
+        vaults: [
+          { tokenAccount: authorityTokenAccount, shareBps: 6_000 },
+          { tokenAccount: treasury.address, shareBps: 4_000 },
+        ],
+      })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        emissionSchedule,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const mintEmission = () =>
+      program.methods
+        .mintEpochEmission()
+        .accounts({
+          state: tokenState,
+          emissionSchedule,
+          mint,
+          mintAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: authorityTokenAccount, isWritable: true, isSigner: false },
+          { pubkey: treasury.address, isWritable: true, isSigner: false },
+        ])
+        .rpc();
+
+    const before = await getAccount(provider.connection, authorityTokenAccount);
+    await mintEmission();
+    const after = await getAccount(provider.connection, authorityTokenAccount);
+    assert.equal(after.amount - before.amount, BigInt(6_000));
+    const treasuryAccount = await getAccount(provider.connection, treasury.address);
+    assert.equal(treasuryAccount.amount, BigInt(4_000));
+
+    const schedule = await program.account.emissionSchedule.fetch(emissionSchedule);
+    assert.equal(schedule.nextEpoch.toNumber(), 1);
+    assert.equal(schedule.totalEmitted.toNumber(), 10_000);
+
+    try {
+      await mintEmission();
+      assert.fail("Expected the second epoch to be rejected before it starts");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "EpochNotReached");
+    }
+  });
+
+  it("Sells tokens along a linear curve into the treasury", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [sale] = await PublicKey.findProgramAddress(
+      [Buffer.from("sale")],
+      program.programId
+    );
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const buyer = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      buyer.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    const [purchase] = await PublicKey.findProgramAddress(
+      [Buffer.from("sale_purchase"), buyer.publicKey.toBuffer()],
+      program.programId
+    );
+    const buyerTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      buyer.publicKey
+    );
+
+    // 1 SOL per whole token, rising 10 SOL with every whole token sold
+    const now = Math.floor(Date.now() / 1000);
+    const args = {
+      paymentMint: PublicKey.default,
+      curve: { linear: { slope: new anchor.BN(10_000_000_000) } },
+      startPrice: new anchor.BN(1_000_000_000),
+      hardCap: new anchor.BN(100_000_000),
+      walletCap: new anchor.BN(20_000_000),
+      startTime: new anchor.BN(now - 60),
+      endTime: new anchor.BN(now + 86_400),
+    };
+    await program.methods
+      .initializeSale(args)
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        sale,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const buy = (amount: number, maxCost: number) =>
+      program.methods
+        .buyFromSale(new anchor.BN(amount), new anchor.BN(maxCost))
+        .accounts({
+          buyer: buyer.publicKey,
+          state: tokenState,
+          sale,
+          purchase,
+          mint,
+          mintAuthority,
+          destination: buyerTokenAccount.address,
+          treasury,
+          paymentSource: null,
+          treasuryVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+    // 0.01 token from zero: 0.01 SOL plus 10 SOL * 0.01^2 / 2 of slope
+    try {
+      await buy(10_000_000, 10_499_999);
+      assert.fail("Expected a cost above the buyer's maximum to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SaleSlippageExceeded");
+    }
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    await buy(10_000_000, 10_500_000);
+    assert.equal(
+      (await provider.connection.getBalance(treasury)) - treasuryBefore,
+      10_500_000
+    );
+    const bought = await getAccount(provider.connection, buyerTokenAccount.address);
+    assert.equal(bought.amount, BigInt(10_000_000));
+
+    const totals = await program.account.sale.fetch(sale);
+    assert.equal(totals.sold.toNumber(), 10_000_000);
+    assert.equal(totals.raised.toNumber(), 10_500_000);
+    const record = await program.account.salePurchase.fetch(purchase);
+    assert.equal(record.purchased.toNumber(), 10_000_000);
+
+    try {
+      await buy(11_000_000, 100_000_000);
+      assert.fail("Expected the wallet cap to be enforced");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SaleWalletCapExceeded");
+    }
+
+    try {
+      await program.methods
+        .updateSale({ ...args, startPrice: new anchor.BN(1) })
+        .accounts({ authority: authority.publicKey, state: tokenState, sale })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected the curve to be fixed once the sale started");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SaleAlreadyStarted");
+    }
+
+    await program.methods
+      .finalizeSale()
+      .accounts({ authority: authority.publicKey, state: tokenState, sale })
+      .signers([authority])
+      .rpc();
+    try {
+      await buy(1_000_000, 100_000_000);
+      assert.fail("Expected a finalized sale to refuse purchases");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SaleClosed");
+    }
+  });
+
+  it("Clears a Dutch auction at one price and fills oversubscription pro rata", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const tranche = new anchor.BN(1);
+    const [auction] = await PublicKey.findProgramAddress(
+      [Buffer.from("auction"), tranche.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const bidders = [Keypair.generate(), Keypair.generate()];
+    for (const bidder of bidders) {
+      const airdrop = await provider.connection.requestAirdrop(
+        bidder.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+    }
+    const bidPda = (bidder: Keypair) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("auction_bid"), auction.toBuffer(), bidder.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    // 0.01 token, from 2 SOL per token down to 1 SOL over an hour
+    const SUPPLY = 10_000_000;
+    await program.methods
+      .startAuction(
+        tranche,
+        new anchor.BN(SUPPLY),
+        new anchor.BN(2_000_000_000),
+        new anchor.BN(1_000_000_000),
+        new anchor.BN(3600)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        auction,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const bid = (bidder: Keypair, lamports: number) =>
+      program.methods
+        .bid(new anchor.BN(lamports))
+        .accounts({
+          bidder: bidder.publicKey,
+          state: tokenState,
+          auction,
+          bid: bidPda(bidder),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bidder])
+        .rpc();
+    const settle = async (bidder: Keypair) => {
+      const destination = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority,
+        mint,
+        bidder.publicKey
+      );
+      await program.methods
+        .settleBid()
+        .accounts({
+          state: tokenState,
+          auction,
+          bid: bidPda(bidder),
+          bidder: bidder.publicKey,
+          mint,
+          mintAuthority,
+          destination: destination.address,
+          treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      return getAccount(provider.connection, destination.address);
+    };
+
+    // Half the supply at about 2 SOL per token
+    await bid(bidders[0], 10_000_000);
+    try {
+      await settle(bidders[0]);
+      assert.fail("Expected settlement to wait for the auction to clear");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AuctionNotConcluded");
+    }
+
+    // Demand for 1.5x the supply clears the auction at the current price
+    await bid(bidders[1], 20_000_000);
+    const cleared = await program.account.auction.fetch(auction);
+    const clearingPrice = cleared.clearingPrice.toNumber();
+    assert.isTrue(clearingPrice > 1_000_000_000 && clearingPrice <= 2_000_000_000);
+    try {
+      await bid(bidders[0], 1_000_000);
+      assert.fail("Expected bids after clearing to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AuctionClosed");
+    }
+
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    const first = await settle(bidders[0]);
+    const second = await settle(bidders[1]);
+    assert.equal(first.amount, BigInt(3_333_333));
+    assert.equal(second.amount, BigInt(6_666_666));
+
+    // Each pays for its allocation at the clearing price, rounded up
+    const cost = (tokens: number) =>
+      (BigInt(tokens) * BigInt(clearingPrice) + BigInt(999_999_999)) / BigInt(1_000_000_000);
+    const settled = await program.account.auction.fetch(auction);
+    assert.equal(settled.settledTokens.toNumber(), 9_999_999);
+    assert.equal(
+      BigInt(settled.proceeds.toString()),
+      cost(3_333_333) + cost(6_666_666)
+    );
+    assert.equal(
+      (await provider.connection.getBalance(treasury)) - treasuryBefore,
+      settled.proceeds.toNumber()
+    );
+    assert.isNull(await provider.connection.getAccountInfo(bidPda(bidders[0])));
+  });
+
+  it("Escrows whitelisted presale contributions until the presale closes", async () => {
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (index: number, wallet: PublicKey, allocation: number) => {
+      const indexBytes = Buffer.alloc(4);
+      indexBytes.writeUInt32LE(index);
+      return sha256(
+        Buffer.from([0]),
+        indexBytes,
+        wallet.toBuffer(),
+        new anchor.BN(allocation).toArrayLike(Buffer, "le", 8)
+      );
+    };
+    const node = (a: Buffer, b: Buffer) =>
+      Buffer.compare(a, b) <= 0
+        ? sha256(Buffer.from([1]), a, b)
+        : sha256(Buffer.from([1]), b, a);
+
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [presale] = await PublicKey.findProgramAddress(
+      [Buffer.from("presale")],
+      program.programId
+    );
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const SOL = anchor.web3.LAMPORTS_PER_SOL;
+    const contributors = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const contributor of contributors) {
+      const airdrop = await provider.connection.requestAirdrop(contributor.publicKey, SOL);
+      await provider.connection.confirmTransaction(airdrop);
+    }
+    const [first, second, outsider] = contributors;
+    const allocations = [400_000_000, 200_000_000];
+    const leaves = [
+      leaf(0, first.publicKey, allocations[0]),
+      leaf(1, second.publicKey, allocations[1]),
+    ];
+    const root = node(leaves[0], leaves[1]);
+
+    // 100 SOL per whole token; wallets may bring 0.05 to 0.3 SOL
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .initializePresale({
+        whitelistRoot: Array.from(root),
+        price: new anchor.BN(100 * SOL),
+        softCap: new anchor.BN(400_000_000),
+        hardCap: new anchor.BN(500_000_000),
+        minContribution: new anchor.BN(50_000_000),
+        maxContribution: new anchor.BN(300_000_000),
+        startTime: new anchor.BN(now - 60),
+        deadline: new anchor.BN(now + 86_400),
+      })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        presale,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const contributionPda = (contributor: Keypair) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("presale_contribution"), contributor.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    const contribute = (
+      contributor: Keypair,
+      lamports: number,
+      index: number,
+      allocation: number,
+      proof: Buffer[]
+    ) =>
+      program.methods
+        .contribute(
+          new anchor.BN(lamports),
+          index,
+          new anchor.BN(allocation),
+          proof.map((sibling) => Array.from(sibling))
+        )
+        .accounts({
+          contributor: contributor.publicKey,
+          state: tokenState,
+          presale,
+          contribution: contributionPda(contributor),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor])
+        .rpc();
+    const expectError = async (promise: Promise<string>, code: string) => {
+      try {
+        await promise;
+        assert.fail(`Expected ${code}`);
+      } catch (err: any) {
+        assert.equal(err.error.errorCode.code, code);
+      }
+    };
+
+    await expectError(
+      contribute(outsider, 100_000_000, 0, allocations[0], [leaves[1]]),
+      "InvalidProof"
+    );
+    await expectError(
+      contribute(first, 10_000_000, 0, allocations[0], [leaves[1]]),
+      "PresaleContributionTooSmall"
+    );
+    await contribute(first, 300_000_000, 0, allocations[0], [leaves[1]]);
+    // The allocation is held to the presale's per-wallet maximum
+    await expectError(
+      contribute(first, 50_000_000, 0, allocations[0], [leaves[1]]),
+      "PresaleContributionTooLarge"
+    );
+
+    const finalize = () =>
+      program.methods
+        .finalizePresale()
+        .accounts({ payer: authority.publicKey, presale, treasury })
+        .signers([authority])
+        .rpc();
+    await expectError(finalize(), "PresaleStillOpen");
+
+    // Reaching the hard cap closes the presale before its deadline
+    await contribute(second, 200_000_000, 1, allocations[1], [leaves[0]]);
+    await expectError(
+      program.methods
+        .enableRefunds()
+        .accounts({ payer: authority.publicKey, presale })
+        .signers([authority])
+        .rpc(),
+      "PresaleStillOpen"
+    );
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    await finalize();
+    assert.equal(
+      (await provider.connection.getBalance(treasury)) - treasuryBefore,
+      500_000_000
+    );
+
+    const destination = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      first.publicKey
+    );
+    await program.methods
+      .claimPresaleTokens()
+      .accounts({
+        contributor: first.publicKey,
+        state: tokenState,
+        presale,
+        contribution: contributionPda(first),
+        mint,
+        mintAuthority,
+        destination: destination.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([first])
+      .rpc();
+
+    // 0.3 SOL at 100 SOL per token
+    const claimed = await getAccount(provider.connection, destination.address);
+    assert.equal(claimed.amount, BigInt(3_000_000));
+    assert.isNull(await provider.connection.getAccountInfo(contributionPda(first)));
+  });
+
+  // Nothing can be minted afterwards, so minting tests go before this one
+  it("Enforces the supply cap and finalizes the supply", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const mintTokens = (amount: anchor.BN) =>
+      program.methods
+        .mintTokens(amount)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          mint,
+          mintAuthority,
+          destination: authorityTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    try {
+      await mintTokens(MAX_SUPPLY);
+      assert.fail("Expected minting past the cap to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "SupplyCapExceeded");
+    }
+
+    await program.methods
+      .finalizeSupply()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        mint,
+        mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([authority])
+      .rpc();
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.isTrue(state.supplyFinalized);
+    try {
+      await mintTokens(new anchor.BN(1));
+      assert.fail("Expected minting after finalization to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "MintAuthorityNotTransferred");
+    }
+  });
+
+  it("Migrates holders to the token-2022 mint 1:1", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), newMint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .beginMintMigration({
+        transferFeeBps: TRANSFER_FEE_BPS,
+        maxTransferFee: MAX_TRANSFER_FEE,
+        transferHookProgram: hookProgram.programId,
+        frozenByDefault: false,
+        interestRateBps: INTEREST_RATE_BPS,
+        confidentialTransfers: { autoApproveNewAccounts: false, auditorElgamalPubkey: null },
+        clawback: true,
+      })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        roleRegistry: null,
+        mint,
+        newMint,
+        mintAuthority,
+        treasury,
+        treasuryVault,
+        adminLog: null,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    await hookProgram.methods
+      .initializeExtraAccountMetaList()
+      .accounts({
+        payer: authority.publicKey,
+        extraAccountMetaList: PublicKey.findProgramAddressSync(
+          [Buffer.from("extra-account-metas"), newMint.toBuffer()],
+          hookProgram.programId
+        )[0],
+        mint: newMint,
+        holderStats: PublicKey.findProgramAddressSync(
+          [Buffer.from("holder_stats"), newMint.toBuffer()],
+          hookProgram.programId
+        )[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const holder = Keypair.generate();
+    const source = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      holder.publicKey
+    );
+    const destination = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      newMint,
+      holder.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      source.address,
+      authority,
+      BigInt(currentRequirement.toString())
+    );
+
+    const legacySupply = (await getMint(provider.connection, mint)).supply;
+    await program.methods
+      .migrateTokens(currentRequirement)
+      .accounts({
+        owner: holder.publicKey,
+        state: tokenState,
+        mint,
+        newMint,
+        source: source.address,
+        destination: destination.address,
+        mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([holder])
+      .rpc();
+
+    const migrated = await getAccount(
+      provider.connection,
+      destination.address,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(migrated.amount.toString(), currentRequirement.toString());
+    assert.equal((await getAccount(provider.connection, source.address)).amount, BigInt(0));
+    assert.equal(
+      (legacySupply - (await getMint(provider.connection, mint)).supply).toString(),
+      currentRequirement.toString()
+    );
+    const state = await program.account.tokenState.fetch(tokenState);
+    assert.isTrue(state.migrationMint.equals(newMint));
+    assert.equal(state.migratedAmount.toString(), currentRequirement.toString());
+
+    // Migrated tokens keep counting towards access
+    assert.isFalse(await verify(source.address));
+    assert.isTrue(await verify(source.address, {}, [destination.address]));
+  });
+
+  it("Withholds transfer fees on the token-2022 mint for the treasury", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const [treasury] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), newMint.toBuffer()],
+      program.programId
+    );
+    const amount = BigInt(10_000);
+    const fee = (amount * BigInt(TRANSFER_FEE_BPS)) / BigInt(10_000);
+
+    const sender = Keypair.generate();
+    const legacyAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      sender.publicKey
+    );
+    const [senderAccount, recipientAccount] = await Promise.all(
+      [sender.publicKey, Keypair.generate().publicKey].map((owner) =>
+        getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          authority,
+          newMint,
+          owner,
+          false,
+          undefined,
+          undefined,
+          TOKEN_2022_PROGRAM_ID
+        )
+      )
+    );
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      legacyAccount.address,
+      authority,
+      amount
+    );
+    await program.methods
+      .migrateTokens(new anchor.BN(amount.toString()))
+      .accounts({
+        owner: sender.publicKey,
+        state: tokenState,
+        mint,
+        newMint,
+        source: legacyAccount.address,
+        destination: senderAccount.address,
+        mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([sender])
+      .rpc();
+
+    const { decimals } = await getMint(
+      provider.connection,
+      newMint,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferCheckedWithTransferHook(
+      provider.connection,
+      authority,
+      senderAccount.address,
+      newMint,
+      recipientAccount.address,
+      sender,
+      amount,
+      decimals,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const received = await getAccount(
+      provider.connection,
+      recipientAccount.address,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(received.amount, amount - fee);
+
+    await program.methods
+      .harvestFees()
+      .accounts({
+        state: tokenState,
+        newMint,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: recipientAccount.address, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+    const withdrawWithheld = () =>
+      program.methods
+        .withdrawWithheld()
+        .accounts({
+          state: tokenState,
+          newMint,
+          treasury,
+          treasuryVault,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+    await withdrawWithheld();
+
+    const vault = await getAccount(
+      provider.connection,
+      treasuryVault,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(vault.amount, fee);
+    try {
+      await withdrawWithheld();
+      assert.fail("Expected withdrawing with nothing withheld to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "NoWithheldFees");
+    }
+  });
+
+  it("Applies the denylist and max-wallet cap in the transfer hook", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const [transferHookConfig] = await PublicKey.findProgramAddress(
+      [Buffer.from("transfer_hook_config")],
+      program.programId
+    );
+    const [holderStats] = await PublicKey.findProgramAddress(
+      [Buffer.from("holder_stats"), newMint.toBuffer()],
+      hookProgram.programId
+    );
+    const amount = BigInt(1_000_000);
+
+    const sender = Keypair.generate();
+    const legacyAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      mint,
+      sender.publicKey
+    );
+    const owners = [sender, Keypair.generate(), Keypair.generate()].map((k) => k.publicKey);
+    const [senderAccount, smallHolder, pool] = await Promise.all(
+      owners.map((owner) =>
+        getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          authority,
+          newMint,
+          owner,
+          false,
+          undefined,
+          undefined,
+          TOKEN_2022_PROGRAM_ID
+        )
+      )
+    );
+    await transfer(
+      provider.connection,
+      authority,
+      authorityTokenAccount,
+      legacyAccount.address,
+      authority,
+      amount
+    );
+    await program.methods
+      .migrateTokens(new anchor.BN(amount.toString()))
+      .accounts({
+        owner: sender.publicKey,
+        state: tokenState,
+        mint,
+        newMint,
+        source: legacyAccount.address,
+        destination: senderAccount.address,
+        mintAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([sender])
+      .rpc();
+
+    await program.methods
+      .initializeTransferHookConfig()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        transferHookConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    // 10% of a supply the sender holds most of
+    await program.methods
+      .setMaxWallet(new anchor.BN(1_000))
+      .accounts({ authority: authority.publicKey, state: tokenState, transferHookConfig })
+      .signers([authority])
+      .rpc();
+
+    const { decimals } = await getMint(
+      provider.connection,
+      newMint,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const send = (destination: PublicKey, value: bigint) =>
+      transferCheckedWithTransferHook(
+        provider.connection,
+        authority,
+        senderAccount.address,
+        newMint,
+        destination,
+        sender,
+        value,
+        decimals,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+    const expectRejected = async (transferring: Promise<unknown>, code: string) => {
+      try {
+        await transferring;
+        assert.fail(`Expected the hook to reject the transfer with ${code}`);
+      } catch (err: any) {
+        assert.match((err.logs ?? []).join("\n"), new RegExp(code));
+      }
+    };
+
+    const before = await hookProgram.account.holderStats.fetch(holderStats);
+    await send(smallHolder.address, amount / BigInt(20));
+    const after = await hookProgram.account.holderStats.fetch(holderStats);
+    assert.equal(after.holders.toNumber(), before.holders.toNumber() + 1);
+
+    await expectRejected(send(pool.address, amount / BigInt(2)), "MaxWalletExceeded");
+    await program.methods
+      .setMaxWalletExemption(owners[2], true)
+      .accounts({ authority: authority.publicKey, state: tokenState, transferHookConfig })
+      .signers([authority])
+      .rpc();
+    await send(pool.address, amount / BigInt(2));
+
+    await program.methods
+      .setAccessOverride(sender.publicKey, { deny: {} } as any, new Array(32).fill(0))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        accessOverride: accessOverridePda(sender.publicKey),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    await expectRejected(send(smallHolder.address, BigInt(1)), "SenderDenied");
+  });
+
+  it("Only thaws token-2022 accounts of verified wallets", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const wallet = Keypair.generate().publicKey;
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      newMint,
+      wallet,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), wallet.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .thawOnVerification()
+        .accounts({
+          state: tokenState,
+          newMint,
+          tokenAccount: tokenAccount.address,
+          receipt,
+          accessOverride: accessOverridePda(wallet),
+          mintAuthority,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Expected thawing an unverified wallet's account to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AccessRequired");
+    }
+  });
+
+  it("Changes the token-2022 interest rate only through the timelock", async () => {
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const interestConfig = getInterestBearingMintConfigState(
+      await getMint(provider.connection, newMint, undefined, TOKEN_2022_PROGRAM_ID)
+    );
+    assert.equal(interestConfig.currentRate, INTEREST_RATE_BPS);
+    assert.isTrue(interestConfig.rateAuthority.equals(mintAuthority));
+
+    // The timelock test above left a delay in place
+    try {
+      await program.methods
+        .setInterestRate(250)
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          roleRegistry: null,
+          newMint,
+          mintAuthority,
+          adminLog: null,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a direct rate change to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "TimelockRequired");
+    }
+
+    const state = await program.account.tokenState.fetch(tokenState);
+    const [pendingChange] = await PublicKey.findProgramAddress(
+      [Buffer.from("pending_change"), state.nextChangeId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .queuePendingChange({ interestRate: { 0: 250 } })
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        pendingChange,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    const pending = await program.account.pendingChange.fetch(pendingChange);
+    assert.deepEqual(pending.change, { interestRate: { 0: 250 } });
+
+    try {
+      await program.methods
+        .executePendingChange()
+        .accounts({
+          executor: provider.wallet.publicKey,
+          state: tokenState,
+          pendingChange,
+          proposer: authority.publicKey,
+          priceFeed: null,
+          interestMint: newMint,
+          mintAuthority,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Expected execution before the eta to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "TimelockNotElapsed");
+    }
+
+    await program.methods
+      .cancelPendingChange()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        pendingChange,
+        proposer: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+  });
+
+  it("Accepts attestations only for confidential token-2022 accounts", async () => {
+    const [roleRegistry] = await PublicKey.findProgramAddress(
+      [Buffer.from("role_registry")],
+      program.programId
+    );
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const attestor = Keypair.generate();
+    await program.methods
+      .grantRole({ attestor: {} }, attestor.publicKey)
+      .accounts({ authority: authority.publicKey, state: tokenState, roleRegistry })
+      .signers([authority])
+      .rpc();
+
+    const wallet = Keypair.generate().publicKey;
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      newMint,
+      wallet,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const [receipt] = await PublicKey.findProgramAddress(
+      [Buffer.from("receipt"), wallet.toBuffer()],
+      program.programId
+    );
+
+    // The account has no confidential transfer extension configured
+    const { currentRequirement } = await program.account.tokenState.fetch(tokenState);
+    const attestation = {
+      wallet,
+      tokenAccount: tokenAccount.address,
+      tier: 1,
+      requirement: currentRequirement,
+      attestedAt: new anchor.BN(Math.floor(Date.now() / 1000) - 10),
+    };
+    const message = Buffer.concat([
+      Buffer.from("AISTM7 confidential attestation v1"),
+      wallet.toBuffer(),
+      tokenAccount.address.toBuffer(),
+      Buffer.from([attestation.tier]),
+      attestation.requirement.toArrayLike(Buffer, "le", 8),
+      attestation.attestedAt.toArrayLike(Buffer, "le", 8),
+    ]);
+    try {
+      await program.methods
+        .submitConfidentialAttestation(attestation)
+        .accounts({
+          payer: authority.publicKey,
+          state: tokenState,
+          roleRegistry,
+          tokenAccount: tokenAccount.address,
+          receipt,
+          accessOverride: accessOverridePda(wallet),
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: attestor.secretKey,
+            message,
+          }),
+        ])
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected an attestation for a public balance to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "NotConfidentialAccount");
+    }
+
+    try {
+      await program.methods
+        .approveConfidentialAccount()
+        .accounts({
+          state: tokenState,
+          newMint,
+          tokenAccount: tokenAccount.address,
+          receipt,
+          accessOverride: accessOverridePda(wallet),
+          mintAuthority,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("Expected approving an unverified wallet's account to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AccessRequired");
+    }
+  });
+
+  it("Holds clawbacks for the dispute window and records disputes", async () => {
+    const [newMint] = await PublicKey.findProgramAddress(
+      [Buffer.from("token_2022_mint")],
+      program.programId
+    );
+    const [clawbackAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("clawback_authority")],
+      program.programId
+    );
+    const owner = Keypair.generate();
+    const source = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority,
+      newMint,
+      owner.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const [clawback] = await PublicKey.findProgramAddress(
+      [Buffer.from("clawback"), source.address.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .proposeClawback(new anchor.BN(1_000), Array(32).fill(7))
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        roleRegistry: null,
+        newMint,
+        source: source.address,
+        clawback,
+        clawbackAuthority,
+        adminLog: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    let proposal = await program.account.clawback.fetch(clawback);
+    assert.isTrue(proposal.owner.equals(owner.publicKey));
+    assert.isAtLeast(proposal.eta.sub(proposal.proposedAt).toNumber(), 7 * 86_400);
+
+    const dispute = () =>
+      program.methods
+        .disputeClawback(Array(32).fill(9))
+        .accounts({ owner: owner.publicKey, clawback })
+        .signers([owner])
+        .rpc();
+    await dispute();
+    proposal = await program.account.clawback.fetch(clawback);
+    assert.equal(proposal.dispute[0], 9);
+    assert.isAtLeast(proposal.eta.sub(proposal.disputedAt).toNumber(), 14 * 86_400);
+    try {
+      await dispute();
+      assert.fail("Expected a second dispute to be rejected");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ClawbackDisputeClosed");
+    }
+
+    const [mintAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [treasuryVault] = await PublicKey.findProgramAddress(
+      [Buffer.from("treasury_token_vault"), newMint.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .executeClawback()
+        .accounts({
+          authority: authority.publicKey,
+          state: tokenState,
+          roleRegistry: null,
+          clawback,
+          proposer: authority.publicKey,
+          newMint,
+          source: source.address,
+          treasuryVault,
+          clawbackAuthority,
+          mintAuthority,
+          adminLog: null,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Expected a clawback under review to be held");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "ClawbackNotReady");
+    }
+
+    await program.methods
+      .cancelClawback()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        roleRegistry: null,
+        clawback,
+        proposer: authority.publicKey,
+        adminLog: null,
+      })
+      .signers([authority])
+      .rpc();
+    assert.isNull(await program.account.clawback.fetchNullable(clawback));
+  });
+
+  // Every admin call needs the log once it is enabled, so this stays last
+  it("Records admin actions in the admin log", async () => {
+    const [adminLog] = await PublicKey.findProgramAddress(
+      [Buffer.from("admin_log")],
+      program.programId
+    );
+    await program.methods
+      .initializeAdminLog()
+      .accounts({
+        authority: authority.publicKey,
+        state: tokenState,
+        adminLog,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    // Raising the timelock is one of the few changes left that never waits
+    // on it
+    const setTimelockDelay = (delaySecs: number, log: PublicKey | null) =>
+      program.methods
+        .setTimelockDelay(new anchor.BN(delaySecs))
+        .accounts({ authority: authority.publicKey, state: tokenState, adminLog: log })
+        .signers([authority])
+        .rpc();
+
+    const delay = (await program.account.tokenState.fetch(tokenState)).timelockDelaySecs;
+    await setTimelockDelay(delay.toNumber() + 60, adminLog);
+
+    const log = await program.account.adminLog.fetch(adminLog);
+    assert.equal(log.count, 1);
+    assert.equal(log.sequence.toNumber(), 1);
+    const [entry] = log.entries;
+    assert.ok(entry.actor.equals(authority.publicKey));
+    assert.deepEqual(entry.action, { setTimelockDelay: {} });
+    assert.equal(Buffer.from(entry.oldValue).readBigUInt64LE(0), BigInt(delay.toString()));
+    assert.equal(
+      Buffer.from(entry.newValue).readBigUInt64LE(0),
+      BigInt(delay.toNumber() + 60)
+    );
+
+    try {
+      await setTimelockDelay(delay.toNumber() + 60, null);
+      assert.fail("Expected an admin call without the log to fail");
+    } catch (err: any) {
+      assert.equal(err.error.errorCode.code, "AdminLogRequired");
+    }
   });
 });
\ No newline at end of file